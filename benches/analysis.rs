@@ -0,0 +1,155 @@
+//! Benchmarks for the analysis behind the AI players.
+//!
+//! Run with `cargo bench`. Compare against a saved baseline when changing
+//! the search or the meld scoring, e.g. `cargo bench -- --save-baseline main`
+//! on main and `cargo bench -- --baseline main` on the branch.
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rummy::analysis::{
+    AnalysisConfig, GameView, KnownInformation, MatchContext, MonteCarlo, Node, Search, Strategy,
+    evaluate_hand_parallel,
+};
+use rummy::engine::GameEngine;
+use rummy::game::rules::Variant;
+use rummy::game::{DeckConfig, Hand, Player, PlayerType};
+use std::collections::VecDeque;
+use std::hint::black_box;
+
+/// Six card hands as the AI holds them mid-turn, from nothing to a made meld.
+const HANDS: &[(&str, [&str; 6])] = &[
+    ("rags", ["2s", "5h", "8c", "Jd", "Ks", "7h"]),
+    ("pair", ["9s", "9h", "2c", "5d", "Js", "Kc"]),
+    ("flush-draw", ["2h", "6h", "9h", "Qh", "4s", "8c"]),
+    ("straight-draw", ["5s", "6h", "7c", "8d", "Ks", "2c"]),
+    ("full-house", ["Qs", "Qh", "Qc", "4d", "4s", "9h"]),
+];
+
+fn hand(cards: &[&str]) -> Hand {
+    Hand {
+        cards: cards.iter().map(|card| card.parse().unwrap()).collect(),
+    }
+}
+
+/// A search from `hand` with everything else in the deck left to draw.
+fn node(hand: Hand) -> Node {
+    let known = KnownInformation {
+        hand: hand.cards.clone(),
+        ..KnownInformation::default()
+    };
+    Node::for_hand(hand, &known)
+}
+
+fn players() -> Vec<Player> {
+    ["Ada", "Bram", "Cathy", "Dev"]
+        .iter()
+        .zip([
+            PlayerType::Balanced,
+            PlayerType::Aggressive,
+            PlayerType::Conservative,
+            PlayerType::Balanced,
+        ])
+        .map(|(name, player_type)| Player {
+            name: name.to_string(),
+            description: String::new(),
+            player_type: Some(player_type),
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        })
+        .collect()
+}
+
+/// Scoring is cached by hand, so after the first pass this is the cost the
+/// search sees for hands it has met before.
+fn best_meld(c: &mut Criterion) {
+    let mut group = c.benchmark_group("best_meld");
+    for (name, cards) in HANDS {
+        let hand = hand(cards);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &hand, |b, hand| {
+            b.iter(|| rummy::scoring::best_meld(black_box(&hand.cards)))
+        });
+    }
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evaluate_hand_parallel");
+    group.sample_size(10);
+    for (name, cards) in HANDS {
+        for (mode, config) in [
+            ("sampled", AnalysisConfig::default()),
+            ("exact", AnalysisConfig::exact()),
+        ] {
+            group.bench_function(BenchmarkId::new(mode, name), |b| {
+                b.iter_batched(
+                    || node(hand(cards)),
+                    |mut node| {
+                        let search = Search::new(config);
+                        let mut rng = StdRng::seed_from_u64(7);
+                        evaluate_hand_parallel(&mut node, &search, &mut rng)
+                            .map(|node| node.branches.len())
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Each way the AI can decide on a hand: the heuristics on the expectation
+/// tree against the Monte Carlo rollouts.
+fn decide(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decide");
+    group.sample_size(10);
+    let context = MatchContext::default();
+    let strategies: [(&str, &dyn Strategy); 3] = [
+        ("balanced", &PlayerType::Balanced),
+        ("conservative", &PlayerType::Conservative),
+        ("monte-carlo", &MonteCarlo::default()),
+    ];
+    for (name, cards) in HANDS {
+        let node = node(hand(cards));
+        let probabilities = node.calculate_cumulative_probabilities();
+        let view = GameView {
+            context: &context,
+            probabilities: &probabilities,
+        };
+        for (strategy_name, strategy) in strategies {
+            group.bench_function(BenchmarkId::new(strategy_name, name), |b| {
+                b.iter(|| strategy.decide(black_box(&node), &view).action)
+            });
+        }
+    }
+    group.finish();
+}
+
+/// One whole AI turn at a four player table: deciding, drawing and
+/// discarding, or playing a hand.
+fn ai_turn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ai_turn");
+    group.sample_size(20);
+    for seed in [1, 2, 3] {
+        group.bench_function(BenchmarkId::from_parameter(seed), |b| {
+            b.iter_batched(
+                || {
+                    let mut engine =
+                        GameEngine::pre_game(players(), DeckConfig::default(), Variant::Bookclub)
+                            .unwrap();
+                    engine.set_seed(seed);
+                    engine.deal_round().unwrap();
+                    engine
+                },
+                |mut engine| engine.take_ai_turn().is_ok(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, best_meld, search, decide, ai_turn);
+criterion_main!(benches);
@@ -1,13 +1,38 @@
-use crate::card::Card;
 use crate::card::ToU64;
-use crate::game::calculate_best_meld_from_hand;
-use crate::game::{AutoPlayDecision, Hand, PlayAction, PlayerType};
-use crate::scoring::{CardVec, MELD_FUNCTIONS};
-use rand::prelude::SliceRandom;
-use rand::rng;
+use crate::card::{Card, remove_one};
+use crate::error::RummyError;
+use crate::game::{AutoPlayDecision, Deck, DeckConfig, Hand, PlayAction, PlayerType};
+use crate::scoring::{CardVec, best_meld, best_meld_score, canonical_key};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
 use smallvec::SmallVec;
 use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+mod advice;
+pub mod audit;
+mod context;
+mod dot;
+pub mod ensemble;
+mod information;
+pub mod mcts;
+pub mod opponent;
+mod search;
+pub mod strategy;
+pub mod temperament;
+pub mod tournament;
+
+pub use advice::{advise, advise_searched, advise_with};
+pub use audit::{AUDIT_SIMULATIONS, AuditReport, Auditor};
+pub use context::{MatchContext, SMALL_MELD_SCORE, TYPICAL_MELD_SCORE};
+pub use ensemble::Ensemble;
+pub use information::{InformationSet, KnownInformation};
+pub use mcts::MonteCarlo;
+pub use opponent::{DANGER_WEIGHT, OpponentModel};
+pub use search::{AnalysisConfig, AnalysisMode, Search};
+pub use strategy::{GameView, RandomStrategy, Strategy};
+pub use temperament::TemperamentPolicy;
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Node {
@@ -19,6 +44,9 @@ pub struct Node {
     pub baseline_score: u64,
     pub branches: Vec<Node>,
     pub depth: usize,
+    /// Draws from here the search passed over, because they couldn't improve
+    /// the hand or the node budget ran out.
+    pub pruned: usize,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -52,6 +80,16 @@ pub struct DecisionAnalysis {
     pub conservative_choice: usize,
     pub aggressive_choice: usize,
     pub balanced_choice: usize,
+    /// How each kind of player rates stopping after each round, round 0 first.
+    pub rounds: Vec<StrategyScores>,
+}
+
+/// How each kind of player rates stopping after a round, higher is better.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StrategyScores {
+    pub conservative: f64,
+    pub aggressive: f64,
+    pub balanced: f64,
 }
 
 #[derive(Clone, Debug)]
@@ -81,6 +119,35 @@ pub struct PlayDecision {
     pub alternative_strategies: Vec<String>,
 }
 
+/// What a hand's cards are worth and what one more card could do for it,
+/// for showing a player why one discard is better than another.
+#[derive(Clone, Debug)]
+pub struct HandOdds {
+    /// The best meld the hand makes as it stands.
+    pub baseline: u64,
+    /// Every card in the hand, least worth keeping first.
+    pub cards: Vec<CardValueAnalysis>,
+    /// How likely a drawn card makes a better meld.
+    pub draw_improves: f64,
+    /// The best meld a draw leaves, on average.
+    pub draw_expected: f64,
+    /// The top discard, and the best meld taking it makes.
+    pub retrieve: Option<(Card, u64)>,
+}
+
+/// Everything the analysis makes of a hand, from
+/// `Node::advanced_round_statistics`, for a front-end to show as it likes.
+/// Prints as a report with `{}`.
+#[derive(Clone, Debug)]
+pub struct RoundStatistics {
+    pub probabilities: HandProbabilityAnalysis,
+    /// Least worth keeping first.
+    pub card_values: Vec<CardValueAnalysis>,
+    pub play_decision: PlayDecision,
+    /// What each kind of AI player would do with the hand.
+    pub autoplay: Vec<(PlayerType, AutoPlayDecision)>,
+}
+
 #[derive(Clone, Debug)]
 struct CombinedAnalysis {
     optimal_round: usize,
@@ -88,53 +155,44 @@ struct CombinedAnalysis {
     details: DecisionAnalysis,
 }
 
-impl RoundProbabilities {
-    pub fn print_score_distribution(&self) {
-        println!("\nScore Distribution for Round {}:", self.round);
-        println!("Total paths evaluated: {}", self.total_simulations);
-
-        for outcome in &self.improvements {
-            let percentage = outcome.probability * 100.0;
-            let bar = "█".repeat((percentage / 2.0) as usize);
-            println!(
-                "  Score {:3}: {:6.2}% ({:5} paths) {}",
-                outcome.final_score, percentage, outcome.path_count, bar
-            );
-        }
-    }
-}
-
-pub fn evaluate_hand(node: &mut Node) -> Result<&mut Node, String> {
+/// Explores what `node`'s hand could become, as thoroughly as `search` is
+/// configured to, sampling draws with `rng`. Give it a fork of the game's
+/// `RngStream::Ai` for the search to follow the game's seed.
+pub fn evaluate_hand<'a>(
+    node: &'a mut Node,
+    search: &Search,
+    rng: &mut StdRng,
+) -> Result<&'a mut Node, RummyError> {
     // Pre-sort once and reuse - avoid repeated sorting
     node.full_hand.cards.sort_unstable(); // unstable is faster
 
     // Pre-allocate vectors with capacity
     let hand_len = node.full_hand.cards.len();
     let mut new_hand = CardVec::with_capacity(hand_len - 1);
-    let mut meld_scores = SmallVec::<[u64; 12]>::with_capacity(12); // Assuming 12 melds
 
     // Pre-calculate samples once for all iterations
-    let base_samples: Vec<_> = if node.depth < 3 {
+    let base_samples: Vec<_> = if search.branches_at(node.depth) {
         node.possible_cards.to_vec()
     } else {
         Vec::new()
     };
 
+    let mut seen = SmallVec::<[u64; 8]>::new();
     for (discard_idx, &discard) in node.clone().full_hand.cards.iter().enumerate() {
         // Efficiently build new_hand without filter/collect
         new_hand.clear();
         new_hand.extend_from_slice(&node.full_hand.cards[..discard_idx]);
         new_hand.extend_from_slice(&node.full_hand.cards[discard_idx + 1..]);
 
-        // Calculate meld scores efficiently
-        meld_scores.clear();
-        for &meld_fn in MELD_FUNCTIONS {
-            if let Ok(score) = meld_fn(new_hand.clone()) {
-                meld_scores.push(score);
-            }
+        // Throwing away either copy of a card leaves the same hand
+        let key = canonical_key(&new_hand);
+        if seen.contains(&key) {
+            continue;
         }
+        seen.push(key);
 
-        let max_meld_score = meld_scores.iter().copied().max().unwrap_or(0);
+        // Hands the search has already met come out of the meld cache
+        let max_meld_score = best_meld_score(&new_hand).unwrap_or(0);
 
         if max_meld_score > 0 {
             // Minimize allocations by reusing Hand structure
@@ -150,13 +208,15 @@ pub fn evaluate_hand(node: &mut Node) -> Result<&mut Node, String> {
             node.discard_pile.push_back(discard);
 
             // Recursive branch evaluation with optimizations
-            if node.depth < 3 && !base_samples.is_empty() {
+            if !base_samples.is_empty() {
                 evaluate_branches_parallel(
                     node,
                     &new_hand,
                     &base_samples,
                     discard,
                     Some(max_meld_score),
+                    search,
+                    rng,
                 )?;
             }
         }
@@ -171,27 +231,22 @@ pub fn evaluate_branches(
     available_samples: &[Card],
     discard: Card,
     max_meld_score: Option<u64>,
-) -> Result<(), String> {
-    let mut rng = rng();
-
-    let sample_count = 10.min(available_samples.len());
-
+    search: &Search,
+    rng: &mut StdRng,
+) -> Result<(), RummyError> {
     // Early exit if no cards available
     if node.possible_cards.is_empty() {
         return Ok(());
     }
 
-    // Use partial_shuffle for better performance than repeated choose
-    let mut samples = available_samples.to_vec();
-    let (selected, _) = samples.partial_shuffle(&mut rng, sample_count);
-
-    for &mut drawn_card in selected {
+    let draws = search.draws(available_samples, rng);
+    for (drawn, &drawn_card) in draws.iter().enumerate() {
         let mut simulated_hand = base_hand.clone();
         simulated_hand.push(drawn_card);
 
         // Create NEW available cards for this branch (don't modify parent's)
         let mut branch_available_cards = node.possible_cards.clone();
-        branch_available_cards.retain(|&c| c != drawn_card); // Remove only from this branch
+        remove_one(&mut branch_available_cards, &drawn_card); // Remove only from this branch
 
         // Create NEW discard pile with the discard from this simulation path
         let mut branch_discard_pile = node.discard_pile.clone();
@@ -204,14 +259,20 @@ pub fn evaluate_branches(
         let new_hand = Hand {
             cards: simulated_hand.to_vec(),
         };
-        let (branch_baseline, _hand) = calculate_best_meld_from_hand(&new_hand);
+        let branch_baseline = best_meld(&new_hand.cards).score;
 
         // USE parent_baseline: Skip branches that can't improve
         if current_depth > 1 && branch_baseline <= parent_baseline {
             // This branch won't improve our position, skip expensive recursion
+            node.pruned += 1;
             continue;
         }
 
+        if !search.take_node() {
+            node.pruned += draws.len() - drawn;
+            break;
+        }
+
         // Create branch with minimal cloning
         let mut branch = Node {
             full_hand: Hand {
@@ -224,37 +285,45 @@ pub fn evaluate_branches(
             baseline_score: branch_baseline,
             branches: Vec::new(),
             depth: node.depth + 1,
+            pruned: 0,
         };
 
-        evaluate_hand(&mut branch)?;
+        evaluate_hand(&mut branch, search, rng)?;
         node.branches.push(branch);
     }
 
     Ok(())
 }
 
-pub fn evaluate_hand_parallel(node: &mut Node) -> Result<&mut Node, String> {
+pub fn evaluate_hand_parallel<'a>(
+    node: &'a mut Node,
+    search: &Search,
+    rng: &mut StdRng,
+) -> Result<&'a mut Node, RummyError> {
+    let _span = tracing::debug_span!("evaluate_hand", hand = %node.full_hand).entered();
     node.full_hand.cards.sort_unstable();
     let hand_len = node.full_hand.cards.len();
     let mut new_hand = CardVec::with_capacity(hand_len - 1);
 
-    let base_samples: Vec<_> = if node.depth < 3 {
+    let base_samples: Vec<_> = if search.branches_at(node.depth) {
         node.possible_cards.to_vec()
     } else {
         Vec::new()
     };
 
+    let mut seen = SmallVec::<[u64; 8]>::new();
     for (discard_idx, &discard) in node.clone().full_hand.cards.iter().enumerate() {
         new_hand.clear();
         new_hand.extend_from_slice(&node.full_hand.cards[..discard_idx]);
         new_hand.extend_from_slice(&node.full_hand.cards[discard_idx + 1..]);
 
-        let scores: Vec<u64> = MELD_FUNCTIONS
-            .par_iter()
-            .filter_map(|&meld_fn| meld_fn(new_hand.clone()).ok())
-            .collect();
+        let key = canonical_key(&new_hand);
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
 
-        let max_meld_score = scores.iter().copied().max().unwrap_or(0);
+        let max_meld_score = best_meld_score(&new_hand).unwrap_or(0);
 
         // ALWAYS add the possible hand, even if score is 0
         let possible_hand = PossibleHand {
@@ -269,7 +338,7 @@ pub fn evaluate_hand_parallel(node: &mut Node) -> Result<&mut Node, String> {
         node.discard_pile.push_back(discard);
 
         // Continue exploring regardless of score
-        if node.depth < 3 && !base_samples.is_empty() {
+        if !base_samples.is_empty() {
             if node.depth <= 1 {
                 evaluate_branches_parallel(
                     node,
@@ -277,6 +346,8 @@ pub fn evaluate_hand_parallel(node: &mut Node) -> Result<&mut Node, String> {
                     &base_samples,
                     discard,
                     Some(max_meld_score),
+                    search,
+                    rng,
                 )?;
             } else {
                 evaluate_branches(
@@ -285,85 +356,190 @@ pub fn evaluate_hand_parallel(node: &mut Node) -> Result<&mut Node, String> {
                     &base_samples,
                     discard,
                     Some(max_meld_score),
+                    search,
+                    rng,
                 )?;
             }
         }
     }
 
+    tracing::debug!(
+        branches = node.branches.len(),
+        pruned = node.pruned,
+        nodes_left = search.nodes_left(),
+        "hand evaluated"
+    );
     Ok(node)
 }
 
+/// Searches `node`'s hand as `config` says. With a time budget the search
+/// goes a level deeper at a time, up to `max_depth`, and the deepest one
+/// finished in time is kept. If time runs out before even the first is done,
+/// that's the one kept, as far as it got.
+pub fn evaluate_hand_deepening(
+    node: &Node,
+    config: AnalysisConfig,
+    rng: &mut StdRng,
+) -> Result<Node, RummyError> {
+    let Some(budget) = config.time_budget else {
+        let mut searched = node.clone();
+        evaluate_hand_parallel(&mut searched, &Search::new(config), rng)?;
+        return Ok(searched);
+    };
+    let deadline = Instant::now() + budget;
+
+    let mut best = None;
+    // A search of no depth at all is just the one level
+    for max_depth in config.max_depth.min(1)..=config.max_depth {
+        let search = Search::until(
+            AnalysisConfig {
+                max_depth,
+                ..config
+            },
+            Some(deadline),
+        );
+        let mut searched = node.clone();
+        evaluate_hand_parallel(&mut searched, &search, rng)?;
+
+        if search.timed_out() {
+            best.get_or_insert(searched);
+            break;
+        }
+        tracing::debug!(max_depth, "search deepened");
+        best = Some(searched);
+    }
+
+    Ok(best.expect("the search runs at least once"))
+}
+
 pub fn evaluate_branches_parallel(
     node: &mut Node,
     base_hand: &CardVec,
     available_samples: &[Card],
     discard: Card,
     max_meld_score: Option<u64>,
-) -> Result<(), String> {
-    let mut rng = rng();
-    let sample_count = 10.min(available_samples.len());
-
+    search: &Search,
+    rng: &mut StdRng,
+) -> Result<(), RummyError> {
     if node.possible_cards.is_empty() {
         return Ok(());
     }
 
-    let mut samples = available_samples.to_vec();
-    let (selected, _) = samples.partial_shuffle(&mut rng, sample_count);
-
-    let selected_cards: Vec<Card> = selected.to_vec();
-    let base_hand_vec = base_hand.to_vec();
-    let possible_cards = node.possible_cards.clone();
-    let discard_pile = node.discard_pile.clone();
+    // Each branch gets its own seed and its nodes up front, so the search
+    // comes out the same whichever threads pick the branches up
+    let selected_cards: Vec<(Card, u64)> = search
+        .draws(available_samples, rng)
+        .into_iter()
+        .map(|card| (card, rng.random()))
+        .collect();
     let current_depth = node.depth;
     let parent_baseline = node.baseline_score; // Pass down baseline
 
-    let branches: Vec<Node> = selected_cards
-        .par_iter()
-        .filter_map(|&drawn_card| {
-            let mut simulated_hand: CardVec = base_hand_vec.clone().into();
-            simulated_hand.push(drawn_card);
+    let mut branches = Vec::with_capacity(selected_cards.len());
+    for (drawn, &(drawn_card, seed)) in selected_cards.iter().enumerate() {
+        let mut simulated_hand = base_hand.clone();
+        simulated_hand.push(drawn_card);
 
-            let mut branch_available_cards = possible_cards.clone();
-            branch_available_cards.retain(|&c| c != drawn_card);
+        let mut branch_available_cards = node.possible_cards.clone();
+        remove_one(&mut branch_available_cards, &drawn_card);
 
-            let mut branch_discard_pile = discard_pile.clone();
-            branch_discard_pile.push_back(discard);
+        let mut branch_discard_pile = node.discard_pile.clone();
+        branch_discard_pile.push_back(discard);
 
-            // Calculate baseline for this new 6-card hand
-            let new_hand = Hand {
-                cards: simulated_hand.to_vec(),
-            };
-            let (branch_baseline, _hand) = calculate_best_meld_from_hand(&new_hand);
+        // Calculate baseline for this new 6-card hand
+        let new_hand = Hand {
+            cards: simulated_hand.to_vec(),
+        };
+        let branch_baseline = best_meld(&new_hand.cards).score;
 
-            // USE parent_baseline: Skip branches that can't improve
-            if current_depth > 1 && branch_baseline <= parent_baseline {
-                // This branch won't improve our position, skip expensive recursion
-                return None;
-            }
+        // USE parent_baseline: Skip branches that can't improve
+        if current_depth > 1 && branch_baseline <= parent_baseline {
+            // This branch won't improve our position, skip expensive recursion
+            node.pruned += 1;
+            continue;
+        }
 
-            let mut branch = Node {
-                full_hand: new_hand,
-                possible_hands: Vec::new(),
-                possible_cards: branch_available_cards,
-                discard_pile: branch_discard_pile,
-                meld_score: max_meld_score,
-                baseline_score: branch_baseline, // NEW: Each branch has its baseline
-                branches: Vec::new(),
-                depth: current_depth + 1,
-            };
+        if !search.take_node() {
+            node.pruned += selected_cards.len() - drawn;
+            break;
+        }
 
-            match evaluate_hand(&mut branch) {
+        let branch = Node {
+            full_hand: new_hand,
+            possible_hands: Vec::new(),
+            possible_cards: branch_available_cards,
+            discard_pile: branch_discard_pile,
+            meld_score: max_meld_score,
+            baseline_score: branch_baseline, // NEW: Each branch has its baseline
+            branches: Vec::new(),
+            depth: current_depth + 1,
+            pruned: 0,
+        };
+        branches.push((branch, seed));
+    }
+
+    // What's left of the budget is shared out evenly between the branches
+    let shares: Vec<Search> = branches
+        .iter()
+        .map(|_| search.share(branches.len()))
+        .collect();
+    let searched: Vec<Option<Node>> = branches
+        .into_par_iter()
+        .zip(&shares)
+        .map(|((mut branch, seed), share)| {
+            match evaluate_hand(&mut branch, share, &mut StdRng::seed_from_u64(seed)) {
                 Ok(_) => Some(branch),
                 Err(_) => None,
             }
         })
         .collect();
+    search.spend(shares.iter().map(Search::nodes_spent).sum());
 
-    node.branches.extend(branches);
+    node.pruned += searched.iter().filter(|branch| branch.is_none()).count();
+    node.branches.extend(searched.into_iter().flatten());
     Ok(())
 }
 
 impl Node {
+    /// The root of a search from `full_hand`, drawing from `possible_cards`
+    /// with `discard_pile` on the table. The baseline is the hand's best
+    /// meld.
+    pub fn new(full_hand: Hand, possible_cards: Vec<Card>, discard_pile: VecDeque<Card>) -> Self {
+        let baseline_score = best_meld(&full_hand.cards).score;
+        Self {
+            full_hand,
+            possible_hands: Vec::new(),
+            possible_cards,
+            discard_pile,
+            meld_score: None,
+            baseline_score,
+            branches: Vec::new(),
+            depth: 0,
+            pruned: 0,
+        }
+    }
+
+    /// The root of a search from `hand`, drawing from whatever of a standard
+    /// deck `known` doesn't place. `known.hand` is what the player holds
+    /// before the card `hand` might add, so a hand weighing a draw still
+    /// counts that card among the possible draws.
+    pub fn for_hand(hand: Hand, known: &KnownInformation) -> Self {
+        Self::for_deck(hand, known, &DeckConfig::default()).expect("the standard deck is valid")
+    }
+
+    /// `for_hand`, dealing from the deck `deck` describes.
+    pub fn for_deck(
+        hand: Hand,
+        known: &KnownInformation,
+        deck: &DeckConfig,
+    ) -> Result<Self, RummyError> {
+        Ok(Self::new(
+            hand,
+            known.unknown(deck)?,
+            known.discards.iter().copied().collect(),
+        ))
+    }
+
     // Create baseline round (round 0)
     fn create_baseline_round(&self, baseline: u64) -> RoundProbabilities {
         RoundProbabilities {
@@ -566,7 +742,7 @@ impl Node {
             .collect();
 
         // Sort by score (highest first)
-        improvements.sort_by(|a, b| b.final_score.cmp(&a.final_score));
+        improvements.sort_by_key(|o| std::cmp::Reverse(o.final_score));
 
         let probability_of_improvement = improvements
             .iter()
@@ -685,7 +861,7 @@ impl Node {
         // Adjust thresholds based on player type
         let (draw_once_threshold, draw_twice_threshold, draw_thrice_threshold) = match player_type {
             PlayerType::Conservative => (1.5, 3.0, 6.0),
-            PlayerType::Balanced => (0.5, 1.5, 3.0),
+            PlayerType::Balanced | PlayerType::Ensemble | PlayerType::MonteCarlo => (0.5, 1.5, 3.0),
             PlayerType::Aggressive => (-0.5, 0.5, 1.0),
         };
 
@@ -777,8 +953,6 @@ impl Node {
     ) -> (usize, DecisionAnalysis) {
         let mut decision_analysis = DecisionAnalysis::default();
 
-        println!("\n=== Decision Analysis ===");
-
         let mut best_conservative = (0, f64::NEG_INFINITY);
         let mut best_aggressive = (0, f64::NEG_INFINITY);
         let mut best_balanced = (0, f64::NEG_INFINITY);
@@ -805,9 +979,18 @@ impl Node {
             let balanced_score =
                 round.expected_improvement - risk_penalty_balanced + certainty_bonus;
 
-            println!(
-                "Round {i}: Conservative={conservative_score:.2}, Aggressive={aggressive_score:.2}, Balanced={balanced_score:.2}"
+            tracing::debug!(
+                round = i,
+                conservative = conservative_score,
+                aggressive = aggressive_score,
+                balanced = balanced_score,
+                "rated stopping after round"
             );
+            decision_analysis.rounds.push(StrategyScores {
+                conservative: conservative_score,
+                aggressive: aggressive_score,
+                balanced: balanced_score,
+            });
 
             if conservative_score > best_conservative.1 {
                 best_conservative = (i, conservative_score);
@@ -826,17 +1009,12 @@ impl Node {
 
         let optimal_round = best_conservative.0;
 
-        println!("\nRecommendations:");
-        println!(
-            "  Conservative player: Stop after round {}",
-            best_conservative.0
-        );
-        println!(
-            "  Aggressive player: Stop after round {}",
-            best_aggressive.0
+        tracing::debug!(
+            conservative = best_conservative.0,
+            aggressive = best_aggressive.0,
+            balanced = best_balanced.0,
+            "recommended stopping after round {optimal_round}"
         );
-        println!("  Balanced player: Stop after round {}", best_balanced.0);
-        println!("  Overall recommendation: Stop after round {optimal_round} (conservative)");
 
         (optimal_round, decision_analysis)
     }
@@ -875,7 +1053,7 @@ impl Node {
             })
             .collect();
 
-        improvements.sort_by(|a, b| b.final_score.cmp(&a.final_score));
+        improvements.sort_by_key(|o| std::cmp::Reverse(o.final_score));
 
         let probability_of_improvement = improvements
             .iter()
@@ -932,12 +1110,13 @@ impl Node {
                     total_count,
                 );
             }
-        } else if current_depth < target_depth && self.branches.is_empty() {
-            if !self.possible_hands.is_empty() {
-                for possible_hand in &self.possible_hands {
-                    *outcomes.entry(possible_hand.meld_score).or_insert(0) += 1;
-                    *total_count += 1;
-                }
+        } else if current_depth < target_depth
+            && self.branches.is_empty()
+            && !self.possible_hands.is_empty()
+        {
+            for possible_hand in &self.possible_hands {
+                *outcomes.entry(possible_hand.meld_score).or_insert(0) += 1;
+                *total_count += 1;
             }
         }
     }
@@ -964,6 +1143,39 @@ impl Node {
         card_analyses
     }
 
+    /// What the hand's cards are worth, and what one more card does for it:
+    /// drawn from `possible_cards`, or taken from the top of `discard_pile`.
+    pub fn hand_odds(&self) -> HandOdds {
+        let prob_analysis = self.calculate_cumulative_probabilities();
+        let with = |card: Card| {
+            let mut cards = self.full_hand.cards.clone();
+            cards.push(card);
+            best_meld(&cards).score
+        };
+
+        let draws: Vec<u64> = self.possible_cards.iter().map(|&card| with(card)).collect();
+        let (draw_improves, draw_expected) = if draws.is_empty() {
+            (0.0, self.baseline_score as f64)
+        } else {
+            let improves = draws
+                .iter()
+                .filter(|&&score| score > self.baseline_score)
+                .count();
+            (
+                improves as f64 / draws.len() as f64,
+                draws.iter().sum::<u64>() as f64 / draws.len() as f64,
+            )
+        };
+
+        HandOdds {
+            baseline: self.baseline_score,
+            cards: self.calculate_strategic_card_values_correct(&prob_analysis),
+            draw_improves,
+            draw_expected,
+            retrieve: self.discard_pile.back().map(|&card| (card, with(card))),
+        }
+    }
+
     /// Calculate how likely this card is to contribute to future melds
     fn calculate_future_meld_potential(
         &self,
@@ -1037,14 +1249,10 @@ impl Node {
 
     fn calculate_score_without_card(&self, target_card: Card) -> u64 {
         let mut remaining_cards = self.full_hand.cards.clone();
-        remaining_cards.retain(|&c| c != target_card);
+        remove_one(&mut remaining_cards, &target_card);
 
         if remaining_cards.len() == 5 {
-            let hand_without_target = Hand {
-                cards: remaining_cards,
-            };
-            let (score, _hand) = calculate_best_meld_from_hand(&hand_without_target);
-            score
+            best_meld(&remaining_cards).score
         } else {
             0
         }
@@ -1183,7 +1391,13 @@ impl Node {
         }
     }
 
-    pub fn make_play_decision(&self, prob_analysis: &HandProbabilityAnalysis) -> PlayDecision {
+    /// Advise a human on whether to play their hand, using the same match
+    /// context the AI strategies see.
+    pub fn make_play_decision(
+        &self,
+        prob_analysis: &HandProbabilityAnalysis,
+        context: &MatchContext,
+    ) -> PlayDecision {
         let baseline = prob_analysis.current_baseline;
         let mut reasoning = Vec::new();
         let mut alternative_strategies = Vec::new();
@@ -1217,7 +1431,16 @@ impl Node {
 
         let confidence = prob_analysis.confidence_level;
 
-        let should_play = if baseline >= 30 {
+        let should_play = if context.is_winning_score(baseline) {
+            reasoning.push("Playing now wins the match".to_string());
+            true
+        } else if context.is_one_small_meld_away() && baseline > 0 {
+            let points = context.points_to_win().unwrap_or_default();
+            reasoning.push(format!(
+                "Only {points} points from winning, play cheap melds immediately"
+            ));
+            true
+        } else if baseline >= 30 {
             reasoning.push("Hand is strong enough to play".to_string());
             true
         } else if baseline >= 15 && !should_continue {
@@ -1245,14 +1468,47 @@ impl Node {
         }
     }
 
-    /// Make a concrete autoplay decision for a specific player type
+    /// Make a concrete autoplay decision for a specific player type, taking the
+    /// state of the match into account.
     pub fn make_autoplay_decision(
         &self,
         player_type: PlayerType,
         prob_analysis: &HandProbabilityAnalysis,
+        context: &MatchContext,
     ) -> AutoPlayDecision {
+        if player_type == PlayerType::Ensemble {
+            let view = GameView {
+                context,
+                probabilities: prob_analysis,
+            };
+            return Ensemble::default().decide(self, &view);
+        }
+        if player_type == PlayerType::MonteCarlo {
+            let view = GameView {
+                context,
+                probabilities: prob_analysis,
+            };
+            let monte_carlo = MonteCarlo {
+                seed: context.seed,
+                ..MonteCarlo::default()
+            };
+            return monte_carlo.decide(self, &view);
+        }
+
         let baseline = prob_analysis.current_baseline as f64;
 
+        // A meld that reaches the target ends the match, there's nothing left to draw for.
+        if context.is_winning_score(prob_analysis.current_baseline) {
+            return AutoPlayDecision {
+                action: PlayAction::Play,
+                confidence: 0.95,
+                expected_score: baseline,
+                card_to_discard: None,
+            };
+        }
+
+        let risk_scale = context.risk_scale();
+
         // Get expected score after one draw (round 1)
         let draw_expected_score = if prob_analysis.round_probabilities.len() > 1 {
             baseline + prob_analysis.round_probabilities[1].expected_improvement
@@ -1260,17 +1516,24 @@ impl Node {
             baseline
         };
 
-        match player_type {
+        let mut decision = match player_type {
             PlayerType::Conservative => {
-                self.conservative_decision(baseline, draw_expected_score, prob_analysis)
+                self.conservative_decision(baseline, draw_expected_score, prob_analysis, risk_scale)
             }
             PlayerType::Aggressive => {
-                self.aggressive_decision(baseline, draw_expected_score, prob_analysis)
+                self.aggressive_decision(baseline, draw_expected_score, prob_analysis, risk_scale)
             }
-            PlayerType::Balanced => {
-                self.balanced_decision(baseline, draw_expected_score, prob_analysis)
+            PlayerType::Balanced | PlayerType::Ensemble | PlayerType::MonteCarlo => {
+                self.balanced_decision(baseline, draw_expected_score, prob_analysis, risk_scale)
             }
+        };
+
+        // Don't hand an opponent the card they've been collecting for
+        if decision.card_to_discard.is_some() {
+            decision.card_to_discard = Some(self.find_safest_discard(context));
         }
+
+        decision
     }
 
     #[warn(clippy::redundant_guards)]
@@ -1279,12 +1542,15 @@ impl Node {
         baseline: f64,
         _draw_expected_score: f64,
         prob_analysis: &HandProbabilityAnalysis,
+        risk_scale: f64,
     ) -> AutoPlayDecision {
+        let risk_baseline = baseline * risk_scale;
+
         // Analyze both rounds to make optimal decision
         let round_1_analysis = if prob_analysis.round_probabilities.len() > 1 {
             let r1 = &prob_analysis.round_probabilities[1];
             Some((
-                r1.expected_improvement - (r1.risk_of_degradation * baseline * 1.0), // Conservative risk penalty
+                r1.expected_improvement - (r1.risk_of_degradation * risk_baseline * 1.0), // Conservative risk penalty
                 r1.probability_of_improvement,
                 r1.expected_improvement,
             ))
@@ -1295,7 +1561,7 @@ impl Node {
         let round_2_analysis = if prob_analysis.round_probabilities.len() > 2 {
             let r2 = &prob_analysis.round_probabilities[2];
             Some((
-                r2.expected_improvement - (r2.risk_of_degradation * baseline * 1.2), // Higher risk penalty for 2 draws
+                r2.expected_improvement - (r2.risk_of_degradation * risk_baseline * 1.2), // Higher risk penalty for 2 draws
                 r2.probability_of_improvement,
                 r2.expected_improvement,
             ))
@@ -1305,7 +1571,7 @@ impl Node {
 
         let round_3_analysis = if prob_analysis.round_probabilities.len() > 3 {
             let r3 = &prob_analysis.round_probabilities[3];
-            let risk_penalty = r3.risk_of_degradation * baseline * 0.6; // Slightly higher for 3 draws
+            let risk_penalty = r3.risk_of_degradation * risk_baseline * 0.6; // Slightly higher for 3 draws
             Some((
                 r3.expected_improvement - risk_penalty,
                 r3.probability_of_improvement,
@@ -1340,7 +1606,7 @@ impl Node {
 
         // Conservative thresholds based on baseline and best available option
         let should_draw = match baseline {
-            b if b == 0.0 => true, // No meld: always draw
+            0.0 => true, // No meld: always draw
             b if b < 5.0 => {
                 // Very weak: draw unless terrible odds
                 net_expected_value > -0.5 || best_prob > 0.25
@@ -1399,11 +1665,14 @@ impl Node {
         baseline: f64,
         _draw_expected_score: f64,
         prob_analysis: &HandProbabilityAnalysis,
+        risk_scale: f64,
     ) -> AutoPlayDecision {
+        let risk_baseline = baseline * risk_scale;
+
         // Analyze both rounds with balanced risk assessment
         let round_1_analysis = if prob_analysis.round_probabilities.len() > 1 {
             let r1 = &prob_analysis.round_probabilities[1];
-            let risk_penalty = r1.risk_of_degradation * baseline * 0.4; // Moderate risk penalty
+            let risk_penalty = r1.risk_of_degradation * risk_baseline * 0.4; // Moderate risk penalty
             Some((
                 r1.expected_improvement - risk_penalty,
                 r1.probability_of_improvement,
@@ -1415,7 +1684,7 @@ impl Node {
 
         let round_2_analysis = if prob_analysis.round_probabilities.len() > 2 {
             let r2 = &prob_analysis.round_probabilities[2];
-            let risk_penalty = r2.risk_of_degradation * baseline * 0.5; // Slightly higher for 2 draws
+            let risk_penalty = r2.risk_of_degradation * risk_baseline * 0.5; // Slightly higher for 2 draws
             Some((
                 r2.expected_improvement - risk_penalty,
                 r2.probability_of_improvement,
@@ -1427,7 +1696,7 @@ impl Node {
 
         let round_3_analysis = if prob_analysis.round_probabilities.len() > 3 {
             let r3 = &prob_analysis.round_probabilities[3];
-            let risk_penalty = r3.risk_of_degradation * baseline * 0.6; // Slightly higher for 3 draws
+            let risk_penalty = r3.risk_of_degradation * risk_baseline * 0.6; // Slightly higher for 3 draws
             Some((
                 r3.expected_improvement - risk_penalty,
                 r3.probability_of_improvement,
@@ -1462,7 +1731,7 @@ impl Node {
 
         // Balanced thresholds considering three rounds
         let should_draw = match baseline {
-            b if b == 0.0 => true, // No meld: always draw
+            0.0 => true, // No meld: always draw
             b if b < 5.0 => {
                 // Very weak: draw unless terrible odds
                 net_expected_value > -1.0 || best_prob > 0.05
@@ -1521,11 +1790,15 @@ impl Node {
         baseline: f64,
         _draw_expected_score: f64,
         prob_analysis: &HandProbabilityAnalysis,
+        risk_scale: f64,
     ) -> AutoPlayDecision {
+        let risk_baseline = baseline * risk_scale;
+
         // Analyze both rounds with minimal risk aversion
         let round_1_analysis = if prob_analysis.round_probabilities.len() > 1 {
             let r1 = &prob_analysis.round_probabilities[1];
-            let risk_adjusted = r1.expected_improvement - (r1.risk_of_degradation * baseline * 0.2);
+            let risk_adjusted =
+                r1.expected_improvement - (r1.risk_of_degradation * risk_baseline * 0.2);
             let max_potential = r1
                 .improvements
                 .first()
@@ -1544,7 +1817,7 @@ impl Node {
         let round_2_analysis = if prob_analysis.round_probabilities.len() > 2 {
             let r2 = &prob_analysis.round_probabilities[2];
             let risk_adjusted =
-                r2.expected_improvement - (r2.risk_of_degradation * baseline * 0.25);
+                r2.expected_improvement - (r2.risk_of_degradation * risk_baseline * 0.25);
             let max_potential = r2
                 .improvements
                 .first()
@@ -1561,7 +1834,7 @@ impl Node {
         };
         let round_3_analysis = if prob_analysis.round_probabilities.len() > 3 {
             let r3 = &prob_analysis.round_probabilities[3];
-            let risk_penalty = r3.risk_of_degradation * baseline * 0.6; // Slightly higher for 3 draws
+            let risk_penalty = r3.risk_of_degradation * risk_baseline * 0.6; // Slightly higher for 3 draws
             let max_potential = r3
                 .improvements
                 .first()
@@ -1722,129 +1995,96 @@ impl Node {
             .unwrap_or(self.full_hand.cards[0]) // Fallback to first card
     }
 
-    /// Execute an autoplay action
-    pub fn execute_autoplay_action(
+    /// Like `find_worst_card_to_discard`, but wary of the cards opponents
+    /// look likely to want: a card's danger counts against throwing it away.
+    pub fn find_safest_discard(&self, context: &MatchContext) -> Card {
+        let dummy_prob_analysis = HandProbabilityAnalysis {
+            current_baseline: self.baseline_score,
+            round_probabilities: vec![],
+            optimal_stop_round: Some(0),
+            confidence_level: 0.5,
+            analysis_details: None,
+        };
+
+        let discard_cost = |analysis: &CardValueAnalysis| {
+            analysis.strategic_value + context.discard_danger(&analysis.card) * DANGER_WEIGHT
+        };
+
+        self.calculate_strategic_card_values_correct(&dummy_prob_analysis)
+            .iter()
+            .min_by(|a, b| {
+                discard_cost(a)
+                    .partial_cmp(&discard_cost(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|analysis| analysis.card)
+            .unwrap_or(self.full_hand.cards[0]) // Fallback to first card
+    }
+
+    /// Plays `action` from `deck`: a drawn or retrieved card is taken into
+    /// the hand, the worst card discarded and the hand scored again. A
+    /// draw reshuffles the discards with `rng` if it has to.
+    pub fn execute_autoplay_action<R: Rng + ?Sized>(
         &mut self,
         action: &PlayAction,
-        deck: &mut crate::game::Deck,
-    ) -> Result<u64, String> {
-        match action {
-            PlayAction::Play => Ok(self.baseline_score),
-            PlayAction::Draw => {
-                // Draw one card
-                if let Some(new_card) = deck.draw_pile.pop_back() {
-                    self.full_hand.cards.push(new_card);
-
-                    // Find worst card to discard from the now 6-card hand
-                    let worst_card = self.find_worst_card_to_discard();
-
-                    // Discard it
-                    self.full_hand.cards.retain(|&card| card != worst_card);
-                    deck.discard_pile.push_back(worst_card);
-
-                    // Calculate final score with the new 5-card hand
-                    let (score, _hand) =
-                        crate::game::calculate_best_meld_from_hand(&self.full_hand);
-                    self.baseline_score = score;
-                    Ok(self.baseline_score)
-                } else {
-                    Err("No cards left in deck".to_string())
-                }
-            }
-            PlayAction::Retrieve => {
-                // Draw one card
-                if let Some(new_card) = deck.discard_pile.pop_back() {
-                    self.full_hand.cards.push(new_card);
-
-                    // Find worst card to discard from the now 6-card hand
-                    let worst_card = self.find_worst_card_to_discard();
-
-                    // Discard it
-                    self.full_hand.cards.retain(|&card| card != worst_card);
-                    deck.discard_pile.push_back(worst_card);
-
-                    // Calculate final score with the new 5-card hand
-                    let (score, _hand) =
-                        crate::game::calculate_best_meld_from_hand(&self.full_hand);
-                    self.baseline_score = score;
-                    Ok(self.baseline_score)
-                } else {
-                    Err("No cards left in deck".to_string())
-                }
-            }
-        }
-    }
+        deck: &mut Deck,
+        rng: &mut R,
+    ) -> Result<u64, RummyError> {
+        let new_card = match action {
+            PlayAction::Play => return Ok(self.baseline_score),
+            PlayAction::Draw => deck.draw(rng)?,
+            PlayAction::Retrieve => deck.take_discard().ok_or(RummyError::EmptyDeck)?,
+        };
+        self.full_hand.cards.push(new_card);
 
-    /// Debug function that prints comprehensive analysis of the current hand
-    pub fn debug_advanced_round_statistics(&self) {
-        let prob_analysis = self.calculate_realistic_probabilities();
-        println!("{prob_analysis}");
-
-        // Strategic analysis based on future meld potential
-        println!("\n=== Strategic Card Analysis (Future-Based) ===");
-        let card_values = self.calculate_strategic_card_values_correct(&prob_analysis);
-
-        println!("Cards ranked by strategic value (lowest = best to discard):");
-        for (i, card_analysis) in card_values.iter().enumerate() {
-            let recommendation = if i == 0 { " ← DISCARD" } else { "" };
-            println!(
-                "  {}: Strategic={:.2} (Future={:.1}, Participation={:.1}%, Risk={:.1}){}",
-                card_analysis.card,
-                card_analysis.strategic_value,
-                card_analysis.keep_expected_value,
-                card_analysis.net_value,
-                card_analysis.risk_impact,
-                recommendation
-            );
-        }
+        // Find worst card to discard from the now 6-card hand
+        let worst_card = self.find_worst_card_to_discard();
+        remove_one(&mut self.full_hand.cards, &worst_card);
+        deck.discard(worst_card);
 
-        println!("\n=== Play Decision ===");
-        let play_decision = self.make_play_decision(&prob_analysis);
-        println!(
-            "Recommendation: {}",
-            if play_decision.should_play {
-                "PLAY HAND"
-            } else {
-                "DRAW/CONTINUE"
-            }
-        );
-        println!("Confidence: {:.1}%", play_decision.confidence * 100.0);
-        println!("Reasoning: {}", play_decision.reasoning);
+        // Calculate final score with the new 5-card hand
+        self.baseline_score = best_meld(&self.full_hand.cards).score;
+        Ok(self.baseline_score)
+    }
 
-        if !play_decision.alternative_strategies.is_empty() {
-            println!("Alternative strategies:");
-            for strategy in &play_decision.alternative_strategies {
-                println!("  - {strategy}");
-            }
-        }
+    /// Everything the analysis makes of the current hand: its
+    /// probabilities, what each card is worth, whether to play it and what
+    /// each kind of AI player would do. Nothing's printed, the front-end
+    /// decides what to show.
+    pub fn advanced_round_statistics(&self) -> RoundStatistics {
+        let probabilities = self.calculate_realistic_probabilities();
+        let card_values = self.calculate_strategic_card_values_correct(&probabilities);
+        let play_decision = self.make_play_decision(&probabilities, &MatchContext::default());
 
-        // Autoplay decisions for different player types
-        let player_types = [
+        let autoplay = [
             PlayerType::Conservative,
             PlayerType::Aggressive,
             PlayerType::Balanced,
-        ];
-
-        println!("\n=== Autoplay Decisions ===");
-        for player_type in &player_types {
-            let decision = self.make_autoplay_decision(player_type.clone(), &prob_analysis);
-
-            println!("\n{player_type:?} Player:");
-            println!("  Decision: {:?}", decision.action);
-            println!("  Confidence: {:.1}%", decision.confidence * 100.0);
-            println!("  Expected Score: {:.1}", decision.expected_score);
-
-            if let Some(card) = decision.card_to_discard {
-                println!("  Card to discard: {card}");
-            }
+            PlayerType::Ensemble,
+        ]
+        .into_iter()
+        .map(|player_type| {
+            let decision = self.make_autoplay_decision(
+                player_type.clone(),
+                &probabilities,
+                &MatchContext::default(),
+            );
+            tracing::debug!(
+                player_type = ?player_type,
+                action = ?decision.action,
+                confidence = decision.confidence,
+                expected_score = decision.expected_score,
+                "autoplay decision"
+            );
+            (player_type, decision)
+        })
+        .collect();
 
-            match decision.action {
-                PlayAction::Play => println!("  → Will play current hand"),
-                PlayAction::Draw => println!("  → Will draw one card and discard worst card"),
-                PlayAction::Retrieve => {
-                    println!("  → Will retrieve the discard and discard worst card")
-                }
-            }
+        RoundStatistics {
+            probabilities,
+            card_values,
+            play_decision,
+            autoplay,
         }
     }
 }
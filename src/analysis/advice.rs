@@ -0,0 +1,204 @@
+//! The whole turn worked out in one call, for anything holding a hand that
+//! isn't the engine: take the discard, draw blind or play what's held.
+
+use crate::analysis::{
+    AnalysisConfig, GameView, KnownInformation, MatchContext, Node, Strategy,
+    evaluate_hand_deepening,
+};
+use crate::card::Card;
+use crate::error::RummyError;
+use crate::game::{AutoPlayDecision, DeckConfig, Hand, PlayAction, PlayerType};
+use crate::scoring::best_meld;
+use rand::rngs::StdRng;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// How a `player_type` AI would play `hand` from a standard deck, knowing
+/// what `known` says about the rest of it. `known.hand` is taken to be
+/// `hand`, and the top of `known.discards` is the card on offer.
+pub fn advise(hand: &Hand, known: &KnownInformation, player_type: PlayerType) -> AutoPlayDecision {
+    let known = KnownInformation {
+        hand: hand.cards.clone(),
+        ..known.clone()
+    };
+    advise_with(
+        &player_type,
+        &known,
+        &DeckConfig::default(),
+        &MatchContext::default(),
+    )
+    .expect("the standard deck is valid")
+}
+
+/// How `strategy` would play `known.hand` dealt from `deck`, where `context`
+/// leaves the player. Taking the discard is weighed against the average of
+/// every card that could be drawn, and a hand worth more as it stands plus
+/// the discard is played instead. Without a discard to take, it draws.
+///
+/// Every hand is judged as it stands, without searching ahead, see
+/// `advise_searched` for that.
+pub fn advise_with(
+    strategy: &dyn Strategy,
+    known: &KnownInformation,
+    deck: &DeckConfig,
+    context: &MatchContext,
+) -> Result<AutoPlayDecision, RummyError> {
+    advise_nodes(strategy, known, deck, context, &mut Ok)
+}
+
+/// Like `advise_with`, searching ahead from every hand as `config` says,
+/// sampling draws with `rng`. The config's node and time budgets are for the
+/// whole turn, shared evenly between the hands, so a turn with a time budget
+/// is decided about when it runs out.
+pub fn advise_searched(
+    strategy: &dyn Strategy,
+    known: &KnownInformation,
+    deck: &DeckConfig,
+    context: &MatchContext,
+    config: AnalysisConfig,
+    rng: &mut StdRng,
+) -> Result<AutoPlayDecision, RummyError> {
+    // The discard and every card that could be drawn
+    let mut hands_left = known.unknown(deck)?.len() as u32 + 1;
+    let deadline = config.time_budget.map(|budget| Instant::now() + budget);
+    advise_nodes(strategy, known, deck, context, &mut |node| {
+        let config = AnalysisConfig {
+            node_budget: (config.node_budget / hands_left as usize).max(1),
+            // Whatever time's left, so a quick search leaves more for the rest
+            time_budget: deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()) / hands_left),
+            ..config
+        };
+        hands_left = hands_left.saturating_sub(1).max(1);
+        evaluate_hand_deepening(&node, config, rng)
+    })
+}
+
+/// `advise_with`, with every hand put through `search` before it's judged.
+fn advise_nodes(
+    strategy: &dyn Strategy,
+    known: &KnownInformation,
+    deck: &DeckConfig,
+    context: &MatchContext,
+    search: &mut dyn FnMut(Node) -> Result<Node, RummyError>,
+) -> Result<AutoPlayDecision, RummyError> {
+    let hand = Hand {
+        cards: known.hand.clone(),
+    };
+    let possible_cards = known.unknown(deck)?;
+    let discard_pile: VecDeque<Card> = known.discards.iter().copied().collect();
+    let mut decide = |node: Node| -> Result<AutoPlayDecision, RummyError> {
+        let node = search(node)?;
+        let probabilities = node.calculate_cumulative_probabilities();
+        Ok(strategy.decide(
+            &node,
+            &GameView {
+                context,
+                probabilities: &probabilities,
+            },
+        ))
+    };
+
+    let retrieve_decision = match known.discards.last() {
+        Some(&discard_card) => {
+            let mut retrieve_hand = hand.clone();
+            retrieve_hand.cards.push(discard_card);
+            Some(decide(Node::new(
+                retrieve_hand,
+                possible_cards.clone(),
+                discard_pile.clone(),
+            ))?)
+        }
+        None => None,
+    };
+
+    // Each draw is judged against the hand as it stands
+    let baseline_score = best_meld(&hand.cards).score;
+    let mut total_draw_score = 0.0;
+    for &possible_draw_card in &possible_cards {
+        let mut draw_hand = hand.clone();
+        draw_hand.cards.push(possible_draw_card);
+        let draw_node = Node {
+            baseline_score,
+            ..Node::new(draw_hand, possible_cards.clone(), discard_pile.clone())
+        };
+        total_draw_score += decide(draw_node)?.expected_score;
+    }
+
+    let average_draw_score = if possible_cards.is_empty() {
+        0.0
+    } else {
+        total_draw_score / possible_cards.len() as f64
+    };
+
+    let draw_decision = AutoPlayDecision {
+        action: PlayAction::Draw,
+        confidence: 0.5,
+        expected_score: average_draw_score,
+        card_to_discard: None,
+    };
+
+    let Some(retrieve_decision) = retrieve_decision else {
+        return Ok(draw_decision);
+    };
+    if retrieve_decision.expected_score <= draw_decision.expected_score {
+        return Ok(draw_decision);
+    }
+
+    Ok(if retrieve_decision.action == PlayAction::Play {
+        AutoPlayDecision {
+            action: PlayAction::Play,
+            confidence: retrieve_decision.confidence,
+            expected_score: retrieve_decision.expected_score,
+            card_to_discard: None,
+        }
+    } else {
+        AutoPlayDecision {
+            action: PlayAction::Retrieve,
+            confidence: retrieve_decision.confidence,
+            expected_score: retrieve_decision.expected_score,
+            card_to_discard: retrieve_decision.card_to_discard,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(cards: &str) -> Vec<Card> {
+        cards.split(' ').map(|card| card.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_advice_from_a_hand_and_the_table() {
+        let hand = Hand {
+            cards: cards("9s 9h 9d 2c 5h"),
+        };
+        let known = KnownInformation {
+            discards: cards("3d 9c"),
+            ..KnownInformation::default()
+        };
+
+        // The last nine on offer makes four of a kind
+        let decision = advise(&hand, &known, PlayerType::Balanced);
+        assert_ne!(decision.action, PlayAction::Draw);
+        assert!(decision.expected_score >= 50.0);
+
+        // With nothing to take, it draws
+        let decision = advise(&hand, &KnownInformation::default(), PlayerType::Balanced);
+        assert_eq!(decision.action, PlayAction::Draw);
+
+        // The hand's in the node, and out of the cards left to draw
+        let node = Node::for_hand(
+            hand.clone(),
+            &KnownInformation {
+                hand: hand.cards.clone(),
+                ..known
+            },
+        );
+        assert_eq!(node.baseline_score, 15);
+        assert_eq!(node.possible_cards.len(), 52 - 7);
+        assert_eq!(node.discard_pile.back(), Some(&"9c".parse().unwrap()));
+    }
+}
@@ -0,0 +1,276 @@
+//! A light-hearted integrity check on the shuffle.
+//!
+//! The `Auditor` watches every hand dealt in a game and, once it's over,
+//! compares how often each player was dealt each meld with how often a fair
+//! shuffle deals it. Outcomes too extreme to put down to luck are flagged,
+//! mostly so a club that's sure the deck hates them can check.
+
+use crate::card::Card;
+use crate::error::RummyError;
+use crate::game::events::{EventSink, GameEvent};
+use crate::game::rules::Variant;
+use crate::game::{DeckConfig, Hand};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+/// How unlikely an outcome has to be, before allowing for how many were
+/// checked, to be flagged.
+pub const AUDIT_SIGNIFICANCE: f64 = 0.01;
+
+/// Deals simulated to estimate how often a fair shuffle deals each meld.
+pub const AUDIT_SIMULATIONS: usize = 20_000;
+
+/// One player's deals, measured against one meld.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditFinding {
+    pub player: String,
+    /// The meld the deals were counted against, e.g. "Flush".
+    pub meld: &'static str,
+    /// How many of the player's deals held that meld or better.
+    pub observed: usize,
+    pub deals: usize,
+    /// How many a fair shuffle would deal, on average.
+    pub expected: f64,
+    /// The chance of a result at least this far from `expected`, either way.
+    pub p_value: f64,
+    /// Whether the result is too extreme to put down to luck.
+    pub flagged: bool,
+}
+
+impl AuditFinding {
+    /// Dealt more of the meld than expected, rather than fewer.
+    pub fn is_lucky(&self) -> bool {
+        self.observed as f64 > self.expected
+    }
+}
+
+/// What the audit found, most extreme first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuditReport {
+    pub deals: usize,
+    pub findings: Vec<AuditFinding>,
+}
+
+impl AuditReport {
+    pub fn flagged(&self) -> impl Iterator<Item = &AuditFinding> {
+        self.findings.iter().filter(|finding| finding.flagged)
+    }
+}
+
+/// Keeps every hand dealt in a game, to audit once it's over.
+#[derive(Clone, Debug)]
+pub struct Auditor {
+    variant: Variant,
+    deck: DeckConfig,
+    /// Every hand dealt, with who it was dealt to.
+    dealt: Vec<(String, Vec<Card>)>,
+}
+
+impl Auditor {
+    pub fn new(variant: Variant, deck: DeckConfig) -> Self {
+        Self {
+            variant,
+            deck,
+            dealt: Vec::new(),
+        }
+    }
+
+    /// Measures every player's deals against a fair shuffle, estimated from
+    /// `simulations` deals shuffled with `rng`. Variants without melds, like
+    /// gin, have nothing to measure.
+    pub fn report<R: Rng + ?Sized>(
+        &self,
+        simulations: usize,
+        rng: &mut R,
+    ) -> Result<AuditReport, RummyError> {
+        let thresholds = self.variant.meld_names();
+        let rates = self.fair_rates(thresholds, simulations, rng)?;
+
+        let mut players: Vec<&str> = Vec::new();
+        for (player, _) in &self.dealt {
+            if !players.contains(&player.as_str()) {
+                players.push(player);
+            }
+        }
+
+        let mut findings = Vec::new();
+        for player in players {
+            let scores: Vec<u64> = self
+                .dealt
+                .iter()
+                .filter(|(name, _)| name == player)
+                .map(|(_, cards)| self.best_meld_score(cards))
+                .collect();
+
+            for (&(threshold, meld), &rate) in thresholds.iter().zip(&rates) {
+                let observed = scores.iter().filter(|score| **score >= threshold).count();
+                findings.push(AuditFinding {
+                    player: player.to_string(),
+                    meld,
+                    observed,
+                    deals: scores.len(),
+                    expected: rate * scores.len() as f64,
+                    p_value: two_sided_p_value(observed, scores.len(), rate),
+                    flagged: false,
+                });
+            }
+        }
+
+        // With this many checks, something is bound to look unlikely by chance
+        let cutoff = AUDIT_SIGNIFICANCE / findings.len().max(1) as f64;
+        for finding in &mut findings {
+            finding.flagged = finding.p_value < cutoff;
+        }
+        findings.sort_by(|a, b| a.p_value.total_cmp(&b.p_value));
+
+        Ok(AuditReport {
+            deals: self.dealt.len(),
+            findings,
+        })
+    }
+
+    fn best_meld_score(&self, cards: &[Card]) -> u64 {
+        self.variant
+            .best_meld(&Hand {
+                cards: cards.to_vec(),
+            })
+            .0
+    }
+
+    /// How often a fair deal holds each threshold's meld or better.
+    fn fair_rates<R: Rng + ?Sized>(
+        &self,
+        thresholds: &[(u64, &'static str)],
+        simulations: usize,
+        rng: &mut R,
+    ) -> Result<Vec<f64>, RummyError> {
+        let mut deck = self.deck.cards()?;
+        let hand_size = self.variant.hand_size().min(deck.len());
+        let mut hits = vec![0usize; thresholds.len()];
+
+        for _ in 0..simulations {
+            let (hand, _) = deck.partial_shuffle(rng, hand_size);
+            let score = self.best_meld_score(hand);
+            for (hit, (threshold, _)) in hits.iter_mut().zip(thresholds) {
+                if score >= *threshold {
+                    *hit += 1;
+                }
+            }
+        }
+
+        // A meld never seen in the simulation still isn't impossible
+        let floor = 1.0 / (simulations as f64 + 1.0);
+        Ok(hits
+            .into_iter()
+            .map(|hit| (hit as f64 / simulations.max(1) as f64).max(floor))
+            .collect())
+    }
+}
+
+impl EventSink for Auditor {
+    fn emit(&mut self, event: &GameEvent) {
+        if let GameEvent::RoundDealt { hands, .. } = event {
+            self.dealt.extend(hands.iter().cloned());
+        }
+    }
+}
+
+/// The chance of `k` or more successes in `n` tries at `p` each.
+pub fn binomial_upper_tail(k: usize, n: usize, p: f64) -> f64 {
+    (k..=n).map(|i| binomial_pmf(i, n, p)).sum::<f64>().min(1.0)
+}
+
+/// The chance of `k` or fewer successes in `n` tries at `p` each.
+pub fn binomial_lower_tail(k: usize, n: usize, p: f64) -> f64 {
+    (0..=k.min(n))
+        .map(|i| binomial_pmf(i, n, p))
+        .sum::<f64>()
+        .min(1.0)
+}
+
+/// The chance of a result as far from the expected as `k` successes in `n`
+/// tries at `p` each, in either direction.
+pub fn two_sided_p_value(k: usize, n: usize, p: f64) -> f64 {
+    let tail = binomial_upper_tail(k, n, p).min(binomial_lower_tail(k, n, p));
+    (tail * 2.0).min(1.0)
+}
+
+fn binomial_pmf(k: usize, n: usize, p: f64) -> f64 {
+    let ln_choose = ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k);
+    let ln_p = if k == 0 { 0.0 } else { k as f64 * p.ln() };
+    let ln_q = if k == n {
+        0.0
+    } else {
+        (n - k) as f64 * (1.0 - p).ln()
+    };
+    (ln_choose + ln_p + ln_q).exp()
+}
+
+fn ln_factorial(n: usize) -> f64 {
+    (2..=n).map(|i| (i as f64).ln()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn cards(cards: &[&str]) -> Vec<Card> {
+        cards.iter().map(|card| card.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_binomial_tails() {
+        let (n, p) = (10, 0.3);
+        let total: f64 = (0..=n).map(|k| binomial_pmf(k, n, p)).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        for k in 0..n {
+            let both = binomial_lower_tail(k, n, p) + binomial_upper_tail(k + 1, n, p);
+            assert!((both - 1.0).abs() < 1e-9);
+        }
+
+        assert!((binomial_upper_tail(3, 3, 0.5) - 0.125).abs() < 1e-9);
+        assert!((two_sided_p_value(3, 3, 0.5) - 0.25).abs() < 1e-9);
+        assert_eq!(two_sided_p_value(5, 10, 0.5), 1.0);
+    }
+
+    #[test]
+    fn test_a_rigged_deck_is_flagged() {
+        let mut auditor = Auditor::new(Variant::Bookclub, DeckConfig::default());
+        for _ in 0..8 {
+            auditor.emit(&GameEvent::RoundDealt {
+                player: "Ada".to_string(),
+                hands: vec![
+                    ("Ada".to_string(), cards(&["2h", "5h", "9h", "Jh", "Kh"])),
+                    ("Bram".to_string(), cards(&["2c", "5d", "9s", "Jc", "Kd"])),
+                ],
+                deal_hash: 0,
+            });
+        }
+
+        let report = auditor
+            .report(2_000, &mut StdRng::seed_from_u64(7))
+            .unwrap();
+        assert_eq!(report.deals, 16);
+        assert_eq!(
+            report.findings.len(),
+            2 * Variant::Bookclub.meld_names().len()
+        );
+
+        let flagged: Vec<_> = report.flagged().collect();
+        assert!(!flagged.is_empty());
+        assert!(flagged.iter().all(|finding| finding.player == "Ada"));
+        assert!(flagged.iter().any(|finding| finding.meld == "Flush"));
+        assert!(flagged.iter().all(|finding| finding.is_lucky()));
+
+        // Gin has no melds to count
+        let gin = Auditor::new(Variant::Gin, DeckConfig::default());
+        assert!(
+            gin.report(10, &mut StdRng::seed_from_u64(7))
+                .unwrap()
+                .findings
+                .is_empty()
+        );
+    }
+}
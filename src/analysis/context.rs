@@ -0,0 +1,217 @@
+use crate::card::Card;
+use crate::game::Player;
+use std::collections::HashMap;
+
+/// Points a typical winning meld (a straight) is worth, used to turn score
+/// gaps into "rounds away".
+pub const TYPICAL_MELD_SCORE: usize = 20;
+
+/// Points a small meld (a sequence of three) is worth.
+pub const SMALL_MELD_SCORE: usize = 10;
+
+/// Where the match stands from one player's point of view.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchContext {
+    pub own_score: usize,
+    pub opponent_scores: Vec<usize>,
+    /// Score that ends the match. Zero means the match has no target, which
+    /// makes every round independent.
+    pub target_score: usize,
+    /// How likely each card is to help an opponent's meld if thrown away,
+    /// see `OpponentModel`. Cards nobody looks to want are left out.
+    pub discard_danger: HashMap<Card, f64>,
+    /// What strategies that sample, like `MonteCarlo`, are seeded from: the
+    /// game's `RngStream::Ai`, or zero away from a game.
+    pub seed: u64,
+}
+
+impl MatchContext {
+    /// Builds the context for `players[player_idx]`, everyone else is an opponent.
+    pub fn for_player(players: &[Player], player_idx: usize, target_score: usize) -> Self {
+        Self::for_team(players, player_idx, target_score, |_| None)
+    }
+
+    /// Builds the context for `players[player_idx]` at a table playing in
+    /// teams, `partner` giving every seat's partner. Scores are pooled, so
+    /// every other team is one opponent and the partner isn't one at all.
+    pub fn for_team(
+        players: &[Player],
+        player_idx: usize,
+        target_score: usize,
+        partner: impl Fn(usize) -> Option<usize>,
+    ) -> Self {
+        let team_score =
+            |seat: usize| players[seat].score + partner(seat).map_or(0, |p| players[p].score);
+        let opponent_scores = (0..players.len())
+            .filter(|&seat| seat != player_idx && Some(seat) != partner(player_idx))
+            // Each team once, by whichever of the two sits first
+            .filter(|&seat| partner(seat).is_none_or(|p| seat < p))
+            .map(team_score)
+            .collect();
+
+        Self {
+            own_score: team_score(player_idx),
+            opponent_scores,
+            target_score,
+            discard_danger: HashMap::new(),
+            seed: 0,
+        }
+    }
+
+    /// How likely throwing `card` away is to help an opponent, 0 to 1.
+    pub fn discard_danger(&self, card: &Card) -> f64 {
+        self.discard_danger.get(card).copied().unwrap_or(0.0)
+    }
+
+    /// Points still needed to reach the target.
+    pub fn points_to_win(&self) -> Option<usize> {
+        if self.target_score == 0 {
+            return None;
+        }
+
+        Some(self.target_score.saturating_sub(self.own_score))
+    }
+
+    /// Points the closest opponent still needs to reach the target.
+    pub fn closest_opponent_distance(&self) -> Option<usize> {
+        if self.target_score == 0 {
+            return None;
+        }
+
+        self.opponent_scores
+            .iter()
+            .map(|score| self.target_score.saturating_sub(*score))
+            .min()
+    }
+
+    /// Estimated rounds this player needs to win to reach the target.
+    pub fn rounds_remaining(&self) -> Option<usize> {
+        self.points_to_win()
+            .map(|points| points.div_ceil(TYPICAL_MELD_SCORE))
+    }
+
+    /// Estimated rounds the closest opponent needs to win to reach the target.
+    pub fn opponent_rounds_remaining(&self) -> Option<usize> {
+        self.closest_opponent_distance()
+            .map(|points| points.div_ceil(TYPICAL_MELD_SCORE))
+    }
+
+    /// How far ahead of the best opponent this player is (negative when behind).
+    pub fn lead(&self) -> i64 {
+        let best_opponent = self.opponent_scores.iter().copied().max().unwrap_or(0);
+        self.own_score as i64 - best_opponent as i64
+    }
+
+    /// Whether playing a meld worth `meld_score` would end the match in our favour.
+    pub fn is_winning_score(&self, meld_score: u64) -> bool {
+        match self.points_to_win() {
+            Some(points) => meld_score > 0 && meld_score as usize >= points,
+            None => false,
+        }
+    }
+
+    /// Whether a single small meld would be enough to reach the target.
+    pub fn is_one_small_meld_away(&self) -> bool {
+        matches!(self.points_to_win(), Some(points) if points > 0 && points <= SMALL_MELD_SCORE)
+    }
+
+    /// Multiplier applied to the risk penalties of the decision heuristics.
+    ///
+    /// When an opponent is one meld away from winning, holding on to a modest
+    /// meld doesn't protect anything, so risk counts for less. When we're a
+    /// full meld ahead, losing the current meld costs more than usual.
+    pub fn risk_scale(&self) -> f64 {
+        let (Some(own_rounds), Some(opponent_rounds)) =
+            (self.rounds_remaining(), self.opponent_rounds_remaining())
+        else {
+            return 1.0;
+        };
+
+        if opponent_rounds <= 1 && own_rounds > 1 {
+            0.5
+        } else if self.lead() >= TYPICAL_MELD_SCORE as i64 {
+            1.5
+        } else {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Hand;
+    use std::collections::VecDeque;
+
+    fn table(scores: &[usize]) -> Vec<Player> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(seat, &score)| Player {
+                name: format!("Seat {seat}"),
+                description: String::new(),
+                player_type: None,
+                hand: Hand { cards: Vec::new() },
+                actions: VecDeque::new(),
+                dialogue: VecDeque::new(),
+                score,
+            })
+            .collect()
+    }
+
+    fn standing(own_score: usize, opponent_scores: &[usize]) -> MatchContext {
+        MatchContext {
+            own_score,
+            opponent_scores: opponent_scores.to_vec(),
+            target_score: 100,
+            ..MatchContext::default()
+        }
+    }
+
+    #[test]
+    fn test_risk_follows_the_race_to_the_target() {
+        // An opponent one meld from the target, with rounds still to go
+        let chasing = standing(30, &[85, 10]);
+        assert_eq!(chasing.opponent_rounds_remaining(), Some(1));
+        assert_eq!(chasing.rounds_remaining(), Some(4));
+        assert_eq!(chasing.lead(), -55);
+        assert_eq!(chasing.risk_scale(), 0.5);
+
+        // A lead of a typical meld or more is worth protecting
+        let leading = standing(50, &[30, 10]);
+        assert_eq!(leading.lead(), TYPICAL_MELD_SCORE as i64);
+        assert_eq!(leading.risk_scale(), 1.5);
+        assert_eq!(standing(40, &[30]).risk_scale(), 1.0);
+
+        assert!(leading.is_winning_score(50));
+        assert!(!leading.is_winning_score(49));
+        // No target, no winning meld and no change in risk
+        let endless = MatchContext {
+            target_score: 0,
+            ..leading
+        };
+        assert!(!endless.is_winning_score(500));
+        assert_eq!(endless.rounds_remaining(), None);
+        assert_eq!(endless.risk_scale(), 1.0);
+    }
+
+    #[test]
+    fn test_partners_pool_their_scores() {
+        // Seat 2, across the table, is nearly home
+        let players = table(&[10, 20, 85, 20]);
+        let across = |seat: usize| Some((seat + 2) % 4);
+
+        // On their own seat 0 is far behind a rival about to win
+        let alone = MatchContext::for_player(&players, 0, 100);
+        assert_eq!(alone.closest_opponent_distance(), Some(15));
+        assert_eq!(alone.risk_scale(), 0.5);
+
+        // As partners they're one small meld away, with a lead to protect
+        let team = MatchContext::for_team(&players, 0, 100, across);
+        assert_eq!(team.own_score, 95);
+        assert_eq!(team.opponent_scores, [40]);
+        assert_eq!(team.closest_opponent_distance(), Some(60));
+        assert!(team.is_one_small_meld_away());
+        assert_eq!(team.risk_scale(), 1.5);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::analysis::Node;
+use crate::card::{Card, remove_one};
+use std::fmt::Write;
+
+impl Node {
+    /// The search tree below this node in Graphviz's DOT language, to see
+    /// why the AI went the way it did: `dot -Tsvg tree.dot > tree.svg`.
+    ///
+    /// Each node shows its hand, the best meld in it, the best discard and
+    /// how likely the search thought the hand was. Edges are labeled with
+    /// the card thrown away and the card drawn, and draws the search passed
+    /// over hang off their node as a dashed box.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph analysis {\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+
+        let mut next_id = 0;
+        self.write_dot(&mut dot, &mut next_id, 1.0);
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes this node and everything below it, numbering them from
+    /// `next_id`. Returns this node's id.
+    fn write_dot(&self, dot: &mut String, next_id: &mut usize, probability: f64) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let mut label = format!(
+            "{}\\nbaseline {}",
+            cards_label(&self.full_hand.cards),
+            self.baseline_score
+        );
+        if let Some(best) = self.possible_hands.iter().max_by_key(|h| h.meld_score) {
+            let _ = write!(label, "\\nbest: -{} for {}", best.discard, best.meld_score);
+        }
+        if self.depth > 0 {
+            let _ = write!(label, "\\np = {probability:.4}");
+        }
+        let _ = writeln!(dot, "    n{id} [label=\"{label}\"];");
+
+        if self.pruned > 0 {
+            let _ = writeln!(
+                dot,
+                "    n{id}_pruned [label=\"{} pruned\", style=dashed, color=gray];",
+                self.pruned
+            );
+            let _ = writeln!(dot, "    n{id} -> n{id}_pruned [style=dashed, color=gray];");
+        }
+
+        let branch_probability = probability / self.branches.len().max(1) as f64;
+        for branch in &self.branches {
+            let (discarded, drawn) = difference(&self.full_hand.cards, &branch.full_hand.cards);
+            let branch_id = branch.write_dot(dot, next_id, branch_probability);
+            let _ = writeln!(
+                dot,
+                "    n{id} -> n{branch_id} [label=\"-{} +{}\"];",
+                cards_label(&discarded),
+                cards_label(&drawn)
+            );
+        }
+
+        id
+    }
+}
+
+fn cards_label(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(Card::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The cards `from` lost and gained on the way to `to`.
+fn difference(from: &[Card], to: &[Card]) -> (Vec<Card>, Vec<Card>) {
+    let mut lost = from.to_vec();
+    let mut gained = Vec::new();
+    for card in to {
+        if !remove_one(&mut lost, card) {
+            gained.push(*card);
+        }
+    }
+    (lost, gained)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::{AnalysisConfig, AnalysisMode, Node, Search, evaluate_hand};
+    use crate::card::Card;
+    use crate::game::{DeckConfig, Hand};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn test_search_tree_as_dot() {
+        let cards: Vec<Card> = ["2s", "2h", "3s", "4s", "Kh", "9d"]
+            .map(|card| card.parse().unwrap())
+            .to_vec();
+        let mut possible_cards = DeckConfig::default().cards().unwrap();
+        possible_cards.retain(|card| !cards.contains(card));
+        let mut node = Node {
+            full_hand: Hand { cards },
+            possible_hands: Vec::new(),
+            possible_cards,
+            discard_pile: VecDeque::new(),
+            meld_score: None,
+            baseline_score: 0,
+            branches: Vec::new(),
+            depth: 0,
+            pruned: 0,
+        };
+        let search = Search::new(AnalysisConfig {
+            mode: AnalysisMode::Sampled(2),
+            max_depth: 1,
+            node_budget: 3,
+            time_budget: None,
+        });
+        evaluate_hand(&mut node, &search, &mut StdRng::seed_from_u64(1)).unwrap();
+
+        let dot = node.to_dot();
+        assert!(dot.starts_with("digraph analysis {"));
+        assert!(dot.contains("\\nbaseline 0\\nbest: -"));
+
+        // Three draws were searched before the budget ran out, the rest hang
+        // off the root as pruned
+        assert_eq!(node.branches.len(), 3);
+        assert!(node.pruned > 0);
+        assert!(dot.contains(&format!("n0_pruned [label=\"{} pruned\"", node.pruned)));
+        let edges = dot.lines().filter(|line| line.contains("->")).count();
+        assert_eq!(edges, node.branches.len() + 1);
+    }
+}
@@ -0,0 +1,280 @@
+//! Committees of strategies. Every member decides on the same hand and the
+//! ensemble goes with the vote, which smooths over any one heuristic's blind
+//! spots. How each member voted is kept, to see who carried the decision.
+
+use crate::analysis::{GameView, MatchContext, Node, Strategy};
+use crate::card::Card;
+use crate::game::{AutoPlayDecision, Hand, PlayAction, PlayerType};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// How many ballots an ensemble keeps before forgetting the oldest. It votes
+/// once for every card that could be drawn, so a turn is a few dozen.
+pub const BALLOTS_KEPT: usize = 256;
+
+/// How the members' decisions are counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Voting {
+    /// The action with the most weight behind it wins.
+    #[default]
+    Weighted,
+    /// The action its backers expect, on their weighted average, to score
+    /// the most with wins, however few of them there are.
+    HighestEv,
+}
+
+/// A strategy with a say in the ensemble's decisions.
+#[derive(Clone, Debug)]
+pub struct Member {
+    pub name: String,
+    /// How much the member's vote counts, relative to the others.
+    pub weight: f64,
+    pub strategy: Arc<dyn Strategy>,
+}
+
+/// How one member voted.
+#[derive(Clone, Debug)]
+pub struct Vote {
+    pub member: String,
+    pub weight: f64,
+    pub decision: AutoPlayDecision,
+}
+
+/// Every member's vote on a hand, and what the ensemble did.
+#[derive(Clone, Debug)]
+pub struct Ballot {
+    pub hand: Hand,
+    pub votes: Vec<Vote>,
+    pub decision: AutoPlayDecision,
+}
+
+/// Plays whatever its members decide between them.
+#[derive(Debug)]
+pub struct Ensemble {
+    members: Vec<Member>,
+    voting: Voting,
+    ballots: Mutex<VecDeque<Ballot>>,
+}
+
+impl Ensemble {
+    pub fn new(members: Vec<Member>, voting: Voting) -> Self {
+        Self {
+            members,
+            voting,
+            ballots: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// The ballots cast since they were last taken, oldest first.
+    pub fn take_ballots(&self) -> Vec<Ballot> {
+        let mut ballots = self.ballots.lock().unwrap_or_else(|err| err.into_inner());
+        ballots.drain(..).collect()
+    }
+
+    /// Counts `votes` into a decision.
+    fn count(&self, votes: &[Vote]) -> AutoPlayDecision {
+        let total_weight: f64 = votes.iter().map(|vote| vote.weight).sum();
+
+        let mut tallies: Vec<(PlayAction, f64, f64)> = Vec::new();
+        for vote in votes {
+            match tallies
+                .iter_mut()
+                .find(|(action, ..)| *action == vote.decision.action)
+            {
+                Some((_, weight, score)) => {
+                    *weight += vote.weight;
+                    *score += vote.weight * vote.decision.expected_score;
+                }
+                None => tallies.push((
+                    vote.decision.action.clone(),
+                    vote.weight,
+                    vote.weight * vote.decision.expected_score,
+                )),
+            }
+        }
+
+        let expected = |weight: f64, score: f64| if weight > 0.0 { score / weight } else { 0.0 };
+        let winner = tallies.iter().max_by(|a, b| {
+            let (a_ev, b_ev) = (expected(a.1, a.2), expected(b.1, b.2));
+            match self.voting {
+                Voting::Weighted => a.1.total_cmp(&b.1).then(a_ev.total_cmp(&b_ev)),
+                Voting::HighestEv => a_ev.total_cmp(&b_ev).then(a.1.total_cmp(&b.1)),
+            }
+        });
+        let Some((action, weight, score)) = winner else {
+            return AutoPlayDecision {
+                action: PlayAction::Draw,
+                confidence: 0.0,
+                expected_score: 0.0,
+                card_to_discard: None,
+            };
+        };
+
+        // The discard of the heaviest member backing the winning action
+        let card_to_discard = votes
+            .iter()
+            .filter(|vote| vote.decision.action == *action)
+            .max_by(|a, b| a.weight.total_cmp(&b.weight))
+            .and_then(|vote| vote.decision.card_to_discard);
+
+        AutoPlayDecision {
+            action: action.clone(),
+            confidence: if total_weight > 0.0 {
+                weight / total_weight
+            } else {
+                0.0
+            },
+            expected_score: expected(*weight, *score),
+            card_to_discard,
+        }
+    }
+}
+
+/// The three heuristics that ship with the game, with an equal say.
+impl Default for Ensemble {
+    fn default() -> Self {
+        let members = [
+            PlayerType::Conservative,
+            PlayerType::Aggressive,
+            PlayerType::Balanced,
+        ]
+        .map(|player_type| Member {
+            name: format!("{player_type:?}").to_lowercase(),
+            weight: 1.0,
+            strategy: Arc::new(player_type),
+        })
+        .to_vec();
+
+        Self::new(members, Voting::default())
+    }
+}
+
+impl Strategy for Ensemble {
+    fn decide(&self, node: &Node, view: &GameView) -> AutoPlayDecision {
+        let votes: Vec<Vote> = self
+            .members
+            .iter()
+            .map(|member| Vote {
+                member: member.name.clone(),
+                weight: member.weight,
+                decision: member.strategy.decide(node, view),
+            })
+            .collect();
+        let decision = self.count(&votes);
+
+        let mut ballots = self.ballots.lock().unwrap_or_else(|err| err.into_inner());
+        if ballots.len() == BALLOTS_KEPT {
+            ballots.pop_front();
+        }
+        ballots.push_back(Ballot {
+            hand: node.full_hand.clone(),
+            votes,
+            decision: decision.clone(),
+        });
+
+        decision
+    }
+
+    /// The card the most weight wants rid of.
+    fn discard(&self, node: &Node, context: &MatchContext) -> Card {
+        let mut tallies: Vec<(Card, f64)> = Vec::new();
+        for member in &self.members {
+            let card = member.strategy.discard(node, context);
+            match tallies.iter_mut().find(|(c, _)| *c == card) {
+                Some((_, weight)) => *weight += member.weight,
+                None => tallies.push((card, member.weight)),
+            }
+        }
+
+        tallies
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(card, _)| card)
+            .unwrap_or_else(|| node.find_safest_discard(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Always does the same thing, expecting the same score.
+    #[derive(Debug)]
+    struct Stubborn(PlayAction, f64);
+
+    impl Strategy for Stubborn {
+        fn decide(&self, node: &Node, _view: &GameView) -> AutoPlayDecision {
+            AutoPlayDecision {
+                action: self.0.clone(),
+                confidence: 1.0,
+                expected_score: self.1,
+                card_to_discard: (self.0 != PlayAction::Play).then(|| node.full_hand.cards[0]),
+            }
+        }
+    }
+
+    fn member(name: &str, weight: f64, action: PlayAction, score: f64) -> Member {
+        Member {
+            name: name.to_string(),
+            weight,
+            strategy: Arc::new(Stubborn(action, score)),
+        }
+    }
+
+    #[test]
+    fn test_ensemble_votes_are_counted_and_kept() {
+        let node = Node {
+            full_hand: Hand {
+                cards: vec!["2h".parse().unwrap(), "Kd".parse().unwrap()],
+            },
+            possible_hands: Vec::new(),
+            possible_cards: Vec::new(),
+            discard_pile: VecDeque::new(),
+            meld_score: None,
+            baseline_score: 0,
+            branches: Vec::new(),
+            depth: 0,
+            pruned: 0,
+        };
+        let probabilities = node.calculate_cumulative_probabilities();
+        let view = GameView {
+            context: &MatchContext::default(),
+            probabilities: &probabilities,
+        };
+        let members = vec![
+            member("cautious", 1.0, PlayAction::Play, 20.0),
+            member("timid", 1.0, PlayAction::Play, 30.0),
+            member("reckless", 1.5, PlayAction::Draw, 60.0),
+        ];
+
+        // Two players outweigh the one, at the score they expect between them
+        let ensemble = Ensemble::new(members.clone(), Voting::Weighted);
+        let decision = ensemble.decide(&node, &view);
+        assert_eq!(decision.action, PlayAction::Play);
+        assert_eq!(decision.expected_score, 25.0);
+        assert_eq!(decision.confidence, 2.0 / 3.5);
+        assert_eq!(decision.card_to_discard, None);
+
+        // But the one expects more
+        let ensemble = Ensemble::new(members, Voting::HighestEv);
+        let decision = ensemble.decide(&node, &view);
+        assert_eq!(decision.action, PlayAction::Draw);
+        assert_eq!(decision.card_to_discard, Some(node.full_hand.cards[0]));
+
+        let ballots = ensemble.take_ballots();
+        assert_eq!(ballots.len(), 1);
+        assert_eq!(ballots[0].hand, node.full_hand);
+        let voters: Vec<&str> = ballots[0].votes.iter().map(|v| v.member.as_str()).collect();
+        assert_eq!(voters, ["cautious", "timid", "reckless"]);
+        assert!(ensemble.take_ballots().is_empty());
+
+        // The built-in ensemble is seated like any other player type
+        let decision = PlayerType::Ensemble.decide(&node, &view);
+        assert!(decision.confidence > 0.0);
+        assert_eq!(Ensemble::default().members().len(), 3);
+    }
+}
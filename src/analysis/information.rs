@@ -0,0 +1,140 @@
+use crate::card::{Card, remove_one};
+use crate::error::RummyError;
+use crate::game::DeckConfig;
+use crate::game::events::{EventSink, GameEvent};
+
+/// What one seat knows about where the cards are this round: every card
+/// that's been shown at the table, in the order it turned up. Everything
+/// else could still be in the draw pile or someone's hand.
+///
+/// Fed from the engine's events, plus `see` for what the events don't say,
+/// like a player's own hand or the card turned over at the deal. A new round
+/// reshuffles everything, so it forgets what it saw once the round is won or
+/// dealt again.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InformationSet {
+    seen: Vec<Card>,
+}
+
+impl InformationSet {
+    pub fn see(&mut self, card: Card) {
+        if !self.seen.contains(&card) {
+            self.seen.push(card);
+        }
+    }
+
+    pub fn has_seen(&self, card: &Card) -> bool {
+        self.seen.contains(card)
+    }
+
+    /// Cards seen so far, oldest first.
+    pub fn seen(&self) -> &[Card] {
+        &self.seen
+    }
+
+    /// Cards of the deck `deck` describes that haven't been seen, every copy
+    /// of them when dealing from more than one deck. A card that's shown up
+    /// once can't be told apart from its twin, so neither copy is listed.
+    pub fn unseen(&self, deck: &DeckConfig) -> Result<Vec<Card>, RummyError> {
+        Ok(deck
+            .cards()?
+            .into_iter()
+            .filter(|card| !self.has_seen(card))
+            .collect())
+    }
+}
+
+/// What one seat knows for certain about where the cards are right now:
+/// the hand they hold, the discard pile everyone watched build up and the
+/// discards opponents picked up and still hold. Whatever's left of the deck
+/// could be anywhere else, in the draw pile or in someone's hand, which is
+/// all the analysis gets to draw from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KnownInformation {
+    pub hand: Vec<Card>,
+    pub discards: Vec<Card>,
+    pub picked_up: Vec<Card>,
+}
+
+impl KnownInformation {
+    /// Cards of the deck `deck` describes that aren't known to be anywhere,
+    /// every copy of them unaccounted for when dealing from more than one
+    /// deck.
+    pub fn unknown(&self, deck: &DeckConfig) -> Result<Vec<Card>, RummyError> {
+        let mut unknown = deck.cards()?;
+        for card in self
+            .hand
+            .iter()
+            .chain(&self.discards)
+            .chain(&self.picked_up)
+        {
+            remove_one(&mut unknown, card);
+        }
+        Ok(unknown)
+    }
+}
+
+impl EventSink for InformationSet {
+    fn emit(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::CardDiscarded { card, .. }
+            | GameEvent::Declared {
+                discarded: card, ..
+            } => self.see(*card),
+            GameEvent::HandPlayed { hand: cards, .. }
+            | GameEvent::Knocked { hand: cards, .. }
+            | GameEvent::LayoffMade { cards, .. } => {
+                for card in cards {
+                    self.see(*card);
+                }
+            }
+            GameEvent::RoundWon { .. }
+            | GameEvent::RoundRedealt { .. }
+            | GameEvent::RoundStalemate { .. }
+            | GameEvent::DeadHand { .. } => self.seen.clear(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(card: &str) -> Card {
+        card.parse().unwrap()
+    }
+
+    #[test]
+    fn test_information_set_follows_the_table() {
+        let mut info = InformationSet::default();
+        info.see(card("Qs"));
+        info.emit(&GameEvent::CardDiscarded {
+            player: "Ada".to_string(),
+            card: card("7h"),
+        });
+        info.emit(&GameEvent::CardDiscarded {
+            player: "Lee".to_string(),
+            card: card("Qs"),
+        });
+
+        assert_eq!(info.seen(), &[card("Qs"), card("7h")]);
+        assert!(info.has_seen(&card("7h")));
+        assert!(!info.has_seen(&card("7d")));
+
+        let unseen = info.unseen(&DeckConfig::default()).unwrap();
+        assert_eq!(unseen.len(), 50);
+        assert!(!unseen.contains(&card("7h")));
+
+        let unseen = info.unseen(&DeckConfig::standard(2)).unwrap();
+        assert_eq!(unseen.len(), 100);
+        assert_eq!(unseen.iter().filter(|c| **c == card("7d")).count(), 2);
+
+        info.emit(&GameEvent::RoundWon {
+            player: "Ada".to_string(),
+            score: 20,
+            hand: Vec::new(),
+        });
+        assert!(info.seen().is_empty());
+    }
+}
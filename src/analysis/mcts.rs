@@ -0,0 +1,222 @@
+//! Monte Carlo rollouts, an alternative to the expectation tree. Rather than
+//! working out the odds of every draw, the rest of the round is played out
+//! at random many times over, against opponents dealt random hands from the
+//! unseen cards, and each way of playing the hand is judged by how those
+//! rounds went.
+
+use crate::analysis::{GameView, MatchContext, Node, Strategy, TYPICAL_MELD_SCORE};
+use crate::card::{Card, remove_one};
+use crate::game::{AutoPlayDecision, PlayAction};
+use crate::scoring::{MELD_SIZE, best_meld_score};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Rounds played out for each way of playing a hand.
+pub const ROLLOUTS: usize = 64;
+
+/// Turns each rollout plays before the hand's played as it stands.
+pub const ROLLOUT_TURNS: usize = 8;
+
+/// The meld a rollout plays its hand at, everyone's included.
+const ROLLOUT_PLAY_AT: u64 = TYPICAL_MELD_SCORE as u64;
+
+/// Plays whichever way does best over rollouts of the rest of the round:
+/// playing the hand now scores its meld, and keeping each card but one is
+/// worth the average of the rollouts from there. A rollout draws a card a
+/// turn for everyone and keeps the best hand it can, and it's over as soon
+/// as anyone's meld is worth playing. It scores the meld if it was ours
+/// and nothing if an opponent got there first.
+///
+/// Rollouts are seeded from `seed` and the hand, so the same hand is played
+/// the same way every time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonteCarlo {
+    pub rollouts: usize,
+    pub turns: usize,
+    pub seed: u64,
+}
+
+impl Default for MonteCarlo {
+    fn default() -> Self {
+        Self {
+            rollouts: ROLLOUTS,
+            turns: ROLLOUT_TURNS,
+            seed: 0,
+        }
+    }
+}
+
+impl MonteCarlo {
+    /// The card in `node`'s hand best thrown away and what keeping the rest
+    /// is worth, over the rollouts.
+    fn best_discard(&self, node: &Node, opponents: usize) -> (Card, f64) {
+        let mut rng = StdRng::seed_from_u64(self.hand_seed(node));
+
+        node.full_hand
+            .cards
+            .iter()
+            .map(|&discard| {
+                let mut kept = node.full_hand.cards.clone();
+                remove_one(&mut kept, &discard);
+                let total: f64 = (0..self.rollouts)
+                    .map(|_| self.rollout(&kept, &node.possible_cards, opponents, &mut rng))
+                    .sum();
+                (discard, total / self.rollouts.max(1) as f64)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("a node has cards in hand")
+    }
+
+    fn hand_seed(&self, node: &Node) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.seed.hash(&mut hasher);
+        node.full_hand.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Plays out the rest of the round once from `hand`, against
+    /// `opponents` dealt from the `unseen` cards, and returns what it
+    /// scored us.
+    fn rollout(&self, hand: &[Card], unseen: &[Card], opponents: usize, rng: &mut StdRng) -> f64 {
+        let mut deck = unseen.to_vec();
+        deck.shuffle(rng);
+        let mut hands: Vec<Vec<Card>> = (0..opponents)
+            .map(|_| deck.split_off(deck.len().saturating_sub(MELD_SIZE)))
+            .collect();
+        let mut hand = hand.to_vec();
+
+        // We've just thrown away, so the opponents draw first
+        for _ in 0..self.turns {
+            for opponent in &mut hands {
+                let Some(card) = deck.pop() else {
+                    return 0.0;
+                };
+                if keep_best(opponent, card) >= ROLLOUT_PLAY_AT {
+                    return 0.0;
+                }
+            }
+            let Some(card) = deck.pop() else {
+                break;
+            };
+            let score = keep_best(&mut hand, card);
+            if score >= ROLLOUT_PLAY_AT {
+                return score as f64;
+            }
+        }
+
+        best_meld_score(&hand).unwrap_or(0) as f64
+    }
+}
+
+/// Adds `card` to `hand` and throws away whichever card leaves the best
+/// meld, returning its score.
+fn keep_best(hand: &mut Vec<Card>, card: Card) -> u64 {
+    hand.push(card);
+    if hand.len() <= MELD_SIZE {
+        return best_meld_score(hand).unwrap_or(0);
+    }
+
+    let (worst, score) = (0..hand.len())
+        .map(|idx| {
+            let mut kept = hand.clone();
+            kept.swap_remove(idx);
+            (idx, best_meld_score(&kept).unwrap_or(0))
+        })
+        .max_by_key(|(_, score)| *score)
+        .expect("the hand has cards in it");
+    hand.swap_remove(worst);
+    score
+}
+
+impl Strategy for MonteCarlo {
+    fn decide(&self, node: &Node, view: &GameView) -> AutoPlayDecision {
+        let baseline = node.baseline_score as f64;
+        let play = AutoPlayDecision {
+            action: PlayAction::Play,
+            confidence: 0.95,
+            expected_score: baseline,
+            card_to_discard: None,
+        };
+        if view.context.is_winning_score(node.baseline_score) {
+            return play;
+        }
+
+        let opponents = view.context.opponent_scores.len().max(1);
+        let (discard, expected) = self.best_discard(node, opponents);
+        if baseline >= expected {
+            return play;
+        }
+
+        AutoPlayDecision {
+            action: PlayAction::Draw,
+            confidence: (expected - baseline) / expected,
+            expected_score: expected,
+            card_to_discard: Some(discard),
+        }
+    }
+
+    fn discard(&self, node: &Node, context: &MatchContext) -> Card {
+        let opponents = context.opponent_scores.len().max(1);
+        self.best_discard(node, opponents).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{HandProbabilityAnalysis, KnownInformation};
+    use crate::game::Hand;
+
+    fn node(hand: &str) -> Node {
+        let cards: Vec<Card> = hand.split(' ').map(|card| card.parse().unwrap()).collect();
+        let known = KnownInformation {
+            hand: cards.clone(),
+            ..KnownInformation::default()
+        };
+        Node {
+            baseline_score: best_meld_score(&cards[..MELD_SIZE]).unwrap_or(0),
+            ..Node::for_hand(Hand { cards }, &known)
+        }
+    }
+
+    #[test]
+    fn test_rollouts_play_a_made_hand_and_keep_what_builds() {
+        let strategy = MonteCarlo {
+            rollouts: 16,
+            ..MonteCarlo::default()
+        };
+        let context = MatchContext::default();
+        let decide = |node: &Node| {
+            let probabilities = HandProbabilityAnalysis {
+                current_baseline: node.baseline_score,
+                round_probabilities: Vec::new(),
+                optimal_stop_round: None,
+                confidence_level: 0.5,
+                analysis_details: None,
+            };
+            strategy.decide(
+                node,
+                &GameView {
+                    context: &context,
+                    probabilities: &probabilities,
+                },
+            )
+        };
+
+        // Four of a kind is worth more than anything the rollouts find
+        let made = node("Ks Kh Kd Kc 2s 7h");
+        assert_eq!(decide(&made).action, PlayAction::Play);
+
+        // Only a pair, so draw and keep the pair
+        let building = node("9s 9h 2c 5h Jd 4s");
+        let decision = decide(&building);
+        assert_eq!(decision.action, PlayAction::Draw);
+        let discard = decision.card_to_discard.unwrap();
+        assert!(!["9s", "9h"].contains(&discard.to_string().as_str()));
+
+        // The same hand's played the same way
+        assert_eq!(strategy.discard(&building, &context), discard);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::card::{Card, Name, Suite, remove_one};
+use crate::game::events::{EventSink, GameEvent};
+use std::collections::HashMap;
+
+/// What picking up a card says about wanting its name, and its suite.
+const PICKUP_NAME_WEIGHT: f64 = 3.0;
+const PICKUP_SUITE_WEIGHT: f64 = 2.0;
+
+/// What picking up a card says about wanting the names either side of it,
+/// for a straight.
+const PICKUP_NEIGHBOUR_WEIGHT: f64 = 1.0;
+
+/// How much less a player is thought to want a name, or a suite, once
+/// they've thrown one away.
+const DISCARD_NAME_DAMPING: f64 = 0.5;
+const DISCARD_SUITE_DAMPING: f64 = 0.75;
+
+/// How much a card's danger counts against throwing it away, in the same
+/// points as the analysis' strategic card values.
+pub const DANGER_WEIGHT: f64 = 10.0;
+
+/// What one opponent has given away about their hand this round: the
+/// discards they've picked up and the cards they've thrown away.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpponentProfile {
+    picked_up: Vec<Card>,
+    discarded: Vec<Card>,
+}
+
+impl OpponentProfile {
+    /// Cards picked up off the discard pile, oldest first.
+    pub fn picked_up(&self) -> &[Card] {
+        &self.picked_up
+    }
+
+    /// Cards thrown away, oldest first.
+    pub fn discarded(&self) -> &[Card] {
+        &self.discarded
+    }
+
+    /// Cards picked up that they haven't thrown away again, so they must
+    /// still be holding.
+    pub fn still_held(&self) -> Vec<Card> {
+        let mut held = self.picked_up.clone();
+        for card in &self.discarded {
+            remove_one(&mut held, card);
+        }
+        held
+    }
+
+    /// How likely they are to be holding each name, in `Name::ALL` order.
+    /// Every name is as likely as the next until they've picked something up.
+    pub fn name_distribution(&self) -> [f64; 13] {
+        let mut weights = [1.0; 13];
+        for card in &self.picked_up {
+            let idx = name_index(card.name);
+            weights[idx] += PICKUP_NAME_WEIGHT;
+            for neighbour in [idx.wrapping_sub(1), idx + 1] {
+                if let Some(weight) = weights.get_mut(neighbour) {
+                    *weight += PICKUP_NEIGHBOUR_WEIGHT;
+                }
+            }
+        }
+        for card in &self.discarded {
+            weights[name_index(card.name)] *= DISCARD_NAME_DAMPING;
+        }
+
+        normalize(weights)
+    }
+
+    /// How likely they are to be holding each suite, in `Suite::ALL` order.
+    pub fn suite_distribution(&self) -> [f64; 4] {
+        let mut weights = [1.0; 4];
+        for card in &self.picked_up {
+            weights[suite_index(card.suite)] += PICKUP_SUITE_WEIGHT;
+        }
+        for card in &self.discarded {
+            weights[suite_index(card.suite)] *= DISCARD_SUITE_DAMPING;
+        }
+
+        normalize(weights)
+    }
+
+    /// How likely `card` is to help this opponent's meld, from 0 when nothing
+    /// they've done points at it towards 1 the more they've collected
+    /// around it.
+    pub fn danger(&self, card: &Card) -> f64 {
+        // Both are 1 for a card no more wanted than any other
+        let name_interest = self.name_distribution()[name_index(card.name)] * 13.0;
+        let suite_interest = self.suite_distribution()[suite_index(card.suite)] * 4.0;
+        let interest = name_interest * 0.6 + suite_interest * 0.4;
+
+        (1.0 - 1.0 / interest).max(0.0)
+    }
+}
+
+/// What every player at the table has given away about their hand, fed from
+/// the engine's events. A new round deals new hands, so it forgets
+/// everything once the round is won or dealt again.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpponentModel {
+    profiles: HashMap<String, OpponentProfile>,
+}
+
+impl OpponentModel {
+    pub fn profile(&self, player: &str) -> Option<&OpponentProfile> {
+        self.profiles.get(player)
+    }
+
+    /// How dangerous throwing `card` away is for the players on `side`, a
+    /// player and their partner if they have one: the most anyone else looks
+    /// likely to want it.
+    pub fn danger(&self, side: &[&str], card: &Card) -> f64 {
+        self.profiles
+            .iter()
+            .filter(|(name, _)| !side.contains(&name.as_str()))
+            .map(|(_, profile)| profile.danger(card))
+            .fold(0.0, f64::max)
+    }
+
+    /// The danger of every card in `cards` that has any, for `side`.
+    pub fn dangers(&self, side: &[&str], cards: &[Card]) -> HashMap<Card, f64> {
+        cards
+            .iter()
+            .map(|card| (*card, self.danger(side, card)))
+            .filter(|(_, danger)| *danger > 0.0)
+            .collect()
+    }
+}
+
+impl EventSink for OpponentModel {
+    fn emit(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::CardDrawn {
+                player,
+                retrieved: Some(card),
+            } => self
+                .profiles
+                .entry(player.clone())
+                .or_default()
+                .picked_up
+                .push(*card),
+            GameEvent::CardDiscarded { player, card } => self
+                .profiles
+                .entry(player.clone())
+                .or_default()
+                .discarded
+                .push(*card),
+            GameEvent::RoundWon { .. }
+            | GameEvent::RoundRedealt { .. }
+            | GameEvent::RoundStalemate { .. }
+            | GameEvent::DeadHand { .. } => self.profiles.clear(),
+            _ => {}
+        }
+    }
+}
+
+fn name_index(name: Name) -> usize {
+    Name::ALL.iter().position(|n| *n == name).unwrap_or(0)
+}
+
+fn suite_index(suite: Suite) -> usize {
+    Suite::ALL.iter().position(|s| *s == suite).unwrap_or(0)
+}
+
+fn normalize<const N: usize>(weights: [f64; N]) -> [f64; N] {
+    let total: f64 = weights.iter().sum();
+    weights.map(|weight| weight / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{MatchContext, Node};
+    use crate::game::Hand;
+    use std::collections::VecDeque;
+
+    fn card(card: &str) -> Card {
+        card.parse().unwrap()
+    }
+
+    #[test]
+    fn test_opponents_collecting_a_name_make_it_dangerous() {
+        let mut model = OpponentModel::default();
+        for retrieved in ["7h", "7c"] {
+            model.emit(&GameEvent::CardDrawn {
+                player: "Bram".to_string(),
+                retrieved: Some(card(retrieved)),
+            });
+        }
+        model.emit(&GameEvent::CardDiscarded {
+            player: "Bram".to_string(),
+            card: card("Ks"),
+        });
+
+        let bram = model.profile("Bram").unwrap();
+        assert_eq!(bram.picked_up(), &[card("7h"), card("7c")]);
+        let names = bram.name_distribution();
+        assert!((names.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(names[name_index(Name::Seven)] > names[name_index(Name::Two)]);
+
+        // Ada should hang on to the last sevens, not the king Bram threw away
+        assert!(model.danger(&["Ada"], &card("7d")) > 0.5);
+        assert!(model.danger(&["Ada"], &card("8s")) > model.danger(&["Ada"], &card("Ks")));
+        assert_eq!(model.danger(&["Ada"], &card("Kd")), 0.0);
+        assert_eq!(model.danger(&["Bram"], &card("7d")), 0.0);
+        assert!(
+            !model
+                .dangers(&["Ada"], &[card("7d"), card("Kd")])
+                .contains_key(&card("Kd"))
+        );
+        // Nor is feeding a partner
+        assert_eq!(model.danger(&["Ada", "Bram"], &card("7d")), 0.0);
+
+        model.emit(&GameEvent::RoundWon {
+            player: "Ada".to_string(),
+            score: 20,
+            hand: Vec::new(),
+        });
+        assert_eq!(model, OpponentModel::default());
+    }
+
+    #[test]
+    fn test_the_ai_keeps_dangerous_cards() {
+        let hand = Hand {
+            cards: ["Ah", "Kh", "Qh", "Jh", "2c", "7d"].map(card).to_vec(),
+        };
+        let node = Node {
+            full_hand: hand,
+            possible_hands: Vec::new(),
+            possible_cards: Vec::new(),
+            discard_pile: VecDeque::new(),
+            meld_score: None,
+            baseline_score: 0,
+            branches: Vec::new(),
+            depth: 0,
+            pruned: 0,
+        };
+
+        let mut context = MatchContext::default();
+        let worst = node.find_worst_card_to_discard();
+        assert_eq!(node.find_safest_discard(&context), worst);
+
+        context.discard_danger.insert(worst, 1.0);
+        let safest = node.find_safest_discard(&context);
+        assert_ne!(safest, worst);
+        assert!(["2c", "7d"].map(card).contains(&safest));
+    }
+}
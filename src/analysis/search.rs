@@ -0,0 +1,287 @@
+use crate::card::Card;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Draws looked at per level when sampling, unless told otherwise.
+pub const DEFAULT_SAMPLES: usize = 10;
+
+/// How deep the search goes, unless told otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+/// Nodes a single search may build before it stops branching.
+pub const DEFAULT_NODE_BUDGET: usize = 100_000;
+
+/// Which draws the search looks at from each node.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AnalysisMode {
+    /// This many draws picked at random. Quick, but two searches of the same
+    /// hand can disagree.
+    Sampled(usize),
+    /// Every card that could still be drawn, so each level's expected values
+    /// are exact rather than estimated.
+    Exact,
+}
+
+/// How thoroughly `evaluate_hand` searches ahead.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AnalysisConfig {
+    pub mode: AnalysisMode,
+    /// Levels of draws searched below the hand being evaluated.
+    pub max_depth: usize,
+    /// Nodes the search may build. Once they're spent it stops branching, so
+    /// an exact search too big for its budget only covers part of the tree.
+    pub node_budget: usize,
+    /// How long the search may take. With a budget `evaluate_hand_deepening`
+    /// goes a level deeper at a time, and stops where the time runs out.
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            mode: AnalysisMode::Sampled(DEFAULT_SAMPLES),
+            max_depth: DEFAULT_MAX_DEPTH,
+            node_budget: DEFAULT_NODE_BUDGET,
+            time_budget: None,
+        }
+    }
+}
+
+impl AnalysisConfig {
+    /// Every possible next draw, one level deep.
+    pub fn exact() -> Self {
+        Self {
+            mode: AnalysisMode::Exact,
+            max_depth: 1,
+            ..Self::default()
+        }
+    }
+}
+
+/// One search of the game tree: how to search, and how many nodes and how
+/// long it has left. Shared between the threads of a parallel search.
+#[derive(Debug)]
+pub struct Search {
+    config: AnalysisConfig,
+    remaining: AtomicUsize,
+    deadline: Option<Instant>,
+}
+
+impl Search {
+    /// The time budget, if any, starts now.
+    pub fn new(config: AnalysisConfig) -> Self {
+        let deadline = config.time_budget.map(|budget| Instant::now() + budget);
+        Self {
+            deadline,
+            ..Self::until(config, None)
+        }
+    }
+
+    /// A search that has to be done by `deadline`, whatever the config's
+    /// time budget.
+    pub fn until(config: AnalysisConfig, deadline: Option<Instant>) -> Self {
+        Self {
+            config,
+            remaining: AtomicUsize::new(config.node_budget),
+            deadline,
+        }
+    }
+
+    pub fn config(&self) -> &AnalysisConfig {
+        &self.config
+    }
+
+    /// Whether a node at `depth` gets branches of its own.
+    pub fn branches_at(&self, depth: usize) -> bool {
+        depth < self.config.max_depth
+    }
+
+    /// Nodes the search can still build.
+    pub fn nodes_left(&self) -> usize {
+        self.remaining.load(Ordering::Relaxed)
+    }
+
+    /// Nodes the search has built so far.
+    pub fn nodes_spent(&self) -> usize {
+        self.config.node_budget - self.nodes_left()
+    }
+
+    /// A search of its own for one of `branches` searched side by side, with
+    /// an even share of the nodes left and the same deadline. Branches that
+    /// draw on their own shares build the same nodes however the threads
+    /// take them up, where a budget they raced for wouldn't.
+    pub fn share(&self, branches: usize) -> Self {
+        let config = AnalysisConfig {
+            node_budget: self.nodes_left() / branches.max(1),
+            ..self.config
+        };
+        Self::until(config, self.deadline)
+    }
+
+    /// Takes `nodes` built by its shares off what's left.
+    pub fn spend(&self, nodes: usize) {
+        let _ = self
+            .remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                Some(left.saturating_sub(nodes))
+            });
+    }
+
+    /// Whether the search has run out of time, and stopped branching.
+    pub fn timed_out(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Spends one node of the budget, or says there are none left or no time
+    /// to build them.
+    pub fn take_node(&self) -> bool {
+        if self.timed_out() {
+            return false;
+        }
+        self.remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok()
+    }
+
+    /// The draws to search from `available`, picked with `rng` when sampling.
+    pub fn draws<R: Rng + ?Sized>(&self, available: &[Card], rng: &mut R) -> Vec<Card> {
+        let mut draws = available.to_vec();
+        if let AnalysisMode::Sampled(samples) = self.config.mode {
+            let (selected, _) = draws.partial_shuffle(rng, samples.min(available.len()));
+            draws = selected.to_vec();
+        }
+        draws
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{Node, evaluate_hand, evaluate_hand_deepening};
+    use crate::game::{DeckConfig, Hand, PlayerType};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use std::collections::VecDeque;
+
+    fn node(hand: &[&str]) -> Node {
+        let cards: Vec<Card> = hand.iter().map(|card| card.parse().unwrap()).collect();
+        let mut possible_cards = DeckConfig::default().cards().unwrap();
+        possible_cards.retain(|card| !cards.contains(card));
+        Node {
+            full_hand: Hand { cards },
+            possible_hands: Vec::new(),
+            possible_cards,
+            discard_pile: VecDeque::new(),
+            meld_score: None,
+            baseline_score: 0,
+            branches: Vec::new(),
+            depth: 0,
+            pruned: 0,
+        }
+    }
+
+    #[test]
+    fn test_exact_search_draws_every_card() {
+        let hand = ["5h", "5c", "5d", "5s", "9h", "9c"];
+        let config = PlayerType::Conservative.analysis_config();
+        assert_eq!(config, AnalysisConfig::exact());
+
+        let mut exact = node(&hand);
+        let search = Search::new(config);
+        evaluate_hand(&mut exact, &search, &mut StdRng::seed_from_u64(1)).unwrap();
+        // Every discard leaves a meld, then every one of the 46 cards left to draw
+        assert_eq!(exact.branches.len(), 6 * 46);
+        assert!(
+            exact
+                .branches
+                .iter()
+                .all(|branch| branch.branches.is_empty())
+        );
+        assert_eq!(search.nodes_left(), DEFAULT_NODE_BUDGET - 6 * 46);
+
+        let mut sampled = node(&hand);
+        let search = Search::new(AnalysisConfig {
+            max_depth: 1,
+            ..AnalysisConfig::default()
+        });
+        evaluate_hand(&mut sampled, &search, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(sampled.branches.len(), 6 * DEFAULT_SAMPLES);
+
+        let mut capped = node(&hand);
+        let search = Search::new(AnalysisConfig {
+            node_budget: 50,
+            ..AnalysisConfig::exact()
+        });
+        evaluate_hand(&mut capped, &search, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(capped.branches.len(), 50);
+        assert!(!search.take_node());
+    }
+
+    #[test]
+    fn test_a_node_budget_builds_the_same_tree_every_time() {
+        fn hands(node: &Node, seen: &mut Vec<Vec<Card>>) {
+            seen.push(node.full_hand.cards.clone());
+            for branch in &node.branches {
+                hands(branch, seen);
+            }
+        }
+        // Too few nodes for the whole tree, so the branches have to share
+        let search = || {
+            let mut searched = node(&["5h", "5c", "5d", "9s", "9h", "2c"]);
+            let search = Search::new(AnalysisConfig {
+                node_budget: 300,
+                ..AnalysisConfig::default()
+            });
+            evaluate_hand(&mut searched, &search, &mut StdRng::seed_from_u64(1)).unwrap();
+            let mut seen = Vec::new();
+            hands(&searched, &mut seen);
+            (seen, search.nodes_left())
+        };
+
+        let (tree, left) = search();
+        assert!(tree.len() <= 301);
+        for _ in 0..5 {
+            assert_eq!(search(), (tree.clone(), left));
+        }
+    }
+
+    #[test]
+    fn test_deepening_keeps_the_deepest_search_in_time() {
+        let hand = node(&["5h", "5c", "5d", "9s", "9h", "2c"]);
+        let config = AnalysisConfig {
+            max_depth: 2,
+            time_budget: Some(Duration::from_secs(60)),
+            ..AnalysisConfig::default()
+        };
+        let deepest = |node: &Node| {
+            node.branches
+                .iter()
+                .any(|branch| !branch.branches.is_empty())
+        };
+
+        // Plenty of time goes all the way down
+        let searched =
+            evaluate_hand_deepening(&hand, config, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert!(deepest(&searched));
+
+        // No time at all still looks at the hand itself
+        let rushed = AnalysisConfig {
+            time_budget: Some(Duration::ZERO),
+            ..config
+        };
+        let searched =
+            evaluate_hand_deepening(&hand, rushed, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(searched.possible_hands.len(), 6);
+        assert!(searched.branches.is_empty());
+
+        let search = Search::new(rushed);
+        assert!(search.timed_out());
+        assert!(!search.take_node());
+        assert!(!Search::new(config).timed_out());
+    }
+}
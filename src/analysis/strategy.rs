@@ -0,0 +1,217 @@
+use crate::analysis::{AnalysisConfig, HandProbabilityAnalysis, MatchContext, MonteCarlo, Node};
+use crate::card::Card;
+use crate::game::{AutoPlayDecision, PlayAction, PlayerType};
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::sync::Mutex;
+
+/// What a strategy is told about the game when it decides: where the match
+/// stands and the odds the analysis has worked out for the hand.
+#[derive(Clone, Copy, Debug)]
+pub struct GameView<'a> {
+    pub context: &'a MatchContext,
+    pub probabilities: &'a HandProbabilityAnalysis,
+}
+
+/// How an AI player plays its turns. The built-in player types are
+/// strategies, and a bot of your own can be given a seat with
+/// `GameEngine::register_strategy`.
+///
+/// The engine asks for a decision on every hand the player could end up
+/// holding, the one with the top discard and one for each card that could
+/// be drawn, and takes whichever way looks best. Only bookclub rummy is
+/// analysed, in the other variants every AI plays the same greedy game.
+pub trait Strategy: fmt::Debug + Send + Sync {
+    /// Whether to play, draw or retrieve holding `node`'s hand.
+    fn decide(&self, node: &Node, view: &GameView) -> AutoPlayDecision;
+
+    /// The card to throw away from `node`'s hand once a card's been taken.
+    fn discard(&self, node: &Node, context: &MatchContext) -> Card {
+        node.find_safest_discard(context)
+    }
+
+    /// How far ahead the engine searches from each hand before asking.
+    fn analysis_config(&self) -> AnalysisConfig {
+        AnalysisConfig::default()
+    }
+
+    /// Starts any randomness the strategy has over from `seed`, so a game
+    /// set up from a seed plays out the same every time. Strategies that
+    /// don't roll dice needn't bother.
+    fn reseed(&self, _seed: u64) {}
+}
+
+/// The three heuristics the analysis ships with.
+impl Strategy for PlayerType {
+    fn decide(&self, node: &Node, view: &GameView) -> AutoPlayDecision {
+        node.make_autoplay_decision(self.clone(), view.probabilities, view.context)
+    }
+
+    fn discard(&self, node: &Node, context: &MatchContext) -> Card {
+        match self {
+            PlayerType::MonteCarlo => MonteCarlo {
+                seed: context.seed,
+                ..MonteCarlo::default()
+            }
+            .discard(node, context),
+            _ => node.find_safest_discard(context),
+        }
+    }
+
+    fn analysis_config(&self) -> AnalysisConfig {
+        PlayerType::analysis_config(self)
+    }
+}
+
+/// Plays, draws and retrieves at random, and throws away any card. Good for
+/// nothing but a baseline to measure other strategies against.
+#[derive(Debug)]
+pub struct RandomStrategy {
+    rng: Mutex<StdRng>,
+}
+
+impl RandomStrategy {
+    /// Moves picked from `seed`, the same every time.
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl Default for RandomStrategy {
+    fn default() -> Self {
+        Self::seeded(rand::rng().random())
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn decide(&self, node: &Node, _view: &GameView) -> AutoPlayDecision {
+        let mut rng = self.rng.lock().unwrap_or_else(|err| err.into_inner());
+        let action = [PlayAction::Draw, PlayAction::Play, PlayAction::Retrieve]
+            .choose(&mut *rng)
+            .cloned()
+            .unwrap_or(PlayAction::Draw);
+
+        AutoPlayDecision {
+            card_to_discard: (action != PlayAction::Play)
+                .then(|| node.full_hand.cards.choose(&mut *rng).copied())
+                .flatten(),
+            action,
+            confidence: 0.0,
+            // Which way the engine goes is down to the scores, so they're
+            // random too
+            expected_score: rng.random_range(0.0..100.0),
+        }
+    }
+
+    fn discard(&self, node: &Node, context: &MatchContext) -> Card {
+        let mut rng = self.rng.lock().unwrap_or_else(|err| err.into_inner());
+        match node.full_hand.cards.choose(&mut *rng) {
+            Some(card) => *card,
+            None => node.find_safest_discard(context),
+        }
+    }
+
+    fn reseed(&self, seed: u64) {
+        *self.rng.lock().unwrap_or_else(|err| err.into_inner()) = StdRng::seed_from_u64(seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{AiTurn, GameEngine};
+    use crate::game::{Hand, Player};
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+
+    /// Plays whatever it's dealt.
+    #[derive(Debug)]
+    struct Impatient;
+
+    impl Strategy for Impatient {
+        fn decide(&self, node: &Node, _view: &GameView) -> AutoPlayDecision {
+            AutoPlayDecision {
+                action: PlayAction::Play,
+                confidence: 1.0,
+                expected_score: node.baseline_score as f64,
+                card_to_discard: None,
+            }
+        }
+    }
+
+    fn player(name: &str) -> Player {
+        Player {
+            name: name.to_string(),
+            description: String::new(),
+            player_type: Some(PlayerType::Conservative),
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        }
+    }
+
+    #[test]
+    fn test_registered_strategies_play_the_seat() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        // Taking the king makes four of a kind, which beats any blind draw,
+        // so the engine goes the way the strategy says
+        engine.players[0].hand = Hand {
+            cards: ["Kh", "Kd", "Kc", "2h", "3s"]
+                .map(|card| card.parse().unwrap())
+                .to_vec(),
+        };
+        engine.deck.discard_pile.push_back("Ks".parse().unwrap());
+        engine
+            .register_strategy("Ada", Arc::new(Impatient))
+            .unwrap();
+        assert!(
+            engine
+                .register_strategy("Zed", Arc::new(Impatient))
+                .is_err()
+        );
+        assert!(matches!(
+            engine.take_ai_turn().unwrap(),
+            AiTurn::Played { .. }
+        ));
+
+        // The same seed makes the same moves
+        let decide = |seed| {
+            let node = Node {
+                full_hand: Hand {
+                    cards: vec!["2h".parse().unwrap(), "Kd".parse().unwrap()],
+                },
+                possible_hands: Vec::new(),
+                possible_cards: Vec::new(),
+                discard_pile: VecDeque::new(),
+                meld_score: None,
+                baseline_score: 0,
+                branches: Vec::new(),
+                depth: 0,
+                pruned: 0,
+            };
+            let probabilities = node.calculate_cumulative_probabilities();
+            let view = GameView {
+                context: &MatchContext::default(),
+                probabilities: &probabilities,
+            };
+            let random = RandomStrategy::seeded(seed);
+            (0..8)
+                .map(|_| random.decide(&node, &view).action)
+                .collect::<Vec<_>>()
+        };
+        let moves = decide(7);
+        assert_eq!(moves, decide(7));
+        assert!(moves.contains(&PlayAction::Draw));
+
+        // A seat searches as its player type does, a bot of your own with
+        // the defaults
+        let conservative: &dyn Strategy = &PlayerType::Conservative;
+        assert_eq!(conservative.analysis_config(), AnalysisConfig::exact());
+        assert_eq!(Impatient.analysis_config(), AnalysisConfig::default());
+    }
+}
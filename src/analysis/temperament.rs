@@ -0,0 +1,89 @@
+use crate::analysis::MatchContext;
+use crate::game::PlayerType;
+
+/// Rounds behind the closest opponent an AI has to fall before it tilts
+/// aggressive.
+pub const BEHIND_ROUNDS: usize = 2;
+
+/// Rounds ahead of the closest opponent an AI has to get before it sits on
+/// its lead.
+pub const LEAD_ROUNDS: usize = 2;
+
+/// How an AI's temper drifts with the score: far enough behind it plays
+/// aggressive to catch up, far enough ahead it plays conservative to hold
+/// on, and otherwise it plays as it was seated. Rounds are counted as in
+/// `MatchContext::rounds_remaining`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TemperamentPolicy {
+    /// Rounds behind that tilt an AI aggressive.
+    pub behind_rounds: usize,
+    /// Rounds ahead that turn an AI conservative.
+    pub lead_rounds: usize,
+}
+
+impl Default for TemperamentPolicy {
+    fn default() -> Self {
+        Self {
+            behind_rounds: BEHIND_ROUNDS,
+            lead_rounds: LEAD_ROUNDS,
+        }
+    }
+}
+
+impl TemperamentPolicy {
+    /// How a player seated as `seated` plays where `context` leaves them.
+    /// A match without a target never tilts anyone.
+    pub fn temperament(&self, seated: &PlayerType, context: &MatchContext) -> PlayerType {
+        let (Some(own), Some(closest)) = (
+            context.rounds_remaining(),
+            context.opponent_rounds_remaining(),
+        ) else {
+            return seated.clone();
+        };
+
+        if own >= closest + self.behind_rounds {
+            PlayerType::Aggressive
+        } else if closest >= own + self.lead_rounds {
+            PlayerType::Conservative
+        } else {
+            seated.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standing(own_score: usize, opponent: usize) -> MatchContext {
+        MatchContext {
+            own_score,
+            opponent_scores: vec![opponent, 0],
+            target_score: 100,
+            ..MatchContext::default()
+        }
+    }
+
+    #[test]
+    fn test_the_score_tilts_the_temperament() {
+        let policy = TemperamentPolicy::default();
+        let tilt =
+            |own, opponent| policy.temperament(&PlayerType::Balanced, &standing(own, opponent));
+
+        // Neck and neck
+        assert_eq!(tilt(40, 30), PlayerType::Balanced);
+        // Three rounds to go against the leader's one
+        assert_eq!(tilt(40, 80), PlayerType::Aggressive);
+        assert_eq!(tilt(80, 40), PlayerType::Conservative);
+
+        // Without a target every round's on its own
+        let endless = MatchContext {
+            target_score: 0,
+            ..standing(0, 80)
+        };
+        assert_eq!(
+            policy.temperament(&PlayerType::Ensemble, &endless),
+            PlayerType::Ensemble
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Round-robin tournaments between strategies, to tell whether a change to
+//! one made it any better. Every pair of entrants plays the same number of
+//! heads-up games, and each game moves their Elo ratings.
+
+use crate::analysis::{Ensemble, MonteCarlo, RandomStrategy, Strategy};
+use crate::engine::{GameEngine, TurnPhase};
+use crate::error::RummyError;
+use crate::game::rules::Variant;
+use crate::game::{DeckConfig, Hand, Player, PlayerType};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// The rating everyone starts the tournament on.
+pub const STARTING_RATING: f64 = 1500.0;
+
+/// How far a single game can move a rating, the usual K-factor for club play.
+pub const RATING_K: f64 = 32.0;
+
+/// Turns a game can last before it's called a draw, so two strategies that
+/// never play their hands can't hold the tournament up forever.
+pub const MAX_TURNS: usize = 1000;
+
+/// A strategy entered under a name, which is what it's seated and rated as.
+#[derive(Clone, Debug)]
+pub struct Entrant {
+    pub name: String,
+    pub strategy: Arc<dyn Strategy>,
+}
+
+impl Entrant {
+    /// One of the strategies that ship with the game, by name:
+    /// conservative, aggressive, balanced, ensemble, monte-carlo or random.
+    pub fn builtin(name: &str) -> Result<Self, RummyError> {
+        let strategy: Arc<dyn Strategy> = match name.trim().to_lowercase().as_str() {
+            "conservative" => Arc::new(PlayerType::Conservative),
+            "aggressive" => Arc::new(PlayerType::Aggressive),
+            "balanced" => Arc::new(PlayerType::Balanced),
+            "ensemble" => Arc::new(Ensemble::default()),
+            "monte-carlo" | "mcts" => Arc::new(MonteCarlo::default()),
+            "random" => Arc::new(RandomStrategy::default()),
+            other => {
+                return Err(RummyError::Config(format!(
+                    "Unknown strategy {other:?}, expected conservative, aggressive, balanced, ensemble, monte-carlo or random"
+                )));
+            }
+        };
+
+        Ok(Self {
+            name: name.trim().to_lowercase(),
+            strategy,
+        })
+    }
+}
+
+/// How the tournament's games are played.
+#[derive(Clone, Debug)]
+pub struct TournamentConfig {
+    /// Games each pair of entrants plays. They take turns going first.
+    pub games: usize,
+    pub variant: Variant,
+    pub deck: DeckConfig,
+    /// Seeds the first game, and each game after it the next seed along, so
+    /// a tournament can be played again exactly.
+    pub seed: u64,
+    pub declarations: bool,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        Self {
+            games: 10,
+            variant: Variant::default(),
+            deck: DeckConfig::default(),
+            seed: 0,
+            declarations: false,
+        }
+    }
+}
+
+/// How one game of the tournament went. `seats` is in turn order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub seats: [String; 2],
+    pub scores: [usize; 2],
+    /// `None` when the game ran out of turns.
+    pub winner: Option<String>,
+    pub turns: usize,
+}
+
+/// An entrant's rating and record over the tournament.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub rating: f64,
+    pub played: usize,
+    pub won: usize,
+    pub drawn: usize,
+    pub lost: usize,
+}
+
+impl Standing {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rating: STARTING_RATING,
+            played: 0,
+            won: 0,
+            drawn: 0,
+            lost: 0,
+        }
+    }
+}
+
+/// Every game played and the standings after them, best rated first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentReport {
+    pub games: Vec<GameRecord>,
+    pub standings: Vec<Standing>,
+}
+
+impl TournamentReport {
+    /// The standings as CSV, with a header row.
+    pub fn standings_csv(&self) -> String {
+        let mut csv = String::from("name,rating,played,won,drawn,lost\n");
+        for standing in &self.standings {
+            csv.push_str(&format!(
+                "{},{:.1},{},{},{},{}\n",
+                csv_field(&standing.name),
+                standing.rating,
+                standing.played,
+                standing.won,
+                standing.drawn,
+                standing.lost
+            ));
+        }
+        csv
+    }
+}
+
+/// Quotes `field` if it would otherwise break the row.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Plays every pair of `entrants` against each other and rates them.
+pub fn run_tournament(
+    entrants: &[Entrant],
+    config: &TournamentConfig,
+) -> Result<TournamentReport, RummyError> {
+    if entrants.len() < 2 {
+        return Err(RummyError::Config(
+            "A tournament needs at least 2 strategies".to_string(),
+        ));
+    }
+    for (idx, entrant) in entrants.iter().enumerate() {
+        if entrants[..idx].iter().any(|e| e.name == entrant.name) {
+            return Err(RummyError::Config(format!(
+                "{} is entered more than once",
+                entrant.name
+            )));
+        }
+    }
+
+    let mut standings: Vec<Standing> = entrants.iter().map(|e| Standing::new(&e.name)).collect();
+    let mut games = Vec::new();
+    let mut seed = config.seed;
+
+    for a in 0..entrants.len() {
+        for b in (a + 1)..entrants.len() {
+            for game in 0..config.games {
+                let (first, second) = if game % 2 == 0 { (a, b) } else { (b, a) };
+                let record = play_game(&entrants[first], &entrants[second], config, seed)?;
+                seed = seed.wrapping_add(1);
+
+                let result = match &record.winner {
+                    Some(winner) if *winner == entrants[first].name => 1.0,
+                    Some(_) => 0.0,
+                    None => 0.5,
+                };
+                rate(&mut standings, first, second, result);
+                games.push(record);
+            }
+        }
+    }
+
+    standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+    Ok(TournamentReport { games, standings })
+}
+
+/// Plays one heads-up game, `first` going first, through to a winner or
+/// `MAX_TURNS`.
+fn play_game(
+    first: &Entrant,
+    second: &Entrant,
+    config: &TournamentConfig,
+    seed: u64,
+) -> Result<GameRecord, RummyError> {
+    let seat = |entrant: &Entrant| Player {
+        name: entrant.name.clone(),
+        description: String::new(),
+        // Seats the player as an AI, the registered strategy plays for it
+        player_type: Some(PlayerType::Balanced),
+        hand: Hand { cards: Vec::new() },
+        actions: VecDeque::new(),
+        dialogue: VecDeque::new(),
+        score: 0,
+    };
+
+    let mut engine = GameEngine::pre_game(
+        vec![seat(first), seat(second)],
+        config.deck.clone(),
+        config.variant,
+    )?;
+    engine.set_seed(seed);
+    engine.allow_declarations(config.declarations);
+    // Each seat rolls its own dice, from the game's seed
+    first.strategy.reseed(seed);
+    second.strategy.reseed(!seed);
+    engine.register_strategy(&first.name, first.strategy.clone())?;
+    engine.register_strategy(&second.name, second.strategy.clone())?;
+    engine.deal_round()?;
+
+    let mut turns = 0;
+    while engine.winner().is_none() && turns < MAX_TURNS {
+        match engine.phase() {
+            TurnPhase::Declaring => {
+                engine.ai_declarations()?;
+                if *engine.phase() == TurnPhase::Declaring {
+                    engine.close_declarations()?;
+                }
+            }
+            TurnPhase::LayingOff => match engine.layoff_player_idx() {
+                Some(_) => {
+                    engine.ai_lay_off()?;
+                }
+                None => {
+                    engine.finish_round()?;
+                }
+            },
+            _ => {
+                engine.take_ai_turn()?;
+                turns += 1;
+            }
+        }
+    }
+
+    Ok(GameRecord {
+        seats: [first.name.clone(), second.name.clone()],
+        scores: [engine.players[0].score, engine.players[1].score],
+        winner: engine.winner().map(|winner| winner.name.clone()),
+        turns,
+    })
+}
+
+/// Moves the ratings of `a` and `b` after a game `a` scored `result` in:
+/// 1 for a win, a half for a draw and 0 for a loss.
+fn rate(standings: &mut [Standing], a: usize, b: usize, result: f64) {
+    let expected = 1.0 / (1.0 + 10f64.powf((standings[b].rating - standings[a].rating) / 400.0));
+    let change = RATING_K * (result - expected);
+    standings[a].rating += change;
+    standings[b].rating -= change;
+
+    for (idx, result) in [(a, result), (b, 1.0 - result)] {
+        let standing = &mut standings[idx];
+        standing.played += 1;
+        if result == 1.0 {
+            standing.won += 1;
+        } else if result == 0.0 {
+            standing.lost += 1;
+        } else {
+            standing.drawn += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin_rates_every_pair() {
+        let mut standings = vec![Standing::new("a"), Standing::new("b")];
+        rate(&mut standings, 0, 1, 1.0);
+        assert_eq!(standings[0].rating, STARTING_RATING + RATING_K / 2.0);
+        assert_eq!((standings[0].won, standings[1].lost), (1, 1));
+
+        let entrants = |seed| {
+            ["first", "second", "third"]
+                .map(|name| Entrant {
+                    name: name.to_string(),
+                    strategy: Arc::new(RandomStrategy::seeded(seed)),
+                })
+                .to_vec()
+        };
+        let config = TournamentConfig {
+            games: 2,
+            seed: 7,
+            ..TournamentConfig::default()
+        };
+
+        let report = run_tournament(&entrants(3), &config).unwrap();
+        assert_eq!(report.games.len(), 6);
+        assert!(report.standings.iter().all(|s| s.played == 4));
+        let total: f64 = report.standings.iter().map(|s| s.rating).sum();
+        assert!((total - 3.0 * STARTING_RATING).abs() < 1e-6);
+        assert_eq!(report.standings_csv().lines().count(), 4);
+
+        // The same seeds play the same tournament
+        assert_eq!(report, run_tournament(&entrants(3), &config).unwrap());
+
+        assert!(run_tournament(&entrants(3)[..1], &config).is_err());
+        assert!(Entrant::builtin("Balanced").is_ok());
+        assert!(Entrant::builtin("psychic").is_err());
+    }
+
+    #[test]
+    fn test_a_tournament_plays_again_exactly() {
+        let entrants = ["random", "balanced"]
+            .map(|name| Entrant::builtin(name).unwrap())
+            .to_vec();
+        let config = TournamentConfig {
+            games: 2,
+            seed: 11,
+            ..TournamentConfig::default()
+        };
+
+        // The random entrant's dice are rolled again from the seed, however
+        // many games it's played before
+        let report = run_tournament(&entrants, &config).unwrap();
+        assert_eq!(report, run_tournament(&entrants, &config).unwrap());
+    }
+}
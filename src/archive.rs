@@ -0,0 +1,258 @@
+//! A night at the club, every match of it, in one file.
+//!
+//! An archive is a run of CBOR items, one per finished match, so matches are
+//! only ever appended and a file cut short by a crash still reads up to the
+//! match it lost. Each match carries everything needed to go over it again:
+//! how the table was set up, the seed and every deal's hash, every event,
+//! the table talk, what it added to the stats and the summary shown at the
+//! end of it.
+
+use crate::engine::GameEngine;
+use crate::error::RummyError;
+use crate::game::events::GameEvent;
+use crate::game::rules::{GameRules, Variant};
+use crate::game::{DealRituals, DeckConfig, PlayerType};
+use crate::rng::SeedDisclosure;
+use crate::save::{TranscriptLine, data_dir};
+use crate::stats::{GameRecorder, GameResult, GameSummary};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever a change to the format would misread older archives.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Where the local game leaves the match it just finished, for `archive`
+/// to file away.
+pub fn last_match_path() -> PathBuf {
+    data_dir().join("last_match.cbor")
+}
+
+/// Someone at the table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Seat {
+    pub name: String,
+    /// `None` for a human.
+    pub player_type: Option<PlayerType>,
+}
+
+/// How the table was set up for a match.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MatchConfig {
+    pub variant: Variant,
+    pub deck: DeckConfig,
+    pub target_score: usize,
+    pub rules: GameRules,
+    pub rituals: DealRituals,
+    pub declarations: bool,
+    /// In seating order.
+    pub seats: Vec<Seat>,
+}
+
+impl MatchConfig {
+    pub fn of(engine: &GameEngine) -> Self {
+        Self {
+            variant: engine.variant,
+            deck: engine.deck_config.clone(),
+            target_score: engine.target_score,
+            rules: engine.rules(),
+            rituals: engine.rituals(),
+            declarations: engine.declarations_allowed(),
+            seats: engine
+                .players
+                .iter()
+                .map(|player| Seat {
+                    name: player.name.clone(),
+                    player_type: player.player_type.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One finished match.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MatchRecord {
+    pub version: u32,
+    pub book: String,
+    pub config: MatchConfig,
+    pub disclosure: SeedDisclosure,
+    /// Every event of the match, in order. A resumed game's start from where
+    /// it was resumed.
+    pub events: Vec<GameEvent>,
+    /// Everything said at the table.
+    pub transcript: Vec<TranscriptLine>,
+    /// The line the match added to the stats file.
+    pub result: Option<GameResult>,
+    pub summary: Option<GameSummary>,
+}
+
+impl MatchRecord {
+    /// The match `engine` has just finished, told by `events` and followed by
+    /// `recorder`.
+    pub fn new(
+        book: &str,
+        engine: &GameEngine,
+        events: Vec<GameEvent>,
+        recorder: &GameRecorder,
+    ) -> Self {
+        let transcript = events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::Dialogue { player, text } => Some(TranscriptLine {
+                    player: player.clone(),
+                    text: text.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            version: ARCHIVE_VERSION,
+            book: book.trim().to_string(),
+            config: MatchConfig::of(engine),
+            disclosure: engine.disclosure(),
+            events,
+            transcript,
+            result: recorder.result(book),
+            summary: recorder.summary(book),
+        }
+    }
+}
+
+/// An archive file matches are appended to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Archive {
+    path: PathBuf,
+}
+
+impl Archive {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, record: &MatchRecord) -> Result<(), RummyError> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| RummyError::Io(err.to_string()))?;
+        }
+
+        // Encoded first, so a match that can't be written leaves no half of it behind
+        let mut bytes = Vec::new();
+        ciborium::into_writer(record, &mut bytes).map_err(|err| RummyError::Io(err.to_string()))?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| RummyError::Io(format!("{}: {err}", self.path.display())))?;
+
+        file.write_all(&bytes)
+            .map_err(|err| RummyError::Io(format!("{}: {err}", self.path.display())))
+    }
+
+    /// Every match in the archive, in the order they were played. No file
+    /// yet means no matches, and reading stops at a match cut short.
+    pub fn load(&self) -> Result<Vec<MatchRecord>, RummyError> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(RummyError::Io(format!("{}: {err}", self.path.display())));
+            }
+        };
+
+        let mut matches = Vec::new();
+        let mut rest = bytes.as_slice();
+        while !rest.is_empty() {
+            let Ok(record) = ciborium::from_reader::<MatchRecord, _>(&mut rest) else {
+                break;
+            };
+            if record.version != ARCHIVE_VERSION {
+                return Err(RummyError::Config(format!(
+                    "{} was archived by a different version of bookclub_rummy",
+                    self.path.display()
+                )));
+            }
+            matches.push(record);
+        }
+
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::events::EventSink;
+    use crate::game::{Hand, Player};
+    use std::collections::VecDeque;
+
+    fn player(name: &str, player_type: Option<PlayerType>) -> Player {
+        Player {
+            name: name.to_string(),
+            description: String::new(),
+            player_type,
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        }
+    }
+
+    #[test]
+    fn test_match_records_bundle_the_whole_match() {
+        let mut engine = GameEngine::new(vec![
+            player("Ada", None),
+            player("Lee", Some(PlayerType::Balanced)),
+        ])
+        .unwrap();
+        engine.set_seed(1234);
+        engine.set_rules(GameRules {
+            max_layoff: 4,
+            ..GameRules::default()
+        });
+
+        let events = vec![
+            GameEvent::Dialogue {
+                player: "Lee".to_string(),
+                text: "Cal is the real hero.".to_string(),
+            },
+            GameEvent::CardDiscarded {
+                player: "Ada".to_string(),
+                card: "9c".parse().unwrap(),
+            },
+            GameEvent::Dialogue {
+                player: "Ada".to_string(),
+                text: "Timshel!".to_string(),
+            },
+        ];
+        let mut recorder = GameRecorder::new(["Ada", "Lee"]);
+        for event in &events {
+            recorder.emit(event);
+        }
+
+        let record = MatchRecord::new(" East of Eden ", &engine, events.clone(), &recorder);
+        assert_eq!(record.book, "East of Eden");
+        assert_eq!(record.events, events);
+        let said: Vec<&str> = record.transcript.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(said, ["Cal is the real hero.", "Timshel!"]);
+        assert_eq!(record.disclosure.seed, 1234);
+        assert_eq!(record.config.rules.max_layoff, 4);
+        assert_eq!(
+            record.config.seats[1].player_type,
+            Some(PlayerType::Balanced)
+        );
+
+        // Nobody has won, so there's nothing for the stats yet
+        assert!(record.result.is_none());
+        assert!(record.summary.is_none());
+
+        let missing = Archive::new("no/such/archive.rummy");
+        assert!(missing.load().unwrap().is_empty());
+    }
+}
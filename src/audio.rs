@@ -0,0 +1,176 @@
+//! Sound effects for a table sat round the same screen: a click for every
+//! card drawn, a chord for every meld played and a fanfare for the winner.
+//!
+//! The sounds are sine tones played through `rodio`, so there are no files
+//! to ship. A `Soundboard` is an `EventSink`, subscribed to a local game's
+//! engine like any other. The output device lives on a thread of its own,
+//! since the engine's sinks have to be `Send` and a device isn't.
+
+use crate::error::RummyError;
+use crate::game::events::{EventSink, GameEvent};
+use rodio::source::SineWave;
+use rodio::{OutputStream, Sink, Source};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// How loud the sounds are, from 0 to 1. Quiet enough to talk over.
+const VOLUME: f32 = 0.2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sound {
+    /// A card taken, from the draw pile or the discards.
+    Draw,
+    /// A hand played, knocked or laid off onto.
+    Meld,
+    Victory,
+}
+
+impl Sound {
+    /// The sound `event` makes, if it makes one.
+    pub fn for_event(event: &GameEvent) -> Option<Sound> {
+        match event {
+            GameEvent::CardDrawn { .. } => Some(Sound::Draw),
+            GameEvent::HandPlayed { .. }
+            | GameEvent::Knocked { .. }
+            | GameEvent::LayoffMade { score: Some(_), .. } => Some(Sound::Meld),
+            GameEvent::GameWon { .. } => Some(Sound::Victory),
+            _ => None,
+        }
+    }
+
+    /// The notes, in hertz, and how many milliseconds each is held.
+    fn notes(self) -> &'static [(f32, u64)] {
+        match self {
+            Sound::Draw => &[(880.0, 40)],
+            // C major, up the arpeggio
+            Sound::Meld => &[(523.25, 70), (659.25, 70), (783.99, 140)],
+            Sound::Victory => &[
+                (523.25, 120),
+                (659.25, 120),
+                (783.99, 120),
+                (1046.5, 240),
+                (783.99, 120),
+                (1046.5, 480),
+            ],
+        }
+    }
+}
+
+/// Plays the sound of every event it's sent.
+pub struct Soundboard {
+    sounds: Sender<Sound>,
+}
+
+impl Soundboard {
+    /// Opens the default output device, or says why it couldn't.
+    pub fn open() -> Result<Self, RummyError> {
+        let (sounds, queue) = mpsc::channel::<Sound>();
+        let (opened, result) = mpsc::sync_channel(1);
+
+        thread::Builder::new()
+            .name("rummy-audio".to_string())
+            .spawn(move || {
+                let device = OutputStream::try_default()
+                    .map_err(|err| RummyError::Audio(err.to_string()))
+                    .and_then(|(stream, handle)| {
+                        let sink = Sink::try_new(&handle)
+                            .map_err(|err| RummyError::Audio(err.to_string()))?;
+                        Ok((stream, sink))
+                    });
+                let (_stream, sink) = match device {
+                    Ok(device) => {
+                        let _ = opened.send(Ok(()));
+                        device
+                    }
+                    Err(err) => {
+                        let _ = opened.send(Err(err));
+                        return;
+                    }
+                };
+
+                // Until the game's over and the soundboard's dropped
+                for sound in queue {
+                    for &(hz, ms) in sound.notes() {
+                        sink.append(
+                            SineWave::new(hz)
+                                .take_duration(Duration::from_millis(ms))
+                                .amplify(VOLUME),
+                        );
+                    }
+                }
+                sink.sleep_until_end();
+            })
+            .map_err(|err| RummyError::Audio(err.to_string()))?;
+
+        result
+            .recv()
+            .map_err(|err| RummyError::Audio(err.to_string()))??;
+        Ok(Self { sounds })
+    }
+}
+
+impl EventSink for Soundboard {
+    fn emit(&mut self, event: &GameEvent) {
+        if let Some(sound) = Sound::for_event(event) {
+            // The device going away mid-game leaves the game quiet, not broken
+            let _ = self.sounds.send(sound);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Card;
+
+    #[test]
+    fn test_draws_melds_and_the_win_make_a_sound() {
+        let player = "Ada".to_string();
+        let card: Card = "Qh".parse().unwrap();
+        let sound = |event| Sound::for_event(&event);
+
+        assert_eq!(
+            sound(GameEvent::CardDrawn {
+                player: player.clone(),
+                retrieved: Some(card),
+            }),
+            Some(Sound::Draw)
+        );
+        assert_eq!(
+            sound(GameEvent::HandPlayed {
+                player: player.clone(),
+                hand: vec![card],
+                score: 10,
+            }),
+            Some(Sound::Meld)
+        );
+        assert_eq!(
+            sound(GameEvent::GameWon {
+                player: player.clone(),
+                score: 100,
+            }),
+            Some(Sound::Victory)
+        );
+
+        // A layoff that didn't beat the meld, or a pass, is quiet
+        let layoff = |score| GameEvent::LayoffMade {
+            player: player.clone(),
+            cards: vec![card],
+            score,
+        };
+        assert_eq!(sound(layoff(Some(20))), Some(Sound::Meld));
+        assert_eq!(sound(layoff(None)), None);
+        assert_eq!(
+            sound(GameEvent::CardDiscarded {
+                player: player.clone(),
+                card,
+            }),
+            None
+        );
+
+        for sound in [Sound::Draw, Sound::Meld, Sound::Victory] {
+            assert!(!sound.notes().is_empty());
+        }
+    }
+}
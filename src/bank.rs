@@ -0,0 +1,345 @@
+//! Playing for chips, kept apart from the scoring: everyone buys in, antes
+//! into a pot every deal, and the round's winner takes the pot. Stealing a
+//! round with a layoff collects a bounty from whoever played the hand. The
+//! bank only listens to the game's events, so a game plays and scores the
+//! same with or without one.
+
+use crate::game::events::{EventSink, GameEvent};
+
+/// Chips everyone antes every deal, unless the table says otherwise.
+pub const DEFAULT_ANTE: u64 = 10;
+
+/// Antes everyone buys in for, unless the table says otherwise.
+pub const BUY_IN_ANTES: u64 = 20;
+
+/// What a table plays for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stakes {
+    /// Chips everyone starts with.
+    pub buy_in: u64,
+    /// Chips everyone puts in the pot every deal.
+    pub ante: u64,
+    /// Chips whoever played the hand pays whoever steals the round with a
+    /// layoff.
+    pub bounty: u64,
+    /// Chips that win the match, `None` to play until someone's felted.
+    pub target: Option<u64>,
+}
+
+impl Stakes {
+    /// A table buying in for `buy_in` with an `ante` a deal, and a bounty
+    /// of two antes.
+    pub fn new(buy_in: u64, ante: u64) -> Self {
+        Self {
+            buy_in,
+            ante,
+            bounty: 2 * ante,
+            target: None,
+        }
+    }
+}
+
+impl Default for Stakes {
+    fn default() -> Self {
+        Self::new(BUY_IN_ANTES * DEFAULT_ANTE, DEFAULT_ANTE)
+    }
+}
+
+/// Why chips changed hands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// Into the pot at the deal.
+    Ante,
+    /// The pot, to the round's winner.
+    Pot,
+    /// Paid for a hand stolen with a layoff, or collected for stealing it.
+    Bounty,
+}
+
+/// Chips a player paid, negative, or collected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub player: String,
+    pub chips: i64,
+    pub reason: Reason,
+}
+
+/// Everyone's chips and the pot, settled from the game's events. Subscribe
+/// it to the engine before the first deal. Not saved, a resumed game buys
+/// in again.
+#[derive(Clone, Debug)]
+pub struct Bank {
+    stakes: Stakes,
+    /// Everyone's chips, in seating order.
+    balances: Vec<(String, u64)>,
+    pot: u64,
+    ledger: Vec<Transaction>,
+    /// Who played the hand in the round in play.
+    played_by: Option<String>,
+    /// Whether the round in play was called off, and the deal after it
+    /// plays for the same pot.
+    redealt: bool,
+    /// Whether the match was over at the last deal.
+    over: bool,
+}
+
+impl Bank {
+    /// Everyone in `players` bought in at `stakes`.
+    pub fn new<'a>(players: impl IntoIterator<Item = &'a str>, stakes: Stakes) -> Self {
+        Self {
+            stakes,
+            balances: players
+                .into_iter()
+                .map(|name| (name.to_string(), stakes.buy_in))
+                .collect(),
+            pot: 0,
+            ledger: Vec::new(),
+            played_by: None,
+            redealt: false,
+            over: false,
+        }
+    }
+
+    pub fn stakes(&self) -> Stakes {
+        self.stakes
+    }
+
+    /// Everyone's chips, in seating order.
+    pub fn balances(&self) -> &[(String, u64)] {
+        &self.balances
+    }
+
+    /// `player`'s chips, 0 if they aren't at the table.
+    pub fn balance(&self, player: &str) -> u64 {
+        self.balances
+            .iter()
+            .find(|(name, _)| name == player)
+            .map_or(0, |(_, chips)| *chips)
+    }
+
+    /// Chips waiting on the round in play, carried over from any round
+    /// nobody won.
+    pub fn pot(&self) -> u64 {
+        self.pot
+    }
+
+    /// Every transaction so far, oldest first.
+    pub fn ledger(&self) -> &[Transaction] {
+        &self.ledger
+    }
+
+    /// Everyone out of chips.
+    pub fn felted(&self) -> impl Iterator<Item = &str> {
+        self.balances
+            .iter()
+            .filter(|(_, chips)| *chips == 0)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Whether the match was over at the last deal: someone felted, or
+    /// holding the target. Nobody antes for that deal, so it's over
+    /// before a card is played.
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// Whoever has the most chips, the earliest seat on a tie.
+    pub fn leader(&self) -> Option<&str> {
+        self.balances
+            .iter()
+            .rev()
+            .max_by_key(|(_, chips)| *chips)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The match's winner once it's over, the chip leader.
+    pub fn match_winner(&self) -> Option<&str> {
+        self.over.then(|| self.leader()).flatten()
+    }
+
+    /// Takes up to `chips` off `player` and returns what they had to give.
+    fn pay(&mut self, player: &str, chips: u64, reason: Reason) -> u64 {
+        let Some((_, balance)) = self.balances.iter_mut().find(|(name, _)| name == player) else {
+            return 0;
+        };
+        let paid = chips.min(*balance);
+        *balance -= paid;
+        if paid > 0 {
+            self.ledger.push(Transaction {
+                player: player.to_string(),
+                chips: -(paid as i64),
+                reason,
+            });
+        }
+        paid
+    }
+
+    fn collect(&mut self, player: &str, chips: u64, reason: Reason) {
+        let Some((_, balance)) = self.balances.iter_mut().find(|(name, _)| name == player) else {
+            return;
+        };
+        *balance += chips;
+        if chips > 0 {
+            self.ledger.push(Transaction {
+                player: player.to_string(),
+                chips: chips as i64,
+                reason,
+            });
+        }
+    }
+
+    fn ante_up(&mut self) {
+        let players: Vec<String> = self.balances.iter().map(|(name, _)| name.clone()).collect();
+        for player in players {
+            self.pot += self.pay(&player, self.stakes.ante, Reason::Ante);
+        }
+    }
+}
+
+impl EventSink for Bank {
+    fn emit(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::RoundDealt { .. } => {
+                self.played_by = None;
+                let target = self.stakes.target.unwrap_or(u64::MAX);
+                self.over = self
+                    .balances
+                    .iter()
+                    .any(|(_, chips)| *chips == 0 || *chips >= target);
+                // A redeal plays for the pot already anted
+                if !self.over && !std::mem::take(&mut self.redealt) {
+                    self.ante_up();
+                }
+            }
+            GameEvent::RoundRedealt { .. } => self.redealt = true,
+            // Gin has no layoffs, an undercut knock pays only the pot
+            GameEvent::HandPlayed { player, .. } => self.played_by = Some(player.clone()),
+            GameEvent::RoundWon { player, .. } => {
+                let pot = std::mem::take(&mut self.pot);
+                self.collect(player, pot, Reason::Pot);
+                if let Some(played_by) = self.played_by.take()
+                    && played_by != *player
+                {
+                    let bounty = self.pay(&played_by, self.stakes.bounty, Reason::Bounty);
+                    self.collect(player, bounty, Reason::Bounty);
+                }
+            }
+            // Stalemates and dead hands nobody melds carry the pot over
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dealt() -> GameEvent {
+        GameEvent::RoundDealt {
+            player: "Ada".to_string(),
+            hands: Vec::new(),
+            deal_hash: 0,
+        }
+    }
+
+    fn played(player: &str) -> GameEvent {
+        GameEvent::HandPlayed {
+            player: player.to_string(),
+            hand: Vec::new(),
+            score: 20,
+        }
+    }
+
+    fn won(player: &str) -> GameEvent {
+        GameEvent::RoundWon {
+            player: player.to_string(),
+            score: 20,
+            hand: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_the_winner_takes_the_pot() {
+        let mut bank = Bank::new(["Ada", "Bram", "Cy"], Stakes::new(100, 10));
+        bank.emit(&dealt());
+        assert_eq!(bank.pot(), 30);
+        assert_eq!(bank.balance("Ada"), 90);
+
+        bank.emit(&played("Ada"));
+        bank.emit(&won("Ada"));
+        assert_eq!(bank.pot(), 0);
+        assert_eq!(bank.balances()[0], ("Ada".to_string(), 120));
+        assert_eq!(bank.balance("Bram"), 90);
+        assert_eq!(bank.leader(), Some("Ada"));
+    }
+
+    #[test]
+    fn test_a_layoff_collects_a_bounty() {
+        let mut bank = Bank::new(["Ada", "Bram"], Stakes::new(100, 10));
+        bank.emit(&dealt());
+        bank.emit(&played("Ada"));
+        bank.emit(&won("Bram"));
+
+        // The pot of 20, and Ada's bounty of 20
+        assert_eq!(bank.balance("Bram"), 130);
+        assert_eq!(bank.balance("Ada"), 70);
+        let bounties: Vec<i64> = bank
+            .ledger()
+            .iter()
+            .filter(|t| t.reason == Reason::Bounty)
+            .map(|t| t.chips)
+            .collect();
+        assert_eq!(bounties, [-20, 20]);
+    }
+
+    #[test]
+    fn test_a_pot_nobody_wins_carries_over() {
+        let mut bank = Bank::new(["Ada", "Bram"], Stakes::new(100, 10));
+        bank.emit(&dealt());
+        bank.emit(&GameEvent::RoundStalemate {
+            player: "Ada".to_string(),
+        });
+        bank.emit(&dealt());
+        assert_eq!(bank.pot(), 40);
+
+        // A redeal doesn't ante again
+        bank.emit(&GameEvent::RoundRedealt {
+            player: "Ada".to_string(),
+        });
+        bank.emit(&dealt());
+        assert_eq!(bank.pot(), 40);
+        assert_eq!(bank.balance("Ada"), 80);
+    }
+
+    #[test]
+    fn test_the_match_ends_when_someone_is_felted() {
+        let mut bank = Bank::new(["Ada", "Bram"], Stakes::new(40, 10));
+        for _ in 0..2 {
+            bank.emit(&dealt());
+            assert!(!bank.is_over());
+            bank.emit(&played("Ada"));
+            bank.emit(&won("Bram"));
+        }
+        // Ada went all in on the second ante
+        assert_eq!(bank.felted().collect::<Vec<_>>(), ["Ada"]);
+        assert_eq!(bank.match_winner(), None);
+
+        bank.emit(&dealt());
+        assert!(bank.is_over());
+        assert_eq!(bank.pot(), 0);
+        assert_eq!(bank.match_winner(), Some("Bram"));
+    }
+
+    #[test]
+    fn test_the_match_ends_at_the_target() {
+        let mut stakes = Stakes::new(100, 10);
+        stakes.target = Some(110);
+        let mut bank = Bank::new(["Ada", "Bram"], stakes);
+        bank.emit(&dealt());
+        bank.emit(&played("Ada"));
+        bank.emit(&won("Ada"));
+        bank.emit(&dealt());
+        assert!(bank.is_over());
+        assert_eq!(bank.match_winner(), Some("Ada"));
+    }
+}
@@ -0,0 +1,186 @@
+//! Scores hands from the command line, for settling a dispute at the table
+//! without dealing a game. Only the card and scoring code is used, nothing
+//! here starts a runtime or talks to an LLM.
+//!
+//! ```text
+//! rummy-score "Ada: Ah Kh Qh Jh 10h" "Bram: 7c 7d 7h 2s 2d"
+//! ```
+
+use clap::Parser;
+use rummy::card::Card;
+use rummy::error::RummyError;
+use rummy::game::Hand;
+use rummy::game::rules::{Variant, arrange_gin, deadwood_points};
+use rummy::scoring::{explain, meld_name};
+use std::cmp::Ordering;
+
+#[derive(Parser, Debug)]
+#[command(name = "rummy-score", about = "Score and compare Bookclub Rummy hands")]
+struct Args {
+    /// Hands to score, each a quoted list of cards like "Ah Kh Qh Jh 10h",
+    /// optionally named: "Ada: Ah Kh Qh Jh 10h"
+    #[arg(required = true)]
+    hands: Vec<String>,
+
+    /// Game the hands are from: bookclub, seven-card or gin
+    #[arg(long, default_value = "bookclub")]
+    variant: Variant,
+}
+
+/// A hand from the command line and what it's worth.
+#[derive(Debug)]
+struct Scored {
+    name: String,
+    hand: Hand,
+    /// The meld's score, or the deadwood in gin.
+    score: u64,
+    meld: Hand,
+}
+
+impl Scored {
+    fn describe(&self, variant: Variant) -> String {
+        let cards = cards_text(&self.meld.cards);
+        match variant {
+            Variant::Gin => format!("{} deadwood, melding {cards}", self.score),
+            Variant::Bookclub | Variant::SevenCard => {
+                let breakdown = explain(&self.meld.cards)
+                    .into_iter()
+                    .find(|meld| meld.score == self.score);
+                match (breakdown, meld_name(self.score)) {
+                    (Some(breakdown), _) => breakdown.to_string(),
+                    (None, Some(meld)) => format!("{meld} ({}) with {cards}", self.score),
+                    (None, None) => "no meld (0)".to_string(),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let scored: Result<Vec<Scored>, RummyError> = args
+        .hands
+        .iter()
+        .enumerate()
+        .map(|(idx, hand)| score_hand(hand, idx, args.variant))
+        .collect();
+    let scored = scored.unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+
+    for hand in &scored {
+        println!(
+            "{}: {} -> {}",
+            hand.name,
+            cards_text(&hand.hand.cards),
+            hand.describe(args.variant)
+        );
+    }
+
+    if scored.len() > 1 {
+        println!("{}", verdict(&scored, args.variant));
+    }
+}
+
+/// Reads `input`, like `Ada: Ah Kh Qh Jh 10h`, and scores it. Unnamed hands
+/// are called by their place on the command line.
+fn score_hand(input: &str, idx: usize, variant: Variant) -> Result<Scored, RummyError> {
+    let (name, cards) = match input.split_once(':') {
+        Some((name, cards)) => (name.trim().to_string(), cards),
+        None => (format!("Hand {}", idx + 1), input),
+    };
+    let cards = cards
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|card| !card.is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<Card>, _>>()?;
+    if cards.is_empty() {
+        return Err(RummyError::InvalidCard(format!("{name} has no cards")));
+    }
+    let hand = Hand { cards };
+
+    let (score, meld) = match variant {
+        Variant::Gin => {
+            let (melded, deadwood) = arrange_gin(&hand.cards);
+            (deadwood_points(&deadwood), Hand { cards: melded })
+        }
+        Variant::Bookclub | Variant::SevenCard => variant.best_meld(&hand),
+    };
+
+    Ok(Scored {
+        name,
+        hand,
+        score,
+        meld,
+    })
+}
+
+/// Who wins: the best meld, or the least deadwood in gin.
+fn verdict(scored: &[Scored], variant: Variant) -> String {
+    let better = |a: &Scored, b: &Scored| match variant {
+        Variant::Gin => b.score.cmp(&a.score),
+        Variant::Bookclub | Variant::SevenCard => a.score.cmp(&b.score),
+    };
+    let Some(best) = scored.iter().max_by(|a, b| better(a, b)) else {
+        return String::new();
+    };
+
+    let winners: Vec<&str> = scored
+        .iter()
+        .filter(|hand| better(hand, best) == Ordering::Equal)
+        .map(|hand| hand.name.as_str())
+        .collect();
+    match winners.as_slice() {
+        [winner] => format!("{winner} wins."),
+        tied => format!("It's a tie between {}.", tied.join(" and ")),
+    }
+}
+
+fn cards_text(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(Card::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settles_a_dispute() {
+        let ada = score_hand("Ada: Ah Kh Qh Jh 10h", 0, Variant::Bookclub).unwrap();
+        let bram = score_hand("7c,7d,7h,2s,2d", 1, Variant::Bookclub).unwrap();
+        assert_eq!(ada.score, 100);
+        assert_eq!(bram.name, "Hand 2");
+        assert_eq!(bram.score, 40);
+        assert_eq!(
+            bram.describe(Variant::Bookclub),
+            "Full House: 7c 7d 7h + 2s 2d = 40"
+        );
+        assert_eq!(verdict(&[bram, ada], Variant::Bookclub), "Ada wins.");
+
+        let tied = [
+            score_hand("Ada: 2s 2h 5c 9d Kd", 0, Variant::Bookclub).unwrap(),
+            score_hand("Cathy: 3s 3h 6c 10d Qd", 1, Variant::Bookclub).unwrap(),
+        ];
+        assert_eq!(
+            verdict(&tied, Variant::Bookclub),
+            "It's a tie between Ada and Cathy."
+        );
+
+        // The least deadwood wins at gin
+        let gin = [
+            score_hand("Ada: As 2s 3s 7h 7c 7d 9c 10c Jc Kh", 0, Variant::Gin).unwrap(),
+            score_hand("Bram: As 2s 3s 7h 7c 7d 9c 10c Jc Qc", 1, Variant::Gin).unwrap(),
+        ];
+        assert_eq!(gin[1].score, 0);
+        assert_eq!(verdict(&gin, Variant::Gin), "Bram wins.");
+
+        assert!(score_hand("Ada: Zz", 0, Variant::Bookclub).is_err());
+        assert!(score_hand("Ada:", 0, Variant::Bookclub).is_err());
+    }
+}
@@ -1,7 +1,19 @@
+use crate::error::RummyError;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub mod set;
+
+pub use set::CardSet;
+
+/// Serialized in its plain-text form (`10h`) so the wire format stays readable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub struct Card {
     pub rank: Rank,
     pub suite: Suite,
@@ -25,7 +37,8 @@ pub enum Rank {
     Fourteen,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub enum Name {
     Two,
     Three,
@@ -42,7 +55,8 @@ pub enum Name {
     Ace,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
 pub enum Suite {
     Spades,
     Hearts,
@@ -51,73 +65,160 @@ pub enum Suite {
 }
 
 pub trait ToU64 {
-    fn to_u64(&self) -> Result<u64, &str> {
+    fn to_u64(&self) -> Result<u64, RummyError> {
         unimplemented!()
     }
 }
 
 pub trait ToSuite {
-    fn to_suite(&self) -> Result<Suite, String> {
+    fn to_suite(&self) -> Result<Suite, RummyError> {
         unimplemented!()
     }
 }
 
 pub trait ToName {
-    fn to_name(&self) -> Result<Name, String> {
+    fn to_name(&self) -> Result<Name, RummyError> {
         unimplemented!()
     }
 }
 
-impl Card {
-    /// Creates a `Card` from a string representation.
-    pub fn from_string(mut input: String) -> Result<Card, String> {
-        let allowed_names = [
-            "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K", "A",
-        ];
-        let allowed_suites = ['s', 'h', 'c', 'd'];
+impl FromStr for Card {
+    type Err = RummyError;
 
+    /// Parses a card from its plain-text form, e.g. `10h` or `Qs`.
+    fn from_str(input: &str) -> Result<Card, RummyError> {
         let char_count = input.chars().count();
         if !(2..=3).contains(&char_count) {
-            let err_msg = "Card formatting is incorrect: {input}";
-            return Err(err_msg.to_string());
-        }
-
-        let suite_char = input.pop().unwrap();
-        let name_string = input;
-
-        if !allowed_suites.contains(&suite_char) {
-            let err_msg = format!("{suite_char} does not match any known suite!");
-            return Err(err_msg.to_string());
+            return Err(RummyError::InvalidCard(input.to_string()));
         }
 
-        if !allowed_names.contains(&name_string.as_str()) {
-            let err_msg = format!("{name_string} does not match any known card name!");
-            return Err(err_msg.to_string());
-        }
+        let suite_len = input.chars().last().map_or(0, char::len_utf8);
+        let (name_str, suite_str) = input.split_at(input.len() - suite_len);
 
-        let name = name_string.to_name()?;
-        let suite = suite_char.to_suite()?;
+        let suite = suite_str.parse::<Suite>()?;
+        let name = name_str.parse::<Name>()?;
         let rank = name.to_rank()?;
 
         Ok(Card { rank, suite, name })
     }
+}
+
+impl fmt::Display for Card {
+    /// Writes the plain-text form that `FromStr` accepts, e.g. `10h`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.name, self.suite)
+    }
+}
+
+impl From<Card> for String {
+    fn from(card: Card) -> String {
+        card.to_string()
+    }
+}
+
+impl TryFrom<String> for Card {
+    type Error = RummyError;
+
+    fn try_from(input: String) -> Result<Card, RummyError> {
+        input.parse()
+    }
+}
+
+/// A card is a string on the wire, so its schema is written out by hand.
+impl JsonSchema for Card {
+    fn schema_name() -> Cow<'static, str> {
+        "Card".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "A card in its plain-text form, name then suite, like \"10h\" or \"Qs\".",
+            "pattern": "^(10|[2-9JQKA])[shcd]$"
+        })
+    }
+}
+
+impl From<Name> for String {
+    fn from(name: Name) -> String {
+        name.to_string()
+    }
+}
+
+impl TryFrom<String> for Name {
+    type Error = RummyError;
+
+    fn try_from(input: String) -> Result<Name, RummyError> {
+        input.parse()
+    }
+}
+
+impl From<Suite> for String {
+    fn from(suite: Suite) -> String {
+        suite.to_string()
+    }
+}
+
+impl TryFrom<String> for Suite {
+    type Error = RummyError;
+
+    fn try_from(input: String) -> Result<Suite, RummyError> {
+        input.parse()
+    }
+}
 
-    /// Converts a `Card` to its string representation.
-    pub fn to_string(&self) -> Result<String, String> {
-        let name_string = self.name.to_string()?;
-        let suite_char = self.suite.to_char()?;
+impl FromStr for Suite {
+    type Err = RummyError;
 
-        if self.suite == Suite::Hearts || self.suite == Suite::Diamonds {
-            return Ok(format!("{name_string}{suite_char}"));
+    fn from_str(input: &str) -> Result<Suite, RummyError> {
+        let mut chars = input.chars();
+        match (chars.next(), chars.next()) {
+            (Some(suite_char), None) => suite_char.to_suite(),
+            _ => Err(RummyError::UnknownSuite(input.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Suite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let suite_char = match self {
+            Suite::Spades => 's',
+            Suite::Hearts => 'h',
+            Suite::Clubs => 'c',
+            Suite::Diamonds => 'd',
         };
 
-        Ok(format!("{name_string}{suite_char}"))
+        write!(f, "{suite_char}")
+    }
+}
+
+impl FromStr for Name {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<Name, RummyError> {
+        input.to_string().to_name()
+    }
+}
+
+impl FromStr for Rank {
+    type Err = RummyError;
+
+    /// Ranks are written the same way as the names they belong to (`J` is `Eleven`).
+    fn from_str(input: &str) -> Result<Rank, RummyError> {
+        input.parse::<Name>()?.to_rank()
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.to_name().map_err(|_| fmt::Error)?;
+        write!(f, "{name}")
     }
 }
 
 impl Ord for Card {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.rank.to_u64().cmp(&other.rank.to_u64())
+        self.rank.to_u64().ok().cmp(&other.rank.to_u64().ok())
     }
 }
 
@@ -129,7 +230,7 @@ impl PartialOrd for Card {
 
 impl ToSuite for char {
     #[rustfmt::skip]
-    fn to_suite(&self) -> Result<Suite, String> {
+    fn to_suite(&self) -> Result<Suite, RummyError> {
         let map = HashMap::from([
             ('s', Suite::Spades),
             ('h', Suite::Hearts),
@@ -139,13 +240,16 @@ impl ToSuite for char {
 
         match map.get(self) {
             Some(suite) => Ok(*suite),
-            None => Err("Unknown Suite!".to_string())
+            None => Err(RummyError::UnknownSuite(self.to_string()))
         }
     }
 }
 
 impl Suite {
-    pub fn to_char(&self) -> Result<char, String> {
+    /// Every suite, in the order a new deck is built.
+    pub const ALL: [Suite; 4] = [Suite::Spades, Suite::Hearts, Suite::Diamonds, Suite::Clubs];
+
+    pub fn to_char(&self) -> Result<char, RummyError> {
         let map = HashMap::from([
             (Suite::Spades, '♤'),
             (Suite::Hearts, '♡'),
@@ -155,14 +259,14 @@ impl Suite {
 
         match map.get(self) {
             Some(char) => Ok(*char),
-            None => Err("Unknown Suite!".to_string()),
+            None => Err(RummyError::UnknownSuite(format!("{self:?}"))),
         }
     }
 }
 
 impl ToName for String {
     #[rustfmt::skip]
-    fn to_name(&self) -> Result<Name, String> {
+    fn to_name(&self) -> Result<Name, RummyError> {
         let map = HashMap::from([
             ("2", Name::Two), ("3", Name::Three), ("4", Name::Four),
             ("5", Name::Five), ("6", Name::Six), ("7", Name::Seven),
@@ -173,14 +277,14 @@ impl ToName for String {
 
         match map.get(self.as_str()) {
             Some(name) => Ok(*name),
-            None => Err("Unknown Suite!".to_string())
+            None => Err(RummyError::UnknownName(self.clone()))
         }
     }
 }
 
 impl Rank {
     #[rustfmt::skip]
-    pub fn to_name(&self) -> Result<Name, &str> {
+    pub fn to_name(&self) -> Result<Name, RummyError> {
         let map = HashMap::from([
             (Rank::Two, Name::Two), (Rank::Three, Name::Three),
             (Rank::Four, Name::Four), (Rank::Five, Name::Five),
@@ -193,14 +297,22 @@ impl Rank {
 
         match map.get(self) {
             Some(name) => Ok(*name),
-            None => Err("Unknown Rank!")
+            None => Err(RummyError::UnknownRank(format!("{self:?}")))
         }
     }
 }
 
 impl Name {
+    /// Every name, from Two up to Ace.
     #[rustfmt::skip]
-    pub fn to_rank(&self) -> Result<Rank, &str> {
+    pub const ALL: [Name; 13] = [
+        Name::Two, Name::Three, Name::Four, Name::Five, Name::Six, Name::Seven,
+        Name::Eight, Name::Nine, Name::Ten, Name::Jack, Name::Queen, Name::King,
+        Name::Ace,
+    ];
+
+    #[rustfmt::skip]
+    pub fn to_rank(&self) -> Result<Rank, RummyError> {
         let map = HashMap::from([
             (Name::Two, Rank::Two), (Name::Three, Rank::Three),
             (Name::Four, Rank::Four), (Name::Five, Rank::Five),
@@ -213,12 +325,14 @@ impl Name {
 
         match map.get(self) {
             Some(rank) => Ok(*rank),
-            None => Err("Unknown Rank!")
+            None => Err(RummyError::UnknownRank(format!("{self:?}")))
         }
     }
+}
 
+impl fmt::Display for Name {
     #[rustfmt::skip]
-    pub fn to_string(&self) -> Result<String, &str> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let map = HashMap::from([
             (Name::Two, "2"), (Name::Three, "3"), (Name::Four, "4"),
             (Name::Five, "5"), (Name::Six, "6"), (Name::Seven, "7"),
@@ -228,15 +342,15 @@ impl Name {
         ]);
 
         match map.get(self) {
-            Some(name) => Ok((*name).to_string()),
-            None => Err("Unknown Name!")
+            Some(name) => write!(f, "{name}"),
+            None => Err(fmt::Error)
         }
     }
 }
 
 impl ToU64 for Rank {
     #[rustfmt::skip]
-    fn to_u64(&self) -> Result<u64, &str> {
+    fn to_u64(&self) -> Result<u64, RummyError> {
         let map = HashMap::from([
             (Rank::Two, 2), (Rank::Three, 3), (Rank::Four, 4),
             (Rank::Five, 5), (Rank::Six, 6), (Rank::Seven, 7),
@@ -247,14 +361,14 @@ impl ToU64 for Rank {
 
         match map.get(self) {
             Some(rank) => Ok(*rank),
-            None => Err("Unknown Rank!")
+            None => Err(RummyError::UnknownRank(format!("{self:?}")))
         }
     }
 }
 
 impl ToU64 for Name {
     #[rustfmt::skip]
-    fn to_u64(&self) -> Result<u64, &str> {
+    fn to_u64(&self) -> Result<u64, RummyError> {
         let map = HashMap::from([
             (Name::Two, 2), (Name::Three, 3), (Name::Four, 4),
             (Name::Five, 5), (Name::Six, 6), (Name::Seven, 7),
@@ -265,13 +379,27 @@ impl ToU64 for Name {
 
         match map.get(self) {
             Some(rank) => Ok(*rank),
-            None => Err("Unknown Rank!")
+            None => Err(RummyError::UnknownRank(format!("{self:?}")))
+        }
+    }
+}
+
+/// Takes one copy of `card` out of `cards`. Dealing from more than one deck
+/// puts the same card in play twice, and only one of them is going anywhere.
+pub fn remove_one(cards: &mut Vec<Card>, card: &Card) -> bool {
+    match cards.iter().position(|c| c == card) {
+        Some(idx) => {
+            cards.remove(idx);
+            true
         }
+        None => false,
     }
 }
 
+#[cfg(test)]
 use quickcheck::{Arbitrary, Gen};
 
+#[cfg(test)]
 impl Arbitrary for Card {
     fn arbitrary(g: &mut Gen) -> Self {
         let all_names = [
@@ -299,3 +427,49 @@ impl Arbitrary for Card {
         Card { name, rank, suite }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    quickcheck! {
+        fn card_round_trips_through_display(card: Card) -> bool {
+            card.to_string().parse::<Card>() == Ok(card)
+        }
+    }
+
+    #[test]
+    fn test_card_from_str() {
+        let card = "10h".parse::<Card>().unwrap();
+        assert_eq!(card.name, Name::Ten);
+        assert_eq!(card.suite, Suite::Hearts);
+        assert_eq!(card.to_string(), "10h");
+
+        assert_eq!(
+            "1h".parse::<Card>(),
+            Err(RummyError::UnknownName("1".to_string()))
+        );
+        assert_eq!(
+            "Qx".parse::<Card>(),
+            Err(RummyError::UnknownSuite("x".to_string()))
+        );
+        assert_eq!(
+            "Queen".parse::<Card>(),
+            Err(RummyError::InvalidCard("Queen".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_duplicate_cards_come_out_one_at_a_time() {
+        let seven: Card = "7h".parse().unwrap();
+        let queen: Card = "Qs".parse().unwrap();
+        let mut cards = vec![seven, queen, seven];
+
+        assert!(remove_one(&mut cards, &seven));
+        assert_eq!(cards, vec![queen, seven]);
+        assert!(remove_one(&mut cards, &seven));
+        assert!(!remove_one(&mut cards, &seven));
+        assert_eq!(cards, vec![queen]);
+    }
+}
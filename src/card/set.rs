@@ -0,0 +1,190 @@
+use crate::card::{Card, Name, Suite};
+use crate::game::Hand;
+use crate::scoring::CardVec;
+
+/// Bits given to each suite, one per name from two up to ace.
+const LANE: u32 = 16;
+const LANE_MASK: u64 = (1 << 13) - 1;
+
+/// A hand held as bits, for the meld checks in hot loops. Each card is a
+/// bit in its suite's 16-bit lane, lowest name first, so a suite's names
+/// are a 13-bit mask and runs are found by shifting.
+///
+/// Dealing from more than one deck can put two copies of a card in a hand.
+/// The bits can't tell them apart, so how many of each name are held is
+/// counted on the side, four bits to a name.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CardSet {
+    bits: u64,
+    name_counts: u64,
+    len: u8,
+}
+
+impl CardSet {
+    pub fn insert(&mut self, card: Card) {
+        self.bits |= 1 << bit(&card);
+        let shift = card.name as u32 * 4;
+        if (self.name_counts >> shift) & 0xF < 0xF {
+            self.name_counts += 1 << shift;
+        }
+        self.len = self.len.saturating_add(1);
+    }
+
+    pub fn contains(&self, card: &Card) -> bool {
+        self.bits & (1 << bit(card)) != 0
+    }
+
+    /// Cards held, every copy counted.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// One bit per card held, in suite lanes.
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// The names held in `suite`, bit 0 for two up to bit 12 for ace.
+    pub fn suite_mask(&self, suite: Suite) -> u16 {
+        ((self.bits >> (suite as u32 * LANE)) & LANE_MASK) as u16
+    }
+
+    /// The names held in any suite.
+    pub fn name_mask(&self) -> u16 {
+        Suite::ALL
+            .iter()
+            .fold(0, |mask, suite| mask | self.suite_mask(*suite))
+    }
+
+    /// How many cards called `name` are held, up to 15.
+    pub fn count(&self, name: Name) -> u8 {
+        ((self.name_counts >> (name as u32 * 4)) & 0xF) as u8
+    }
+
+    /// How many names are held exactly `n` times.
+    pub fn names_held(&self, n: u8) -> usize {
+        Name::ALL
+            .iter()
+            .filter(|name| self.count(**name) == n)
+            .count()
+    }
+
+    /// The highest name held at least `n` times.
+    pub fn highest_held(&self, n: u8) -> Option<Name> {
+        Name::ALL
+            .iter()
+            .rev()
+            .find(|name| self.count(**name) >= n)
+            .copied()
+    }
+
+    /// Every card held, each once, lowest suite lane first.
+    pub fn cards(&self) -> impl Iterator<Item = Card> + '_ {
+        Suite::ALL.into_iter().flat_map(move |suite| {
+            Name::ALL
+                .into_iter()
+                .filter(move |name| self.suite_mask(suite) & (1 << *name as u32) != 0)
+                .filter_map(move |name| {
+                    Some(Card {
+                        rank: name.to_rank().ok()?,
+                        suite,
+                        name,
+                    })
+                })
+        })
+    }
+
+    /// The cards as a hand, each once. A second copy from another deck isn't
+    /// a card of its own to the set.
+    pub fn to_hand(&self) -> Hand {
+        Hand {
+            cards: self.cards().collect(),
+        }
+    }
+}
+
+/// Whether `mask` holds `n` names in a row.
+pub fn has_run(mask: u16, n: u32) -> bool {
+    let mut run = mask;
+    for shift in 1..n {
+        run &= mask >> shift;
+    }
+    n > 0 && run != 0
+}
+
+/// Whether the names in `mask` are all in a row, with no gaps.
+pub fn is_run(mask: u16) -> bool {
+    let names = mask.checked_shr(mask.trailing_zeros()).unwrap_or(0);
+    names & names.wrapping_add(1) == 0
+}
+
+fn bit(card: &Card) -> u32 {
+    card.suite as u32 * LANE + card.name as u32
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(cards: I) -> Self {
+        let mut set = CardSet::default();
+        for card in cards {
+            set.insert(card);
+        }
+        set
+    }
+}
+
+impl From<&[Card]> for CardSet {
+    fn from(cards: &[Card]) -> Self {
+        cards.iter().copied().collect()
+    }
+}
+
+impl From<&CardVec> for CardSet {
+    fn from(cards: &CardVec) -> Self {
+        Self::from(cards.as_slice())
+    }
+}
+
+impl From<&Hand> for CardSet {
+    fn from(hand: &Hand) -> Self {
+        Self::from(hand.cards.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(cards: &[&str]) -> Vec<Card> {
+        cards.iter().map(|card| card.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_card_set_round_trips_a_hand() {
+        let hand = Hand {
+            cards: cards(&["2s", "As", "10h", "Jh", "Qh", "Qh"]),
+        };
+        let set = CardSet::from(&hand);
+
+        assert_eq!(set.len(), 6);
+        assert!(set.contains(&"10h".parse().unwrap()));
+        assert!(!set.contains(&"10s".parse().unwrap()));
+        assert_eq!(set.suite_mask(Suite::Spades), 1 | 1 << 12);
+        assert_eq!(set.count(Name::Queen), 2);
+        assert_eq!(set.highest_held(2), Some(Name::Queen));
+        assert!(has_run(set.suite_mask(Suite::Hearts), 3));
+        assert!(!has_run(set.suite_mask(Suite::Hearts), 4));
+        assert!(is_run(set.suite_mask(Suite::Hearts)));
+        assert!(!is_run(set.suite_mask(Suite::Spades)));
+
+        // The twin queen is the same card to the set
+        let mut round_trip = set.to_hand().cards;
+        round_trip.sort_unstable_by_key(bit);
+        let mut distinct = cards(&["2s", "As", "10h", "Jh", "Qh"]);
+        distinct.sort_unstable_by_key(bit);
+        assert_eq!(round_trip, distinct);
+    }
+}
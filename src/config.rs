@@ -0,0 +1,549 @@
+//! Everything about how a game's set up, written down once in a
+//! `rummy.toml` instead of passed on the command line every time:
+//!
+//! ```toml
+//! llm = "~/.config/aj/config.yaml"
+//! players = "club.toml"
+//! theme = "solarized.toml"
+//! ai = "aggressive"
+//! locale = "es"
+//! celebration = "fireworks"
+//! tts = "system"
+//! table_talk_level = "lively"
+//! chat_prompt = false
+//! ai_time_budget_ms = 250
+//!
+//! [rules]
+//! variant = "gin"
+//! deck = "2x"
+//! turn_order = "cut"
+//! cut = "random"
+//! burn = true
+//! declare = true
+//! max_layoff = 3
+//! dead_hand_after = 2
+//! tiebreak = "meld-rank"
+//! teams = true
+//! turn_timer = 60
+//!
+//! [keys]
+//! draw = "j"
+//! play = "k"
+//! ```
+//!
+//! Anything left out has the same default as the command line. A
+//! `RUMMY_` environment variable overrides the file, `RUMMY_VARIANT=gin`
+//! for `variant` under `[rules]`, and a command line flag overrides both.
+
+use crate::dialogue::TableTalkLevel;
+use crate::display::celebration::CelebrationKind;
+use crate::engine::AiSearch;
+use crate::error::RummyError;
+use crate::game::rules::{MAX_LAYOFF, Tiebreak, Variant};
+use crate::game::{CutPosition, DeckConfig, PlayerType, TurnOrder};
+use crate::i18n::{Locale, Prompt};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The file looked for in the working directory, and then in the config
+/// directory, when none is given.
+pub const CONFIG_FILE: &str = "rummy.toml";
+
+/// Where the settings file is kept if it isn't in the working directory.
+pub fn config_dir() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .unwrap_or_default()
+        .join("bookclub_rummy")
+}
+
+/// The game's settings, with nothing left to look up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// awful_aj's configuration, for the LLM.
+    pub llm: Option<PathBuf>,
+    /// Character profiles to seat the AI players from.
+    pub players: Option<PathBuf>,
+    /// Theme file to draw the table in.
+    pub theme: Option<PathBuf>,
+    /// How AI players typed in at the table play.
+    pub ai: PlayerType,
+    /// The language the table speaks.
+    pub locale: Locale,
+    /// How a human winner's celebrated.
+    pub celebration: CelebrationKind,
+    /// What reads the AI players' lines aloud, `system` or a command line,
+    /// `None` for nothing.
+    pub tts: Option<String>,
+    /// How much the game gets into the AI players' talk.
+    pub table_talk_level: TableTalkLevel,
+    /// Whether a human's asked to join the conversation after every turn.
+    /// They can always `/say` something.
+    pub chat_prompt: bool,
+    /// How far an AI's turn searches ahead.
+    pub ai_search: AiSearch,
+    pub deck: DeckConfig,
+    pub variant: Variant,
+    pub turn_order: TurnOrder,
+    pub cut: Option<CutPosition>,
+    pub burn: bool,
+    pub declare: bool,
+    pub max_layoff: usize,
+    /// Reshuffles before a round's called as a dead hand, `None` for no limit.
+    pub dead_hand_after: Option<usize>,
+    /// How a layoff tying the meld it's laid off onto is settled.
+    pub tiebreak: Tiebreak,
+    /// Whether the table plays in partnerships.
+    pub teams: bool,
+    /// How long a human has for each turn, `None` for as long as they like.
+    pub turn_timer: Option<Duration>,
+    pub keys: KeyBindings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            llm: None,
+            players: None,
+            theme: None,
+            ai: PlayerType::Balanced,
+            locale: Locale::default(),
+            celebration: CelebrationKind::default(),
+            tts: None,
+            table_talk_level: TableTalkLevel::default(),
+            chat_prompt: true,
+            ai_search: AiSearch::Nodes,
+            deck: DeckConfig::default(),
+            variant: Variant::default(),
+            turn_order: TurnOrder::default(),
+            cut: None,
+            burn: false,
+            declare: false,
+            max_layoff: MAX_LAYOFF,
+            dead_hand_after: None,
+            tiebreak: Tiebreak::default(),
+            teams: false,
+            turn_timer: None,
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+impl Config {
+    /// The settings in `path`, or in `rummy.toml` wherever `settings_path`
+    /// finds it if not given, with the environment over the top. No file
+    /// at all is the defaults.
+    pub fn load(path: Option<&Path>) -> Result<Self, RummyError> {
+        let var = |name: &str| std::env::var(name).ok();
+
+        let file = match path {
+            Some(path) => Some(ConfigFile::load(path)?),
+            None => match settings_path(var) {
+                Some(path) => Some(ConfigFile::load(&path)?),
+                None => None,
+            },
+        };
+
+        let mut file = file.unwrap_or_default();
+        file.override_from(var)?;
+        file.resolve()
+    }
+}
+
+/// Where to read the settings from when no file's given: `RUMMY_CONFIG`,
+/// or `rummy.toml` in the working directory or the config directory,
+/// whichever there is.
+pub fn settings_path(var: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    if let Some(path) = var("RUMMY_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    [PathBuf::from(CONFIG_FILE), config_dir().join(CONFIG_FILE)]
+        .into_iter()
+        .find(|path| path.exists())
+}
+
+/// The settings as written in the file, before anything's parsed.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    llm: Option<PathBuf>,
+    players: Option<PathBuf>,
+    theme: Option<PathBuf>,
+    ai: Option<String>,
+    locale: Option<String>,
+    celebration: Option<String>,
+    tts: Option<String>,
+    table_talk_level: Option<String>,
+    chat_prompt: Option<bool>,
+    /// In milliseconds, 0 for no search. Unset searches to a node budget.
+    ai_time_budget_ms: Option<u64>,
+    rules: RulesFile,
+    keys: KeyBindings,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct RulesFile {
+    variant: Option<String>,
+    deck: Option<String>,
+    turn_order: Option<String>,
+    cut: Option<String>,
+    burn: Option<bool>,
+    declare: Option<bool>,
+    max_layoff: Option<usize>,
+    dead_hand_after: Option<usize>,
+    tiebreak: Option<String>,
+    teams: Option<bool>,
+    /// In seconds.
+    turn_timer: Option<u64>,
+}
+
+impl ConfigFile {
+    fn load(path: &Path) -> Result<Self, RummyError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+        toml::from_str(&contents).map_err(|err| {
+            RummyError::Config(format!("{} isn't a settings file: {err}", path.display()))
+        })
+    }
+
+    /// Takes whatever `var` has for a setting over the file.
+    fn override_from(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), RummyError> {
+        let rules = &mut self.rules;
+        for (name, setting) in [
+            ("RUMMY_AI", &mut self.ai),
+            ("RUMMY_LOCALE", &mut self.locale),
+            ("RUMMY_CELEBRATION", &mut self.celebration),
+            ("RUMMY_TTS", &mut self.tts),
+            ("RUMMY_TABLE_TALK_LEVEL", &mut self.table_talk_level),
+            ("RUMMY_VARIANT", &mut rules.variant),
+            ("RUMMY_DECK", &mut rules.deck),
+            ("RUMMY_TURN_ORDER", &mut rules.turn_order),
+            ("RUMMY_CUT", &mut rules.cut),
+            ("RUMMY_TIEBREAK", &mut rules.tiebreak),
+        ] {
+            if let Some(value) = var(name) {
+                *setting = Some(value);
+            }
+        }
+        for (name, setting) in [
+            ("RUMMY_LLM", &mut self.llm),
+            ("RUMMY_PLAYERS", &mut self.players),
+            ("RUMMY_THEME", &mut self.theme),
+        ] {
+            if let Some(value) = var(name) {
+                *setting = Some(PathBuf::from(value));
+            }
+        }
+
+        if let Some(value) = var("RUMMY_CHAT_PROMPT") {
+            self.chat_prompt = Some(parse_var("RUMMY_CHAT_PROMPT", &value)?);
+        }
+        if let Some(value) = var("RUMMY_AI_TIME_BUDGET_MS") {
+            self.ai_time_budget_ms = Some(parse_var("RUMMY_AI_TIME_BUDGET_MS", &value)?);
+        }
+        if let Some(value) = var("RUMMY_BURN") {
+            rules.burn = Some(parse_var("RUMMY_BURN", &value)?);
+        }
+        if let Some(value) = var("RUMMY_DECLARE") {
+            rules.declare = Some(parse_var("RUMMY_DECLARE", &value)?);
+        }
+        if let Some(value) = var("RUMMY_TEAMS") {
+            rules.teams = Some(parse_var("RUMMY_TEAMS", &value)?);
+        }
+        if let Some(value) = var("RUMMY_MAX_LAYOFF") {
+            rules.max_layoff = Some(parse_var("RUMMY_MAX_LAYOFF", &value)?);
+        }
+        if let Some(value) = var("RUMMY_DEAD_HAND_AFTER") {
+            rules.dead_hand_after = Some(parse_var("RUMMY_DEAD_HAND_AFTER", &value)?);
+        }
+        if let Some(value) = var("RUMMY_TURN_TIMER") {
+            rules.turn_timer = Some(parse_var("RUMMY_TURN_TIMER", &value)?);
+        }
+
+        Ok(())
+    }
+
+    /// Parses every setting, the defaults for any left out.
+    fn resolve(self) -> Result<Config, RummyError> {
+        let defaults = Config::default();
+        let rules = self.rules;
+        self.keys.check()?;
+
+        Ok(Config {
+            llm: self.llm,
+            players: self.players,
+            theme: self.theme,
+            ai: parse_or(self.ai, defaults.ai)?,
+            locale: parse_or(self.locale, defaults.locale)?,
+            celebration: parse_or(self.celebration, defaults.celebration)?,
+            tts: self.tts,
+            table_talk_level: parse_or(self.table_talk_level, defaults.table_talk_level)?,
+            chat_prompt: self.chat_prompt.unwrap_or(defaults.chat_prompt),
+            ai_search: self
+                .ai_time_budget_ms
+                .map_or(defaults.ai_search, ai_time_budget),
+            deck: parse_or(rules.deck, defaults.deck)?,
+            variant: parse_or(rules.variant, defaults.variant)?,
+            turn_order: parse_or(rules.turn_order, defaults.turn_order)?,
+            cut: rules.cut.map(|cut| cut.parse()).transpose()?,
+            burn: rules.burn.unwrap_or(defaults.burn),
+            declare: rules.declare.unwrap_or(defaults.declare),
+            max_layoff: rules.max_layoff.unwrap_or(defaults.max_layoff),
+            dead_hand_after: rules.dead_hand_after,
+            tiebreak: parse_or(rules.tiebreak, defaults.tiebreak)?,
+            teams: rules.teams.unwrap_or(defaults.teams),
+            turn_timer: rules.turn_timer.map(Duration::from_secs),
+            keys: self.keys,
+        })
+    }
+}
+
+/// An AI time budget of `ms` milliseconds, where none at all is no search.
+pub fn ai_time_budget(ms: u64) -> AiSearch {
+    match ms {
+        0 => AiSearch::Off,
+        ms => AiSearch::Time(Duration::from_millis(ms)),
+    }
+}
+
+fn parse_or<T>(setting: Option<String>, default: T) -> Result<T, RummyError>
+where
+    T: FromStr<Err = RummyError>,
+{
+    setting.map_or(Ok(default), |setting| setting.parse())
+}
+
+fn parse_var<T: FromStr>(name: &str, value: &str) -> Result<T, RummyError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| RummyError::Config(format!("{name} can't be {value:?}")))
+}
+
+/// Something to do at the prompt on your turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Draw,
+    Play,
+    Retrieve,
+    Hint,
+    Odds,
+    Discards,
+}
+
+impl Key {
+    pub const ALL: [Key; 6] = [
+        Key::Draw,
+        Key::Play,
+        Key::Retrieve,
+        Key::Hint,
+        Key::Odds,
+        Key::Discards,
+    ];
+
+    /// What it's called at the prompt, which can always be typed out in
+    /// full instead of pressing its key.
+    pub fn name(self) -> &'static str {
+        match self {
+            Key::Draw => "Draw",
+            Key::Play => "Play",
+            Key::Retrieve => "Retrieve",
+            Key::Hint => "Hint",
+            Key::Odds => "Odds",
+            Key::Discards => "Discards",
+        }
+    }
+}
+
+/// What's typed for each `Key`, a letter or a word.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub draw: String,
+    pub play: String,
+    pub retrieve: String,
+    pub hint: String,
+    pub odds: String,
+    pub discards: String,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            draw: "d".to_string(),
+            play: "p".to_string(),
+            retrieve: "r".to_string(),
+            hint: "h".to_string(),
+            odds: "a".to_string(),
+            discards: "v".to_string(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, key: Key) -> &str {
+        match key {
+            Key::Draw => &self.draw,
+            Key::Play => &self.play,
+            Key::Retrieve => &self.retrieve,
+            Key::Hint => &self.hint,
+            Key::Odds => &self.odds,
+            Key::Discards => &self.discards,
+        }
+    }
+
+    /// The key `input` presses, by its binding or its name, in any case.
+    pub fn pressed(&self, input: &str) -> Option<Key> {
+        let input = input.trim();
+        Key::ALL.into_iter().find(|&key| {
+            input.eq_ignore_ascii_case(self.key(key))
+                || input.eq_ignore_ascii_case(key.name())
+                || input.to_lowercase() == Prompt::Key(key).to_string().to_lowercase()
+        })
+    }
+
+    /// The keys in `keys` as a choice, e.g. "Draw (D), Play (P) or Hint (H)?".
+    pub fn prompt(&self, keys: &[Key]) -> String {
+        let choices: Vec<String> = keys
+            .iter()
+            .map(|&key| format!("{} ({})", Prompt::Key(key), self.key(key).to_uppercase()))
+            .collect();
+
+        Prompt::Choice(choices).to_string()
+    }
+
+    /// Every key needs something to press, and something of its own.
+    fn check(&self) -> Result<(), RummyError> {
+        for (idx, key) in Key::ALL.into_iter().enumerate() {
+            let binding = self.key(key).trim();
+            if binding.is_empty() {
+                return Err(RummyError::Config(format!(
+                    "{} has no key bound to it",
+                    key.name()
+                )));
+            }
+            if let Some(other) = Key::ALL[..idx]
+                .iter()
+                .find(|&&other| self.key(other).trim().eq_ignore_ascii_case(binding))
+            {
+                return Err(RummyError::Config(format!(
+                    "{} and {} are both bound to {binding:?}",
+                    other.name(),
+                    key.name()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_the_environment_overrides_the_file() {
+        let mut file = ConfigFile {
+            ai: Some("conservative".to_string()),
+            rules: RulesFile {
+                variant: Some("gin".to_string()),
+                burn: Some(true),
+                ..RulesFile::default()
+            },
+            ..ConfigFile::default()
+        };
+        let env = HashMap::from([
+            ("RUMMY_VARIANT", "seven-card"),
+            ("RUMMY_DECK", "2x"),
+            ("RUMMY_TURN_TIMER", "45"),
+            ("RUMMY_THEME", "dusk.toml"),
+            ("RUMMY_TIEBREAK", "earliest"),
+            ("RUMMY_TEAMS", "true"),
+            ("RUMMY_LOCALE", "es"),
+            ("RUMMY_CELEBRATION", "random"),
+            ("RUMMY_TTS", "system"),
+            ("RUMMY_TABLE_TALK_LEVEL", "off"),
+            ("RUMMY_CHAT_PROMPT", "false"),
+            ("RUMMY_AI_TIME_BUDGET_MS", "0"),
+        ]);
+        let var = |name: &str| env.get(name).map(|value| value.to_string());
+        file.override_from(var).unwrap();
+
+        let config = file.resolve().unwrap();
+        assert_eq!(config.variant, Variant::SevenCard);
+        assert_eq!(config.deck, DeckConfig::standard(2));
+        assert_eq!(config.ai, PlayerType::Conservative);
+        assert!(config.burn);
+        assert_eq!(config.turn_timer, Some(Duration::from_secs(45)));
+        assert_eq!(config.theme, Some(PathBuf::from("dusk.toml")));
+        assert_eq!(config.max_layoff, MAX_LAYOFF);
+        assert_eq!(config.tiebreak, Tiebreak::Earliest);
+        assert!(config.teams);
+        assert_eq!(config.locale, Locale::Es);
+        assert_eq!(config.celebration, CelebrationKind::Random);
+        assert_eq!(config.tts.as_deref(), Some("system"));
+        assert_eq!(config.table_talk_level, TableTalkLevel::Off);
+        assert!(!config.chat_prompt);
+        assert_eq!(config.ai_search, AiSearch::Off);
+
+        // Nothing anywhere is the defaults
+        assert_eq!(ConfigFile::default().resolve().unwrap(), Config::default());
+
+        let bad = |name: &'static str, value: &'static str| {
+            let mut file = ConfigFile::default();
+            file.override_from(|var: &str| (var == name).then(|| value.to_string()))
+                .and_then(|()| file.resolve())
+        };
+        assert!(bad("RUMMY_MAX_LAYOFF", "lots").is_err());
+        assert!(bad("RUMMY_VARIANT", "canasta").is_err());
+        assert!(bad("RUMMY_AI", "reckless").is_err());
+        assert!(bad("RUMMY_TIEBREAK", "coin-flip").is_err());
+        assert!(bad("RUMMY_LOCALE", "klingon").is_err());
+
+        assert_eq!(
+            settings_path(|name| (name == "RUMMY_CONFIG").then(|| "club.toml".to_string())),
+            Some(PathBuf::from("club.toml"))
+        );
+    }
+
+    #[test]
+    fn test_keys_can_be_rebound() {
+        let keys = KeyBindings {
+            draw: "j".to_string(),
+            play: "k".to_string(),
+            ..KeyBindings::default()
+        };
+        keys.check().unwrap();
+        assert_eq!(keys.pressed(" J "), Some(Key::Draw));
+        assert_eq!(keys.pressed("draw"), Some(Key::Draw));
+        assert_eq!(keys.pressed("d"), None);
+        assert_eq!(keys.pressed("v"), Some(Key::Discards));
+        assert_eq!(
+            keys.prompt(&[Key::Draw, Key::Play, Key::Hint]),
+            "Draw (J), Play (K) or Hint (H)?"
+        );
+        assert_eq!(
+            KeyBindings::default().prompt(&Key::ALL),
+            "Draw (D), Play (P), Retrieve (R), Hint (H), Odds (A) or Discards (V)?"
+        );
+
+        let clash = KeyBindings {
+            hint: "P".to_string(),
+            ..KeyBindings::default()
+        };
+        assert!(clash.check().is_err());
+        let unbound = KeyBindings {
+            odds: " ".to_string(),
+            ..KeyBindings::default()
+        };
+        assert!(unbound.check().is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Cleaning up what players type and what the LLM says before it joins the conversation.
+
+pub mod discussion;
+pub mod memory;
+pub mod profile;
+#[cfg(feature = "cli")]
+pub mod provider;
+pub mod relationships;
+#[cfg(feature = "cli")]
+pub mod speech;
+pub mod table_talk;
+
+pub use memory::Memory;
+pub use profile::{Lineup, Profile, Profiles};
+#[cfg(feature = "cli")]
+pub use provider::DialogueProvider;
+pub use table_talk::TableTalkLevel;
+
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use regex::Regex;
+use unicode_normalization::UnicodeNormalization;
+
+/// What AI players say when there's no LLM to ask, like in demo mode.
+/// They fit any book, and end with an emoji like the LLM's answers do.
+const CANNED_LINES: &[&str] = &[
+    "I did not see that ending coming at all 😮",
+    "The middle dragged for me, but the last hundred pages flew by 📖",
+    "That chapter made me call my sister afterwards 😢",
+    "I keep thinking about why the narrator told it that way 🤔",
+    "Whoever picked this one, thank you 😊",
+    "I underlined half of the third chapter ✏️",
+    "Honestly the villain was more sympathetic than the hero 😈",
+    "I would read a sequel tomorrow, would anyone else? 🙋",
+    "The setting felt like a character of its own 🌄",
+    "I cried on the train reading the last pages 😭",
+    "I'll admit I skimmed some of the descriptions 🙈",
+    "That line about choice is going on my fridge 🧲",
+];
+
+/// A canned line of book talk, never the same as the speaker's `previous` one.
+pub fn canned_line<R: Rng + ?Sized>(previous: Option<&str>, rng: &mut R) -> String {
+    let choices: Vec<&str> = CANNED_LINES
+        .iter()
+        .copied()
+        .filter(|line| Some(*line) != previous)
+        .collect();
+
+    choices
+        .choose(rng)
+        .copied()
+        .unwrap_or(CANNED_LINES[0])
+        .to_string()
+}
+
+/// NFC normalizes dialogue, folds line breaks and other control characters
+/// into spaces and trims the result.
+pub fn normalize_dialogue(input: &str) -> String {
+    let dialogue: String = input
+        .nfc()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+
+    dialogue.trim().to_string()
+}
+
+/// Removes `<think>` blocks reasoning models put before their answer.
+pub fn strip_think_blocks(s: &str) -> String {
+    // (?is) -> case-insensitive + dot matches newlines
+    // Allow spaces and optional attributes inside the opening tag
+    // and optional spaces inside the closing tag.
+    let re = Regex::new(r"(?is)<\s*think\b[^>]*>.*?<\s*/\s*think\s*>").unwrap();
+    let cleaned = re.replace_all(s, "");
+    // also collapse any excessive blank lines that may be left behind
+    let re_blank = Regex::new(r"\n{3,}").unwrap();
+    re_blank
+        .replace_all(cleaned.as_ref(), "\n\n")
+        .trim()
+        .to_string()
+}
+
+/// Removes the `Name: ` the model likes to start its reply with.
+///
+/// Both the answer and the name are normalized first, so a name typed with
+/// combining accents still matches the precomposed form the model answers with.
+pub fn strip_speaker_prefix(answer: &str, name: &str) -> String {
+    let answer = normalize_dialogue(answer);
+    let name: String = name.nfc().collect();
+
+    match answer
+        .strip_prefix(name.as_str())
+        .and_then(|rest| rest.strip_prefix(':'))
+    {
+        Some(rest) => rest.trim_start().to_string(),
+        None => answer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_dialogue() {
+        assert_eq!(
+            normalize_dialogue("  Cafe\u{301} talk ☕\n"),
+            "Café talk ☕"
+        );
+        assert_eq!(
+            normalize_dialogue("第一章\r\nが好き 😊"),
+            "第一章  が好き 😊"
+        );
+    }
+
+    #[test]
+    fn test_strip_speaker_prefix() {
+        assert_eq!(
+            strip_speaker_prefix("村上春樹: 井戸の場面が忘れられない 🌙", "村上春樹"),
+            "井戸の場面が忘れられない 🌙"
+        );
+        assert_eq!(
+            strip_speaker_prefix("🐙 Otto: Cal never stood a chance 😢", "🐙 Otto"),
+            "Cal never stood a chance 😢"
+        );
+
+        // Decomposed accent in the name, precomposed in the answer
+        assert_eq!(
+            strip_speaker_prefix("Renée: Timshel! 🙌", "Rene\u{301}e"),
+            "Timshel! 🙌"
+        );
+
+        // Only a leading prefix is removed
+        assert_eq!(
+            strip_speaker_prefix("I agree with 李白: Adam is naive 🤔", "李白"),
+            "I agree with 李白: Adam is naive 🤔"
+        );
+    }
+
+    #[test]
+    fn test_canned_line_does_not_repeat() {
+        let mut rng = rand::rng();
+        let mut previous = canned_line(None, &mut rng);
+        for _ in 0..50 {
+            let line = canned_line(Some(&previous), &mut rng);
+            assert_ne!(line, previous);
+            previous = line;
+        }
+    }
+
+    #[test]
+    fn test_strip_think_blocks() {
+        assert_eq!(
+            strip_think_blocks("<think>考えています…</think>\nLee is the heart of it 💛"),
+            "Lee is the heart of it 💛"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Discussion questions. Every few rounds a moderator puts a question about
+//! the book to the table, written by the dialogue provider, and everyone's
+//! next line answers it. Once the game's over the provider can sum up what
+//! everyone made of the book, too.
+
+use crate::dialogue::strip_think_blocks;
+use crate::game::events::{EventSink, GameEvent};
+
+/// Who asks the discussion questions, as they appear in the conversation.
+pub const MODERATOR: &str = "Moderator";
+
+/// How many rounds go by between questions, unless the table says otherwise.
+pub const DISCUSSION_EVERY: usize = 3;
+
+/// What to ask the provider for a question about `book`, one the table
+/// hasn't been `asked` already.
+pub fn discussion_prompt(book: &str, asked: &[String]) -> String {
+    let mut prompt = format!(
+        "You are moderating a book club discussing {book}. Ask the members one open question about the book, about its themes, its characters or what they would have done in their place. Answer with the question alone, in a single sentence."
+    );
+    if !asked.is_empty() {
+        prompt.push_str(" Don't ask any of these again:");
+        for question in asked {
+            prompt.push_str("\n- ");
+            prompt.push_str(question);
+        }
+    }
+    prompt
+}
+
+/// What to ask the provider for a recap of the talk about `book`, from the
+/// last thing each player said, in seating order.
+pub fn recap_prompt(book: &str, lines: &[(String, String)]) -> String {
+    let mut prompt = format!(
+        "A book club has just finished discussing {book}. Here is the last thing each member said:"
+    );
+    for (player, line) in lines {
+        prompt.push_str(&format!("\n{player}: {line}"));
+    }
+    prompt.push_str("\n\nSum up the themes each member raised about the book, a sentence for each, starting with their name. Answer with the summary alone.");
+    prompt
+}
+
+/// The recap when there's no provider to write one, or it couldn't: what
+/// everyone last said, by name.
+pub fn offline_recap(lines: &[(String, String)]) -> String {
+    lines
+        .iter()
+        .map(|(player, line)| format!("{player} left it at \"{line}\""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The question in the provider's answer: its first line ending in a
+/// question mark, without any numbering or quotes around it.
+pub fn parse_question(answer: &str) -> Option<String> {
+    strip_think_blocks(answer)
+        .lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '.' | ')' | '-'))
+                .trim()
+                .trim_matches(|c| matches!(c, '"' | '“' | '”' | '*'))
+                .trim()
+        })
+        .find(|line| line.ends_with('?') && line.len() > 1)
+        .map(str::to_string)
+}
+
+/// Keeps count of the rounds and says when a question is due. The question
+/// itself comes from whichever `DialogueProvider` the table talks through:
+/// ask it `prompt`, and hand its answer to `take_question`.
+#[derive(Clone, Debug)]
+pub struct Moderator {
+    every: usize,
+    rounds: usize,
+    asked: Vec<String>,
+}
+
+impl Moderator {
+    /// A question every `every` rounds, from the first.
+    pub fn new(every: usize) -> Self {
+        Self {
+            every: every.max(1),
+            rounds: 0,
+            asked: Vec::new(),
+        }
+    }
+
+    /// Whether enough rounds have been dealt for another question.
+    pub fn is_due(&self) -> bool {
+        self.rounds >= self.every
+    }
+
+    /// The questions asked so far, oldest first.
+    pub fn asked(&self) -> &[String] {
+        &self.asked
+    }
+
+    /// What to ask the provider for the next question about `book`.
+    pub fn prompt(&self, book: &str) -> String {
+        discussion_prompt(book, &self.asked)
+    }
+
+    /// The question in the provider's `answer`, which starts the count to
+    /// the next one. `None` if it didn't write one, in which case it's tried
+    /// again next turn.
+    pub fn take_question(&mut self, answer: &str) -> Option<String> {
+        let question = parse_question(answer)?;
+
+        self.rounds = 0;
+        self.asked.push(question.clone());
+        Some(question)
+    }
+}
+
+impl EventSink for Moderator {
+    fn emit(&mut self, event: &GameEvent) {
+        if let GameEvent::RoundDealt { .. } = event {
+            self.rounds += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dealt() -> GameEvent {
+        GameEvent::RoundDealt {
+            player: "Ada".to_string(),
+            hands: Vec::new(),
+            deal_hash: 0,
+        }
+    }
+
+    #[test]
+    fn test_moderator_asks_every_few_rounds() {
+        let mut moderator = Moderator::new(2);
+
+        moderator.emit(&dealt());
+        assert!(!moderator.is_due());
+        moderator.emit(&dealt());
+        assert!(moderator.is_due());
+
+        let question =
+            moderator.take_question("<think>hmm</think>\n1. \"Is Cathy born evil, or made so?\"");
+        assert_eq!(question.as_deref(), Some("Is Cathy born evil, or made so?"));
+        assert!(!moderator.is_due());
+
+        // An answer without a question leaves one due
+        moderator.emit(&dealt());
+        moderator.emit(&dealt());
+        assert_eq!(moderator.take_question("I couldn't say."), None);
+        assert!(moderator.is_due());
+        let question = moderator.take_question("Sure!\nWhat does timshel mean to you?");
+        assert_eq!(question.as_deref(), Some("What does timshel mean to you?"));
+
+        // The provider is told what's been asked, so it doesn't repeat itself
+        let prompt = moderator.prompt("East of Eden");
+        assert!(prompt.contains("discussing East of Eden"));
+        assert!(prompt.contains("- Is Cathy born evil, or made so?"));
+        assert_eq!(moderator.asked().len(), 2);
+        assert!(!Moderator::new(2).prompt("East of Eden").contains("again"));
+    }
+
+    #[test]
+    fn test_the_recap_goes_through_everyone_in_order() {
+        let lines = [
+            ("Lee".to_string(), "Timshel is the whole book.".to_string()),
+            ("Ada".to_string(), "Cathy scared me 😱".to_string()),
+        ];
+
+        let prompt = recap_prompt("East of Eden", &lines);
+        assert!(prompt.contains("discussing East of Eden"));
+        assert!(prompt.contains("\nLee: Timshel is the whole book.\nAda: Cathy scared me 😱"));
+
+        assert_eq!(
+            offline_recap(&lines),
+            "Lee left it at \"Timshel is the whole book.\"\nAda left it at \"Cathy scared me 😱\""
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! What the players remember of earlier sessions on the same book. Each
+//! session's talk is boiled down to its meatiest lines and kept from game to
+//! game, so the next time the club reads the book the AI players can pick up
+//! points they made, or argued with, before.
+
+use crate::error::RummyError;
+use crate::save::{TranscriptLine, data_dir};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How many sessions on a book a player remembers, the latest.
+pub const SESSIONS_REMEMBERED: usize = 5;
+
+/// How many lines of a session are kept, the longest, which are the ones
+/// most likely to be making a point about the book.
+pub const LINES_PER_SESSION: usize = 8;
+
+/// How many remembered lines a player is reminded of when they speak.
+const LINES_PROMPTED: usize = 4;
+
+/// Where the players' memories are kept.
+pub fn default_memory_path() -> PathBuf {
+    data_dir().join("dialogue_memory.json")
+}
+
+/// One session's talk, as a player remembers it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// In the order they were said.
+    pub lines: Vec<TranscriptLine>,
+}
+
+/// Every player's sessions, by player and then by book.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Memory {
+    players: HashMap<String, HashMap<String, Vec<Session>>>,
+}
+
+impl Memory {
+    /// What's been remembered at `path`. No file yet means nothing's been.
+    pub fn load(path: &Path) -> Result<Self, RummyError> {
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(RummyError::Io(format!("{}: {err}", path.display()))),
+        };
+
+        serde_json::from_str(&json).map_err(|err| {
+            RummyError::Config(format!("{} isn't a dialogue memory: {err}", path.display()))
+        })
+    }
+
+    /// Writes the memory next to `path` first and moves it into place, so a
+    /// crash mid-write doesn't lose every earlier session.
+    pub fn save(&self, path: &Path) -> Result<(), RummyError> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| RummyError::Io(err.to_string()))?;
+        }
+
+        let json = serde_json::to_string(self).map_err(|err| RummyError::Io(err.to_string()))?;
+        let partial = path.with_extension("partial");
+        fs::write(&partial, json)
+            .and_then(|()| fs::rename(&partial, path))
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))
+    }
+
+    /// The sessions `player` remembers on `book`, oldest first.
+    pub fn sessions(&self, player: &str, book: &str) -> &[Session] {
+        self.players
+            .get(player)
+            .and_then(|books| books.get(&book_key(book)))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Has each of `players` remember the session's `lines` about `book`.
+    /// A session nobody spoke in isn't worth remembering.
+    pub fn remember<'a>(
+        &mut self,
+        book: &str,
+        players: impl IntoIterator<Item = &'a str>,
+        lines: &[TranscriptLine],
+    ) {
+        if lines.is_empty() {
+            return;
+        }
+
+        let session = Session {
+            lines: longest(lines.iter(), LINES_PER_SESSION)
+                .into_iter()
+                .cloned()
+                .collect(),
+        };
+
+        for player in players {
+            let sessions = self
+                .players
+                .entry(player.to_string())
+                .or_default()
+                .entry(book_key(book))
+                .or_default();
+            sessions.push(session.clone());
+            if sessions.len() > SESSIONS_REMEMBERED {
+                sessions.remove(0);
+            }
+        }
+    }
+
+    /// What `player` remembers being said about `book` in earlier sessions,
+    /// for the LLM. Empty if they've never discussed it.
+    pub fn prompt_for(&self, player: &str, book: &str) -> String {
+        let lines = self
+            .sessions(player, book)
+            .iter()
+            .flat_map(|session| &session.lines);
+
+        longest(lines, LINES_PROMPTED)
+            .into_iter()
+            .map(|line| {
+                let speaker = if line.player == player {
+                    "You"
+                } else {
+                    line.player.as_str()
+                };
+                format!("{speaker} said \"{}\"", line.text)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The `keep` longest of `lines`, in the order they were said.
+fn longest<'a>(
+    lines: impl Iterator<Item = &'a TranscriptLine>,
+    keep: usize,
+) -> Vec<&'a TranscriptLine> {
+    let mut lines: Vec<(usize, &TranscriptLine)> = lines.enumerate().collect();
+    lines.sort_by_key(|(_, line)| std::cmp::Reverse(line.text.chars().count()));
+    lines.truncate(keep);
+    lines.sort_by_key(|(idx, _)| *idx);
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// Books are remembered however their title was typed.
+fn book_key(book: &str) -> String {
+    book.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn said(player: &str, text: &str) -> TranscriptLine {
+        TranscriptLine {
+            player: player.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sessions_are_remembered_by_book() {
+        let mut memory = Memory::default();
+        let mut lines = vec![
+            said("Lee", "Cal is the real hero, he chooses to be good."),
+            said("Ada", "Hm"),
+            said("Ada", "Timshel means thou mayest, it's all about choice!"),
+        ];
+        lines.extend((0..LINES_PER_SESSION).map(|_| said("Sam", "Ok.")));
+        memory.remember("East of Eden", ["Lee", "Ada"], &lines);
+        memory.remember("Dune", ["Lee"], &[said("Ada", "The spice must flow.")]);
+        memory.remember("East of Eden", ["Lee"], &[]);
+
+        // The short lines are the first to go, the rest stay in order
+        let sessions = memory.sessions("Lee", " east of  Eden");
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].lines.len(), LINES_PER_SESSION);
+        assert_eq!(sessions[0].lines[0], lines[0]);
+        assert_eq!(sessions[0].lines[1], lines[2]);
+
+        let prompt = memory.prompt_for("Lee", "East of Eden");
+        assert!(prompt.starts_with("You said \"Cal is the real hero"));
+        assert!(prompt.contains("Ada said \"Timshel means thou mayest"));
+        assert!(!prompt.contains("spice"));
+        assert_eq!(memory.prompt_for("Sam", "East of Eden"), "");
+
+        for _ in 0..SESSIONS_REMEMBERED {
+            memory.remember("Dune", ["Lee"], &[said("Lee", "Fear is the mind-killer.")]);
+        }
+        let sessions = memory.sessions("Lee", "Dune");
+        assert_eq!(sessions.len(), SESSIONS_REMEMBERED);
+        assert!(
+            sessions
+                .iter()
+                .all(|session| session.lines[0].player == "Lee")
+        );
+    }
+}
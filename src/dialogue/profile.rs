@@ -0,0 +1,320 @@
+//! Characters for the AI seats, written down once instead of typed in at
+//! the start of every game. A profiles file is TOML, a `[[player]]` table for
+//! each character:
+//!
+//! ```toml
+//! [[player]]
+//! name = "Lee"
+//! description = "a philosophy student"
+//! player_type = "balanced"
+//! verbosity = "chatty"
+//! favorite_genres = ["russian novels", "stoicism"]
+//! quote_style = "quote it word for word, chapter and verse"
+//! voice = "en-gb"
+//! ```
+//!
+//! Only the name is needed. Everything else shapes how the character plays
+//! and what the LLM is asked to say for them.
+
+use crate::display::normalize_name;
+use crate::error::RummyError;
+use crate::game::{Hand, Player, PlayerType};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// How much a character says when it's their turn to talk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verbosity {
+    /// A few words.
+    Terse,
+    /// A sentence, like everyone typed in at the table.
+    #[default]
+    Normal,
+    /// Two or three sentences.
+    Chatty,
+}
+
+impl Verbosity {
+    /// How long the LLM's answer should be, finishing "Please continue the
+    /// roleplay by responding with ...".
+    pub fn length(self) -> &'static str {
+        match self {
+            Verbosity::Terse => "a few words",
+            Verbosity::Normal => "a single sentence",
+            Verbosity::Chatty => "two or three sentences",
+        }
+    }
+}
+
+/// One AI character.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Balanced, like the AI players typed in at the table, if not given.
+    #[serde(default)]
+    pub player_type: Option<PlayerType>,
+    #[serde(default)]
+    pub verbosity: Verbosity,
+    #[serde(default)]
+    pub favorite_genres: Vec<String>,
+    /// How they bring the book into it, finishing "you ...", e.g.
+    /// "paraphrase it loosely".
+    #[serde(default)]
+    pub quote_style: Option<String>,
+    /// The voice their lines are read aloud in, if the table has `tts` on:
+    /// a system voice, or whatever `{voice}` means to the command.
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+
+impl Profile {
+    /// The character sat down with an empty hand.
+    pub fn player(&self) -> Player {
+        Player {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            player_type: Some(self.player_type.clone().unwrap_or(PlayerType::Balanced)),
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        }
+    }
+
+    /// The character's tastes and habits for the LLM, beyond their
+    /// description. Empty if the profile doesn't say.
+    pub fn prompt(&self) -> String {
+        let mut prompt = Vec::new();
+        if let Some((last, rest)) = self.favorite_genres.split_last() {
+            let genres = if rest.is_empty() {
+                last.clone()
+            } else {
+                format!("{} and {last}", rest.join(", "))
+            };
+            prompt.push(format!(
+                "You love {genres}, and can't help comparing books to them."
+            ));
+        }
+        if let Some(style) = &self.quote_style {
+            prompt.push(format!("When you bring up the book, you {style}."));
+        }
+
+        prompt.join(" ")
+    }
+}
+
+/// Everyone in a profiles file, in the order they're written.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profiles {
+    #[serde(rename = "player", default)]
+    players: Vec<Profile>,
+}
+
+impl Profiles {
+    /// Names are tidied like any typed in at the table, and have to tell the
+    /// characters apart.
+    pub fn new(mut players: Vec<Profile>) -> Result<Self, RummyError> {
+        for idx in 0..players.len() {
+            let name = normalize_name(&players[idx].name);
+            if name.is_empty() {
+                return Err(RummyError::Config(format!(
+                    "Player {} in the profiles has no name",
+                    idx + 1
+                )));
+            }
+            if players[..idx].iter().any(|player| player.name == name) {
+                return Err(RummyError::Config(format!(
+                    "There's more than one {name} in the profiles"
+                )));
+            }
+            players[idx].name = name;
+        }
+
+        Ok(Self { players })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, RummyError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+        let profiles: Self = toml::from_str(&contents).map_err(|err| {
+            RummyError::Config(format!("{} isn't a profiles file: {err}", path.display()))
+        })?;
+
+        Self::new(profiles.players)
+    }
+
+    pub fn players(&self) -> &[Profile] {
+        &self.players
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.players.iter().find(|profile| profile.name == name)
+    }
+}
+
+/// Everyone at the table, given up front instead of asked for, like
+/// `Me,Hemingbot:aggressive,Austenbot:conservative`. A name with a player type
+/// after it is an AI; one without is a human at the keyboard, unless the
+/// profiles have a character by that name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lineup {
+    seats: Vec<(String, Option<PlayerType>)>,
+}
+
+impl FromStr for Lineup {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<Lineup, RummyError> {
+        let mut seats: Vec<(String, Option<PlayerType>)> = Vec::new();
+        for (idx, seat) in input.split(',').enumerate() {
+            let (name, player_type) = match seat.split_once(':') {
+                Some((name, player_type)) => (name, Some(player_type.parse()?)),
+                None => (seat, None),
+            };
+            let name = normalize_name(name);
+            if name.is_empty() {
+                return Err(RummyError::Config(format!(
+                    "Seat {} in the lineup has no name",
+                    idx + 1
+                )));
+            }
+            if seats.iter().any(|(seated, _)| *seated == name) {
+                return Err(RummyError::Config(format!(
+                    "There's more than one {name} in the lineup"
+                )));
+            }
+            seats.push((name, player_type));
+        }
+        if seats.len() < 2 {
+            return Err(RummyError::Config(
+                "A lineup needs at least 2 players".to_string(),
+            ));
+        }
+
+        Ok(Lineup { seats })
+    }
+}
+
+impl Lineup {
+    /// Everyone sat down with an empty hand, in the order they're given.
+    /// Characters in `profiles` keep their description, and their player
+    /// type unless the lineup gives them another.
+    pub fn players(&self, profiles: &Profiles) -> Vec<Player> {
+        self.seats
+            .iter()
+            .map(|(name, player_type)| {
+                let mut player = match profiles.get(name) {
+                    Some(profile) => profile.player(),
+                    None => Player {
+                        name: name.clone(),
+                        description: String::new(),
+                        player_type: None,
+                        hand: Hand { cards: Vec::new() },
+                        actions: VecDeque::new(),
+                        dialogue: VecDeque::new(),
+                        score: 0,
+                    },
+                };
+                if player_type.is_some() {
+                    player.player_type = player_type.clone();
+                }
+                player
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> Profile {
+        Profile {
+            name: name.to_string(),
+            description: String::new(),
+            player_type: None,
+            verbosity: Verbosity::default(),
+            favorite_genres: Vec::new(),
+            quote_style: None,
+            voice: None,
+        }
+    }
+
+    #[test]
+    fn test_profiles_seat_and_prompt_their_characters() {
+        let lee = Profile {
+            description: "a philosophy student".to_string(),
+            player_type: Some(PlayerType::Aggressive),
+            verbosity: Verbosity::Chatty,
+            favorite_genres: vec!["russian novels".to_string(), "stoicism".to_string()],
+            quote_style: Some("quote it word for word".to_string()),
+            ..profile("  Lee ")
+        };
+        let profiles = Profiles::new(vec![lee, profile("Ada")]).unwrap();
+
+        let lee = profiles.get("Lee").unwrap();
+        let player = lee.player();
+        assert_eq!(player.description, "a philosophy student");
+        assert_eq!(player.player_type, Some(PlayerType::Aggressive));
+        assert_eq!(
+            lee.prompt(),
+            "You love russian novels and stoicism, and can't help comparing books to them. When you bring up the book, you quote it word for word."
+        );
+        assert_eq!(lee.verbosity.length(), "two or three sentences");
+
+        // Nothing beyond a name says nothing more
+        let ada = profiles.get("Ada").unwrap();
+        assert_eq!(ada.prompt(), "");
+        assert_eq!(ada.player().player_type, Some(PlayerType::Balanced));
+
+        assert!(Profiles::new(vec![profile("Ada"), profile("Ada ")]).is_err());
+        assert!(Profiles::new(vec![profile("  ")]).is_err());
+    }
+
+    #[test]
+    fn test_a_lineup_seats_humans_ais_and_characters() {
+        let lee = Profile {
+            description: "a philosophy student".to_string(),
+            player_type: Some(PlayerType::Aggressive),
+            ..profile("Lee")
+        };
+        let profiles = Profiles::new(vec![lee]).unwrap();
+
+        let lineup: Lineup = " Me, Hemingbot:aggressive,Lee ,Austenbot: Conservative"
+            .parse()
+            .unwrap();
+        let players = lineup.players(&profiles);
+        let seats: Vec<_> = players
+            .iter()
+            .map(|player| (player.name.as_str(), player.player_type.clone()))
+            .collect();
+        assert_eq!(
+            seats,
+            [
+                ("Me", None),
+                ("Hemingbot", Some(PlayerType::Aggressive)),
+                ("Lee", Some(PlayerType::Aggressive)),
+                ("Austenbot", Some(PlayerType::Conservative)),
+            ]
+        );
+        assert_eq!(players[2].description, "a philosophy student");
+
+        // A type in the lineup outranks the profile's
+        let lineup: Lineup = "Me,Lee:conservative".parse().unwrap();
+        assert_eq!(
+            lineup.players(&profiles)[1].player_type,
+            Some(PlayerType::Conservative)
+        );
+
+        assert!("Me".parse::<Lineup>().is_err());
+        assert!("Me,,Lee".parse::<Lineup>().is_err());
+        assert!("Me,Me".parse::<Lineup>().is_err());
+        assert!("Me,Hemingbot:reckless".parse::<Lineup>().is_err());
+    }
+}
@@ -0,0 +1,134 @@
+//! Where the words come from. The front-ends talk to the LLM, or whatever
+//! stands in for it, through a `DialogueProvider`, so nothing that writes
+//! prompts has to know which backend answers them.
+
+use crate::error::RummyError;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::time::Duration;
+
+/// A provider's answer, once it's written.
+pub type Reply<'a> = Pin<Box<dyn Future<Output = Result<String, RummyError>> + 'a>>;
+
+/// Something that answers prompts, like the LLM.
+pub trait DialogueProvider {
+    /// The answer to `prompt`, as the backend wrote it. Think blocks and
+    /// speaker prefixes are left for the caller to strip.
+    fn ask(&self, prompt: String) -> Reply<'_>;
+}
+
+/// How hard to try a backend that fails, or hangs, before giving up on a
+/// prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Tries in all, the first included.
+    pub attempts: u32,
+    /// How long each try gets to answer.
+    pub timeout: Duration,
+    /// Pause before the second try, doubled before each one after.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            timeout: Duration::from_secs(30),
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A provider that asks `inner` again, after a growing pause, when it fails
+/// or takes too long. Its error is the last try's.
+pub struct Retrying<P> {
+    inner: P,
+    policy: RetryPolicy,
+}
+
+impl<P: DialogueProvider> Retrying<P> {
+    pub fn new(inner: P, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+
+impl<P: DialogueProvider> DialogueProvider for Retrying<P> {
+    fn ask(&self, prompt: String) -> Reply<'_> {
+        Box::pin(async move {
+            let mut backoff = self.policy.backoff;
+            let mut attempt = 1;
+            loop {
+                let answer =
+                    tokio::time::timeout(self.policy.timeout, self.inner.ask(prompt.clone()))
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(RummyError::Network(format!(
+                                "No answer within {}s",
+                                self.policy.timeout.as_secs()
+                            )))
+                        });
+                match answer {
+                    Err(err) if attempt < self.policy.attempts => {
+                        tracing::warn!(attempt, error = %err, "Asking the LLM again");
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                        attempt += 1;
+                    }
+                    answer => return answer,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Fails the first `failures` prompts it's asked, then echoes them.
+    struct Flaky {
+        failures: Cell<u32>,
+        asked: Cell<u32>,
+    }
+
+    impl DialogueProvider for Flaky {
+        fn ask(&self, prompt: String) -> Reply<'_> {
+            Box::pin(async move {
+                self.asked.set(self.asked.get() + 1);
+                if self.failures.get() > 0 {
+                    self.failures.set(self.failures.get() - 1);
+                    return Err(RummyError::Network("connection reset".to_string()));
+                }
+                Ok(prompt)
+            })
+        }
+    }
+
+    fn flaky(failures: u32) -> Retrying<Flaky> {
+        let policy = RetryPolicy {
+            backoff: Duration::ZERO,
+            ..RetryPolicy::default()
+        };
+        let flaky = Flaky {
+            failures: Cell::new(failures),
+            asked: Cell::new(0),
+        };
+        Retrying::new(flaky, policy)
+    }
+
+    #[tokio::test]
+    async fn test_a_failed_prompt_is_asked_again() {
+        let provider = flaky(2);
+        assert_eq!(provider.ask("Hi".to_string()).await, Ok("Hi".to_string()));
+        assert_eq!(provider.inner.asked.get(), 3);
+
+        // Out of tries, the last error's the answer
+        let provider = flaky(3);
+        assert!(matches!(
+            provider.ask("Hi".to_string()).await,
+            Err(RummyError::Network(_))
+        ));
+        assert_eq!(provider.inner.asked.get(), 3);
+    }
+}
@@ -0,0 +1,248 @@
+//! Who at the table has it in for whom. Stolen melds and handy discards are
+//! remembered for the whole game and put to the LLM, so the players carry
+//! their grudges and alliances from round to round.
+
+use crate::card::Card;
+use crate::game::events::{EventSink, GameEvent};
+use serde::{Deserialize, Serialize};
+
+/// How much having the meld taken off them sours a player on the taker.
+const STEAL_WARMTH: i32 = -2;
+
+/// How much picking up someone's discard warms a player to them.
+const GIFT_WARMTH: i32 = 1;
+
+/// Feelings this strong are a grudge or an alliance, not a passing mood.
+const FIRM_WARMTH: i32 = 3;
+
+/// How many of their feelings a player is reminded of when they speak.
+const FEELINGS_PROMPTED: usize = 3;
+
+/// How `player` feels about `toward`, and the last thing that made them feel it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tie {
+    pub player: String,
+    pub toward: String,
+    /// Below zero is a grudge, above it an alliance.
+    pub warmth: i32,
+    /// What `toward` last did, as `player` remembers it.
+    pub reason: String,
+}
+
+/// Every tie between the players, kept from the game's events.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Relationships {
+    ties: Vec<Tie>,
+    /// Who threw away the last discard, to know whose card was picked up.
+    #[serde(skip)]
+    last_discard: Option<(String, Card)>,
+    /// Whose cards make up the meld being laid off onto.
+    #[serde(skip)]
+    meld_owner: Option<String>,
+}
+
+impl Relationships {
+    pub fn ties(&self) -> impl Iterator<Item = &Tie> {
+        self.ties.iter()
+    }
+
+    /// How `player` feels about `toward`, nothing either way if they've
+    /// never crossed paths.
+    pub fn warmth(&self, player: &str, toward: &str) -> i32 {
+        self.tie(player, toward).map_or(0, |tie| tie.warmth)
+    }
+
+    /// Forgets every tie to or from a player `keep` turns down, like someone
+    /// who's left the table.
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.ties
+            .retain(|tie| keep(&tie.player) && keep(&tie.toward));
+    }
+
+    /// How `player` feels about the rest of the table, strongest feelings
+    /// first, for the LLM. Empty until something's happened between them.
+    pub fn prompt_for(&self, player: &str) -> String {
+        let mut ties: Vec<&Tie> = self
+            .ties
+            .iter()
+            .filter(|tie| tie.player == player && tie.warmth != 0)
+            .collect();
+        ties.sort_by_key(|tie| std::cmp::Reverse(tie.warmth.abs()));
+
+        ties.iter()
+            .take(FEELINGS_PROMPTED)
+            .map(|tie| {
+                let feeling = match tie.warmth {
+                    warmth if warmth <= -FIRM_WARMTH => "You hold a grudge against",
+                    warmth if warmth < 0 => "You're wary of",
+                    warmth if warmth >= FIRM_WARMTH => "You see an ally in",
+                    _ => "You're warming to",
+                };
+                format!("{feeling} {}: they {}.", tie.toward, tie.reason)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn tie(&self, player: &str, toward: &str) -> Option<&Tie> {
+        self.ties
+            .iter()
+            .find(|tie| tie.player == player && tie.toward == toward)
+    }
+
+    fn feel(&mut self, player: &str, toward: &str, warmth: i32, reason: String) {
+        if player == toward {
+            return;
+        }
+
+        match self
+            .ties
+            .iter_mut()
+            .find(|tie| tie.player == player && tie.toward == toward)
+        {
+            Some(tie) => {
+                tie.warmth += warmth;
+                tie.reason = reason;
+            }
+            None => self.ties.push(Tie {
+                player: player.to_string(),
+                toward: toward.to_string(),
+                warmth,
+                reason,
+            }),
+        }
+    }
+
+    /// Whoever threw away `card`, if it was the last discard.
+    fn discarded_by(&mut self, card: Card) -> Option<String> {
+        match self.last_discard.take() {
+            Some((player, discarded)) if discarded == card => Some(player),
+            _ => None,
+        }
+    }
+}
+
+impl EventSink for Relationships {
+    fn emit(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::CardDiscarded { player, card } => {
+                self.last_discard = Some((player.clone(), *card));
+            }
+            GameEvent::CardDrawn {
+                player,
+                retrieved: Some(card),
+            } => {
+                if let Some(giver) = self.discarded_by(*card) {
+                    let reason = format!("threw away the {card} you picked up");
+                    self.feel(player, &giver, GIFT_WARMTH, reason);
+                }
+            }
+            GameEvent::Declared { player, card, .. } => {
+                if let Some(giver) = self.discarded_by(*card) {
+                    let reason = format!("threw away the {card} you declared on");
+                    self.feel(player, &giver, GIFT_WARMTH, reason);
+                }
+            }
+            GameEvent::HandPlayed { player, .. } => self.meld_owner = Some(player.clone()),
+            GameEvent::LayoffMade {
+                player,
+                cards,
+                score: Some(_),
+            } => {
+                if let Some(owner) = self.meld_owner.replace(player.clone()) {
+                    let cards: Vec<String> = cards.iter().map(Card::to_string).collect();
+                    let reason = format!(
+                        "took your meld off you by laying off the {}",
+                        cards.join(" and ")
+                    );
+                    self.feel(&owner, player, STEAL_WARMTH, reason);
+                }
+            }
+            GameEvent::RoundDealt { .. }
+            | GameEvent::RoundRedealt { .. }
+            | GameEvent::RoundStalemate { .. }
+            | GameEvent::DeadHand { .. } => {
+                self.last_discard = None;
+                self.meld_owner = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(card: &str) -> Card {
+        card.parse().unwrap()
+    }
+
+    #[test]
+    fn test_grudges_outlast_the_round() {
+        let mut relationships = Relationships::default();
+        let steal = |relationships: &mut Relationships| {
+            for event in [
+                GameEvent::HandPlayed {
+                    player: "Ada".to_string(),
+                    hand: Vec::new(),
+                    score: 40,
+                },
+                GameEvent::LayoffMade {
+                    player: "Bram".to_string(),
+                    cards: vec![card("Ks")],
+                    score: Some(50),
+                },
+                GameEvent::LayoffMade {
+                    player: "Cy".to_string(),
+                    cards: vec![card("3d")],
+                    score: None,
+                },
+                GameEvent::RoundDealt {
+                    player: "Bram".to_string(),
+                    hands: Vec::new(),
+                    deal_hash: 0,
+                },
+            ] {
+                relationships.emit(&event);
+            }
+        };
+
+        steal(&mut relationships);
+        assert_eq!(relationships.warmth("Ada", "Bram"), STEAL_WARMTH);
+        assert_eq!(relationships.warmth("Bram", "Ada"), 0);
+        assert_eq!(relationships.warmth("Ada", "Cy"), 0);
+        assert!(
+            relationships
+                .prompt_for("Ada")
+                .starts_with("You're wary of Bram")
+        );
+
+        steal(&mut relationships);
+        assert!(
+            relationships
+                .prompt_for("Ada")
+                .starts_with("You hold a grudge against Bram: they took your meld")
+        );
+
+        // Picking up a discard warms Cy to Bram, the card before doesn't count
+        relationships.emit(&GameEvent::CardDiscarded {
+            player: "Ada".to_string(),
+            card: card("9c"),
+        });
+        relationships.emit(&GameEvent::CardDiscarded {
+            player: "Bram".to_string(),
+            card: card("2h"),
+        });
+        relationships.emit(&GameEvent::CardDrawn {
+            player: "Cy".to_string(),
+            retrieved: Some(card("2h")),
+        });
+        assert_eq!(relationships.warmth("Cy", "Bram"), GIFT_WARMTH);
+        assert_eq!(relationships.warmth("Cy", "Ada"), 0);
+
+        relationships.retain(|name| name != "Bram");
+        assert_eq!(relationships.ties().count(), 0);
+        assert_eq!(relationships.prompt_for("Ada"), "");
+    }
+}
@@ -0,0 +1,318 @@
+//! Reading the AI players' book talk aloud, with `tts` in the settings file
+//! or `--tts`.
+//!
+//! The words go to a `SpeechBackend`: the system's own voice (`say` on a
+//! Mac, `espeak-ng` anywhere else), or any command line that reads text on
+//! its input, like piper piped into a player. A `Narrator` hears the lines
+//! as `GameEvent::Dialogue` and speaks them on a thread of its own, one at a
+//! time, taking turns between the speakers so nobody talks over anybody.
+
+use crate::error::RummyError;
+use crate::game::events::{EventSink, GameEvent};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+/// Lines a speaker can have waiting before their oldest is dropped, so the
+/// voices don't fall minutes behind the table.
+pub const MAX_QUEUED_LINES: usize = 2;
+
+/// Something that can say a line out loud.
+pub trait SpeechBackend: Send {
+    /// Says `text` in `voice`, or the backend's own if `None`, and returns
+    /// once it's been said.
+    fn speak(&mut self, text: &str, voice: Option<&str>) -> Result<(), RummyError>;
+}
+
+/// The backend `tts` asks for: `system`, or a command line.
+pub fn backend(tts: &str) -> Box<dyn SpeechBackend> {
+    match tts.trim() {
+        "system" => Box::new(SystemVoice),
+        command => Box::new(ShellVoice::new(command)),
+    }
+}
+
+/// The operating system's speech, `say` on macOS and `espeak-ng` elsewhere.
+/// A voice is one of theirs, like "Samantha" or "en-gb".
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemVoice;
+
+impl SpeechBackend for SystemVoice {
+    fn speak(&mut self, text: &str, voice: Option<&str>) -> Result<(), RummyError> {
+        let mut command = if cfg!(target_os = "macos") {
+            let mut say = Command::new("say");
+            say.args(["-f", "-"]);
+            say
+        } else {
+            let mut espeak = Command::new("espeak-ng");
+            espeak.arg("--stdin");
+            espeak
+        };
+        if let Some(voice) = voice {
+            command.args(["-v", voice]);
+        }
+        run(command, text)
+    }
+}
+
+/// A command line run through `sh` for every line, which reads the line on
+/// its input. `{voice}` in it is the speaker's voice, empty if they haven't
+/// one, e.g. for piper
+/// `piper --model ~/voices/{voice}.onnx --output-raw | aplay -r 22050 -f S16_LE -t raw -`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShellVoice {
+    command: String,
+}
+
+impl ShellVoice {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+
+    /// The command line for a line in `voice`, quoted for the shell.
+    fn command_line(&self, voice: Option<&str>) -> String {
+        let voice = voice.unwrap_or_default();
+        let quoted = format!("'{}'", voice.replace('\'', r"'\''"));
+        self.command.replace("{voice}", &quoted)
+    }
+}
+
+impl SpeechBackend for ShellVoice {
+    fn speak(&mut self, text: &str, voice: Option<&str>) -> Result<(), RummyError> {
+        let mut command = Command::new("sh");
+        command.args(["-c", &self.command_line(voice)]);
+        run(command, text)
+    }
+}
+
+/// Runs `command` with `text` on its input, and waits for it to finish.
+fn run(mut command: Command, text: &str) -> Result<(), RummyError> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| RummyError::Io(format!("Couldn't start the speech: {err}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let status = child
+        .wait()
+        .map_err(|err| RummyError::Io(format!("The speech stopped: {err}")))?;
+    if !status.success() {
+        return Err(RummyError::Io(format!("The speech failed: {status}")));
+    }
+    Ok(())
+}
+
+/// What's left of a line once the emoji are gone, so the voice doesn't read
+/// out "face with tears of joy".
+pub fn speakable(text: &str) -> String {
+    let words: String = text
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace() || (*c as u32) < 0x2190)
+        .collect();
+    words.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The lines waiting to be said, each speaker's in the order they said them.
+/// Speakers take turns, so one who won't stop talking doesn't hold up the rest.
+#[derive(Clone, Debug, Default)]
+pub struct SpeechQueue {
+    speakers: VecDeque<(String, VecDeque<String>)>,
+}
+
+impl SpeechQueue {
+    pub fn push(&mut self, speaker: &str, line: String) {
+        match self.speakers.iter_mut().find(|(name, _)| name == speaker) {
+            Some((_, lines)) => {
+                lines.push_back(line);
+                if lines.len() > MAX_QUEUED_LINES {
+                    lines.pop_front();
+                }
+            }
+            None => self
+                .speakers
+                .push_back((speaker.to_string(), VecDeque::from([line]))),
+        }
+    }
+
+    /// The next line to say and who says it, the speaker going to the back
+    /// of the queue if they've more to say.
+    pub fn pop(&mut self) -> Option<(String, String)> {
+        let (speaker, mut lines) = self.speakers.pop_front()?;
+        let line = lines.pop_front()?;
+        if !lines.is_empty() {
+            self.speakers.push_back((speaker.clone(), lines));
+        }
+        Some((speaker, line))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.speakers.is_empty()
+    }
+}
+
+/// Speaks the AI players' lines. Anyone else's, like a human's chat, are left
+/// unsaid.
+pub struct Narrator {
+    lines: Sender<(String, String)>,
+    /// Everyone whose lines are read, and the voice they're read in.
+    voices: HashMap<String, Option<String>>,
+}
+
+impl Narrator {
+    /// Starts the thread `backend` speaks on, for the speakers in `voices`.
+    pub fn new(
+        mut backend: Box<dyn SpeechBackend>,
+        voices: HashMap<String, Option<String>>,
+    ) -> Self {
+        let (lines, said) = mpsc::channel::<(String, String)>();
+        let speaker_voices = voices.clone();
+
+        thread::spawn(move || {
+            let mut queue = SpeechQueue::default();
+            let mut warned = false;
+            loop {
+                if queue.is_empty() {
+                    // Until the game's over and the narrator's dropped
+                    match said.recv() {
+                        Ok((speaker, line)) => queue.push(&speaker, line),
+                        Err(_) => return,
+                    }
+                }
+                // Everything said while the last line was being read
+                for (speaker, line) in said.try_iter() {
+                    queue.push(&speaker, line);
+                }
+
+                let Some((speaker, line)) = queue.pop() else {
+                    continue;
+                };
+                let voice = speaker_voices.get(&speaker).cloned().flatten();
+                if let Err(err) = backend.speak(&line, voice.as_deref())
+                    && !warned
+                {
+                    tracing::warn!(error = %err, "Couldn't read the table talk aloud");
+                    warned = true;
+                }
+            }
+        });
+
+        Self { lines, voices }
+    }
+}
+
+impl EventSink for Narrator {
+    fn emit(&mut self, event: &GameEvent) {
+        let GameEvent::Dialogue { player, text } = event else {
+            return;
+        };
+        if !self.voices.contains_key(player) {
+            return;
+        }
+        let line = speakable(text);
+        if !line.is_empty() {
+            let _ = self.lines.send((player.clone(), line));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    /// Every line said, and the voice it was said in.
+    type Said = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+    /// Writes down what it's asked to say.
+    struct Recorder(Said);
+
+    impl SpeechBackend for Recorder {
+        fn speak(&mut self, text: &str, voice: Option<&str>) -> Result<(), RummyError> {
+            let voice = voice.map(str::to_string);
+            self.0.lock().unwrap().push((text.to_string(), voice));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_speakers_take_turns_and_keep_their_latest_lines() {
+        let mut queue = SpeechQueue::default();
+        for line in ["one", "two", "three"] {
+            queue.push("Lee", line.to_string());
+        }
+        queue.push("Ada", "hello".to_string());
+
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+        let said = |speaker: &str, line: &str| (speaker.to_string(), line.to_string());
+        assert_eq!(
+            order,
+            [
+                said("Lee", "two"),
+                said("Ada", "hello"),
+                said("Lee", "three")
+            ]
+        );
+        assert!(queue.is_empty());
+
+        assert_eq!(
+            speakable("I did not see that ending coming 😮 at all!"),
+            "I did not see that ending coming at all!"
+        );
+        assert_eq!(speakable("Tolstoy 📖✏️"), "Tolstoy");
+
+        let piper = ShellVoice::new("piper --model {voice}.onnx | aplay");
+        assert_eq!(
+            piper.command_line(Some("en_GB-alan")),
+            "piper --model 'en_GB-alan'.onnx | aplay"
+        );
+        assert_eq!(
+            piper.command_line(Some("it's")),
+            r"piper --model 'it'\''s'.onnx | aplay"
+        );
+    }
+
+    #[test]
+    fn test_the_narrator_reads_the_ai_players_in_their_voices() {
+        let said = Arc::new(Mutex::new(Vec::new()));
+        let voices = HashMap::from([
+            ("Lee".to_string(), Some("en-gb".to_string())),
+            ("Hemingbot".to_string(), None),
+        ]);
+        let mut narrator = Narrator::new(Box::new(Recorder(said.clone())), voices);
+
+        for (player, text) in [
+            ("Me", "What did everyone think?"),
+            ("Lee", "Cal is the real hero 😊"),
+            ("Hemingbot", "🎉"),
+            ("Hemingbot", "Short sentences."),
+        ] {
+            narrator.emit(&GameEvent::Dialogue {
+                player: player.to_string(),
+                text: text.to_string(),
+            });
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while said.lock().unwrap().len() < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            *said.lock().unwrap(),
+            [
+                (
+                    "Cal is the real hero".to_string(),
+                    Some("en-gb".to_string())
+                ),
+                ("Short sentences.".to_string(), None),
+            ]
+        );
+    }
+}
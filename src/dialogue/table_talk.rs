@@ -0,0 +1,134 @@
+//! Table talk. The AI players hear what just happened at the table along
+//! with the conversation, so now and then they bring a big hand or a sly
+//! discard into what they say about the book.
+
+use crate::error::RummyError;
+use crate::game::events::{ACTION_LOG_LEN, Action};
+use crate::i18n::Locale;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How much the game gets into the AI players' talk, from
+/// `table_talk_level` in the settings file or `--table-talk-level`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableTalkLevel {
+    /// Only the book, whatever happens at the table.
+    Off,
+    /// The latest few moves, to bring up when one stands out.
+    #[default]
+    Occasional,
+    /// Every move under the table, to react to before getting to the book.
+    Lively,
+}
+
+impl TableTalkLevel {
+    /// How many of the latest actions the players hear about.
+    fn recent(self) -> usize {
+        match self {
+            TableTalkLevel::Off => 0,
+            TableTalkLevel::Occasional => 3,
+            TableTalkLevel::Lively => ACTION_LOG_LEN,
+        }
+    }
+
+    /// What to add to a player's prompt about the `recent` actions at the
+    /// table, oldest first. Empty if there's nothing to say, or the table
+    /// would rather they didn't.
+    pub fn prompt(self, recent: &[Action]) -> String {
+        let recent = &recent[recent.len().saturating_sub(self.recent())..];
+        if recent.is_empty() {
+            return String::new();
+        }
+
+        // The prompt's in English whatever the table speaks
+        let mut prompt = String::from("\n\nHere is what just happened in the card game:");
+        for action in recent {
+            prompt.push_str(&format!(
+                "\n- {} {}",
+                action.player,
+                action.text_in(Locale::En)
+            ));
+            for card in &action.cards {
+                prompt.push_str(&format!(" {card}"));
+            }
+        }
+        prompt.push_str(match self {
+            TableTalkLevel::Lively => {
+                "\nReact to the game in what you say, then bring it back to the book."
+            }
+            _ => {
+                "\nIf something in the game stands out, you may tie it to the book, but mostly talk about the book."
+            }
+        });
+        prompt
+    }
+}
+
+impl FromStr for TableTalkLevel {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<TableTalkLevel, RummyError> {
+        match input.trim().to_lowercase().as_str() {
+            "off" => Ok(TableTalkLevel::Off),
+            "occasional" => Ok(TableTalkLevel::Occasional),
+            "lively" => Ok(TableTalkLevel::Lively),
+            _ => Err(RummyError::Config(format!(
+                "{input} isn't a table talk level, try off, occasional or lively"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for TableTalkLevel {
+    /// Writes the level as it's typed, e.g. `lively`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            TableTalkLevel::Off => "off",
+            TableTalkLevel::Occasional => "occasional",
+            TableTalkLevel::Lively => "lively",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Message;
+
+    fn action(player: &str, message: Message, cards: &str) -> Action {
+        Action {
+            player: player.to_string(),
+            message,
+            cards: cards
+                .split_whitespace()
+                .map(|card| card.parse().unwrap())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_the_players_hear_about_the_latest_moves() {
+        let actions: Vec<Action> = (0..5)
+            .map(|_| action("Lee", Message::Discarded, "2c"))
+            .chain([action("Ada", Message::HandPlayed { score: 40 }, "Qh Qs Qd")])
+            .collect();
+
+        let occasional = TableTalkLevel::Occasional.prompt(&actions);
+        assert_eq!(occasional.matches("\n- ").count(), 3);
+        assert!(occasional.contains("- Ada "));
+        assert!(occasional.contains("Qh Qs Qd"));
+        assert!(occasional.contains("mostly talk about the book"));
+
+        let lively = TableTalkLevel::Lively.prompt(&actions);
+        assert_eq!(lively.matches("\n- ").count(), ACTION_LOG_LEN);
+        assert!(lively.contains("React to the game"));
+
+        assert_eq!(TableTalkLevel::Off.prompt(&actions), "");
+        assert_eq!(TableTalkLevel::Lively.prompt(&[]), "");
+
+        assert_eq!("Lively".parse(), Ok(TableTalkLevel::Lively));
+        assert!("chatty".parse::<TableTalkLevel>().is_err());
+    }
+}
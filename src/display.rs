@@ -1,7 +1,31 @@
-use crate::analysis::{HandProbabilityAnalysis, RoundProbabilities};
+use crate::analysis::tournament::TournamentReport;
+use crate::analysis::{
+    CardValueAnalysis, DecisionAnalysis, HandProbabilityAnalysis, RoundProbabilities,
+    RoundStatistics,
+};
 use crate::card::{Card, Suite};
-use crate::game::Hand;
+use crate::game::events::Action;
+use crate::game::{Hand, PlayAction};
+use crate::i18n::Prompt;
+use crate::stats::{GameSummary, PlayerStats};
 use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+
+pub mod card_art;
+pub mod celebration;
+#[cfg(feature = "cli")]
+pub mod input;
+pub mod layout;
+pub mod prompt;
+pub mod terminal;
+pub mod theme;
+
+pub use layout::{display_width, pad_to_width, truncate_to_width};
+pub use theme::Theme;
+
+/// Widest a player name may be, in terminal columns. Names are shown in a 20
+/// column field, which leaves room for the `'s` in the layoff panel.
+pub const MAX_NAME_WIDTH: usize = 16;
 
 impl fmt::Display for Hand {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -13,23 +37,128 @@ impl fmt::Display for Hand {
     }
 }
 
-impl fmt::Display for Card {
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.player, self.message)?;
+        for card in &self.cards {
+            write!(f, " {card}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PlayerStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: won {} of {} games ({:.0}%), average meld {:.1}, favorite meld {}",
+            self.name,
+            self.wins,
+            self.games,
+            self.win_rate() * 100.0,
+            self.average_meld_score(),
+            self.favorite_meld().unwrap_or("none yet")
+        )
+    }
+}
+
+/// The standings as a table, best rated first.
+impl fmt::Display for TournamentReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} Rating  Played  Won  Drawn  Lost",
+            pad_to_width("Strategy", MAX_NAME_WIDTH)
+        )?;
+        for standing in &self.standings {
+            writeln!(
+                f,
+                "{} {:>6.0}  {:>6}  {:>3}  {:>5}  {:>4}",
+                pad_to_width(&standing.name, MAX_NAME_WIDTH),
+                standing.rating,
+                standing.played,
+                standing.won,
+                standing.drawn,
+                standing.lost
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GameSummary {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let name_string = self.name.to_string().map_err(|_| fmt::Error)?;
-        let suite_char = self.suite.to_char().map_err(|_| fmt::Error)?;
+        let won = Prompt::WonAfter {
+            player: self.winner.clone(),
+            rounds: self.rounds,
+            book: self.book.clone(),
+        };
+        writeln!(f, "{won}")?;
+        if let Some(best) = &self.biggest_hand {
+            let best = Prompt::HandOfTheGame {
+                player: best.player.clone(),
+                meld: best.meld.clone(),
+                score: best.score,
+            };
+            writeln!(f, "{best}")?;
+        }
 
-        let colored_output = match self.suite {
-            Suite::Hearts | Suite::Diamonds => {
-                format!("\x1B[31m{name_string}{suite_char}\x1B[0m") // Red
+        for player in &self.players {
+            writeln!(f)?;
+            let points = Prompt::Points {
+                name: pad_to_width(&player.name, MAX_NAME_WIDTH),
+                score: player.score,
+            };
+            write!(f, "{points}")?;
+            if player.layoff_wins > 0 {
+                let times = player.layoff_wins;
+                write!(f, "{}", Prompt::WonOnLayoffs { times })?;
             }
-            Suite::Spades | Suite::Clubs => {
-                // Light pastel brown using 256-color palette
-                format!("\x1B[38;5;180m{name_string}{suite_char}\x1B[0m")
+            writeln!(f)?;
+            if !player.melds.is_empty() {
+                let melds = player.melds.iter().map(|hit| hit.meld.clone()).collect();
+                writeln!(f, "  {}", Prompt::Melds { melds })?;
             }
-        };
+            for line in &player.excerpts {
+                writeln!(f, "  \"{line}\"")?;
+            }
+        }
+        if let Some(discussion) = &self.discussion {
+            writeln!(f, "\n{}", Prompt::WhatTheClubMadeOfIt)?;
+            for line in discussion.lines().filter(|line| !line.trim().is_empty()) {
+                writeln!(f, "  {}", line.trim())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders a card with its unicode suite symbol, in the theme's suit colors.
+pub fn pretty(card: &Card) -> String {
+    let suite_char = card.suite.to_char().unwrap_or('?');
+    let palette = theme::palette();
+    let color = match card.suite {
+        Suite::Hearts | Suite::Diamonds => &palette.red_suits,
+        Suite::Spades | Suite::Clubs => &palette.black_suits,
+    };
 
-        write!(f, "{colored_output}")
+    theme::paint(color, &format!("{}{suite_char}", card.name))
+}
+
+/// Renders every card in a hand with `pretty`, each preceded by a space.
+pub fn pretty_hand(hand: &Hand) -> String {
+    let mut pretty_cards = "".to_string();
+    for card in &hand.cards {
+        pretty_cards = format!("{pretty_cards} {}", pretty(card));
     }
+    pretty_cards
+}
+
+/// Cleans up a name as typed: NFC normalized, control characters dropped,
+/// trimmed and limited to `MAX_NAME_WIDTH` columns.
+pub fn normalize_name(input: &str) -> String {
+    let name: String = input.nfc().filter(|c| !c.is_control()).collect();
+    truncate_to_width(name.trim(), MAX_NAME_WIDTH)
 }
 
 impl fmt::Display for RoundProbabilities {
@@ -80,6 +209,20 @@ impl fmt::Display for RoundProbabilities {
     }
 }
 
+impl fmt::Display for CardValueAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: strategic value {:.2}, worth {:.2} kept, in a meld {:.0}% of the time, risk {:.2}",
+            pretty(&self.card),
+            self.strategic_value,
+            self.keep_expected_value,
+            self.net_value * 10.0,
+            self.risk_impact
+        )
+    }
+}
+
 impl fmt::Display for HandProbabilityAnalysis {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "=== Conditional Hand Analysis ===")?;
@@ -114,3 +257,149 @@ impl fmt::Display for HandProbabilityAnalysis {
         Ok(())
     }
 }
+
+impl fmt::Display for DecisionAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "=== Decision Analysis ===")?;
+        for (round, scores) in self.rounds.iter().enumerate() {
+            writeln!(
+                f,
+                "Round {round}: Conservative={:.2}, Aggressive={:.2}, Balanced={:.2}",
+                scores.conservative, scores.aggressive, scores.balanced
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Recommendations:")?;
+        writeln!(
+            f,
+            "  Conservative player: Stop after round {}",
+            self.conservative_choice
+        )?;
+        writeln!(
+            f,
+            "  Aggressive player: Stop after round {}",
+            self.aggressive_choice
+        )?;
+        writeln!(
+            f,
+            "  Balanced player: Stop after round {}",
+            self.balanced_choice
+        )
+    }
+}
+
+impl fmt::Display for RoundStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(details) = &self.probabilities.analysis_details {
+            writeln!(f, "{details}")?;
+        }
+        writeln!(f, "{}", self.probabilities)?;
+
+        writeln!(f, "=== Strategic Card Analysis (Future-Based) ===")?;
+        writeln!(
+            f,
+            "Cards ranked by strategic value (lowest = best to discard):"
+        )?;
+        for (i, value) in self.card_values.iter().enumerate() {
+            let recommendation = if i == 0 { " ← DISCARD" } else { "" };
+            writeln!(f, "  {value}{recommendation}")?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "=== Play Decision ===")?;
+        let recommendation = if self.play_decision.should_play {
+            "PLAY HAND"
+        } else {
+            "DRAW/CONTINUE"
+        };
+        writeln!(f, "Recommendation: {recommendation}")?;
+        writeln!(
+            f,
+            "Confidence: {:.1}%",
+            self.play_decision.confidence * 100.0
+        )?;
+        writeln!(f, "Reasoning: {}", self.play_decision.reasoning)?;
+        if !self.play_decision.alternative_strategies.is_empty() {
+            writeln!(f, "Alternative strategies:")?;
+            for strategy in &self.play_decision.alternative_strategies {
+                writeln!(f, "  - {strategy}")?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "=== Autoplay Decisions ===")?;
+        for (player_type, decision) in &self.autoplay {
+            writeln!(f)?;
+            writeln!(f, "{player_type:?} Player:")?;
+            writeln!(f, "  Decision: {:?}", decision.action)?;
+            writeln!(f, "  Confidence: {:.1}%", decision.confidence * 100.0)?;
+            writeln!(f, "  Expected Score: {:.1}", decision.expected_score)?;
+            if let Some(card) = decision.card_to_discard {
+                writeln!(f, "  Card to discard: {}", pretty(&card))?;
+            }
+            let plan = match decision.action {
+                PlayAction::Play => "Will play current hand",
+                PlayAction::Draw => "Will draw one card and discard worst card",
+                PlayAction::Retrieve => "Will retrieve the discard and discard worst card",
+            };
+            writeln!(f, "  → {plan}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("  Ame\u{301}lie\n"), "Amélie");
+        assert_eq!(normalize_name("Zoë 📚"), "Zoë 📚");
+        assert_eq!(normalize_name("村上春樹"), "村上春樹");
+
+        // Eight CJK characters are exactly 16 columns wide, a ninth is too many
+        assert_eq!(normalize_name("一二三四五六七八"), "一二三四五六七八");
+        let long = normalize_name("一二三四五六七八九");
+        assert_eq!(long, "一二三四五六七…");
+        assert!(display_width(&long) <= MAX_NAME_WIDTH);
+
+        let emoji = normalize_name("🐙🐙🐙🐙🐙🐙🐙🐙🐙🐙");
+        assert!(emoji.ends_with('…'));
+        assert!(display_width(&emoji) <= MAX_NAME_WIDTH);
+    }
+
+    #[test]
+    fn test_round_statistics_report_what_was_returned() {
+        use crate::analysis::{AnalysisConfig, KnownInformation, Node, Search, evaluate_hand};
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let cards: Vec<Card> = ["5h", "5c", "5d", "9s", "9h", "2c"]
+            .iter()
+            .map(|card| card.parse().unwrap())
+            .collect();
+        let known = KnownInformation {
+            hand: cards.clone(),
+            ..KnownInformation::default()
+        };
+        let mut node = Node::for_hand(Hand { cards }, &known);
+        let search = Search::new(AnalysisConfig::exact());
+        evaluate_hand(&mut node, &search, &mut StdRng::seed_from_u64(1)).unwrap();
+
+        let stats = node.advanced_round_statistics();
+        let details = stats.probabilities.analysis_details.as_ref().unwrap();
+        assert_eq!(
+            details.rounds.len(),
+            stats.probabilities.round_probabilities.len()
+        );
+        assert_eq!(stats.card_values.len(), 6);
+        assert_eq!(stats.autoplay.len(), 4);
+
+        let report = stats.to_string();
+        assert!(report.starts_with("=== Decision Analysis ==="));
+        assert!(report.contains("=== Autoplay Decisions ==="));
+    }
+}
@@ -0,0 +1,127 @@
+//! Hands drawn as rows of little cards, rank and suit in the corners, for
+//! players who'd rather see a hand than read one:
+//!
+//! ```text
+//! ┌─────┐ ┌─────┐
+//! │10   │ │K    │
+//! │  ♡  │ │  ♤  │
+//! │   10│ │    K│
+//! └─────┘ └─────┘
+//! ```
+//!
+//! Chosen with `cards = "boxes"` in a theme file.
+
+use crate::card::{Card, Suite};
+use crate::display::layout::pad_to_width;
+use crate::display::theme::{Palette, paint, palette};
+use serde::{Deserialize, Serialize};
+
+/// Columns a card takes up, border and all.
+pub const CARD_WIDTH: usize = 7;
+
+/// Lines a card takes up.
+pub const CARD_HEIGHT: usize = 5;
+
+/// How hands are drawn on the table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CardStyle {
+    /// A card to a few columns, `7♡`.
+    #[default]
+    Compact,
+    /// A card to a box, `CARD_WIDTH` by `CARD_HEIGHT`.
+    Boxes,
+}
+
+/// `card` as a box, top line first, its rank and suit in the theme's suit
+/// colors.
+pub fn card_lines(card: &Card) -> [String; CARD_HEIGHT] {
+    card_lines_in(card, &palette())
+}
+
+fn card_lines_in(card: &Card, palette: &Palette) -> [String; CARD_HEIGHT] {
+    let color = match card.suite {
+        Suite::Hearts | Suite::Diamonds => &palette.red_suits,
+        Suite::Spades | Suite::Clubs => &palette.black_suits,
+    };
+    let inner = CARD_WIDTH - 2;
+    let rank = card.name.to_string();
+    let suite = card.suite.to_char().unwrap_or('?').to_string();
+    let right = format!("{rank:>inner$}");
+
+    [
+        format!("┌{}┐", "─".repeat(inner)),
+        format!("│{}│", paint(color, &pad_to_width(&rank, inner))),
+        format!("│{}│", paint(color, &format!("{suite:^inner$}"))),
+        format!("│{}│", paint(color, &right)),
+        format!("└{}┘", "─".repeat(inner)),
+    ]
+}
+
+/// `cards` side by side, a space between them, in as many rows of cards as
+/// it takes to fit `cols` columns. A row of cards is `CARD_HEIGHT` lines.
+pub fn hand_art(cards: &[Card], cols: usize) -> Vec<String> {
+    hand_art_in(cards, cols, &palette())
+}
+
+fn hand_art_in(cards: &[Card], cols: usize, palette: &Palette) -> Vec<String> {
+    let per_row = ((cols + 1) / (CARD_WIDTH + 1)).max(1);
+
+    cards
+        .chunks(per_row)
+        .flat_map(|row| {
+            let boxes: Vec<[String; CARD_HEIGHT]> = row
+                .iter()
+                .map(|card| card_lines_in(card, palette))
+                .collect();
+            (0..CARD_HEIGHT).map(move |line| {
+                boxes
+                    .iter()
+                    .map(|card| card[line].as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::display_width;
+    use crate::display::theme::{ColorSupport, Theme};
+
+    #[test]
+    fn test_hands_are_drawn_as_boxes() {
+        let plain = Theme::default().palette(ColorSupport::None);
+        let cards: Vec<Card> = ["10h", "Ks", "2d"]
+            .iter()
+            .map(|card| card.parse().unwrap())
+            .collect();
+
+        assert_eq!(
+            card_lines_in(&cards[0], &plain),
+            ["┌─────┐", "│10   │", "│  ♡  │", "│   10│", "└─────┘"]
+        );
+
+        let art = hand_art_in(&cards, 80, &plain);
+        assert_eq!(art.len(), CARD_HEIGHT);
+        assert_eq!(art[1], "│10   │ │K    │ │2    │");
+        assert!(
+            art.iter()
+                .all(|line| display_width(line) == 3 * CARD_WIDTH + 2)
+        );
+
+        // Two to a row is all that fits in 16 columns
+        let narrow = hand_art_in(&cards, 16, &plain);
+        assert_eq!(narrow.len(), 2 * CARD_HEIGHT);
+        assert_eq!(narrow[CARD_HEIGHT + 3], "│    2│");
+
+        // Only the rank and suit are colored, not the border
+        let colored = Theme::default().palette(ColorSupport::Ansi256);
+        assert_eq!(
+            card_lines_in(&cards[1], &colored)[1],
+            "│\x1B[38;5;180mK    \x1B[0m│"
+        );
+    }
+}
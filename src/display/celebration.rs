@@ -0,0 +1,359 @@
+//! What the winner of a local game sees before the final scores: an angel
+//! gliding across the screen, fireworks, or just their name.
+//!
+//! A `Celebration` only works out its frames. `play` draws them, and a line
+//! typed while it does skips the rest, so nobody has to sit through it
+//! twice. Network players and the server never celebrate, the client just
+//! says who won.
+
+use crate::display::layout::{centered, display_width};
+use crate::error::RummyError;
+use crate::i18n::Prompt;
+use rand::Rng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::Write as _;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// One screen of a celebration, and how long it stays up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub screen: String,
+    pub hold: Duration,
+}
+
+/// A way to celebrate a win.
+pub trait Celebration: Send + Sync {
+    /// Every frame for `winner`, whose name is drawn in `color_code`, on a
+    /// terminal `cols` wide. Anything left to chance comes from `rng`, the
+    /// game's effects stream, so a seeded game celebrates the same way.
+    fn frames(&self, winner: &str, color_code: &str, cols: usize, rng: &mut StdRng) -> Vec<Frame>;
+}
+
+/// The celebrations there are, chosen with `celebration` in the settings
+/// file or `--celebration`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CelebrationKind {
+    #[default]
+    Angel,
+    Fireworks,
+    Minimal,
+    /// One of the others, picked afresh every game.
+    Random,
+}
+
+impl CelebrationKind {
+    const BUILT_IN: [CelebrationKind; 3] = [
+        CelebrationKind::Angel,
+        CelebrationKind::Fireworks,
+        CelebrationKind::Minimal,
+    ];
+
+    /// The celebration to play, `Random` picking one from `rng`.
+    pub fn pick(self, rng: &mut StdRng) -> Box<dyn Celebration> {
+        match self {
+            CelebrationKind::Angel => Box::new(Angel),
+            CelebrationKind::Fireworks => Box::new(Fireworks),
+            CelebrationKind::Minimal => Box::new(Minimal),
+            CelebrationKind::Random => {
+                Self::BUILT_IN[rng.random_range(0..Self::BUILT_IN.len())].pick(rng)
+            }
+        }
+    }
+}
+
+impl FromStr for CelebrationKind {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<CelebrationKind, RummyError> {
+        match input.trim().to_lowercase().as_str() {
+            "angel" => Ok(CelebrationKind::Angel),
+            "fireworks" => Ok(CelebrationKind::Fireworks),
+            "minimal" => Ok(CelebrationKind::Minimal),
+            "random" => Ok(CelebrationKind::Random),
+            _ => Err(RummyError::Config(format!(
+                "{input} isn't a celebration, try angel, fireworks, minimal or random"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for CelebrationKind {
+    /// Writes the celebration as it's typed, e.g. `fireworks`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            CelebrationKind::Angel => "angel",
+            CelebrationKind::Fireworks => "fireworks",
+            CelebrationKind::Minimal => "minimal",
+            CelebrationKind::Random => "random",
+        })
+    }
+}
+
+const ANGEL: [&str; 12] = [
+    "               ______",
+    "              '-._   ```\"\"\"---.._",
+    "           ,-----.:___           `\\  ,;;;,",
+    "            '-.._     ```\"\"\"--.._  |,%%%%%%              _",
+    "            ,    '.              `\\;;;;  -\\      _    _.'/\\",
+    "          .' `-.__ \\            ,;;;;\" .__{=====/_)==:_  ||",
+    "     ,===/        ```\";,,,,,,,;;;;;'`-./.____,'/ /     '.\\/",
+    "    '---/              ';;;;;;;;'      `--.._.' /",
+    "   ,===/                          '-.        `\\/",
+    "  '---/                            ,'`.        |",
+    "     ;                        __.-'    \\     ,'",
+    "jgs  \\______,,.....------'''``          `---`",
+];
+
+const RAINBOW: [&str; 6] = ["31", "33", "32", "36", "34", "35"];
+
+/// The angel glides across the screen trailing sparkles, then settles in
+/// the middle under the winner's name and a shower of confetti.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Angel;
+
+impl Celebration for Angel {
+    fn frames(&self, winner: &str, color_code: &str, cols: usize, rng: &mut StdRng) -> Vec<Frame> {
+        let angel_width = ANGEL
+            .iter()
+            .map(|line| display_width(line))
+            .max()
+            .unwrap_or(0);
+        let mut frames = Vec::new();
+
+        // Phase 1: the angel glides from left to right
+        let banner = Prompt::VictoryBanner.to_string();
+        for position in (0..=(cols.saturating_sub(angel_width))).step_by(2) {
+            let mut screen = String::new();
+
+            let pad = (cols / 2).saturating_sub(display_width(&banner) / 2);
+            let _ = write!(screen, "{:pad$}", "");
+            for (i, ch) in banner.chars().enumerate() {
+                let _ = write!(screen, "\x1B[1;{}m{ch}\x1B[0m", RAINBOW[i % RAINBOW.len()]);
+            }
+            let _ = writeln!(screen, "\n");
+
+            let wins = Prompt::Wins {
+                player: winner.to_string(),
+            };
+            let _ = writeln!(
+                screen,
+                "\x1B[1;{color_code}m{}\x1B[0m\n",
+                centered(&wins.to_string(), cols)
+            );
+
+            // Lighter towards the feet
+            for (i, line) in ANGEL.iter().enumerate() {
+                let color = if i < ANGEL.len() / 2 { "229" } else { "231" };
+                let _ = writeln!(screen, "{:position$}\x1B[38;5;{color}m{line}\x1B[0m", "");
+            }
+
+            // Trailing sparkles
+            let sparkles = ["✨", "⭐", "✦", "✧", "⋆"];
+            for (i, sparkle) in sparkles.iter().enumerate() {
+                let sparkle_line = 5 + i;
+                if position > (i + 1) * 8 && sparkle_line < ANGEL.len() {
+                    let sparkle_pos = position - (i + 1) * 8;
+                    let _ = write!(screen, "\x1B[{}A", ANGEL.len() - sparkle_line);
+                    let _ = writeln!(screen, "{:sparkle_pos$}\x1B[38;5;226m{sparkle}\x1B[0m", "");
+                    let _ = write!(screen, "\x1B[{}B", ANGEL.len() - sparkle_line - 1);
+                }
+            }
+
+            frames.push(Frame {
+                screen,
+                hold: Duration::from_millis(100),
+            });
+        }
+
+        // Phase 2: confetti, and the winner's name pulsing
+        let confetti = ["🎉", "🎊", "🌟", "✨", "🎈"];
+        for frame in 0..3 {
+            let mut screen = String::new();
+            for _ in 0..10 {
+                let x = rng.random_range(0..cols.max(1));
+                let y = rng.random_range(0..5);
+                let piece = confetti[rng.random_range(0..confetti.len())];
+                let _ = write!(screen, "\x1B[{};{}H{piece}", y + 1, x + 1);
+            }
+            let _ = writeln!(screen, "\n\n\n\n\n");
+
+            let size = if frame % 2 == 0 { "1" } else { "1;5" };
+            let wins = Prompt::WinsTheGame {
+                player: winner.to_uppercase(),
+            };
+            let _ = writeln!(
+                screen,
+                "\x1B[{size};{color_code}m{}\x1B[0m\n",
+                centered(&wins.to_string(), cols)
+            );
+
+            let center = cols.saturating_sub(angel_width) / 2;
+            for line in ANGEL {
+                let _ = writeln!(screen, "{:center$}\x1B[38;5;229m{line}\x1B[0m", "");
+            }
+
+            frames.push(Frame {
+                screen,
+                hold: Duration::from_secs(1),
+            });
+        }
+
+        frames
+    }
+}
+
+/// Rockets burst at random over the top of the screen, each in a colour of
+/// its own, above the winner's name.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fireworks;
+
+/// Rockets going off at once, and how far their sparks fly.
+const ROCKETS: usize = 4;
+const BURST_RADIUS: usize = 5;
+/// Lines the bursts have to go off in, above the winner's name.
+const SKY_LINES: usize = 14;
+
+impl Celebration for Fireworks {
+    fn frames(&self, winner: &str, color_code: &str, cols: usize, rng: &mut StdRng) -> Vec<Frame> {
+        let wins = Prompt::WinsTheGame {
+            player: winner.to_uppercase(),
+        };
+        let name_line = format!(
+            "\x1B[{};1H\x1B[1;{color_code}m{}\x1B[0m\n",
+            SKY_LINES + 2,
+            centered(&wins.to_string(), cols)
+        );
+        let mut frames = Vec::new();
+
+        for _volley in 0..3 {
+            let rockets: Vec<(usize, usize, &str)> = (0..ROCKETS)
+                .map(|_| {
+                    let col = rng.random_range(
+                        BURST_RADIUS * 2..=cols.max(BURST_RADIUS * 4) - BURST_RADIUS * 2,
+                    );
+                    let row = rng.random_range(BURST_RADIUS + 1..=SKY_LINES - BURST_RADIUS);
+                    (col, row, RAINBOW[rng.random_range(0..RAINBOW.len())])
+                })
+                .collect();
+
+            for radius in 0..=BURST_RADIUS {
+                let mut screen = String::new();
+                for &(col, row, color) in &rockets {
+                    let spark = if radius == BURST_RADIUS { "·" } else { "*" };
+                    for (dx, dy) in [
+                        (-2, 0),
+                        (2, 0),
+                        (0, -1),
+                        (0, 1),
+                        (-2, -1),
+                        (2, -1),
+                        (-2, 1),
+                        (2, 1),
+                    ] {
+                        let x = col as isize + dx * radius as isize;
+                        let y = row as isize + dy * radius as isize / 2;
+                        if x >= 1 && y >= 1 {
+                            let _ = write!(screen, "\x1B[{y};{x}H\x1B[1;{color}m{spark}\x1B[0m");
+                        }
+                    }
+                }
+                screen.push_str(&name_line);
+                frames.push(Frame {
+                    screen,
+                    hold: Duration::from_millis(120),
+                });
+            }
+        }
+
+        frames.push(Frame {
+            screen: name_line,
+            hold: Duration::from_secs(1),
+        });
+        frames
+    }
+}
+
+/// Just the winner's name, for a table that would rather get on with it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Minimal;
+
+impl Celebration for Minimal {
+    fn frames(&self, winner: &str, color_code: &str, cols: usize, _: &mut StdRng) -> Vec<Frame> {
+        let wins = Prompt::Wins {
+            player: winner.to_string(),
+        };
+        vec![Frame {
+            screen: format!(
+                "\n\n\x1B[1;{color_code}m{}\x1B[0m\n",
+                centered(&wins.to_string(), cols)
+            ),
+            hold: Duration::from_secs(1),
+        }]
+    }
+}
+
+/// Draws `frames` one after the other, each for as long as it holds, until
+/// they run out or a line's typed. Returns whether it was skipped.
+#[cfg(feature = "cli")]
+pub async fn play(frames: &[Frame], input: &crate::display::input::Input) -> bool {
+    use crate::display::terminal::clear_screen;
+    use std::io::{self, Write};
+
+    for frame in frames {
+        clear_screen();
+        print!("{}", frame.screen);
+        let _ = io::stdout().flush();
+        if input.line_within(frame.hold).await.is_some() {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_every_celebration_names_the_winner() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for kind in CelebrationKind::BUILT_IN {
+            let frames = kind.pick(&mut rng).frames("Ada", "32", 100, &mut rng);
+            assert!(!frames.is_empty(), "{kind}");
+            let last = &frames.last().unwrap().screen;
+            assert!(last.contains("Ada") || last.contains("ADA"), "{kind}");
+        }
+
+        // Narrower than the angel, it still fits
+        assert!(!Angel.frames("Ada", "32", 20, &mut rng).is_empty());
+        assert!(!Fireworks.frames("Ada", "32", 20, &mut rng).is_empty());
+
+        assert_eq!("Fireworks".parse(), Ok(CelebrationKind::Fireworks));
+        assert!("confetti".parse::<CelebrationKind>().is_err());
+    }
+
+    #[cfg(feature = "cli")]
+    #[tokio::test]
+    async fn test_a_line_typed_skips_the_rest() {
+        use crate::display::input::Input;
+        use tokio::sync::mpsc;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let input = Input::new(rx);
+        let frames = vec![
+            Frame {
+                screen: String::new(),
+                hold: Duration::from_secs(60),
+            };
+            3
+        ];
+
+        tx.send(String::new()).unwrap();
+        assert!(play(&frames, &input).await);
+    }
+}
@@ -0,0 +1,90 @@
+//! Lines typed at the terminal.
+//!
+//! Reading stdin blocks, so the lines are read on a thread of their own and
+//! handed over a channel. The table can keep talking and redrawing while a
+//! player makes up their mind, and nothing they type meanwhile is lost.
+
+use std::io::BufRead;
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::time::Duration;
+
+/// The lines typed at the terminal, read from the first time one is asked
+/// for. Only one should be reading stdin at a time.
+#[derive(Debug, Default)]
+pub struct Input {
+    lines: OnceLock<Mutex<UnboundedReceiver<String>>>,
+}
+
+impl Input {
+    /// Lines from `lines` rather than stdin.
+    pub fn new(lines: UnboundedReceiver<String>) -> Self {
+        Self {
+            lines: OnceLock::from(Mutex::new(lines)),
+        }
+    }
+
+    fn lines(&self) -> &Mutex<UnboundedReceiver<String>> {
+        self.lines.get_or_init(|| Mutex::new(read_stdin_lines()))
+    }
+
+    /// The next line typed, without its line ending, waiting for it. `None`
+    /// once stdin's closed. A line typed as the wait is given up on is kept
+    /// for the next call.
+    pub async fn next_line(&self) -> Option<String> {
+        self.lines().lock().await.recv().await
+    }
+
+    /// The next line typed, or an empty one once stdin's closed, the way
+    /// `read_line` leaves it.
+    pub async fn line(&self) -> String {
+        self.next_line().await.unwrap_or_default()
+    }
+
+    /// The next line typed, if one is within `timeout`.
+    pub async fn line_within(&self, timeout: Duration) -> Option<String> {
+        tokio::time::timeout(timeout, self.line()).await.ok()
+    }
+}
+
+fn read_stdin_lines() -> UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lines_are_handed_over_in_order() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let input = Input::new(rx);
+        tx.send("d".to_string()).unwrap();
+        tx.send("9h".to_string()).unwrap();
+
+        assert_eq!(
+            input.line_within(Duration::from_secs(5)).await.as_deref(),
+            Some("d")
+        );
+        assert_eq!(input.line().await, "9h");
+
+        // Closed like read_line at the end of stdin
+        drop(tx);
+        assert_eq!(input.next_line().await, None);
+        assert_eq!(input.line().await, "");
+    }
+}
@@ -0,0 +1,114 @@
+//! Laying text out in terminal columns rather than bytes or characters, so
+//! names with accents, emoji and CJK characters line up with everyone else's.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Number of terminal columns `text` takes up. Emoji and CJK characters are two wide.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Pads `text` with spaces so it fills `width` columns.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let padding = " ".repeat(width.saturating_sub(display_width(text)));
+    format!("{text}{padding}")
+}
+
+/// Pads `text` on both sides so it sits in the middle of `width` columns,
+/// a column left of it if it can't be exactly.
+pub fn centered(text: &str, width: usize) -> String {
+    let spare = width.saturating_sub(display_width(text));
+    let left = " ".repeat(spare / 2);
+    let right = " ".repeat(spare - spare / 2);
+    format!("{left}{text}{right}")
+}
+
+/// Cuts `text` down to at most `width` columns, ending in `…` if anything was cut.
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        used += char_width;
+    }
+
+    format!("{truncated}…")
+}
+
+/// Wraps `text` at spaces into lines of at most `line_width` columns, each
+/// after the first indented by `indent`. A word too wide for a line, like a
+/// sentence of CJK with no spaces in it, is broken wherever it runs out of room.
+pub fn wrap_text(text: &str, line_width: usize, indent: usize) -> String {
+    let line_width = line_width.max(1);
+    let mut lines = Vec::new();
+    let mut current_line = String::new();
+    let mut current_width = 0;
+
+    for word in text.split_whitespace() {
+        let word_width = display_width(word);
+        if current_width > 0 && current_width + word_width + 1 > line_width {
+            lines.push(std::mem::take(&mut current_line));
+            current_width = 0;
+        }
+
+        if current_width > 0 {
+            current_line.push(' ');
+            current_width += 1;
+        }
+        for c in word.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if current_width > 0 && current_width + char_width > line_width {
+                lines.push(std::mem::take(&mut current_line));
+                current_width = 0;
+            }
+            current_line.push(c);
+            current_width += char_width;
+        }
+    }
+
+    if !current_line.is_empty() {
+        lines.push(current_line);
+    }
+
+    let indent_str = " ".repeat(indent);
+    lines.join(&format!("\n{indent_str}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pad_to_width() {
+        assert_eq!(pad_to_width("Ada", 6), "Ada   ");
+        assert_eq!(pad_to_width("李白", 6), "李白  ");
+        assert_eq!(pad_to_width("Zoë 🎉", 8), "Zoë 🎉  ");
+        assert_eq!(display_width(&pad_to_width("村上春樹", 20)), 20);
+        assert_eq!(pad_to_width("Bartholomew", 4), "Bartholomew");
+
+        assert_eq!(centered("李白", 9), "  李白   ");
+        assert_eq!(centered("Bartholomew", 4), "Bartholomew");
+    }
+
+    #[test]
+    fn test_wrapping_counts_columns() {
+        // Accents take no room of their own, so this fits on one line
+        assert_eq!(wrap_text("Zoë café naïve", 14, 0), "Zoë café naïve");
+        assert_eq!(
+            wrap_text("Zoë café naïve 🎉", 16, 2),
+            "Zoë café naïve\n  🎉"
+        );
+
+        let wrapped = wrap_text("吾輩は猫である。名前はまだ無い。", 10, 0);
+        assert_eq!(wrapped, "吾輩は猫で\nある。名前\nはまだ無い\n。");
+        assert!(wrapped.lines().all(|line| display_width(line) <= 10));
+    }
+}
@@ -0,0 +1,158 @@
+//! Questions put to the player until they're answered properly.
+//!
+//! A bad answer is said back with what's wrong with it and the question asked
+//! again, however many tries it takes. Nothing typed ends the game, and
+//! nothing recurses on each try.
+
+use crate::card::Card;
+use crate::display::terminal::highlighted;
+use crate::error::RummyError;
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+/// Cards typed in, like `7h Jc` or `2s,3s`. None if nothing's typed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cards(pub Vec<Card>);
+
+impl FromStr for Cards {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<Cards, RummyError> {
+        input
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|card| !card.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Cards)
+    }
+}
+
+/// `line` read as a `T` that `valid` accepts, or what's wrong with it, to be
+/// said back to the player.
+pub fn answer<T: FromStr>(line: &str, valid: impl Fn(&T) -> Result<(), String>) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    let value = line.trim().parse::<T>().map_err(|err| err.to_string())?;
+    valid(&value)?;
+    Ok(value)
+}
+
+/// Asks `question` at the terminal until the answer is a `T` that `valid`
+/// accepts. Fails only if stdin closes first.
+pub fn prompt_until<T: FromStr>(
+    question: &str,
+    valid: impl Fn(&T) -> Result<(), String>,
+) -> Result<T, RummyError>
+where
+    T::Err: Display,
+{
+    ask(
+        &mut io::stdin().lock(),
+        &mut io::stdout(),
+        question,
+        None,
+        valid,
+    )
+}
+
+/// Like [`prompt_until`], with a blank answer taken as `default`.
+pub fn prompt_or<T: FromStr>(
+    question: &str,
+    default: T,
+    valid: impl Fn(&T) -> Result<(), String>,
+) -> Result<T, RummyError>
+where
+    T::Err: Display,
+{
+    ask(
+        &mut io::stdin().lock(),
+        &mut io::stdout(),
+        question,
+        Some(default),
+        valid,
+    )
+}
+
+fn ask<T: FromStr>(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    mut default: Option<T>,
+    valid: impl Fn(&T) -> Result<(), String>,
+) -> Result<T, RummyError>
+where
+    T::Err: Display,
+{
+    let io_error = |err: io::Error| RummyError::Io(err.to_string());
+    loop {
+        writeln!(output, "{}", highlighted(question)).map_err(io_error)?;
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(io_error)? == 0 {
+            return Err(RummyError::Io(format!(
+                "Input closed before {question:?} was answered"
+            )));
+        }
+        if line.trim().is_empty()
+            && let Some(default) = default.take()
+        {
+            return Ok(default);
+        }
+
+        match answer(&line, &valid) {
+            Ok(value) => return Ok(value),
+            Err(why) => writeln!(output, "{why}").map_err(io_error)?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at_least_two(n: &usize) -> Result<(), String> {
+        if *n >= 2 {
+            Ok(())
+        } else {
+            Err("A game needs at least 2 players".to_string())
+        }
+    }
+
+    #[test]
+    fn test_a_bad_answer_asks_again() {
+        let mut input = "lots\n1\n3\n".as_bytes();
+        let mut output = Vec::new();
+        let players = ask(&mut input, &mut output, "Players?", None, at_least_two);
+        assert_eq!(players, Ok(3));
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("Players?").count(), 3);
+        assert!(output.contains("invalid digit"));
+        assert!(output.contains("A game needs at least 2 players"));
+
+        // A blank answer is the default, if there is one
+        let mut input = "\n".as_bytes();
+        let players = ask(
+            &mut input,
+            &mut io::sink(),
+            "Players?",
+            Some(2),
+            at_least_two,
+        );
+        assert_eq!(players, Ok(2));
+
+        // Running out of input doesn't ask forever
+        let mut input = "1\n".as_bytes();
+        let players = ask(&mut input, &mut io::sink(), "Players?", None, at_least_two);
+        assert!(matches!(players, Err(RummyError::Io(_))));
+    }
+
+    #[test]
+    fn test_cards_are_read_from_a_line() {
+        let cards = answer::<Cards>(" 7h, Jc ", |_| Ok(())).unwrap();
+        assert_eq!(cards.0.len(), 2);
+        assert_eq!(answer::<Cards>("", |_| Ok(())), Ok(Cards::default()));
+        assert!(answer::<Cards>("7h Zz", |_| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,1065 @@
+//! The full-screen table UI, shared by the local game and network clients.
+//!
+//! The table is laid out for the terminal's width every time it's drawn, and
+//! a `Screen` draws it again straight away when the terminal is resized.
+
+use crate::analysis::HandOdds;
+use crate::bank::Bank;
+use crate::card::Card;
+use crate::display::card_art::{CardStyle, hand_art};
+use crate::display::layout::{pad_to_width, wrap_text};
+use crate::display::pretty;
+use crate::display::theme::{paint, palette};
+use crate::engine::RoundSummary;
+use crate::game::events::{Action, Recap};
+use crate::game::sheet::ScoreSheet;
+use crate::i18n::Prompt;
+use crate::scoring::{MeldMatch, meld_name};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "cli")]
+use terminal_size::{Width, terminal_size};
+
+/// Lines of dialogue shown above the table.
+pub const DIALOGUE_LINES: usize = 11;
+
+/// Actions shown below the prompt.
+pub const ACTION_LINES: usize = 6;
+
+/// Lines of the discard pile shown at once, and how many cards to a line.
+pub const DISCARD_LINES: usize = 4;
+const DISCARDS_PER_LINE: usize = 13;
+
+/// What PgUp and PgDn send. The terminal only hands them over with the rest
+/// of the line, once Enter is pressed.
+const PAGE_UP: &str = "\x1B[5~";
+const PAGE_DOWN: &str = "\x1B[6~";
+
+/// What Esc sends, for the pause menu, also only once Enter is pressed.
+const ESCAPE: &str = "\x1B";
+
+/// Width of the padded name field in the dialogue, hand and action panels.
+pub const NAME_COLS: usize = 20;
+
+/// Rounds the score sheet shows, the latest ones.
+pub const SHEET_ROUNDS: usize = 8;
+
+/// Width of the score sheet's bars, the leader's filling it.
+const SHEET_BAR_COLS: usize = 20;
+
+/// Columns to lay out for when the terminal won't say how wide it is.
+const DEFAULT_COLS: usize = 100;
+
+/// Dialogue is wrapped to fit the terminal, but never narrower than this or
+/// wider than is comfortable to read.
+const MIN_DIALOGUE_COLS: usize = 20;
+const MAX_DIALOGUE_COLS: usize = 75;
+
+/// How wide the terminal is right now.
+#[cfg(feature = "cli")]
+pub fn terminal_cols() -> usize {
+    terminal_size()
+        .map(|(Width(w), _)| w as usize)
+        .unwrap_or(DEFAULT_COLS)
+}
+
+/// Without the terminal to ask, as wide as a terminal usually is.
+#[cfg(not(feature = "cli"))]
+pub fn terminal_cols() -> usize {
+    DEFAULT_COLS
+}
+
+#[derive(Clone, Debug)]
+pub struct ColoredName {
+    pub name: String,
+    pub color_code: String,
+}
+
+impl ColoredName {
+    /// Colored by the theme for whoever sits at `player_index`.
+    pub fn new(name: String, player_index: usize) -> Self {
+        Self {
+            name,
+            color_code: palette().player(player_index).to_string(),
+        }
+    }
+
+    pub fn colored(&self) -> String {
+        paint(&self.color_code, &self.name)
+    }
+
+    pub fn colored_padded(&self, width: usize) -> String {
+        // Pad the visible name, the ANSI codes take up no columns
+        paint(&self.color_code, &pad_to_width(&self.name, width))
+    }
+}
+
+/// Something said at the table, wrapped when it's drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Said {
+    pub speaker: String,
+    pub text: String,
+}
+
+/// Everything one frame of the table shows. Frames own what they show, so
+/// the last one can be drawn again when the terminal is resized.
+#[derive(Clone, Debug, Default)]
+pub struct TableFrame {
+    pub book: String,
+    /// The most recent table talk, oldest first. Only the last lines fit.
+    pub dialogue: Vec<Said>,
+    pub top_discard: Option<Card>,
+    pub player_name: String,
+    pub hand: Vec<Card>,
+    /// During a layoff round: whose cards make up the meld, and the meld.
+    pub layoff: Option<(String, Vec<Card>)>,
+    /// What the last player to move did, shown just above the prompt.
+    pub recap: Option<String>,
+    /// `None` while the table is only being shown, between turns.
+    pub prompt: Option<String>,
+    /// Most recent first.
+    pub actions: Vec<String>,
+    pub scores: Vec<(String, usize)>,
+    /// Everyone's score round by round, from `score_sheet_panel`. Empty
+    /// until a round's finished, and `scores` is shown instead.
+    pub sheet: Vec<String>,
+    /// The pot and everyone's chips, from `chips_panel`. Empty unless the
+    /// table's playing for chips.
+    pub chips: Vec<String>,
+    pub message: Option<String>,
+    /// How the last round was stolen, from `layoff_chain`. Empty unless a
+    /// layoff won it.
+    pub reveal: Vec<String>,
+    /// What the player's cards are worth, from `odds_panel`. Empty unless
+    /// they've asked to see it.
+    pub odds: Vec<String>,
+    /// The discard pile, from `discard_panel`. Empty unless they've asked to
+    /// see it.
+    pub discards: Vec<String>,
+    pub colors: Vec<ColoredName>,
+    /// How many pages back the dialogue and actions are scrolled, 0 for the
+    /// latest.
+    pub scroll: usize,
+}
+
+impl TableFrame {
+    /// The furthest back there's anything to scroll to.
+    fn max_scroll(&self) -> usize {
+        let dialogue = wrap_dialogue(&self.dialogue, &self.colors, terminal_cols()).len();
+        pages_back(dialogue, DIALOGUE_LINES)
+            .max(pages_back(self.actions.len(), ACTION_LINES))
+            .max(pages_back(self.discards.len(), DISCARD_LINES))
+    }
+}
+
+/// Scrolling back through the table talk and the action log, or forward again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scroll {
+    Back,
+    Forward,
+}
+
+impl Scroll {
+    /// The scroll a line typed at the prompt asks for, if it's PgUp or PgDn.
+    pub fn from_input(line: &str) -> Option<Self> {
+        match line.trim() {
+            PAGE_UP => Some(Scroll::Back),
+            PAGE_DOWN => Some(Scroll::Forward),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a line typed at the prompt asks for the pause menu: Esc, or
+/// `menu` in a terminal that won't pass Esc along.
+pub fn wants_menu(line: &str) -> bool {
+    let line = line.trim();
+    line == ESCAPE || line.eq_ignore_ascii_case("menu")
+}
+
+/// Pages there are before the latest of `len` lines, `per_page` to a page.
+fn pages_back(len: usize, per_page: usize) -> usize {
+    len.saturating_sub(1) / per_page
+}
+
+/// The page of `lines` `scroll` pages back from the end, or the first page
+/// if that's as far back as they go.
+fn page<T>(lines: &[T], per_page: usize, scroll: usize) -> &[T] {
+    let end = lines
+        .len()
+        .saturating_sub(scroll * per_page)
+        .max(per_page.min(lines.len()));
+    &lines[end.saturating_sub(per_page)..end]
+}
+
+/// The table as it's on screen, so it can be drawn again, laid out afresh,
+/// when the terminal is resized. Clones share the screen.
+#[derive(Clone, Default)]
+pub struct Screen {
+    frame: Arc<Mutex<Option<TableFrame>>>,
+}
+
+impl Screen {
+    /// Draws `frame` and remembers it.
+    pub fn draw(&self, frame: TableFrame) {
+        let mut last = self.frame.lock().unwrap();
+        render_table(&frame);
+        *last = Some(frame);
+    }
+
+    /// Something other than the table has the screen now, like an
+    /// animation, so a resize should leave it be.
+    pub fn forget(&self) {
+        *self.frame.lock().unwrap() = None;
+    }
+
+    /// Whether the table's on screen. Locked by a draw that's gone wrong,
+    /// or poisoned by one that panicked, it's as good as up.
+    fn is_up(&self) -> bool {
+        self.frame.try_lock().map_or(true, |frame| frame.is_some())
+    }
+
+    /// Scrolls the last frame back or forward a page, as far as there's
+    /// anything to show. A new frame is drawn showing the latest again.
+    pub fn scroll(&self, scroll: Scroll) {
+        let mut last = self.frame.lock().unwrap();
+        if let Some(frame) = last.as_mut() {
+            frame.scroll = match scroll {
+                Scroll::Back => (frame.scroll + 1).min(frame.max_scroll()),
+                Scroll::Forward => frame.scroll.saturating_sub(1),
+            };
+            render_table(frame);
+        }
+    }
+
+    /// Draws the last frame again for the terminal's current size.
+    pub fn repaint(&self) {
+        if let Some(frame) = self.frame.lock().unwrap().as_ref() {
+            render_table(frame);
+        }
+    }
+
+    /// Repaints whenever the terminal is resized, for as long as the tokio
+    /// runtime runs.
+    #[cfg(feature = "cli")]
+    pub fn repaint_on_resize(&self) {
+        tokio::spawn(watch_resizes(self.clone()));
+    }
+}
+
+#[cfg(all(unix, feature = "cli"))]
+async fn watch_resizes(screen: Screen) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let Ok(mut resizes) = signal(SignalKind::window_change()) else {
+        return;
+    };
+    while resizes.recv().await.is_some() {
+        screen.repaint();
+    }
+}
+
+#[cfg(all(not(unix), feature = "cli"))]
+async fn watch_resizes(screen: Screen) {
+    // No resize signal to wait on, so keep an eye on the size instead
+    let mut size = terminal_size();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        if terminal_size() != size {
+            size = terminal_size();
+            screen.repaint();
+        }
+    }
+}
+
+/// Puts the terminal back the way it was found when dropped, however the
+/// game ends: the colors reset and the cursor showing, and the table
+/// cleared away if it's still up, so the shell's prompt doesn't land in the
+/// middle of it.
+#[must_use]
+pub struct TerminalGuard {
+    screen: Screen,
+}
+
+impl TerminalGuard {
+    pub fn new(screen: &Screen) -> Self {
+        Self {
+            screen: screen.clone(),
+        }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // A panic's hook has already cleared up, and left its message to read
+        if std::thread::panicking() {
+            return;
+        }
+        if self.screen.is_up() {
+            clear_screen();
+        }
+        restore_terminal();
+    }
+}
+
+/// Resets the colors and shows the cursor.
+pub fn restore_terminal() {
+    print!("\x1B[0m\x1B[?25h");
+    let _ = io::stdout().flush();
+}
+
+/// Puts the terminal back before a panic's message is printed, so it isn't
+/// lost in the middle of the table or printed in a player's colors.
+pub fn restore_on_panic(screen: &Screen) {
+    let screen = screen.clone();
+    let report = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if screen.is_up() {
+            print!("\x1B[2J\x1B[1;1H");
+        }
+        restore_terminal();
+        report(info);
+    }));
+}
+
+/// Ends the program on Ctrl-C, or SIGTERM, with the terminal put back the
+/// way a `TerminalGuard` leaves it, and whatever `farewell` has to say
+/// then. Runs on the tokio runtime.
+#[cfg(feature = "cli")]
+pub fn exit_on_signal(screen: &Screen, farewell: impl FnOnce() -> Option<String> + Send + 'static) {
+    let screen = screen.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        drop(TerminalGuard::new(&screen));
+        if let Some(farewell) = farewell() {
+            println!("{farewell}");
+        }
+        std::process::exit(130);
+    });
+}
+
+#[cfg(all(unix, feature = "cli"))]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+        let _ = tokio::signal::ctrl_c().await;
+        return;
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = terminate.recv() => {},
+    }
+}
+
+#[cfg(all(not(unix), feature = "cli"))]
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+pub fn clear_screen() {
+    print!("\x1B[2J\x1B[1;1H");
+    io::stdout().flush().unwrap();
+}
+
+pub fn colored_book_title(book: &str) -> String {
+    highlighted(
+        &Prompt::BookTitle {
+            book: book.to_string(),
+        }
+        .to_string(),
+    )
+}
+
+/// `text` in the theme's bold title color, for prompts that need to stand out.
+pub fn highlighted(text: &str) -> String {
+    paint(&palette().title, text)
+}
+
+/// `text` faded, for hints like "Press Enter".
+pub fn dimmed(text: &str) -> String {
+    paint(&palette().dim, text)
+}
+
+// Helper function to colorize multi-line text
+pub fn colorize_text(text: &str, color_code: &str) -> String {
+    let lines: Vec<String> = text.lines().map(|line| paint(color_code, line)).collect();
+    lines.join("\n")
+}
+
+/// Formats what a player said as `Name says: ...`, wrapped to fit a
+/// terminal `cols` wide.
+pub fn dialogue_lines(speaker: &ColoredName, dialogue: &str, cols: usize) -> Vec<String> {
+    // Visible layout constants
+    const SAYS: &str = " says: ";
+    const PREFIX_VIS_COLS: usize = NAME_COLS + SAYS.len();
+    let content_cols = cols
+        .saturating_sub(PREFIX_VIS_COLS)
+        .clamp(MIN_DIALOGUE_COLS, MAX_DIALOGUE_COLS);
+
+    // Get the colored name, already padded to NAME_COLS visible columns
+    let name_field_colored = speaker.colored_padded(NAME_COLS);
+
+    // 1) Wrap the *raw* dialogue to the content width with NO indent
+    //    (avoid double-indenting)
+    let wrapped = wrap_text(dialogue, content_cols, 0);
+
+    // 2) Emit the first line with the colored prefix
+    let mut lines = Vec::new();
+    let mut iter = wrapped.lines();
+    if let Some(first) = iter.next() {
+        lines.push(format!(
+            "{}{}{}",
+            name_field_colored,
+            SAYS,
+            colorize_text(first, &speaker.color_code)
+        ));
+    }
+
+    // 3) Emit continuation lines: indent exactly to the *visible* prefix column
+    for cont in iter {
+        lines.push(format!(
+            "{:width$}{}",
+            "",
+            colorize_text(cont, &speaker.color_code),
+            width = PREFIX_VIS_COLS
+        ));
+    }
+
+    lines
+}
+
+pub fn print_dialogues(log: &[String]) {
+    let start = log.len().saturating_sub(DIALOGUE_LINES);
+    let recent = &log[start..];
+
+    // pad blank lines at top if not enough
+    for _ in 0..(DIALOGUE_LINES - recent.len()) {
+        println!();
+    }
+
+    // print the recent lines (already wrapped/indented)
+    for line in recent {
+        println!("{line}");
+    }
+}
+
+/// Wraps `dialogue` for a terminal `cols` wide, in each speaker's color.
+pub fn wrap_dialogue(dialogue: &[Said], colors: &[ColoredName], cols: usize) -> Vec<String> {
+    dialogue
+        .iter()
+        .flat_map(|said| match find_color(colors, &said.speaker) {
+            Some(colored) => dialogue_lines(colored, &said.text, cols),
+            None => dialogue_lines(
+                &ColoredName::new(said.speaker.clone(), colors.len()),
+                &said.text,
+                cols,
+            ),
+        })
+        .collect()
+}
+
+/// Draws the whole table, laid out for the terminal's current width, and
+/// leaves the cursor just after the prompt.
+pub fn render_table(frame: &TableFrame) {
+    clear_screen();
+
+    println!("{}\n", colored_book_title(&frame.book));
+
+    if frame.scroll == 0 {
+        // Each line said takes at least a line on screen, so older ones can't show
+        let recent = &frame.dialogue[frame.dialogue.len().saturating_sub(DIALOGUE_LINES)..];
+        print_dialogues(&wrap_dialogue(recent, &frame.colors, terminal_cols()));
+        println!();
+    } else {
+        let lines = wrap_dialogue(&frame.dialogue, &frame.colors, terminal_cols());
+        print_dialogues(page(&lines, DIALOGUE_LINES, frame.scroll));
+        let back = Prompt::PagesBack {
+            pages: frame.scroll,
+        };
+        println!("{}", dimmed(&back.to_string()));
+    }
+
+    match &frame.layoff {
+        Some((meld_owner, meld)) => {
+            println!("[--] [⌧]");
+            print_named_hand(frame, meld_owner, meld);
+            println!();
+            print_named_hand(frame, &frame.player_name, &frame.hand);
+        }
+        None => {
+            if let Some(top_card) = &frame.top_discard {
+                println!("[{}] [⌧]", pretty(top_card));
+            } else {
+                println!("[--] [⌧]");
+            }
+
+            print!(" ");
+            print_cards(&frame.hand);
+        }
+    }
+    println!("\n");
+
+    if let Some(recap) = &frame.recap {
+        println!("{recap}");
+    }
+    if let Some(prompt) = &frame.prompt {
+        print!("{prompt} ");
+        io::stdout().flush().unwrap();
+    }
+
+    print!("\x1B[s");
+
+    if !frame.actions.is_empty() {
+        println!("\n\n{}", Prompt::Actions);
+        // Most recent first, so scrolling back is paging forward through them
+        let pages = frame
+            .scroll
+            .min(pages_back(frame.actions.len(), ACTION_LINES));
+        for action in frame
+            .actions
+            .iter()
+            .skip(pages * ACTION_LINES)
+            .take(ACTION_LINES)
+        {
+            println!("{action}");
+        }
+    }
+
+    if !frame.reveal.is_empty() {
+        println!("\n\n{}", Prompt::LastRound);
+        for line in &frame.reveal {
+            println!("{line}");
+        }
+    }
+
+    // Color the names in the Scoreboard
+    println!("\n\n{}", Prompt::Scoreboard);
+    for line in &frame.sheet {
+        println!("{line}");
+    }
+    for (name, score) in frame.scores.iter().filter(|_| frame.sheet.is_empty()) {
+        match find_color(&frame.colors, name) {
+            Some(colored_name) => println!("{}: {}", colored_name.colored(), score),
+            None => println!("{name}: {score}"),
+        }
+    }
+
+    if !frame.chips.is_empty() {
+        println!("\n\n{}", Prompt::Chips);
+        for line in &frame.chips {
+            println!("{line}");
+        }
+    }
+
+    if !frame.odds.is_empty() {
+        println!("\n\n{}", Prompt::Odds);
+        for line in &frame.odds {
+            println!("{line}");
+        }
+    }
+
+    if !frame.discards.is_empty() {
+        println!("\n\n{}", Prompt::Discards);
+        let pages = frame
+            .scroll
+            .min(pages_back(frame.discards.len(), DISCARD_LINES));
+        for line in frame
+            .discards
+            .iter()
+            .skip(pages * DISCARD_LINES)
+            .take(DISCARD_LINES)
+        {
+            println!("{line}");
+        }
+    }
+
+    if let Some(message) = &frame.message {
+        println!("\n{}", Prompt::Messages);
+        println!("{message}");
+    }
+
+    print!("\x1B[u");
+    io::stdout().flush().unwrap();
+}
+
+/// Formats a line of the action log with the player's name and text in their color.
+pub fn action_line(action: &Action, colors: &[ColoredName]) -> String {
+    let (name, color_code) = match find_color(colors, &action.player) {
+        Some(colored) => (
+            colored.colored_padded(NAME_COLS),
+            colored.color_code.as_str(),
+        ),
+        None => (pad_to_width(&action.player, NAME_COLS), ""),
+    };
+
+    let mut line = format!("{name} {}", paint(color_code, &action.text()));
+    for card in &action.cards {
+        line = format!("{line} {}", pretty(card));
+    }
+    line
+}
+
+/// A line on the last turn taken, from `TurnRecap`, e.g. "Theo retrieved
+/// the 6♡ and discarded the Q♧ — discard pile now 14 cards".
+pub fn recap_line(recap: &Recap, discard_pile: usize, colors: &[ColoredName]) -> String {
+    let name = match find_color(colors, recap.player()) {
+        Some(colored) => colored.colored(),
+        None => recap.player().to_string(),
+    };
+
+    match recap {
+        Recap::Discarded {
+            retrieved, card, ..
+        } => Prompt::DiscardedRecap {
+            player: name,
+            retrieved: retrieved.as_ref().map(pretty),
+            card: pretty(card),
+            pile: discard_pile,
+        },
+        Recap::Played { score, .. } => Prompt::PlayedRecap {
+            player: name,
+            score: *score,
+        },
+        Recap::Knocked { deadwood, .. } => Prompt::AiPlayed {
+            player: name,
+            score: *deadwood,
+            knocked: true,
+        },
+    }
+    .to_string()
+}
+
+/// The odds overlay for a hand: its best meld, what drawing or taking the
+/// discard does for it, and every card's worth, the one best thrown away
+/// first.
+pub fn odds_panel(odds: &HandOdds) -> Vec<String> {
+    let mut lines = vec![
+        Prompt::BestMeldNow {
+            meld: meld_label(odds.baseline),
+        }
+        .to_string(),
+        Prompt::DrawImproves {
+            percent: (odds.draw_improves * 100.0).round() as i64,
+            expected: format!("{:.1}", odds.draw_expected),
+        }
+        .to_string(),
+    ];
+    if let Some((card, score)) = odds.retrieve {
+        let card = pretty(&card);
+        let taking = if score > odds.baseline {
+            Prompt::TakingImproves {
+                card,
+                meld: meld_label(score),
+            }
+        } else {
+            Prompt::TakingDoesNotImprove { card }
+        };
+        lines.push(taking.to_string());
+    }
+
+    lines.push(Prompt::WorthKeeping.to_string());
+    for (idx, value) in odds.cards.iter().enumerate() {
+        let worth = Prompt::CardWorth {
+            card: pretty(&value.card),
+            value: format!("{:6.2}", value.strategic_value),
+            percent: (value.net_value * 10.0).round() as i64,
+            best: idx == 0,
+        };
+        lines.push(worth.to_string());
+    }
+    lines
+}
+
+/// The discard pile from `GameEngine::visible_discards`, the top card first,
+/// a line to every `DISCARDS_PER_LINE` cards and each line numbered by the
+/// first card on it.
+pub fn discard_panel(discards: &[Card]) -> Vec<String> {
+    if discards.is_empty() {
+        return vec![format!("  {}", Prompt::DiscardPileEmpty)];
+    }
+
+    discards
+        .chunks(DISCARDS_PER_LINE)
+        .enumerate()
+        .map(|(idx, cards)| {
+            let cards: Vec<String> = cards.iter().map(pretty).collect();
+            format!("{:>4}  {}", idx * DISCARDS_PER_LINE + 1, cards.join(" "))
+        })
+        .collect()
+}
+
+/// The layoff chain of a stolen round, a line a step: the hand as it was
+/// played, each layoff onto it in turn and the meld that won. Nothing if the
+/// player who played kept the round.
+pub fn layoff_chain(summary: &RoundSummary, colors: &[ColoredName]) -> Vec<String> {
+    if !summary.is_steal() {
+        return Vec::new();
+    }
+
+    let name = |player: &str| match find_color(colors, player) {
+        Some(colored) => colored.colored_padded(NAME_COLS),
+        None => pad_to_width(player, NAME_COLS),
+    };
+    let cards = |cards: &[Card]| cards.iter().map(pretty).collect::<Vec<_>>().join(" ");
+
+    let mut lines = vec![
+        Prompt::ChainPlayed {
+            player: name(&summary.played_by),
+            cards: cards(&summary.played_hand),
+            meld: meld_label(summary.played_score),
+        }
+        .to_string(),
+    ];
+    for step in &summary.layoffs {
+        let result = match (&step.meld, step.score) {
+            (Some(meld), Some(score)) => format!("→ {}  {}", cards(meld), meld_label(score)),
+            _ => Prompt::DidNotBeatIt.to_string(),
+        };
+        let laid_off = Prompt::ChainLaidOff {
+            player: name(&step.player),
+            cards: cards(&step.cards),
+            result,
+        };
+        lines.push(laid_off.to_string());
+    }
+    let won = Prompt::ChainWon {
+        player: name(&summary.winner),
+        score: summary.score,
+        cards: cards(&summary.hand),
+    };
+    lines.push(won.to_string());
+    lines
+}
+
+/// The score sheet as a table, a line a player under a header: what each of
+/// the last `SHEET_ROUNDS` rounds added, their total and a bar of it against
+/// the leader's. Nothing until a round's finished.
+pub fn score_sheet_panel(sheet: &ScoreSheet, colors: &[ColoredName]) -> Vec<String> {
+    let rounds = sheet.rounds();
+    if rounds.is_empty() {
+        return Vec::new();
+    }
+
+    let shown = rounds.len().saturating_sub(SHEET_ROUNDS);
+    let mut header = pad_to_width("", NAME_COLS);
+    for round in shown + 1..=rounds.len() {
+        header.push_str(&format!("{:>5}", format!("R{round}")));
+    }
+    header.push_str(&format!("{:>7}", "Total"));
+
+    let totals = sheet.totals();
+    let leader = totals.iter().copied().max().unwrap_or(0).max(1);
+    let mut lines = vec![header];
+    for (seat, name) in sheet.players().iter().enumerate() {
+        let mut line = match find_color(colors, name) {
+            Some(colored) => colored.colored_padded(NAME_COLS),
+            None => pad_to_width(name, NAME_COLS),
+        };
+        for round in &rounds[shown..] {
+            let points = match round[seat] {
+                0 => "·".to_string(),
+                points => format!("{points:+}"),
+            };
+            line.push_str(&format!("{points:>5}"));
+        }
+        let total = totals[seat];
+        let bar = total.max(0) as usize * SHEET_BAR_COLS / leader as usize;
+        line.push_str(&format!("{total:>7}  {}", "█".repeat(bar)));
+        lines.push(line);
+    }
+    lines
+}
+
+/// The pot, then everyone's chips, a line a player. Anyone out of chips is
+/// marked felted.
+pub fn chips_panel(bank: &Bank, colors: &[ColoredName]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "{}{:>7}",
+        pad_to_width("Pot", NAME_COLS),
+        bank.pot()
+    )];
+    for (name, chips) in bank.balances() {
+        let mut line = match find_color(colors, name) {
+            Some(colored) => colored.colored_padded(NAME_COLS),
+            None => pad_to_width(name, NAME_COLS),
+        };
+        line.push_str(&format!("{chips:>7}"));
+        if *chips == 0 {
+            line.push_str("  felted");
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// A meld's name and score, or just the score if it has no name.
+fn meld_label(score: u64) -> String {
+    match meld_name(score) {
+        Some(name) => format!("{name} ({score})"),
+        None => score.to_string(),
+    }
+}
+
+/// A meld as its parts add up, with the cards drawn with `pretty`, e.g.
+/// `Full House: 2♤ 2♡ 2♧ + 3♤ 3♡ = 40`.
+pub fn meld_breakdown(meld: &MeldMatch) -> String {
+    let parts: Vec<String> = meld
+        .parts
+        .iter()
+        .map(|part| part.iter().map(pretty).collect::<Vec<_>>().join(" "))
+        .collect();
+    format!("{}: {} = {}", meld.name, parts.join(" + "), meld.score)
+}
+
+pub fn find_color<'a>(colors: &'a [ColoredName], name: &str) -> Option<&'a ColoredName> {
+    colors.iter().find(|cn| cn.name == name)
+}
+
+fn print_named_hand(frame: &TableFrame, name: &str, cards: &[Card]) {
+    // Color the player names in the hand display
+    print!(" ");
+    if let Some(colored_name) = find_color(&frame.colors, name) {
+        print!("{} hand: ", colored_name.colored_padded(NAME_COLS));
+    } else {
+        let name = format!("{name}'s");
+        print!("{} hand: ", pad_to_width(&name, NAME_COLS));
+    }
+    print_cards(cards);
+}
+
+/// Prints `cards` the theme's way, compact on the current line or as boxes
+/// on lines of their own. Either way the cursor's left after the last card.
+fn print_cards(cards: &[Card]) {
+    match palette().cards {
+        CardStyle::Compact => {
+            for card in cards {
+                print!("{} ", pretty(card));
+            }
+        }
+        CardStyle::Boxes => {
+            for line in hand_art(cards, terminal_cols().saturating_sub(1)) {
+                print!("\n {line}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bank::Stakes;
+    use crate::engine::LayoffStep;
+    use crate::game::events::{EventSink, GameEvent};
+    use crate::game::{Hand, Player};
+
+    #[test]
+    fn test_dialogue_rewraps_for_the_terminal() {
+        let ada = ColoredName::new("Ada".to_string(), 0);
+        let said = "It was the best of times, it was the worst of times, it was the age of \
+                    wisdom, it was the age of foolishness";
+
+        let wide = dialogue_lines(&ada, said, 200);
+        let narrow = dialogue_lines(&ada, said, 60);
+        assert_eq!(wide.len(), 2);
+        assert!(narrow.len() > wide.len());
+
+        // Too narrow to be readable still gets a usable column
+        let cramped = dialogue_lines(&ada, said, 10);
+        assert_eq!(
+            cramped,
+            dialogue_lines(&ada, said, NAME_COLS + 7 + MIN_DIALOGUE_COLS)
+        );
+
+        // Speakers are colored when drawn, whether or not they're seated
+        let dialogue = [Said {
+            speaker: "Ada".to_string(),
+            text: said.to_string(),
+        }];
+        assert_eq!(
+            wrap_dialogue(&dialogue, std::slice::from_ref(&ada), 60),
+            narrow
+        );
+        assert_eq!(wrap_dialogue(&dialogue, &[], 200).len(), 2);
+    }
+
+    #[test]
+    fn test_esc_opens_the_menu() {
+        assert!(wants_menu("\x1B"));
+        assert!(wants_menu(" Menu\n"));
+        assert!(!wants_menu("\x1B[5~"));
+        assert!(!wants_menu("m"));
+    }
+
+    #[test]
+    fn test_history_scrolls_a_page_at_a_time() {
+        assert_eq!(Scroll::from_input("\x1B[5~"), Some(Scroll::Back));
+        assert_eq!(Scroll::from_input("\x1B[6~\n"), Some(Scroll::Forward));
+        assert_eq!(Scroll::from_input("D"), None);
+
+        let lines: Vec<usize> = (0..25).collect();
+        assert_eq!(page(&lines, 10, 0), &lines[15..]);
+        assert_eq!(page(&lines, 10, 1), &lines[5..15]);
+        // There's no scrolling past the first page
+        assert_eq!(page(&lines, 10, 2), &lines[..10]);
+        assert_eq!(page(&lines, 10, 9), &lines[..10]);
+        assert_eq!(page(&lines[..3], 10, 1), &lines[..3]);
+        assert_eq!(pages_back(25, 10), 2);
+        assert_eq!(pages_back(10, 10), 0);
+        assert_eq!(pages_back(0, 10), 0);
+    }
+
+    #[test]
+    fn test_the_discard_pile_is_listed_top_first() {
+        let discards: Vec<Card> = (0..15)
+            .map(|idx| ["2h", "3s", "Kd"][idx % 3].parse().unwrap())
+            .collect();
+
+        let panel = discard_panel(&discards);
+        assert_eq!(panel.len(), 2);
+        assert!(panel[0].starts_with("   1  "));
+        assert!(panel[1].starts_with("  14  "));
+        assert!(panel[1].ends_with(&format!(
+            "{} {}",
+            pretty(&discards[13]),
+            pretty(&discards[14])
+        )));
+        assert_eq!(discard_panel(&[]).len(), 1);
+    }
+
+    #[test]
+    fn test_recap_reads_the_last_turn() {
+        use crate::game::events::{EventSink, GameEvent, TurnRecap};
+
+        let card = |card: &str| card.parse::<Card>().unwrap();
+        let mut recap = TurnRecap::default();
+        for event in [
+            GameEvent::CardDrawn {
+                player: "Theo".to_string(),
+                retrieved: Some(card("6h")),
+            },
+            GameEvent::CardDiscarded {
+                player: "Theo".to_string(),
+                card: card("Qc"),
+            },
+        ] {
+            recap.emit(&event);
+        }
+
+        let line = recap_line(recap.last().unwrap(), 14, &[]);
+        assert_eq!(
+            line,
+            format!(
+                "Theo retrieved the {} and discarded the {} — discard pile now 14 cards",
+                pretty(&card("6h")),
+                pretty(&card("Qc"))
+            )
+        );
+
+        recap.emit(&GameEvent::CardDrawn {
+            player: "Ada".to_string(),
+            retrieved: None,
+        });
+        recap.emit(&GameEvent::CardDiscarded {
+            player: "Ada".to_string(),
+            card: card("2s"),
+        });
+        assert!(
+            recap_line(recap.last().unwrap(), 1, &[]).starts_with("Ada drew a card and discarded")
+        );
+        assert!(recap_line(recap.last().unwrap(), 1, &[]).ends_with("now 1 card"));
+
+        recap.emit(&GameEvent::RoundDealt {
+            player: "Ada".to_string(),
+            hands: Vec::new(),
+            deal_hash: 0,
+        });
+        assert_eq!(recap.last(), None);
+    }
+
+    #[test]
+    fn test_the_score_sheet_is_a_table_of_rounds() {
+        let player = |name: &str, score| Player {
+            name: name.to_string(),
+            description: String::new(),
+            player_type: None,
+            hand: Hand { cards: Vec::new() },
+            actions: Default::default(),
+            dialogue: Default::default(),
+            score,
+        };
+        let mut players = vec![player("Ada", 0), player("Bram", 0)];
+        let mut sheet = ScoreSheet::new(&players);
+        assert!(score_sheet_panel(&sheet, &[]).is_empty());
+
+        players[0].score = 40;
+        sheet.end_round(&players);
+        players[1].score = 20;
+        sheet.end_round(&players);
+
+        let lines = score_sheet_panel(&sheet, &[]);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].trim_start().starts_with("R1   R2  Total"));
+        assert!(lines[1].starts_with("Ada") && lines[1].contains("+40    ·     40"));
+        // Bars are against the leader's total
+        assert!(lines[1].ends_with(&"█".repeat(SHEET_BAR_COLS)));
+        assert!(lines[2].ends_with(&format!(" {}", "█".repeat(SHEET_BAR_COLS / 2))));
+    }
+
+    #[test]
+    fn test_chips_panel_shows_the_pot_and_who_is_felted() {
+        let mut bank = Bank::new(["Ada", "Bram"], Stakes::new(10, 10));
+        bank.emit(&GameEvent::RoundDealt {
+            player: "Ada".to_string(),
+            hands: Vec::new(),
+            deal_hash: 0,
+        });
+
+        let lines = chips_panel(&bank, &[]);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("Pot") && lines[0].ends_with(" 20"));
+        assert!(lines[1].starts_with("Ada") && lines[1].ends_with(" 0  felted"));
+    }
+
+    #[test]
+    fn test_layoff_chain_reads_in_order() {
+        let cards = |cards: &str| -> Vec<Card> {
+            cards.split(' ').map(|card| card.parse().unwrap()).collect()
+        };
+        let mut summary = RoundSummary {
+            played_by: "Ada".to_string(),
+            played_hand: cards("Kh Kd Kc 2h 2s"),
+            played_score: 40,
+            layoffs: vec![LayoffStep {
+                player: "Cy".to_string(),
+                cards: cards("3d"),
+                meld: None,
+                score: None,
+            }],
+            winner: "Ada".to_string(),
+            score: 40,
+            hand: cards("Kh Kd Kc 2h 2s"),
+        };
+        assert!(layoff_chain(&summary, &[]).is_empty());
+
+        summary.layoffs.insert(
+            0,
+            LayoffStep {
+                player: "Bram".to_string(),
+                cards: cards("Ks"),
+                meld: Some(cards("Kh Kd Kc Ks 2s")),
+                score: Some(50),
+            },
+        );
+        summary.winner = "Bram".to_string();
+        summary.score = 50;
+        summary.hand = cards("Kh Kd Kc Ks 2s");
+
+        let lines = layoff_chain(&summary, &[]);
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("Ada") && lines[0].ends_with("Full House (40)"));
+        assert!(lines[1].starts_with("Bram") && lines[1].ends_with("Four of a Kind (50)"));
+        assert!(lines[2].ends_with("didn't beat it"));
+        assert!(lines[3].starts_with("Bram") && lines[3].contains("won 50"));
+    }
+}
@@ -0,0 +1,386 @@
+//! The colors the table is drawn in. A theme file is TOML, every key
+//! optional:
+//!
+//! ```toml
+//! red_suits = "red"
+//! black_suits = "180"
+//! players = ["#0087ff", "127", "bright-magenta"]
+//! title = "120"
+//! colors = "256"
+//! cards = "boxes"
+//! ```
+//!
+//! A color is a name (`red`, `bright-green`, ...), an index into the 256
+//! color palette, or `#rrggbb`. Each is brought down to what the terminal can
+//! show, and nothing is colored at all when `NO_COLOR` is set or the output
+//! isn't a terminal.
+//!
+//! `cards` draws hands `compact`, as `7♡`, or as `boxes`, see `card_art`.
+
+use crate::display::card_art::CardStyle;
+use crate::error::RummyError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// The basic colors, in their ANSI order. The bright ones follow them.
+const BASIC_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// What the basic colors look like in xterm, to find the nearest of them.
+const BASIC_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The levels of each channel in the 6x6x6 color cube.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// How many colors the terminal can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorSupport {
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+    #[serde(rename = "none")]
+    None,
+}
+
+impl ColorSupport {
+    /// None when `NO_COLOR` is set or stdout isn't a terminal, otherwise
+    /// whatever `COLORTERM` and `TERM` claim.
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        if !var("NO_COLOR").is_empty() || !std::io::stdout().is_terminal() {
+            return ColorSupport::None;
+        }
+
+        let colorterm = var("COLORTERM");
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorSupport::TrueColor
+        } else if var("TERM").contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+}
+
+/// One color of a theme, however it was written down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Color {
+    Rgb(u8, u8, u8),
+    /// Into the 256 color palette.
+    Indexed(u8),
+    /// One of the 16 basic colors, the bright ones 8 to 15.
+    Basic(u8),
+}
+
+impl Color {
+    /// The SGR parameters that set this as the foreground color, brought down
+    /// to what `support` can show. Empty for no color.
+    pub fn sgr(self, support: ColorSupport) -> String {
+        match (self, support) {
+            (_, ColorSupport::None) => String::new(),
+            (Color::Basic(n), _) => basic_sgr(n),
+            (Color::Rgb(r, g, b), ColorSupport::TrueColor) => format!("38;2;{r};{g};{b}"),
+            (Color::Rgb(r, g, b), ColorSupport::Ansi256) => {
+                format!("38;5;{}", nearest_indexed(r, g, b))
+            }
+            (Color::Indexed(n), ColorSupport::TrueColor | ColorSupport::Ansi256) => {
+                format!("38;5;{n}")
+            }
+            (color, ColorSupport::Ansi16) => basic_sgr(nearest_basic(color.rgb())),
+        }
+    }
+
+    /// What the color looks like, for bringing it down to the basic ones.
+    fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Basic(n) => BASIC_RGB[n as usize % 16],
+            Color::Indexed(n @ 0..=15) => BASIC_RGB[n as usize],
+            Color::Indexed(n @ 16..=231) => {
+                let n = n as usize - 16;
+                (
+                    CUBE_LEVELS[n / 36],
+                    CUBE_LEVELS[n / 6 % 6],
+                    CUBE_LEVELS[n % 6],
+                )
+            }
+            Color::Indexed(n) => {
+                let gray = 8 + 10 * (n - 232);
+                (gray, gray, gray)
+            }
+        }
+    }
+}
+
+fn basic_sgr(n: u8) -> String {
+    if n < 8 {
+        (30 + n).to_string()
+    } else {
+        (90 + n % 16 - 8).to_string()
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).unsigned_abs().pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> u8 {
+    (0..16u8)
+        .min_by_key(|&n| distance(rgb, BASIC_RGB[n as usize]))
+        .unwrap_or(0)
+}
+
+/// The nearest of the color cube and the gray ramp.
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    (16..=255u8)
+        .min_by_key(|&n| distance((r, g, b), Color::Indexed(n).rgb()))
+        .unwrap_or(16)
+}
+
+impl FromStr for Color {
+    type Err = RummyError;
+
+    /// Parses a color name (`bright-green`), a 256 color index (`120`) or
+    /// `#rrggbb`.
+    fn from_str(input: &str) -> Result<Color, RummyError> {
+        let input = input.trim().to_lowercase();
+        let unknown = || RummyError::Config(format!("{input} isn't a color"));
+
+        if let Some(hex) = input.strip_prefix('#') {
+            if hex.len() != 6 || !hex.is_ascii() {
+                return Err(unknown());
+            }
+            let channel =
+                |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).map_err(|_| unknown());
+            return Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        if let Ok(n) = input.parse::<u8>() {
+            return Ok(Color::Indexed(n));
+        }
+
+        let (bright, name) = match input.strip_prefix("bright-") {
+            Some(name) => (8, name),
+            None => (0, input.as_str()),
+        };
+        BASIC_NAMES
+            .iter()
+            .position(|basic| *basic == name)
+            .map(|n| Color::Basic(n as u8 + bright))
+            .ok_or_else(unknown)
+    }
+}
+
+impl fmt::Display for Color {
+    /// Writes the form that `FromStr` accepts.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Color::Rgb(r, g, b) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+            Color::Indexed(n) => write!(f, "{n}"),
+            Color::Basic(n) if n < 8 => write!(f, "{}", BASIC_NAMES[n as usize]),
+            Color::Basic(n) => write!(f, "bright-{}", BASIC_NAMES[(n % 16 - 8) as usize]),
+        }
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> String {
+        color.to_string()
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = RummyError;
+
+    fn try_from(input: String) -> Result<Color, RummyError> {
+        input.parse()
+    }
+}
+
+/// The colors of the table, as written in a theme file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Hearts and diamonds.
+    pub red_suits: Color,
+    /// Spades and clubs.
+    pub black_suits: Color,
+    /// Handed out in seating order, round again if there are more players.
+    pub players: Vec<Color>,
+    /// The book title and the prompts, in bold.
+    pub title: Color,
+    /// What the terminal can show, worked out with `ColorSupport::detect`
+    /// if not given.
+    pub colors: Option<ColorSupport>,
+    /// How hands are drawn.
+    pub cards: CardStyle,
+}
+
+impl Default for Theme {
+    /// Colors that work on both dark and light backgrounds.
+    fn default() -> Self {
+        Self {
+            red_suits: Color::Basic(1),
+            // Light pastel brown
+            black_suits: Color::Indexed(180),
+            // Blue, purple, orange, green, maroon, brown, teal, dark orange
+            players: [33, 127, 166, 28, 124, 94, 31, 130]
+                .into_iter()
+                .map(Color::Indexed)
+                .collect(),
+            // Pastel green
+            title: Color::Indexed(120),
+            colors: None,
+            cards: CardStyle::default(),
+        }
+    }
+}
+
+impl Theme {
+    pub fn load(path: &Path) -> Result<Self, RummyError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+        toml::from_str(&contents).map_err(|err| {
+            RummyError::Config(format!("{} isn't a theme file: {err}", path.display()))
+        })
+    }
+
+    /// The theme brought down to what `support` can show.
+    pub fn palette(&self, support: ColorSupport) -> Palette {
+        let bold = |color: Color| match color.sgr(support) {
+            sgr if sgr.is_empty() => sgr,
+            sgr => format!("1;{sgr}"),
+        };
+
+        Palette {
+            red_suits: self.red_suits.sgr(support),
+            black_suits: self.black_suits.sgr(support),
+            players: self
+                .players
+                .iter()
+                .map(|color| color.sgr(support))
+                .collect(),
+            title: bold(self.title),
+            dim: if support == ColorSupport::None {
+                String::new()
+            } else {
+                "2".to_string()
+            },
+            cards: self.cards,
+        }
+    }
+}
+
+/// A theme as SGR parameters, ready to `paint` with, and the way it draws
+/// hands. Empty parameters leave the text as it is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub red_suits: String,
+    pub black_suits: String,
+    pub players: Vec<String>,
+    pub title: String,
+    pub dim: String,
+    pub cards: CardStyle,
+}
+
+impl Palette {
+    /// The color of whoever sits at `player_index`.
+    pub fn player(&self, player_index: usize) -> &str {
+        if self.players.is_empty() {
+            return "";
+        }
+        &self.players[player_index % self.players.len()]
+    }
+}
+
+static PALETTE: LazyLock<RwLock<Arc<Palette>>> = LazyLock::new(|| {
+    let theme = Theme::default();
+    RwLock::new(Arc::new(theme.palette(ColorSupport::detect())))
+});
+
+/// Draws everything from now on in `theme`.
+pub fn install(theme: &Theme) {
+    let palette = theme.palette(theme.colors.unwrap_or_else(ColorSupport::detect));
+    *PALETTE.write().unwrap() = Arc::new(palette);
+}
+
+/// The palette everything is drawn in, the default theme's until another
+/// is installed.
+pub fn palette() -> Arc<Palette> {
+    PALETTE.read().unwrap().clone()
+}
+
+/// `text` in the SGR parameters `sgr`, or as it is if they're empty.
+pub fn paint(sgr: &str, text: &str) -> String {
+    if sgr.is_empty() {
+        text.to_string()
+    } else {
+        format!("\x1B[{sgr}m{text}\x1B[0m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_themes_come_down_to_what_the_terminal_shows() {
+        assert_eq!("#ff8700".parse::<Color>().unwrap(), Color::Rgb(255, 135, 0));
+        assert_eq!("120".parse::<Color>().unwrap(), Color::Indexed(120));
+        assert_eq!(" Bright-Green".parse::<Color>().unwrap(), Color::Basic(10));
+        assert!("#ff87".parse::<Color>().is_err());
+        assert!("mauve".parse::<Color>().is_err());
+        for color in [Color::Rgb(0, 95, 255), Color::Indexed(7), Color::Basic(13)] {
+            assert_eq!(color.to_string().parse::<Color>().unwrap(), color);
+        }
+
+        let orange = Color::Rgb(255, 135, 0);
+        assert_eq!(orange.sgr(ColorSupport::TrueColor), "38;2;255;135;0");
+        assert_eq!(orange.sgr(ColorSupport::Ansi256), "38;5;208");
+        assert_eq!(orange.sgr(ColorSupport::Ansi16), "33");
+        assert_eq!(Color::Indexed(244).sgr(ColorSupport::Ansi16), "90");
+        assert_eq!(Color::Basic(1).sgr(ColorSupport::TrueColor), "31");
+        assert_eq!(orange.sgr(ColorSupport::None), "");
+
+        // The default theme draws the table as it always has
+        let palette = Theme::default().palette(ColorSupport::Ansi256);
+        assert_eq!(palette.red_suits, "31");
+        assert_eq!(palette.title, "1;38;5;120");
+        assert_eq!(palette.player(9), "38;5;127");
+        assert_eq!(
+            paint(&palette.dim, "Press Enter"),
+            "\x1B[2mPress Enter\x1B[0m"
+        );
+
+        let plain = Theme::default().palette(ColorSupport::None);
+        assert_eq!(plain.player(0), "");
+        assert_eq!(paint(&plain.title, "East of Eden"), "East of Eden");
+    }
+}
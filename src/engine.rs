@@ -0,0 +1,3397 @@
+use crate::analysis::{
+    AnalysisConfig, HandOdds, KnownInformation, MatchContext, Node, OpponentModel, PlayDecision,
+    Strategy, TemperamentPolicy, advise_searched, advise_with,
+};
+use crate::card::{Card, ToU64, remove_one};
+use crate::error::RummyError;
+use crate::game::events::{EventSink, EventSinks, GameEvent, SharedSink};
+use crate::game::layoff::{self, LayoffError, LayoffOutcome};
+use crate::game::rules::{self, GameRules, KnockResult, Variant};
+use crate::game::sheet::ScoreSheet;
+use crate::game::{
+    ActionHistory, AutoPlayDecision, Choice, CutPosition, DealPlan, DealRituals, Deck, DeckConfig,
+    Hand, PlayAction, Player, PlayerType, TurnOrder, build_deck_with,
+};
+use crate::quiz::quiz_bonus;
+use crate::rng::{self as rng, RngService, RngStream, SeedDisclosure};
+use crate::scoring::deadwood;
+#[cfg(feature = "scripting")]
+use crate::scripting::{HouseRuleEffect, HouseRules};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+#[cfg(feature = "cli")]
+pub mod stream;
+
+/// Meld score a greedy AI plays at, in the variants the analysis wasn't
+/// built for: a Straight.
+const GREEDY_PLAY_SCORE: u64 = 20;
+
+/// How far an AI's turn searches ahead from every hand it could hold.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AiSearch {
+    /// Not at all, every hand's judged as it stands.
+    #[default]
+    Off,
+    /// As far as the seat's node budget goes. A seed plays out the same
+    /// game on any machine.
+    Nodes,
+    /// A level deeper at a time until the time runs out, so a slow machine
+    /// doesn't stall on a hand. How deep it gets varies from turn to turn,
+    /// so a seed no longer plays out the same game.
+    Time(Duration),
+}
+
+#[derive(Debug, Clone)]
+pub struct LayOffResult {
+    pub player: Player,
+    pub cards_laid_off: Vec<Card>,
+    pub resulting_hand: Hand,
+    pub resulting_score: u64,
+    pub cards_used: usize,
+}
+
+/// What the engine is waiting for next.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum TurnPhase {
+    /// The game hasn't started: the table is deciding who goes first.
+    Seating,
+    /// Before the first deal, the table is answering the book quiz.
+    Quiz,
+    /// The current player chooses to draw, retrieve or play.
+    Choosing,
+    /// The current player holds an extra card and must discard one.
+    Discarding,
+    /// The last discard completes a winning meld for someone else, who can
+    /// declare on it before play goes on.
+    Declaring,
+    /// A hand has been played and the other players take turns laying off onto it.
+    LayingOff,
+}
+
+/// A played hand that the other players are laying off onto.
+#[derive(Clone, Debug)]
+pub struct LayoffRound {
+    /// The player who played their hand.
+    pub winner_idx: usize,
+    /// The player whose cards currently make up the meld.
+    pub layoff_winner_idx: usize,
+    /// The player whose turn it is to lay off.
+    pub current_idx: usize,
+    pub winning_hand: Hand,
+    pub score_to_beat: u64,
+    pub results: Vec<LayOffResult>,
+    /// The meld as it was played, before anything was laid off onto it.
+    pub played_hand: Hand,
+    pub played_score: u64,
+    /// Every layoff tried so far, in turn order. Passes aren't kept.
+    pub steps: Vec<LayoffStep>,
+}
+
+/// One player's cards laid off onto the played hand.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LayoffStep {
+    pub player: String,
+    pub cards: Vec<Card>,
+    /// The meld the cards made, if it beat the one on the table.
+    pub meld: Option<Vec<Card>>,
+    pub score: Option<u64>,
+}
+
+/// How the last round was won, from the hand as it was played through every
+/// layoff onto it to the meld that took the round.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RoundSummary {
+    pub played_by: String,
+    pub played_hand: Vec<Card>,
+    pub played_score: u64,
+    pub layoffs: Vec<LayoffStep>,
+    pub winner: String,
+    pub score: u64,
+    pub hand: Vec<Card>,
+}
+
+impl RoundSummary {
+    /// Whether a layoff took the round off the player who played.
+    pub fn is_steal(&self) -> bool {
+        self.layoffs.iter().any(|step| step.meld.is_some())
+    }
+}
+
+/// A discard the other players can declare on, out of turn.
+#[derive(Clone, Debug)]
+pub struct DeclareWindow {
+    /// The player who discarded it.
+    pub discarded_by: usize,
+    pub card: Card,
+    /// Everyone the card completes a winning meld for, in the order they'd
+    /// get it: around the table from the discarder.
+    pub eligible: Vec<usize>,
+    pub declared: Vec<usize>,
+    pub passed: Vec<usize>,
+}
+
+impl DeclareWindow {
+    /// Whether `player_idx` can still declare or pass.
+    pub fn is_open_to(&self, player_idx: usize) -> bool {
+        self.eligible.contains(&player_idx)
+            && !self.declared.contains(&player_idx)
+            && !self.passed.contains(&player_idx)
+    }
+}
+
+/// Who won a round, and with what.
+#[derive(Clone, Debug)]
+pub struct RoundOutcome {
+    pub winner: Player,
+    pub score: u64,
+    pub hand: Hand,
+}
+
+/// What an AI player did on its turn.
+#[derive(Clone, Debug)]
+pub enum AiTurn {
+    Drew { discarded: Card },
+    Retrieved { discarded: Card },
+    Played { score: u64, hand: Hand },
+}
+
+/// A turn the current player is still making up their mind about. Nothing
+/// is played until it's committed, apart from a card drawn blind: that's
+/// been seen, so it's theirs from the start.
+#[derive(Clone, Debug)]
+pub struct StagedTurn {
+    /// Whether the card was drawn or retrieved.
+    pub choice: Choice,
+    pub taken: Card,
+    pub discard: Option<Card>,
+}
+
+/// AI decisions worked out ahead of their turn by `precompute_next_turn`,
+/// keyed on the `decision_key` of the table they were worked out for. Shared
+/// with the copies of the engine doing the working out.
+#[derive(Clone, Debug, Default)]
+struct Precomputed(Arc<Mutex<HashMap<u64, AutoPlayDecision>>>);
+
+impl Precomputed {
+    /// The decisions, even if a search panicked holding them: a panic can't
+    /// leave half a decision in the map, and the game shouldn't fall over too.
+    fn decisions(&self) -> MutexGuard<'_, HashMap<u64, AutoPlayDecision>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn insert(&self, key: u64, decision: AutoPlayDecision) {
+        self.decisions().insert(key, decision);
+    }
+
+    fn take(&self, key: u64) -> Option<AutoPlayDecision> {
+        self.decisions().remove(&key)
+    }
+
+    fn clear(&self) {
+        self.decisions().clear();
+    }
+}
+
+/// Everything needed to pick a game back up between turns: the seats, with
+/// their hands and scores, both piles and the seed they're dealt from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub players: Vec<Player>,
+    pub draw_pile: VecDeque<Card>,
+    pub discard_pile: VecDeque<Card>,
+    pub current_player_idx: usize,
+    pub target_score: usize,
+    pub deck_config: DeckConfig,
+    /// Older saves are all of bookclub rummy.
+    #[serde(default)]
+    pub variant: Variant,
+    #[serde(default)]
+    pub declarations: bool,
+    #[serde(default)]
+    pub rituals: DealRituals,
+    #[serde(default)]
+    pub burned: Vec<Card>,
+    #[serde(default)]
+    pub turns: usize,
+    #[serde(default)]
+    pub rules: GameRules,
+    #[serde(default)]
+    pub reshuffles: usize,
+    /// Older saves start their sheet from the scores they were saved with.
+    #[serde(default)]
+    pub score_sheet: ScoreSheet,
+    /// Older saves deal on from a new seed, and disclose only the deals
+    /// since they were picked back up.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub deal_hashes: Vec<u64>,
+}
+
+/// The rules of a game of rummy, driven one action at a time. Which rummy is
+/// up to its `variant`.
+///
+/// The engine knows nothing about terminals or sockets: the local game and the
+/// network server both feed it actions and render whatever state it ends up in.
+#[derive(Clone, Debug)]
+pub struct GameEngine {
+    pub players: Vec<Player>,
+    pub deck: Deck,
+    pub current_player_idx: usize,
+    pub target_score: usize,
+    /// The cards every round is dealt from.
+    pub deck_config: DeckConfig,
+    pub variant: Variant,
+    phase: TurnPhase,
+    layoff: Option<LayoffRound>,
+    /// How the last round was won. Not saved.
+    last_round: Option<RoundSummary>,
+    /// Everyone's score round by round, started at the first deal.
+    score_sheet: ScoreSheet,
+    /// Whoever the game was called for, whatever the scores. Not saved.
+    called_winner: Option<usize>,
+    /// Whether the table plays the house rule that lets anyone declare on a
+    /// discard that completes a winning meld for them.
+    declarations: bool,
+    /// What the dealer does before every deal.
+    rituals: DealRituals,
+    /// Cards fixed before every deal, if any. Not saved.
+    deal_plan: Option<DealPlan>,
+    rules: GameRules,
+    declare: Option<DeclareWindow>,
+    /// The current player's draw or retrieve and discard, waiting for them
+    /// to confirm it.
+    staged: Option<StagedTurn>,
+    /// Turns finished this game, across every round.
+    turns: usize,
+    /// Every deal's hash this game, disclosed with the seed at the end. Not
+    /// saved, a resumed game's dealt from a fresh seed.
+    deal_hashes: Vec<u64>,
+    events: EventSinks,
+    /// What everyone's given away about their hand this round, for the AI.
+    /// Not saved, a resumed game starts reading the table afresh.
+    opponents: OpponentModel,
+    /// Bots playing a seat in place of its player type, by player name. Not
+    /// saved, they're registered again after a restore.
+    strategies: HashMap<String, Arc<dyn Strategy>>,
+    /// How the AI players' temper drifts with the score, `None` for them
+    /// to play as they were seated all game.
+    temperament_policy: Option<TemperamentPolicy>,
+    /// The AI players playing other than they were seated, by player name.
+    /// Not saved, the next deal works them out again.
+    temperaments: HashMap<String, PlayerType>,
+    /// The next AI's decision for the turns the current player could take.
+    /// Not saved.
+    precomputed: Precomputed,
+    /// How far an AI's turn searches ahead. Not saved.
+    ai_search: AiSearch,
+    rng: RngService,
+    #[cfg(feature = "scripting")]
+    house_rules: Option<Arc<HouseRules>>,
+}
+
+impl GameEngine {
+    /// Starts a game with `players` seated in the given order and deals the first round.
+    pub fn new(players: Vec<Player>) -> Result<Self, RummyError> {
+        Self::with_deck(players, DeckConfig::default())
+    }
+
+    /// Like `new`, dealing from the deck `deck_config` describes.
+    pub fn with_deck(players: Vec<Player>, deck_config: DeckConfig) -> Result<Self, RummyError> {
+        Self::with_variant(players, deck_config, Variant::default())
+    }
+
+    /// Like `with_deck`, playing `variant`.
+    pub fn with_variant(
+        players: Vec<Player>,
+        deck_config: DeckConfig,
+        variant: Variant,
+    ) -> Result<Self, RummyError> {
+        let mut engine = Self::pre_game(players, deck_config, variant)?;
+        engine.deal_round()?;
+
+        Ok(engine)
+    }
+
+    /// Seats `players` without dealing, so the table can decide who goes first
+    /// with `decide_turn_order` while everyone watches.
+    pub fn pre_game(
+        players: Vec<Player>,
+        deck_config: DeckConfig,
+        variant: Variant,
+    ) -> Result<Self, RummyError> {
+        if players.len() < 2 {
+            return Err(RummyError::Config(
+                "A game needs at least 2 players".to_string(),
+            ));
+        }
+
+        // Everyone's hand, a card to turn over and at least one to draw
+        let deck = deck_config.cards()?;
+        if deck.len() < players.len() * variant.hand_size() + 2 {
+            return Err(RummyError::Config(format!(
+                "A deck of {} cards is too small for {} players",
+                deck.len(),
+                players.len()
+            )));
+        }
+
+        Ok(Self {
+            players,
+            deck: Deck::new(deck),
+            current_player_idx: 0,
+            target_score: variant.target_score(),
+            deck_config,
+            variant,
+            phase: TurnPhase::Seating,
+            layoff: None,
+            last_round: None,
+            score_sheet: ScoreSheet::default(),
+            called_winner: None,
+            declarations: false,
+            rituals: DealRituals::default(),
+            deal_plan: None,
+            rules: GameRules::default(),
+            declare: None,
+            staged: None,
+            turns: 0,
+            deal_hashes: Vec::new(),
+            events: EventSinks::default(),
+            opponents: OpponentModel::default(),
+            strategies: HashMap::new(),
+            temperament_policy: None,
+            temperaments: HashMap::new(),
+            precomputed: Precomputed::default(),
+            ai_search: AiSearch::Off,
+            rng: RngService::default(),
+            #[cfg(feature = "scripting")]
+            house_rules: None,
+        })
+    }
+
+    /// Decides who goes first the `turn_order` way, seats everyone in playing
+    /// order and deals the first round. Returns the ceremony's events, which
+    /// are emitted as well, so a front-end can play it out.
+    pub fn decide_turn_order(
+        &mut self,
+        turn_order: TurnOrder,
+    ) -> Result<Vec<GameEvent>, RummyError> {
+        // Seating ends the quiz, if there was one
+        if self.phase == TurnPhase::Quiz {
+            self.phase = TurnPhase::Seating;
+        }
+        self.expect_phase(TurnPhase::Seating)?;
+
+        let mut rng = self.rng.fork(RngStream::Seating);
+        let mut ceremony = Vec::new();
+
+        let first = match turn_order {
+            TurnOrder::Shuffle => {
+                self.players.shuffle(&mut rng);
+                0
+            }
+            TurnOrder::HighCard => {
+                let mut deck = build_deck_with(&self.deck_config, &mut rng)?;
+                let mut contenders: Vec<usize> = (0..self.players.len()).collect();
+
+                // Whoever ties for the highest card draws again
+                while contenders.len() > 1 {
+                    let mut draws = Vec::with_capacity(contenders.len());
+                    for &idx in &contenders {
+                        if deck.is_empty() {
+                            deck = build_deck_with(&self.deck_config, &mut rng)?;
+                        }
+                        let card = deck.pop_back().ok_or(RummyError::EmptyDeck)?;
+                        ceremony.push(GameEvent::SeatCardDrawn {
+                            player: self.players[idx].name.clone(),
+                            card,
+                        });
+                        draws.push((idx, card));
+                    }
+
+                    // Cards order by rank alone, so suites don't break ties
+                    let highest = draws.iter().map(|(_, card)| *card).max();
+                    contenders = draws
+                        .into_iter()
+                        .filter(|(_, card)| highest.is_some_and(|high| card.cmp(&high).is_eq()))
+                        .map(|(idx, _)| idx)
+                        .collect();
+                }
+
+                contenders[0]
+            }
+            TurnOrder::DealerCut => {
+                let dealer = rng.random_range(0..self.players.len());
+                let card = build_deck_with(&self.deck_config, &mut rng)?
+                    .pop_back()
+                    .ok_or(RummyError::EmptyDeck)?;
+                ceremony.push(GameEvent::DeckCut {
+                    player: self.players[dealer].name.clone(),
+                    card,
+                });
+
+                (dealer + card.rank.to_u64()? as usize) % self.players.len()
+            }
+        };
+
+        // Play carries on around the table from whoever goes first
+        self.players.rotate_left(first);
+        ceremony.push(GameEvent::TurnOrderDecided {
+            player: self.players[0].name.clone(),
+            order: self.players.iter().map(|p| p.name.clone()).collect(),
+        });
+
+        for event in &ceremony {
+            self.events.emit(event);
+        }
+
+        self.current_player_idx = 0;
+        self.deal_round()?;
+
+        Ok(ceremony)
+    }
+
+    /// Picks a game back up from `snapshot`. Nobody is subscribed yet.
+    pub fn restore(snapshot: EngineSnapshot) -> Result<Self, RummyError> {
+        if snapshot.players.len() < 2 {
+            return Err(RummyError::Config(
+                "A game needs at least 2 players".to_string(),
+            ));
+        }
+        if snapshot.current_player_idx >= snapshot.players.len() {
+            return Err(RummyError::Config(format!(
+                "Seat {} isn't at the table",
+                snapshot.current_player_idx
+            )));
+        }
+        let hand_size = snapshot.variant.hand_size();
+        if snapshot
+            .players
+            .iter()
+            .any(|p| p.hand.cards.len() != hand_size)
+        {
+            return Err(RummyError::Config(format!(
+                "Every player should be holding {hand_size} cards between turns"
+            )));
+        }
+
+        let score_sheet = if snapshot.score_sheet.is_empty() {
+            ScoreSheet::new(&snapshot.players)
+        } else {
+            snapshot.score_sheet
+        };
+        Ok(Self {
+            players: snapshot.players,
+            deck: Deck {
+                draw_pile: snapshot.draw_pile,
+                discard_pile: snapshot.discard_pile,
+                burned: snapshot.burned,
+                reshuffles: snapshot.reshuffles,
+            },
+            current_player_idx: snapshot.current_player_idx,
+            target_score: snapshot.target_score,
+            deck_config: snapshot.deck_config,
+            variant: snapshot.variant,
+            phase: TurnPhase::Choosing,
+            layoff: None,
+            last_round: None,
+            score_sheet,
+            called_winner: None,
+            declarations: snapshot.declarations,
+            rituals: snapshot.rituals,
+            deal_plan: None,
+            rules: snapshot.rules,
+            declare: None,
+            staged: None,
+            turns: snapshot.turns,
+            deal_hashes: snapshot.deal_hashes,
+            events: EventSinks::default(),
+            opponents: OpponentModel::default(),
+            strategies: HashMap::new(),
+            temperament_policy: None,
+            temperaments: HashMap::new(),
+            precomputed: Precomputed::default(),
+            ai_search: AiSearch::Off,
+            rng: snapshot
+                .seed
+                .map_or_else(RngService::default, RngService::seeded),
+            #[cfg(feature = "scripting")]
+            house_rules: None,
+        })
+    }
+
+    /// The game as it stands, for saving. Games are only saved between turns,
+    /// so this fails in the middle of one.
+    pub fn snapshot(&self) -> Result<EngineSnapshot, RummyError> {
+        self.expect_phase(TurnPhase::Choosing)?;
+
+        Ok(EngineSnapshot {
+            players: self.players.clone(),
+            draw_pile: self.deck.draw_pile.clone(),
+            discard_pile: self.deck.discard_pile.clone(),
+            current_player_idx: self.current_player_idx,
+            target_score: self.target_score,
+            deck_config: self.deck_config.clone(),
+            variant: self.variant,
+            declarations: self.declarations,
+            rituals: self.rituals,
+            burned: self.deck.burned.clone(),
+            turns: self.turns,
+            rules: self.rules,
+            reshuffles: self.deck.reshuffles,
+            score_sheet: self.score_sheet.clone(),
+            seed: Some(self.rng.seed()),
+            deal_hashes: self.deal_hashes.clone(),
+        })
+    }
+
+    /// Seeds everything random in the game from here on. Seeded before the
+    /// first deal, the same seed plays out the same game.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = RngService::seeded(seed);
+    }
+
+    /// The seed the game is played from and the hash of every deal so far,
+    /// to prove the deals came from it.
+    pub fn disclosure(&self) -> SeedDisclosure {
+        SeedDisclosure {
+            seed: self.rng.seed(),
+            deal_hashes: self.deal_hashes.clone(),
+        }
+    }
+
+    /// The game's randomness, for anything the front-end does by chance.
+    pub fn rng(&mut self) -> &mut RngService {
+        &mut self.rng
+    }
+
+    /// Plays the house rule that lets anyone declare out of turn on a discard
+    /// that completes a winning meld for them, see `Variant::declaration`.
+    pub fn allow_declarations(&mut self, allowed: bool) {
+        self.declarations = allowed;
+    }
+
+    pub fn declarations_allowed(&self) -> bool {
+        self.declarations
+    }
+
+    /// Has the dealer cut the deck or burn a card, or both, before every deal.
+    pub fn set_deal_rituals(&mut self, rituals: DealRituals) {
+        self.rituals = rituals;
+    }
+
+    /// Rigs every deal from here on the way `plan` says. A rigged deal isn't
+    /// cut or burned.
+    pub fn set_deal_plan(&mut self, plan: DealPlan) {
+        self.deal_plan = Some(plan);
+    }
+
+    pub fn rituals(&self) -> DealRituals {
+        self.rituals
+    }
+
+    /// Plays by `rules`, whichever the variant.
+    pub fn set_rules(&mut self, rules: GameRules) {
+        self.rules = rules;
+    }
+
+    pub fn rules(&self) -> GameRules {
+        self.rules
+    }
+
+    /// Has `strategy` play `player`'s turns in place of their player type.
+    /// They still need a player type to be seated as an AI.
+    pub fn register_strategy(
+        &mut self,
+        player: &str,
+        strategy: Arc<dyn Strategy>,
+    ) -> Result<(), RummyError> {
+        if !self.players.iter().any(|p| p.name == player) {
+            return Err(RummyError::InvalidMove(format!(
+                "{player} isn't at the table"
+            )));
+        }
+        self.strategies.insert(player.to_string(), strategy);
+
+        Ok(())
+    }
+
+    /// How `players[player_idx]` plays: their registered strategy, or else
+    /// their player type's, as their temper has it. `None` for human players.
+    pub fn strategy(&self, player_idx: usize) -> Option<&dyn Strategy> {
+        let temperament = self.temperament(player_idx)?;
+
+        Some(match self.strategies.get(&self.players[player_idx].name) {
+            Some(strategy) => strategy.as_ref(),
+            None => temperament,
+        })
+    }
+
+    /// Has every AI turn search ahead as far as `search` says. They don't
+    /// search at all unless told to.
+    pub fn set_ai_search(&mut self, search: AiSearch) {
+        self.ai_search = search;
+    }
+
+    /// Lets the AI players' temper drift with the score from the next deal
+    /// on, as `policy` says.
+    pub fn set_temperament_policy(&mut self, policy: TemperamentPolicy) {
+        self.temperament_policy = Some(policy);
+    }
+
+    /// How the AI `players[player_idx]` plays for now: as they were seated,
+    /// unless the score's tilted them. `None` for human players.
+    pub fn temperament(&self, player_idx: usize) -> Option<&PlayerType> {
+        let player = &self.players[player_idx];
+        let seated = player.player_type.as_ref()?;
+        Some(self.temperaments.get(&player.name).unwrap_or(seated))
+    }
+
+    /// Works out every AI player's temper for the round just dealt, and
+    /// emits a `TemperamentShifted` for anyone's that's changed. Seats a bot
+    /// plays are left alone.
+    fn drift_temperaments(&mut self) {
+        let Some(policy) = self.temperament_policy else {
+            return;
+        };
+
+        for player_idx in 0..self.players.len() {
+            let player = &self.players[player_idx];
+            let Some(seated) = player.player_type.as_ref() else {
+                continue;
+            };
+            if self.strategies.contains_key(&player.name) {
+                continue;
+            }
+
+            let context = self.standing(player_idx);
+            let temperament = policy.temperament(seated, &context);
+            if self.temperament(player_idx) == Some(&temperament) {
+                continue;
+            }
+
+            let name = player.name.clone();
+            if temperament == *seated {
+                self.temperaments.remove(&name);
+            } else {
+                self.temperaments.insert(name.clone(), temperament.clone());
+            }
+            self.publish(GameEvent::TemperamentShifted {
+                player: name,
+                temperament,
+            });
+        }
+    }
+
+    /// Sends every event from now on to `sink` as well.
+    pub fn subscribe(&mut self, sink: SharedSink) {
+        self.events.subscribe(sink);
+    }
+
+    /// Tells the subscribers about something that happened outside the
+    /// engine's own moves, like table talk.
+    pub fn emit(&self, event: GameEvent) {
+        self.events.emit(&event);
+    }
+
+    /// Runs `rules`' hooks on every move from now on.
+    #[cfg(feature = "scripting")]
+    pub fn set_house_rules(&mut self, rules: Arc<HouseRules>) {
+        self.house_rules = Some(rules);
+    }
+
+    /// Emits one of the engine's own moves, and applies any house rules it sets off.
+    fn publish(&mut self, event: GameEvent) {
+        self.opponents.emit(&event);
+        self.events.emit(&event);
+
+        #[cfg(feature = "scripting")]
+        self.apply_house_rules(&event);
+    }
+
+    #[cfg(feature = "scripting")]
+    fn apply_house_rules(&mut self, event: &GameEvent) {
+        let Some(rules) = self.house_rules.clone() else {
+            return;
+        };
+
+        // A broken rule is owned up to at the table rather than ending the game
+        let effects = rules.on_event(event).unwrap_or_else(|err| {
+            vec![HouseRuleEffect::Say {
+                text: format!("set off a broken house rule: {err}"),
+            }]
+        });
+
+        let had_winner = self.winner().is_some();
+        for effect in effects {
+            let event = match effect {
+                HouseRuleEffect::Award { player, points } => {
+                    let Some(seat) = self.players.iter_mut().find(|p| p.name == player) else {
+                        continue;
+                    };
+                    seat.score = seat.score.saturating_add_signed(points as isize);
+                    GameEvent::ScoreAdjusted { player, points }
+                }
+                HouseRuleEffect::Say { text } => GameEvent::Announcement {
+                    player: event.player().to_string(),
+                    text,
+                },
+            };
+            self.events.emit(&event);
+        }
+
+        // Points awarded mid-round can end the game too. A round's winner is
+        // announced by `finish_round` once its hooks are done.
+        if !had_winner
+            && !matches!(event, GameEvent::RoundWon { .. })
+            && let Some(seat) = self.winner_seat()
+        {
+            let player = self.players[seat].name.clone();
+            self.events.emit(&GameEvent::GameWon {
+                player: player.clone(),
+                score: self.team_score(seat),
+            });
+            self.events.emit(&GameEvent::SeedDisclosed {
+                player,
+                disclosure: self.disclosure(),
+            });
+        }
+    }
+
+    pub fn phase(&self) -> &TurnPhase {
+        &self.phase
+    }
+
+    pub fn current_player(&self) -> &Player {
+        &self.players[self.current_player_idx]
+    }
+
+    pub fn layoff_round(&self) -> Option<&LayoffRound> {
+        self.layoff.as_ref()
+    }
+
+    /// How the last finished round was won, layoff by layoff.
+    pub fn last_round(&self) -> Option<&RoundSummary> {
+        self.last_round.as_ref()
+    }
+
+    /// What every finished round added to each player's score.
+    pub fn score_sheet(&self) -> &ScoreSheet {
+        &self.score_sheet
+    }
+
+    /// The discard waiting on declarations, if there is one.
+    pub fn declare_window(&self) -> Option<&DeclareWindow> {
+        self.declare.as_ref()
+    }
+
+    pub fn top_discard(&self) -> Option<&Card> {
+        self.deck.peek_discard()
+    }
+
+    /// Every card in the discard pile, the top one first. The pile's
+    /// face-up, so anyone at the table may look through it. Burned cards
+    /// aren't in it.
+    pub fn visible_discards(&self) -> Vec<Card> {
+        self.deck.discard_pile.iter().rev().copied().collect()
+    }
+
+    /// The player the engine is waiting on, including during a layoff round.
+    pub fn acting_player_idx(&self) -> usize {
+        match &self.layoff {
+            Some(layoff) => layoff.current_idx,
+            None => self.current_player_idx,
+        }
+    }
+
+    /// The player who still has to lay off, or `None` once everyone has had a go.
+    pub fn layoff_player_idx(&self) -> Option<usize> {
+        self.layoff
+            .as_ref()
+            .filter(|layoff| layoff.current_idx != layoff.winner_idx)
+            .map(|layoff| layoff.current_idx)
+    }
+
+    /// Where the match stands for `players[player_idx]`, and which of the
+    /// cards they hold or could pick up would be dangerous to throw away.
+    pub fn match_context(&self, player_idx: usize) -> MatchContext {
+        let player = &self.players[player_idx];
+        let mut cards = player.hand.cards.clone();
+        cards.extend(self.top_discard());
+
+        let mut side = vec![player.name.as_str()];
+        side.extend(
+            self.partner(player_idx)
+                .map(|p| self.players[p].name.as_str()),
+        );
+
+        MatchContext {
+            discard_danger: self.opponents.dangers(&side, &cards),
+            seed: self.rng.seed_of(RngStream::Ai),
+            ..self.standing(player_idx)
+        }
+    }
+
+    /// The scores as `players[player_idx]` sees them, pooled with their
+    /// partner's when the table plays in teams.
+    fn standing(&self, player_idx: usize) -> MatchContext {
+        MatchContext::for_team(&self.players, player_idx, self.target_score, |seat| {
+            self.partner(seat)
+        })
+    }
+
+    /// What everyone's given away about their hand this round.
+    pub fn opponents(&self) -> &OpponentModel {
+        &self.opponents
+    }
+
+    /// What `players[player_idx]` knows for certain about where the cards
+    /// are: their hand, the discard pile and what their opponents have
+    /// picked up off it and still hold.
+    pub fn known_information(&self, player_idx: usize) -> KnownInformation {
+        let player = &self.players[player_idx];
+        let picked_up = self
+            .players
+            .iter()
+            .filter(|opponent| opponent.name != player.name)
+            .filter_map(|opponent| self.opponents.profile(&opponent.name))
+            .flat_map(|profile| profile.still_held())
+            .collect();
+
+        KnownInformation {
+            hand: player.hand.cards.clone(),
+            discards: self.deck.discard_pile.iter().copied().collect(),
+            picked_up,
+        }
+    }
+
+    /// The cards `players[player_idx]` could draw as far as they know, for
+    /// the analysis: everything they don't know to be somewhere else. The
+    /// draw pile itself is face-down.
+    fn possible_cards(&self, player_idx: usize) -> Vec<Card> {
+        self.known_information(player_idx)
+            .unknown(&self.deck_config)
+            .expect("the deck was dealt from its config")
+    }
+
+    /// A search from `hand` for the current player, drawing from whatever
+    /// they don't know to be somewhere else.
+    fn node(&self, hand: Hand) -> Node {
+        Node::for_deck(
+            hand,
+            &self.known_information(self.current_player_idx),
+            &self.deck_config,
+        )
+        .expect("the deck was dealt from its config")
+    }
+
+    /// Whoever the game was called for, or the first player to reach the
+    /// target score, if any. Playing in teams, it's the first of the team
+    /// whose pooled score reaches it.
+    pub fn winner(&self) -> Option<&Player> {
+        self.winner_seat().map(|seat| &self.players[seat])
+    }
+
+    fn winner_seat(&self) -> Option<usize> {
+        self.called_winner.or_else(|| {
+            (0..self.players.len()).find(|&seat| self.team_score(seat) >= self.target_score)
+        })
+    }
+
+    /// `seat`'s partner when the table plays in teams, whoever sits across
+    /// from them. `None` without teams, or without an even number of
+    /// players, four or more, to pair up.
+    pub fn partner(&self, seat: usize) -> Option<usize> {
+        let seats = self.players.len();
+        (self.rules.teams && seats >= 4 && seats.is_multiple_of(2))
+            .then_some((seat + seats / 2) % seats)
+    }
+
+    /// `seat`'s score, pooled with their partner's when the table plays in
+    /// teams.
+    pub fn team_score(&self, seat: usize) -> usize {
+        let partner = self
+            .partner(seat)
+            .map_or(0, |partner| self.players[partner].score);
+        self.players[seat].score + partner
+    }
+
+    /// Ends the game with `player` as its winner whatever the scores, say
+    /// when the table's playing for chips and someone's been felted. Emits
+    /// `GameWon`.
+    pub fn call_game(&mut self, player: &str) -> Result<(), RummyError> {
+        if self.winner().is_some() {
+            return Err(RummyError::InvalidMove(
+                "The game's already won".to_string(),
+            ));
+        }
+        let seat = self
+            .players
+            .iter()
+            .position(|p| p.name == player)
+            .ok_or_else(|| RummyError::InvalidMove(format!("{player} isn't at the table")))?;
+        self.called_winner = Some(seat);
+        self.publish(GameEvent::GameWon {
+            player: player.to_string(),
+            score: self.team_score(seat),
+        });
+        self.publish(GameEvent::SeedDisclosed {
+            player: player.to_string(),
+            disclosure: self.disclosure(),
+        });
+        Ok(())
+    }
+
+    /// Deals the next round: every hand is cleared, the whole deck shuffled,
+    /// cut and burned as the rituals say and dealt out, and the top card
+    /// turned over to start the discard pile. Emits `RoundDealt`.
+    pub fn deal_round(&mut self) -> Result<(), RummyError> {
+        // Once the seats are settled and any head starts won
+        if self.score_sheet.is_empty() {
+            self.score_sheet = ScoreSheet::new(&self.players);
+        }
+
+        // Clear hands
+        for player in self.players.iter_mut() {
+            player.hand.cards.clear();
+        }
+
+        // Get all cards from deck and reshuffle
+        let mut all_cards = Vec::new();
+        all_cards.extend(self.deck.draw_pile.drain(..));
+        all_cards.extend(self.deck.discard_pile.drain(..));
+        all_cards.append(&mut self.deck.burned);
+        self.deck.reshuffles = 0;
+
+        // If not enough cards, create a new deck
+        let full_deck = self.deck_config.cards()?;
+        if all_cards.len() < full_deck.len() {
+            all_cards = full_deck;
+        }
+
+        let deals = self.deal_hashes.len() as u64;
+        self.rng.restart(RngStream::Deal, deals);
+        all_cards.shuffle(self.rng.stream(RngStream::Deal));
+        let deal_hash = rng::deal_hash(&all_cards);
+        self.deal_hashes.push(deal_hash);
+        tracing::info!(
+            deal = self.deal_hashes.len(),
+            deal_hash,
+            "dealing a new round"
+        );
+        self.deck.draw_pile = all_cards.into_iter().collect();
+        match self.deal_plan.clone() {
+            Some(plan) => self.stack_deck(&plan)?,
+            None => self.deal_rituals(),
+        }
+
+        let hand_size = self.variant.hand_size();
+        for player in self.players.iter_mut() {
+            for _ in 0..hand_size {
+                let card = self
+                    .deck
+                    .draw_pile
+                    .pop_back()
+                    .ok_or(RummyError::EmptyDeck)?;
+                player.hand.cards.push(card);
+            }
+        }
+
+        // Turn over one card for discard pile
+        if let Some(card) = self.deck.draw_pile.pop_back() {
+            self.deck.discard(card);
+        }
+
+        self.phase = TurnPhase::Choosing;
+        self.layoff = None;
+        self.declare = None;
+        self.staged = None;
+
+        self.publish(GameEvent::RoundDealt {
+            player: self.current_player().name.clone(),
+            hands: self
+                .players
+                .iter()
+                .map(|p| (p.name.clone(), p.hand.cards.clone()))
+                .collect(),
+            deal_hash,
+        });
+        self.drift_temperaments();
+
+        Ok(())
+    }
+
+    /// Stacks the shuffled draw pile for the deal the way `plan` says: the
+    /// planned hands to their players and the stacked cards off the top, in
+    /// the order they're dealt, with the rest of the shuffle under them.
+    fn stack_deck(&mut self, plan: &DealPlan) -> Result<(), RummyError> {
+        let hand_size = self.variant.hand_size();
+        let mut rest: Vec<Card> = self.deck.draw_pile.drain(..).collect();
+        let mut take = |card: &Card| {
+            if remove_one(&mut rest, card) {
+                Ok(*card)
+            } else {
+                Err(RummyError::Config(format!(
+                    "There's no {card} left in the deck to deal"
+                )))
+            }
+        };
+
+        let mut hands = vec![None; self.players.len()];
+        for (name, hand) in &plan.hands {
+            let seat = self
+                .players
+                .iter()
+                .position(|p| p.name == *name)
+                .ok_or_else(|| RummyError::Config(format!("{name} isn't at the table")))?;
+            if hand.len() != hand_size {
+                return Err(RummyError::Config(format!(
+                    "{name}'s hand has {} cards, {hand_size} are dealt",
+                    hand.len()
+                )));
+            }
+            hands[seat] = Some(hand.iter().map(&mut take).collect::<Result<Vec<_>, _>>()?);
+        }
+        let mut stack: VecDeque<Card> = plan.stack.iter().map(take).collect::<Result<_, _>>()?;
+
+        let mut dealt = Vec::new();
+        for hand in hands {
+            match hand {
+                Some(hand) => dealt.extend(hand),
+                None => {
+                    for _ in 0..hand_size {
+                        let card = stack
+                            .pop_front()
+                            .or_else(|| rest.pop())
+                            .ok_or(RummyError::EmptyDeck)?;
+                        dealt.push(card);
+                    }
+                }
+            }
+        }
+        dealt.extend(stack);
+
+        // The top of the pile is its back
+        rest.extend(dealt.into_iter().rev());
+        self.deck.draw_pile = rest.into();
+        Ok(())
+    }
+
+    /// Cuts the shuffled deck and burns its top card, as the table's rituals
+    /// say, before it's dealt. The dealer sits before whoever goes first.
+    fn deal_rituals(&mut self) {
+        let num_players = self.players.len();
+        let dealer = self.players[(self.current_player_idx + num_players - 1) % num_players]
+            .name
+            .clone();
+
+        if let Some(cut) = self.rituals.cut {
+            let position = match cut {
+                CutPosition::Random => {
+                    let len = self.deck.draw_pile.len().max(2);
+                    self.rng.stream(RngStream::Deal).random_range(1..len)
+                }
+                CutPosition::At(position) => position,
+            };
+            let position = self.deck.cut(position);
+            self.publish(GameEvent::DeckCutForDeal {
+                player: dealer.clone(),
+                position,
+            });
+        }
+
+        if self.rituals.burn
+            && let Some(card) = self.deck.burn()
+        {
+            self.publish(GameEvent::CardBurned {
+                player: dealer,
+                card,
+            });
+        }
+    }
+
+    /// Opens the book quiz, which has to come before the first deal.
+    pub fn start_quiz(&mut self) -> Result<(), RummyError> {
+        self.expect_phase(TurnPhase::Seating)?;
+        self.phase = TurnPhase::Quiz;
+
+        Ok(())
+    }
+
+    /// Gives `player` their head start for getting `correct` of `asked` quiz
+    /// questions right. Returns the points awarded.
+    pub fn score_quiz(
+        &mut self,
+        player: &str,
+        correct: usize,
+        asked: usize,
+    ) -> Result<usize, RummyError> {
+        self.expect_phase(TurnPhase::Quiz)?;
+        let correct = correct.min(asked);
+        let points = quiz_bonus(correct);
+
+        let seat = self
+            .players
+            .iter_mut()
+            .find(|p| p.name == player)
+            .ok_or_else(|| RummyError::InvalidMove(format!("{player} isn't at the table")))?;
+        seat.score += points;
+
+        self.publish(GameEvent::QuizScored {
+            player: player.to_string(),
+            correct,
+            asked,
+            points,
+        });
+
+        Ok(points)
+    }
+
+    /// Calls off the round in play and deals it again, for when something's
+    /// gone wrong at the table, like a misdeal. Nobody scores for it and the
+    /// same player keeps the turn. `player` is whoever called for it.
+    pub fn redeal(&mut self, player: &str) -> Result<(), RummyError> {
+        if matches!(self.phase, TurnPhase::Seating | TurnPhase::Quiz) {
+            return Err(RummyError::InvalidMove(
+                "There's no round to redeal yet".to_string(),
+            ));
+        }
+        if !self.players.iter().any(|p| p.name == player) {
+            return Err(RummyError::InvalidMove(format!(
+                "{player} isn't at the table"
+            )));
+        }
+
+        self.publish(GameEvent::RoundRedealt {
+            player: player.to_string(),
+        });
+        self.deal_round()
+    }
+
+    /// The current player draws from the draw pile, reshuffling the discards if it's empty.
+    pub fn draw(&mut self) -> Result<Card, RummyError> {
+        self.expect_phase(TurnPhase::Choosing)?;
+
+        let card = self.deck.draw(self.rng.stream(RngStream::Deal))?;
+        self.players[self.current_player_idx].hand.cards.push(card);
+        self.phase = TurnPhase::Discarding;
+        self.record(Choice::Draw, Some(card));
+        self.publish(GameEvent::CardDrawn {
+            player: self.current_player().name.clone(),
+            retrieved: None,
+        });
+
+        Ok(card)
+    }
+
+    /// The current player takes the top card of the discard pile.
+    pub fn retrieve(&mut self) -> Result<Card, RummyError> {
+        self.expect_phase(TurnPhase::Choosing)?;
+
+        let card = self
+            .deck
+            .take_discard()
+            .ok_or_else(|| RummyError::InvalidMove("The discard pile is empty".to_string()))?;
+        self.players[self.current_player_idx].hand.cards.push(card);
+        self.phase = TurnPhase::Discarding;
+        self.record(Choice::Retrieve, Some(card));
+        self.publish(GameEvent::CardDrawn {
+            player: self.current_player().name.clone(),
+            retrieved: Some(card),
+        });
+
+        Ok(card)
+    }
+
+    /// The current player discards `card`, which ends their turn.
+    pub fn discard(&mut self, card: Card) -> Result<(), RummyError> {
+        self.expect_phase(TurnPhase::Discarding)?;
+
+        let hand = &mut self.players[self.current_player_idx].hand.cards;
+        let idx = hand
+            .iter()
+            .position(|c| *c == card)
+            .ok_or_else(|| RummyError::InvalidMove("You don't have that card!".to_string()))?;
+        hand.remove(idx);
+        self.deck.discard(card);
+        if let Some(action) = self.players[self.current_player_idx].actions.back_mut() {
+            action.card_to_discard = Some(card);
+        }
+        self.turns += 1;
+        self.publish(GameEvent::CardDiscarded {
+            player: self.current_player().name.clone(),
+            card,
+        });
+
+        self.phase = TurnPhase::Choosing;
+        if self.deck.draw_pile.is_empty()
+            && self
+                .rules
+                .dead_hand_after
+                .is_some_and(|most| self.deck.reshuffles >= most)
+        {
+            return self.call_dead_hand();
+        }
+        if !self.deck.can_draw() {
+            return self.end_in_stalemate();
+        }
+        self.open_declarations(card);
+        self.advance_turn();
+
+        Ok(())
+    }
+
+    /// Ends the round with nothing left for the next player to draw. Nobody
+    /// scores, and the cards are dealt again for them to start.
+    fn end_in_stalemate(&mut self) -> Result<(), RummyError> {
+        self.publish(GameEvent::RoundStalemate {
+            player: self.current_player().name.clone(),
+        });
+        self.score_sheet.end_round(&self.players);
+        self.advance_turn();
+        self.deal_round()
+    }
+
+    /// Calls the round once the draw pile's run out again after as many
+    /// reshuffles as the rules allow. Every hand is shown, and the best meld
+    /// among them takes the round for a share of its score, the first round
+    /// the table from the next player winning a tie. Nobody scores if nobody
+    /// melds. The next player starts the new deal.
+    fn call_dead_hand(&mut self) -> Result<(), RummyError> {
+        self.publish(GameEvent::DeadHand {
+            player: self.current_player().name.clone(),
+            reshuffles: self.deck.reshuffles,
+            hands: self
+                .players
+                .iter()
+                .map(|p| (p.name.clone(), p.hand.cards.clone()))
+                .collect(),
+        });
+
+        let seats = self.players.len();
+        let mut best: Option<(usize, u64, Hand)> = None;
+        for offset in 1..=seats {
+            let idx = (self.current_player_idx + offset) % seats;
+            let (score, meld) = self.variant.best_meld(&self.players[idx].hand);
+            if score > best.as_ref().map_or(0, |(_, best, _)| *best) {
+                best = Some((idx, score, meld));
+            }
+        }
+        if let Some((idx, score, hand)) = best {
+            self.award(RoundOutcome {
+                winner: self.players[idx].clone(),
+                score: score * self.rules.dead_hand_percent / 100,
+                hand,
+            });
+        }
+        self.score_sheet.end_round(&self.players);
+
+        self.advance_turn();
+        self.deal_round()
+    }
+
+    /// Starts the current player's turn without playing it yet. A retrieve
+    /// only shows them the card, a draw has to take it off the pile. Returns
+    /// the card taken.
+    pub fn stage(&mut self, choice: Choice) -> Result<Card, RummyError> {
+        self.expect_phase(TurnPhase::Choosing)?;
+        if self.staged.is_some() {
+            return Err(RummyError::InvalidMove(
+                "There's a turn waiting to be confirmed".to_string(),
+            ));
+        }
+
+        let taken = match choice {
+            Choice::Draw => self.draw()?,
+            Choice::Retrieve => *self
+                .top_discard()
+                .ok_or_else(|| RummyError::InvalidMove("The discard pile is empty".to_string()))?,
+            Choice::Play | Choice::Knock => {
+                return Err(RummyError::InvalidMove(
+                    "Playing your hand can't be taken back".to_string(),
+                ));
+            }
+        };
+        self.staged = Some(StagedTurn {
+            choice,
+            taken,
+            discard: None,
+        });
+
+        Ok(taken)
+    }
+
+    /// Picks the card the staged turn throws away.
+    pub fn stage_discard(&mut self, card: Card) -> Result<(), RummyError> {
+        let hand = self.staged_hand().ok_or_else(no_staged_turn)?;
+        if !hand.cards.contains(&card) {
+            return Err(RummyError::InvalidMove(
+                "You don't have that card!".to_string(),
+            ));
+        }
+        if let Some(staged) = &mut self.staged {
+            staged.discard = Some(card);
+        }
+
+        Ok(())
+    }
+
+    pub fn staged(&self) -> Option<&StagedTurn> {
+        self.staged.as_ref()
+    }
+
+    /// The current player's hand as it'll be once the staged turn's played.
+    pub fn staged_hand(&self) -> Option<Hand> {
+        let staged = self.staged.as_ref()?;
+        let mut hand = self.current_player().hand.clone();
+        if matches!(staged.choice, Choice::Retrieve) {
+            hand.cards.push(staged.taken);
+        }
+        if let Some(card) = staged.discard {
+            remove_one(&mut hand.cards, &card);
+        }
+
+        Some(hand)
+    }
+
+    /// Takes back the last step of the staged turn: the discard, and then a
+    /// retrieve. A drawn card has been seen and can't go back on the pile.
+    pub fn undo(&mut self) -> Result<(), RummyError> {
+        let staged = self.staged.as_mut().ok_or_else(no_staged_turn)?;
+
+        if staged.discard.take().is_some() {
+            return Ok(());
+        }
+        match staged.choice {
+            Choice::Retrieve => {
+                self.staged = None;
+                Ok(())
+            }
+            _ => Err(RummyError::InvalidMove(
+                "You've seen the card you drew, it can't go back".to_string(),
+            )),
+        }
+    }
+
+    /// Plays the staged turn, which ends it.
+    pub fn commit(&mut self) -> Result<(), RummyError> {
+        let staged = self.staged.as_ref().ok_or_else(no_staged_turn)?;
+        let discard = staged
+            .discard
+            .ok_or_else(|| RummyError::InvalidMove("Pick a card to discard first".to_string()))?;
+
+        if matches!(staged.choice, Choice::Retrieve) {
+            self.retrieve()?;
+        }
+        self.staged = None;
+        self.discard(discard)
+    }
+
+    /// Which turn of the game is being played, counting from 1.
+    pub fn turn(&self) -> usize {
+        self.turns + 1
+    }
+
+    /// Adds the current player's choice this turn to their history.
+    fn record(&mut self, choice: Choice, drawn: Option<Card>) {
+        let turn = self.turn();
+        self.players[self.current_player_idx]
+            .actions
+            .push_back(ActionHistory {
+                choice,
+                card_to_discard: None,
+                drawn,
+                turn,
+            });
+    }
+
+    /// Gives everyone `card` completes a winning meld for the chance to
+    /// declare on it, if the table plays declarations. Called by the
+    /// discarder's turn, before it passes on.
+    fn open_declarations(&mut self, card: Card) {
+        if !self.declarations {
+            return;
+        }
+
+        let discarded_by = self.current_player_idx;
+        let seats = self.players.len();
+        let eligible: Vec<usize> = (1..seats)
+            .map(|offset| (discarded_by + offset) % seats)
+            .filter(|&idx| {
+                self.variant
+                    .declaration(&self.players[idx].hand, card)
+                    .is_some()
+            })
+            .collect();
+        if eligible.is_empty() {
+            return;
+        }
+
+        self.declare = Some(DeclareWindow {
+            discarded_by,
+            card,
+            eligible,
+            declared: Vec::new(),
+            passed: Vec::new(),
+        });
+        self.phase = TurnPhase::Declaring;
+    }
+
+    /// `player_idx` declares on the discard waiting on declarations. Everyone
+    /// can answer at once, and whoever's first around the table from the
+    /// discarder gets the card: a declaration stands once everyone ahead of
+    /// them has passed, and then their hand is played.
+    pub fn declare(&mut self, player_idx: usize) -> Result<(), RummyError> {
+        self.answer_declaration(player_idx, true)
+    }
+
+    /// `player_idx` lets the discard waiting on declarations go by.
+    pub fn pass_declaration(&mut self, player_idx: usize) -> Result<(), RummyError> {
+        self.answer_declaration(player_idx, false)
+    }
+
+    /// Closes declarations when time's up. Anyone who hasn't answered passes.
+    pub fn close_declarations(&mut self) -> Result<(), RummyError> {
+        self.expect_phase(TurnPhase::Declaring)?;
+
+        if let Some(window) = self.declare.as_mut() {
+            let unanswered: Vec<usize> = window
+                .eligible
+                .iter()
+                .copied()
+                .filter(|&idx| window.is_open_to(idx))
+                .collect();
+            window.passed.extend(unanswered);
+        }
+
+        self.settle_declarations()
+    }
+
+    /// Has every AI player declarations are waiting on answer. The card
+    /// completes a winning meld for them, and an AI never lets one go by.
+    pub fn ai_declarations(&mut self) -> Result<(), RummyError> {
+        self.expect_phase(TurnPhase::Declaring)?;
+
+        let waiting: Vec<usize> = self
+            .declare
+            .iter()
+            .flat_map(|window| window.eligible.iter().copied())
+            .filter(|&idx| self.players[idx].player_type.is_some())
+            .collect();
+        for idx in waiting {
+            if self.phase != TurnPhase::Declaring {
+                break;
+            }
+            if self.declare.as_ref().is_some_and(|w| w.is_open_to(idx)) {
+                self.declare(idx)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn answer_declaration(&mut self, player_idx: usize, declaring: bool) -> Result<(), RummyError> {
+        self.expect_phase(TurnPhase::Declaring)?;
+
+        let window = self
+            .declare
+            .as_mut()
+            .filter(|window| window.is_open_to(player_idx))
+            .ok_or_else(|| RummyError::InvalidMove("You can't declare on that card".to_string()))?;
+        if declaring {
+            window.declared.push(player_idx);
+        } else {
+            window.passed.push(player_idx);
+        }
+
+        self.settle_declarations()
+    }
+
+    /// Hands the discard to the first declarer in line once nobody ahead of
+    /// them can still claim it, or carries on with play once everyone has
+    /// passed. Waits while neither is settled.
+    fn settle_declarations(&mut self) -> Result<(), RummyError> {
+        let Some(window) = &self.declare else {
+            return Ok(());
+        };
+
+        let mut declarer = None;
+        for &idx in &window.eligible {
+            if window.declared.contains(&idx) {
+                declarer = Some(idx);
+                break;
+            }
+            if !window.passed.contains(&idx) {
+                return Ok(());
+            }
+        }
+
+        let card = window.card;
+        self.declare = None;
+        self.phase = TurnPhase::Choosing;
+        match declarer {
+            Some(idx) => self.take_declaration(idx, card),
+            None => Ok(()),
+        }
+    }
+
+    /// `player_idx` takes the discard `card`, throws away a card their meld
+    /// doesn't need and plays out of turn. Play carries on from them.
+    fn take_declaration(&mut self, player_idx: usize, card: Card) -> Result<(), RummyError> {
+        let (hand, discarded) = self
+            .variant
+            .declaration(&self.players[player_idx].hand, card)
+            .ok_or_else(|| RummyError::InvalidMove(format!("The {card} doesn't make a meld")))?;
+
+        self.deck.take_discard();
+        self.deck.discard(discarded);
+        self.players[player_idx].hand = hand;
+        self.current_player_idx = player_idx;
+        self.publish(GameEvent::Declared {
+            player: self.current_player().name.clone(),
+            card,
+            discarded,
+        });
+
+        self.play().map(|_| ())
+    }
+
+    /// The current player plays their hand, which starts a layoff round.
+    ///
+    /// In gin this is a knock: the score is the knocker's deadwood, and with
+    /// no layoffs the round is ready for `finish_round` straight away.
+    pub fn play(&mut self) -> Result<(u64, Hand), RummyError> {
+        self.expect_phase(TurnPhase::Choosing)?;
+
+        let hand = &self.current_player().hand;
+        self.variant.check_play(hand)?;
+
+        let player = self.current_player().name.clone();
+        let (score, melded_hand, event, choice) = if self.variant.allows_layoffs() {
+            let (score, melded_hand) = self.variant.best_meld(hand);
+            let event = GameEvent::HandPlayed {
+                player,
+                hand: melded_hand.cards.clone(),
+                score,
+            };
+            (score, melded_hand, event, Choice::Play)
+        } else {
+            let (melded, deadwood) = rules::arrange_gin(&hand.cards);
+            let deadwood = rules::deadwood_points(&deadwood);
+            let event = GameEvent::Knocked {
+                player,
+                hand: melded.clone(),
+                deadwood,
+            };
+            (deadwood, Hand { cards: melded }, event, Choice::Knock)
+        };
+        self.record(choice, None);
+        self.turns += 1;
+
+        // Without layoffs nobody gets a layoff turn before the round is scored
+        let next_idx = if self.variant.allows_layoffs() {
+            (self.current_player_idx + 1) % self.players.len()
+        } else {
+            self.current_player_idx
+        };
+        self.layoff = Some(LayoffRound {
+            winner_idx: self.current_player_idx,
+            layoff_winner_idx: self.current_player_idx,
+            current_idx: next_idx,
+            winning_hand: melded_hand.clone(),
+            score_to_beat: score,
+            results: Vec::new(),
+            played_hand: melded_hand.clone(),
+            played_score: score,
+            steps: Vec::new(),
+        });
+        self.phase = TurnPhase::LayingOff;
+        self.publish(event);
+
+        Ok((score, melded_hand))
+    }
+
+    /// What laying `cards` off would make of the played hand, for the player
+    /// whose layoff turn it is, without laying them off.
+    pub fn check_layoff(&self, cards: &[Card]) -> Result<LayoffOutcome, RummyError> {
+        let (player_idx, layoff) = self.layoff_turn()?;
+        self.check_held(player_idx, cards)?;
+
+        Ok(layoff::validate_layoff(
+            &layoff.winning_hand,
+            cards,
+            layoff.score_to_beat,
+            self.variant,
+            self.rules,
+        )?)
+    }
+
+    /// The player whose layoff turn it is tries to lay `cards` off onto the
+    /// played hand. An empty slice passes, and so do cards that don't beat
+    /// it, though they're noted in the round's summary.
+    pub fn lay_off(&mut self, cards: &[Card]) -> Result<Option<LayOffResult>, RummyError> {
+        let (player_idx, layoff) = self.layoff_turn()?;
+        self.check_held(player_idx, cards)?;
+
+        let outcome = match layoff::validate_layoff(
+            &layoff.winning_hand,
+            cards,
+            layoff.score_to_beat,
+            self.variant,
+            self.rules,
+        ) {
+            Ok(outcome) => Some(outcome),
+            Err(LayoffError::NoCards | LayoffError::DoesNotBeat { .. }) => None,
+            Err(err) => return Err(err.into()),
+        };
+        let best_layoff = outcome.map(|outcome| LayOffResult {
+            player: self.players[player_idx].clone(),
+            cards_used: outcome.cards.len(),
+            cards_laid_off: outcome.cards,
+            resulting_hand: outcome.hand,
+            resulting_score: outcome.score,
+        });
+
+        self.record_layoff(player_idx, cards, best_layoff.clone());
+
+        Ok(best_layoff)
+    }
+
+    /// Lays off for an AI player whose layoff turn it is.
+    pub fn ai_lay_off(&mut self) -> Result<Option<LayOffResult>, RummyError> {
+        let (player_idx, layoff) = self.layoff_turn()?;
+
+        let result = check_for_layoff(
+            &self.players[player_idx],
+            &layoff.winning_hand,
+            layoff.score_to_beat,
+            self.variant,
+            self.rules,
+        )
+        // Taking a partner's meld on the tiebreak wins the team nothing
+        .filter(|result| {
+            self.partner(player_idx) != Some(layoff.layoff_winner_idx)
+                || result.resulting_score > layoff.score_to_beat
+        });
+
+        let cards = result
+            .as_ref()
+            .map(|result| result.cards_laid_off.clone())
+            .unwrap_or_default();
+        self.record_layoff(player_idx, &cards, result.clone());
+
+        Ok(result)
+    }
+
+    /// Scores the finished layoff round, deals the next one and passes the turn on.
+    pub fn finish_round(&mut self) -> Result<RoundOutcome, RummyError> {
+        self.expect_phase(TurnPhase::LayingOff)?;
+        if self.layoff_player_idx().is_some() {
+            return Err(RummyError::InvalidMove(
+                "Not everyone has laid off yet".to_string(),
+            ));
+        }
+
+        let Some(mut layoff) = self.layoff.take() else {
+            return Err(RummyError::InvalidMove(
+                "No hand has been played".to_string(),
+            ));
+        };
+        let played_by = layoff.winner_idx;
+        let played_hand = std::mem::take(&mut layoff.played_hand.cards);
+        let played_score = layoff.played_score;
+        let steps = std::mem::take(&mut layoff.steps);
+
+        let outcome = if !self.variant.allows_layoffs() {
+            self.knock_outcome(played_by)
+        } else if layoff.results.is_empty() {
+            RoundOutcome {
+                winner: self.players[played_by].clone(),
+                score: layoff.score_to_beat,
+                hand: layoff.winning_hand,
+            }
+        } else {
+            // The earliest layoff holds unless a later one outranks it
+            let rules = self.rules;
+            let winning_lay_off = layoff
+                .results
+                .into_iter()
+                .reduce(|best, next| {
+                    let outranks = rules.outranks(
+                        &next.resulting_hand.cards,
+                        next.resulting_score,
+                        &best.resulting_hand.cards,
+                        best.resulting_score,
+                    );
+                    if outranks { next } else { best }
+                })
+                .expect("a layoff round with results has a best one");
+
+            RoundOutcome {
+                winner: winning_lay_off.player,
+                score: winning_lay_off.resulting_score,
+                hand: winning_lay_off.resulting_hand,
+            }
+        };
+
+        self.last_round = Some(RoundSummary {
+            played_by: self.players[played_by].name.clone(),
+            played_hand,
+            played_score,
+            layoffs: steps,
+            winner: outcome.winner.name.clone(),
+            score: outcome.score,
+            hand: outcome.hand.cards.clone(),
+        });
+        self.award(outcome.clone());
+        self.score_sheet.end_round(&self.players);
+
+        // Whoever's after the player who played starts the new deal
+        self.current_player_idx = played_by;
+        self.advance_turn();
+        self.deal_round()?;
+
+        Ok(outcome)
+    }
+
+    /// Scores the round for its winner, and the game too if that wins it.
+    fn award(&mut self, outcome: RoundOutcome) {
+        if let Some(player) = self.players.iter_mut().find(|p| **p == outcome.winner) {
+            player.score += outcome.score as usize;
+        }
+        self.publish(GameEvent::RoundWon {
+            player: outcome.winner.name,
+            score: outcome.score,
+            hand: outcome.hand.cards,
+        });
+        if let Some(seat) = self.winner_seat() {
+            let player = self.players[seat].name.clone();
+            self.publish(GameEvent::GameWon {
+                player: player.clone(),
+                score: self.team_score(seat),
+            });
+            self.publish(GameEvent::SeedDisclosed {
+                player,
+                disclosure: self.disclosure(),
+            });
+        }
+    }
+
+    /// Settles a gin knock by `knocker` against everyone else's deadwood.
+    fn knock_outcome(&self, knocker: usize) -> RoundOutcome {
+        let arrangements: Vec<(Vec<Card>, u64)> = self
+            .players
+            .iter()
+            .map(|player| {
+                let (melded, deadwood) = rules::arrange_gin(&player.hand.cards);
+                (melded, rules::deadwood_points(&deadwood))
+            })
+            .collect();
+
+        let defenders: Vec<usize> = (0..self.players.len()).filter(|&i| i != knocker).collect();
+        let defender_deadwood: Vec<u64> = defenders.iter().map(|&i| arrangements[i].1).collect();
+
+        let (winner, score) = match rules::score_knock(arrangements[knocker].1, &defender_deadwood)
+        {
+            KnockResult::Knocker { points } => (knocker, points),
+            KnockResult::Undercut { defender, points } => (defenders[defender], points),
+        };
+
+        RoundOutcome {
+            winner: self.players[winner].clone(),
+            score,
+            hand: Hand {
+                cards: arrangements[winner].0.clone(),
+            },
+        }
+    }
+
+    /// Works out what the current player's strategy would have them do, or
+    /// uses what `precompute_next_turn` worked out for this very table.
+    /// Returns `None` for human players.
+    pub fn ai_decision(&self) -> Option<AutoPlayDecision> {
+        if self.variant == Variant::Bookclub
+            && self.strategy(self.current_player_idx).is_some()
+            && let Some(decision) = self.precomputed.take(self.decision_key())
+        {
+            return Some(decision);
+        }
+
+        self.search_decision()
+    }
+
+    /// Starts working out, on another thread, what the next player would do
+    /// after each turn the current player could take, if the next player is
+    /// a bookclub AI. Their `ai_decision` uses it if the table ends up the
+    /// way it was worked out for, so the AI doesn't keep everyone waiting.
+    pub fn precompute_next_turn(&self) {
+        let engine = self.detached();
+        rayon::spawn(move || engine.precompute());
+    }
+
+    /// A copy of the engine to work things out on, away from the table:
+    /// nothing it does is told to anyone or runs the house rules.
+    fn detached(&self) -> GameEngine {
+        let mut engine = self.clone();
+        engine.events = EventSinks::default();
+        #[cfg(feature = "scripting")]
+        {
+            engine.house_rules = None;
+        }
+        engine
+    }
+
+    /// What `precompute_next_turn` leaves running, on this thread.
+    fn precompute(&self) {
+        let _span = tracing::debug_span!("precompute", turn = self.turns).entered();
+        self.precomputed.clear();
+
+        self.projections().par_iter().for_each(|projection| {
+            if let Some(decision) = projection.search_decision() {
+                self.precomputed.insert(projection.decision_key(), decision);
+            }
+        });
+    }
+
+    /// The tables the current player's turn could leave the next player
+    /// with, if they're a bookclub AI: one for every card the current player
+    /// could throw away after drawing or taking the discard.
+    fn projections(&self) -> Vec<GameEngine> {
+        let next = (self.current_player_idx + 1) % self.players.len();
+        if self.variant != Variant::Bookclub
+            || self.phase != TurnPhase::Choosing
+            || self.staged.is_some()
+            || self.strategy(next).is_none()
+        {
+            return Vec::new();
+        }
+
+        let mut projections = Vec::new();
+        for choice in [Choice::Draw, Choice::Retrieve] {
+            let mut taken = self.clone();
+            let took = match choice {
+                Choice::Draw => taken.draw(),
+                _ => taken.retrieve(),
+            };
+            if took.is_err() {
+                continue;
+            }
+
+            for &card in &taken.current_player().hand.cards {
+                let mut projection = taken.clone();
+                if projection.discard(card).is_ok() {
+                    projections.push(projection);
+                }
+            }
+        }
+
+        projections
+    }
+
+    /// Everything the current player's `ai_decision` rests on, hashed.
+    fn decision_key(&self) -> u64 {
+        let context = self.match_context(self.current_player_idx);
+        let mut dangers: Vec<(Card, u64)> = context
+            .discard_danger
+            .iter()
+            .map(|(card, danger)| (*card, danger.to_bits()))
+            .collect();
+        dangers.sort_by_key(|(card, _)| (card.name as u8, card.suite as u8));
+
+        let mut hasher = DefaultHasher::new();
+        (
+            self.current_player_idx,
+            &self.current_player().hand.cards,
+            self.top_discard(),
+            self.possible_cards(self.current_player_idx),
+            &self.deck.discard_pile,
+            context.own_score,
+            &context.opponent_scores,
+            context.target_score,
+            dangers,
+        )
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// `ai_decision` worked out from scratch.
+    fn search_decision(&self) -> Option<AutoPlayDecision> {
+        let strategy = self.strategy(self.current_player_idx)?;
+
+        // The analysis only knows 5 card hands
+        if self.variant != Variant::Bookclub {
+            return self.greedy_decision();
+        }
+
+        self.top_discard()?;
+        let known = self.known_information(self.current_player_idx);
+        let context = self.match_context(self.current_player_idx);
+        let decision = match self.ai_search {
+            AiSearch::Off => advise_with(strategy, &known, &self.deck_config, &context),
+            search => {
+                // Searched as thoroughly as the seat's strategy would
+                let time_budget = match search {
+                    AiSearch::Time(budget) => Some(budget),
+                    _ => None,
+                };
+                let config = AnalysisConfig {
+                    time_budget,
+                    ..strategy.analysis_config()
+                };
+                // Seeded from the table as well as the game, so a decision
+                // worked out ahead of time samples the draws the turn would.
+                // To a node budget it's the same decision, but a time budget
+                // can stop the two searches at different depths
+                let mut rng =
+                    StdRng::seed_from_u64(self.rng.seed_of(RngStream::Ai) ^ self.decision_key());
+                advise_searched(
+                    strategy,
+                    &known,
+                    &self.deck_config,
+                    &context,
+                    config,
+                    &mut rng,
+                )
+            }
+        }
+        .expect("the deck was dealt from its config");
+        Some(decision)
+    }
+
+    /// A simple AI for the variants the analysis wasn't built for: play as
+    /// soon as the hand is good enough, take the discard when it improves the
+    /// hand and draw otherwise.
+    fn greedy_decision(&self) -> Option<AutoPlayDecision> {
+        let hand = &self.current_player().hand;
+
+        if self.ready_to_play(hand) {
+            return Some(AutoPlayDecision {
+                action: PlayAction::Play,
+                confidence: 1.0,
+                expected_score: self.hand_value(hand) as f64,
+                card_to_discard: None,
+            });
+        }
+
+        let current_value = self.hand_value(hand);
+        let discard = *self.top_discard()?;
+        let mut with_discard = hand.clone();
+        with_discard.cards.push(discard);
+        let card_to_discard = self.greedy_discard(&with_discard);
+        let retrieved_value = self.hand_value(&without(&with_discard, card_to_discard));
+
+        let decision = if card_to_discard != discard && retrieved_value > current_value {
+            AutoPlayDecision {
+                action: PlayAction::Retrieve,
+                confidence: 0.75,
+                expected_score: retrieved_value as f64,
+                card_to_discard: Some(card_to_discard),
+            }
+        } else {
+            AutoPlayDecision {
+                action: PlayAction::Draw,
+                confidence: 0.5,
+                expected_score: current_value as f64,
+                card_to_discard: None,
+            }
+        };
+
+        Some(decision)
+    }
+
+    /// How good `hand` is in this variant, higher is better: the best meld's
+    /// score, or in gin how little deadwood it leaves.
+    fn hand_value(&self, hand: &Hand) -> i64 {
+        if self.variant.allows_layoffs() {
+            self.variant.best_meld(hand).0 as i64
+        } else {
+            -(deadwood(&hand.cards) as i64)
+        }
+    }
+
+    /// Whether the greedy AI would play `hand` now.
+    fn ready_to_play(&self, hand: &Hand) -> bool {
+        if self.variant.allows_layoffs() {
+            self.variant.best_meld(hand).0 >= GREEDY_PLAY_SCORE
+        } else {
+            self.variant.check_play(hand).is_ok()
+        }
+    }
+
+    /// The card whose loss hurts `hand` least, the highest of those.
+    fn greedy_discard(&self, hand: &Hand) -> Card {
+        *hand
+            .cards
+            .iter()
+            .max_by_key(|card| (self.hand_value(&without(hand, **card)), **card))
+            .expect("a hand to discard from")
+    }
+
+    /// Advice for the current player on whether to play their hand now.
+    pub fn hint(&self) -> PlayDecision {
+        let hand = self.current_player().hand.clone();
+
+        if self.variant != Variant::Bookclub {
+            let should_play = self.ready_to_play(&hand);
+            let reasoning = match (self.variant, should_play) {
+                (Variant::Gin, true) => "You have little enough deadwood to knock.",
+                (Variant::Gin, false) => "Too much deadwood to knock yet.",
+                (_, true) => "Your best five cards make a strong meld.",
+                (_, false) => "Your best five cards don't meld well yet.",
+            };
+            return PlayDecision {
+                should_play,
+                confidence: 0.5,
+                reasoning: reasoning.to_string(),
+                alternative_strategies: Vec::new(),
+            };
+        }
+
+        let baseline_score = self.variant.score_meld(&hand);
+
+        let node = Node {
+            baseline_score,
+            ..self.node(hand)
+        };
+
+        let prob_analysis = node.calculate_cumulative_probabilities();
+        node.make_play_decision(&prob_analysis, &self.match_context(self.current_player_idx))
+    }
+
+    /// What the current player's cards are worth and what drawing or taking
+    /// the discard could do for them, for a player learning why a discard is
+    /// good. `None` outside the bookclub game, the only one the analysis
+    /// knows.
+    pub fn hand_odds(&self) -> Option<HandOdds> {
+        if self.variant != Variant::Bookclub {
+            return None;
+        }
+        let hand = self.current_player().hand.clone();
+        let baseline_score = self.variant.score_meld(&hand);
+
+        let node = Node {
+            baseline_score,
+            ..self.node(hand)
+        };
+
+        Some(node.hand_odds())
+    }
+
+    /// The card an AI would throw away from the current player's hand.
+    pub fn ai_discard_choice(&self) -> Card {
+        let hand = self.current_player().hand.clone();
+        if self.variant != Variant::Bookclub {
+            return self.greedy_discard(&hand);
+        }
+        let node = self.node(hand);
+
+        let context = self.match_context(self.current_player_idx);
+        match self.strategy(self.current_player_idx) {
+            Some(strategy) => strategy.discard(&node, &context),
+            None => node.find_safest_discard(&context),
+        }
+    }
+
+    /// Plays a whole turn for the current player, who must be an AI.
+    /// Playing a hand leaves the engine in the layoff round.
+    pub fn take_ai_turn(&mut self) -> Result<AiTurn, RummyError> {
+        let decision = self
+            .ai_decision()
+            .ok_or_else(|| RummyError::InvalidMove("It's a human player's turn".to_string()))?;
+        self.play_ai_decision(decision)
+    }
+
+    /// Does what the analysis recommends for whoever the engine is waiting
+    /// on, for a player who's run out of time. A turn they'd staged is played
+    /// as far as they took it, and a human's hand is played, drawn to or laid
+    /// off from the way it would be for an AI.
+    pub fn auto_play(&mut self) -> Result<(), RummyError> {
+        if let Some(staged) = self.staged.clone() {
+            if staged.discard.is_some() {
+                return self.commit();
+            }
+            self.staged = None;
+            if matches!(staged.choice, Choice::Retrieve) {
+                self.retrieve()?;
+            }
+            return self.discard(self.ai_discard_choice());
+        }
+
+        match self.phase {
+            TurnPhase::Choosing => {
+                let hint = self.hint();
+                let action = if hint.should_play {
+                    PlayAction::Play
+                } else {
+                    PlayAction::Draw
+                };
+                self.play_ai_decision(AutoPlayDecision {
+                    action,
+                    confidence: hint.confidence,
+                    expected_score: 0.0,
+                    card_to_discard: None,
+                })
+                .map(|_| ())
+            }
+            TurnPhase::Discarding => self.discard(self.ai_discard_choice()),
+            TurnPhase::LayingOff => self.ai_lay_off().map(|_| ()),
+            _ => Err(RummyError::InvalidMove(
+                "There's no turn to play".to_string(),
+            )),
+        }
+    }
+
+    /// Plays the current player's turn the way `decision` says, picking
+    /// their discard if it doesn't.
+    pub fn play_ai_decision(&mut self, decision: AutoPlayDecision) -> Result<AiTurn, RummyError> {
+        let _span = tracing::info_span!(
+            "ai_turn",
+            player = %self.current_player().name,
+            turn = self.turns
+        )
+        .entered();
+
+        tracing::info!(
+            action = ?decision.action,
+            confidence = decision.confidence,
+            expected_score = decision.expected_score,
+            "decision made"
+        );
+
+        match decision.action {
+            PlayAction::Play => {
+                let (score, hand) = self.play()?;
+                Ok(AiTurn::Played { score, hand })
+            }
+            PlayAction::Draw => {
+                self.draw()?;
+                let discarded = self.ai_discard_choice();
+                self.discard(discarded)?;
+                Ok(AiTurn::Drew { discarded })
+            }
+            PlayAction::Retrieve => {
+                self.retrieve()?;
+                let discarded = self.ai_discard_choice();
+                self.discard(discarded)?;
+                Ok(AiTurn::Retrieved { discarded })
+            }
+        }
+    }
+
+    /// The layoff round and whose turn it is in it.
+    fn layoff_turn(&self) -> Result<(usize, &LayoffRound), RummyError> {
+        self.expect_phase(TurnPhase::LayingOff)?;
+
+        match (self.layoff_player_idx(), self.layoff.as_ref()) {
+            (Some(player_idx), Some(layoff)) => Ok((player_idx, layoff)),
+            _ => Err(RummyError::InvalidMove("Everyone has laid off".to_string())),
+        }
+    }
+
+    /// Whether `player_idx` holds every one of `cards`. A card counts once
+    /// for every copy held, with more than one deck in play.
+    fn check_held(&self, player_idx: usize, cards: &[Card]) -> Result<(), RummyError> {
+        let mut held = self.players[player_idx].hand.cards.clone();
+        match cards.iter().find(|c| !remove_one(&mut held, c)) {
+            Some(card) => Err(RummyError::InvalidMove(format!("You don't have {card}"))),
+            None => Ok(()),
+        }
+    }
+
+    fn record_layoff(&mut self, player_idx: usize, cards: &[Card], result: Option<LayOffResult>) {
+        self.publish(GameEvent::LayoffMade {
+            player: self.players[player_idx].name.clone(),
+            cards: cards.to_vec(),
+            score: result.as_ref().map(|result| result.resulting_score),
+        });
+
+        let num_players = self.players.len();
+        let Some(layoff) = self.layoff.as_mut() else {
+            return;
+        };
+
+        if !cards.is_empty() {
+            layoff.steps.push(LayoffStep {
+                player: self.players[player_idx].name.clone(),
+                cards: cards.to_vec(),
+                meld: result
+                    .as_ref()
+                    .map(|result| result.resulting_hand.cards.clone()),
+                score: result.as_ref().map(|result| result.resulting_score),
+            });
+        }
+        if let Some(result) = result {
+            layoff.winning_hand = result.resulting_hand.clone();
+            layoff.score_to_beat = result.resulting_score;
+            layoff.layoff_winner_idx = player_idx;
+            layoff.results.push(result);
+        }
+
+        layoff.current_idx = (layoff.current_idx + 1) % num_players;
+    }
+
+    fn advance_turn(&mut self) {
+        self.current_player_idx = (self.current_player_idx + 1) % self.players.len();
+        tracing::debug!(
+            player = %self.current_player().name,
+            turn = self.turns,
+            "turn started"
+        );
+    }
+
+    fn expect_phase(&self, phase: TurnPhase) -> Result<(), RummyError> {
+        if self.phase == phase {
+            Ok(())
+        } else {
+            Err(RummyError::InvalidMove(format!(
+                "Can't do that while {:?}",
+                self.phase
+            )))
+        }
+    }
+}
+
+/// `hand` with one copy of `card` taken out.
+fn without(hand: &Hand, card: Card) -> Hand {
+    let mut hand = hand.clone();
+    remove_one(&mut hand.cards, &card);
+    hand
+}
+
+/// The best layoff `player` can make onto `played_hand` under `rules`, if
+/// any beats `score_to_beat`. See `GameRules::best_layoff`.
+pub fn check_for_layoff(
+    player: &Player,
+    played_hand: &Hand,
+    score_to_beat: u64,
+    variant: Variant,
+    rules: GameRules,
+) -> Option<LayOffResult> {
+    rules
+        .best_layoff(variant, &player.hand.cards, played_hand, score_to_beat)
+        .map(|(cards, resulting_hand, score)| LayOffResult {
+            player: player.clone(),
+            cards_used: cards.len(),
+            cards_laid_off: cards,
+            resulting_hand,
+            resulting_score: score,
+        })
+}
+
+fn no_staged_turn() -> RummyError {
+    RummyError::InvalidMove("There's no turn waiting to be confirmed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bank::{Bank, Stakes};
+    use crate::card::ToU64;
+    use crate::game::DeckEmpty;
+    use crate::game::events::ActionLog;
+    use crate::i18n::{Locale, Message};
+    use std::sync::{Arc, Mutex};
+
+    fn player(name: &str) -> Player {
+        Player {
+            name: name.to_string(),
+            description: String::new(),
+            player_type: None,
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        }
+    }
+
+    fn hand(cards: &str) -> Hand {
+        Hand {
+            cards: cards.split(' ').map(|card| card.parse().unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_draw_and_discard_pass_the_turn() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        assert!(engine.players.iter().all(|p| p.hand.cards.len() == 5));
+
+        let drawn = engine.draw().unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Discarding);
+        assert!(engine.draw().is_err());
+
+        engine.discard(drawn).unwrap();
+        assert_eq!(engine.top_discard(), Some(&drawn));
+        assert_eq!(engine.current_player_idx, 1);
+        assert_eq!(engine.players[0].hand.cards.len(), 5);
+
+        // Both turns are in the players' histories
+        let retrieved = engine.retrieve().unwrap();
+        let kept = engine.players[1].hand.cards[0];
+        engine.discard(kept).unwrap();
+        assert_eq!(engine.turn(), 3);
+        assert_eq!(
+            engine.players[0].history().back(),
+            Some(&ActionHistory {
+                choice: Choice::Draw,
+                card_to_discard: Some(drawn),
+                drawn: Some(drawn),
+                turn: 1,
+            })
+        );
+        assert_eq!(
+            engine.players[1].history().back(),
+            Some(&ActionHistory {
+                choice: Choice::Retrieve,
+                card_to_discard: Some(kept),
+                drawn: Some(retrieved),
+                turn: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_play_runs_a_layoff_round() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+
+        let (score, _hand) = engine.play().unwrap();
+        assert_eq!(engine.layoff_player_idx(), Some(1));
+        assert_eq!(engine.acting_player_idx(), 1);
+        assert!(engine.finish_round().is_err());
+
+        engine.lay_off(&[]).unwrap();
+        assert_eq!(engine.layoff_player_idx(), None);
+
+        let outcome = engine.finish_round().unwrap();
+        assert_eq!(outcome.winner.name, "Ada");
+        assert_eq!(outcome.score, score);
+        assert_eq!(engine.players[0].score, score as usize);
+        assert_eq!(engine.current_player_idx, 1);
+        assert_eq!(*engine.phase(), TurnPhase::Choosing);
+        assert!(!engine.last_round().unwrap().is_steal());
+    }
+
+    #[test]
+    fn test_the_next_deal_starts_after_whoever_played() {
+        let players = vec![player("Ada"), player("Bram"), player("Cathy")];
+        let mut engine = GameEngine::new(players).unwrap();
+        engine.set_deal_rituals(DealRituals {
+            cut: Some(CutPosition::At(10)),
+            burn: false,
+        });
+        let recording = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+        engine.subscribe(recording.clone());
+
+        engine.play().unwrap();
+        while engine.layoff_player_idx().is_some() {
+            engine.lay_off(&[]).unwrap();
+        }
+        engine.finish_round().unwrap();
+        assert_eq!(engine.current_player().name, "Bram");
+
+        // Bram starts, so Ada deals
+        let recording = recording.lock().unwrap();
+        let [.., cut, GameEvent::RoundDealt { player, .. }] = recording.as_slice() else {
+            panic!("the new deal wasn't recorded");
+        };
+        assert_eq!(player, "Bram");
+        assert_eq!(
+            *cut,
+            GameEvent::DeckCutForDeal {
+                player: "Ada".to_string(),
+                position: 10
+            }
+        );
+    }
+
+    #[test]
+    fn test_layoffs_take_as_many_cards_as_the_table_allows() {
+        let deal = |rules| {
+            let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+            engine.set_rules(rules);
+            engine.players[0].hand = hand("Kh Kd Kc 2h 2s");
+            engine.players[1].hand = hand("Qh Jh 10h Ah 3s");
+            engine.play().unwrap();
+            engine
+        };
+
+        // Three hearts only make a flush, which doesn't beat the full house
+        let mut engine = deal(GameRules::default());
+        let royal = hand("Qh Jh 10h Ah").cards;
+        assert!(engine.lay_off(&royal).is_err());
+        assert_eq!(engine.layoff_player_idx(), Some(1));
+        assert!(engine.ai_lay_off().unwrap().is_none());
+
+        // Four of them make a royal flush, scored like any other meld
+        let mut engine = deal(GameRules {
+            max_layoff: 4,
+            ..GameRules::default()
+        });
+        let result = engine.ai_lay_off().unwrap().unwrap();
+        assert_eq!(result.cards_laid_off, royal);
+        assert_eq!(result.cards_used, 4);
+        assert_eq!(result.resulting_hand, hand("Kh Qh Jh 10h Ah"));
+        assert_eq!(result.resulting_score, 100);
+        let outcome = engine.finish_round().unwrap();
+        assert_eq!(outcome.winner.name, "Bram");
+        assert_eq!(outcome.score, 100);
+
+        let mut engine = deal(GameRules {
+            max_layoff: 4,
+            ..GameRules::default()
+        });
+        let result = engine.lay_off(&royal).unwrap().unwrap();
+        assert_eq!(result.resulting_score, 100);
+
+        // Two cards score their meld too
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        engine.players[0].hand = hand("Kh Kd 7c 2h 2s");
+        engine.players[1].hand = hand("Ks Kc 9d 4h 3s");
+        engine.play().unwrap();
+        let result = engine.ai_lay_off().unwrap().unwrap();
+        assert_eq!(result.cards_used, 2);
+        assert_eq!(result.resulting_score, 50);
+        assert_eq!(engine.finish_round().unwrap().score, 50);
+    }
+
+    #[test]
+    fn test_layoff_steals_are_summarised() {
+        let card = |card: &str| card.parse::<Card>().unwrap();
+        let mut engine =
+            GameEngine::new(vec![player("Ada"), player("Bram"), player("Cy")]).unwrap();
+        engine.players[0].hand = hand("Kh Kd Kc 2h 2s");
+        engine.players[1].hand = hand("Ks 9c 7d 4h 3s");
+        engine.players[2].hand = hand("2d 5c 6h 8s Jd");
+
+        engine.play().unwrap();
+        engine.lay_off(&[card("Ks")]).unwrap();
+        // Checking first doesn't use up Cy's turn
+        assert!(engine.check_layoff(&[card("2d")]).is_err());
+        assert!(engine.check_layoff(&[card("Ks")]).is_err());
+        assert_eq!(engine.layoff_player_idx(), Some(2));
+        engine.lay_off(&[card("2d")]).unwrap();
+        engine.finish_round().unwrap();
+
+        // Bram's king makes four of a kind, Cy's two only ties it
+        let summary = engine.last_round().unwrap();
+        assert!(summary.is_steal());
+        assert_eq!(summary.played_by, "Ada");
+        assert_eq!(summary.played_hand, hand("Kh Kd Kc 2h 2s").cards);
+        assert_eq!(summary.played_score, 40);
+        assert_eq!(
+            summary.layoffs,
+            vec![
+                LayoffStep {
+                    player: "Bram".to_string(),
+                    cards: vec![card("Ks")],
+                    meld: Some(hand("Kh Kd Kc Ks 2s").cards),
+                    score: Some(50),
+                },
+                LayoffStep {
+                    player: "Cy".to_string(),
+                    cards: vec![card("2d")],
+                    meld: None,
+                    score: None,
+                },
+            ]
+        );
+        assert_eq!(summary.winner, "Bram");
+        assert_eq!(summary.score, 50);
+    }
+
+    #[test]
+    fn test_seeded_games_play_out_the_same() {
+        let deal = |seed| {
+            let players = vec![player("Ada"), player("Bram"), player("Cy")];
+            let mut engine =
+                GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+            engine.set_seed(seed);
+            engine.decide_turn_order(TurnOrder::HighCard).unwrap();
+            engine.draw().unwrap();
+            engine
+        };
+        let hands = |engine: &GameEngine| -> Vec<(String, Hand)> {
+            engine
+                .players
+                .iter()
+                .map(|p| (p.name.clone(), p.hand.clone()))
+                .collect()
+        };
+
+        let (game, replay) = (deal(42), deal(42));
+        assert_eq!(hands(&game), hands(&replay));
+        assert_eq!(game.deck.draw_pile, replay.deck.draw_pile);
+        assert_eq!(game.top_discard(), replay.top_discard());
+
+        // The disclosed seed and hashes check out against a replay
+        let disclosure = game.disclosure();
+        assert_eq!(disclosure.seed, 42);
+        assert_eq!(disclosure.deal_hashes.len(), 1);
+        assert_eq!(disclosure, replay.disclosure());
+
+        let other = deal(43);
+        assert_ne!(hands(&game), hands(&other));
+        assert_ne!(disclosure.deal_hashes, other.disclosure().deal_hashes);
+    }
+
+    #[test]
+    fn test_a_restored_game_deals_on_from_its_seed() {
+        let players = vec![player("Ada"), player("Bram")];
+        let mut game =
+            GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+        game.set_seed(42);
+        game.decide_turn_order(TurnOrder::HighCard).unwrap();
+        let mut restored = GameEngine::restore(game.snapshot().unwrap()).unwrap();
+        assert_eq!(restored.disclosure(), game.disclosure());
+
+        for engine in [&mut game, &mut restored] {
+            let card = engine.draw().unwrap();
+            engine.discard(card).unwrap();
+            engine.redeal("Ada").unwrap();
+        }
+        assert_eq!(restored.disclosure(), game.disclosure());
+        assert_eq!(game.disclosure().deal_hashes.len(), 2);
+        assert_eq!(restored.deck.draw_pile, game.deck.draw_pile);
+    }
+
+    #[test]
+    fn test_the_score_sheet_keeps_every_round() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        assert_eq!(engine.score_sheet().players(), ["Ada", "Bram"]);
+
+        engine.players[0].hand = hand("Kh Kd Kc 2h 2s");
+        engine.players[1].hand = hand("3d 5c 7h 9s Jd");
+        engine.play().unwrap();
+        engine.lay_off(&[]).unwrap();
+        engine.finish_round().unwrap();
+
+        // A redeal isn't a round of its own
+        engine.redeal("Bram").unwrap();
+        assert_eq!(engine.score_sheet().rounds(), [vec![40, 0]]);
+
+        // It's saved with the game
+        let restored = GameEngine::restore(engine.snapshot().unwrap()).unwrap();
+        assert_eq!(restored.score_sheet(), engine.score_sheet());
+    }
+
+    #[test]
+    fn test_a_felted_table_calls_the_game() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        let bank = Arc::new(Mutex::new(Bank::new(["Ada", "Bram"], Stakes::new(10, 10))));
+        engine.subscribe(bank.clone());
+
+        engine.players[0].hand = hand("Kh Kd Kc 2h 2s");
+        engine.players[1].hand = hand("3d 5c 7h 9s Jd");
+        engine.play().unwrap();
+        engine.lay_off(&[]).unwrap();
+        engine.finish_round().unwrap();
+        // Everyone's all in on the next deal's ante
+        assert_eq!(bank.lock().unwrap().pot(), 20);
+        assert!(engine.winner().is_none());
+
+        engine.redeal("Bram").unwrap();
+        let winner = bank.lock().unwrap().match_winner().map(str::to_string);
+        assert_eq!(winner.as_deref(), Some("Ada"));
+        engine.call_game("Ada").unwrap();
+        assert_eq!(engine.winner().map(|p| p.name.as_str()), Some("Ada"));
+        assert!(engine.call_game("Bram").is_err());
+    }
+
+    #[test]
+    fn test_partners_pool_their_scores() {
+        let mut engine = GameEngine::new(vec![
+            player("Ada"),
+            player("Bram"),
+            player("Cy"),
+            player("Dot"),
+        ])
+        .unwrap();
+        engine.target_score = 100;
+        engine.players[0].score = 60;
+        engine.players[2].score = 50;
+        assert!(engine.winner().is_none());
+        assert_eq!(engine.partner(0), None);
+
+        engine.set_rules(GameRules {
+            teams: true,
+            ..GameRules::default()
+        });
+        assert_eq!(engine.partner(0), Some(2));
+        assert_eq!(engine.partner(3), Some(1));
+        assert_eq!(engine.team_score(2), 110);
+        assert_eq!(engine.winner().map(|p| p.name.as_str()), Some("Ada"));
+    }
+
+    #[test]
+    fn test_ai_partners_only_lay_off_for_more_points() {
+        let ai = |name| Player {
+            player_type: Some(crate::game::PlayerType::Balanced),
+            ..player(name)
+        };
+        let mut engine =
+            GameEngine::new(vec![player("Ada"), ai("Bram"), ai("Cy"), ai("Dot")]).unwrap();
+        engine.set_rules(GameRules {
+            teams: true,
+            ..GameRules::default()
+        });
+
+        engine.players[0].hand = hand("5s 5h Qc Jd 10h");
+        engine.players[1].hand = hand("3d 4c 6c 7s 8d");
+        // Cy's king only takes their partner's pair on the tiebreak
+        engine.players[2].hand = hand("Kc 3s 4h 7d 8c");
+        engine.players[3].hand = hand("3c 4d 6d 7c 8h");
+        engine.play().unwrap();
+        engine.ai_lay_off().unwrap();
+        assert!(engine.ai_lay_off().unwrap().is_none());
+        assert_eq!(engine.layoff_round().unwrap().layoff_winner_idx, 0);
+    }
+
+    #[test]
+    fn test_ai_temperaments_drift_with_the_score() {
+        let mut engine =
+            GameEngine::new(vec![player("Ada"), player("Bram"), player("Cy")]).unwrap();
+        engine.players[1].player_type = Some(PlayerType::Balanced);
+        engine.players[2].player_type = Some(PlayerType::Balanced);
+        engine.set_temperament_policy(TemperamentPolicy::default());
+        let log = Arc::new(Mutex::new(ActionLog::default()));
+        engine.subscribe(log.clone());
+
+        engine.players[0].score = 80;
+        engine.players[1].score = 20;
+        engine.players[2].score = 85;
+        engine.deal_round().unwrap();
+        assert_eq!(engine.temperament(0), None);
+        assert_eq!(engine.temperament(1), Some(&PlayerType::Aggressive));
+        assert_eq!(engine.temperament(2), Some(&PlayerType::Balanced));
+        let tilts: Vec<String> = log
+            .lock()
+            .unwrap()
+            .actions()
+            .iter()
+            .map(|action| action.text_in(Locale::En))
+            .collect();
+        assert_eq!(tilts, ["is tilting aggressive now."]);
+
+        // Caught up, they settle back
+        engine.players[1].score = 70;
+        engine.deal_round().unwrap();
+        assert_eq!(engine.temperament(1), Some(&PlayerType::Balanced));
+    }
+
+    #[test]
+    fn test_gin_knocks_without_a_layoff_round() {
+        let mut engine = GameEngine::with_variant(
+            vec![player("Ada"), player("Bram")],
+            DeckConfig::default(),
+            Variant::Gin,
+        )
+        .unwrap();
+        assert!(engine.players.iter().all(|p| p.hand.cards.len() == 10));
+
+        engine.players[0].hand = hand("Ah 2h 3h 8c 8d 8s Js Qs Ks 5d");
+        engine.players[1].hand = hand("2c 3c 4c 9d 9h 9s Kd Kh 7s 6d");
+        let (deadwood, melded) = engine.play().unwrap();
+        assert_eq!(deadwood, 5);
+        assert_eq!(melded.cards.len(), 9);
+        assert_eq!(engine.layoff_player_idx(), None);
+        assert_eq!(
+            engine.players[0].history().back().map(|turn| &turn.choice),
+            Some(&Choice::Knock)
+        );
+
+        // Bram's deadwood is Kd, Kh, 7s and 6d
+        let outcome = engine.finish_round().unwrap();
+        assert_eq!(outcome.winner.name, "Ada");
+        assert_eq!(outcome.score, 33 - 5);
+        assert!(engine.players.iter().all(|p| p.hand.cards.len() == 10));
+
+        // Too much deadwood to knock
+        engine.players[1].hand = hand("2c 3c 4c 9d 9h 9s Kd Kh 7s 6d");
+        assert!(engine.play().is_err());
+        assert_eq!(*engine.phase(), TurnPhase::Choosing);
+    }
+
+    #[test]
+    fn test_staged_turns_can_be_taken_back() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        let recording = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+        engine.subscribe(recording.clone());
+        let hand = engine.players[0].hand.clone();
+        let top = *engine.top_discard().unwrap();
+
+        // A retrieve is only a preview until it's committed
+        assert_eq!(engine.stage(Choice::Retrieve).unwrap(), top);
+        assert!(engine.stage(Choice::Draw).is_err());
+        assert!(engine.commit().is_err());
+        let kept = hand.cards[0];
+        engine.stage_discard(kept).unwrap();
+        assert!(!engine.staged_hand().unwrap().cards.contains(&kept));
+        assert_eq!(engine.players[0].hand, hand);
+        assert!(recording.lock().unwrap().is_empty());
+
+        engine.undo().unwrap();
+        assert_eq!(engine.staged().unwrap().discard, None);
+        engine.undo().unwrap();
+        assert!(engine.staged().is_none());
+        assert_eq!(engine.top_discard(), Some(&top));
+        assert!(engine.undo().is_err());
+
+        // A drawn card's been seen, only the discard can be taken back
+        let drawn = engine.stage(Choice::Draw).unwrap();
+        engine.stage_discard(drawn).unwrap();
+        engine.undo().unwrap();
+        assert!(engine.undo().is_err());
+        let elsewhere = *engine.deck.draw_pile.front().unwrap();
+        assert!(engine.stage_discard(elsewhere).is_err());
+        engine.stage_discard(kept).unwrap();
+        engine.commit().unwrap();
+
+        assert_eq!(engine.current_player().name, "Bram");
+        assert_eq!(engine.top_discard(), Some(&kept));
+        assert!(engine.players[0].hand.cards.contains(&drawn));
+        assert_eq!(recording.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_a_turn_out_of_time_is_played_for_them() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        let dealt = engine.players[0].hand.cards.len();
+
+        // A retrieve they'd staged is played, the discard picked for them
+        engine.stage(Choice::Retrieve).unwrap();
+        engine.auto_play().unwrap();
+        assert!(engine.staged().is_none());
+        assert_eq!(engine.players[0].hand.cards.len(), dealt);
+        assert_eq!(engine.current_player().name, "Bram");
+
+        // Having drawn, they discard what an AI would
+        engine.draw().unwrap();
+        let discard = engine.ai_discard_choice();
+        engine.auto_play().unwrap();
+        assert_eq!(engine.top_discard(), Some(&discard));
+
+        // A discard they'd already picked is kept to
+        let drawn = engine.stage(Choice::Draw).unwrap();
+        engine.stage_discard(drawn).unwrap();
+        engine.auto_play().unwrap();
+        assert_eq!(engine.top_discard(), Some(&drawn));
+
+        // Nothing chosen yet, the hint decides, and the layoffs are played out too
+        engine.auto_play().unwrap();
+        while engine.layoff_player_idx().is_some() {
+            engine.auto_play().unwrap();
+        }
+        assert_ne!(*engine.phase(), TurnPhase::Discarding);
+        assert!(engine.staged().is_none());
+    }
+
+    #[test]
+    fn test_moves_are_emitted_as_events() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        let recording = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+        let log = Arc::new(Mutex::new(ActionLog::default()));
+        engine.subscribe(recording.clone());
+        engine.subscribe(log.clone());
+
+        let drawn = engine.draw().unwrap();
+        engine.discard(drawn).unwrap();
+
+        assert_eq!(
+            *recording.lock().unwrap(),
+            vec![
+                GameEvent::CardDrawn {
+                    player: "Ada".to_string(),
+                    retrieved: None
+                },
+                GameEvent::CardDiscarded {
+                    player: "Ada".to_string(),
+                    card: drawn
+                },
+            ]
+        );
+
+        // The draw and discard make one line in the log
+        let actions = log.lock().unwrap().actions();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].message, Message::DrewAndDiscarded);
+        assert_eq!(actions[0].cards, vec![drawn]);
+    }
+
+    #[test]
+    fn test_redeal_calls_off_the_round() {
+        let players = vec![player("Ada"), player("Bram")];
+        let mut engine =
+            GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+        assert!(engine.redeal("Ada").is_err());
+        engine.set_seed(3);
+        engine.deal_round().unwrap();
+
+        let recording = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+        engine.subscribe(recording.clone());
+        engine.draw().unwrap();
+        let hand = engine.players[0].hand.clone();
+
+        assert!(engine.redeal("Zed").is_err());
+        engine.redeal("Ada").unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Choosing);
+        assert_eq!(engine.current_player().name, "Ada");
+        assert_eq!(engine.players[0].hand.cards.len(), 5);
+        assert_ne!(engine.players[0].hand, hand);
+        assert_eq!(
+            engine.deck.draw_pile.len() + engine.deck.discard_pile.len(),
+            42
+        );
+        assert!(engine.players.iter().all(|p| p.score == 0));
+
+        let recording = recording.lock().unwrap();
+        let [.., redealt, dealt] = recording.as_slice() else {
+            panic!("the redeal wasn't recorded");
+        };
+        assert_eq!(
+            *redealt,
+            GameEvent::RoundRedealt {
+                player: "Ada".to_string()
+            }
+        );
+        let GameEvent::RoundDealt { player, hands, .. } = dealt else {
+            panic!("the new deal wasn't recorded");
+        };
+        assert_eq!(player, "Ada");
+        assert_eq!(
+            hands[0],
+            ("Ada".to_string(), engine.players[0].hand.cards.clone())
+        );
+    }
+
+    #[test]
+    fn test_drawing_reshuffles_the_discards_under_the_top_one() {
+        let card = |card: &str| card.parse::<Card>().unwrap();
+        let mut deck = Deck::new(Vec::new());
+        deck.discard_pile = VecDeque::from([card("2s"), card("3s")]);
+        let mut rng = rand::rng();
+
+        assert!(deck.can_draw());
+        assert_eq!(deck.draw(&mut rng), Ok(card("2s")));
+        assert_eq!(deck.discard_pile, [card("3s")]);
+
+        // The top discard stays where it is
+        assert!(!deck.can_draw());
+        assert_eq!(deck.draw(&mut rng), Err(DeckEmpty));
+        assert_eq!(deck.discard_pile, [card("3s")]);
+    }
+
+    #[test]
+    fn test_nothing_left_to_draw_is_a_stalemate() {
+        let players = vec![player("Ada"), player("Bram")];
+        let mut engine =
+            GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+        engine.set_seed(5);
+        engine.deal_round().unwrap();
+
+        // The draw pile's run out, and Ada takes the only discard
+        engine.deck.draw_pile.clear();
+        engine.deck.discard_pile.truncate(1);
+        let recording = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+        engine.subscribe(recording.clone());
+        engine.retrieve().unwrap();
+        let kept = engine.players[0].hand.cards[0];
+        engine.discard(kept).unwrap();
+
+        assert_eq!(*engine.phase(), TurnPhase::Choosing);
+        assert_eq!(engine.current_player().name, "Bram");
+        assert!(engine.players.iter().all(|p| p.hand.cards.len() == 5));
+        assert!(engine.players.iter().all(|p| p.score == 0));
+        assert!(engine.deck.can_draw());
+
+        let recording = recording.lock().unwrap();
+        assert!(recording.contains(&GameEvent::RoundStalemate {
+            player: "Ada".to_string()
+        }));
+        assert!(matches!(
+            recording.last(),
+            Some(GameEvent::RoundDealt { player, .. }) if player == "Bram"
+        ));
+    }
+
+    #[test]
+    fn test_a_dead_hand_goes_to_the_best_meld_for_half() {
+        let players = vec![player("Ada"), player("Bram"), player("Cathy")];
+        let mut engine = GameEngine::new(players).unwrap();
+        engine.set_rules(GameRules {
+            dead_hand_after: Some(2),
+            ..GameRules::default()
+        });
+        engine.players[0].hand = hand("2s 5d 9c Jh Kd");
+        engine.players[1].hand = hand("8h 8d 3c 6s Ac");
+        engine.players[2].hand = hand("Qh Qd Qc 4s 7h");
+
+        // Ada draws the last card after the second reshuffle
+        engine.deck.reshuffles = 2;
+        engine.deck.draw_pile = VecDeque::from([hand("10s").cards[0]]);
+        let recording = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+        engine.subscribe(recording.clone());
+        let drawn = engine.draw().unwrap();
+        engine.discard(drawn).unwrap();
+
+        // Cathy's three of a kind beats Bram's pair, for half its 15
+        let scores: Vec<usize> = engine.players.iter().map(|p| p.score).collect();
+        assert_eq!(scores, [0, 0, 7]);
+        assert_eq!(engine.current_player().name, "Bram");
+        assert_eq!(engine.deck.reshuffles, 0);
+        assert!(engine.players.iter().all(|p| p.hand.cards.len() == 5));
+
+        let recording = recording.lock().unwrap();
+        let dead_hand = recording
+            .iter()
+            .position(|event| matches!(event, GameEvent::DeadHand { reshuffles: 2, .. }))
+            .expect("the dead hand wasn't recorded");
+        assert!(matches!(
+            &recording[dead_hand + 1],
+            GameEvent::RoundWon { player, score: 7, .. } if player == "Cathy"
+        ));
+
+        // Without the rule the discards are reshuffled as often as it takes
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        engine.deck.reshuffles = 2;
+        engine.deck.draw_pile.truncate(1);
+        let drawn = engine.draw().unwrap();
+        engine.discard(drawn).unwrap();
+        assert_eq!(engine.deck.reshuffles, 2);
+        assert!(engine.draw().is_ok());
+        assert_eq!(engine.deck.reshuffles, 3);
+    }
+
+    #[test]
+    fn test_a_deal_plan_rigs_every_deal() {
+        let cards = |cards: &str| -> Vec<Card> {
+            cards.split(' ').map(|card| card.parse().unwrap()).collect()
+        };
+        let players = vec![player("Ada"), player("Bram"), player("Cathy")];
+        let mut engine =
+            GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+        engine.set_deal_rituals(DealRituals {
+            cut: Some(CutPosition::At(10)),
+            burn: true,
+        });
+        engine.set_deal_plan(DealPlan {
+            hands: vec![("Bram".to_string(), cards("2s 3s 4s Kh Ah"))],
+            stack: cards("Qc Jc 10c 9c Js Qd Jd 10d 9d 7h 8h"),
+        });
+
+        for _ in 0..2 {
+            engine.deal_round().unwrap();
+            let hands: Vec<_> = engine
+                .players
+                .iter()
+                .map(|p| p.hand.cards.clone())
+                .collect();
+            assert_eq!(
+                hands,
+                [
+                    cards("Qc Jc 10c 9c Js"),
+                    cards("2s 3s 4s Kh Ah"),
+                    cards("Qd Jd 10d 9d 7h")
+                ]
+            );
+            assert_eq!(engine.top_discard(), Some(&cards("8h")[0]));
+            assert_eq!(engine.deck.draw_pile.len(), 52 - 16);
+            assert!(engine.deck.burned.is_empty());
+        }
+
+        // Only what's in the deck can be dealt
+        engine.set_deal_plan(DealPlan {
+            hands: Vec::new(),
+            stack: cards("As As"),
+        });
+        assert!(engine.deal_round().is_err());
+        engine.set_deal_plan(DealPlan {
+            hands: vec![("Zed".to_string(), cards("2s 3s 4s Kh Ah"))],
+            stack: Vec::new(),
+        });
+        assert!(engine.deal_round().is_err());
+    }
+
+    #[test]
+    fn test_deck_is_cut_and_burned_before_the_deal() {
+        let cards: Vec<Card> = ["2s", "3s", "4s", "5s"]
+            .map(|card| card.parse().unwrap())
+            .to_vec();
+        let mut deck = Deck::new(cards.clone());
+        // The top card goes to the bottom, and a cut can't take every card
+        assert_eq!(deck.cut(1), 1);
+        assert_eq!(deck.draw_pile.back(), Some(&cards[2]));
+        assert_eq!(deck.draw_pile.front(), Some(&cards[3]));
+        assert_eq!(deck.cut(9), 3);
+        assert_eq!(deck.burn(), Some(cards[3]));
+        assert_eq!(deck.burned, vec![cards[3]]);
+
+        let deal = |rituals| {
+            let players = vec![player("Ada"), player("Bram")];
+            let mut engine =
+                GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+            let recording = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+            engine.subscribe(recording.clone());
+            engine.set_seed(5);
+            engine.set_deal_rituals(rituals);
+            engine.deal_round().unwrap();
+            let events = recording.lock().unwrap().clone();
+            (engine, events)
+        };
+
+        let (plain, events) = deal(DealRituals::default());
+        assert!(matches!(events.as_slice(), [GameEvent::RoundDealt { .. }]));
+
+        let (ritual, events) = deal(DealRituals {
+            cut: Some(CutPosition::At(20)),
+            burn: true,
+        });
+        assert_ne!(ritual.players[0].hand, plain.players[0].hand);
+        assert_eq!(ritual.deck.burned.len(), 1);
+        assert_eq!(ritual.deck.draw_pile.len() + 1, plain.deck.draw_pile.len());
+        let [cut, burned, GameEvent::RoundDealt { .. }] = events.as_slice() else {
+            panic!("the rituals weren't recorded before the deal");
+        };
+        // Ada goes first, so Bram deals
+        assert_eq!(
+            *cut,
+            GameEvent::DeckCutForDeal {
+                player: "Bram".to_string(),
+                position: 20
+            }
+        );
+        assert_eq!(
+            *burned,
+            GameEvent::CardBurned {
+                player: "Bram".to_string(),
+                card: ritual.deck.burned[0]
+            }
+        );
+
+        // The burned card's shuffled back in for the next deal
+        let mut ritual = ritual;
+        ritual.set_deal_rituals(DealRituals::default());
+        ritual.deal_round().unwrap();
+        assert!(ritual.deck.burned.is_empty());
+        assert_eq!(
+            ritual.deck.draw_pile.len() + ritual.deck.discard_pile.len(),
+            42
+        );
+    }
+
+    #[test]
+    fn test_declaring_on_a_discard() {
+        let king: Card = "Ks".parse().unwrap();
+        let deal = || {
+            let players = vec![player("Ada"), player("Bram"), player("Cathy")];
+            let mut engine = GameEngine::new(players).unwrap();
+            engine.allow_declarations(true);
+            engine.players[0].hand = hand("Ks 3d 4c 7h 8s");
+            engine.players[1].hand = hand("2h 2s Kd Kc 9h");
+            engine.players[2].hand = hand("Kh Qd Qc Qs 5h");
+            engine.draw().unwrap();
+            engine.discard(king).unwrap();
+            engine
+        };
+
+        // The king fills a house for both of them
+        let mut engine = deal();
+        assert_eq!(*engine.phase(), TurnPhase::Declaring);
+        assert_eq!(engine.declare_window().unwrap().eligible, vec![1, 2]);
+        assert!(engine.draw().is_err());
+        assert!(engine.declare(0).is_err());
+
+        // Cathy's quicker, but Bram is next in line and has first say
+        engine.declare(2).unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Declaring);
+        assert!(engine.declare(2).is_err());
+        engine.pass_declaration(1).unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::LayingOff);
+        let layoff = engine.layoff_round().unwrap();
+        assert_eq!((layoff.winner_idx, layoff.score_to_beat), (2, 40));
+        assert_eq!(engine.top_discard(), Some(&"5h".parse().unwrap()));
+
+        // Nobody answers in time and play goes on
+        let mut engine = deal();
+        engine.close_declarations().unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Choosing);
+        assert_eq!(engine.current_player_idx, 1);
+        assert_eq!(engine.top_discard(), Some(&king));
+
+        let mut engine = deal();
+        engine.players[1].player_type = Some(crate::game::PlayerType::Balanced);
+        engine.ai_declarations().unwrap();
+        assert_eq!(engine.layoff_round().unwrap().winner_idx, 1);
+        assert_eq!(engine.players[1].hand, hand("2h 2s Kd Kc Ks"));
+    }
+
+    #[test]
+    fn test_hand_odds_weigh_the_next_card() {
+        let cards = |cards: &str| -> VecDeque<Card> {
+            cards.split(' ').map(|card| card.parse().unwrap()).collect()
+        };
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        engine.players[0].hand = Hand {
+            cards: cards("Kh Kd 7c 2h 2s").into(),
+        };
+        engine.deck.discard_pile = cards("9d 2d");
+        assert_eq!(engine.visible_discards(), Vec::from(cards("2d 9d")));
+        engine.opponents.emit(&GameEvent::CardDrawn {
+            player: "Bram".to_string(),
+            retrieved: Some("Kc".parse().unwrap()),
+        });
+
+        // Ada knows where her hand, the discards and Bram's king are, and
+        // nothing else, so only the last king and two could fill the house
+        let known = engine.known_information(0);
+        assert_eq!(known.picked_up, vec!["Kc".parse().unwrap()]);
+        let odds = engine.hand_odds().unwrap();
+        assert_eq!(odds.baseline, 5);
+        assert_eq!(odds.draw_improves, 2.0 / 44.0);
+        assert_eq!(odds.draw_expected, 290.0 / 44.0);
+        assert_eq!(odds.retrieve, Some(("2d".parse().unwrap(), 40)));
+        assert_eq!(odds.cards.len(), 5);
+
+        // Once Bram throws the king away again it's back in the discards
+        engine.opponents.emit(&GameEvent::CardDiscarded {
+            player: "Bram".to_string(),
+            card: "Kc".parse().unwrap(),
+        });
+        assert!(engine.known_information(0).picked_up.is_empty());
+
+        engine.variant = Variant::Gin;
+        assert!(engine.hand_odds().is_none());
+    }
+
+    #[test]
+    fn test_quiz_gives_a_head_start() {
+        let players = vec![player("Ada"), player("Bram")];
+        let mut engine =
+            GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+        let recording = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+        engine.subscribe(recording.clone());
+
+        assert!(engine.score_quiz("Ada", 2, 3).is_err());
+        engine.start_quiz().unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Quiz);
+        assert!(engine.redeal("Ada").is_err());
+        assert!(engine.score_quiz("Zed", 2, 3).is_err());
+        assert_eq!(engine.score_quiz("Ada", 5, 3).unwrap(), 6);
+        assert_eq!(
+            recording.lock().unwrap().first(),
+            Some(&GameEvent::QuizScored {
+                player: "Ada".to_string(),
+                correct: 3,
+                asked: 3,
+                points: 6,
+            })
+        );
+
+        engine.decide_turn_order(TurnOrder::Shuffle).unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Choosing);
+        assert!(engine.start_quiz().is_err());
+        let ada = engine.players.iter().find(|p| p.name == "Ada").unwrap();
+        assert_eq!(ada.score, 6);
+    }
+
+    #[test]
+    fn test_high_card_goes_first() {
+        let players = vec![player("Ada"), player("Bram"), player("Cathy")];
+        let mut engine =
+            GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Seating);
+        assert!(engine.draw().is_err());
+
+        let ceremony = engine.decide_turn_order(TurnOrder::HighCard).unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Choosing);
+        assert!(engine.players.iter().all(|p| p.hand.cards.len() == 5));
+
+        // The last draw for the first player beat every other draw that round
+        let first = engine.players[0].name.clone();
+        let winning_draw = ceremony
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                GameEvent::SeatCardDrawn { player, card } if *player == first => Some(*card),
+                _ => None,
+            })
+            .unwrap();
+        let last_round = &ceremony[ceremony.len() - 3..ceremony.len() - 1];
+        assert!(last_round.iter().all(|event| match event {
+            GameEvent::SeatCardDrawn { player, card } => *player == first || *card < winning_draw,
+            _ => true,
+        }));
+
+        // Seating is kept, play just starts from the winner
+        let order: Vec<&str> = engine.players.iter().map(|p| p.name.as_str()).collect();
+        assert!(
+            ["Ada Bram Cathy", "Bram Cathy Ada", "Cathy Ada Bram"]
+                .contains(&order.join(" ").as_str())
+        );
+        assert_eq!(
+            ceremony.last(),
+            Some(&GameEvent::TurnOrderDecided {
+                player: first,
+                order: order.iter().map(|name| name.to_string()).collect(),
+            })
+        );
+
+        assert!(engine.decide_turn_order(TurnOrder::HighCard).is_err());
+    }
+
+    #[test]
+    fn test_stripped_deck() {
+        let engine =
+            GameEngine::with_deck(vec![player("Ada"), player("Bram")], DeckConfig::piquet())
+                .unwrap();
+
+        // 32 cards, less two hands and the turned over discard
+        assert_eq!(engine.deck.draw_pile.len(), 32 - 10 - 1);
+        let low_cards = engine
+            .deck
+            .draw_pile
+            .iter()
+            .chain(engine.players.iter().flat_map(|p| &p.hand.cards))
+            .filter(|card| card.rank.to_u64().unwrap() < 7);
+        assert_eq!(low_cards.count(), 0);
+
+        let tiny_deck = "2-10,h,d,c".parse::<DeckConfig>().unwrap();
+        assert_eq!(tiny_deck.cards().unwrap().len(), 4);
+        assert!(GameEngine::with_deck(vec![player("Ada"), player("Bram")], tiny_deck).is_err());
+    }
+
+    #[test]
+    fn test_multi_deck_table() {
+        let players = || -> Vec<Player> {
+            ["Ada", "Bram", "Cathy", "Dev", "Esme", "Finn", "Gus"]
+                .into_iter()
+                .map(player)
+                .collect()
+        };
+        assert!(GameEngine::with_variant(players(), DeckConfig::default(), Variant::Gin).is_err());
+
+        let two_decks = "2x".parse::<DeckConfig>().unwrap();
+        assert_eq!(two_decks, DeckConfig::standard(2));
+        let engine = GameEngine::with_variant(players(), two_decks, Variant::Gin).unwrap();
+        assert_eq!(engine.deck.draw_pile.len(), 104 - 70 - 1);
+
+        let stripped = "2x,2-6".parse::<DeckConfig>().unwrap();
+        assert_eq!(stripped.cards().unwrap().len(), 64);
+        assert!("0x".parse::<DeckConfig>().is_err());
+    }
+
+    #[test]
+    fn test_the_hint_plays_a_cheap_meld_for_the_win() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        engine.target_score = 100;
+        engine.current_player_idx = 0;
+        engine.players[0].hand = Hand {
+            cards: ["5h", "5c", "9d", "9s", "Kh"]
+                .map(|card| card.parse().unwrap())
+                .to_vec(),
+        };
+
+        // Two pair is nothing to play early in the game
+        assert!(!engine.hint().should_play);
+
+        // One small meld from the target, it's worth playing now for the
+        // points towards it, though it doesn't win outright
+        engine.players[0].score = 100 - crate::analysis::SMALL_MELD_SCORE;
+        let hint = engine.hint();
+        assert!(hint.should_play);
+        assert!(hint.reasoning.contains("play cheap melds"));
+    }
+
+    #[test]
+    fn test_an_ai_turn_keeps_to_its_time_budget() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        engine.set_seed(7);
+        engine.players[0].player_type = Some(crate::game::PlayerType::Balanced);
+        engine.set_ai_search(AiSearch::Time(Duration::from_millis(1)));
+
+        let decision = engine.ai_decision().unwrap();
+        assert!(decision.expected_score.is_finite());
+        assert!(engine.take_ai_turn().is_ok());
+    }
+
+    #[test]
+    fn test_a_panicked_precompute_leaves_the_cache_usable() {
+        let precomputed = Precomputed::default();
+        let searching = precomputed.clone();
+        let _ = std::thread::spawn(move || {
+            let _decisions = searching.0.lock().unwrap();
+            panic!("the search fell over");
+        })
+        .join();
+        assert!(precomputed.0.is_poisoned());
+
+        assert!(precomputed.take(7).is_none());
+        precomputed.clear();
+    }
+
+    #[test]
+    fn test_the_next_ai_decision_is_worked_out_ahead() {
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        engine.set_seed(7);
+        engine.players[1].player_type = Some(crate::game::PlayerType::Balanced);
+
+        // Every card Ada could throw away, drawing or taking the discard
+        engine.precompute();
+        assert_eq!(engine.precomputed.0.lock().unwrap().len(), 12);
+
+        let drawn = engine.draw().unwrap();
+        engine.discard(drawn).unwrap();
+        let key = engine.decision_key();
+        assert!(engine.precomputed.0.lock().unwrap().contains_key(&key));
+        let worked_out = engine.search_decision().unwrap();
+        let decision = engine.ai_decision().unwrap();
+        assert_eq!(decision.action, worked_out.action);
+        assert_eq!(decision.expected_score, worked_out.expected_score);
+        assert!(engine.precomputed.take(key).is_none());
+
+        // Nothing to work out for a human next, or in the middle of a turn
+        engine.players[0].player_type = None;
+        engine.draw().unwrap();
+        assert!(engine.projections().is_empty());
+    }
+
+    #[test]
+    fn test_a_seed_replays_at_the_default_search() {
+        let config = crate::config::Config::default();
+        let play = |precompute: bool| {
+            let players = vec![player("Ada"), player("Bram")];
+            let mut engine =
+                GameEngine::pre_game(players, DeckConfig::default(), Variant::default()).unwrap();
+            engine.set_seed(11);
+            engine.set_ai_search(config.ai_search);
+            for player in &mut engine.players {
+                player.player_type = Some(config.ai.clone());
+            }
+            engine.decide_turn_order(TurnOrder::HighCard).unwrap();
+
+            // The second turn's decision worked out ahead, or on the turn
+            if precompute {
+                engine.precompute();
+            }
+            let mut turns = Vec::new();
+            for _ in 0..2 {
+                let turn = engine.take_ai_turn().unwrap();
+                turns.push(format!("{turn:?}"));
+                if matches!(turn, AiTurn::Played { .. }) {
+                    break;
+                }
+            }
+            (turns, engine.disclosure())
+        };
+
+        assert_eq!(config.ai_search, AiSearch::Nodes);
+        assert_eq!(play(false), play(true));
+    }
+}
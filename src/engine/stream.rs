@@ -0,0 +1,124 @@
+//! The engine for async front-ends.
+//!
+//! A TUI or the network server has input, table talk and the AI's thinking
+//! to wait on at once. Everything here can be raced against the rest in a
+//! `select!`: a future dropped before it's finished leaves the engine, and
+//! every event, where it was.
+
+use super::{AiTurn, GameEngine};
+use crate::error::RummyError;
+use crate::game::AutoPlayDecision;
+use crate::game::events::GameEvent;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+/// Every event of a game from when it was asked for, one at a time.
+#[derive(Debug)]
+pub struct EventStream {
+    events: UnboundedReceiver<GameEvent>,
+}
+
+impl EventStream {
+    /// The next thing to happen at the table, waiting for it if it hasn't
+    /// yet. `None` once the engine's gone. An event arriving as the wait is
+    /// given up on is kept for the next call.
+    pub async fn next_event(&mut self) -> Option<GameEvent> {
+        self.events.recv().await
+    }
+
+    /// The next thing that's already happened, without waiting.
+    pub fn try_next_event(&mut self) -> Option<GameEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+impl GameEngine {
+    /// Every event from now on, to be waited on.
+    pub fn event_stream(&mut self) -> EventStream {
+        let (tx, events) = mpsc::unbounded_channel();
+        self.subscribe(Arc::new(Mutex::new(tx)));
+        EventStream { events }
+    }
+
+    /// `ai_decision`, worked out on a blocking thread so the async workers
+    /// aren't held up. The future doesn't borrow the engine, so it can be
+    /// awaited with the engine borrowed elsewhere, and dropping it changes
+    /// nothing.
+    pub fn decide(&self) -> impl Future<Output = Option<AutoPlayDecision>> + Send + 'static {
+        let engine = self.detached();
+        async move {
+            tokio::task::spawn_blocking(move || engine.ai_decision())
+                .await
+                .ok()
+                .flatten()
+        }
+    }
+
+    /// `take_ai_turn`, deciding with `decide`. Nothing's played until the
+    /// decision's made, so giving up on it leaves the turn untouched.
+    pub async fn take_ai_turn_async(&mut self) -> Result<AiTurn, RummyError> {
+        let decision = self
+            .decide()
+            .await
+            .ok_or_else(|| RummyError::InvalidMove("It's a human player's turn".to_string()))?;
+        self.play_ai_decision(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Hand, Player, PlayerType};
+    use std::collections::VecDeque;
+
+    fn player(name: &str, player_type: Option<PlayerType>) -> Player {
+        Player {
+            name: name.to_string(),
+            description: String::new(),
+            player_type,
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_and_ai_turns_can_be_awaited() {
+        let mut engine = GameEngine::new(vec![
+            player("Ada", None),
+            player("Bram", Some(PlayerType::Balanced)),
+        ])
+        .unwrap();
+        let mut events = engine.event_stream();
+        assert!(events.try_next_event().is_none());
+
+        let drawn = engine.draw().unwrap();
+        engine.discard(drawn).unwrap();
+        assert!(matches!(
+            events.next_event().await,
+            Some(GameEvent::CardDrawn { player, .. }) if player == "Ada"
+        ));
+        assert!(matches!(
+            events.try_next_event(),
+            Some(GameEvent::CardDiscarded { card, .. }) if card == drawn
+        ));
+
+        // Nothing worth playing, so Bram has to draw or take the discard
+        engine.players[1].hand = Hand {
+            cards: ["2h", "5d", "9c", "Js", "Kd"]
+                .iter()
+                .map(|card| card.parse().unwrap())
+                .collect(),
+        };
+
+        // A decision given up on leaves the turn to be taken
+        drop(engine.decide());
+        assert_eq!(engine.current_player_idx, 1);
+        engine.take_ai_turn_async().await.unwrap();
+        assert_eq!(engine.current_player().name, "Ada");
+        assert!(events.try_next_event().is_some());
+
+        assert!(engine.take_ai_turn_async().await.is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use thiserror::Error;
+
+/// Every way the rummy library can fail.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum RummyError {
+    /// The card string doesn't have the `<name><suite>` shape (e.g. `10h`).
+    #[error("Card formatting is incorrect: {0}")]
+    InvalidCard(String),
+
+    /// The suite part of a card string isn't one of `s`, `h`, `c` or `d`.
+    #[error("{0} does not match any known suite!")]
+    UnknownSuite(String),
+
+    /// The name part of a card string isn't one of `2`-`10`, `J`, `Q`, `K` or `A`.
+    #[error("{0} does not match any known card name!")]
+    UnknownName(String),
+
+    /// A rank or card name has no counterpart to convert to.
+    #[error("{0} does not match any known rank!")]
+    UnknownRank(String),
+
+    /// There are no cards left to draw or deal.
+    #[error("Deck is empty")]
+    EmptyDeck,
+
+    /// The requested action isn't allowed in the current game state.
+    #[error("Invalid move: {0}")]
+    InvalidMove(String),
+
+    /// A configuration file or option couldn't be loaded or understood.
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    /// A socket couldn't be opened, or a peer sent something we couldn't read.
+    #[error("Network error: {0}")]
+    Network(String),
+
+    /// A message, snapshot or event wasn't JSON in this version of the protocol.
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    /// The sound effects' output device couldn't be opened.
+    #[error("Audio error: {0}")]
+    Audio(String),
+
+    /// A file couldn't be read or written.
+    #[error("IO error: {0}")]
+    Io(String),
+}
@@ -0,0 +1,288 @@
+//! C bindings for embedding the scoring and analysis in a native app, built
+//! into a `cdylib` or `staticlib` with `cargo rustc --lib --release
+//! --no-default-features --features ffi --crate-type cdylib --crate-type staticlib`.
+//!
+//! Cards go in as NUL-terminated text, written the way they are everywhere
+//! else, like "10h Jc", and come back out as `RummyCard`s. The structs are
+//! `#[repr(C)]` and keep their layout. Every function returns `RUMMY_OK`, or
+//! one of the `RUMMY_ERR_` codes without writing to `out`.
+
+use crate::analysis::{self, KnownInformation};
+use crate::card::Card;
+use crate::game::rules::Variant;
+use crate::game::{Hand, PlayAction, PlayerType};
+use crate::scoring::{MELD_SIZE, best_meld};
+use std::ffi::{CStr, c_char};
+
+pub const RUMMY_OK: i32 = 0;
+/// A pointer was null.
+pub const RUMMY_ERR_NULL: i32 = -1;
+/// Text wasn't UTF-8.
+pub const RUMMY_ERR_UTF8: i32 = -2;
+/// Text wasn't a card, or a player type.
+pub const RUMMY_ERR_PARSE: i32 = -3;
+/// A hand to advise on wasn't five cards, or one to meld had none.
+pub const RUMMY_ERR_HAND: i32 = -4;
+
+/// `RummyAdvice::action`: play the hand as it stands.
+pub const RUMMY_PLAY: u8 = 0;
+/// `RummyAdvice::action`: draw from the deck.
+pub const RUMMY_DRAW: u8 = 1;
+/// `RummyAdvice::action`: take the top discard.
+pub const RUMMY_RETRIEVE: u8 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RummyCard {
+    /// 2 to 10, then 11 to 14 for jack, queen, king and ace.
+    pub rank: u8,
+    /// 0 spades, 1 hearts, 2 clubs, 3 diamonds.
+    pub suit: u8,
+}
+
+impl From<Card> for RummyCard {
+    fn from(card: Card) -> Self {
+        Self {
+            rank: card.rank as u8 + 2,
+            suit: card.suite as u8,
+        }
+    }
+}
+
+/// A hand's best meld, from `rummy_best_meld`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RummyMeld {
+    pub score: u64,
+    /// How many of `cards` make the meld.
+    pub len: usize,
+    pub cards: [RummyCard; MELD_SIZE],
+}
+
+/// What the AI would do, from `rummy_advise`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RummyAdvice {
+    /// `RUMMY_PLAY`, `RUMMY_DRAW` or `RUMMY_RETRIEVE`.
+    pub action: u8,
+    pub confidence: f64,
+    pub expected_score: f64,
+    /// Whether `discard` is the card to throw away after retrieving.
+    pub has_discard: bool,
+    pub discard: RummyCard,
+}
+
+/// # Safety
+///
+/// `text` is null or a NUL-terminated string.
+unsafe fn text<'a>(text: *const c_char) -> Result<&'a str, i32> {
+    if text.is_null() {
+        return Err(RUMMY_ERR_NULL);
+    }
+    unsafe { CStr::from_ptr(text) }
+        .to_str()
+        .map_err(|_| RUMMY_ERR_UTF8)
+}
+
+fn cards(text: &str) -> Result<Vec<Card>, i32> {
+    text.split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .map_err(|_| RUMMY_ERR_PARSE)
+}
+
+/// A hand the analysis knows how to advise on, all five cards of it.
+fn hand(text: &str) -> Result<Hand, i32> {
+    let cards = cards(text)?;
+    if cards.len() != Variant::Bookclub.hand_size() {
+        return Err(RUMMY_ERR_HAND);
+    }
+    Ok(Hand { cards })
+}
+
+/// # Safety
+///
+/// `out` is null or valid to write a `T` to.
+unsafe fn write<T>(out: *mut T, result: Result<T, i32>) -> i32 {
+    if out.is_null() {
+        return RUMMY_ERR_NULL;
+    }
+    match result {
+        Ok(value) => {
+            unsafe { out.write(value) };
+            RUMMY_OK
+        }
+        Err(code) => code,
+    }
+}
+
+/// Parses one card, like "10h", into `out`.
+///
+/// # Safety
+///
+/// `card` is a NUL-terminated string and `out` points to a `RummyCard`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rummy_parse_card(card: *const c_char, out: *mut RummyCard) -> i32 {
+    let card = unsafe { text(card) }.and_then(|card| {
+        card.trim()
+            .parse::<Card>()
+            .map(RummyCard::from)
+            .map_err(|_| RUMMY_ERR_PARSE)
+    });
+    unsafe { write(out, card) }
+}
+
+/// The best meld in `cards`, space separated, into `out`.
+///
+/// # Safety
+///
+/// `cards` is a NUL-terminated string and `out` points to a `RummyMeld`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rummy_best_meld(cards: *const c_char, out: *mut RummyMeld) -> i32 {
+    let cards = unsafe { text(cards) }
+        .and_then(self::cards)
+        .and_then(|cards| {
+            if cards.is_empty() {
+                Err(RUMMY_ERR_HAND)
+            } else {
+                Ok(cards)
+            }
+        });
+    let meld = cards.map(|cards| {
+        let meld = best_meld(&cards);
+        let mut view = RummyMeld {
+            score: meld.score,
+            len: meld.cards.len(),
+            ..RummyMeld::default()
+        };
+        for (slot, card) in view.cards.iter_mut().zip(meld.cards) {
+            *slot = card.into();
+        }
+        view
+    });
+    unsafe { write(out, meld) }
+}
+
+/// How a `player_type` AI, like "balanced", would play the five cards of
+/// `hand` with `discards` on the table, the top one last, into `out`. Both
+/// are space separated cards, `discards` empty if there are none.
+///
+/// # Safety
+///
+/// `hand`, `discards` and `player_type` are NUL-terminated strings and `out`
+/// points to a `RummyAdvice`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rummy_advise(
+    hand: *const c_char,
+    discards: *const c_char,
+    player_type: *const c_char,
+    out: *mut RummyAdvice,
+) -> i32 {
+    let advice = (|| {
+        let hand = self::hand(unsafe { text(hand) }?)?;
+        let known = KnownInformation {
+            discards: cards(unsafe { text(discards) }?)?,
+            ..KnownInformation::default()
+        };
+        let player_type: PlayerType = unsafe { text(player_type) }?
+            .parse()
+            .map_err(|_| RUMMY_ERR_PARSE)?;
+
+        let decision = analysis::advise(&hand, &known, player_type);
+        Ok(RummyAdvice {
+            action: match decision.action {
+                PlayAction::Play => RUMMY_PLAY,
+                PlayAction::Draw => RUMMY_DRAW,
+                PlayAction::Retrieve => RUMMY_RETRIEVE,
+            },
+            confidence: decision.confidence,
+            expected_score: decision.expected_score,
+            has_discard: decision.card_to_discard.is_some(),
+            discard: decision.card_to_discard.map(Into::into).unwrap_or_default(),
+        })
+    })();
+    unsafe { write(out, advice) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    #[test]
+    fn test_cards_meld_and_advice_cross_the_boundary() {
+        let mut card = RummyCard::default();
+        assert_eq!(
+            unsafe { rummy_parse_card(c"10h".as_ptr(), &mut card) },
+            RUMMY_OK
+        );
+        assert_eq!(card, RummyCard { rank: 10, suit: 1 });
+        assert_eq!(
+            unsafe { rummy_parse_card(c"1z".as_ptr(), &mut card) },
+            RUMMY_ERR_PARSE
+        );
+        assert_eq!(
+            unsafe { rummy_parse_card(ptr::null(), &mut card) },
+            RUMMY_ERR_NULL
+        );
+
+        // The best five of six
+        let mut meld = RummyMeld::default();
+        let hand = c"Ks Kh Kd 2c 5h As";
+        assert_eq!(
+            unsafe { rummy_best_meld(hand.as_ptr(), &mut meld) },
+            RUMMY_OK
+        );
+        assert_eq!(meld.len, MELD_SIZE);
+        assert_eq!(meld.score, 15);
+        assert!(meld.cards.contains(&RummyCard { rank: 13, suit: 0 }));
+
+        // The last nine on offer makes four of a kind
+        let mut advice = RummyAdvice::default();
+        let code = unsafe {
+            rummy_advise(
+                c"9s 9h 9d 2c 5h".as_ptr(),
+                c"3d 9c".as_ptr(),
+                c"balanced".as_ptr(),
+                &mut advice,
+            )
+        };
+        assert_eq!(code, RUMMY_OK);
+        assert_ne!(advice.action, RUMMY_DRAW);
+        assert!(advice.expected_score >= 50.0);
+        assert_eq!(
+            unsafe {
+                rummy_advise(
+                    hand.as_ptr(),
+                    c"".as_ptr(),
+                    c"reckless".as_ptr(),
+                    &mut advice,
+                )
+            },
+            RUMMY_ERR_PARSE
+        );
+    }
+
+    #[test]
+    fn test_only_whole_hands_are_analysed() {
+        let mut meld = RummyMeld::default();
+        assert_eq!(
+            unsafe { rummy_best_meld(c" ".as_ptr(), &mut meld) },
+            RUMMY_ERR_HAND
+        );
+
+        let mut advice = RummyAdvice::default();
+        for hand in [c"", c"9s 9h 9d 2c 5h As", c"2s 3s 4s 5s 6s 7s 8s 9s 10s Js"] {
+            let code = unsafe {
+                rummy_advise(
+                    hand.as_ptr(),
+                    c"".as_ptr(),
+                    c"balanced".as_ptr(),
+                    &mut advice,
+                )
+            };
+            assert_eq!(code, RUMMY_ERR_HAND);
+        }
+        assert_eq!(advice, RummyAdvice::default());
+    }
+}
@@ -0,0 +1,4 @@
+//! What the terminal front-ends have in common, whether the game's played
+//! here or on a server somewhere else.
+
+pub mod commands;
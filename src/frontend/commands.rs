@@ -0,0 +1,135 @@
+//! Slash commands, typed at any prompt instead of an answer: `/help`,
+//! `/hand`, `/scores`, `/history`, `/hint`, `/save`, `/quit` and
+//! `/say <message>`.
+//!
+//! Parsing them is shared. What they do is up to the front-end, since a
+//! local game can save itself and a network player can't.
+
+use crate::card::Card;
+use crate::display::terminal::Scroll;
+use crate::error::RummyError;
+use crate::i18n::Prompt;
+use crate::scoring::best_meld;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    /// Your hand, and the best meld in it.
+    Hand,
+    Scores,
+    /// A page back through the talk and the actions, like PgUp.
+    History,
+    Hint,
+    Save,
+    Quit,
+    /// Something to add to the conversation.
+    Say(String),
+}
+
+impl FromStr for Command {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<Command, RummyError> {
+        let input = input.trim();
+        let (name, rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        let command = match name.to_lowercase().as_str() {
+            "/help" | "/?" => Command::Help,
+            "/hand" => Command::Hand,
+            "/scores" => Command::Scores,
+            "/history" => Command::History,
+            "/hint" => Command::Hint,
+            "/save" => Command::Save,
+            "/quit" => Command::Quit,
+            "/say" => return Ok(Command::Say(rest.trim().to_string())),
+            _ => {
+                let unknown = Prompt::UnknownCommand {
+                    command: name.to_string(),
+                };
+                return Err(RummyError::InvalidMove(unknown.to_string()));
+            }
+        };
+        if !rest.trim().is_empty() {
+            let takes_nothing = Prompt::CommandTakesNothing {
+                command: name.to_string(),
+            };
+            return Err(RummyError::InvalidMove(takes_nothing.to_string()));
+        }
+        Ok(command)
+    }
+}
+
+/// The command in a line typed at the prompt, or what's wrong with it.
+/// `None` for anything not starting with `/`, to be read as an answer.
+pub fn parse(line: &str) -> Option<Result<Command, RummyError>> {
+    line.trim_start().starts_with('/').then(|| line.parse())
+}
+
+/// The scroll a line asks for: PgUp or PgDn, or `/history` for a page back.
+pub fn scroll(line: &str) -> Option<Scroll> {
+    Scroll::from_input(line)
+        .or_else(|| matches!(parse(line), Some(Ok(Command::History))).then_some(Scroll::Back))
+}
+
+/// What `/hand` says about `hand`.
+pub fn hand_line(hand: &[Card]) -> String {
+    let cards: Vec<String> = hand.iter().map(Card::to_string).collect();
+    Prompt::YourHand {
+        cards: cards.join(" "),
+        score: best_meld(hand).score,
+    }
+    .to_string()
+}
+
+/// What `/scores` says about everyone's `scores`, in seating order.
+pub fn scores_line(scores: &[(String, usize)]) -> String {
+    Prompt::Scores {
+        scores: scores.to_vec(),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+
+    #[test]
+    fn test_commands_are_read_at_any_prompt() {
+        assert_eq!(parse(" /Hand").map(Result::unwrap), Some(Command::Hand));
+        assert_eq!(
+            parse("/say Timshel! 🙌").map(Result::unwrap),
+            Some(Command::Say("Timshel! 🙌".to_string()))
+        );
+        assert_eq!(
+            parse("/say").map(Result::unwrap),
+            Some(Command::Say(String::new()))
+        );
+        assert!(parse("/sayonara").unwrap().is_err());
+        assert!(parse("/quit now").unwrap().is_err());
+
+        // Anything else is an answer
+        assert!(parse("say hi").is_none());
+        assert!(parse("10h").is_none());
+
+        assert_eq!(scroll("/history"), Some(Scroll::Back));
+        assert_eq!(scroll("/hint"), None);
+
+        let hand: Vec<Card> = ["Ks", "Kh", "Kd", "2c", "5h"]
+            .iter()
+            .map(|card| card.parse().unwrap())
+            .collect();
+        assert!(hand_line(&hand).starts_with("Your hand: Ks Kh Kd 2c 5h."));
+        assert_eq!(
+            scores_line(&[("Ada".to_string(), 40), ("Lee".to_string(), 12)]),
+            "Scores: Ada 40, Lee 12"
+        );
+        let unknown = Prompt::UnknownCommand {
+            command: "/sayonara".to_string(),
+        };
+        assert_eq!(
+            unknown.text(Locale::Es),
+            "/sayonara no es una orden, /help las enumera"
+        );
+    }
+}
@@ -1,21 +1,36 @@
-use crate::card::Card;
+use crate::analysis::AnalysisConfig;
+use crate::card::{Card, Name, Rank, Suite, ToU64};
+use crate::error::RummyError;
 use rand::prelude::SliceRandom;
-use rand::rng;
-use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
-
-#[derive(Debug, Eq, PartialEq, Hash)]
-pub struct Deck<'a> {
-    pub draw_pile: &'a mut VecDeque<Card>,
-    pub discard_pile: &'a mut VecDeque<Card>,
-}
-
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+use rand::{Rng, rng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+pub mod events;
+pub mod layoff;
+pub mod rules;
+pub mod sheet;
+
+/// Score a player needs to reach to win the match.
+pub const TARGET_SCORE: usize = 100;
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Hand {
     pub cards: Vec<Card>,
 }
 
-#[derive(Clone, Debug)]
+impl Hand {
+    /// The same for every order of the same cards, unlike comparing hands.
+    /// See `scoring::canonical_key`.
+    pub fn canonical_key(&self) -> u64 {
+        crate::scoring::canonical_key(&self.cards)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Player {
     pub name: String,
     pub description: String,
@@ -26,11 +41,66 @@ pub struct Player {
     pub score: usize,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum PlayerType {
+    #[serde(alias = "conservative")]
     Conservative,
+    #[serde(alias = "aggressive")]
     Aggressive,
+    #[serde(alias = "balanced")]
     Balanced,
+    /// All three of the above, voting on every decision.
+    #[serde(alias = "ensemble")]
+    Ensemble,
+    /// Plays out the rest of the round at random, many times over, instead
+    /// of working out the odds. See `MonteCarlo`.
+    #[serde(alias = "monte-carlo", alias = "mcts")]
+    MonteCarlo,
+}
+
+impl PlayerType {
+    /// How thoroughly this kind of player searches ahead. Conservative
+    /// players count every card that could come, the rest play the odds.
+    pub fn analysis_config(&self) -> AnalysisConfig {
+        match self {
+            PlayerType::Conservative => AnalysisConfig::exact(),
+            PlayerType::Aggressive
+            | PlayerType::Balanced
+            | PlayerType::Ensemble
+            | PlayerType::MonteCarlo => AnalysisConfig::default(),
+        }
+    }
+}
+
+impl fmt::Display for PlayerType {
+    /// Writes the player type as it's typed, e.g. `aggressive`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PlayerType::Conservative => "conservative",
+            PlayerType::Aggressive => "aggressive",
+            PlayerType::Balanced => "balanced",
+            PlayerType::Ensemble => "ensemble",
+            PlayerType::MonteCarlo => "monte-carlo",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for PlayerType {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<PlayerType, RummyError> {
+        match input.trim().to_lowercase().as_str() {
+            "conservative" => Ok(PlayerType::Conservative),
+            "aggressive" => Ok(PlayerType::Aggressive),
+            "balanced" => Ok(PlayerType::Balanced),
+            "ensemble" => Ok(PlayerType::Ensemble),
+            "monte-carlo" | "mcts" => Ok(PlayerType::MonteCarlo),
+            other => Err(RummyError::Config(format!(
+                "Unknown player type {other:?}, expected conservative, aggressive, balanced, ensemble or monte-carlo"
+            ))),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -48,17 +118,34 @@ pub struct AutoPlayDecision {
     pub card_to_discard: Option<Card>, // Which card to discard if drawing
 }
 
-#[derive(Clone, Debug)]
+/// One of a player's turns, as the engine recorded it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ActionHistory {
     pub choice: Choice,
     pub card_to_discard: Option<Card>, // Which card to discard if drawing
+    /// The card drawn or retrieved. A drawn card is the player's secret.
+    #[serde(default)]
+    pub drawn: Option<Card>,
+    /// Which turn of the game it was, counting from 1 across every round.
+    #[serde(default)]
+    pub turn: usize,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Choice {
     Draw,
     Play,
     Retrieve,
+    /// Ending a gin round on the player's deadwood, which is how gin hands
+    /// are played.
+    Knock,
+}
+
+impl Player {
+    /// Every turn the player has taken this game, oldest first.
+    pub fn history(&self) -> &VecDeque<ActionHistory> {
+        &self.actions
+    }
 }
 
 impl PartialEq for Player {
@@ -68,141 +155,323 @@ impl PartialEq for Player {
     }
 }
 
-/// Creates and shuffles a standard 52-card deck.
-pub fn shuffle_deck() -> Result<VecDeque<Card>, String> {
-    use crate::card::{Suite, ToName};
+/// Creates and shuffles a standard 52-card deck. Bigger tables can deal from
+/// more decks shuffled together, see `DeckConfig::standard`.
+pub fn shuffle_deck() -> Result<VecDeque<Card>, RummyError> {
+    build_deck(&DeckConfig::default())
+}
 
-    let mut deck: Vec<Card> = [Suite::Spades, Suite::Hearts, Suite::Diamonds, Suite::Clubs]
-        .iter()
-        .flat_map(|suite: &Suite| {
-            let cards = [
-                "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K", "A",
-            ]
-            .map(|name_string| {
-                let name = (*name_string).to_string().to_name().unwrap();
-                let rank = name.to_rank().unwrap();
+/// Creates and shuffles a deck holding the cards `config` allows.
+pub fn build_deck(config: &DeckConfig) -> Result<VecDeque<Card>, RummyError> {
+    build_deck_with(config, &mut rng())
+}
 
-                Card {
-                    name,
-                    rank,
-                    suite: *suite,
-                }
-            });
+/// Like `build_deck`, shuffling with `rng`.
+pub fn build_deck_with<R: Rng + ?Sized>(
+    config: &DeckConfig,
+    rng: &mut R,
+) -> Result<VecDeque<Card>, RummyError> {
+    let mut deck = config.cards()?;
+    deck.shuffle(rng);
 
-            cards.to_vec()
-        })
-        .collect::<Vec<Card>>();
+    Ok(VecDeque::from(deck))
+}
 
-    deck.shuffle(&mut rng());
+/// Which cards a deck is built from: one or more standard decks shuffled
+/// together, minus the stripped names and suites.
+///
+/// The analysis only ever considers cards still in the draw pile, so a
+/// stripped deck shrinks the unseen cards it reasons about along with it,
+/// and a second deck puts a second copy of every card in it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeckConfig {
+    /// How many decks are shuffled together.
+    pub decks: usize,
+    /// Card names left out of the deck, e.g. `["2", "3", "4", "5", "6"]`.
+    pub strip_names: Vec<Name>,
+    /// Suites left out of the deck.
+    pub strip_suites: Vec<Suite>,
+}
 
-    Ok(VecDeque::from(deck))
+impl Default for DeckConfig {
+    fn default() -> Self {
+        Self::standard(1)
+    }
 }
 
-type PlayersAndPiles = (Vec<Player>, VecDeque<Card>, VecDeque<Card>);
-
-/// Deals 5 cards to each player from the deck.
-pub fn deal_cards<'a>(
-    mut players: Vec<Player>,
-    deck: RefCell<&mut Deck<'a>>,
-) -> Result<PlayersAndPiles, String> {
-    for _ in 0..5 {
-        for player in players.iter_mut() {
-            let card = deck
-                .borrow_mut()
-                .draw_pile
-                .pop_back()
-                .ok_or("Deck is empty")?;
-            player.hand.cards.push(card);
+impl DeckConfig {
+    /// `n_decks` standard 52 card decks shuffled together, for tables too big
+    /// to deal from one.
+    pub fn standard(n_decks: usize) -> Self {
+        Self {
+            decks: n_decks,
+            strip_names: Vec::new(),
+            strip_suites: Vec::new(),
         }
     }
 
-    let draw_pile = deck
-        .borrow_mut()
-        .draw_pile
-        .iter()
-        .copied()
-        .collect::<VecDeque<Card>>();
-    let discard_pile = deck
-        .borrow_mut()
-        .discard_pile
-        .iter()
-        .copied()
-        .collect::<VecDeque<Card>>();
-
-    Ok((players, draw_pile, discard_pile))
-}
-
-/// Calculates the best possible meld score from a 6-card hand by trying all 5-card combinations
-pub fn calculate_best_meld_from_hand(hand: &Hand) -> (u64, Hand) {
-    use crate::scoring::{CardVec, MELD_FUNCTIONS};
-    let mut score_to_hand = HashMap::new();
-
-    // Try all possible 5-card combinations from the 6-card hand
-    for skip_idx in 0..hand.cards.len() {
-        let mut five_card_hand = CardVec::new();
-        for (i, &card) in hand.cards.iter().enumerate() {
-            if i != skip_idx {
-                five_card_hand.push(card);
-            }
+    /// The 32 card piquet deck: 7 up to Ace in every suite.
+    pub fn piquet() -> Self {
+        Self {
+            strip_names: vec![Name::Two, Name::Three, Name::Four, Name::Five, Name::Six],
+            ..Self::standard(1)
         }
+    }
+
+    pub fn contains(&self, card: &Card) -> bool {
+        !self.strip_names.contains(&card.name) && !self.strip_suites.contains(&card.suite)
+    }
 
-        if five_card_hand.len() == 5 {
-            for meld_fn in MELD_FUNCTIONS {
-                let score = meld_fn(five_card_hand.clone());
-                score_to_hand
-                    .entry(score)
-                    .or_insert_with(|| five_card_hand.clone());
+    /// Every card in the deck, unshuffled, with a copy of each card for
+    /// every deck.
+    pub fn cards(&self) -> Result<Vec<Card>, RummyError> {
+        let mut cards = Vec::new();
+        for suite in Suite::ALL {
+            for name in Name::ALL {
+                let card = Card {
+                    rank: name.to_rank()?,
+                    suite,
+                    name,
+                };
+                if self.contains(&card) {
+                    cards.extend(std::iter::repeat_n(card, self.decks));
+                }
             }
         }
+
+        Ok(cards)
     }
+}
 
-    if let Some((best_score, high_hand)) = score_to_hand.iter().max_by_key(|(score, _)| *score) {
-        match best_score {
-            Ok(score) => {
-                let mut card_vec = Vec::new();
-                for card in high_hand {
-                    card_vec.push(*card);
-                }
-                let hand = Hand { cards: card_vec };
+impl FromStr for DeckConfig {
+    type Err = RummyError;
+
+    /// Either a named deck (`standard` or `piquet`) or a comma separated list
+    /// of how many decks to shuffle together and what to strip: `2x`, card
+    /// names, ranges of names and suites, e.g. `2x` or `2-6,h`.
+    fn from_str(input: &str) -> Result<DeckConfig, RummyError> {
+        match input.trim() {
+            "standard" => return Ok(DeckConfig::default()),
+            "piquet" => return Ok(DeckConfig::piquet()),
+            _ => {}
+        }
 
-                return (*score, hand);
+        let mut config = DeckConfig::default();
+        for item in input.split(',').map(str::trim) {
+            if let Some(decks) = item.strip_suffix('x') {
+                config.decks = match decks.parse() {
+                    Ok(decks) if decks > 0 => decks,
+                    _ => {
+                        return Err(RummyError::Config(format!(
+                            "Can't deal from {decks:?} decks"
+                        )));
+                    }
+                };
+            } else if let Some((low, high)) = item.split_once('-') {
+                let (low, high) = (low.parse::<Rank>()?, high.parse::<Rank>()?);
+                let (low, high) = (low.to_u64()?, high.to_u64()?);
+                for name in Name::ALL {
+                    if (low..=high).contains(&name.to_rank()?.to_u64()?) {
+                        config.strip_names.push(name);
+                    }
+                }
+            } else if let Ok(name) = item.parse::<Name>() {
+                config.strip_names.push(name);
+            } else if let Ok(suite) = item.parse::<Suite>() {
+                config.strip_suites.push(suite);
+            } else {
+                return Err(RummyError::Config(format!(
+                    "Don't know how to strip {item:?} from the deck"
+                )));
             }
-            _ => return (0, hand.clone()),
         }
+
+        Ok(config)
     }
+}
 
-    (0, hand.clone())
+/// How the table decides who goes first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TurnOrder {
+    /// Shuffle the seats, no ceremony.
+    Shuffle,
+    /// Everyone draws a card and the highest goes first. Ties draw again.
+    #[default]
+    HighCard,
+    /// A dealer cuts the deck and counts the cut card's value around the
+    /// table from their left. Whoever the count lands on goes first.
+    DealerCut,
 }
 
-pub fn calculate_best_meld_from_5_card_hand(hand: &Hand) -> (u64, Hand) {
-    use crate::scoring::{CardVec, MELD_FUNCTIONS};
+impl FromStr for TurnOrder {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<TurnOrder, RummyError> {
+        match input.trim().to_lowercase().as_str() {
+            "shuffle" => Ok(TurnOrder::Shuffle),
+            "high-card" => Ok(TurnOrder::HighCard),
+            "cut" | "dealer-cut" => Ok(TurnOrder::DealerCut),
+            other => Err(RummyError::Config(format!(
+                "Unknown turn order {other:?}, expected shuffle, high-card or cut"
+            ))),
+        }
+    }
+}
 
-    let mut best_score = 0;
-    let mut five_card_hand = CardVec::new();
+/// Where the dealer cuts the shuffled deck.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CutPosition {
+    /// Anywhere, like a real cut.
+    Random,
+    /// This many cards from the top.
+    At(usize),
+}
 
-    for card in &hand.cards {
-        five_card_hand.push(*card);
+impl FromStr for CutPosition {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<CutPosition, RummyError> {
+        match input.trim().to_lowercase().as_str() {
+            "random" => Ok(CutPosition::Random),
+            other => other.parse().map(CutPosition::At).map_err(|_| {
+                RummyError::Config(format!(
+                    "Unknown cut {other:?}, expected random or a number of cards"
+                ))
+            }),
+        }
     }
+}
 
-    for meld_fn in MELD_FUNCTIONS {
-        let score = meld_fn(five_card_hand.clone()).unwrap();
-        if score > best_score {
-            best_score = score;
+/// What the dealer does between the shuffle and the deal. Neither changes
+/// the odds, but some tables won't deal without them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DealRituals {
+    pub cut: Option<CutPosition>,
+    /// Burn the top card face-down.
+    pub burn: bool,
+}
+
+/// Cards fixed before every deal, to set the same scenario up again and
+/// again: to practise on a known hand, or see what the AI makes of one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DealPlan {
+    /// Hands dealt to players by name, whatever the shuffle.
+    pub hands: Vec<(String, Vec<Card>)>,
+    /// Cards off the top of the deck, top first. They're dealt a hand at a
+    /// time to the players without one of their own, then turned up and
+    /// drawn, with the rest of the shuffle under them.
+    pub stack: Vec<Card>,
+}
+
+/// The draw and discard piles a round is played from.
+#[derive(Clone, Debug)]
+pub struct Deck {
+    pub draw_pile: VecDeque<Card>,
+    pub discard_pile: VecDeque<Card>,
+    /// Cards burned before the deal, out of play until the next one.
+    pub burned: Vec<Card>,
+    /// Times the discards have gone back into the draw pile this round.
+    pub reshuffles: usize,
+}
+
+impl Deck {
+    pub fn new(cards: Vec<Card>) -> Self {
+        Self {
+            draw_pile: cards.into_iter().collect(),
+            discard_pile: VecDeque::new(),
+            burned: Vec::new(),
+            reshuffles: 0,
         }
     }
 
-    (best_score, hand.clone())
-}
+    /// Cuts the draw pile `position` cards from the top, which go to the
+    /// bottom. A cut leaves at least a card either side, so one too near an
+    /// end is moved in. Returns where the cut was made.
+    pub fn cut(&mut self, position: usize) -> usize {
+        let len = self.draw_pile.len();
+        if len < 2 {
+            return 0;
+        }
 
-impl<'a> Deck<'a> {
-    pub fn reshuffle_deck(&mut self) -> Result<(), String> {
-        let mut deck: Vec<Card> = (*self.discard_pile).clone().into();
+        // The top of the pile is its back
+        let position = position.clamp(1, len - 1);
+        self.draw_pile.rotate_right(position);
+        position
+    }
 
-        deck.shuffle(&mut rng());
+    /// Takes the top card out of play, face-down, until the next deal.
+    pub fn burn(&mut self) -> Option<Card> {
+        let card = self.draw_pile.pop_back()?;
+        self.burned.push(card);
+        Some(card)
+    }
+
+    /// The top card of the draw pile, reshuffling the discards under the
+    /// top one into it first if it's empty.
+    pub fn draw<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<Card, DeckEmpty> {
+        if self.draw_pile.is_empty() {
+            self.reshuffle(rng);
+            tracing::info!(
+                draw_pile = self.draw_pile.len(),
+                "reshuffled the discards into the draw pile"
+            );
+        }
+
+        self.draw_pile.pop_back().ok_or(DeckEmpty)
+    }
+
+    /// Whether there's a card to draw, counting the discards a reshuffle
+    /// would bring back.
+    pub fn can_draw(&self) -> bool {
+        !self.draw_pile.is_empty() || self.discard_pile.len() > 1
+    }
+
+    /// Puts `card` face-up on the discard pile.
+    pub fn discard(&mut self, card: Card) {
+        self.discard_pile.push_back(card);
+    }
+
+    /// The card on top of the discard pile, the one a retrieve takes.
+    pub fn peek_discard(&self) -> Option<&Card> {
+        self.discard_pile.back()
+    }
+
+    /// Takes the card on top of the discard pile.
+    pub fn take_discard(&mut self) -> Option<Card> {
+        self.discard_pile.pop_back()
+    }
+
+    pub fn reshuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        // Keep the top card of discard pile
+        let top_card = self.discard_pile.pop_back();
+
+        // Move all other discard cards to draw pile
+        self.draw_pile.extend(self.discard_pile.drain(..));
+
+        // Shuffle the draw pile
+        let mut cards: Vec<Card> = self.draw_pile.drain(..).collect();
+        cards.shuffle(rng);
+        self.draw_pile = cards.into_iter().collect();
+        self.reshuffles += 1;
+
+        // Put the top card back
+        if let Some(card) = top_card {
+            self.discard_pile.push_back(card);
+        }
+    }
+}
 
-        *self.draw_pile = VecDeque::from(deck);
-        *self.discard_pile = VecDeque::new();
+/// There's nothing to draw: the draw pile's empty, and so are the discards
+/// under the top one that would be reshuffled into it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("No cards left to draw")]
+pub struct DeckEmpty;
 
-        Ok(())
+impl From<DeckEmpty> for RummyError {
+    fn from(_: DeckEmpty) -> Self {
+        RummyError::EmptyDeck
     }
 }
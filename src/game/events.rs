@@ -0,0 +1,555 @@
+//! Everything that happens at the table, as a stream of events.
+//!
+//! The engine emits a `GameEvent` for every move into the `EventSink`s
+//! subscribed to it. The action log, the scoreboard and replay recordings are
+//! all sinks, so every front-end tells the same story about a game.
+
+use crate::card::Card;
+use crate::game::{Player, PlayerType};
+use crate::i18n::{Locale, Message};
+use crate::rng::SeedDisclosure;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// How many of the latest actions fit under the table, and are sent to
+/// network players.
+pub const ACTION_LOG_LEN: usize = 6;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// A player drew `card` to decide who goes first.
+    SeatCardDrawn {
+        player: String,
+        card: Card,
+    },
+    /// The dealer cut the deck to decide who goes first.
+    DeckCut {
+        player: String,
+        card: Card,
+    },
+    /// `player` goes first, and `order` is everyone in the order they play.
+    TurnOrderDecided {
+        player: String,
+        order: Vec<String>,
+    },
+    /// A player took a card. `retrieved` is the top discard they picked up,
+    /// or `None` for a blind draw from the draw pile.
+    CardDrawn {
+        player: String,
+        retrieved: Option<Card>,
+    },
+    CardDiscarded {
+        player: String,
+        card: Card,
+    },
+    /// `player` declared out of turn on the discard `card`, which completed
+    /// a winning meld for them, and threw away `discarded` for it. Their
+    /// hand is played straight after.
+    Declared {
+        player: String,
+        card: Card,
+        discarded: Card,
+    },
+    /// A player played their hand, which starts the layoff round.
+    HandPlayed {
+        player: String,
+        hand: Vec<Card>,
+        score: u64,
+    },
+    /// A gin player knocked. `hand` is the cards they melded and `deadwood`
+    /// what the rest add up to. There's no layoff round, the round is
+    /// settled straight away.
+    Knocked {
+        player: String,
+        hand: Vec<Card>,
+        deadwood: u64,
+    },
+    /// A player tried to lay `cards` off onto the played hand. `score` is the
+    /// meld's new score, or `None` if the cards didn't beat it. No cards is a pass.
+    LayoffMade {
+        player: String,
+        cards: Vec<Card>,
+        score: Option<u64>,
+    },
+    RoundWon {
+        player: String,
+        score: u64,
+        hand: Vec<Card>,
+    },
+    /// The dealer, `player`, cut the shuffled deck `position` cards from the
+    /// top before dealing.
+    DeckCutForDeal {
+        player: String,
+        position: usize,
+    },
+    /// The dealer, `player`, burned the top card face-down before dealing.
+    /// It's out of play until the next deal, so like the dealt hands it's
+    /// for audits and replays and never shown at the table.
+    CardBurned {
+        player: String,
+        card: Card,
+    },
+    /// A round was dealt and `player` goes first. `hands` is every hand
+    /// dealt, so it's for audits only and never shown at the table.
+    /// `deal_hash` fingerprints the shuffle, see `rng::deal_hash`.
+    RoundDealt {
+        player: String,
+        hands: Vec<(String, Vec<Card>)>,
+        #[serde(default)]
+        deal_hash: u64,
+    },
+    /// `player` called off the round in play, say after a misdeal. Nobody
+    /// scores for it and every hand is dealt again.
+    RoundRedealt {
+        player: String,
+    },
+    /// `player` discarded as the draw pile ran out after `reshuffles`
+    /// reshuffles, as many as the rules allow, so the round was called and
+    /// every hand shown. The best meld among them, if there is one, wins the
+    /// round for a share of its score, in a `RoundWon` to follow.
+    DeadHand {
+        player: String,
+        reshuffles: usize,
+        hands: Vec<(String, Vec<Card>)>,
+    },
+    /// `player` discarded the last card there was to draw, so the round
+    /// ended with nobody winning it and every hand is dealt again.
+    RoundStalemate {
+        player: String,
+    },
+    /// `player` got `correct` of `asked` questions right in the book quiz
+    /// and starts the match `points` ahead.
+    QuizScored {
+        player: String,
+        correct: usize,
+        asked: usize,
+        points: usize,
+    },
+    /// A house rule changed `player`'s score by `points`.
+    ScoreAdjusted {
+        player: String,
+        points: i64,
+    },
+    /// The AI `player`'s temper drifted with the score, and they play as
+    /// `temperament` from here on. See `TemperamentPolicy`.
+    TemperamentShifted {
+        player: String,
+        temperament: PlayerType,
+    },
+    /// A house rule had something to say about `player`, read after their name.
+    Announcement {
+        player: String,
+        text: String,
+    },
+    /// `score` is the winner's final total.
+    GameWon {
+        player: String,
+        score: usize,
+    },
+    /// Follows `GameWon`, `player` being the winner: the seed the game was
+    /// played from and every deal's hash, to check the deals against.
+    SeedDisclosed {
+        player: String,
+        disclosure: SeedDisclosure,
+    },
+    Dialogue {
+        player: String,
+        text: String,
+    },
+}
+
+impl GameEvent {
+    /// The player who acted.
+    pub fn player(&self) -> &str {
+        match self {
+            GameEvent::SeatCardDrawn { player, .. }
+            | GameEvent::DeckCut { player, .. }
+            | GameEvent::TurnOrderDecided { player, .. }
+            | GameEvent::CardDrawn { player, .. }
+            | GameEvent::CardDiscarded { player, .. }
+            | GameEvent::Declared { player, .. }
+            | GameEvent::HandPlayed { player, .. }
+            | GameEvent::Knocked { player, .. }
+            | GameEvent::LayoffMade { player, .. }
+            | GameEvent::RoundWon { player, .. }
+            | GameEvent::DeckCutForDeal { player, .. }
+            | GameEvent::CardBurned { player, .. }
+            | GameEvent::RoundDealt { player, .. }
+            | GameEvent::RoundRedealt { player }
+            | GameEvent::RoundStalemate { player }
+            | GameEvent::DeadHand { player, .. }
+            | GameEvent::QuizScored { player, .. }
+            | GameEvent::ScoreAdjusted { player, .. }
+            | GameEvent::TemperamentShifted { player, .. }
+            | GameEvent::Announcement { player, .. }
+            | GameEvent::GameWon { player, .. }
+            | GameEvent::SeedDisclosed { player, .. }
+            | GameEvent::Dialogue { player, .. } => player,
+        }
+    }
+}
+
+/// Something that wants to hear about every event in a game.
+pub trait EventSink: Send {
+    fn emit(&mut self, event: &GameEvent);
+}
+
+/// Records every event, e.g. for a replay.
+impl EventSink for Vec<GameEvent> {
+    fn emit(&mut self, event: &GameEvent) {
+        self.push(event.clone());
+    }
+}
+
+impl EventSink for std::sync::mpsc::Sender<GameEvent> {
+    fn emit(&mut self, event: &GameEvent) {
+        // Nobody listening any more is fine
+        let _ = self.send(event.clone());
+    }
+}
+
+#[cfg(feature = "cli")]
+impl EventSink for tokio::sync::mpsc::UnboundedSender<GameEvent> {
+    fn emit(&mut self, event: &GameEvent) {
+        let _ = self.send(event.clone());
+    }
+}
+
+/// A sink shared between the engine and whoever reads it back.
+pub type SharedSink = Arc<Mutex<dyn EventSink>>;
+
+/// The sinks subscribed to an engine.
+#[derive(Clone, Default)]
+pub struct EventSinks(Vec<SharedSink>);
+
+impl EventSinks {
+    pub fn subscribe(&mut self, sink: SharedSink) {
+        self.0.push(sink);
+    }
+
+    pub fn emit(&self, event: &GameEvent) {
+        for sink in &self.0 {
+            if let Ok(mut sink) = sink.lock() {
+                sink.emit(event);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for EventSinks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EventSinks({} subscribed)", self.0.len())
+    }
+}
+
+/// One line of the action log: who did what, and the cards it involved.
+/// What they did is kept as a `Message`, and put in words by whoever shows it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Action {
+    pub player: String,
+    pub message: Message,
+    /// Shown after the message.
+    pub cards: Vec<Card>,
+}
+
+impl Action {
+    /// What they did, in the installed locale.
+    pub fn text(&self) -> String {
+        self.message.to_string()
+    }
+
+    pub fn text_in(&self, locale: Locale) -> String {
+        self.message.text(locale)
+    }
+}
+
+/// Every action at the table, oldest first, kept for the whole game so it
+/// can be scrolled back through and written out at the end.
+///
+/// A draw and the discard that follows it are folded into one line, the way
+/// players talk about a turn.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionLog {
+    actions: Vec<Action>,
+    /// The draw waiting for its discard, so the two can be folded together.
+    pending_draw: Option<GameEvent>,
+}
+
+impl ActionLog {
+    pub fn actions(&self) -> Vec<Action> {
+        self.actions.clone()
+    }
+
+    /// The latest `len` actions, oldest first.
+    pub fn recent(&self, len: usize) -> Vec<Action> {
+        self.actions[self.actions.len().saturating_sub(len)..].to_vec()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Logs something that isn't a move, like a player leaving the table.
+    pub fn note(&mut self, player: &str, message: Message) {
+        self.push(player, message, Vec::new());
+    }
+
+    fn push(&mut self, player: &str, message: Message, cards: Vec<Card>) {
+        self.actions.push(Action {
+            player: player.to_string(),
+            message,
+            cards,
+        });
+    }
+}
+
+impl EventSink for ActionLog {
+    fn emit(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::SeatCardDrawn { player, card } => {
+                self.push(player, Message::DrewForSeat, vec![*card])
+            }
+            GameEvent::DeckCut { player, card } => {
+                self.push(player, Message::CutForSeat, vec![*card])
+            }
+            GameEvent::TurnOrderDecided { player, .. } => self.note(player, Message::GoesFirst),
+            GameEvent::CardDrawn { .. } => self.pending_draw = Some(event.clone()),
+            GameEvent::CardDiscarded { player, card } => {
+                let message = match self.pending_draw.take() {
+                    Some(GameEvent::CardDrawn {
+                        retrieved: Some(_), ..
+                    }) => Message::RetrievedAndDiscarded,
+                    Some(_) => Message::DrewAndDiscarded,
+                    None => Message::Discarded,
+                };
+                self.push(player, message, vec![*card]);
+            }
+            GameEvent::Declared {
+                player,
+                card,
+                discarded,
+            } => self.push(player, Message::Declared { card: *card }, vec![*discarded]),
+            GameEvent::HandPlayed {
+                player,
+                hand,
+                score,
+            } => self.push(player, Message::HandPlayed { score: *score }, hand.clone()),
+            GameEvent::Knocked {
+                player,
+                hand,
+                deadwood,
+            } => self.push(
+                player,
+                Message::Knocked {
+                    deadwood: *deadwood,
+                },
+                hand.clone(),
+            ),
+            GameEvent::LayoffMade {
+                player,
+                cards,
+                score: Some(score),
+            } => self.note(
+                player,
+                Message::LaidOff {
+                    cards: cards.len(),
+                    score: *score,
+                },
+            ),
+            GameEvent::LayoffMade {
+                player,
+                cards,
+                score: None,
+            } if !cards.is_empty() => self.note(player, Message::CouldNotLayOff),
+            GameEvent::RoundWon {
+                player,
+                score,
+                hand,
+            } => self.push(player, Message::RoundWon { score: *score }, hand.clone()),
+            GameEvent::RoundRedealt { player } => self.note(player, Message::RoundRedealt),
+            GameEvent::DeadHand {
+                player, reshuffles, ..
+            } => self.note(
+                player,
+                Message::DeadHand {
+                    reshuffles: *reshuffles,
+                },
+            ),
+            GameEvent::RoundStalemate { player } => self.note(player, Message::RoundStalemate),
+            GameEvent::DeckCutForDeal { player, position } => self.note(
+                player,
+                Message::DeckCutForDeal {
+                    position: *position,
+                },
+            ),
+            GameEvent::CardBurned { player, .. } => self.note(player, Message::CardBurned),
+            GameEvent::QuizScored {
+                player,
+                correct,
+                asked,
+                points,
+            } => self.note(
+                player,
+                Message::QuizScored {
+                    correct: *correct,
+                    asked: *asked,
+                    points: *points,
+                },
+            ),
+            GameEvent::ScoreAdjusted { player, points } => {
+                self.note(player, Message::ScoreAdjusted { points: *points })
+            }
+            GameEvent::TemperamentShifted {
+                player,
+                temperament,
+            } => self.note(
+                player,
+                Message::TemperamentShifted {
+                    temperament: temperament.clone(),
+                },
+            ),
+            GameEvent::Announcement { player, text } => {
+                self.note(player, Message::Said { text: text.clone() })
+            }
+            GameEvent::GameWon { player, score } => {
+                self.note(player, Message::GameWon { score: *score })
+            }
+            GameEvent::LayoffMade { .. }
+            | GameEvent::Dialogue { .. }
+            | GameEvent::RoundDealt { .. }
+            | GameEvent::SeedDisclosed { .. } => {}
+        }
+    }
+}
+
+/// Running totals, kept from the `RoundWon` events.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scoreboard {
+    scores: Vec<(String, usize)>,
+}
+
+impl Scoreboard {
+    /// Everyone starts on zero, listed in seating order.
+    pub fn new<'a>(players: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            scores: players
+                .into_iter()
+                .map(|name| (name.to_string(), 0))
+                .collect(),
+        }
+    }
+
+    /// Picks up from the players' current scores, e.g. in a restored game.
+    pub fn from_players(players: &[Player]) -> Self {
+        Self {
+            scores: players.iter().map(|p| (p.name.clone(), p.score)).collect(),
+        }
+    }
+
+    pub fn scores(&self) -> &[(String, usize)] {
+        &self.scores
+    }
+}
+
+impl EventSink for Scoreboard {
+    fn emit(&mut self, event: &GameEvent) {
+        let (player, points) = match event {
+            GameEvent::RoundWon { player, score, .. } => (player, *score as i64),
+            GameEvent::ScoreAdjusted { player, points } => (player, *points),
+            GameEvent::QuizScored { player, points, .. } => (player, *points as i64),
+            _ => return,
+        };
+
+        match self.scores.iter_mut().find(|(name, _)| name == player) {
+            Some((_, total)) => *total = total.saturating_add_signed(points as isize),
+            None => self.scores.push((player.clone(), points.max(0) as usize)),
+        }
+    }
+}
+
+/// What the last player to move did, for a recap of their turn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Recap {
+    /// `retrieved` is the discard they took, `None` for a blind draw.
+    Discarded {
+        player: String,
+        retrieved: Option<Card>,
+        card: Card,
+    },
+    Played {
+        player: String,
+        score: u64,
+    },
+    Knocked {
+        player: String,
+        deadwood: u64,
+    },
+}
+
+impl Recap {
+    pub fn player(&self) -> &str {
+        match self {
+            Recap::Discarded { player, .. }
+            | Recap::Played { player, .. }
+            | Recap::Knocked { player, .. } => player,
+        }
+    }
+}
+
+/// Keeps the last turn taken, so a player coming back to the table can see
+/// at a glance what just happened.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TurnRecap {
+    last: Option<Recap>,
+    /// The discard taken this turn, waiting for the card thrown away.
+    retrieved: Option<(String, Option<Card>)>,
+}
+
+impl TurnRecap {
+    pub fn last(&self) -> Option<&Recap> {
+        self.last.as_ref()
+    }
+}
+
+impl EventSink for TurnRecap {
+    fn emit(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::CardDrawn { player, retrieved } => {
+                self.retrieved = Some((player.clone(), *retrieved));
+            }
+            GameEvent::CardDiscarded { player, card } => {
+                let retrieved = match self.retrieved.take() {
+                    Some((drew, retrieved)) if drew == *player => retrieved,
+                    _ => None,
+                };
+                self.last = Some(Recap::Discarded {
+                    player: player.clone(),
+                    retrieved,
+                    card: *card,
+                });
+            }
+            GameEvent::HandPlayed { player, score, .. } => {
+                self.last = Some(Recap::Played {
+                    player: player.clone(),
+                    score: *score,
+                });
+            }
+            GameEvent::Knocked {
+                player, deadwood, ..
+            } => {
+                self.last = Some(Recap::Knocked {
+                    player: player.clone(),
+                    deadwood: *deadwood,
+                });
+            }
+            GameEvent::RoundDealt { .. } => {
+                self.last = None;
+                self.retrieved = None;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Checking a layoff before it's made.
+//!
+//! Nothing here touches a hand or the table, so a front-end can tell a
+//! player their layoff won't take the meld while they can still change it.
+
+use crate::card::Card;
+use crate::error::RummyError;
+use crate::game::Hand;
+use crate::game::rules::{GameRules, Variant};
+use thiserror::Error;
+
+/// What a layoff would make of the meld.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoffOutcome {
+    pub cards: Vec<Card>,
+    /// The meld with the cards laid off onto it.
+    pub hand: Hand,
+    pub score: u64,
+}
+
+/// Why a layoff wouldn't take the meld.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum LayoffError {
+    /// No cards were chosen. Laying off nothing is passing.
+    #[error("Choose at least one card to lay off")]
+    NoCards,
+
+    /// More cards than the table lets anyone lay off at once.
+    #[error("You can lay off at most {max} cards at once")]
+    TooManyCards { max: usize },
+
+    /// More cards than the meld has for them to replace.
+    #[error("The meld only has {len} cards to replace")]
+    LongerThanMeld { len: usize },
+
+    /// The best the cards make of the meld scores `best`, which doesn't beat
+    /// the `score_to_beat` on the table.
+    #[error("That layoff doesn't beat {score_to_beat} points")]
+    DoesNotBeat { best: u64, score_to_beat: u64 },
+}
+
+impl From<LayoffError> for RummyError {
+    fn from(err: LayoffError) -> Self {
+        RummyError::InvalidMove(err.to_string())
+    }
+}
+
+/// The best meld laying `cards` off onto `winner_hand` makes, if it beats
+/// `score_to_beat`, or ties it and wins the `rules`' tiebreak, and `rules`
+/// allow it. See `Variant::lay_off` for how the
+/// cards take their places.
+pub fn validate_layoff(
+    winner_hand: &Hand,
+    cards: &[Card],
+    score_to_beat: u64,
+    variant: Variant,
+    rules: GameRules,
+) -> Result<LayoffOutcome, LayoffError> {
+    if cards.is_empty() {
+        return Err(LayoffError::NoCards);
+    }
+    if cards.len() > rules.max_layoff {
+        return Err(LayoffError::TooManyCards {
+            max: rules.max_layoff,
+        });
+    }
+
+    let Some((score, hand)) = variant.lay_off(winner_hand, cards) else {
+        return Err(LayoffError::LongerThanMeld {
+            len: winner_hand.cards.len(),
+        });
+    };
+    if !rules.outranks(&hand.cards, score, &winner_hand.cards, score_to_beat) {
+        return Err(LayoffError::DoesNotBeat {
+            best: score,
+            score_to_beat,
+        });
+    }
+
+    Ok(LayoffOutcome {
+        cards: cards.to_vec(),
+        hand,
+        score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::Tiebreak;
+
+    fn hand(cards: &str) -> Hand {
+        Hand {
+            cards: cards.split(' ').map(|card| card.parse().unwrap()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_layoffs_are_checked_before_they_are_made() {
+        let meld = hand("Kh Kd Kc 2h 2s");
+        let check = |cards: &str, rules| {
+            validate_layoff(&meld, &hand(cards).cards, 40, Variant::Bookclub, rules)
+        };
+
+        let outcome = check("Ks", GameRules::default()).unwrap();
+        assert_eq!(outcome.hand, hand("Kh Kd Kc Ks 2s"));
+        assert_eq!(outcome.score, 50);
+
+        // A full house again only ties
+        assert_eq!(
+            check("2d", GameRules::default()),
+            Err(LayoffError::DoesNotBeat {
+                best: 40,
+                score_to_beat: 40
+            })
+        );
+
+        assert_eq!(
+            check("Qh Jh 10h Ah", GameRules::default()),
+            Err(LayoffError::TooManyCards { max: 3 })
+        );
+        assert_eq!(
+            check(
+                "Qh Jh 10h Ah 9h 8h",
+                GameRules {
+                    max_layoff: 6,
+                    ..GameRules::default()
+                }
+            ),
+            Err(LayoffError::LongerThanMeld { len: 5 })
+        );
+        assert_eq!(
+            validate_layoff(&meld, &[], 40, Variant::Bookclub, GameRules::default()),
+            Err(LayoffError::NoCards)
+        );
+    }
+
+    #[test]
+    fn test_a_tie_goes_by_the_tiebreak() {
+        // A king in place of the nine makes the same pair with higher cards
+        let meld = hand("5s 5h 9c Jd 2h");
+        let check = |tiebreak| {
+            let rules = GameRules {
+                tiebreak,
+                ..GameRules::default()
+            };
+            validate_layoff(&meld, &hand("Kd").cards, 2, Variant::Bookclub, rules)
+        };
+
+        let outcome = check(Tiebreak::HighCard).unwrap();
+        assert_eq!(outcome.hand, hand("5s 5h Kd Jd 2h"));
+        assert_eq!(outcome.score, 2);
+        for tiebreak in [Tiebreak::MeldRank, Tiebreak::Earliest] {
+            assert_eq!(
+                check(tiebreak),
+                Err(LayoffError::DoesNotBeat {
+                    best: 2,
+                    score_to_beat: 2
+                })
+            );
+        }
+    }
+}
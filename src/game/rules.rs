@@ -0,0 +1,574 @@
+//! The games the engine can host.
+//!
+//! Everything that differs between them, how many cards are dealt, which
+//! melds count, whether there's a layoff round and how a round is won, is
+//! asked of the `Variant` rather than built into the engine.
+
+use crate::card::{Card, Name, ToU64};
+use crate::error::RummyError;
+use crate::game::{Hand, TARGET_SCORE};
+use crate::scoring::{
+    CardVec, MELD_FUNCTIONS, MELD_NAMES, MELD_SIZE, MeldScoringClosure, combinations,
+    compare_hands, compare_meld_rank, deadwood,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// The most deadwood a gin player can knock with.
+pub const KNOCK_LIMIT: u64 = 10;
+
+/// Bonus for knocking with no deadwood at all.
+pub const GIN_BONUS: u64 = 25;
+
+/// Bonus for a defender who matches or beats the knocker's deadwood.
+pub const UNDERCUT_BONUS: u64 = 25;
+
+/// Most cards a player can lay off in one go, unless the table says
+/// otherwise.
+pub const MAX_LAYOFF: usize = 3;
+
+/// Meld score a discard has to complete for someone to declare on it out of
+/// turn: a Full House or better.
+pub const DECLARE_SCORE: u64 = 40;
+
+/// Share of the best meld's score a dead hand is won with, in percent.
+pub const DEAD_HAND_PERCENT: u64 = 50;
+
+/// Which game the table is playing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Variant {
+    /// Five cards each. Play your hand as a poker-style meld and everyone
+    /// else gets a chance to lay off onto it and steal the round.
+    #[default]
+    Bookclub,
+    /// Seven cards each, and the best five are played. Pairs don't count,
+    /// with seven cards nearly every hand holds one.
+    SevenCard,
+    /// Ten cards each, melded into sets and runs. Knock once your unmelded
+    /// cards add up to 10 or less, and the lowest deadwood takes the round.
+    Gin,
+}
+
+impl Variant {
+    /// Cards dealt to each player and held between turns.
+    pub fn hand_size(self) -> usize {
+        match self {
+            Variant::Bookclub => 5,
+            Variant::SevenCard => 7,
+            Variant::Gin => 10,
+        }
+    }
+
+    /// Score that wins the game. Seven card hands meld big more often, so
+    /// they play to a higher target.
+    pub fn target_score(self) -> usize {
+        match self {
+            Variant::Bookclub | Variant::Gin => TARGET_SCORE,
+            Variant::SevenCard => TARGET_SCORE * 3 / 2,
+        }
+    }
+
+    /// The melds a played hand is scored with. Gin has none, its rounds are
+    /// settled by deadwood.
+    pub fn melds(self) -> &'static [MeldScoringClosure] {
+        match self {
+            Variant::Bookclub => MELD_FUNCTIONS,
+            // Everything from Sequence of Three up
+            Variant::SevenCard => &MELD_FUNCTIONS[2..],
+            Variant::Gin => &[],
+        }
+    }
+
+    /// The scores and names of the melds `melds` scores, lowest first.
+    pub fn meld_names(self) -> &'static [(u64, &'static str)] {
+        match self {
+            Variant::Bookclub => MELD_NAMES,
+            Variant::SevenCard => &MELD_NAMES[2..],
+            Variant::Gin => &[],
+        }
+    }
+
+    /// Whether the other players get a layoff round after a hand is played.
+    pub fn allows_layoffs(self) -> bool {
+        !self.melds().is_empty()
+    }
+
+    /// Score of the meld `hand` makes, by this variant's melds.
+    pub fn score_meld(self, hand: &Hand) -> u64 {
+        let cards: CardVec = hand.cards.iter().copied().collect();
+        self.melds()
+            .iter()
+            .filter_map(|meld_fn| meld_fn(cards.clone()).ok())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The best meld among `hand`'s cards and its score. Hands bigger than a
+    /// meld try every 5 card combination.
+    pub fn best_meld(self, hand: &Hand) -> (u64, Hand) {
+        if hand.cards.len() <= MELD_SIZE {
+            return (self.score_meld(hand), hand.clone());
+        }
+
+        let mut best = (0, hand.clone());
+        for meld in combinations(&hand.cards, MELD_SIZE) {
+            let meld = Hand { cards: meld };
+            let score = self.score_meld(&meld);
+            if score > best.0 {
+                best = (score, meld);
+            }
+        }
+
+        best
+    }
+
+    /// The best meld laying `cards` off onto `meld` makes, and its score.
+    /// Each card takes the place of one of the meld's, and every one of them
+    /// has to go somewhere. `None` if there are more cards than the meld has.
+    pub fn lay_off(self, meld: &Hand, cards: &[Card]) -> Option<(u64, Hand)> {
+        if cards.is_empty() || cards.len() > meld.cards.len() {
+            return None;
+        }
+
+        // By position, so replacing one of a pair of duplicate cards keeps the other
+        let positions: Vec<usize> = (0..meld.cards.len()).collect();
+        let mut best: Option<(u64, Hand)> = None;
+        for replaced in combinations(&positions, cards.len()) {
+            let mut laid_off = meld.clone();
+            for (&idx, &card) in replaced.iter().zip(cards) {
+                laid_off.cards[idx] = card;
+            }
+            let score = self.score_meld(&laid_off);
+            if best.as_ref().is_none_or(|(best, _)| score > *best) {
+                best = Some((score, laid_off));
+            }
+        }
+
+        best
+    }
+
+    /// The hand a player holding `hand` would declare with on the discard
+    /// `card`, and the card they'd throw away for it. `None` if the card
+    /// doesn't complete a winning meld for them: one scoring at least
+    /// `DECLARE_SCORE` that needs the card, or gin.
+    pub fn declaration(self, hand: &Hand, card: Card) -> Option<(Hand, Card)> {
+        let mut cards = hand.cards.clone();
+        cards.push(card);
+
+        // Never the card being declared on, which went in last
+        let mut kept_hands = (0..hand.cards.len()).map(|idx| {
+            let mut kept = cards.clone();
+            let thrown = kept.remove(idx);
+            (Hand { cards: kept }, thrown)
+        });
+
+        match self {
+            Variant::Gin => kept_hands.find(|(kept, _)| arrange_gin(&kept.cards).1.is_empty()),
+            Variant::Bookclub | Variant::SevenCard => {
+                // Beating what's already held means the meld needs the card
+                let holding = self.best_meld(hand).0;
+                kept_hands
+                    .map(|declared| (self.best_meld(&declared.0).0, declared))
+                    .filter(|(score, _)| *score >= DECLARE_SCORE && *score > holding)
+                    .max_by_key(|(score, _)| *score)
+                    .map(|(_, declared)| declared)
+            }
+        }
+    }
+
+    /// Why `hand` can't be played right now, if it can't.
+    pub fn check_play(self, hand: &Hand) -> Result<(), RummyError> {
+        match self {
+            Variant::Gin => {
+                let points = deadwood(&hand.cards);
+                if points > KNOCK_LIMIT {
+                    return Err(RummyError::InvalidMove(format!(
+                        "You can only knock with {KNOCK_LIMIT} or less deadwood, you have {points}"
+                    )));
+                }
+                Ok(())
+            }
+            Variant::Bookclub | Variant::SevenCard => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Variant {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<Variant, RummyError> {
+        match input.trim().to_lowercase().as_str() {
+            "bookclub" | "five-card" => Ok(Variant::Bookclub),
+            "seven-card" => Ok(Variant::SevenCard),
+            "gin" => Ok(Variant::Gin),
+            other => Err(RummyError::Config(format!(
+                "Unknown variant {other:?}, expected bookclub, seven-card or gin"
+            ))),
+        }
+    }
+}
+
+/// How a layoff is settled when its meld scores the same as the one it's
+/// laid off onto.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tiebreak {
+    /// The meld on the table holds, a layoff has to score more.
+    Earliest,
+    /// The higher ranked meld takes it, a pair of kings over a pair of
+    /// twos. The meld on the table holds if they rank the same.
+    MeldRank,
+    /// The higher ranked meld, then the higher cards, as `compare_hands`.
+    /// The meld on the table holds if they're the same on both.
+    #[default]
+    HighCard,
+}
+
+impl Tiebreak {
+    /// How `a` compares to `b`, two melds scoring the same.
+    pub fn compare(self, a: &[Card], b: &[Card]) -> Ordering {
+        match self {
+            Tiebreak::Earliest => Ordering::Equal,
+            Tiebreak::MeldRank => compare_meld_rank(a, b),
+            Tiebreak::HighCard => compare_hands(a, b),
+        }
+    }
+}
+
+impl FromStr for Tiebreak {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<Tiebreak, RummyError> {
+        match input.trim().to_lowercase().as_str() {
+            "earliest" => Ok(Tiebreak::Earliest),
+            "meld-rank" => Ok(Tiebreak::MeldRank),
+            "high-card" => Ok(Tiebreak::HighCard),
+            other => Err(RummyError::Config(format!(
+                "Unknown tiebreak {other:?}, expected earliest, meld-rank or high-card"
+            ))),
+        }
+    }
+}
+
+/// Table rules that hold whichever variant is played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameRules {
+    /// Most cards a player can lay off onto a played meld at once.
+    pub max_layoff: usize,
+    /// Times the discards can be reshuffled into the draw pile in a round
+    /// before it's called as a dead hand, `None` for as often as it takes.
+    pub dead_hand_after: Option<usize>,
+    /// Percent of the best meld's score a dead hand is won with.
+    pub dead_hand_percent: u64,
+    /// How a layoff scoring the same as the meld it's laid off onto is
+    /// settled.
+    pub tiebreak: Tiebreak,
+    /// Whether the table plays in partnerships, everyone with whoever sits
+    /// across from them, their scores pooled. See `GameEngine::partner`.
+    pub teams: bool,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            max_layoff: MAX_LAYOFF,
+            dead_hand_after: None,
+            dead_hand_percent: DEAD_HAND_PERCENT,
+            tiebreak: Tiebreak::default(),
+            teams: false,
+        }
+    }
+}
+
+impl GameRules {
+    /// Whether `hand`, a meld scoring `score`, takes the round from `held`
+    /// scoring `held_score`: it scores more, or the same and wins the
+    /// tiebreak.
+    pub fn outranks(&self, hand: &[Card], score: u64, held: &[Card], held_score: u64) -> bool {
+        score
+            .cmp(&held_score)
+            .then_with(|| self.tiebreak.compare(hand, held))
+            .is_gt()
+    }
+
+    /// The best layoff a player holding `hand` can make onto `meld`: the
+    /// cards laid off, the meld they make and its score.
+    ///
+    /// A layoff scores whatever meld it makes, however many cards went into
+    /// it, and only counts if that outranks the meld, scoring
+    /// `score_to_beat`. Between layoffs scoring the same, the one giving up
+    /// the fewest cards is made.
+    pub fn best_layoff(
+        &self,
+        variant: Variant,
+        hand: &[Card],
+        meld: &Hand,
+        score_to_beat: u64,
+    ) -> Option<(Vec<Card>, Hand, u64)> {
+        let most = self.max_layoff.min(hand.len()).min(meld.cards.len());
+
+        let mut best: Option<(Vec<Card>, Hand, u64)> = None;
+        for k in 1..=most {
+            for cards in combinations(hand, k) {
+                let Some((score, laid_off)) = variant.lay_off(meld, &cards) else {
+                    continue;
+                };
+                if !self.outranks(&laid_off.cards, score, &meld.cards, score_to_beat) {
+                    continue;
+                }
+                if best.as_ref().is_none_or(|(.., best)| score > *best) {
+                    best = Some((cards, laid_off, score));
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// How a knock came out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KnockResult {
+    /// The knocker wins `points`.
+    Knocker { points: u64 },
+    /// The defender at `defender` matched or beat the knocker's deadwood.
+    Undercut { defender: usize, points: u64 },
+}
+
+/// Settles a knock with `knocker` deadwood against each defender's.
+///
+/// The knocker scores the difference to every defender, plus the gin bonus
+/// if they had no deadwood. A defender with as little deadwood as the
+/// knocker undercuts them and scores the difference plus the undercut bonus
+/// instead. Nobody can undercut gin.
+pub fn score_knock(knocker: u64, defenders: &[u64]) -> KnockResult {
+    let undercut = defenders
+        .iter()
+        .enumerate()
+        .filter(|(_, deadwood)| knocker > 0 && **deadwood <= knocker)
+        .min_by_key(|(_, deadwood)| **deadwood);
+
+    match undercut {
+        Some((defender, deadwood)) => KnockResult::Undercut {
+            defender,
+            points: knocker - deadwood + UNDERCUT_BONUS,
+        },
+        None => {
+            let points = defenders
+                .iter()
+                .map(|deadwood| deadwood - knocker)
+                .sum::<u64>();
+            let bonus = if knocker == 0 { GIN_BONUS } else { 0 };
+            KnockResult::Knocker {
+                points: points + bonus,
+            }
+        }
+    }
+}
+
+/// What a card counts for as deadwood: aces 1, court cards 10, the rest
+/// their number.
+pub fn deadwood_value(card: &Card) -> u64 {
+    match card.name {
+        Name::Ace => 1,
+        Name::Jack | Name::Queen | Name::King => 10,
+        name => name.to_u64().unwrap_or(10),
+    }
+}
+
+pub fn deadwood_points(cards: &[Card]) -> u64 {
+    cards.iter().map(deadwood_value).sum()
+}
+
+/// Splits `cards` into the cards that meld and the deadwood left over, in
+/// whichever arrangement leaves the least deadwood. Melds are sets of 3 or 4
+/// of a name and runs of 3 or more in a suite, aces low.
+pub fn arrange_gin(cards: &[Card]) -> (Vec<Card>, Vec<Card>) {
+    let melds = gin_melds(cards);
+    let all = (1u32 << cards.len()) - 1;
+    let melded = least_deadwood(cards, &melds, all).1;
+
+    let (melded, deadwood): (Vec<usize>, Vec<usize>) =
+        (0..cards.len()).partition(|idx| melded & (1 << idx) != 0);
+
+    (
+        melded.into_iter().map(|idx| cards[idx]).collect(),
+        deadwood.into_iter().map(|idx| cards[idx]).collect(),
+    )
+}
+
+/// The least deadwood the cards in `left` can be left with, and which of them
+/// melded to get it. Each step settles the lowest card left: it's either
+/// deadwood or in one of the melds that fit.
+fn least_deadwood(cards: &[Card], melds: &[u32], left: u32) -> (u64, u32) {
+    if left == 0 {
+        return (0, 0);
+    }
+
+    let first = left.trailing_zeros();
+    let (rest, melded) = least_deadwood(cards, melds, left & !(1 << first));
+    let mut best = (rest + deadwood_value(&cards[first as usize]), melded);
+
+    for &meld in melds {
+        if meld & (1 << first) != 0 && meld & left == meld {
+            let (deadwood, melded) = least_deadwood(cards, melds, left & !meld);
+            if deadwood < best.0 {
+                best = (deadwood, melded | meld);
+            }
+        }
+    }
+
+    best
+}
+
+/// Every set and run among `cards`, as bitmasks of their indices.
+fn gin_melds(cards: &[Card]) -> Vec<u32> {
+    let mut melds = Vec::new();
+
+    for name in Name::ALL {
+        let same: Vec<usize> = (0..cards.len())
+            .filter(|&i| cards[i].name == name)
+            .collect();
+        if same.len() >= 3 {
+            for set in combinations(&same, 3) {
+                melds.push(mask(&set));
+            }
+        }
+        // More than 4 when dealing from more than one deck
+        if same.len() >= 4 {
+            for set in combinations(&same, 4) {
+                melds.push(mask(&set));
+            }
+        }
+    }
+
+    for (i, card) in cards.iter().enumerate() {
+        // Runs are found from their lowest card up
+        let mut run = vec![i];
+        let mut next = gin_rank(card) + 1;
+        while let Some(j) =
+            (0..cards.len()).find(|&j| cards[j].suite == card.suite && gin_rank(&cards[j]) == next)
+        {
+            run.push(j);
+            next += 1;
+            if run.len() >= 3 {
+                melds.push(mask(&run));
+            }
+        }
+    }
+
+    melds
+}
+
+/// Aces are low in gin runs.
+fn gin_rank(card: &Card) -> u64 {
+    match card.name {
+        Name::Ace => 1,
+        name => name.to_u64().unwrap_or_default(),
+    }
+}
+
+fn mask(indices: &[usize]) -> u32 {
+    indices.iter().fold(0, |mask, idx| mask | 1 << idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(cards: &str) -> Vec<Card> {
+        cards.split(' ').map(|card| card.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_variants_parse_and_deal() {
+        assert_eq!("gin".parse::<Variant>().unwrap(), Variant::Gin);
+        assert_eq!(
+            " Seven-Card ".parse::<Variant>().unwrap(),
+            Variant::SevenCard
+        );
+        assert!("canasta".parse::<Variant>().is_err());
+        assert_eq!(Variant::default().hand_size(), 5);
+        assert!(!Variant::Gin.allows_layoffs());
+
+        // Seven card rummy plays the best five and doesn't count pairs
+        let hand = Hand {
+            cards: cards("2h 9s 3h Kd 4h 5h Jc"),
+        };
+        let (score, meld) = Variant::SevenCard.best_meld(&hand);
+        assert_eq!(meld.cards.len(), 5);
+        assert_eq!(score, 30);
+        let pair = Hand {
+            cards: cards("2h 9s Kd 5h Kc"),
+        };
+        assert_eq!(Variant::Bookclub.best_meld(&pair).0, 2);
+        assert_eq!(Variant::SevenCard.best_meld(&pair).0, 0);
+
+        // A third king fills the house, the pair of twos alone doesn't count
+        let pair_of_each = Hand {
+            cards: cards("2h 2s Kd Kc 9h"),
+        };
+        let declared = Variant::Bookclub.declaration(&pair_of_each, "Ks".parse().unwrap());
+        assert_eq!(
+            declared.map(|(hand, thrown)| (hand.cards, thrown)),
+            Some((cards("2h 2s Kd Kc Ks"), "9h".parse().unwrap()))
+        );
+        assert_eq!(
+            Variant::Bookclub.declaration(&pair_of_each, "9s".parse().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gin_counts_the_least_deadwood() {
+        // The 7 of hearts fits the run or the set, the run leaves less over
+        let (melded, deadwood) = arrange_gin(&cards("7h 8h 9h 7s 7d Kc Ah 2c 3c 9d"));
+        assert_eq!(melded, cards("7h 8h 9h"));
+        assert_eq!(deadwood_points(&deadwood), 39);
+
+        let (melded, deadwood) = arrange_gin(&cards("Ah 2h 3h Qs Ks As 9c 9d 9h 9s"));
+        assert_eq!(melded.len(), 7);
+        assert_eq!(deadwood_points(&deadwood), 21);
+
+        // Two decks can deal five of a name, only four of them make a set
+        let (melded, deadwood) = arrange_gin(&cards("9c 9d 9h 9s 9h Ah 2c 5d"));
+        assert_eq!(melded.len(), 4);
+        assert_eq!(deadwood_points(&deadwood), 17);
+
+        let hand = Hand {
+            cards: cards("Ah 2h 3h 4h 8c 8d 8s Js Qs Ks"),
+        };
+        assert!(Variant::Gin.check_play(&hand).is_ok());
+        let hand = Hand {
+            cards: cards("Ah 2h 3h 4h 8c 8d 10d Js Qs Ks"),
+        };
+        assert!(Variant::Gin.check_play(&hand).is_err());
+
+        // The 4 of hearts goes gin, the 9 of clubs makes nothing
+        let hand = Hand {
+            cards: cards("Ah 2h 3h 8c 8d 8s Js Qs Ks 5d"),
+        };
+        let (declared, thrown) = Variant::Gin
+            .declaration(&hand, "4h".parse().unwrap())
+            .unwrap();
+        assert_eq!(declared.cards, cards("Ah 2h 3h 8c 8d 8s Js Qs Ks 4h"));
+        assert_eq!(thrown, "5d".parse().unwrap());
+        assert_eq!(Variant::Gin.declaration(&hand, "9c".parse().unwrap()), None);
+
+        assert_eq!(
+            score_knock(4, &[20, 9]),
+            KnockResult::Knocker { points: 21 }
+        );
+        assert_eq!(score_knock(0, &[0, 9]), KnockResult::Knocker { points: 34 });
+        assert_eq!(
+            score_knock(6, &[20, 5]),
+            KnockResult::Undercut {
+                defender: 1,
+                points: 26
+            }
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! Everyone's score, round by round.
+
+use crate::game::Player;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What every round of a game added to each player's score: a row a round
+/// and a column a player, in seating order. Quiz head starts come before the
+/// first round, and points a house rule awards count in the round they're
+/// awarded in.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ScoreSheet {
+    players: Vec<String>,
+    /// Everyone's score before the first round.
+    starts: Vec<usize>,
+    rounds: Vec<Vec<i64>>,
+}
+
+impl ScoreSheet {
+    /// A sheet starting from `players`' scores as they stand.
+    pub fn new(players: &[Player]) -> Self {
+        Self {
+            players: players.iter().map(|p| p.name.clone()).collect(),
+            starts: players.iter().map(|p| p.score).collect(),
+            rounds: Vec::new(),
+        }
+    }
+
+    /// Whether the sheet was ever started. A started sheet with no rounds
+    /// on it yet isn't empty.
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    pub fn players(&self) -> &[String] {
+        &self.players
+    }
+
+    pub fn starts(&self) -> &[usize] {
+        &self.starts
+    }
+
+    /// Each finished round, what it added to each player's score.
+    pub fn rounds(&self) -> &[Vec<i64>] {
+        &self.rounds
+    }
+
+    /// Everyone's score after the last round.
+    pub fn totals(&self) -> Vec<i64> {
+        self.starts
+            .iter()
+            .enumerate()
+            .map(|(seat, start)| {
+                *start as i64 + self.rounds.iter().map(|round| round[seat]).sum::<i64>()
+            })
+            .collect()
+    }
+
+    /// Ends a round with `players`' scores as they stand, each credited
+    /// with what they've gained since the last one.
+    pub fn end_round(&mut self, players: &[Player]) {
+        let round = self
+            .players
+            .iter()
+            .zip(self.totals())
+            .map(|(name, total)| {
+                players
+                    .iter()
+                    .find(|p| p.name == *name)
+                    .map_or(0, |p| p.score as i64 - total)
+            })
+            .collect();
+        self.rounds.push(round);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Hand;
+    use std::collections::VecDeque;
+
+    fn player(name: &str, score: usize) -> Player {
+        Player {
+            name: name.to_string(),
+            description: String::new(),
+            player_type: None,
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score,
+        }
+    }
+
+    #[test]
+    fn test_rounds_are_what_each_added() {
+        // Bram won a head start in the quiz
+        let mut players = vec![player("Ada", 0), player("Bram", 3)];
+        let mut sheet = ScoreSheet::new(&players);
+        assert!(!sheet.is_empty());
+        assert!(sheet.rounds().is_empty());
+
+        players[0].score = 40;
+        sheet.end_round(&players);
+        // A stalemate scores nobody anything
+        sheet.end_round(&players);
+        players[1].score = 28;
+        sheet.end_round(&players);
+
+        assert_eq!(sheet.rounds(), [vec![40, 0], vec![0, 0], vec![0, 25]]);
+        assert_eq!(sheet.starts(), [0, 3]);
+        assert_eq!(sheet.totals(), [40, 28]);
+    }
+}
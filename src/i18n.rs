@@ -0,0 +1,1360 @@
+//! Everything the table says to the players, in the language they picked.
+//!
+//! What's said is looked up by key, a `Message` for the action log or a
+//! `Prompt` for the screen, with whatever goes into the words, and the
+//! `Locale` puts it into words. The action log keeps its messages as keys,
+//! so a network player reads the table in their own language whatever the
+//! host speaks.
+
+use crate::card::Card;
+use crate::config::Key;
+use crate::error::RummyError;
+use crate::game::PlayerType;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// A language the table speaks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+impl FromStr for Locale {
+    type Err = RummyError;
+
+    fn from_str(input: &str) -> Result<Locale, RummyError> {
+        match input.trim().to_lowercase().as_str() {
+            "en" | "english" => Ok(Locale::En),
+            "es" | "spanish" | "español" => Ok(Locale::Es),
+            _ => Err(RummyError::Config(format!(
+                "{input} isn't a language the table speaks, try en or es"
+            ))),
+        }
+    }
+}
+
+impl Locale {
+    /// Whether `answer` is yes, in this language or in English.
+    pub fn is_yes(self, answer: &str) -> bool {
+        let answer = answer.trim().to_lowercase();
+        match self {
+            Locale::En => matches!(answer.as_str(), "y" | "yes"),
+            Locale::Es => matches!(answer.as_str(), "s" | "si" | "sí" | "y" | "yes"),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    /// Writes the locale as it's typed, e.g. `es`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+        })
+    }
+}
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::En);
+
+/// Says everything from now on in `locale`.
+pub fn install(locale: Locale) {
+    *LOCALE.write().unwrap() = locale;
+}
+
+/// The language everything is said in, English until another is installed.
+pub fn locale() -> Locale {
+    *LOCALE.read().unwrap()
+}
+
+/// What a player did, as the action log tells it after their name. Any
+/// cards it involved are shown after the words.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "key", rename_all = "snake_case")]
+pub enum Message {
+    /// Drew the card shown to decide who goes first.
+    DrewForSeat,
+    /// Cut the deck to the card shown to decide who goes first.
+    CutForSeat,
+    GoesFirst,
+    Discarded,
+    DrewAndDiscarded,
+    RetrievedAndDiscarded,
+    /// Declared on `card`, and threw away the card shown for it.
+    Declared {
+        card: Card,
+    },
+    HandPlayed {
+        score: u64,
+    },
+    Knocked {
+        deadwood: u64,
+    },
+    LaidOff {
+        cards: usize,
+        score: u64,
+    },
+    CouldNotLayOff,
+    RoundWon {
+        score: u64,
+    },
+    RoundRedealt,
+    DeadHand {
+        reshuffles: usize,
+    },
+    RoundStalemate,
+    DeckCutForDeal {
+        position: usize,
+    },
+    CardBurned,
+    QuizScored {
+        correct: usize,
+        asked: usize,
+        points: usize,
+    },
+    ScoreAdjusted {
+        points: i64,
+    },
+    TemperamentShifted {
+        temperament: PlayerType,
+    },
+    GameWon {
+        score: usize,
+    },
+    LeftTable,
+    RejoinedTable,
+    RanOutOfTime,
+    /// Already in words, like a house rule's announcement, so it's never
+    /// translated.
+    Said {
+        text: String,
+    },
+}
+
+impl Message {
+    pub fn text(&self, locale: Locale) -> String {
+        match locale {
+            Locale::En => self.english(),
+            Locale::Es => self.spanish(),
+        }
+    }
+
+    fn english(&self) -> String {
+        match self {
+            Message::DrewForSeat => "drew the".to_string(),
+            Message::CutForSeat => "cut the deck to the".to_string(),
+            Message::GoesFirst => "goes first.".to_string(),
+            Message::Discarded => "discarded the".to_string(),
+            Message::DrewAndDiscarded => "drew and discarded the".to_string(),
+            Message::RetrievedAndDiscarded => "retrieved discard and discarded the".to_string(),
+            Message::Declared { card } => format!("declared on the {card} and threw away the"),
+            Message::HandPlayed { score } => {
+                format!("played their hand for {score} points, time to layoff:")
+            }
+            Message::Knocked { deadwood } => format!("knocked with {deadwood} deadwood, melding:"),
+            Message::LaidOff { cards, score } => {
+                format!("laid off {cards} card(s) to winner's meld, scoring: {score}")
+            }
+            Message::CouldNotLayOff => "could not layoff cards to form a meld.".to_string(),
+            Message::RoundWon { score } => {
+                format!("won this round with a score of {score} and the hand")
+            }
+            Message::RoundRedealt => {
+                "called off the round, the cards are dealt again.".to_string()
+            }
+            Message::DeadHand { reshuffles } => format!(
+                "discarded as the draw pile ran out after {reshuffles} reshuffles, it's a dead hand and everyone shows their cards."
+            ),
+            Message::RoundStalemate => "discarded the last card to draw, the round's a stalemate and the cards are dealt again.".to_string(),
+            Message::DeckCutForDeal { position } => format!("cut the deck {position} cards down."),
+            Message::CardBurned => "burned the top card face-down.".to_string(),
+            Message::QuizScored {
+                correct,
+                asked,
+                points,
+            } => format!("got {correct} of {asked} quiz questions right, {points:+} points."),
+            Message::ScoreAdjusted { points } => {
+                format!("gets {points:+} points from the house rules.")
+            }
+            Message::TemperamentShifted { temperament } => {
+                format!("is tilting {temperament} now.")
+            }
+            Message::GameWon { score } => format!("won the game with {score} points!"),
+            Message::LeftTable => "left the table.".to_string(),
+            Message::RejoinedTable => "rejoined the table.".to_string(),
+            Message::RanOutOfTime => {
+                "ran out of time, and their turn was played for them.".to_string()
+            }
+            Message::Said { text } => text.clone(),
+        }
+    }
+
+    fn spanish(&self) -> String {
+        match self {
+            Message::DrewForSeat => "sacó el".to_string(),
+            Message::CutForSeat => "cortó la baraja en el".to_string(),
+            Message::GoesFirst => "empieza.".to_string(),
+            Message::Discarded => "descartó el".to_string(),
+            Message::DrewAndDiscarded => "robó y descartó el".to_string(),
+            Message::RetrievedAndDiscarded => "recogió el descarte y descartó el".to_string(),
+            Message::Declared { card } => format!("declaró sobre el {card} y tiró el"),
+            Message::HandPlayed { score } => {
+                format!("jugó su mano por {score} puntos, a colocar cartas:")
+            }
+            Message::Knocked { deadwood } => {
+                format!("tocó con {deadwood} de cartas sueltas, combinando:")
+            }
+            Message::LaidOff { cards, score } => {
+                format!("colocó {cards} carta(s) en la combinación ganadora, con {score} puntos")
+            }
+            Message::CouldNotLayOff => {
+                "no pudo colocar cartas para formar una combinación.".to_string()
+            }
+            Message::RoundWon { score } => format!("ganó esta ronda con {score} puntos y la mano"),
+            Message::RoundRedealt => "anuló la ronda, se reparten las cartas de nuevo.".to_string(),
+            Message::DeadHand { reshuffles } => format!(
+                "descartó cuando se acabó el mazo tras {reshuffles} rebarajadas, es una mano muerta y todos enseñan sus cartas."
+            ),
+            Message::RoundStalemate => "descartó la última carta por robar, la ronda queda en tablas y se reparten las cartas de nuevo.".to_string(),
+            Message::DeckCutForDeal { position } => {
+                format!("cortó la baraja {position} cartas más abajo.")
+            }
+            Message::CardBurned => "quemó la carta de arriba boca abajo.".to_string(),
+            Message::QuizScored {
+                correct,
+                asked,
+                points,
+            } => format!(
+                "acertó {correct} de {asked} preguntas del cuestionario, {points:+} puntos."
+            ),
+            Message::ScoreAdjusted { points } => {
+                format!("recibe {points:+} puntos por las reglas de la casa.")
+            }
+            Message::TemperamentShifted { temperament } => {
+                let temperament = match temperament {
+                    PlayerType::Conservative => "conservador",
+                    PlayerType::Aggressive => "agresivo",
+                    PlayerType::Balanced => "equilibrado",
+                    PlayerType::Ensemble => "en conjunto",
+                    PlayerType::MonteCarlo => "a lo Monte Carlo",
+                };
+                format!("ahora juega {temperament}.")
+            }
+            Message::GameWon { score } => format!("¡ganó la partida con {score} puntos!"),
+            Message::LeftTable => "se levantó de la mesa.".to_string(),
+            Message::RejoinedTable => "volvió a la mesa.".to_string(),
+            Message::RanOutOfTime => "se quedó sin tiempo y se jugó su turno por él.".to_string(),
+            Message::Said { text } => text.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    /// Writes the message in the installed locale.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text(locale()))
+    }
+}
+
+/// What the screen asks of a player, or tells them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Prompt {
+    /// The choices on a network player's turn.
+    Choose,
+    Discard,
+    LayOff,
+    Chat,
+    ConfirmRedeal,
+    /// The redeal was called off.
+    CarryOn,
+    Redealt,
+    DeclareOrPass,
+    /// `card` was discarded, and it's yours to declare on.
+    Completes {
+        card: Card,
+    },
+    DeclareOnIt {
+        card: Card,
+    },
+    /// The card taken on a staged turn and the card to throw away for it,
+    /// both already drawn for the screen.
+    Staged {
+        card: String,
+        retrieved: bool,
+        discard: Option<String>,
+    },
+    SecondsLeft {
+        secs: u64,
+    },
+    Victory {
+        player: String,
+    },
+    /// The banner over a celebration.
+    VictoryBanner,
+    Wins {
+        player: String,
+    },
+    WinsTheGame {
+        player: String,
+    },
+    PressEnterToExit,
+    /// What `/help` lists.
+    Commands,
+    UnknownCommand {
+        command: String,
+    },
+    /// `command` was typed with something after it it doesn't take.
+    CommandTakesNothing {
+        command: String,
+    },
+    /// What `/hand` says: the cards, already drawn, and the best meld's score.
+    YourHand {
+        cards: String,
+        score: u64,
+    },
+    /// What `/scores` says, everyone's name and score in seating order.
+    Scores {
+        scores: Vec<(String, usize)>,
+    },
+    /// The Esc menu.
+    Paused,
+    PauseChoices,
+    FinishTurnToSave,
+    /// Where the game was saved, and whether the table's closing for it.
+    Saved {
+        path: String,
+        quitting: bool,
+    },
+    NotSaved {
+        quitting: bool,
+    },
+    CouldNotSave {
+        error: String,
+    },
+    GameAbandoned,
+    HintsAtStartOfTurn,
+    Hint {
+        play: bool,
+        reasoning: String,
+    },
+    /// Anything else typed at the choices `prompt`.
+    InvalidInput {
+        prompt: String,
+    },
+    /// `C` and `U` are all a staged turn takes once its discard's picked.
+    ConfirmOrUndo,
+    /// Hot-seat play, the keyboard going to `player`.
+    PassTheKeyboard {
+        player: String,
+    },
+    DecidingTurnOrder,
+    /// The card `player` draws for their seat follows, once it's turned over.
+    DrawsForSeat {
+        player: String,
+    },
+    DealsAndCuts {
+        player: String,
+    },
+    CountingFromDealer {
+        count: u64,
+    },
+    GoesFirst {
+        player: String,
+    },
+    /// Everyone in the order they play, already drawn for the screen.
+    PlayOrder {
+        order: Vec<String>,
+    },
+    WritingQuiz {
+        book: String,
+    },
+    CouldNotWriteQuiz {
+        error: String,
+    },
+    NoQuiz,
+    QuizFor {
+        player: String,
+        points: usize,
+    },
+    QuizRight,
+    /// The right answer, lettered.
+    QuizWrong {
+        answer: String,
+    },
+    QuizScored {
+        player: String,
+        correct: usize,
+        asked: usize,
+        points: usize,
+    },
+    /// A layoff onto the meld would only take the round from their own team.
+    PartnersMeld {
+        partner: String,
+    },
+    DeclaredOutOfTurn {
+        player: String,
+        score: u64,
+    },
+    /// An AI's hand was played, or knocked with `score` deadwood.
+    AiPlayed {
+        player: String,
+        score: u64,
+        knocked: bool,
+    },
+    WinsOnChips {
+        player: String,
+    },
+    /// The headings of a written-out game.
+    TranscriptTitle {
+        book: String,
+    },
+    TableTalk,
+    Actions,
+    /// How far back the screen's scrolled, with how to get back.
+    PagesBack {
+        pages: usize,
+    },
+    /// The headings of the table's panels, along with `Actions`.
+    LastRound,
+    Scoreboard,
+    Chips,
+    Odds,
+    Discards,
+    Messages,
+    /// The memory trainer, over every card it flashes up.
+    MemoryTrainer,
+    /// Cards and hands in the memory trainer, already drawn for the screen.
+    TurnedOver {
+        card: String,
+    },
+    DiscardsCard {
+        player: String,
+        card: String,
+    },
+    PlaysHand {
+        player: String,
+        cards: String,
+    },
+    /// The `number`th of `asked` questions, about whether `card` went by.
+    WentBy {
+        number: usize,
+        asked: usize,
+        card: String,
+    },
+    ItWentBy,
+    StillOutThere,
+    TrainerScored {
+        correct: usize,
+        asked: usize,
+    },
+    CardsThatWentBy {
+        cards: String,
+    },
+    /// The setup questions, and what's wrong with an answer to them.
+    NumberOfPlayers,
+    TooFewPlayers,
+    PlayerDescription,
+    NumberOfHumans,
+    NoHumans,
+    OnlyPlayers {
+        most: usize,
+    },
+    YourName,
+    /// The name of the player in `seat`, counting from 1.
+    PlayerName {
+        seat: usize,
+    },
+    NameNeeded,
+    NameTaken {
+        name: String,
+    },
+    BookAndAuthor {
+        book: String,
+    },
+    BookTitle {
+        book: String,
+    },
+    /// What a player did last turn, their name already drawn for the screen
+    /// and `retrieved` the card they took from the discard pile, if they did.
+    DiscardedRecap {
+        player: String,
+        retrieved: Option<String>,
+        card: String,
+        pile: usize,
+    },
+    PlayedRecap {
+        player: String,
+        score: u64,
+    },
+    /// The odds overlay, cards and melds already drawn for the screen.
+    BestMeldNow {
+        meld: String,
+    },
+    DrawImproves {
+        percent: i64,
+        expected: String,
+    },
+    TakingImproves {
+        card: String,
+        meld: String,
+    },
+    TakingDoesNotImprove {
+        card: String,
+    },
+    WorthKeeping,
+    /// A card's worth in the odds overlay, `best` the one to throw away.
+    CardWorth {
+        card: String,
+        value: String,
+        percent: i64,
+        best: bool,
+    },
+    DiscardPileEmpty,
+    /// The steps of a stolen round's layoff chain, names padded to line up.
+    ChainPlayed {
+        player: String,
+        cards: String,
+        meld: String,
+    },
+    ChainLaidOff {
+        player: String,
+        cards: String,
+        result: String,
+    },
+    DidNotBeatIt,
+    ChainWon {
+        player: String,
+        score: u64,
+        cards: String,
+    },
+    /// The lines of a game's summary.
+    WonAfter {
+        player: String,
+        rounds: usize,
+        book: String,
+    },
+    HandOfTheGame {
+        player: String,
+        meld: String,
+        score: u64,
+    },
+    /// A player's points, `name` padded to line up.
+    Points {
+        name: String,
+        score: usize,
+    },
+    WonOnLayoffs {
+        times: usize,
+    },
+    Melds {
+        melds: Vec<String>,
+    },
+    WhatTheClubMadeOfIt,
+    FinalScores,
+    PressEnterToContinue,
+    SummingUp,
+    CouldNotSumUp {
+        error: String,
+    },
+    TranscriptNotice {
+        path: String,
+    },
+    CouldNotAudit {
+        error: String,
+    },
+    IntegrityReport {
+        deals: usize,
+    },
+    ShuffleLooksFair,
+    /// A flagged `AuditFinding`, the odds already rounded for the screen.
+    DealtOrBetter {
+        player: String,
+        meld: String,
+        observed: usize,
+        deals: usize,
+        expected: String,
+        p_value: String,
+    },
+    ModeratorLostForWords {
+        error: String,
+    },
+    LostForWords {
+        player: String,
+        error: String,
+    },
+    NotInHand {
+        card: String,
+    },
+    /// What a Ctrl-C leaves behind when there's a save to pick back up.
+    SavedAtStartOfTurn,
+    /// What was lost, but not the game, when something couldn't be done.
+    CouldNotRecallSessions {
+        error: String,
+    },
+    WithoutSound {
+        error: String,
+    },
+    CouldNotRecord {
+        error: String,
+    },
+    CouldNotArchive {
+        error: String,
+    },
+    CouldNotRemember {
+        error: String,
+    },
+    CouldNotWriteOut {
+        error: String,
+    },
+    /// The network client's side of the table.
+    CouldNotConnect {
+        url: String,
+        error: String,
+    },
+    ConnectionClosed,
+    NotStarted,
+    DrawPlayOrRetrieve,
+    MayBeDeclared,
+    /// What the hint comes to, over the network where there's no reasoning.
+    Advice(Key),
+    HostSaves,
+    WaitingForPlayers {
+        players: Vec<String>,
+        seats: usize,
+    },
+    /// What a key's called at the prompt.
+    Key(Key),
+    /// A choice between what's listed, e.g. "Draw (D), Play (P) or Hint (H)?".
+    Choice(Vec<String>),
+}
+
+impl Prompt {
+    pub fn text(&self, locale: Locale) -> String {
+        match locale {
+            Locale::En => self.english(),
+            Locale::Es => self.spanish(),
+        }
+    }
+
+    fn english(&self) -> String {
+        match self {
+            Prompt::Choose => "Draw (D), Play (P), or Retrieve (R)?".to_string(),
+            Prompt::Discard => "Which card to discard?".to_string(),
+            Prompt::LayOff => {
+                "Enter cards to lay off separated by spaces (e.g. \"7h Jc\"): ".to_string()
+            }
+            Prompt::Chat => "Join the conversation: ".to_string(),
+            Prompt::ConfirmRedeal => "Call off this round and deal it again? (y/n)".to_string(),
+            Prompt::CarryOn => "Carrying on with this round.".to_string(),
+            Prompt::Redealt => "The round was called off and dealt again.".to_string(),
+            Prompt::DeclareOrPass => "Declare (Y) or let it go (N)?".to_string(),
+            Prompt::Completes { card } => format!("The {card} completes your meld!"),
+            Prompt::DeclareOnIt { card } => {
+                format!("The {card} completes your meld! Declare on it? (y/n)")
+            }
+            Prompt::Staged {
+                card,
+                retrieved,
+                discard,
+            } => {
+                let taken = if *retrieved {
+                    format!("Retrieve the {card}")
+                } else {
+                    format!("You drew the {card}")
+                };
+                match discard {
+                    Some(discard) => {
+                        format!("{taken} and discard the {discard}. Confirm (C) or Undo (U)?")
+                    }
+                    None => format!("{taken}. Which card to discard, or Undo (U)?"),
+                }
+            }
+            Prompt::SecondsLeft { secs } => format!("{secs}s left"),
+            Prompt::Victory { player } => format!("{player} won todays Bookclub Rummy!"),
+            Prompt::VictoryBanner => "VICTORY!".to_string(),
+            Prompt::Wins { player } => format!("{player} wins!"),
+            Prompt::WinsTheGame { player } => format!("{player} WINS THE GAME!"),
+            Prompt::PressEnterToExit => "Press Enter to exit...".to_string(),
+            Prompt::Commands => {
+                "Commands: /hand, /scores, /history, /hint, /save, /quit, /say <message>"
+                    .to_string()
+            }
+            Prompt::UnknownCommand { command } => {
+                format!("{command} isn't a command, /help lists them")
+            }
+            Prompt::CommandTakesNothing { command } => {
+                format!("{command} doesn't take anything after it")
+            }
+            Prompt::YourHand { cards, score } => {
+                format!("Your hand: {cards}. Best meld worth {score}.")
+            }
+            Prompt::Scores { scores } => format!("Scores: {}", scores_list(scores)),
+            Prompt::Paused => "Paused. Resume (R), Save & Quit (S) or Quit (Q)?".to_string(),
+            Prompt::PauseChoices => "Expected R (resume), S (save & quit) or Q (quit).".to_string(),
+            Prompt::FinishTurnToSave => {
+                "Finish your turn first, only a turn that hasn't started can be saved.".to_string()
+            }
+            Prompt::Saved {
+                path,
+                quitting: true,
+            } => format!("Saved to {path}. Pick it back up with --resume."),
+            Prompt::Saved { path, .. } => format!("Saved to {path}."),
+            Prompt::NotSaved { quitting: true } => {
+                "This game isn't saved, so it's over.".to_string()
+            }
+            Prompt::NotSaved { .. } => "This game isn't saved.".to_string(),
+            Prompt::CouldNotSave { error } => format!("Couldn't save the game: {error}"),
+            Prompt::GameAbandoned => "Game abandoned.".to_string(),
+            Prompt::HintsAtStartOfTurn => "Hints are for the start of your turn.".to_string(),
+            Prompt::Hint { play, reasoning } => {
+                let advice = if *play { "Play" } else { "Draw" };
+                format!("Hint: {advice}. {reasoning}")
+            }
+            Prompt::InvalidInput { prompt } => format!("Invalid input. {prompt}"),
+            Prompt::ConfirmOrUndo => "Expected C (confirm) or U (undo).".to_string(),
+            Prompt::PassTheKeyboard { player } => {
+                format!("Pass the laptop to {player}, press Enter")
+            }
+            Prompt::DecidingTurnOrder => "Deciding who goes first...".to_string(),
+            Prompt::DrawsForSeat { player } => format!("{player} draws"),
+            Prompt::DealsAndCuts { player } => format!("{player} deals, and cuts the deck to"),
+            Prompt::CountingFromDealer { count } => {
+                format!("Counting {count} from the dealer's left...")
+            }
+            Prompt::GoesFirst { player } => format!("{player} goes first!"),
+            Prompt::PlayOrder { order } => format!("Play goes {}", order.join(" → ")),
+            Prompt::WritingQuiz { book } => format!("Writing a quiz about {book}..."),
+            Prompt::CouldNotWriteQuiz { error } => format!("Couldn't write the quiz: {error}"),
+            Prompt::NoQuiz => "No quiz today, on with the game.".to_string(),
+            Prompt::QuizFor { player, points } => {
+                format!("{player}, {points} points for every right answer:")
+            }
+            Prompt::QuizRight => "Right!".to_string(),
+            Prompt::QuizWrong { answer } => format!("Not quite, it was {answer}"),
+            Prompt::QuizScored {
+                player,
+                correct,
+                asked,
+                points,
+            } => format!("{player} got {correct} of {asked} and starts with {points} points."),
+            Prompt::PartnersMeld { partner } => {
+                format!("That's your partner {partner}'s meld, lay off nothing to let it stand")
+            }
+            Prompt::DeclaredOutOfTurn { player, score } => {
+                format!("{player} declared out of turn with score: {score}")
+            }
+            Prompt::AiPlayed {
+                player,
+                score,
+                knocked: true,
+            } => format!("{player} knocked with {score} deadwood"),
+            Prompt::AiPlayed { player, score, .. } => {
+                format!("{player} played their hand with score: {score}")
+            }
+            Prompt::WinsOnChips { player } => format!("{player} wins the match on chips"),
+            Prompt::TranscriptTitle { book } => format!("Bookclub Rummy on {book}"),
+            Prompt::TableTalk => "Table talk:".to_string(),
+            Prompt::Actions => "Actions:".to_string(),
+            Prompt::PagesBack { pages: 1 } => {
+                "— 1 page back, PgDn and Enter for the latest —".to_string()
+            }
+            Prompt::PagesBack { pages } => {
+                format!("— {pages} pages back, PgDn and Enter for the latest —")
+            }
+            Prompt::LastRound => "Last round:".to_string(),
+            Prompt::Scoreboard => "Scoreboard:".to_string(),
+            Prompt::Chips => "Chips:".to_string(),
+            Prompt::Odds => "Odds:".to_string(),
+            Prompt::Discards => "Discards:".to_string(),
+            Prompt::Messages => "Messages:".to_string(),
+            Prompt::MemoryTrainer => "Memory trainer: watch the table".to_string(),
+            Prompt::TurnedOver { card } => format!("The deal turns over {card}"),
+            Prompt::DiscardsCard { player, card } => format!("{player} discards {card}"),
+            Prompt::PlaysHand { player, cards } => format!("{player} plays {cards}"),
+            Prompt::WentBy {
+                number,
+                asked,
+                card,
+            } => format!("{number}/{asked}: Did the {card} go by? (y/n)"),
+            Prompt::ItWentBy => "It did.".to_string(),
+            Prompt::StillOutThere => "It's still out there.".to_string(),
+            Prompt::TrainerScored { correct, asked } => {
+                format!("You got {correct} of {asked} right.")
+            }
+            Prompt::CardsThatWentBy { cards } => format!("The cards that went by: {cards}"),
+            Prompt::NumberOfPlayers => "Enter number of players:".to_string(),
+            Prompt::TooFewPlayers => "A game needs at least 2 players".to_string(),
+            Prompt::PlayerDescription => {
+                "Enter player description (Press enter if none):".to_string()
+            }
+            Prompt::NumberOfHumans => {
+                "Enter number of human players (Press enter for 1):".to_string()
+            }
+            Prompt::NoHumans => "There has to be at least 1 human player".to_string(),
+            Prompt::OnlyPlayers { most } => format!("There are only {most} players"),
+            Prompt::YourName => "Enter your name:".to_string(),
+            Prompt::PlayerName { seat } => format!("Enter name of player {seat}:"),
+            Prompt::NameNeeded => "Everyone needs a name".to_string(),
+            Prompt::NameTaken { name } => format!("There's already a {name} at the table"),
+            Prompt::BookAndAuthor { book } => format!("Enter book and author ({book})"),
+            Prompt::BookTitle { book } => format!("Today's Bookclub Rummy is on {book}"),
+            Prompt::DiscardedRecap {
+                player,
+                retrieved,
+                card,
+                pile,
+            } => {
+                let taken = match retrieved {
+                    Some(retrieved) => format!("retrieved the {retrieved}"),
+                    None => "drew a card".to_string(),
+                };
+                let cards = if *pile == 1 { "card" } else { "cards" };
+                format!(
+                    "{player} {taken} and discarded the {card} — discard pile now {pile} {cards}"
+                )
+            }
+            Prompt::PlayedRecap { player, score } => {
+                format!("{player} played their hand for {score}")
+            }
+            Prompt::BestMeldNow { meld } => format!("Best meld now: {meld}"),
+            Prompt::DrawImproves { percent, expected } => {
+                format!("A draw improves it {percent}% of the time, to {expected} on average")
+            }
+            Prompt::TakingImproves { card, meld } => format!("Taking the {card} makes it {meld}"),
+            Prompt::TakingDoesNotImprove { card } => {
+                format!("Taking the {card} doesn't improve it")
+            }
+            Prompt::WorthKeeping => "Worth keeping, least first:".to_string(),
+            Prompt::CardWorth {
+                card,
+                value,
+                percent,
+                best,
+            } => {
+                let advice = if *best { "  ← best to discard" } else { "" };
+                format!("  {card}  {value}  in a meld {percent}% of the time{advice}")
+            }
+            Prompt::DiscardPileEmpty => "The discard pile is empty".to_string(),
+            Prompt::ChainPlayed {
+                player,
+                cards,
+                meld,
+            } => format!("{player} played    {cards}  {meld}"),
+            Prompt::ChainLaidOff {
+                player,
+                cards,
+                result,
+            } => format!("{player} laid off  {cards}  {result}"),
+            Prompt::DidNotBeatIt => "didn't beat it".to_string(),
+            Prompt::ChainWon {
+                player,
+                score,
+                cards,
+            } => format!("{player} won {score} with {cards}"),
+            Prompt::WonAfter {
+                player,
+                rounds: 1,
+                book,
+            } => format!("{player} won after 1 round of {book}."),
+            Prompt::WonAfter {
+                player,
+                rounds,
+                book,
+            } => format!("{player} won after {rounds} rounds of {book}."),
+            Prompt::HandOfTheGame {
+                player,
+                meld,
+                score,
+            } => format!("Hand of the game: {player}'s {meld} for {score}."),
+            Prompt::Points { name, score } => format!("{name} {score:>4} points"),
+            Prompt::WonOnLayoffs { times: 1 } => ", won on a layoff 1 time".to_string(),
+            Prompt::WonOnLayoffs { times } => format!(", won on a layoff {times} times"),
+            Prompt::Melds { melds } => format!("Melds: {}", melds.join(", ")),
+            Prompt::WhatTheClubMadeOfIt => "What the club made of it:".to_string(),
+            Prompt::FinalScores => "Final Scores:".to_string(),
+            Prompt::PressEnterToContinue => "Press Enter to continue...".to_string(),
+            Prompt::SummingUp => "Summing up the discussion...".to_string(),
+            Prompt::CouldNotSumUp { error } => format!("Couldn't sum up the discussion: {error}"),
+            Prompt::TranscriptNotice { path } => {
+                format!("The whole game, talk and all, is in {path}")
+            }
+            Prompt::CouldNotAudit { error } => format!("Couldn't audit this game: {error}"),
+            Prompt::IntegrityReport { deals } => {
+                format!("Integrity report ({deals} hands dealt):")
+            }
+            Prompt::ShuffleLooksFair => {
+                "Nothing out of the ordinary, the shuffle looks fair.".to_string()
+            }
+            Prompt::DealtOrBetter {
+                player,
+                meld,
+                observed,
+                deals,
+                expected,
+                p_value,
+            } => format!(
+                "{player} was dealt a {meld} or better in {observed} of {deals} deals, where a fair shuffle averages {expected} (p = {p_value})."
+            ),
+            Prompt::ModeratorLostForWords { error } => {
+                format!("The moderator is lost for words: {error}")
+            }
+            Prompt::LostForWords { player, error } => {
+                format!("{player} is lost for words: {error}")
+            }
+            Prompt::NotInHand { card } => format!("You don't have {card}"),
+            Prompt::SavedAtStartOfTurn => {
+                "The game's saved as of the start of this turn, pick it back up with --resume."
+                    .to_string()
+            }
+            Prompt::CouldNotRecallSessions { error } => {
+                format!("Couldn't remember earlier sessions: {error}")
+            }
+            Prompt::WithoutSound { error } => format!("Playing without sound: {error}"),
+            Prompt::CouldNotRecord { error } => format!("Couldn't record this game: {error}"),
+            Prompt::CouldNotArchive { error } => {
+                format!("Couldn't keep this game for the archive: {error}")
+            }
+            Prompt::CouldNotRemember { error } => {
+                format!("Couldn't remember this session: {error}")
+            }
+            Prompt::CouldNotWriteOut { error } => format!("Couldn't write out this game: {error}"),
+            Prompt::CouldNotConnect { url, error } => format!("Couldn't connect to {url}: {error}"),
+            Prompt::ConnectionClosed => "The server closed the connection".to_string(),
+            Prompt::NotStarted => "The game hasn't started yet.".to_string(),
+            Prompt::DrawPlayOrRetrieve => "Invalid choice! Please enter D, P, or R.".to_string(),
+            Prompt::MayBeDeclared => "Hold on, someone may declare on that discard.".to_string(),
+            Prompt::Advice(key) => format!("Hint: {}.", key.name()),
+            Prompt::HostSaves => "Only the host can save a network game.".to_string(),
+            Prompt::WaitingForPlayers { players, seats } => format!(
+                "Waiting for players ({}/{seats}): {}",
+                players.len(),
+                players.join(", ")
+            ),
+            Prompt::Key(key) => key.name().to_string(),
+            Prompt::Choice(choices) => match choices.split_last() {
+                Some((last, rest)) if !rest.is_empty() => {
+                    format!("{} or {last}?", rest.join(", "))
+                }
+                Some((last, _)) => format!("{last}?"),
+                None => String::new(),
+            },
+        }
+    }
+
+    fn spanish(&self) -> String {
+        match self {
+            Prompt::Choose => "¿Robar (D), Jugar (P) o Recoger (R)?".to_string(),
+            Prompt::Discard => "¿Qué carta descartas?".to_string(),
+            Prompt::LayOff => {
+                "Escribe las cartas que colocas separadas por espacios (p. ej. \"7h Jc\"): "
+                    .to_string()
+            }
+            Prompt::Chat => "Únete a la conversación: ".to_string(),
+            Prompt::ConfirmRedeal => "¿Anular esta ronda y repartirla de nuevo? (s/n)".to_string(),
+            Prompt::CarryOn => "Seguimos con esta ronda.".to_string(),
+            Prompt::Redealt => "Se anuló la ronda y se repartió de nuevo.".to_string(),
+            Prompt::DeclareOrPass => "¿Declarar (S) o dejarla pasar (N)?".to_string(),
+            Prompt::Completes { card } => format!("¡El {card} completa tu combinación!"),
+            Prompt::DeclareOnIt { card } => {
+                format!("¡El {card} completa tu combinación! ¿Declaras? (s/n)")
+            }
+            Prompt::Staged {
+                card,
+                retrieved,
+                discard,
+            } => {
+                let taken = if *retrieved {
+                    format!("Recoges el {card}")
+                } else {
+                    format!("Robaste el {card}")
+                };
+                match discard {
+                    Some(discard) => {
+                        format!("{taken} y descartas el {discard}. ¿Confirmar (C) o Deshacer (U)?")
+                    }
+                    None => format!("{taken}. ¿Qué carta descartas, o Deshacer (U)?"),
+                }
+            }
+            Prompt::SecondsLeft { secs } => format!("quedan {secs}s"),
+            Prompt::Victory { player } => format!("¡{player} ganó el Bookclub Rummy de hoy!"),
+            Prompt::VictoryBanner => "¡VICTORIA!".to_string(),
+            Prompt::Wins { player } => format!("¡{player} gana!"),
+            Prompt::WinsTheGame { player } => format!("¡{player} GANA LA PARTIDA!"),
+            Prompt::PressEnterToExit => "Pulsa Intro para salir...".to_string(),
+            Prompt::Commands => {
+                "Órdenes: /hand, /scores, /history, /hint, /save, /quit, /say <mensaje>".to_string()
+            }
+            Prompt::UnknownCommand { command } => {
+                format!("{command} no es una orden, /help las enumera")
+            }
+            Prompt::CommandTakesNothing { command } => {
+                format!("{command} no lleva nada detrás")
+            }
+            Prompt::YourHand { cards, score } => {
+                format!("Tu mano: {cards}. La mejor combinación vale {score}.")
+            }
+            Prompt::Scores { scores } => format!("Puntuaciones: {}", scores_list(scores)),
+            Prompt::Paused => "En pausa. ¿Seguir (R), Guardar y salir (S) o Salir (Q)?".to_string(),
+            Prompt::PauseChoices => {
+                "Se esperaba R (seguir), S (guardar y salir) o Q (salir).".to_string()
+            }
+            Prompt::FinishTurnToSave => {
+                "Termina tu turno antes, solo se puede guardar un turno sin empezar.".to_string()
+            }
+            Prompt::Saved {
+                path,
+                quitting: true,
+            } => format!("Guardada en {path}. Retómala con --resume."),
+            Prompt::Saved { path, .. } => format!("Guardada en {path}."),
+            Prompt::NotSaved { quitting: true } => {
+                "Esta partida no se guarda, así que se acabó.".to_string()
+            }
+            Prompt::NotSaved { .. } => "Esta partida no se guarda.".to_string(),
+            Prompt::CouldNotSave { error } => format!("No se pudo guardar la partida: {error}"),
+            Prompt::GameAbandoned => "Partida abandonada.".to_string(),
+            Prompt::HintsAtStartOfTurn => {
+                "Las pistas son para el comienzo de tu turno.".to_string()
+            }
+            Prompt::Hint { play, reasoning } => {
+                let advice = if *play { "Juega" } else { "Roba" };
+                format!("Pista: {advice}. {reasoning}")
+            }
+            Prompt::InvalidInput { prompt } => format!("Entrada no válida. {prompt}"),
+            Prompt::ConfirmOrUndo => "Se esperaba C (confirmar) o U (deshacer).".to_string(),
+            Prompt::PassTheKeyboard { player } => {
+                format!("Pasa el portátil a {player} y pulsa Intro")
+            }
+            Prompt::DecidingTurnOrder => "Decidiendo quién empieza...".to_string(),
+            Prompt::DrawsForSeat { player } => format!("{player} saca el"),
+            Prompt::DealsAndCuts { player } => format!("{player} reparte y corta la baraja en el"),
+            Prompt::CountingFromDealer { count } => {
+                format!("Se cuentan {count} desde la izquierda de quien reparte...")
+            }
+            Prompt::GoesFirst { player } => format!("¡{player} empieza!"),
+            Prompt::PlayOrder { order } => format!("El orden de juego es {}", order.join(" → ")),
+            Prompt::WritingQuiz { book } => format!("Preparando un cuestionario sobre {book}..."),
+            Prompt::CouldNotWriteQuiz { error } => {
+                format!("No se pudo preparar el cuestionario: {error}")
+            }
+            Prompt::NoQuiz => "Hoy no hay cuestionario, sigamos con la partida.".to_string(),
+            Prompt::QuizFor { player, points } => {
+                format!("{player}, {points} puntos por cada respuesta correcta:")
+            }
+            Prompt::QuizRight => "¡Correcto!".to_string(),
+            Prompt::QuizWrong { answer } => format!("No exactamente, era {answer}"),
+            Prompt::QuizScored {
+                player,
+                correct,
+                asked,
+                points,
+            } => format!("{player} acertó {correct} de {asked} y empieza con {points} puntos."),
+            Prompt::PartnersMeld { partner } => format!(
+                "Es la combinación de tu pareja {partner}, no coloques nada para que se mantenga"
+            ),
+            Prompt::DeclaredOutOfTurn { player, score } => {
+                format!("{player} declaró fuera de turno con {score} puntos")
+            }
+            Prompt::AiPlayed {
+                player,
+                score,
+                knocked: true,
+            } => format!("{player} tocó con {score} de cartas sueltas"),
+            Prompt::AiPlayed { player, score, .. } => {
+                format!("{player} jugó su mano con {score} puntos")
+            }
+            Prompt::WinsOnChips { player } => format!("{player} gana la partida por fichas"),
+            Prompt::TranscriptTitle { book } => format!("Bookclub Rummy sobre {book}"),
+            Prompt::TableTalk => "Charla de la mesa:".to_string(),
+            Prompt::Actions => "Jugadas:".to_string(),
+            Prompt::PagesBack { pages: 1 } => {
+                "— 1 página atrás, AvPág e Intro para lo último —".to_string()
+            }
+            Prompt::PagesBack { pages } => {
+                format!("— {pages} páginas atrás, AvPág e Intro para lo último —")
+            }
+            Prompt::LastRound => "Última ronda:".to_string(),
+            Prompt::Scoreboard => "Marcador:".to_string(),
+            Prompt::Chips => "Fichas:".to_string(),
+            Prompt::Odds => "Probabilidades:".to_string(),
+            Prompt::Discards => "Descartes:".to_string(),
+            Prompt::Messages => "Mensajes:".to_string(),
+            Prompt::MemoryTrainer => "Entrenador de memoria: mira la mesa".to_string(),
+            Prompt::TurnedOver { card } => format!("El reparto destapa el {card}"),
+            Prompt::DiscardsCard { player, card } => format!("{player} descarta el {card}"),
+            Prompt::PlaysHand { player, cards } => format!("{player} juega {cards}"),
+            Prompt::WentBy {
+                number,
+                asked,
+                card,
+            } => format!("{number}/{asked}: ¿Pasó el {card}? (s/n)"),
+            Prompt::ItWentBy => "Sí que pasó.".to_string(),
+            Prompt::StillOutThere => "Sigue ahí fuera.".to_string(),
+            Prompt::TrainerScored { correct, asked } => {
+                format!("Acertaste {correct} de {asked}.")
+            }
+            Prompt::CardsThatWentBy { cards } => format!("Las cartas que pasaron: {cards}"),
+            Prompt::NumberOfPlayers => "Número de jugadores:".to_string(),
+            Prompt::TooFewPlayers => "Una partida necesita al menos 2 jugadores".to_string(),
+            Prompt::PlayerDescription => "Descripción del jugador (Intro si no tiene):".to_string(),
+            Prompt::NumberOfHumans => "Número de jugadores humanos (Intro para 1):".to_string(),
+            Prompt::NoHumans => "Tiene que haber al menos 1 jugador humano".to_string(),
+            Prompt::OnlyPlayers { most } => format!("Solo hay {most} jugadores"),
+            Prompt::YourName => "Escribe tu nombre:".to_string(),
+            Prompt::PlayerName { seat } => format!("Nombre del jugador {seat}:"),
+            Prompt::NameNeeded => "Todo el mundo necesita un nombre".to_string(),
+            Prompt::NameTaken { name } => format!("Ya hay un {name} en la mesa"),
+            Prompt::BookAndAuthor { book } => format!("Libro y autor ({book})"),
+            Prompt::BookTitle { book } => format!("Hoy el Bookclub Rummy va de {book}"),
+            Prompt::DiscardedRecap {
+                player,
+                retrieved,
+                card,
+                pile,
+            } => {
+                let taken = match retrieved {
+                    Some(retrieved) => format!("recogió el {retrieved}"),
+                    None => "robó una carta".to_string(),
+                };
+                let cards = if *pile == 1 { "carta" } else { "cartas" };
+                format!("{player} {taken} y descartó el {card} — en el descarte hay {pile} {cards}")
+            }
+            Prompt::PlayedRecap { player, score } => format!("{player} jugó su mano por {score}"),
+            Prompt::BestMeldNow { meld } => format!("Mejor combinación ahora: {meld}"),
+            Prompt::DrawImproves { percent, expected } => {
+                format!("Robar la mejora el {percent}% de las veces, a {expected} de media")
+            }
+            Prompt::TakingImproves { card, meld } => {
+                format!("Recoger el {card} la convierte en {meld}")
+            }
+            Prompt::TakingDoesNotImprove { card } => format!("Recoger el {card} no la mejora"),
+            Prompt::WorthKeeping => "Lo que vale guardar, de menos a más:".to_string(),
+            Prompt::CardWorth {
+                card,
+                value,
+                percent,
+                best,
+            } => {
+                let advice = if *best { "  ← mejor descartarla" } else { "" };
+                format!("  {card}  {value}  en una combinación el {percent}% de las veces{advice}")
+            }
+            Prompt::DiscardPileEmpty => "El descarte está vacío".to_string(),
+            Prompt::ChainPlayed {
+                player,
+                cards,
+                meld,
+            } => format!("{player} jugó      {cards}  {meld}"),
+            Prompt::ChainLaidOff {
+                player,
+                cards,
+                result,
+            } => format!("{player} colocó    {cards}  {result}"),
+            Prompt::DidNotBeatIt => "no la superó".to_string(),
+            Prompt::ChainWon {
+                player,
+                score,
+                cards,
+            } => format!("{player} ganó {score} con {cards}"),
+            Prompt::WonAfter {
+                player,
+                rounds: 1,
+                book,
+            } => format!("{player} ganó tras 1 ronda de {book}."),
+            Prompt::WonAfter {
+                player,
+                rounds,
+                book,
+            } => format!("{player} ganó tras {rounds} rondas de {book}."),
+            Prompt::HandOfTheGame {
+                player,
+                meld,
+                score,
+            } => format!("Mano de la partida: {meld} de {player} por {score}."),
+            Prompt::Points { name, score } => format!("{name} {score:>4} puntos"),
+            Prompt::WonOnLayoffs { times: 1 } => ", ganó colocando 1 vez".to_string(),
+            Prompt::WonOnLayoffs { times } => format!(", ganó colocando {times} veces"),
+            Prompt::Melds { melds } => format!("Combinaciones: {}", melds.join(", ")),
+            Prompt::WhatTheClubMadeOfIt => "Lo que opinó el club:".to_string(),
+            Prompt::FinalScores => "Puntuaciones finales:".to_string(),
+            Prompt::PressEnterToContinue => "Pulsa Intro para continuar...".to_string(),
+            Prompt::SummingUp => "Resumiendo la conversación...".to_string(),
+            Prompt::CouldNotSumUp { error } => {
+                format!("No se pudo resumir la conversación: {error}")
+            }
+            Prompt::TranscriptNotice { path } => {
+                format!("La partida entera, charla incluida, está en {path}")
+            }
+            Prompt::CouldNotAudit { error } => format!("No se pudo auditar la partida: {error}"),
+            Prompt::IntegrityReport { deals } => {
+                format!("Informe de integridad ({deals} manos repartidas):")
+            }
+            Prompt::ShuffleLooksFair => {
+                "Nada fuera de lo normal, el barajado parece justo.".to_string()
+            }
+            Prompt::DealtOrBetter {
+                player,
+                meld,
+                observed,
+                deals,
+                expected,
+                p_value,
+            } => format!(
+                "A {player} le tocó {meld} o mejor en {observed} de {deals} repartos, donde un barajado justo da {expected} de media (p = {p_value})."
+            ),
+            Prompt::ModeratorLostForWords { error } => {
+                format!("El moderador se ha quedado sin palabras: {error}")
+            }
+            Prompt::LostForWords { player, error } => {
+                format!("{player} se ha quedado sin palabras: {error}")
+            }
+            Prompt::NotInHand { card } => format!("No tienes el {card}"),
+            Prompt::SavedAtStartOfTurn => {
+                "La partida está guardada desde el comienzo de este turno, retómala con --resume."
+                    .to_string()
+            }
+            Prompt::CouldNotRecallSessions { error } => {
+                format!("No se pudieron recordar las sesiones anteriores: {error}")
+            }
+            Prompt::WithoutSound { error } => format!("Jugando sin sonido: {error}"),
+            Prompt::CouldNotRecord { error } => format!("No se pudo registrar la partida: {error}"),
+            Prompt::CouldNotArchive { error } => {
+                format!("No se pudo guardar la partida en el archivo: {error}")
+            }
+            Prompt::CouldNotRemember { error } => {
+                format!("No se pudo recordar esta sesión: {error}")
+            }
+            Prompt::CouldNotWriteOut { error } => {
+                format!("No se pudo escribir la partida: {error}")
+            }
+            Prompt::CouldNotConnect { url, error } => {
+                format!("No se pudo conectar con {url}: {error}")
+            }
+            Prompt::ConnectionClosed => "El servidor cerró la conexión".to_string(),
+            Prompt::NotStarted => "La partida aún no ha empezado.".to_string(),
+            Prompt::DrawPlayOrRetrieve => "¡Opción no válida! Escribe D, P o R.".to_string(),
+            Prompt::MayBeDeclared => {
+                "Espera, alguien puede declarar sobre ese descarte.".to_string()
+            }
+            Prompt::Advice(key) => format!("Pista: {}.", Prompt::Key(*key).spanish()),
+            Prompt::HostSaves => "Solo el anfitrión puede guardar una partida en red.".to_string(),
+            Prompt::WaitingForPlayers { players, seats } => format!(
+                "Esperando jugadores ({}/{seats}): {}",
+                players.len(),
+                players.join(", ")
+            ),
+            Prompt::Key(key) => match key {
+                Key::Draw => "Robar",
+                Key::Play => "Jugar",
+                Key::Retrieve => "Recoger",
+                Key::Hint => "Pista",
+                Key::Odds => "Probabilidades",
+                Key::Discards => "Descartes",
+            }
+            .to_string(),
+            Prompt::Choice(choices) => match choices.split_last() {
+                Some((last, rest)) if !rest.is_empty() => {
+                    format!("¿{} o {last}?", rest.join(", "))
+                }
+                Some((last, _)) => format!("¿{last}?"),
+                None => String::new(),
+            },
+        }
+    }
+}
+
+/// Everyone's name and score, like "Ada 40, Lee 12".
+fn scores_list(scores: &[(String, usize)]) -> String {
+    let scores: Vec<String> = scores
+        .iter()
+        .map(|(name, score)| format!("{name} {score}"))
+        .collect();
+    scores.join(", ")
+}
+
+impl fmt::Display for Prompt {
+    /// Writes the prompt in the installed locale.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text(locale()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_the_same_keys_in_every_locale() {
+        let played = Message::HandPlayed { score: 50 };
+        assert_eq!(
+            played.text(Locale::En),
+            "played their hand for 50 points, time to layoff:"
+        );
+        assert_eq!(
+            played.text(Locale::Es),
+            "jugó su mano por 50 puntos, a colocar cartas:"
+        );
+
+        let choice = Prompt::Choice(vec!["Robar (D)".to_string(), "Jugar (P)".to_string()]);
+        assert_eq!(choice.text(Locale::Es), "¿Robar (D) o Jugar (P)?");
+        assert_eq!(Prompt::Key(Key::Hint).text(Locale::Es), "Pista");
+        assert_eq!(
+            Prompt::NotSaved { quitting: true }.text(Locale::Es),
+            "Esta partida no se guarda, así que se acabó."
+        );
+        let played = Prompt::AiPlayed {
+            player: "Bram".to_string(),
+            score: 12,
+            knocked: true,
+        };
+        assert_eq!(played.text(Locale::En), "Bram knocked with 12 deadwood");
+        assert_eq!(
+            played.text(Locale::Es),
+            "Bram tocó con 12 de cartas sueltas"
+        );
+        assert_eq!(
+            Prompt::ConfirmOrUndo.text(Locale::Es),
+            "Se esperaba C (confirmar) o U (deshacer)."
+        );
+        assert_eq!(
+            Prompt::PagesBack { pages: 2 }.text(Locale::Es),
+            "— 2 páginas atrás, AvPág e Intro para lo último —"
+        );
+        assert_eq!(Prompt::Scoreboard.text(Locale::Es), "Marcador:");
+        let recap = Prompt::DiscardedRecap {
+            player: "Ada".to_string(),
+            retrieved: None,
+            card: "7♥".to_string(),
+            pile: 1,
+        };
+        assert_eq!(
+            recap.text(Locale::En),
+            "Ada drew a card and discarded the 7♥ — discard pile now 1 card"
+        );
+        assert_eq!(
+            recap.text(Locale::Es),
+            "Ada robó una carta y descartó el 7♥ — en el descarte hay 1 carta"
+        );
+        assert_eq!(
+            Prompt::Advice(Key::Retrieve).text(Locale::Es),
+            "Pista: Recoger."
+        );
+
+        // Yes in the table's language, and in English wherever it's played
+        assert!(Locale::Es.is_yes(" Sí"));
+        assert!(Locale::Es.is_yes("y"));
+        assert!(!Locale::En.is_yes("s"));
+
+        // Words from elsewhere are left as they are
+        let said = Message::Said {
+            text: "Brontë bonus!".to_string(),
+        };
+        assert_eq!(said.text(Locale::Es), "Brontë bonus!");
+
+        assert_eq!(" ES".parse::<Locale>(), Ok(Locale::Es));
+        assert!("fr".parse::<Locale>().is_err());
+    }
+}
@@ -1,10 +1,33 @@
 pub mod analysis;
+pub mod archive;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod bank;
 pub mod card;
+pub mod config;
+pub mod dialogue;
 pub mod display;
+pub mod engine;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod frontend;
 pub mod game;
+pub mod i18n;
+pub mod net;
+pub mod quiz;
+pub mod rng;
+pub mod save;
 pub mod scoring;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod stats;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use analysis::*;
 pub use card::*;
+pub use engine::*;
+pub use error::*;
 pub use game::*;
 pub use scoring::*;
@@ -1,657 +1,1666 @@
-use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, OpenOptions};
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use rand::Rng;
 use rand::seq::SliceRandom;
-use regex::Regex;
 use terminal_size::{Width, terminal_size};
+use tracing_subscriber::fmt::format::FmtSpan;
 
-use rummy::{analysis::*, card::*, game::*};
+use rummy::analysis::tournament::{Entrant, TournamentConfig, run_tournament};
+use rummy::analysis::{
+    AUDIT_SIMULATIONS, AnalysisConfig, Auditor, InformationSet, MatchContext, Node,
+    TemperamentPolicy, evaluate_hand_deepening,
+};
+use rummy::archive::{Archive, MatchRecord, last_match_path};
+use rummy::bank::{Bank, DEFAULT_ANTE, Stakes};
+use rummy::config::{Config, Key, KeyBindings, ai_time_budget};
+use rummy::dialogue::discussion::{MODERATOR, Moderator, offline_recap, recap_prompt};
+use rummy::dialogue::memory::default_memory_path;
+use rummy::dialogue::provider::{Reply, RetryPolicy, Retrying};
+use rummy::dialogue::speech::{self, Narrator};
+use rummy::dialogue::{
+    DialogueProvider, Lineup, Memory, Profiles, TableTalkLevel, canned_line, normalize_dialogue,
+    strip_speaker_prefix, strip_think_blocks,
+};
+use rummy::display::celebration::{self, CelebrationKind};
+use rummy::display::input::Input;
+use rummy::display::layout::centered;
+use rummy::display::prompt::{Cards, answer, prompt_or, prompt_until};
+use rummy::display::terminal::{
+    ColoredName, Said, Screen, Scroll, TableFrame, TerminalGuard, action_line, chips_panel,
+    clear_screen, colored_book_title, dimmed, discard_panel, exit_on_signal, find_color,
+    highlighted, layoff_chain, meld_breakdown, odds_panel, recap_line, restore_on_panic,
+    restore_terminal, score_sheet_panel, wants_menu,
+};
+use rummy::display::theme::{self, Theme};
+use rummy::display::{normalize_name, pretty};
+use rummy::frontend::commands::{self, Command as ChatCommand};
+use rummy::game::events::{ACTION_LOG_LEN, ActionLog, GameEvent, Scoreboard, TurnRecap};
+use rummy::game::rules::{GameRules, Tiebreak, Variant};
+use rummy::i18n::{self, Locale, Message, Prompt};
+use rummy::net::client;
+use rummy::net::protocol::{self, ReplayWriter};
+use rummy::net::server::{self, ServerConfig};
+use rummy::quiz::{QUIZ_POINTS, QUIZ_QUESTIONS, parse_quiz, quiz_prompt};
+use rummy::rng::{RngService, RngStream};
+use rummy::save::{
+    SavedGame, Transcript, TranscriptLine, default_save_path, default_transcript_path,
+    write_transcript,
+};
+use rummy::scoring::{best_meld, explain};
+#[cfg(feature = "scripting")]
+use rummy::scripting::HouseRules;
+use rummy::stats::{
+    ExportOptions, GameRecorder, GameResult, GameSummary, Pseudonymizer, StatsStore, lifetime_stats,
+};
+use rummy::{card::*, engine::*, error::RummyError, game::*};
 
 use awful_aj::{
     config::AwfulJadeConfig,
     template::{self},
 };
 
-#[derive(Debug, Clone)]
-struct LayOffResult {
-    player: Player,
-    cards_laid_off: Vec<Card>,
-    resulting_hand: Hand,
-    resulting_score: u64,
-    cards_used: usize,
-}
-
-// Create a wrapper to own the deck data
-struct DeckData {
-    draw_pile: VecDeque<Card>,
-    discard_pile: VecDeque<Card>,
-}
-
-struct ColoredName {
-    name: String,
-    color_code: String,
+struct GameState {
+    book: String,
+    engine: GameEngine,
+    player_colors: Vec<ColoredName>,
+    action_log: Arc<Mutex<ActionLog>>,
+    /// The last turn taken, recapped for the human over their prompt.
+    recap: Arc<Mutex<TurnRecap>>,
+    scoreboard: Arc<Mutex<Scoreboard>>,
+    /// Table talk so far, saved with the game.
+    transcript: Arc<Mutex<Transcript>>,
+    /// What was said about the book in earlier sessions.
+    memory: Memory,
+    /// The AI players' characters, if they came from a profiles file.
+    profiles: Profiles,
+    /// Where to save between turns, `None` to not bother.
+    save_path: Option<PathBuf>,
+    messages: Vec<String>,
+    /// `None` plays offline, with canned dialogue instead of the LLM.
+    dialogue: Option<Box<dyn DialogueProvider>>,
+    /// Puts a question about the book to the table every few rounds, if
+    /// the table asked for one.
+    moderator: Option<Arc<Mutex<Moderator>>>,
+    /// Everyone's chips, if the table's playing for them.
+    bank: Option<Arc<Mutex<Bank>>>,
+    /// How long to leave the table up after each turn.
+    pacing: Duration,
+    player_quotes: Vec<Said>,
+    player_dialogues: HashMap<String, String>,
+    /// The table as last drawn, to draw again when the terminal is resized.
+    screen: Screen,
+    /// Which human has the keyboard, in a hot-seat game.
+    seat: Option<String>,
+    /// Whether the odds overlay is up, toggled with A.
+    show_odds: bool,
+    /// Whether the discard pile is listed, toggled with V.
+    show_discards: bool,
+    /// How long a human has for each turn, `None` for as long as they like.
+    turn_timer: Option<Duration>,
+    /// When the human whose turn it is runs out of time.
+    turn_deadline: Option<Instant>,
+    /// What the humans press to take their turn.
+    keys: KeyBindings,
+    /// What the humans type, read from the first time they're asked.
+    input: Input,
+    /// How many times the AI players have filled a silence, to take turns at it.
+    chatter: usize,
+    /// How much of the game the AI players hear about when they talk.
+    table_talk_level: TableTalkLevel,
+    /// Whether a human's asked to join the conversation after every turn.
+    chat_prompt: bool,
 }
 
-impl ColoredName {
-    fn new(name: String, player_index: usize) -> Self {
-        // Colors that work well on both dark and light backgrounds
-        // Using the 256-color palette for better visibility
-        let safe_colors = [
-            "38;5;33",  // Blue
-            "38;5;127", // Purple
-            "38;5;166", // Orange
-            "38;5;28",  // Green
-            "38;5;124", // Red/Maroon
-            "38;5;94",  // Brown
-            "38;5;31",  // Teal
-            "38;5;130", // Dark Orange
-        ];
-
-        // Cycle through colors if more players than colors
-        let color = safe_colors[player_index % safe_colors.len()];
-
-        Self {
-            name,
-            color_code: color.to_string(),
-        }
-    }
-
-    fn colored(&self) -> String {
-        format!("\x1B[{}m{}\x1B[0m", self.color_code, self.name)
-    }
-
-    fn colored_padded(&self, width: usize) -> String {
-        // Account for ANSI codes when padding
-        let visible_len = self.name.len();
-        let padding = " ".repeat(width.saturating_sub(visible_len));
-        format!("\x1B[{}m{}{}\x1B[0m", self.color_code, self.name, padding)
-    }
+/// The LLM, through awful_aj's `bookclub_rummy` template.
+struct AwfulJade {
+    config: AwfulJadeConfig,
 }
 
-impl DeckData {
-    fn new(cards: Vec<Card>) -> Self {
-        Self {
-            draw_pile: cards.into_iter().collect(),
-            discard_pile: VecDeque::new(),
-        }
-    }
-
-    fn reshuffle(&mut self) {
-        // Keep the top card of discard pile
-        let top_card = self.discard_pile.pop_back();
-
-        // Move all other discard cards to draw pile
-        self.draw_pile.extend(self.discard_pile.drain(..));
-
-        // Shuffle the draw pile
-        let mut cards: Vec<Card> = self.draw_pile.drain(..).collect();
-        let mut rng = rand::rng();
-        cards.shuffle(&mut rng);
-        self.draw_pile = cards.into_iter().collect();
-
-        // Put the top card back
-        if let Some(card) = top_card {
-            self.discard_pile.push_back(card);
-        }
+impl DialogueProvider for AwfulJade {
+    fn ask(&self, prompt: String) -> Reply<'_> {
+        Box::pin(async move {
+            let template = template::load_template("bookclub_rummy")
+                .await
+                .map_err(|err| RummyError::Network(err.to_string()))?;
+
+            let asked = Instant::now();
+            let answer = awful_aj::api::ask(&self.config, prompt, &template, None, None)
+                .await
+                .map_err(|err| RummyError::Network(err.to_string()));
+            tracing::info!(
+                latency_ms = asked.elapsed().as_millis() as u64,
+                ok = answer.is_ok(),
+                "LLM answered"
+            );
+            answer
+        })
     }
 }
 
-struct GameState {
-    book: String,
-    players: RefCell<Vec<Player>>,
-    player_colors: Vec<ColoredName>,
-    deck: RefCell<DeckData>,
-    actions_log: RefCell<Vec<String>>,
-    messages: RefCell<Vec<String>>,
-    current_player_idx: RefCell<usize>,
-    aj_config: AwfulJadeConfig,
-    player_quotes: RefCell<Vec<String>>,
-    player_dialogues: RefCell<HashMap<String, String>>,
-}
+/// How long a human can keep the table waiting before an AI player fills
+/// the silence.
+const CHATTER_AFTER: Duration = Duration::from_secs(20);
+
+/// Pause after each turn so the table can be followed.
+const TURN_PACING: Duration = Duration::from_millis(1500);
+
+/// Demo games move along quicker, nobody is waiting to play.
+const DEMO_PACING: Duration = Duration::from_millis(400);
+
+/// How long the final scores of a demo game stay up before the next deal.
+const DEMO_RESTART_PAUSE: Duration = Duration::from_secs(8);
+
+const DEMO_BOOK: &str = "East of Eden by John Steinbeck";
+
+/// How many cards the memory trainer asks about.
+const PRACTICE_QUESTIONS: usize = 8;
+
+/// Who sits at the demo table.
+const DEMO_PLAYERS: [(&str, &str, PlayerType); 4] = [
+    (
+        "Ada",
+        "a retired librarian who has read everything twice",
+        PlayerType::Conservative,
+    ),
+    (
+        "Bram",
+        "a night shift nurse who reads between rounds",
+        PlayerType::Aggressive,
+    ),
+    (
+        "Cathy",
+        "a high school English teacher with strong opinions",
+        PlayerType::Balanced,
+    ),
+    (
+        "Lee",
+        "a philosophy student who quotes the book constantly",
+        PlayerType::Balanced,
+    ),
+];
 
 /// CLI arguments
 #[derive(Parser, Debug)]
 #[command(name = "bookclub_rummy")]
 #[command(about = "Talk about a book and play 5 Card Rummy", long_about = None)]
 struct Args {
-    /// Configuration file
-    #[arg(short, long)]
-    config: PathBuf,
+    /// awful_aj configuration file, for the LLM
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Settings file, rummy.toml in the working directory or
+    /// ~/.config/bookclub_rummy if not given
+    #[arg(long, global = true, value_name = "FILE")]
+    settings: Option<PathBuf>,
+
+    /// Loop AI-only games with canned dialogue, no configuration needed
+    #[arg(long)]
+    demo: bool,
+
+    /// Pick the last local game back up where it was saved
+    #[arg(long)]
+    resume: bool,
+
+    /// Seat the AI players from a TOML file of character profiles instead
+    /// of asking for them
+    #[arg(long, value_name = "FILE")]
+    players: Option<PathBuf>,
+
+    /// Who's playing, instead of asking: names, with a player type after
+    /// the AIs, e.g. "Me,Hemingbot:aggressive,Austenbot:conservative"
+    #[arg(long, value_name = "LINEUP")]
+    seats: Option<Lineup>,
+
+    /// Book and author under discussion, instead of asking
+    #[arg(long)]
+    book: Option<String>,
+
+    /// Score that wins the game [default: the variant's]
+    #[arg(long, value_name = "POINTS")]
+    target: Option<usize>,
+
+    /// Deck to deal from: standard, piquet, or how many decks and what to
+    /// strip, e.g. 2x, 2-6 or 2x,2-6,h [default: standard]
+    #[arg(long, global = true)]
+    deck: Option<DeckConfig>,
+
+    /// How the table decides who goes first: shuffle, high-card or cut
+    /// [default: high-card]
+    #[arg(long, global = true)]
+    turn_order: Option<TurnOrder>,
+
+    /// Game to play: bookclub (5 cards), seven-card or gin [default: bookclub]
+    #[arg(long, global = true)]
+    variant: Option<Variant>,
+
+    /// Language the table speaks: en or es [default: en]
+    #[arg(long, global = true)]
+    locale: Option<Locale>,
+
+    /// How a win's celebrated: angel, fireworks, minimal or random [default: angel]
+    #[arg(long, global = true)]
+    celebration: Option<CelebrationKind>,
+
+    /// Read the AI players' lines aloud: system for the computer's own voice,
+    /// or a command line that reads text on its input, {voice} standing for
+    /// the character's
+    #[arg(long, global = true, value_name = "BACKEND")]
+    tts: Option<String>,
+
+    /// How much the AI players bring the game into their talk: off, occasional
+    /// or lively [default: occasional]
+    #[arg(long, global = true)]
+    table_talk_level: Option<TableTalkLevel>,
+
+    /// Don't ask to join the conversation after every turn, /say something
+    /// at any prompt instead
+    #[arg(long, global = true)]
+    no_chat_prompt: bool,
+
+    /// Seed for the shuffles and everything else left to chance, to play the same game again
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// Check the shuffle after the game for deals too lucky, or unlucky, to be chance
+    #[arg(long)]
+    audit: bool,
+
+    /// Open with a quiz on the book, a couple of points for each right answer
+    #[arg(long)]
+    quiz: bool,
+
+    /// Sum up what everyone made of the book after the game, in the summary
+    #[arg(long)]
+    recap: bool,
+
+    /// Have a moderator put a question about the book to the table every
+    /// ROUNDS rounds, 3 if not given
+    #[arg(long, value_name = "ROUNDS", num_args = 0..=1)]
+    #[arg(default_missing_value_t = rummy::dialogue::discussion::DISCUSSION_EVERY)]
+    discussion: Option<usize>,
+
+    /// Play for chips, everyone buying in for BUY_IN, 200 if not given
+    #[arg(long, value_name = "BUY_IN", num_args = 0..=1)]
+    #[arg(default_missing_value_t = rummy::bank::BUY_IN_ANTES * DEFAULT_ANTE)]
+    chips: Option<u64>,
+
+    /// Chips everyone antes every deal when playing for chips [default: 10]
+    #[arg(long, value_name = "CHIPS", requires = "chips")]
+    ante: Option<u64>,
+
+    /// Chips that win the match when playing for chips, instead of playing
+    /// until someone's felted
+    #[arg(long, value_name = "CHIPS", requires = "chips")]
+    chip_target: Option<u64>,
+
+    /// Let anyone declare out of turn on a discard that completes a winning meld for them
+    #[arg(long, global = true)]
+    declare: bool,
+
+    /// Let the AI players' temper drift with the score: aggressive when
+    /// they're well behind, conservative when they're well ahead
+    #[arg(long, global = true)]
+    tilt: bool,
+
+    /// Play in partnerships, everyone with whoever sits across from them,
+    /// their scores pooled. Needs an even number of players, four or more
+    #[arg(long, global = true)]
+    teams: bool,
+
+    /// Cut the deck before every deal: random, or how many cards from the top
+    #[arg(long, global = true)]
+    cut: Option<CutPosition>,
+
+    /// Burn the top card face-down before every deal
+    #[arg(long, global = true)]
+    burn: bool,
+
+    /// Write what the engine, the AI and the LLM are up to to FILE, as JSON lines
+    #[arg(long, global = true, value_name = "FILE")]
+    log_file: Option<PathBuf>,
+
+    /// Write every event of a local game to FILE as it happens, a line of the
+    /// network protocol's JSON each
+    #[arg(long, global = true, value_name = "FILE")]
+    replay: Option<PathBuf>,
+
+    /// Draw the table in the colors of a TOML theme file
+    #[arg(long, global = true, value_name = "FILE")]
+    theme: Option<PathBuf>,
+
+    /// Most cards anyone can lay off onto a played hand at once [default: 3]
+    #[arg(long, global = true)]
+    max_layoff: Option<usize>,
+
+    /// Call a round as a dead hand once its draw pile runs out after this
+    /// many reshuffles: the best meld on the table wins it for half its score
+    #[arg(long, global = true, value_name = "RESHUFFLES")]
+    dead_hand_after: Option<usize>,
+
+    /// How a layoff that only ties the meld on the table is settled:
+    /// earliest (the meld holds), meld-rank (a pair of kings takes a pair of
+    /// twos) or high-card (meld rank, then the higher cards) [default: high-card]
+    #[arg(long, global = true, value_name = "RULE")]
+    tiebreak: Option<Tiebreak>,
+
+    /// Seconds a human has for each turn before it's played for them the
+    /// way the analysis recommends
+    #[arg(long, global = true, value_name = "SECS")]
+    turn_timer: Option<u64>,
+
+    /// Milliseconds an AI's turn may spend searching ahead, 0 for it to
+    /// judge its hands as they stand [default: as far as its node budget
+    /// goes, the same on any machine]
+    #[arg(long, global = true, value_name = "MS")]
+    ai_time_budget_ms: Option<u64>,
+
+    /// Deal yourself this hand every round, like "2s 3s 4s Kh Ah", to
+    /// practise on it
+    #[arg(long, global = true, value_name = "CARDS")]
+    preset_hand: Option<Cards>,
+
+    /// Stack every deal from the cards listed in FILE, top of the deck
+    /// first, with # starting a comment
+    #[arg(long, global = true, value_name = "FILE")]
+    stacked_deck: Option<PathBuf>,
+
+    /// Rhai script of house rules to play by
+    #[cfg(feature = "scripting")]
+    #[arg(long, global = true)]
+    house_rules: Option<PathBuf>,
+
+    /// Play a local game without the sound effects
+    #[cfg(feature = "audio")]
+    #[arg(long, global = true)]
+    mute: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-impl GameState {
-    fn clear_screen() {
-        print!("\x1B[2J\x1B[1;1H");
-        io::stdout().flush().unwrap();
-    }
+/// How every game dealt this run is set up.
+#[derive(Clone, Debug)]
+struct TableOptions {
+    /// How AI players typed in at the table play.
+    ai: PlayerType,
+    deck: DeckConfig,
+    turn_order: TurnOrder,
+    variant: Variant,
+    seed: Option<u64>,
+    audit: bool,
+    quiz: bool,
+    /// Whether the summary sums up the talk about the book.
+    recap: bool,
+    /// Rounds between discussion questions, `None` for no moderator.
+    discussion: Option<usize>,
+    /// What the table plays for, `None` to play for points alone.
+    stakes: Option<Stakes>,
+    declare: bool,
+    /// Whether the AI players' temper drifts with the score.
+    tilt: bool,
+    rituals: DealRituals,
+    rules: GameRules,
+    /// How long a human has for each turn, `None` for as long as they like.
+    turn_timer: Option<Duration>,
+    /// How far an AI's turn searches ahead.
+    ai_search: AiSearch,
+    /// Score that wins the game, `None` for the variant's.
+    target: Option<usize>,
+    /// The hand the human, or the first one, is dealt every round.
+    preset_hand: Option<Vec<Card>>,
+    /// Cards every deal's stacked with, listed in this file.
+    stacked_deck: Option<PathBuf>,
+    /// Where a local game writes its events, for replaying elsewhere.
+    replay: Option<PathBuf>,
+    /// How a human winner's celebrated.
+    celebration: CelebrationKind,
+    /// What reads the AI players' lines aloud, `None` for nothing.
+    tts: Option<String>,
+    table_talk_level: TableTalkLevel,
+    /// Whether a human's asked to join the conversation after every turn.
+    chat_prompt: bool,
+    #[cfg(feature = "scripting")]
+    house_rules: Option<PathBuf>,
+    #[cfg(feature = "audio")]
+    mute: bool,
+}
 
-    fn actions(&self) -> Vec<String> {
-        let mut revd = self.actions_log.borrow().clone();
-        revd.reverse();
-        revd
+impl TableOptions {
+    /// The table `args` ask for, `config` for anything they don't say.
+    fn new(args: &Args, config: &Config) -> Self {
+        Self {
+            ai: config.ai.clone(),
+            deck: args.deck.clone().unwrap_or_else(|| config.deck.clone()),
+            turn_order: args.turn_order.unwrap_or(config.turn_order),
+            variant: args.variant.unwrap_or(config.variant),
+            seed: args.seed,
+            audit: args.audit,
+            quiz: args.quiz,
+            recap: args.recap,
+            discussion: args.discussion,
+            stakes: args.chips.map(|buy_in| Stakes {
+                target: args.chip_target,
+                ..Stakes::new(buy_in, args.ante.unwrap_or(DEFAULT_ANTE))
+            }),
+            declare: args.declare || config.declare,
+            tilt: args.tilt,
+            rituals: DealRituals {
+                cut: args.cut.or(config.cut),
+                burn: args.burn || config.burn,
+            },
+            rules: GameRules {
+                max_layoff: args.max_layoff.unwrap_or(config.max_layoff),
+                dead_hand_after: args.dead_hand_after.or(config.dead_hand_after),
+                tiebreak: args.tiebreak.unwrap_or(config.tiebreak),
+                teams: args.teams || config.teams,
+                ..GameRules::default()
+            },
+            turn_timer: args
+                .turn_timer
+                .map(Duration::from_secs)
+                .or(config.turn_timer),
+            ai_search: args
+                .ai_time_budget_ms
+                .map_or(config.ai_search, ai_time_budget),
+            target: args.target,
+            preset_hand: args.preset_hand.clone().map(|Cards(cards)| cards),
+            stacked_deck: args.stacked_deck.clone(),
+            replay: args.replay.clone(),
+            celebration: args.celebration.unwrap_or(config.celebration),
+            tts: args.tts.clone().or_else(|| config.tts.clone()),
+            table_talk_level: args.table_talk_level.unwrap_or(config.table_talk_level),
+            chat_prompt: config.chat_prompt && !args.no_chat_prompt,
+            #[cfg(feature = "scripting")]
+            house_rules: args.house_rules.clone(),
+            #[cfg(feature = "audio")]
+            mute: args.mute,
+        }
     }
 
-    fn wrap_text(text: &str, line_width: usize, indent: usize) -> String {
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let mut lines = Vec::new();
-        let mut current_line = String::new();
+    /// The flags asked for that only a local game plays by, which a hosted
+    /// table would otherwise quietly go without.
+    fn local_only_flags(&self) -> Vec<&'static str> {
+        [
+            (self.tilt, "--tilt"),
+            (self.preset_hand.is_some(), "--preset-hand"),
+            (self.stacked_deck.is_some(), "--stacked-deck"),
+            (self.replay.is_some(), "--replay"),
+        ]
+        .into_iter()
+        .filter_map(|(asked, flag)| asked.then_some(flag))
+        .collect()
+    }
 
-        for word in words {
-            if !current_line.is_empty() && current_line.len() + word.len() + 1 > line_width {
-                lines.push(current_line.clone());
-                current_line.clear();
+    /// Seeds `engine`, sets out the declarations rule, the table's rules and
+    /// the dealer's rituals and hooks the house rules, if there are any, up to a game
+    /// about `book`. Called before the first deal.
+    #[cfg_attr(not(feature = "scripting"), allow(unused_variables))]
+    fn prepare(&self, engine: &mut GameEngine, book: &str) -> Result<(), RummyError> {
+        if let Some(seed) = self.seed {
+            engine.set_seed(seed);
+        }
+        engine.allow_declarations(self.declare);
+        engine.set_ai_search(self.ai_search);
+        if self.tilt {
+            engine.set_temperament_policy(TemperamentPolicy::default());
+        }
+        engine.set_deal_rituals(self.rituals);
+        engine.set_rules(self.rules);
+        if self.rules.teams && engine.partner(0).is_none() {
+            return Err(RummyError::Config(format!(
+                "Teams need an even number of players, four or more, not {}",
+                engine.players.len()
+            )));
+        }
+        if let Some(target) = self.target {
+            engine.target_score = target;
+        }
+        if self.preset_hand.is_some() || self.stacked_deck.is_some() {
+            let mut plan = DealPlan::default();
+            if let Some(hand) = &self.preset_hand {
+                let seat = engine
+                    .players
+                    .iter()
+                    .find(|p| p.player_type.is_none())
+                    .unwrap_or(&engine.players[0]);
+                plan.hands.push((seat.name.clone(), hand.clone()));
             }
-
-            if !current_line.is_empty() {
-                current_line.push(' ');
+            if let Some(path) = &self.stacked_deck {
+                plan.stack = load_stacked_deck(path)?;
             }
-            current_line.push_str(word);
+            engine.set_deal_plan(plan);
         }
 
-        if !current_line.is_empty() {
-            lines.push(current_line);
+        #[cfg(feature = "scripting")]
+        if let Some(path) = &self.house_rules {
+            engine.set_house_rules(Arc::new(HouseRules::load(path, book)?));
         }
 
-        let indent_str = " ".repeat(indent);
-        lines.join(&format!("\n{indent_str}"))
+        Ok(())
     }
+}
 
-    fn get_player_color(&self, player_name: &str) -> Option<&ColoredName> {
-        self.player_colors.iter().find(|cn| cn.name == player_name)
-    }
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Play at this terminal against AI players (the default)
+    Local,
+    /// Host a game that bookclub members join from their own terminals
+    Server {
+        /// Address to listen for WebSocket connections on
+        #[arg(long, default_value = "0.0.0.0:7878")]
+        bind: String,
+        /// Number of human players to wait for before dealing
+        #[arg(long, default_value_t = 2)]
+        seats: usize,
+        /// Name of an AI player to seat at the table (repeat for more)
+        #[arg(long = "bot")]
+        bots: Vec<String>,
+        /// Book and author under discussion
+        #[arg(long, default_value = "East of Eden by John Steinbeck")]
+        book: String,
+    },
+    /// Take a seat at a game someone else is hosting
+    Join {
+        /// WebSocket address of the host, e.g. ws://192.168.1.20:7878
+        url: String,
+        /// Your name at the table
+        #[arg(long)]
+        name: String,
+    },
+    /// Train your card memory: watch a round's discards, then say which went by
+    Practice {
+        /// How many discards to watch
+        #[arg(long, default_value_t = 12)]
+        discards: usize,
+        /// How long each card stays up, in milliseconds
+        #[arg(long, default_value_t = 1200)]
+        flash_ms: u64,
+    },
+    /// Study a bookclub hand: its odds, what each card is worth and what each AI would do
+    Analyze {
+        /// Your hand, like "2s 3s 4s Kh Ah"
+        #[arg(long)]
+        hand: String,
+        /// The top discard, analysed as if you'd picked it up
+        #[arg(long)]
+        discard: Option<String>,
+        /// Cards you've seen go by, which can't be drawn
+        #[arg(long, default_value = "")]
+        seen: String,
+        /// Write the search tree to this file in Graphviz's DOT language
+        #[arg(long, value_name = "FILE")]
+        dump_tree: Option<PathBuf>,
+        /// Search a level deeper at a time, for at most this many
+        /// milliseconds, rather than going all the way down
+        #[arg(long, value_name = "MS")]
+        time_budget_ms: Option<u64>,
+    },
+    /// Play AI strategies against each other, round robin, and rate them
+    Tournament {
+        /// Strategies to enter: conservative, aggressive, balanced, ensemble,
+        /// monte-carlo or random
+        #[arg(required = true, num_args = 2..)]
+        strategies: Vec<String>,
+        /// Games each pair of strategies plays
+        #[arg(long, default_value_t = 10)]
+        games: usize,
+        /// Write the standings to this CSV file too
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Show lifetime win rates and favorite melds from every recorded game
+    Stats {
+        /// Show pseudonyms salted with this instead of names, for sharing
+        #[arg(long, value_name = "SALT")]
+        pseudonymize: Option<String>,
+    },
+    /// File the last game played here away in an archive, e.g. one for the night
+    Archive {
+        /// Archive to add the game to, created if it isn't there yet
+        file: PathBuf,
+    },
+    /// List the games in an archive, or go over one of them in full
+    Inspect {
+        /// Archive to read
+        file: PathBuf,
+        /// Which game to go over, counting from 1
+        #[arg(long)]
+        game: Option<usize>,
+    },
+    /// Write the JSON schemas of the network protocol, snapshots and replays
+    Schema {
+        /// Directory to write a NAME.schema.json for each into, created if
+        /// it isn't there yet
+        dir: PathBuf,
+    },
+}
 
-    fn display_dialogues(&self) {
-        const MAX_LINES: usize = 11; // size of dialogue field
+impl GameState {
+    /// Hooks the action log, recap, scoreboard and transcript up to
+    /// `engine`'s events.
+    /// A restored game's `transcript` is put back on screen and is what the
+    /// players carry the conversation on from.
+    fn new(
+        book: String,
+        mut engine: GameEngine,
+        transcript: Transcript,
+        dialogue: Option<Box<dyn DialogueProvider>>,
+        pacing: Duration,
+    ) -> Self {
+        // Create colored names for each player
+        let player_colors: Vec<ColoredName> = engine
+            .players
+            .iter()
+            .enumerate()
+            .map(|(idx, player)| ColoredName::new(player.name.clone(), idx))
+            .collect();
 
-        let log = self.player_quotes.borrow();
-        let start = log.len().saturating_sub(MAX_LINES);
-        let recent = &log[start..];
+        let player_quotes = transcript
+            .lines()
+            .map(|line| Said {
+                speaker: line.player.clone(),
+                text: line.text.clone(),
+            })
+            .collect();
+        let player_dialogues = transcript.last_lines();
 
-        // pad blank lines at top if not enough
-        for _ in 0..(MAX_LINES - recent.len()) {
-            println!();
-        }
+        let action_log = Arc::new(Mutex::new(ActionLog::default()));
+        let recap = Arc::new(Mutex::new(TurnRecap::default()));
+        let scoreboard = Arc::new(Mutex::new(Scoreboard::from_players(&engine.players)));
+        let transcript = Arc::new(Mutex::new(transcript));
+        engine.subscribe(action_log.clone());
+        engine.subscribe(recap.clone());
+        engine.subscribe(scoreboard.clone());
+        engine.subscribe(transcript.clone());
 
-        // print the recent lines (already wrapped/indented)
-        for line in recent {
-            println!("{line}");
+        Self {
+            book,
+            engine,
+            player_colors,
+            action_log,
+            recap,
+            scoreboard,
+            transcript,
+            memory: Memory::default(),
+            profiles: Profiles::default(),
+            save_path: None,
+            messages: Vec::new(),
+            dialogue,
+            moderator: None,
+            bank: None,
+            pacing,
+            player_quotes,
+            player_dialogues,
+            screen: Screen::default(),
+            seat: None,
+            show_odds: false,
+            show_discards: false,
+            turn_timer: None,
+            turn_deadline: None,
+            keys: KeyBindings::default(),
+            input: Input::default(),
+            chatter: 0,
+            table_talk_level: TableTalkLevel::default(),
+            chat_prompt: true,
         }
     }
 
-    fn push_dialogue(&self, player: &Player, dialogue: &str) {
-        // Visible layout constants
-        const NAME_COLS: usize = 20; // matches your padded name field
-        const SAYS: &str = " says: ";
-        const PREFIX_VIS_COLS: usize = NAME_COLS + SAYS.len();
-        const CONTENT_COLS: usize = 75; // width of the dialogue text area
-
-        // Get the colored name, already padded to NAME_COLS visible columns
-        let colored = self.get_player_color(&player.name).unwrap();
-        let name_field_colored = colored.colored_padded(NAME_COLS);
-
-        // 1) Wrap the *raw* dialogue to the content width with NO indent
-        //    (avoid double-indenting)
-        let wrapped = Self::wrap_text(dialogue, CONTENT_COLS, 0);
-
-        // 2) Emit the first line with the colored prefix
-        let mut lines = Vec::new();
-        let mut iter = wrapped.lines();
-        if let Some(first) = iter.next() {
-            lines.push(format!(
-                "{}{}{}",
-                name_field_colored,
-                SAYS,
-                Self::colorize_text(first, &colored.color_code)
-            ));
-        }
+    /// Plays out how the table decides who goes first, one card at a time,
+    /// then deals the first round.
+    async fn decide_turn_order(&mut self, turn_order: TurnOrder) -> Result<(), RummyError> {
+        let ceremony = self.engine.decide_turn_order(turn_order)?;
+
+        self.screen.forget();
+        clear_screen();
+        println!("{}\n", colored_book_title(&self.book));
+        println!("{}\n", Prompt::DecidingTurnOrder);
+
+        for event in &ceremony {
+            let name = self
+                .get_player_color(event.player())
+                .map(ColoredName::colored)
+                .unwrap_or_else(|| event.player().to_string());
+
+            match event {
+                GameEvent::SeatCardDrawn { card, .. } => {
+                    print!("{} ", Prompt::DrawsForSeat { player: name });
+                    io::stdout().flush().unwrap();
+                    tokio::time::sleep(self.pacing).await;
+                    println!("{}", pretty(card));
+                }
+                GameEvent::DeckCut { card, .. } => {
+                    print!("{} ", Prompt::DealsAndCuts { player: name });
+                    io::stdout().flush().unwrap();
+                    tokio::time::sleep(self.pacing).await;
+                    println!("{}", pretty(card));
+                    let count = card.rank.to_u64().unwrap_or_default();
+                    println!("{}", Prompt::CountingFromDealer { count });
+                }
+                GameEvent::TurnOrderDecided { order, .. } => {
+                    tokio::time::sleep(self.pacing).await;
+                    println!("\n{}", Prompt::GoesFirst { player: name });
 
-        // 3) Emit continuation lines: indent exactly to the *visible* prefix column
-        for cont in iter {
-            lines.push(format!(
-                "{:width$}{}",
-                "",
-                Self::colorize_text(cont, &colored.color_code),
-                width = PREFIX_VIS_COLS
-            ));
+                    let order: Vec<String> = order
+                        .iter()
+                        .map(|player| match self.get_player_color(player) {
+                            Some(colored) => colored.colored(),
+                            None => player.clone(),
+                        })
+                        .collect();
+                    println!("{}", Prompt::PlayOrder { order });
+                }
+                _ => {}
+            }
         }
 
-        self.player_quotes.borrow_mut().extend(lines);
+        tokio::time::sleep(self.pacing * 2).await;
+        Ok(())
     }
 
-    async fn display(&self, human_player: &Player, prompt: &str) {
-        Self::clear_screen();
+    /// Saves the game between turns, if it's being saved.
+    fn autosave(&mut self) {
+        if let Err(err) = self.save() {
+            self.add_message(
+                Prompt::CouldNotSave {
+                    error: err.to_string(),
+                }
+                .to_string(),
+            );
+        }
+    }
 
-        println!("{}\n", self.colored_book_title());
+    /// Saves the game to pick back up later, and says where. `None` if this
+    /// game isn't saved. Only a turn that hasn't started can be.
+    fn save(&self) -> Result<Option<&Path>, RummyError> {
+        let Some(path) = &self.save_path else {
+            return Ok(None);
+        };
 
-        self.display_dialogues();
-        println!();
+        let transcript = self.transcript.lock().unwrap();
+        SavedGame::new(&self.book, &self.engine, &transcript)?.save(path)?;
+        Ok(Some(path))
+    }
 
-        let deck = self.deck.borrow();
-        if let Some(top_card) = deck.discard_pile.back() {
-            println!("[{top_card}] [⌧]");
-        } else {
-            println!("[--] [⌧]");
+    fn print_final_scores(&self) {
+        println!("\n\n{}", Prompt::FinalScores);
+        for (name, score) in self.scoreboard.lock().unwrap().scores() {
+            if let Some(colored_name) = self.get_player_color(name) {
+                println!("{}: {}", colored_name.colored(), score);
+            } else {
+                println!("{name}: {score}");
+            }
         }
+    }
 
-        print!(" ");
-        for card in &human_player.hand.cards {
-            print!("{card} ");
+    /// Shows how the game went, and where it was written out to, and waits
+    /// for Enter before the celebrations.
+    async fn show_summary(&self, summary: &GameSummary, transcript: Option<&Path>) {
+        clear_screen();
+        print!("{summary}");
+        if let Some(path) = transcript {
+            println!("{}", transcript_notice(path));
         }
-        println!("\n");
-
-        print!("{prompt} ");
-        io::stdout().flush().unwrap();
+        println!("\n{}", dimmed(&Prompt::PressEnterToContinue.to_string()));
+        self.input.line().await;
+    }
 
-        print!("\x1B[s");
+    /// Prints the seed the game was dealt from and every deal's hash, so the
+    /// deals can be checked by playing the seed again.
+    fn print_disclosure(&self) {
+        print!("\n{}", self.engine.disclosure());
+    }
 
-        if !self.actions_log.borrow().is_empty() {
-            println!("\n\nActions:");
-            let start = self.actions_log.borrow().len().saturating_sub(6);
-            for action in &self.actions()[start..] {
-                println!("{action}");
+    /// Prints what `auditor` makes of every deal this game.
+    fn print_audit_report(&mut self, auditor: &Auditor) {
+        let mut rng = self.engine.rng().fork(RngStream::Effects);
+        let report = match auditor.report(AUDIT_SIMULATIONS, &mut rng) {
+            Ok(report) => report,
+            Err(err) => {
+                let error = err.to_string();
+                eprintln!("{}", Prompt::CouldNotAudit { error });
+                return;
             }
-        }
+        };
 
-        // Color the names in the Scoreboard
-        println!("\n\nScoreboard:");
-        for player in self.players.borrow().iter() {
-            if let Some(colored_name) = self.get_player_color(&player.name) {
-                println!("{}: {}", colored_name.colored(), player.score);
-            } else {
-                println!("{}: {}", player.name, player.score);
-            }
+        let deals = report.deals;
+        println!("\n{}", Prompt::IntegrityReport { deals });
+        let mut flagged = report.flagged().peekable();
+        if flagged.peek().is_none() {
+            println!("{}", Prompt::ShuffleLooksFair);
         }
-
-        if !self.messages.borrow().is_empty() {
-            println!("\nMessages:");
-            println!("{}", self.messages.borrow().last().unwrap());
+        for finding in flagged {
+            let name = match self.get_player_color(&finding.player) {
+                Some(colored_name) => colored_name.colored(),
+                None => finding.player.clone(),
+            };
+            let finding = Prompt::DealtOrBetter {
+                player: name,
+                meld: finding.meld.to_string(),
+                observed: finding.observed,
+                deals: finding.deals,
+                expected: format!("{:.1}", finding.expected),
+                p_value: format!("{:.4}", finding.p_value),
+            };
+            println!("{finding}");
         }
+    }
 
-        print!("\x1B[u");
-        io::stdout().flush().unwrap();
+    fn actions(&self) -> Vec<String> {
+        let mut revd: Vec<String> = self
+            .action_log
+            .lock()
+            .unwrap()
+            .actions()
+            .iter()
+            .map(|action| action_line(action, &self.player_colors))
+            .collect();
+        revd.reverse();
+        revd
     }
 
-    async fn display_layoff(&self, human_player: &Player, hand_player: &Player, prompt: &str) {
-        Self::clear_screen();
+    fn get_player_color(&self, player_name: &str) -> Option<&ColoredName> {
+        find_color(&self.player_colors, player_name)
+    }
 
-        println!("{}\n", self.colored_book_title());
+    fn push_dialogue(&mut self, speaker: &str, dialogue: &str) {
+        self.engine.emit(GameEvent::Dialogue {
+            player: speaker.to_string(),
+            text: dialogue.to_string(),
+        });
 
-        self.display_dialogues();
+        self.player_quotes.push(Said {
+            speaker: speaker.to_string(),
+            text: dialogue.to_string(),
+        });
+    }
 
-        println!();
+    /// Has the keyboard passed to `human_player` if another human had it,
+    /// behind a screen that hides the last hand shown. Nobody has to pass it
+    /// when there's only one human at the table.
+    async fn hand_to(&mut self, human_player: &Player) {
+        let previous = self.seat.replace(human_player.name.clone());
+        let humans = self
+            .engine
+            .players
+            .iter()
+            .filter(|p| p.player_type.is_none())
+            .count();
+        if humans < 2 || previous.as_deref() == Some(human_player.name.as_str()) {
+            return;
+        }
 
-        println!("[--] [⌧]");
+        self.screen.forget();
+        clear_screen();
+        let name = self
+            .get_player_color(&human_player.name)
+            .map(ColoredName::colored)
+            .unwrap_or_else(|| human_player.name.clone());
+        println!("{}\n", colored_book_title(&self.book));
+        println!("{}", Prompt::PassTheKeyboard { player: name });
+        self.input.line().await;
+    }
 
-        // Color the player names in the hand display
-        print!(" ");
-        if let Some(colored_name) = self.get_player_color(&hand_player.name) {
-            print!("{} hand: ", colored_name.colored_padded(20));
-        } else {
-            let name = format!("{}'s", hand_player.name);
-            print!("{name:20} hand: ");
-        }
-        for card in &hand_player.hand.cards {
-            print!("{card} ");
-        }
-        println!();
+    /// Whose seat the table is shown from between turns: whoever has the
+    /// keyboard, the first human until anyone's been passed it, or
+    /// `current_idx`, the seat that just moved, when nobody human is playing.
+    fn viewer(&self, current_idx: usize) -> Player {
+        let engine = &self.engine;
+        let seat = &self.seat;
+        engine
+            .players
+            .iter()
+            .find(|p| seat.as_deref() == Some(p.name.as_str()))
+            .or_else(|| engine.players.iter().find(|p| p.player_type.is_none()))
+            .unwrap_or(&engine.players[current_idx])
+            .clone()
+    }
 
-        print!(" ");
-        if let Some(colored_name) = self.get_player_color(&human_player.name) {
-            print!("{} hand: ", colored_name.colored_padded(20));
-        } else {
-            let name = format!("{}'s", human_player.name);
-            print!("{name:20} hand: ");
-        }
-        for card in &human_player.hand.cards {
-            print!("{card} ");
-        }
-        println!("\n");
+    async fn display(&self, human_player: &Player, prompt: &str) {
+        self.render(human_player, None, prompt);
+    }
 
-        print!("{prompt} ");
-        io::stdout().flush().unwrap();
+    /// Shows `human_player` the table with `prompt` and waits for their
+    /// answer. PgUp and PgDn scroll back through the talk and actions in the
+    /// meantime, Esc brings up the pause menu and `/say` joins the
+    /// conversation. The AI players fill any long silence, and the table's
+    /// drawn again with what they said.
+    async fn ask(&mut self, human_player: &Player, prompt: &str) -> String {
+        self.ask_until(human_player, prompt, false)
+            .await
+            .unwrap_or_default()
+    }
 
-        print!("\x1B[s");
+    /// Like `ask`, with the time the turn has left on the prompt. `None` if
+    /// it runs out first.
+    async fn ask_in_time(&mut self, human_player: &Player, prompt: &str) -> Option<String> {
+        self.ask_until(human_player, prompt, true).await
+    }
+
+    async fn ask_until(
+        &mut self,
+        human_player: &Player,
+        prompt: &str,
+        timed: bool,
+    ) -> Option<String> {
+        // Read afresh each time, the pause menu stops the clock
+        let deadline = |state: &Self| state.turn_deadline.filter(|_| timed);
 
-        if !self.actions_log.borrow().is_empty() {
-            println!("\n\nActions:");
-            let start = self.actions_log.borrow().len().saturating_sub(6);
-            for action in &self.actions()[start..] {
-                println!("{action}");
+        self.display(human_player, &time_left(prompt, deadline(self)))
+            .await;
+        loop {
+            let wait = deadline(self).map_or(CHATTER_AFTER, |deadline| {
+                CHATTER_AFTER.min(deadline.saturating_duration_since(Instant::now()))
+            });
+            match self.input.line_within(wait).await {
+                Some(line) if wants_menu(&line) => {
+                    self.pause_menu(human_player).await;
+                    self.display(human_player, &time_left(prompt, deadline(self)))
+                        .await;
+                }
+                Some(line) => match (commands::scroll(&line), commands::parse(&line)) {
+                    (Some(scroll), _) => self.screen.scroll(scroll),
+                    (None, Some(command)) => {
+                        self.command(human_player, command);
+                        self.display(human_player, &time_left(prompt, deadline(self)))
+                            .await;
+                    }
+                    (None, None) => return Some(line),
+                },
+                None if deadline(self).is_some_and(|deadline| Instant::now() >= deadline) => {
+                    return None;
+                }
+                None => {
+                    self.chatter().await;
+                    self.display(human_player, &time_left(prompt, deadline(self)))
+                        .await;
+                }
             }
         }
+    }
 
-        // Color the names in the Scoreboard
-        println!("\n\nScoreboard:");
-        for player in self.players.borrow().iter() {
-            if let Some(colored_name) = self.get_player_color(&player.name) {
-                println!("{}: {}", colored_name.colored(), player.score);
-            } else {
-                println!("{}: {}", player.name, player.score);
+    /// The Esc menu: carry on, save and quit, or quit. The turn's clock
+    /// stops while it's up.
+    async fn pause_menu(&mut self, human_player: &Player) {
+        let left = self
+            .turn_deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+        loop {
+            self.display(human_player, &Prompt::Paused.to_string())
+                .await;
+            let input = self.input.line().await;
+            match input.trim().to_lowercase().as_str() {
+                "r" | "resume" | "" => break,
+                _ if wants_menu(&input) => break,
+                "s" | "save" if *self.engine.phase() != TurnPhase::Choosing => {
+                    self.add_message(Prompt::FinishTurnToSave.to_string())
+                }
+                "s" | "save" => match self.save() {
+                    Ok(Some(path)) => quit(
+                        &Prompt::Saved {
+                            path: path.display().to_string(),
+                            quitting: true,
+                        }
+                        .to_string(),
+                    ),
+                    Ok(None) => quit(&Prompt::NotSaved { quitting: true }.to_string()),
+                    Err(err) => self.add_message(
+                        Prompt::CouldNotSave {
+                            error: err.to_string(),
+                        }
+                        .to_string(),
+                    ),
+                },
+                "q" | "quit" => quit(&Prompt::GameAbandoned.to_string()),
+                _ => self.add_message(Prompt::PauseChoices.to_string()),
             }
         }
 
-        if !self.messages.borrow().is_empty() {
-            println!("\nMessages:");
-            println!("{}", self.messages.borrow().last().unwrap());
+        self.clear_messages();
+        self.turn_deadline = left.map(|left| Instant::now() + left);
+    }
+
+    /// Starts the clock on a human's turn, if the table's playing to one.
+    fn start_turn_clock(&mut self) {
+        self.turn_deadline = self.turn_timer.map(|timer| Instant::now() + timer);
+    }
+
+    fn stop_turn_clock(&mut self) {
+        self.turn_deadline = None;
+    }
+
+    /// The next line typed, `None` if the turn runs out of time first.
+    async fn line_in_time(&self) -> Option<String> {
+        match self.turn_deadline {
+            Some(deadline) => {
+                self.input
+                    .line_within(deadline.saturating_duration_since(Instant::now()))
+                    .await
+            }
+            None => Some(self.input.line().await),
         }
+    }
 
-        print!("\x1B[u");
-        io::stdout().flush().unwrap();
+    /// Whether the human whose turn it is has run out of time.
+    fn out_of_time(&self) -> bool {
+        self.turn_deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
     }
 
-    async fn update_current_player_dialogue(&self) {
-        let current_idx = *self.current_player_idx.borrow();
-        let current_player = self.players.borrow()[current_idx].clone();
-        if current_player.player_type.is_some() {
-            let dialogue = self.get_player_dialogue(&current_player).await;
-            self.push_dialogue(&current_player, dialogue.trim());
-            self.player_dialogues
-                .borrow_mut()
-                .insert(current_player.name.clone(), dialogue.clone());
+    /// Plays what's left of `player`'s turn the way the analysis recommends,
+    /// now they've run out of time for it.
+    fn play_out_of_time(&mut self, player: &Player) {
+        let played = self.engine.auto_play();
+        match played {
+            Ok(()) => self.add_message(format!("{} {}", player.name, Message::RanOutOfTime)),
+            Err(err) => self.add_message(err.to_string()),
         }
     }
 
-    async fn display_updated_state(&self, human_player: &Player) {
-        Self::clear_screen();
+    /// Writes out the whole game's talk and actions, to read back over.
+    fn write_transcript(&self) -> Result<PathBuf, RummyError> {
+        let lines: Vec<TranscriptLine> = self
+            .player_quotes
+            .iter()
+            .map(|said| TranscriptLine {
+                player: said.speaker.clone(),
+                text: said.text.clone(),
+            })
+            .collect();
+        let actions = self.action_log.lock().unwrap().actions();
 
-        println!("{}\n", self.colored_book_title());
+        let path = default_transcript_path();
+        write_transcript(&path, &self.book, &lines, &actions)?;
+        Ok(path)
+    }
 
-        self.display_dialogues();
+    async fn display_layoff(&self, human_player: &Player, hand_player: &Player, prompt: &str) {
+        self.render(human_player, Some(hand_player), prompt);
+    }
 
-        println!();
+    fn render(&self, human_player: &Player, hand_player: Option<&Player>, prompt: &str) {
+        self.screen
+            .draw(self.frame(human_player, hand_player, Some(prompt)));
+    }
 
-        let deck = self.deck.borrow();
-        if let Some(top_card) = deck.discard_pile.back() {
-            println!("[{top_card}] [⌧]");
-        } else {
-            println!("[--] [⌧]");
+    fn frame(
+        &self,
+        human_player: &Player,
+        hand_player: Option<&Player>,
+        prompt: Option<&str>,
+    ) -> TableFrame {
+        TableFrame {
+            book: self.book.clone(),
+            dialogue: self.player_quotes.clone(),
+            top_discard: self.engine.top_discard().copied(),
+            player_name: human_player.name.clone(),
+            hand: human_player.hand.cards.clone(),
+            layoff: hand_player.map(|p| (p.name.clone(), p.hand.cards.clone())),
+            recap: self.recap_for(human_player),
+            prompt: prompt.map(str::to_string),
+            actions: self.actions(),
+            scores: self.scoreboard.lock().unwrap().scores().to_vec(),
+            sheet: score_sheet_panel(self.engine.score_sheet(), &self.player_colors),
+            chips: self
+                .bank
+                .as_ref()
+                .map(|bank| chips_panel(&bank.lock().unwrap(), &self.player_colors))
+                .unwrap_or_default(),
+            message: self.messages.last().cloned(),
+            reveal: self
+                .engine
+                .last_round()
+                .map(|summary| layoff_chain(summary, &self.player_colors))
+                .unwrap_or_default(),
+            odds: match hand_player {
+                None => self.odds_for(human_player),
+                Some(_) => Vec::new(),
+            },
+            discards: match hand_player {
+                None => self.discards_for(human_player),
+                Some(_) => Vec::new(),
+            },
+            colors: self.player_colors.clone(),
+            scroll: 0,
         }
+    }
 
-        print!(" ");
-        for card in &human_player.hand.cards {
-            print!("{card} ");
+    /// The odds overlay for `human_player`'s hand, if it's up and it's their
+    /// turn.
+    fn odds_for(&self, human_player: &Player) -> Vec<String> {
+        let engine = &self.engine;
+        if !self.show_odds || engine.current_player().name != human_player.name {
+            return Vec::new();
         }
-        println!();
 
-        if !self.actions_log.borrow().is_empty() {
-            println!("\nActions:");
-            let start = self.actions_log.borrow().len().saturating_sub(6);
-            for action in &self.actions()[start..] {
-                println!("{action}");
-            }
+        engine
+            .hand_odds()
+            .map(|odds| odds_panel(&odds))
+            .unwrap_or_default()
+    }
+
+    /// The discard pile for `human_player` to look through, if they've asked
+    /// to and it's their turn.
+    fn discards_for(&self, human_player: &Player) -> Vec<String> {
+        let engine = &self.engine;
+        if !self.show_discards || engine.current_player().name != human_player.name {
+            return Vec::new();
         }
 
-        tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
+        discard_panel(&engine.visible_discards())
     }
 
-    fn add_message(&self, msg: String) {
-        self.messages.borrow_mut().push(msg);
-        let len = self.messages.borrow().len();
-        if len > 1 {
-            self.messages.borrow_mut().drain(0..len - 1);
+    /// A line on the last turn, unless it was `human_player`'s own.
+    fn recap_for(&self, human_player: &Player) -> Option<String> {
+        let recap = self.recap.lock().unwrap();
+        let last = recap
+            .last()
+            .filter(|last| last.player() != human_player.name)?;
+        let discard_pile = self.engine.deck.discard_pile.len();
+        Some(recap_line(last, discard_pile, &self.player_colors))
+    }
+
+    async fn update_current_player_dialogue(&mut self) {
+        let current_player = self.engine.current_player().clone();
+        if current_player.player_type.is_some() {
+            self.speak(&current_player).await;
         }
     }
 
-    fn add_action(&self, player_name: &str, action: &str, card: Option<Card>) {
-        let colored_name = self
-            .player_colors
+    /// Has the next AI player, taking turns, say something while a human
+    /// makes up their mind.
+    async fn chatter(&mut self) {
+        let ai_players: Vec<Player> = self
+            .engine
+            .players
             .iter()
-            .find(|cn| cn.name == player_name)
-            .map(|cn| cn.colored_padded(20))
-            .unwrap_or_else(|| format!("{player_name:20}"));
+            .filter(|p| p.player_type.is_some())
+            .cloned()
+            .collect();
+        if ai_players.is_empty() {
+            return;
+        }
 
-        let color_code = self
-            .player_colors
-            .iter()
-            .find(|cn| cn.name == player_name)
-            .map(|cn| cn.color_code.clone())
-            .unwrap_or_else(|| "0".to_string());
+        let speaker = &ai_players[self.chatter % ai_players.len()];
+        self.chatter += 1;
+        self.speak(speaker).await;
+    }
 
-        // Check if action contains cards
-        let contains_cards = action.contains("♢")
-            || action.contains("♡")
-            || action.contains("♤")
-            || action.contains("♧");
-
-        let action_text = if contains_cards {
-            // For "played their hand X X X X X for Y points" format
-            if action.contains("played their hand") && action.contains(" for ") {
-                // Find the positions
-                let hand_start =
-                    action.find("played their hand").unwrap() + "played their hand".len();
-                let for_pos = action.rfind(" for ").unwrap();
-
-                // Split into parts
-                let prefix = &action[..hand_start]; // "played their hand"
-                let cards = &action[hand_start..for_pos]; // " 9♧ 7♧ Q♤ 9♤ 7♤"
-                let suffix = &action[for_pos..]; // " for 2 points. It's time to layoff."
-
-                // TODO cards is blank when the human player plays and an extra blank space shows after "for"
-                // Color the non-card parts
-                format!(
-                    "{colored_name} \x1B[{color_code}m{prefix}\x1B[0m{cards}\x1B[{color_code}m{suffix}\x1B[0m"
-                )
-            } else if action.contains("won this round") && action.contains("hand ") {
-                // Find the positions
-                let hand_start = action.find("won this round with a score of").unwrap()
-                    + "won this round with the score of 2 and the hand".len();
-
-                // Split into parts
-                let prefix = &action[..hand_start]; // "won this round with the score of and the hand"
-                let cards = &action[hand_start..]; // " 9♧ 7♧ Q♤ 9♤ 7♤"
-
-                // Color the nonjjj
-                format!("{colored_name} \x1B[{color_code}m{prefix}\x1B[0m{cards}")
-            } else {
-                // Other card-containing actions - just color the name
-                format!("{colored_name} {action}")
+    /// Has the AI `player` say their piece to the table.
+    async fn speak(&mut self, player: &Player) {
+        let dialogue = self.get_player_dialogue(player).await;
+        self.push_dialogue(&player.name, dialogue.trim());
+        self.player_dialogues.insert(player.name.clone(), dialogue);
+    }
+
+    /// Puts the moderator's next question to the table if one's due, for
+    /// everyone to answer in their next line. Offline there's nobody to
+    /// write it.
+    async fn moderate(&mut self) {
+        let (Some(moderator), Some(provider)) = (&self.moderator, &self.dialogue) else {
+            return;
+        };
+        if !moderator.lock().unwrap().is_due() {
+            return;
+        }
+
+        let prompt = moderator.lock().unwrap().prompt(&self.book);
+        let question = match provider.ask(prompt).await {
+            Ok(answer) => moderator.lock().unwrap().take_question(&answer),
+            Err(err) => {
+                let error = err.to_string();
+                self.add_message(Prompt::ModeratorLostForWords { error }.to_string());
+                None
             }
-        } else if let Some(card) = card {
-            // Single card action
-            format!("{colored_name} \x1B[{color_code}m{action}\x1B[0m {card}")
-        } else {
-            // No cards
-            format!("{colored_name} \x1B[{color_code}m{action}\x1B[0m")
         };
+        let Some(question) = question else {
+            return;
+        };
+
+        self.push_dialogue(MODERATOR, &question);
+        self.player_dialogues
+            .insert(MODERATOR.to_string(), question);
+    }
+
+    async fn display_updated_state(&self, human_player: &Player) {
+        self.screen.draw(self.frame(human_player, None, None));
 
-        self.actions_log.borrow_mut().push(action_text);
+        tokio::time::sleep(self.pacing).await;
+    }
 
-        if self.actions_log.borrow().len() > 6 {
-            self.actions_log.borrow_mut().remove(0);
+    fn add_message(&mut self, msg: String) {
+        self.messages.push(msg);
+        let len = self.messages.len();
+        if len > 1 {
+            self.messages.drain(0..len - 1);
         }
     }
 
-    async fn get_player_dialogue(&self, player: &Player) -> String {
-        let template = template::load_template("bookclub_rummy").await.unwrap();
+    /// What `player` says next, from the LLM. A canned line if there's no
+    /// LLM, or it couldn't answer.
+    async fn get_player_dialogue(&mut self, player: &Player) -> String {
+        let Some(provider) = &self.dialogue else {
+            return self.canned_dialogue(player);
+        };
 
         let mut previous_conversation = String::new();
-        for (name, quote) in self.player_dialogues.borrow().iter() {
+        for (name, quote) in self.player_dialogues.iter() {
             let line = format!("{name}: {quote}");
             previous_conversation = format!("{previous_conversation}\n{line}");
         }
 
         let book_and_author = &self.book;
         let name = &player.name;
-        let description_section = format!(": {}", &player.description);
+        let profile = self.profiles.get(name);
+        let length = profile.map_or("a single sentence", |profile| profile.verbosity.length());
+        let description_section = match profile.map(|profile| profile.prompt()) {
+            Some(tastes) if !tastes.is_empty() => format!(": {}. {tastes}", &player.description),
+            _ => format!(": {}", &player.description),
+        };
+        let feelings = self
+            .transcript
+            .lock()
+            .unwrap()
+            .relationships()
+            .prompt_for(name);
+        let feelings_section = if feelings.is_empty() {
+            String::new()
+        } else {
+            format!("\n\nLet how you feel about the other players color what you say. {feelings}")
+        };
+        let recent = self.action_log.lock().unwrap().recent(ACTION_LOG_LEN);
+        let table_section = self.table_talk_level.prompt(&recent);
+        let memories = self.memory.prompt_for(name, book_and_author);
+        let memory_section = if memories.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\n\nYou've discussed this book before, feel free to come back to it. {memories}"
+            )
+        };
         let question = format!(
-            "Here is the conversation about {book_and_author}\n{previous_conversation}\n\nPlease continue the roleplay by responding with a single sentence. Always end the sentence with an emoji representing your emotional state. Please ensure you are responding directly to another player's previous dialogue. You are playing the role of {name}{description_section}"
+            "Here is the conversation about {book_and_author}\n{previous_conversation}\n\nPlease continue the roleplay by responding with {length}. Always end the sentence with an emoji representing your emotional state. Please ensure you are responding directly to another player's previous dialogue. You are playing the role of {name}{description_section}{feelings_section}{memory_section}{table_section}"
         );
 
-        let answer = awful_aj::api::ask(&self.aj_config, question, &template, None, None)
-            .await
-            .unwrap();
-        let answer = self.strip_think_blocks(&answer);
-
-        let name_prompt = format!("{name}: ");
+        let answer = match provider.ask(question).await {
+            Ok(answer) => answer,
+            Err(err) => {
+                let lost = Prompt::LostForWords {
+                    player: name.to_string(),
+                    error: err.to_string(),
+                };
+                self.add_message(lost.to_string());
+                return self.canned_dialogue(player);
+            }
+        };
+        let answer = strip_think_blocks(&answer);
 
-        answer.replace(&name_prompt, "")
+        strip_speaker_prefix(&answer, name)
     }
 
-    fn strip_think_blocks(&self, s: &str) -> String {
-        // (?is) -> case-insensitive + dot matches newlines
-        // Allow spaces and optional attributes inside the opening tag
-        // and optional spaces inside the closing tag.
-        let re = Regex::new(r"(?is)<\s*think\b[^>]*>.*?<\s*/\s*think\s*>").unwrap();
-        let cleaned = re.replace_all(s, "");
-        // also collapse any excessive blank lines that may be left behind
-        let re_blank = Regex::new(r"\n{3,}").unwrap();
-        re_blank
-            .replace_all(cleaned.as_ref(), "\n\n")
-            .trim()
-            .to_string()
+    /// What everyone made of the book, summed up by the LLM from the last
+    /// thing each player said. Offline, or if it can't, just what they said.
+    /// `None` if nobody said anything.
+    async fn recap_discussion(&self) -> Option<String> {
+        let lines: Vec<(String, String)> = self
+            .engine
+            .players
+            .iter()
+            .filter_map(|player| {
+                let line = self.player_dialogues.get(&player.name)?;
+                Some((player.name.clone(), line.clone()))
+            })
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+        let Some(provider) = &self.dialogue else {
+            return Some(offline_recap(&lines));
+        };
+
+        println!("{}", highlighted(&Prompt::SummingUp.to_string()));
+        match provider.ask(recap_prompt(&self.book, &lines)).await {
+            Ok(answer) => {
+                let answer = strip_think_blocks(&answer);
+                let answer = answer.trim();
+                Some(if answer.is_empty() {
+                    offline_recap(&lines)
+                } else {
+                    answer.to_string()
+                })
+            }
+            Err(err) => {
+                let error = err.to_string();
+                eprintln!("{}", Prompt::CouldNotSumUp { error });
+                Some(offline_recap(&lines))
+            }
+        }
     }
 
-    fn clear_messages(&self) {
-        self.messages.borrow_mut().clear();
+    /// A canned line for `player`, for when there's no LLM to ask.
+    fn canned_dialogue(&mut self, player: &Player) -> String {
+        let previous = self.player_dialogues.get(&player.name).cloned();
+        canned_line(
+            previous.as_deref(),
+            self.engine.rng().stream(RngStream::Talk),
+        )
     }
 
-    fn prompt_for_layoff_cards(
-        &self,
-        human_player: &Player,
-        hand_player: &Player,
-    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Vec<Card>> + '_>> {
-        let human_player = human_player.clone();
-        let hand_player = hand_player.clone();
+    /// Quizzes every human at the table on the book before the first deal,
+    /// with questions from the LLM. A right answer is worth `QUIZ_POINTS`.
+    async fn run_quiz(&mut self) {
+        let Some(provider) = &self.dialogue else {
+            return;
+        };
 
-        Box::pin(async move {
-            self.display_layoff(
-                &human_player,
-                &hand_player,
-                "Enter cards to lay off separated by spaces (e.g. \"7h Jc\"): ",
+        println!(
+            "{}",
+            highlighted(
+                &Prompt::WritingQuiz {
+                    book: self.book.clone()
+                }
+                .to_string()
             )
-            .await;
+        );
+        let questions = match provider.ask(quiz_prompt(&self.book, QUIZ_QUESTIONS)).await {
+            Ok(answer) => parse_quiz(&strip_think_blocks(&answer)),
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    Prompt::CouldNotWriteQuiz {
+                        error: err.to_string()
+                    }
+                );
+                Vec::new()
+            }
+        };
+        if questions.is_empty() {
+            println!("{}", Prompt::NoQuiz);
+            return;
+        }
+        if let Err(err) = self.engine.start_quiz() {
+            eprintln!("{err}");
+            return;
+        }
 
-            let mut input = String::new();
-            io::stdout().flush().unwrap();
-            io::stdin().read_line(&mut input).unwrap();
-            let trimmed = input.trim();
-            if trimmed.is_empty() {
-                return vec![];
+        let humans: Vec<String> = self
+            .engine
+            .players
+            .iter()
+            .filter(|p| p.player_type.is_none())
+            .map(|p| p.name.clone())
+            .collect();
+        for name in humans {
+            clear_screen();
+            let colored_name = self
+                .get_player_color(&name)
+                .map(ColoredName::colored)
+                .unwrap_or_else(|| name.clone());
+            let quiz_for = Prompt::QuizFor {
+                player: colored_name.clone(),
+                points: QUIZ_POINTS,
+            };
+            println!("{quiz_for}\n");
+
+            let mut correct = 0;
+            for question in &questions {
+                println!("{}", highlighted(&question.question));
+                for choice in question.lettered_choices() {
+                    println!("  {choice}");
+                }
+
+                let reply = self.input.line().await;
+                if question.is_correct(&reply) {
+                    correct += 1;
+                    println!("{}\n", Prompt::QuizRight);
+                } else {
+                    let answer = question.lettered_choices().nth(question.answer);
+                    let answer = answer.unwrap_or_default();
+                    println!("{}\n", Prompt::QuizWrong { answer });
+                }
             }
 
-            let mut chosen = Vec::new();
-            for token in trimmed.split_whitespace() {
-                let card = Card::from_string(token.to_string());
-                match card {
-                    Ok(card) => {
-                        if human_player.hand.cards.contains(&card) {
-                            self.clear_messages();
-                            chosen.push(card)
-                        } else {
-                            self.add_message(format!("You don't have {token}"));
-                            return self
-                                .prompt_for_layoff_cards(&human_player, &hand_player)
-                                .await;
-                        }
-                    }
-                    _ => {
-                        self.add_message("Invalid card!".to_string());
-                        return self
-                            .prompt_for_layoff_cards(&human_player, &hand_player)
-                            .await;
+            let scored = self.engine.score_quiz(&name, correct, questions.len());
+            match scored {
+                Ok(points) => println!(
+                    "{}",
+                    Prompt::QuizScored {
+                        player: colored_name,
+                        correct,
+                        asked: questions.len(),
+                        points,
                     }
-                }
+                ),
+                Err(err) => eprintln!("{err}"),
             }
+            tokio::time::sleep(self.pacing).await;
+        }
+    }
 
-            chosen
-        })
-    }
+    /// Asks before calling off the round, and redeals if the answer is yes.
+    async fn confirm_redeal(&mut self, human_player: &Player) -> bool {
+        let input = self
+            .ask(human_player, &Prompt::ConfirmRedeal.to_string())
+            .await;
+        if !i18n::locale().is_yes(&input) {
+            return false;
+        }
 
-    fn update_scores(&self, player: &Player, score: usize) {
-        self.players.borrow_mut().iter_mut().for_each(|p| {
-            if p == player {
-                p.score += score
+        match self.engine.redeal(&human_player.name) {
+            Ok(()) => {
+                self.add_message(Prompt::Redealt.to_string());
+                true
+            }
+            Err(err) => {
+                self.add_message(err.to_string());
+                false
             }
-        });
-    }
-
-    fn deal_new_round(&self) {
-        // Clear hands
-        for player in self.players.borrow_mut().iter_mut() {
-            player.hand.cards.clear();
         }
+    }
 
-        // Get all cards from deck and reshuffle
-        let mut all_cards = Vec::new();
-        all_cards.extend(self.deck.borrow_mut().draw_pile.drain(..));
-        all_cards.extend(self.deck.borrow_mut().discard_pile.drain(..));
+    /// Asks whether `human_player` declares on the discard `card`.
+    async fn confirm_declaration(&mut self, human_player: &Player, card: Card) -> bool {
+        let prompt = Prompt::DeclareOnIt { card }.to_string();
+        let input = self.ask(human_player, &prompt).await;
+        i18n::locale().is_yes(&input)
+    }
 
-        // If not enough cards, create a new deck
-        if all_cards.len() < 52 {
-            all_cards = shuffle_deck().unwrap().into();
-        }
+    fn clear_messages(&mut self) {
+        self.messages.clear();
+    }
 
-        let mut rng = rand::rng();
-        all_cards.shuffle(&mut rng);
+    /// Walks the current player through their staged turn: which card to
+    /// discard, then whether to confirm it. `U` takes back a step. Returns
+    /// `false` if they undid their retrieve, to choose again.
+    async fn confirm_staged_turn(&mut self, player: &Player) -> bool {
+        loop {
+            let (preview, staged) = {
+                let engine = &self.engine;
+                let (Some(staged), Some(hand)) = (engine.staged().cloned(), engine.staged_hand())
+                else {
+                    return false;
+                };
+                let mut preview = engine.current_player().clone();
+                preview.hand = hand;
+                (preview, staged)
+            };
 
-        // Deal 5 cards to each player
-        for player in self.players.borrow_mut().iter_mut() {
-            for _ in 0..5 {
-                if let Some(card) = all_cards.pop() {
-                    player.hand.cards.push(card);
+            let prompt = Prompt::Staged {
+                card: pretty(&staged.taken),
+                retrieved: staged.choice == Choice::Retrieve,
+                discard: staged.discard.map(|card| pretty(&card)),
+            }
+            .to_string();
+            let Some(input) = self.ask_in_time(&preview, &prompt).await else {
+                self.play_out_of_time(player);
+                return true;
+            };
+            let input = input.trim().to_lowercase();
+
+            let result = match input.as_str() {
+                "u" | "undo" => self.engine.undo(),
+                "c" | "confirm" if staged.discard.is_some() => {
+                    let committed = self.engine.commit();
+                    if committed.is_ok() {
+                        self.clear_messages();
+                        return true;
+                    }
+                    committed
+                }
+                _ if staged.discard.is_some() => {
+                    Err(RummyError::InvalidMove(Prompt::ConfirmOrUndo.to_string()))
+                }
+                other => match answer::<Card>(other, |_| Ok(())) {
+                    Ok(card) => self.engine.stage_discard(card),
+                    Err(why) => Err(RummyError::InvalidCard(why)),
+                },
+            };
+            match result {
+                Ok(()) => self.clear_messages(),
+                Err(RummyError::InvalidMove(msg)) | Err(RummyError::InvalidCard(msg)) => {
+                    self.add_message(msg)
                 }
+                Err(err) => self.add_message(err.to_string()),
             }
         }
+    }
 
-        // Put remaining cards in draw pile
-        self.deck.borrow_mut().draw_pile = all_cards.into_iter().collect();
+    /// Asks `player` what they'd like to say, unless the table's turned the
+    /// question off. Nothing typed says nothing.
+    async fn prompt_for_dialogue(&mut self, player: &Player) {
+        if !self.chat_prompt {
+            return;
+        }
+        let dialogue = self.ask(player, &Prompt::Chat.to_string()).await;
+        self.say(&player.name, &dialogue);
+    }
 
-        // Turn over one card for discard pile
-        let mut deck = self.deck.borrow_mut();
-        if let Some(card) = deck.draw_pile.pop_back() {
-            deck.discard_pile.push_back(card);
+    /// Does what a slash command typed by `human_player` asks, or says
+    /// what's wrong with it.
+    fn command(&mut self, human_player: &Player, command: Result<ChatCommand, RummyError>) {
+        let command = match command {
+            Ok(command) => command,
+            Err(err) => return self.add_message(err.to_string()),
+        };
+        let engine = &self.engine;
+        let choosing = *engine.phase() == TurnPhase::Choosing;
+
+        match command {
+            ChatCommand::Help => self.add_message(Prompt::Commands.to_string()),
+            ChatCommand::Hand => {
+                let hand = engine
+                    .players
+                    .iter()
+                    .find(|player| player.name == human_player.name)
+                    .map_or(&human_player.hand, |player| &player.hand);
+                self.add_message(commands::hand_line(&hand.cards));
+            }
+            ChatCommand::Scores => {
+                let scores: Vec<(String, usize)> = engine
+                    .players
+                    .iter()
+                    .map(|player| (player.name.clone(), player.score))
+                    .collect();
+                self.add_message(commands::scores_line(&scores));
+            }
+            ChatCommand::History => self.screen.scroll(Scroll::Back),
+            ChatCommand::Hint if choosing && engine.current_player().name == human_player.name => {
+                self.hint()
+            }
+            ChatCommand::Hint => self.add_message(Prompt::HintsAtStartOfTurn.to_string()),
+            ChatCommand::Save if !choosing => {
+                self.add_message(Prompt::FinishTurnToSave.to_string())
+            }
+            ChatCommand::Save => {
+                let said = match self.save() {
+                    Ok(Some(path)) => Prompt::Saved {
+                        path: path.display().to_string(),
+                        quitting: false,
+                    },
+                    Ok(None) => Prompt::NotSaved { quitting: false },
+                    Err(err) => Prompt::CouldNotSave {
+                        error: err.to_string(),
+                    },
+                };
+                self.add_message(said.to_string())
+            }
+            ChatCommand::Quit => quit(&Prompt::GameAbandoned.to_string()),
+            ChatCommand::Say(dialogue) => self.say(&human_player.name, &dialogue),
         }
     }
 
-    // Helper function to colorize multi-line text
-    fn colorize_text(text: &str, color_code: &str) -> String {
-        let lines: Vec<String> = text
-            .lines()
-            .map(|line| format!("\x1B[{color_code}m{line}\x1B[0m"))
-            .collect();
-        lines.join("\n")
+    /// Whether the analysis would play or draw, for the player whose turn
+    /// it is.
+    fn hint(&mut self) {
+        let hint = self.engine.hint();
+        let said = Prompt::Hint {
+            play: hint.should_play,
+            reasoning: hint.reasoning,
+        };
+        self.add_message(said.to_string());
     }
 
-    fn colored_book_title(&self) -> String {
-        // Pastel green (using 256-color palette) + bold
-        format!(
-            "\x1B[1;38;5;120mToday's Bookclub Rummy is on {}\x1B[0m",
-            self.book
-        )
+    /// Adds what `speaker` typed to the conversation, if it's anything.
+    fn say(&mut self, speaker: &str, dialogue: &str) {
+        let dialogue = normalize_dialogue(dialogue);
+        if dialogue.is_empty() {
+            return;
+        }
+        self.push_dialogue(speaker, &dialogue);
+        self.player_dialogues.insert(speaker.to_string(), dialogue);
+    }
 
-        // Alternative pastel green options:
-        // format!("\x1B[1;38;5;114m...")  // Slightly different pastel green
-        // format!("\x1B[1;38;5;156m...")  // Very light pastel green
-        // format!("\x1B[1;92m...")        // Basic bright green (16-color)
-    }
-
-    async fn display_victory_animation(&self, winner_name: &str) {
-        let angel = vec![
-            "               ______",
-            "              '-._   ```\"\"\"---.._",
-            "           ,-----.:___           `\\  ,;;;,",
-            "            '-.._     ```\"\"\"--.._  |,%%%%%%              _",
-            "            ,    '.              `\\;;;;  -\\      _    _.'/\\",
-            "          .' `-.__ \\            ,;;;;\" .__{=====/_)==:_  ||",
-            "     ,===/        ```\";,,,,,,,;;;;;'`-./.____,'/ /     '.\\/",
-            "    '---/              ';;;;;;;;'      `--.._.' /",
-            "   ,===/                          '-.        `\\/",
-            "  '---/                            ,'`.        |",
-            "     ;                        __.-'    \\     ,'",
-            "jgs  \\______,,.....------'''``          `---`",
-        ];
-
-        // Get terminal width
+    async fn prompt_for_layoff_cards(
+        &mut self,
+        human_player: &Player,
+        hand_player: &Player,
+    ) -> Option<Vec<Card>> {
+        loop {
+            let prompt = time_left(&Prompt::LayOff.to_string(), self.turn_deadline);
+            self.display_layoff(human_player, hand_player, &prompt)
+                .await;
+
+            io::stdout().flush().unwrap();
+            let input = self.line_in_time().await?;
+            if wants_menu(&input) {
+                self.pause_menu(human_player).await;
+                continue;
+            }
+            if let Some(command) = commands::parse(&input) {
+                self.command(human_player, command);
+                continue;
+            }
+
+            let held = |cards: &Cards| {
+                let mut held = human_player.hand.cards.clone();
+                match cards.0.iter().find(|card| !remove_one(&mut held, card)) {
+                    Some(card) => Err(Prompt::NotInHand {
+                        card: card.to_string(),
+                    }
+                    .to_string()),
+                    None => Ok(()),
+                }
+            };
+            match answer::<Cards>(&input, held) {
+                Ok(Cards(chosen)) => {
+                    self.clear_messages();
+                    return Some(chosen);
+                }
+                Err(why) => self.add_message(why),
+            }
+        }
+    }
+
+    async fn display_victory_animation(&mut self, winner_name: &str, kind: CelebrationKind) {
         let term_width = terminal_size()
             .map(|(Width(w), _)| w as usize)
             .unwrap_or(80);
-        let angel_width = angel.iter().map(|line| line.len()).max().unwrap_or(0);
-
-        // Get winner's color
         let winner_color = self
             .player_colors
             .iter()
@@ -659,1009 +1668,1428 @@ impl GameState {
             .map(|cn| cn.color_code.clone())
             .unwrap_or_else(|| "0".to_string());
 
-        // Create RNG instance
-        let mut rng = rand::rng();
+        let mut rng = self.engine.rng().fork(RngStream::Effects);
+        let frames = kind
+            .pick(&mut rng)
+            .frames(winner_name, &winner_color, term_width, &mut rng);
+        self.screen.forget();
 
-        // Phase 1: Angel glides from left to right
-        for position in (0..=(term_width.saturating_sub(angel_width))).step_by(2) {
-            Self::clear_screen();
+        // Skipping it goes straight on to whatever's shown after
+        celebration::play(&frames, &self.input).await;
+    }
 
-            // Rainbow colors for victory message
-            let colors = ["31", "33", "32", "36", "34", "35"];
-            let victory_msg = "VICTORY!";
-            print!("{:^width$}", "", width = term_width / 2 - 4);
-            for (i, ch) in victory_msg.chars().enumerate() {
-                print!("\x1B[1;{}m{}\x1B[0m", colors[i % colors.len()], ch);
-            }
-            println!("\n");
+    /// Leaves the screen up until the player presses Enter, then exits.
+    async fn wait_to_exit(&self) {
+        let term_width = terminal_size()
+            .map(|(Width(w), _)| w as usize)
+            .unwrap_or(80);
+        let msg = format!(
+            "\n\n{}",
+            dimmed(&centered(&Prompt::PressEnterToExit.to_string(), term_width))
+        );
+        io::stdout().write_all(msg.as_bytes()).unwrap();
+        io::stdout().flush().unwrap();
 
-            println!(
-                "{:^width$}\x1B[1;{}m{} wins!\x1B[0m\n",
-                "",
-                winner_color,
-                winner_name,
-                width = term_width
+        if self.input.line().await.trim() == "" {
+            std::process::exit(0);
+        }
+    }
+}
+
+/// Lets every other player lay off onto the hand the engine has just had
+/// played, then scores the round.
+async fn run_layoff_round(game_state: &mut GameState) -> RoundOutcome {
+    loop {
+        let (player, meld_holder, partners) = {
+            let engine = &game_state.engine;
+            let (Some(idx), Some(layoff)) = (engine.layoff_player_idx(), engine.layoff_round())
+            else {
+                break;
+            };
+
+            // Show the meld as the hand of whoever it currently belongs to
+            let mut meld_holder = engine.players[layoff.layoff_winner_idx].clone();
+            meld_holder.hand = layoff.winning_hand.clone();
+
+            let partners = engine.partner(idx) == Some(layoff.layoff_winner_idx);
+            (engine.players[idx].clone(), meld_holder, partners)
+        };
+        if partners {
+            game_state.add_message(
+                Prompt::PartnersMeld {
+                    partner: meld_holder.name.clone(),
+                }
+                .to_string(),
             );
+        }
 
-            // Display the angel with gradient effect
-            for (i, line) in angel.iter().enumerate() {
-                let color = if i < angel.len() / 2 { "229" } else { "231" };
-                println!(
-                    "{:width$}\x1B[38;5;{}m{}\x1B[0m",
-                    "",
-                    color,
-                    line,
-                    width = position
-                );
-            }
+        if player.player_type.is_none() {
+            game_state.hand_to(&player).await;
+            game_state.start_turn_clock();
+            loop {
+                let Some(chosen_cards) = game_state
+                    .prompt_for_layoff_cards(&player, &meld_holder)
+                    .await
+                else {
+                    game_state.play_out_of_time(&player);
+                    break;
+                };
 
-            // Trailing sparkles
-            let sparkles = ["✨", "⭐", "✦", "✧", "⋆"];
-            for i in 0..5 {
-                if position > (i + 1) * 8 {
-                    let sparkle_pos = position - (i + 1) * 8;
-                    let sparkle_line = 5 + i;
-                    if sparkle_line < angel.len() {
-                        print!("\x1B[{}A", angel.len() - sparkle_line);
-                        println!(
-                            "{:width$}\x1B[38;5;226m{}\x1B[0m",
-                            "",
-                            sparkles[i % sparkles.len()],
-                            width = sparkle_pos
-                        );
-                        print!("\x1B[{}B", angel.len() - sparkle_line - 1);
-                    }
+                // Tell them a layoff won't take the meld while they can still change it
+                let engine = &mut game_state.engine;
+                let laid_off = if chosen_cards.is_empty() {
+                    engine.lay_off(&chosen_cards)
+                } else {
+                    engine
+                        .check_layoff(&chosen_cards)
+                        .and_then(|_| engine.lay_off(&chosen_cards))
+                };
+                match laid_off {
+                    Ok(_) => break,
+                    Err(err) => game_state.add_message(err.to_string()),
                 }
             }
-
-            io::stdout().flush().unwrap();
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            game_state.stop_turn_clock();
+        } else {
+            game_state.engine.ai_lay_off().unwrap();
         }
+    }
 
-        // Phase 2: Final celebration with confetti
-        for frame in 0..3 {
-            Self::clear_screen();
+    game_state.engine.finish_round().unwrap()
+}
 
-            // Random confetti
-            let confetti = ["🎉", "🎊", "🌟", "✨", "🎈"];
-            for _ in 0..10 {
-                let x = rng.random_range(0..term_width);
-                let y = rng.random_range(0..5);
-                let confetti_char = confetti[rng.random_range(0..confetti.len())];
-                print!("\x1B[{};{}H{}", y + 1, x + 1, confetti_char);
-            }
+/// Gives everyone the discard completes a winning meld for the chance to
+/// declare on it, and plays out the round if anyone does.
+async fn run_declarations(game_state: &mut GameState) {
+    game_state.engine.ai_declarations().unwrap();
 
-            println!("\n\n\n\n\n");
+    loop {
+        let waiting = {
+            let engine = &game_state.engine;
+            engine.declare_window().and_then(|window| {
+                window
+                    .eligible
+                    .iter()
+                    .find(|&&idx| {
+                        window.is_open_to(idx) && engine.players[idx].player_type.is_none()
+                    })
+                    .map(|&idx| (idx, engine.players[idx].clone(), window.card))
+            })
+        };
+        let Some((idx, human_player, card)) = waiting else {
+            break;
+        };
 
-            // Victory message with pulsing effect
-            let size = if frame % 2 == 0 { "1" } else { "1;5" };
-            println!(
-                "{:^width$}\x1B[{};{}m{} WINS THE GAME!\x1B[0m",
-                "",
-                size,
-                winner_color,
-                winner_name.to_uppercase(),
-                width = term_width
-            );
+        game_state.hand_to(&human_player).await;
+        let declared = if game_state.confirm_declaration(&human_player, card).await {
+            game_state.engine.declare(idx)
+        } else {
+            game_state.engine.pass_declaration(idx)
+        };
+        if let Err(err) = declared {
+            game_state.add_message(err.to_string());
+        }
+    }
 
-            println!("\n");
+    if *game_state.engine.phase() == TurnPhase::Declaring {
+        game_state.engine.close_declarations().unwrap();
+    }
 
-            // Center the angel
-            let center_pos = (term_width - angel_width) / 2;
-            for line in &angel {
-                println!(
-                    "{:width$}\x1B[38;5;229m{}\x1B[0m",
-                    "",
-                    line,
-                    width = center_pos
-                );
+    let declarer = {
+        let engine = &game_state.engine;
+        engine.layoff_round().map(|layoff| {
+            (
+                engine.players[layoff.winner_idx].name.clone(),
+                layoff.score_to_beat,
+            )
+        })
+    };
+    if let Some((name, score)) = declarer {
+        game_state.add_message(
+            Prompt::DeclaredOutOfTurn {
+                player: name,
+                score,
             }
+            .to_string(),
+        );
+        run_layoff_round(game_state).await;
+    }
+}
 
-            io::stdout().flush().unwrap();
-            tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let config = Config::load(args.settings.as_deref()).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+    let table = TableOptions::new(&args, &config);
+
+    if let Some(path) = &args.log_file
+        && let Err(err) = init_logging(path)
+    {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+
+    if let Some(path) = args.theme.as_ref().or(config.theme.as_ref()) {
+        match Theme::load(path) {
+            Ok(theme) => theme::install(&theme),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
         }
+    }
 
-        let msg = format!(
-            "\n\n{:^width$}\x1B[2mPress Enter to exit...\x1B[0m",
-            "",
-            width = term_width
-        );
-        let mut input = String::new();
-        io::stdout().write_all(msg.as_bytes()).unwrap();
-        io::stdout().flush().unwrap();
+    i18n::install(args.locale.unwrap_or(config.locale));
 
-        if io::stdin().read_line(&mut input).is_err() {
-            panic!("Couldn't read from stdin!");
-        } else if input.trim() == "" {
-            std::process::exit(0);
-        }
+    if args.demo {
+        run_demo(&table).await;
+        return;
     }
-}
 
-async fn run_layoff_round(
-    winner_idx: usize,
-    winner_hand: &Hand,
-    players: &[Player],
-    score_to_beat: u64,
-    game_state: &GameState,
-) -> (Option<Vec<LayOffResult>>, Hand) {
-    let mut score_to_beat = score_to_beat;
-    let mut lay_off_results = Vec::new();
-    let mut all_layoff_cards = Vec::new();
-    let num_players = players.len();
-
-    game_state.add_action(
-        &players[winner_idx].name,
-        &format!(
-            "played their hand {winner_hand} for {score_to_beat} points. It's time to layoff."
-        ),
-        None,
-    );
+    match args.command.unwrap_or(Command::Local) {
+        Command::Local => {
+            let Some(conf_file) = args.config.or(config.llm) else {
+                eprintln!(
+                    "A local game needs an awful_aj configuration file, pass one with --config or set llm in rummy.toml"
+                );
+                std::process::exit(1);
+            };
 
-    let mut current_idx = (winner_idx + 1) % num_players;
-    let mut players = players.to_owned();
-    let mut winner_hand = winner_hand.clone();
-    let mut layoff_winner_idx = winner_idx;
+            let profiles = match args.players.as_ref().or(config.players.as_ref()) {
+                Some(path) => Profiles::load(path).unwrap_or_else(|err| {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }),
+                None => Profiles::default(),
+            };
 
-    while current_idx != winner_idx {
-        let is_human = players[current_idx].player_type.is_none();
-        if is_human {
-            let chosen_cards = game_state
-                .prompt_for_layoff_cards(&players[current_idx], &players[layoff_winner_idx])
-                .await;
+            let setup = LocalSetup {
+                profiles,
+                lineup: args.seats,
+                book: args.book,
+            };
+            run_local(conf_file, &table, args.resume, setup, config.keys).await;
+        }
+        Command::Server {
+            bind,
+            seats,
+            bots,
+            book,
+        } => {
+            let local_only = table.local_only_flags();
+            if !local_only.is_empty() {
+                eprintln!(
+                    "{} only work in a local game, not a hosted one",
+                    local_only.join(", ")
+                );
+                std::process::exit(1);
+            }
 
-            if !chosen_cards.is_empty() {
-                // Remove chosen cards from player's hand
-                for card in &chosen_cards {
-                    if let Some(pos) = players[current_idx]
-                        .hand
-                        .cards
-                        .iter()
-                        .position(|c| c == card)
-                    {
-                        players[current_idx].hand.cards.remove(pos);
-                    }
-                }
+            println!("Hosting Bookclub Rummy on ws://{bind}");
 
-                let mut best_layoff: Option<LayOffResult> = None;
-                let mut best_score = score_to_beat;
-
-                if chosen_cards.len() == 1 {
-                    // For single card layoff, try replacing each card in winner's hand
-                    for i in 0..winner_hand.cards.len() {
-                        let mut test_hand = winner_hand.clone();
-                        test_hand.cards[i] = chosen_cards[0];
-
-                        let (test_score, new_hand) =
-                            calculate_best_meld_from_5_card_hand(&test_hand);
-
-                        if test_score > best_score {
-                            best_score = test_score;
-                            best_layoff = Some(LayOffResult {
-                                player: players[current_idx].clone(),
-                                cards_laid_off: chosen_cards.clone(),
-                                resulting_hand: new_hand,
-                                resulting_score: test_score,
-                                cards_used: 1,
-                            });
-                        }
-                    }
-                } else if chosen_cards.len() == 2 {
-                    // For two card layoff, try all combinations of replacing 2 cards
-                    for i in 0..(winner_hand.cards.len() - 1) {
-                        for j in (i + 1)..winner_hand.cards.len() {
-                            let mut test_hand = winner_hand.clone();
-                            test_hand.cards[i] = chosen_cards[0];
-                            test_hand.cards[j] = chosen_cards[1];
-
-                            let (test_score, new_hand) =
-                                calculate_best_meld_from_5_card_hand(&test_hand);
-
-                            if test_score > best_score {
-                                best_score = test_score;
-                                best_layoff = Some(LayOffResult {
-                                    player: players[current_idx].clone(),
-                                    cards_laid_off: chosen_cards.clone(),
-                                    resulting_hand: new_hand,
-                                    resulting_score: test_score,
-                                    cards_used: 2,
-                                });
-                            }
-                        }
+            #[cfg(feature = "scripting")]
+            let house_rules = match &table.house_rules {
+                Some(path) => match HouseRules::load(path, &book) {
+                    Ok(rules) => Some(Arc::new(rules)),
+                    Err(err) => {
+                        eprintln!("{err}");
+                        std::process::exit(1);
                     }
-                }
+                },
+                None => None,
+            };
 
-                if let Some(layoff) = best_layoff {
-                    winner_hand = layoff.resulting_hand.clone();
-                    all_layoff_cards.extend(chosen_cards.clone());
-                    score_to_beat = layoff.resulting_score;
-
-                    game_state.add_action(
-                        &players[current_idx].name,
-                        &format!(
-                            "laid off {} card(s) to winner's meld, scoring: {}",
-                            chosen_cards.len(),
-                            score_to_beat
-                        ),
-                        None,
-                    );
-
-                    lay_off_results.push(layoff);
-                } else {
-                    // Put cards back in player's hand if layoff failed
-                    players[current_idx].hand.cards.extend(chosen_cards.clone());
-
-                    game_state.add_action(
-                        &players[current_idx].name,
-                        "could not layoff cards to form a meld.",
-                        None,
-                    );
-                }
-            }
-        } else {
-            // AI layoff logic
-            if let Some(layoff) =
-                check_for_layoff(&players[current_idx], &winner_hand, score_to_beat)
-            {
-                winner_hand.cards = layoff.resulting_hand.cards.clone();
-                all_layoff_cards.extend(layoff.cards_laid_off.clone());
-
-                score_to_beat = layoff.resulting_score;
-
-                game_state.add_action(
-                    &players[current_idx].name,
-                    &format!(
-                        "laid off {} card(s) to winner's meld, scoring: {}",
-                        layoff.cards_used, score_to_beat
-                    ),
-                    None,
-                );
+            let config = ServerConfig {
+                bind,
+                book,
+                seats,
+                bots,
+                deck: table.deck,
+                turn_order: table.turn_order,
+                variant: table.variant,
+                seed: table.seed,
+                declarations: table.declare,
+                rituals: table.rituals,
+                rules: table.rules,
+                turn_timer: table.turn_timer,
+                ai_search: table.ai_search,
+                #[cfg(feature = "scripting")]
+                house_rules,
+            };
 
-                winner_hand = layoff.resulting_hand.clone();
-                lay_off_results.push(layoff);
-                players[current_idx].hand = winner_hand.clone();
-                layoff_winner_idx = current_idx;
+            if let Err(err) = server::run(config).await {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Command::Join { url, name } => {
+            if let Err(err) = client::run(&url, &name).await {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Command::Practice { discards, flash_ms } => {
+            let flash = Duration::from_millis(flash_ms);
+            if let Err(err) = run_practice(table.deck, table.seed, discards, flash).await {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Command::Analyze {
+            hand,
+            discard,
+            seen,
+            dump_tree,
+            time_budget_ms,
+        } => {
+            let analyzed = run_analyze(
+                &table,
+                &hand,
+                discard.as_deref(),
+                &seen,
+                dump_tree.as_deref(),
+                time_budget_ms.map(Duration::from_millis),
+            );
+            if let Err(err) = analyzed {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Command::Tournament {
+            strategies,
+            games,
+            csv,
+        } => {
+            if let Err(err) = run_tournament_command(&table, &strategies, games, csv.as_deref()) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Command::Stats { pseudonymize } => {
+            if let Err(err) = print_stats(pseudonymize.as_deref()) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Command::Archive { file } => {
+            if let Err(err) = archive_last_match(&file) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Command::Inspect { file, game } => {
+            if let Err(err) = inspect_archive(&file, game) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Command::Schema { dir } => {
+            if let Err(err) = write_schemas(&dir) {
+                eprintln!("{err}");
+                std::process::exit(1);
             }
         }
-
-        current_idx = (current_idx + 1) % num_players;
     }
+}
 
-    let lay_off_results = if lay_off_results.is_empty() {
-        None
-    } else {
-        Some(lay_off_results)
-    };
+/// The cards listed in a stacked deck file, top of the deck first. Anything
+/// after a `#` on a line is a comment.
+fn load_stacked_deck(path: &Path) -> Result<Vec<Card>, RummyError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+    let cards: Vec<&str> = contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .collect();
 
-    (lay_off_results, winner_hand)
+    parse_cards(&cards.join(" "))
+        .map_err(|err| RummyError::Config(format!("{}: {err}", path.display())))
 }
 
-#[tokio::main]
-async fn main() {
-    let args = Args::parse();
-    let conf_file = args.config;
+/// Cards typed on the command line, like `2s 3s 4s`.
+fn parse_cards(input: &str) -> Result<Vec<Card>, RummyError> {
+    input.parse().map(|Cards(cards)| cards)
+}
 
-    let awful_config = awful_aj::config::load_config(conf_file.to_str().unwrap()).unwrap();
+/// Searches ahead from `hand`, holding `discard` too, the way the AI does on
+/// its turn, and prints what it makes of the hand. `seen` are cards out of
+/// the draw pile. The tree goes to `dump_tree` as DOT if asked for. With a
+/// `time_budget` the search stops at the deepest level it finishes in time.
+fn run_analyze(
+    table: &TableOptions,
+    hand: &str,
+    discard: Option<&str>,
+    seen: &str,
+    dump_tree: Option<&Path>,
+    time_budget: Option<Duration>,
+) -> Result<(), RummyError> {
+    let mut cards = parse_cards(hand)?;
+    cards.extend(discard.map(parse_cards).transpose()?.unwrap_or_default());
+    if cards.len() != 6 {
+        return Err(RummyError::Config(
+            "The analysis looks at 6 cards: a hand of 5 and the discard, or a hand of 6"
+                .to_string(),
+        ));
+    }
+    let seen = parse_cards(seen)?;
+
+    let mut possible_cards = table.deck.cards()?;
+    for card in cards.iter().chain(&seen) {
+        if !remove_one(&mut possible_cards, card) {
+            return Err(RummyError::InvalidCard(format!(
+                "There's no {card} left in the deck"
+            )));
+        }
+    }
 
-    let shuffled_deck = shuffle_deck().unwrap();
+    let meld = best_meld(&cards).cards;
+    let node = Node::new(Hand { cards }, possible_cards, seen.into_iter().collect());
+    let baseline_score = node.baseline_score;
 
-    println!("\x1B[1;38;5;120mEnter number of players:\x1B[0m");
-    let mut num_players = String::new();
-    io::stdin()
-        .read_line(&mut num_players)
-        .expect("Failed to read number of players");
+    let config = AnalysisConfig {
+        time_budget,
+        ..AnalysisConfig::default()
+    };
+    let mut rng = RngService::seeded(table.seed.unwrap_or_else(rand::random)).fork(RngStream::Ai);
+    let node = evaluate_hand_deepening(&node, config, &mut rng)?;
+    let analysis = node.calculate_cumulative_probabilities();
+
+    match explain(&meld)
+        .into_iter()
+        .find(|breakdown| breakdown.score == baseline_score)
+    {
+        Some(breakdown) => println!("Best meld: {}\n", meld_breakdown(&breakdown)),
+        None => println!("Best meld: nothing\n"),
+    }
+    print!("{analysis}");
 
-    let num_players = match num_players.trim().parse::<usize>() {
-        Ok(n) if n >= 2 => n,
-        _ => {
-            println!(
-                "Invalid input. Please enter a number between {} and {}",
-                2, 4
-            );
-            std::process::exit(1);
-        }
+    println!("\nWhat each card is worth, least first:");
+    for value in node.calculate_strategic_card_values_correct(&analysis) {
+        println!("  {value}");
+    }
+
+    println!("\nWhat the AI would do:");
+    let context = MatchContext::default();
+    for player_type in [
+        PlayerType::Conservative,
+        PlayerType::Aggressive,
+        PlayerType::Balanced,
+        PlayerType::Ensemble,
+        PlayerType::MonteCarlo,
+    ] {
+        let decision = node.make_autoplay_decision(player_type.clone(), &analysis, &context);
+        let discard = decision
+            .card_to_discard
+            .map(|card| format!(", throwing away {}", pretty(&card)))
+            .unwrap_or_default();
+        println!(
+            "  {player_type:?}: {:?}{discard}, expecting {:.1} ({:.0}% sure)",
+            decision.action,
+            decision.expected_score,
+            decision.confidence * 100.0
+        );
+    }
+
+    if let Some(path) = dump_tree {
+        fs::write(path, node.to_dot())
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+        println!("\nSearch tree written to {}", path.display());
+    }
+    Ok(())
+}
+
+/// Plays `strategies` against each other and prints their ratings, and
+/// writes them to `csv` if asked to.
+fn run_tournament_command(
+    table: &TableOptions,
+    strategies: &[String],
+    games: usize,
+    csv: Option<&Path>,
+) -> Result<(), RummyError> {
+    let entrants = strategies
+        .iter()
+        .map(|name| Entrant::builtin(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let config = TournamentConfig {
+        games,
+        variant: table.variant,
+        deck: table.deck.clone(),
+        seed: table.seed.unwrap_or_else(rand::random),
+        declarations: table.declare,
     };
 
-    let mut players = Vec::with_capacity(num_players);
-    for i in 0..num_players {
-        let name_input = match i {
-            0 => "\x1B[1;38;5;120mEnter your name:\x1B[0m".to_string(),
-            _ => format!("\x1B[1;38;5;120mEnter name of player {}:\x1B[0m:", i + 1),
-        };
-        println!("{name_input}");
-        let mut name = String::new();
-        io::stdin()
-            .read_line(&mut name)
-            .expect("Failed to read player name");
-
-        let description = if i != 0 {
-            let mut description = String::new();
-            println!("\x1B[1;38;5;120mEnter player description (Press enter if none):\x1B[0m");
-            io::stdin()
-                .read_line(&mut description)
-                .expect("Failed to read player description");
-            description
-        } else {
-            "".to_string()
-        };
+    let pairs = entrants.len() * (entrants.len() - 1) / 2;
+    println!("Playing {} games...\n", pairs * games);
+    let report = run_tournament(&entrants, &config)?;
+    print!("{report}");
 
-        players.push(Player {
-            name: name.trim().to_string(),
-            description,
-            player_type: match i {
-                0 => None,
-                _ => Some(PlayerType::Balanced),
-            },
-            hand: Hand { cards: Vec::new() },
-            actions: VecDeque::new(),
-            dialogue: VecDeque::new(),
-            score: 0,
-        });
+    if let Some(path) = csv {
+        fs::write(path, report.standings_csv())
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+        println!("\nStandings written to {}", path.display());
     }
+    Ok(())
+}
 
-    println!("\x1B[1;38;5;120mEnter book and author (East of Eden by John Steinbeck)\x1B[0m");
-    let mut book_and_author = String::new();
-    io::stdin()
-        .read_line(&mut book_and_author)
-        .expect("Failed to get book and author");
+/// Lifetime records for everyone in the stats file.
+fn print_stats(salt: Option<&str>) -> Result<(), RummyError> {
+    let store = StatsStore::default();
+    let options = ExportOptions {
+        pseudonymizer: salt.map(Pseudonymizer::new).transpose()?,
+    };
 
-    let mut rng = rand::rng();
-    players.shuffle(&mut rng);
+    let results = store.load()?;
+    if results.is_empty() {
+        println!("No games recorded in {} yet.", store.path().display());
+        return Ok(());
+    }
 
-    // Initialize deck data
-    let deck_data = DeckData::new(shuffled_deck.into());
+    println!("{} games recorded\n", results.len());
+    for player in lifetime_stats(&results, &options) {
+        println!("{player}");
+    }
 
-    // Create colored names for each player
-    let player_colors: Vec<ColoredName> = players
-        .iter()
-        .enumerate()
-        .map(|(idx, player)| ColoredName::new(player.name.clone(), idx))
-        .collect();
+    // Names on the sheet aren't pseudonymized, so it's left out of exports
+    let last_sheet = results
+        .last()
+        .and_then(|result| result.score_sheet.as_ref());
+    if options.pseudonymizer.is_none()
+        && let Some(sheet) = last_sheet
+    {
+        println!("\nThe last game, round by round:");
+        for line in score_sheet_panel(sheet, &[]) {
+            println!("{line}");
+        }
+    }
+    Ok(())
+}
 
-    let game_state = GameState {
-        book: book_and_author,
-        players: RefCell::new(players.clone()),
-        player_colors,
-        deck: RefCell::new(deck_data),
-        actions_log: RefCell::new(Vec::new()),
-        messages: RefCell::new(Vec::new()),
-        current_player_idx: RefCell::new(0),
-        aj_config: awful_config,
-        player_quotes: RefCell::new(Vec::new()),
-        player_dialogues: RefCell::new(HashMap::new()),
+/// Appends the last game played here to the archive at `path`. It's only
+/// filed once.
+fn archive_last_match(path: &Path) -> Result<(), RummyError> {
+    let last = Archive::new(last_match_path());
+    let Some(record) = last.load()?.pop() else {
+        return Err(RummyError::Config(
+            "There's no finished game to archive, play one first".to_string(),
+        ));
     };
 
-    // Initial deal
-    game_state.deal_new_round();
+    let archive = Archive::new(path);
+    archive.append(&record)?;
+    let _ = std::fs::remove_file(last.path());
 
-    loop {
-        let winner = winning_player(&game_state);
+    let games = archive.load()?.len();
+    let plural = if games == 1 { "game" } else { "games" };
+    println!(
+        "Archived {} in {}, {games} {plural} so far.",
+        record.book,
+        archive.path().display()
+    );
+    Ok(())
+}
 
-        if let Some(winning_player) = winner {
-            if winning_player.player_type.is_none() {
-                game_state
-                    .display_victory_animation(&winning_player.name)
-                    .await;
-            } else {
-                println!("\n{} won todays Bookclub Rummy!", winning_player.name);
-                println!("\n\nFinal Scores:");
-                for player in game_state.players.borrow().iter() {
-                    if let Some(colored_name) = game_state.get_player_color(&player.name) {
-                        println!("{}: {}", colored_name.colored(), player.score);
-                    } else {
-                        println!("{}: {}", player.name, player.score);
-                    }
-                }
-                std::process::exit(0);
-            }
+/// Writes every schema in the protocol to its own file in `dir`.
+fn write_schemas(dir: &Path) -> Result<(), RummyError> {
+    fs::create_dir_all(dir).map_err(|err| RummyError::Io(format!("{}: {err}", dir.display())))?;
+    for (name, schema) in protocol::schemas() {
+        let json = serde_json::to_string_pretty(&schema)
+            .map_err(|err| RummyError::Protocol(err.to_string()))?;
+        let path = dir.join(format!("{name}.schema.json"));
+        fs::write(&path, json + "\n")
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Lists the games archived at `path`, or goes over the `game`th of them.
+fn inspect_archive(path: &Path, game: Option<usize>) -> Result<(), RummyError> {
+    let archive = Archive::new(path);
+    let matches = archive.load()?;
+    if matches.is_empty() {
+        println!("No games archived in {} yet.", path.display());
+        return Ok(());
+    }
+
+    let Some(game) = game else {
+        for (idx, record) in matches.iter().enumerate() {
+            let scores: Vec<String> = record
+                .result
+                .iter()
+                .flat_map(|result| &result.players)
+                .map(|player| format!("{} {}", player.name, player.score))
+                .collect();
+            let winner = record
+                .result
+                .as_ref()
+                .map(|result| format!("{} won in {} rounds", result.winner, result.rounds))
+                .unwrap_or_else(|| "unfinished".to_string());
+            println!(
+                "{}. {} ({:?}): {winner}, {}. Seed {}.",
+                idx + 1,
+                record.book,
+                record.config.variant,
+                scores.join(", "),
+                record.disclosure.seed
+            );
         }
+        return Ok(());
+    };
 
-        let current_idx = *game_state.current_player_idx.borrow();
-        // Get current player from game_state, not from local players array
-        let mut current_player = game_state.players.borrow()[current_idx].clone();
+    let record = game
+        .checked_sub(1)
+        .and_then(|idx| matches.get(idx))
+        .ok_or_else(|| {
+            RummyError::Config(format!(
+                "{} has games 1 to {}, not {game}",
+                path.display(),
+                matches.len()
+            ))
+        })?;
+
+    let config = &record.config;
+    let seats: Vec<String> = config
+        .seats
+        .iter()
+        .map(|seat| match &seat.player_type {
+            Some(player_type) => format!("{} ({player_type:?})", seat.name),
+            None => seat.name.clone(),
+        })
+        .collect();
+    println!(
+        "{}, {:?} to {}",
+        record.book, config.variant, config.target_score
+    );
+    println!("Seats: {}", seats.join(", "));
+    println!(
+        "Decks: {}, layoffs of up to {} cards{}{}{}",
+        config.deck.decks,
+        config.rules.max_layoff,
+        if config.declarations {
+            ", declaring"
+        } else {
+            ""
+        },
+        if config.rituals.cut.is_some() {
+            ", cut"
+        } else {
+            ""
+        },
+        if config.rituals.burn { ", burn" } else { "" },
+    );
+    if let Some(summary) = &record.summary {
+        print!("\n{summary}");
+    }
+    print!("\n{}", record.disclosure);
+    println!(
+        "\n{} events, {} lines said",
+        record.events.len(),
+        record.transcript.len()
+    );
+    for line in &record.transcript {
+        println!("{}: {}", line.player, line.text);
+    }
+    Ok(())
+}
 
-        game_state.update_current_player_dialogue().await;
+/// Logs to `path` as JSON lines, appended so a resumed game's log carries
+/// on, rather than to the terminal where it would scribble over the table.
+fn init_logging(path: &Path) -> Result<(), RummyError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+
+    tracing_subscriber::fmt()
+        .json()
+        .with_writer(Mutex::new(file))
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(false)
+        .try_init()
+        .map_err(|err| RummyError::Config(format!("Couldn't start logging: {err}")))
+}
 
-        if let Some(player_type) = current_player.player_type.clone() {
-            // AI Player Turn
-            let possible_cards: Vec<Card> =
-                game_state.deck.borrow().draw_pile.iter().cloned().collect();
-            let discard_card = *game_state.deck.borrow().discard_pile.back().unwrap();
-
-            let mut retrieve_hand = current_player.hand.clone();
-            retrieve_hand.cards.push(discard_card);
-
-            let (baseline_score, _hand) = calculate_best_meld_from_hand(&retrieve_hand);
-
-            let retrieve_node = Node {
-                full_hand: retrieve_hand.clone(),
-                possible_hands: Vec::new(),
-                possible_cards: possible_cards.clone(),
-                discard_pile: game_state.deck.borrow().discard_pile.clone(),
-                meld_score: None,
-                baseline_score,
-                branches: Vec::new(),
-                depth: 0,
-            };
+/// Who sits down to a new local game and what they're reading, as far as
+/// the command line and profiles say. Anything they don't is asked for.
+struct LocalSetup {
+    profiles: Profiles,
+    lineup: Option<Lineup>,
+    book: Option<String>,
+}
 
-            let retrieve_prob_analysis = retrieve_node.calculate_cumulative_probabilities();
+/// The single-terminal game: the humans at the keyboard against AI players,
+/// passing it round between turns if there's more than one of them.
+/// The game saves itself between turns, and `resume` picks the saved one back up.
+/// AI players come from `setup`'s profiles if there are any, and are asked for if not.
+/// The humans play with `keys`.
+async fn run_local(
+    conf_file: PathBuf,
+    table: &TableOptions,
+    resume: bool,
+    setup: LocalSetup,
+    keys: KeyBindings,
+) {
+    let awful_config = awful_aj::config::load_config(conf_file.to_str().unwrap()).unwrap();
+    let save_path = default_save_path();
 
-            let retrieve_decision =
-                retrieve_node.make_autoplay_decision(player_type.clone(), &retrieve_prob_analysis);
+    let game = if resume {
+        SavedGame::load(&save_path).and_then(SavedGame::restore)
+    } else {
+        new_local_game(table.deck.clone(), table.variant, &table.ai, &setup)
+            .map(|(book, engine)| (book, engine, Transcript::default()))
+    };
+    let (book, engine, transcript) = game
+        .and_then(|(book, mut engine, transcript)| {
+            table.prepare(&mut engine, &book)?;
+            Ok((book, engine, transcript))
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(1);
+        });
 
-            let mut total_draw_score = 0.0;
-            let mut draw_scenarios = 0;
+    let provider = Retrying::new(
+        AwfulJade {
+            config: awful_config,
+        },
+        RetryPolicy::default(),
+    );
+    let mut game_state = GameState::new(
+        book,
+        engine,
+        transcript,
+        Some(Box::new(provider)),
+        TURN_PACING,
+    );
+    game_state.save_path = Some(save_path.clone());
+    game_state.profiles = setup.profiles;
+    if let Some(tts) = &table.tts {
+        let voices = game_state
+            .engine
+            .players
+            .iter()
+            .filter(|player| player.player_type.is_some())
+            .map(|player| {
+                let profile = game_state.profiles.get(&player.name);
+                (player.name.clone(), profile.and_then(|p| p.voice.clone()))
+            })
+            .collect();
+        let narrator = Narrator::new(speech::backend(tts), voices);
+        game_state.engine.subscribe(Arc::new(Mutex::new(narrator)));
+    }
+    game_state.turn_timer = table.turn_timer;
+    game_state.table_talk_level = table.table_talk_level;
+    game_state.chat_prompt = table.chat_prompt;
+    game_state.keys = keys;
+    game_state.memory = Memory::load(&default_memory_path()).unwrap_or_else(|err| {
+        let error = err.to_string();
+        eprintln!("{}", Prompt::CouldNotRecallSessions { error });
+        Memory::default()
+    });
+    game_state.screen.repaint_on_resize();
+    let _terminal = TerminalGuard::new(&game_state.screen);
+    restore_on_panic(&game_state.screen);
+    // The game's saved at the start of every turn, that's where Ctrl-C leaves it
+    let saved = save_path.clone();
+    exit_on_signal(&game_state.screen, move || {
+        saved
+            .exists()
+            .then(|| Prompt::SavedAtStartOfTurn.to_string())
+    });
+
+    // A resumed game's audit starts from the next deal
+    let auditor = table.audit.then(|| {
+        let auditor = Arc::new(Mutex::new(Auditor::new(table.variant, table.deck.clone())));
+        game_state.engine.subscribe(auditor.clone());
+        auditor
+    });
+
+    let recorder = {
+        let engine = &game_state.engine;
+        Arc::new(Mutex::new(GameRecorder::new(
+            engine.players.iter().map(|p| p.name.as_str()),
+        )))
+    };
+    game_state.engine.subscribe(recorder.clone());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    game_state.engine.subscribe(events.clone());
+    if let Some(path) = &table.replay {
+        let file = fs::File::create(path).unwrap_or_else(|err| {
+            eprintln!("{}: {err}", path.display());
+            std::process::exit(1);
+        });
+        game_state
+            .engine
+            .subscribe(Arc::new(Mutex::new(ReplayWriter::new(file))));
+    }
+    #[cfg(feature = "audio")]
+    if !table.mute {
+        match rummy::audio::Soundboard::open() {
+            Ok(soundboard) => game_state
+                .engine
+                .subscribe(Arc::new(Mutex::new(soundboard))),
+            Err(err) => eprintln!(
+                "{}",
+                Prompt::WithoutSound {
+                    error: err.to_string()
+                }
+            ),
+        }
+    }
 
-            for &possible_draw_card in &possible_cards {
-                let mut draw_hand = current_player.hand.clone();
-                let (baseline_score, _hand) = calculate_best_meld_from_hand(&draw_hand);
-                draw_hand.cards.push(possible_draw_card);
+    if let Some(every) = table.discussion {
+        let moderator = Arc::new(Mutex::new(Moderator::new(every)));
+        game_state.engine.subscribe(moderator.clone());
+        game_state.moderator = Some(moderator);
+    }
 
-                let draw_node = Node {
-                    full_hand: draw_hand.clone(),
-                    possible_hands: Vec::new(),
-                    possible_cards: possible_cards.clone(),
-                    discard_pile: game_state.deck.borrow().discard_pile.clone(),
-                    meld_score: None,
-                    baseline_score,
-                    branches: Vec::new(),
-                    depth: 0,
-                };
+    // A resumed game buys in again, and the round in play has no pot
+    if let Some(stakes) = table.stakes {
+        let bank = {
+            let engine = &game_state.engine;
+            Bank::new(engine.players.iter().map(|p| p.name.as_str()), stakes)
+        };
+        let bank = Arc::new(Mutex::new(bank));
+        game_state.engine.subscribe(bank.clone());
+        game_state.bank = Some(bank);
+    }
 
-                let prob_analysis = draw_node.calculate_cumulative_probabilities();
-                let decision =
-                    draw_node.make_autoplay_decision(player_type.clone(), &prob_analysis);
+    // A resumed game already knows who goes first
+    if !resume {
+        if table.quiz {
+            game_state.run_quiz().await;
+        }
+        if let Err(err) = game_state.decide_turn_order(table.turn_order).await {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
 
-                total_draw_score += decision.expected_score;
-                draw_scenarios += 1;
-            }
+    let winning_player = play_game(&mut game_state).await;
 
-            let average_draw_score = if draw_scenarios > 0 {
-                total_draw_score / draw_scenarios as f64
-            } else {
-                0.0
-            };
+    let discussion = if table.recap {
+        game_state.recap_discussion().await
+    } else {
+        None
+    };
+    let summary = recorder
+        .lock()
+        .unwrap()
+        .summary(&game_state.book)
+        .map(|summary| GameSummary {
+            discussion: discussion.clone(),
+            ..summary
+        });
 
-            let draw_decision = AutoPlayDecision {
-                action: PlayAction::Draw,
-                confidence: 0.5,
-                expected_score: average_draw_score,
-                card_to_discard: None,
-            };
+    // Losing the record shouldn't spoil the ending
+    let result = recorder
+        .lock()
+        .unwrap()
+        .result(&game_state.book)
+        .map(|result| GameResult {
+            score_sheet: Some(game_state.engine.score_sheet().clone()),
+            ..result
+        });
+    if let Some(result) = result
+        && let Err(err) = StatsStore::default().append(&result)
+    {
+        let error = err.to_string();
+        eprintln!("{}", Prompt::CouldNotRecord { error });
+    }
 
-            let final_decision = if retrieve_decision.expected_score > draw_decision.expected_score
-            {
-                if retrieve_decision.action == PlayAction::Play {
-                    AutoPlayDecision {
-                        action: PlayAction::Play,
-                        confidence: retrieve_decision.confidence,
-                        expected_score: retrieve_decision.expected_score,
-                        card_to_discard: None,
-                    }
-                } else {
-                    AutoPlayDecision {
-                        action: PlayAction::Retrieve,
-                        confidence: retrieve_decision.confidence,
-                        expected_score: retrieve_decision.expected_score,
-                        card_to_discard: retrieve_decision.card_to_discard,
-                    }
-                }
-            } else {
-                draw_decision
-            };
+    // Kept until it's archived, or the next game takes its place
+    let mut record = MatchRecord::new(
+        &game_state.book,
+        &game_state.engine,
+        std::mem::take(&mut *events.lock().unwrap()),
+        &recorder.lock().unwrap(),
+    );
+    if let Some(summary) = &mut record.summary {
+        summary.discussion = discussion;
+    }
+    let last = Archive::new(last_match_path());
+    let _ = std::fs::remove_file(last.path());
+    if let Err(err) = last.append(&record) {
+        let error = err.to_string();
+        eprintln!("{}", Prompt::CouldNotArchive { error });
+    }
 
-            match final_decision.action {
-                PlayAction::Play => {
-                    let (score, melded_hand) =
-                        calculate_best_meld_from_5_card_hand(&current_player.hand);
-
-                    game_state.add_message(format!(
-                        "{} played their hand with score: {}",
-                        &current_player.name, score
-                    ));
-
-                    // Use game_state.players for layoff round
-                    let layoff_players = game_state.players.borrow().clone();
-
-                    let (lay_offs, _winning_hand) = run_layoff_round(
-                        current_idx,
-                        &melded_hand, // Pass the melded hand, not the original
-                        &layoff_players,
-                        score,
-                        &game_state,
-                    )
-                    .await;
-
-                    if let Some(mut lay_offs) = lay_offs {
-                        lay_offs.sort_by(|a, b| b.resulting_score.cmp(&a.resulting_score));
-                        let winning_lay_off = lay_offs[0].clone();
-
-                        let layoff_score = if winning_lay_off.cards_used == 2 {
-                            0
-                        } else {
-                            winning_lay_off.resulting_score
-                        };
-
-                        game_state.add_action(
-                            &winning_lay_off.player.name,
-                            &format!(
-                                "won this round with a score of {} and the hand {}",
-                                &layoff_score, &winning_lay_off.resulting_hand
-                            ),
-                            None,
-                        );
-
-                        game_state.update_scores(&winning_lay_off.player, layoff_score as usize);
-                    } else {
-                        game_state.add_action(
-                            &current_player.name,
-                            &format!(
-                                "won this round with a score of {} and the hand {}",
-                                score,
-                                &melded_hand // Use melded_hand here too
-                            ),
-                            None,
-                        );
-
-                        // Update score using current_player reference
-                        game_state.update_scores(&current_player, score as usize);
-                    }
+    // The talk's remembered next time the club reads the book
+    let mut memory = game_state.memory.clone();
+    memory.remember(
+        &game_state.book,
+        record.config.seats.iter().map(|seat| seat.name.as_str()),
+        &record.transcript,
+    );
+    if let Err(err) = memory.save(&default_memory_path()) {
+        let error = err.to_string();
+        eprintln!("{}", Prompt::CouldNotRemember { error });
+    }
 
-                    // Deal new round after someone wins
-                    game_state.deal_new_round();
-                }
-                PlayAction::Draw => {
-                    let drawn_card =
-                        if let Some(card) = game_state.deck.borrow_mut().draw_pile.pop_back() {
-                            card
-                        } else {
-                            game_state.deck.borrow_mut().reshuffle();
-                            game_state.deck.borrow_mut().draw_pile.pop_back().unwrap()
-                        };
-
-                    current_player.hand.cards.push(drawn_card);
-                    let (baseline_score, _hand) =
-                        calculate_best_meld_from_hand(&current_player.hand);
-
-                    let actual_hand = current_player.hand.clone();
-                    let node = Node {
-                        full_hand: actual_hand,
-                        possible_hands: Vec::new(),
-                        possible_cards: game_state
-                            .deck
-                            .borrow()
-                            .draw_pile
-                            .iter()
-                            .cloned()
-                            .collect(),
-                        discard_pile: game_state.deck.borrow().discard_pile.clone(),
-                        meld_score: None,
-                        baseline_score,
-                        branches: Vec::new(),
-                        depth: 0,
-                    };
-
-                    let discard_card = node.find_worst_card_to_discard();
-                    let idx = current_player
-                        .hand
-                        .cards
-                        .iter()
-                        .position(|c| *c == discard_card)
-                        .unwrap();
-                    let discarded = current_player.hand.cards.remove(idx);
-                    game_state
-                        .deck
-                        .borrow_mut()
-                        .discard_pile
-                        .push_back(discarded);
-
-                    game_state.add_action(
-                        &current_player.name,
-                        "drew and discarded the",
-                        Some(discarded),
-                    );
-
-                    // Update the player in game_state
-                    game_state.players.borrow_mut()[current_idx] = current_player.clone();
-                }
-                PlayAction::Retrieve => {
-                    let discard_card = game_state
-                        .deck
-                        .borrow_mut()
-                        .discard_pile
-                        .pop_back()
-                        .unwrap();
-                    current_player.hand.cards.push(discard_card);
-
-                    let (baseline_score, _hand) =
-                        calculate_best_meld_from_hand(&current_player.hand);
-
-                    let node = Node {
-                        full_hand: current_player.hand.clone(),
-                        possible_hands: Vec::new(),
-                        possible_cards: game_state
-                            .deck
-                            .borrow()
-                            .draw_pile
-                            .iter()
-                            .cloned()
-                            .collect(),
-                        discard_pile: game_state.deck.borrow().discard_pile.clone(),
-                        meld_score: None,
-                        baseline_score,
-                        branches: Vec::new(),
-                        depth: 0,
-                    };
-
-                    let worst_card = node.find_worst_card_to_discard();
-                    let idx = current_player
-                        .hand
-                        .cards
-                        .iter()
-                        .position(|c| *c == worst_card)
-                        .unwrap();
-                    let discarded = current_player.hand.cards.remove(idx);
-                    game_state
-                        .deck
-                        .borrow_mut()
-                        .discard_pile
-                        .push_back(discarded);
-
-                    game_state.add_action(
-                        &current_player.name,
-                        "retrieved discard and discarded the",
-                        Some(discarded),
-                    );
-
-                    // Update the player in game_state
-                    game_state.players.borrow_mut()[current_idx] = current_player.clone();
-                }
-            }
+    // A finished game isn't worth resuming
+    let _ = std::fs::remove_file(&save_path);
 
-            players[current_idx] = current_player;
-        } else {
-            // Human player turn
-            let mut player_choice = None;
-            while player_choice.is_none() {
-                game_state
-                    .display(&current_player, "Draw (D), Play (P), or Retrieve (R)?")
-                    .await;
-
-                let mut input = String::new();
-                io::stdin()
-                    .read_line(&mut input)
-                    .expect("Failed to read line");
-
-                match parse_choice(input.trim()) {
-                    Ok(choice) => {
-                        game_state.clear_messages();
-                        player_choice = Some(choice)
-                    }
-                    Err(err) => {
-                        game_state.add_message(err);
-                    }
-                }
+    let transcript = game_state
+        .write_transcript()
+        .inspect_err(|err| {
+            let error = err.to_string();
+            eprintln!("{}", Prompt::CouldNotWriteOut { error });
+        })
+        .ok();
+
+    if winning_player.player_type.is_none() {
+        if let Some(summary) = &summary {
+            game_state
+                .show_summary(summary, transcript.as_deref())
+                .await;
+        }
+        game_state
+            .display_victory_animation(&winning_player.name, table.celebration)
+            .await;
+        // Left on screen once the celebration's cleared it
+        if let Some(auditor) = &auditor {
+            game_state.print_audit_report(&auditor.lock().unwrap());
+        }
+        game_state.print_disclosure();
+        game_state.wait_to_exit().await;
+    } else {
+        println!(
+            "\n{}",
+            Prompt::Victory {
+                player: winning_player.name.clone()
             }
+        );
+        game_state.print_final_scores();
+        if let Some(summary) = &summary {
+            print!("\n{summary}");
+        }
+        if let Some(path) = &transcript {
+            println!("{}", transcript_notice(path));
+        }
+        if let Some(auditor) = &auditor {
+            game_state.print_audit_report(&auditor.lock().unwrap());
+        }
+        game_state.print_disclosure();
+        std::process::exit(0);
+    }
+}
 
-            match player_choice.unwrap() {
-                Choice::Draw => {
-                    let drawn_card =
-                        if let Some(card) = game_state.deck.borrow_mut().draw_pile.pop_back() {
-                            card
-                        } else {
-                            game_state.deck.borrow_mut().reshuffle();
-                            game_state.deck.borrow_mut().draw_pile.pop_back().unwrap()
-                        };
-
-                    current_player.hand.cards.push(drawn_card);
-
-                    let mut discard_card = None;
-                    while discard_card.is_none() {
-                        game_state
-                            .display(&current_player, "Which card to discard?")
-                            .await;
+fn transcript_notice(path: &Path) -> String {
+    let path = path.display().to_string();
+    format!("\n{}", Prompt::TranscriptNotice { path })
+}
 
-                        let mut input = String::new();
-                        io::stdin()
-                            .read_line(&mut input)
-                            .expect("Failed to read line");
-
-                        match Card::from_string(input.trim().to_string()) {
-                            Ok(card) => {
-                                if current_player.hand.cards.contains(&card) {
-                                    game_state.clear_messages();
-                                    discard_card = Some(card);
-                                } else {
-                                    game_state.add_message("You don't have that card!".to_string());
-                                }
-                            }
-                            Err(err) => {
-                                game_state.add_message(format!("Invalid card format: {err}"));
-                            }
-                        }
-                    }
+/// Asks who is playing and what they're reading, and deals the first round.
+/// The AI players are seated from `profiles` instead, if there are any, and
+/// any asked for play like `ai`.
+/// More than one human makes a hot-seat game, passing the keyboard round.
+fn new_local_game(
+    deck: DeckConfig,
+    variant: Variant,
+    ai: &PlayerType,
+    setup: &LocalSetup,
+) -> Result<(String, GameEngine), RummyError> {
+    let book = || match &setup.book {
+        Some(book) => Ok(book.clone()),
+        None => ask_book(),
+    };
+    if let Some(lineup) = &setup.lineup {
+        let players = lineup.players(&setup.profiles);
+        return Ok((book()?, GameEngine::pre_game(players, deck, variant)?));
+    }
 
-                    let card = discard_card.unwrap();
-                    let idx = current_player
-                        .hand
-                        .cards
-                        .iter()
-                        .position(|c| *c == card)
-                        .unwrap();
-                    current_player.hand.cards.remove(idx);
-                    game_state.deck.borrow_mut().discard_pile.push_back(card);
-
-                    game_state.add_action(
-                        &current_player.name,
-                        "drew and discarded the",
-                        Some(card),
-                    );
-
-                    game_state
-                        .display(&current_player, "Join the conversation: ")
-                        .await;
+    let profiles = &setup.profiles;
+    if !profiles.players().is_empty() {
+        let humans = ask_humans(None)?;
+        let mut players: Vec<Player> = profiles
+            .players()
+            .iter()
+            .map(|profile| profile.player())
+            .collect();
+        for i in 0..humans {
+            let name = ask_name(i, humans, &players)?;
+            players.insert(i, human(&name));
+        }
 
-                    let mut dialogue = String::new();
-                    io::stdin()
-                        .read_line(&mut dialogue)
-                        .expect("Failed to read line");
+        return Ok((book()?, GameEngine::pre_game(players, deck, variant)?));
+    }
 
-                    game_state.push_dialogue(&current_player, dialogue.trim());
-                    game_state
-                        .player_dialogues
-                        .borrow_mut()
-                        .insert(current_player.name.clone(), dialogue);
+    let num_players = prompt_until(&Prompt::NumberOfPlayers.to_string(), |&n: &usize| {
+        if n >= 2 {
+            Ok(())
+        } else {
+            Err(Prompt::TooFewPlayers.to_string())
+        }
+    })?;
+    let humans = ask_humans(Some(num_players))?;
 
-                    // Update the player in game_state
-                    game_state.players.borrow_mut()[current_idx] = current_player.clone();
-                }
-                Choice::Play => {
-                    let (score, hand) = calculate_best_meld_from_5_card_hand(&current_player.hand);
+    let mut players: Vec<Player> = Vec::with_capacity(num_players);
+    for i in 0..num_players {
+        let name = ask_name(i, humans, &players)?;
+        if i < humans {
+            players.push(human(&name));
+            continue;
+        }
 
-                    let layoff_players = game_state.players.borrow().clone();
+        let description: String = prompt_or(
+            &Prompt::PlayerDescription.to_string(),
+            String::new(),
+            |_| Ok(()),
+        )?;
 
-                    let (lay_offs, _winning_hand) =
-                        run_layoff_round(current_idx, &hand, &layoff_players, score, &game_state)
-                            .await;
+        players.push(Player {
+            name,
+            description: normalize_dialogue(&description),
+            player_type: Some(ai.clone()),
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        });
+    }
 
-                    if let Some(mut lay_offs) = lay_offs {
-                        lay_offs.sort_by(|a, b| b.resulting_score.cmp(&a.resulting_score));
-                        let winning_lay_off = lay_offs[0].clone();
-
-                        let layoff_score = if winning_lay_off.cards_used == 2 {
-                            0
-                        } else {
-                            winning_lay_off.resulting_score
-                        };
-
-                        game_state.add_action(
-                            &winning_lay_off.player.name,
-                            &format!(
-                                "won this round with a score of {} and the hand {}",
-                                &layoff_score, &winning_lay_off.resulting_hand
-                            ),
-                            None,
-                        );
-
-                        game_state.update_scores(&winning_lay_off.player, layoff_score as usize);
-                        game_state.deal_new_round();
-                    } else {
-                        game_state.add_action(
-                            &current_player.name,
-                            &format!(
-                                "won this round with a score of {} and the hand {}",
-                                score, &hand
-                            ),
-                            None,
-                        );
-
-                        game_state.update_scores(&current_player.clone(), score as usize);
-                        game_state.deal_new_round();
-                    }
-                }
-                Choice::Retrieve => {
-                    let discard_visible = game_state
-                        .deck
-                        .borrow_mut()
-                        .discard_pile
-                        .pop_back()
-                        .unwrap();
-                    current_player.hand.cards.push(discard_visible);
-
-                    let mut discard_card = None;
-                    while discard_card.is_none() {
-                        game_state.clear_messages();
-                        game_state
-                            .display(&current_player, "Which card to discard?")
-                            .await;
+    Ok((book()?, GameEngine::pre_game(players, deck, variant)?))
+}
 
-                        let mut input = String::new();
-                        io::stdin()
-                            .read_line(&mut input)
-                            .expect("Failed to read line");
-
-                        match Card::from_string(input.trim().to_string()) {
-                            Ok(card) => {
-                                if current_player.hand.cards.contains(&card) {
-                                    game_state.clear_messages();
-                                    discard_card = Some(card);
-                                } else {
-                                    game_state.add_message("You don't have that card!".to_string());
-                                }
-                            }
-                            Err(err) => {
-                                game_state.add_message(format!("Invalid card format: {err}"));
-                            }
-                        }
-                    }
+/// Asks how many of the players are at the keyboard, at most `most` if
+/// there's a limit. They sit first.
+fn ask_humans(most: Option<usize>) -> Result<usize, RummyError> {
+    prompt_or(
+        &Prompt::NumberOfHumans.to_string(),
+        1,
+        |&n: &usize| match most {
+            _ if n < 1 => Err(Prompt::NoHumans.to_string()),
+            Some(most) if n > most => Err(Prompt::OnlyPlayers { most }.to_string()),
+            _ => Ok(()),
+        },
+    )
+}
 
-                    let card = discard_card.unwrap();
-                    let idx = current_player
-                        .hand
-                        .cards
-                        .iter()
-                        .position(|c| *c == card)
-                        .unwrap();
-                    current_player.hand.cards.remove(idx);
-                    game_state.deck.borrow_mut().discard_pile.push_back(card);
-
-                    game_state.add_action(
-                        &current_player.name,
-                        "retrieved the discard and discarded the",
-                        discard_card,
-                    );
-
-                    game_state
-                        .display(&current_player, "Join the conversation: ")
-                        .await;
+/// Asks for the name of the player in seat `i`, tidied up, which none of
+/// the `seated` can have. A lone human is just "you".
+fn ask_name(i: usize, humans: usize, seated: &[Player]) -> Result<String, RummyError> {
+    let question = if i == 0 && humans == 1 {
+        Prompt::YourName
+    } else {
+        Prompt::PlayerName { seat: i + 1 }
+    };
+    let name: String = prompt_until(&question.to_string(), |name: &String| {
+        let name = normalize_name(name);
+        if name.is_empty() {
+            Err(Prompt::NameNeeded.to_string())
+        } else if seated.iter().any(|player| player.name == name) {
+            Err(Prompt::NameTaken { name }.to_string())
+        } else {
+            Ok(())
+        }
+    })?;
 
-                    let mut dialogue = String::new();
-                    io::stdin()
-                        .read_line(&mut dialogue)
-                        .expect("Failed to read line");
+    Ok(normalize_name(&name))
+}
 
-                    game_state.push_dialogue(&current_player, dialogue.trim());
-                    game_state
-                        .player_dialogues
-                        .borrow_mut()
-                        .insert(current_player.name.clone(), dialogue);
+/// A human player called `name`, with an empty hand.
+fn human(name: &str) -> Player {
+    Player {
+        name: normalize_name(name),
+        description: String::new(),
+        player_type: None,
+        hand: Hand { cards: Vec::new() },
+        actions: VecDeque::new(),
+        dialogue: VecDeque::new(),
+        score: 0,
+    }
+}
 
-                    game_state.players.borrow_mut()[current_idx] = current_player.clone();
-                }
-            }
+/// Asks what the club is reading, East of Eden if they don't say.
+fn ask_book() -> Result<String, RummyError> {
+    let book = "East of Eden by John Steinbeck".to_string();
+    let question = Prompt::BookAndAuthor { book: book.clone() };
+    prompt_or(&question.to_string(), book, |_| Ok(()))
+}
 
-            players[current_idx] = current_player;
-        }
+/// An AI-only game with canned dialogue that deals again forever, for leaving
+/// on a screen at a book fair.
+async fn run_demo(table: &TableOptions) {
+    // One screen for every game, so resizes are only watched for once
+    let screen = Screen::default();
+    screen.repaint_on_resize();
+    let _terminal = TerminalGuard::new(&screen);
+    restore_on_panic(&screen);
+    exit_on_signal(&screen, || None);
 
-        // Get human player from game_state, not local players
-        let human_player = game_state
-            .players
-            .borrow()
-            .iter()
-            .find(|p| p.player_type.is_none())
-            .cloned()
+    loop {
+        let engine = GameEngine::pre_game(demo_players(), table.deck.clone(), table.variant)
+            .and_then(|mut engine| {
+                table.prepare(&mut engine, DEMO_BOOK)?;
+                Ok(engine)
+            })
+            .unwrap_or_else(|err| {
+                eprintln!("{err}");
+                std::process::exit(1);
+            });
+        let mut game_state = GameState::new(
+            DEMO_BOOK.to_string(),
+            engine,
+            Transcript::default(),
+            None,
+            DEMO_PACING,
+        );
+        game_state.screen = screen.clone();
+        game_state
+            .decide_turn_order(table.turn_order)
+            .await
             .unwrap();
 
-        game_state.display_updated_state(&human_player).await;
+        let winning_player = play_game(&mut game_state).await;
 
-        *game_state.current_player_idx.borrow_mut() =
-            (current_idx + 1) % game_state.players.borrow().len();
+        game_state.screen.forget();
+        clear_screen();
+        println!("{}\n", colored_book_title(&game_state.book));
+        println!(
+            "{}",
+            Prompt::Victory {
+                player: winning_player.name.clone()
+            }
+        );
+        game_state.print_final_scores();
+
+        tokio::time::sleep(DEMO_RESTART_PAUSE).await;
     }
 }
 
-fn check_for_layoff(
-    player: &Player,
-    played_hand: &Hand,
-    score_to_beat: u64,
-) -> Option<LayOffResult> {
-    let mut layoff_results = Vec::new();
+/// The demo table's AI players, seated in order.
+fn demo_players() -> Vec<Player> {
+    DEMO_PLAYERS
+        .iter()
+        .map(|(name, description, player_type)| Player {
+            name: name.to_string(),
+            description: description.to_string(),
+            player_type: Some(player_type.clone()),
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        })
+        .collect()
+}
 
-    for i in 0..(player.hand.cards.len().saturating_sub(1)) {
-        let card_to_test = player.hand.cards[i];
+/// The memory trainer: flashes the discards of a simulated round one at a
+/// time, then asks which cards went by.
+async fn run_practice(
+    deck: DeckConfig,
+    seed: Option<u64>,
+    discards: usize,
+    flash: Duration,
+) -> Result<(), RummyError> {
+    let mut engine = GameEngine::pre_game(demo_players(), deck.clone(), Variant::Bookclub)?;
+    if let Some(seed) = seed {
+        engine.set_seed(seed);
+    }
+    engine.deal_round()?;
+    let colors: Vec<ColoredName> = engine
+        .players
+        .iter()
+        .enumerate()
+        .map(|(idx, player)| ColoredName::new(player.name.clone(), idx))
+        .collect();
 
-        for j in 0..(played_hand.cards.len().saturating_sub(1)) {
-            let mut played_cards = played_hand.cards.clone();
-            played_cards.remove(j);
-            played_cards.push(card_to_test);
+    let info = Arc::new(Mutex::new(InformationSet::default()));
+    let events = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+    engine.subscribe(info.clone());
+    engine.subscribe(events.clone());
 
-            let resulting_hand = Hand {
-                cards: played_cards,
-            };
+    let turned_over = engine.top_discard().copied();
+    if let Some(card) = turned_over {
+        info.lock().unwrap().see(card);
+    }
 
-            let (score, _hand) = calculate_best_meld_from_5_card_hand(&resulting_hand);
-            let layoff_result = LayOffResult {
-                player: player.clone(),
-                cards_laid_off: vec![card_to_test],
-                resulting_hand,
-                resulting_score: score,
-                cards_used: 1,
-            };
-            layoff_results.push(layoff_result);
+    // Play until enough cards have gone by, or someone lays their hand down
+    let mut discarded = 0;
+    while discarded < discards {
+        match engine.take_ai_turn()? {
+            AiTurn::Played { .. } => break,
+            _ => discarded += 1,
         }
     }
 
-    layoff_results.retain(|r| r.resulting_score != 0);
-
-    let mut two_card_layoff_combos = Vec::new();
-    for i in 0..(player.hand.cards.len().saturating_sub(1)) {
-        for j in (i + 1)..player.hand.cards.len() {
-            let two_card_combo = vec![player.hand.cards[i], player.hand.cards[j]];
-            two_card_layoff_combos.push(two_card_combo);
-        }
+    let mut shown = Vec::new();
+    if let Some(card) = turned_over {
+        let card = pretty(&card);
+        shown.push(Prompt::TurnedOver { card }.to_string());
     }
+    for event in events.lock().unwrap().iter() {
+        let name = |player: &str| match find_color(&colors, player) {
+            Some(colored) => colored.colored(),
+            None => player.to_string(),
+        };
 
-    let mut two_card_played_hand_combos = Vec::new();
-    for i in 0..(played_hand.cards.len().saturating_sub(1)) {
-        for j in (i + 1)..played_hand.cards.len() {
-            let two_card_combo = vec![played_hand.cards[i], played_hand.cards[j]];
-            two_card_played_hand_combos.push(two_card_combo);
+        match event {
+            GameEvent::CardDiscarded { player, card } => {
+                let discards = Prompt::DiscardsCard {
+                    player: name(player),
+                    card: pretty(card),
+                };
+                shown.push(discards.to_string());
+            }
+            GameEvent::HandPlayed { player, hand, .. } => {
+                let hand: Vec<String> = hand.iter().map(pretty).collect();
+                let plays = Prompt::PlaysHand {
+                    player: name(player),
+                    cards: hand.join(" "),
+                };
+                shown.push(plays.to_string());
+            }
+            _ => {}
         }
     }
 
-    for two_card_played_hand_combo in two_card_played_hand_combos {
-        let mut played_cards = played_hand.cards.clone();
-        played_cards.retain(|c| !two_card_played_hand_combo.contains(c));
-        for two_card_layoff_combo in two_card_layoff_combos.clone() {
-            let mut played_cards = played_cards.clone();
-            let cards_laid_off = two_card_layoff_combo.clone();
-            played_cards.extend(cards_laid_off.clone());
-            let resulting_hand = Hand {
-                cards: played_cards.clone(),
-            };
-            let (score, _hand) = calculate_best_meld_from_hand(&resulting_hand);
-            let layoff_result = LayOffResult {
-                player: player.clone(),
-                cards_laid_off: two_card_layoff_combo,
-                resulting_hand,
-                resulting_score: score,
-                cards_used: 2,
-            };
-            layoff_results.push(layoff_result);
-        }
+    for line in shown {
+        clear_screen();
+        println!("{}\n\n{line}", Prompt::MemoryTrainer);
+        tokio::time::sleep(flash).await;
+        clear_screen();
+        tokio::time::sleep(flash / 4).await;
     }
 
-    let mut one_card_layoff_results: Vec<LayOffResult> = layoff_results
-        .iter()
-        .filter(|l| l.cards_used == 1 && l.resulting_score > 0)
-        .cloned()
-        .collect();
+    let info = info.lock().unwrap().clone();
+    let mut rng = engine.rng().fork(RngStream::Effects);
+    let questions = practice_questions(&info, &deck, &mut rng)?;
+    let mut correct = 0;
+    for (idx, (card, seen)) in questions.iter().enumerate() {
+        let went_by = Prompt::WentBy {
+            number: idx + 1,
+            asked: questions.len(),
+            card: pretty(card),
+        };
+        println!("{}", highlighted(&went_by.to_string()));
 
-    let mut two_card_layoff_results: Vec<LayOffResult> = layoff_results
-        .iter()
-        .filter(|l| l.cards_used == 2 && l.resulting_score > 0)
-        .cloned()
-        .collect();
+        let mut answer = String::new();
+        io::stdin()
+            .read_line(&mut answer)
+            .map_err(|err| RummyError::Io(err.to_string()))?;
+        let said_seen = i18n::locale().is_yes(&answer);
+
+        if said_seen == *seen {
+            correct += 1;
+            println!("{}\n", Prompt::QuizRight);
+        } else if *seen {
+            println!("{}\n", Prompt::ItWentBy);
+        } else {
+            println!("{}\n", Prompt::StillOutThere);
+        }
+    }
 
-    let layoff_result = if !one_card_layoff_results.is_empty() {
-        one_card_layoff_results.sort_by(|a, b| b.resulting_score.cmp(&a.resulting_score));
-        one_card_layoff_results.first().cloned()
-    } else if !two_card_layoff_results.is_empty() {
-        two_card_layoff_results.sort_by(|a, b| b.resulting_score.cmp(&a.resulting_score));
-        two_card_layoff_results.first().cloned()
-    } else {
-        None
+    let scored = Prompt::TrainerScored {
+        correct,
+        asked: questions.len(),
     };
-
-    layoff_result.filter(|result| result.resulting_score > score_to_beat)
+    println!("{scored}");
+    let seen: Vec<String> = info.seen().iter().map(pretty).collect();
+    let seen = Prompt::CardsThatWentBy {
+        cards: seen.join(" "),
+    };
+    println!("{seen}");
+    Ok(())
 }
 
-fn winning_player(gs: &GameState) -> Option<Player> {
-    let winning_players: Vec<Player> = gs
-        .players
-        .borrow()
-        .iter()
-        .filter(|p| p.score >= 100)
-        .cloned()
+/// Cards to quiz on and whether each went by: as many seen cards as unseen
+/// ones, so answering all one way doesn't pay.
+fn practice_questions<R: Rng + ?Sized>(
+    info: &InformationSet,
+    deck: &DeckConfig,
+    rng: &mut R,
+) -> Result<Vec<(Card, bool)>, RummyError> {
+    let per_side = PRACTICE_QUESTIONS / 2;
+
+    let mut seen = info.seen().to_vec();
+    seen.shuffle(rng);
+    // Every copy of an unseen card is unseen, but it only needs asking about once
+    let mut unseen = info.unseen(deck)?;
+    let mut asked = HashSet::new();
+    unseen.retain(|card| asked.insert(*card));
+    unseen.shuffle(rng);
+
+    let mut questions: Vec<(Card, bool)> = seen
+        .into_iter()
+        .take(per_side)
+        .map(|card| (card, true))
+        .chain(unseen.into_iter().take(per_side).map(|card| (card, false)))
         .collect();
+    questions.shuffle(rng);
 
-    winning_players.first().cloned()
+    Ok(questions)
 }
 
-fn parse_choice(input: &str) -> Result<Choice, String> {
-    match input.to_lowercase().as_str() {
-        "d" | "draw" => Ok(Choice::Draw),
-        "p" | "play" => Ok(Choice::Play),
-        "r" | "retrieve" => Ok(Choice::Retrieve),
-        _ => Err("Invalid input. Expected D (draw) or P (play) or R (retrieve).".to_string()),
+/// Plays turns until someone reaches the target score, or the chips decide
+/// it, and returns the winner.
+async fn play_game(game_state: &mut GameState) -> Player {
+    loop {
+        let on_chips = game_state
+            .bank
+            .as_ref()
+            .and_then(|bank| bank.lock().unwrap().match_winner().map(str::to_string));
+        if let Some(leader) = on_chips
+            && game_state.engine.winner().is_none()
+        {
+            game_state
+                .engine
+                .call_game(&leader)
+                .expect("the chip leader is at the table");
+            game_state.add_message(Prompt::WinsOnChips { player: leader }.to_string());
+        }
+
+        if let Some(winning_player) = game_state.engine.winner().cloned() {
+            return winning_player;
+        }
+
+        game_state.autosave();
+
+        let current_idx = game_state.engine.current_player_idx;
+        let current_player = game_state.engine.current_player().clone();
+
+        game_state.moderate().await;
+        game_state.update_current_player_dialogue().await;
+
+        if current_player.player_type.is_some() {
+            // AI Player Turn
+            let decide = game_state.engine.decide();
+            let decision = decide.await.expect("it's an AI player's turn");
+            let turn = game_state.engine.play_ai_decision(decision).unwrap();
+
+            if let AiTurn::Played { score, .. } = turn {
+                let played = Prompt::AiPlayed {
+                    player: current_player.name.clone(),
+                    score,
+                    knocked: game_state.engine.variant == Variant::Gin,
+                };
+                game_state.add_message(played.to_string());
+
+                run_layoff_round(game_state).await;
+            }
+        } else {
+            // Human player turn
+            game_state.hand_to(&current_player).await;
+            // Whatever they do, the AI after them is ready for it
+            game_state.engine.precompute_next_turn();
+            // Undoing back to the start of the turn doesn't stop the clock
+            if game_state.turn_deadline.is_none() {
+                game_state.start_turn_clock();
+            }
+            let mut player_choice = None;
+            let mut timed_out = false;
+            while player_choice.is_none() {
+                let prompt = game_state.keys.prompt(&Key::ALL);
+                let Some(input) = game_state.ask_in_time(&current_player, &prompt).await else {
+                    game_state.play_out_of_time(&current_player);
+                    timed_out = true;
+                    break;
+                };
+
+                // The human at the keyboard is the host
+                if input.trim().eq_ignore_ascii_case("redeal") {
+                    if game_state.confirm_redeal(&current_player).await {
+                        break;
+                    }
+                    continue;
+                }
+
+                match game_state.keys.pressed(&input) {
+                    Some(Key::Hint) => game_state.hint(),
+                    Some(Key::Odds) => game_state.show_odds = !game_state.show_odds,
+                    Some(Key::Discards) => game_state.show_discards = !game_state.show_discards,
+                    Some(Key::Draw) => player_choice = Some(Choice::Draw),
+                    Some(Key::Play) => {
+                        // Playing a gin hand is knocking
+                        player_choice = match game_state.engine.variant {
+                            Variant::Gin => Some(Choice::Knock),
+                            Variant::Bookclub | Variant::SevenCard => Some(Choice::Play),
+                        }
+                    }
+                    Some(Key::Retrieve) => player_choice = Some(Choice::Retrieve),
+                    None => game_state.add_message(
+                        Prompt::InvalidInput {
+                            prompt: prompt.clone(),
+                        }
+                        .to_string(),
+                    ),
+                }
+            }
+            if player_choice.is_some() {
+                game_state.clear_messages();
+            }
+
+            match player_choice {
+                Some(choice @ (Choice::Draw | Choice::Retrieve)) => {
+                    let staged = game_state.engine.stage(choice);
+                    if let Err(err) = staged {
+                        game_state.add_message(err.to_string());
+                        continue;
+                    }
+                    if !game_state.confirm_staged_turn(&current_player).await {
+                        continue;
+                    }
+
+                    // No one's waiting on a player who's run out of time to talk
+                    if !game_state.out_of_time() {
+                        let player = game_state.engine.players[current_idx].clone();
+                        game_state.prompt_for_dialogue(&player).await;
+                    }
+                }
+                Some(Choice::Play | Choice::Knock) => {
+                    // Gin only lets you knock with little enough deadwood
+                    let played = game_state.engine.play();
+                    if let Err(err) = played {
+                        game_state.add_message(err.to_string());
+                        continue;
+                    }
+
+                    run_layoff_round(game_state).await;
+                }
+                // Played for them, which may have been their hand
+                None if timed_out => {
+                    if *game_state.engine.phase() == TurnPhase::LayingOff {
+                        run_layoff_round(game_state).await;
+                    }
+                }
+                // Called off the round, the turn starts over with the new hand
+                None => continue,
+            }
+            game_state.stop_turn_clock();
+        }
+
+        if *game_state.engine.phase() == TurnPhase::Declaring {
+            run_declarations(game_state).await;
+        }
+
+        let viewer = game_state.viewer(current_idx);
+        game_state.display_updated_state(&viewer).await;
     }
 }
+
+/// Clears the table away, says `farewell` and ends the program.
+fn quit(farewell: &str) -> ! {
+    clear_screen();
+    restore_terminal();
+    println!("{farewell}");
+    std::process::exit(0);
+}
+
+/// `prompt` with how long's left until `deadline` on the end, if there is one.
+fn time_left(prompt: &str, deadline: Option<Instant>) -> String {
+    let Some(deadline) = deadline else {
+        return prompt.to_string();
+    };
+    let secs = deadline
+        .saturating_duration_since(Instant::now())
+        .as_secs_f64()
+        .ceil() as u64;
+    let asked = prompt.trim_end();
+    let trailing = &prompt[asked.len()..];
+    let left = Prompt::SecondsLeft { secs };
+    format!("{asked} {}{trailing}", dimmed(&format!("({left})")))
+}
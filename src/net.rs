@@ -0,0 +1,9 @@
+//! Playing over the network: the JSON protocol, the WebSocket server and the
+//! terminal client that joins it. The protocol builds without the `cli`
+//! feature, for front-ends that bring their own transport.
+
+#[cfg(feature = "cli")]
+pub mod client;
+pub mod protocol;
+#[cfg(feature = "cli")]
+pub mod server;
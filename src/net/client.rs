@@ -0,0 +1,540 @@
+use crate::analysis::{KnownInformation, advise};
+use crate::card::Card;
+use crate::config::Key;
+use crate::dialogue::normalize_dialogue;
+use crate::display::input::Input;
+use crate::display::prompt::{Cards, answer};
+use crate::display::terminal::{
+    ColoredName, Said, Screen, TableFrame, TerminalGuard, action_line, clear_screen,
+    exit_on_signal, layoff_chain, restore_on_panic, score_sheet_panel,
+};
+use crate::engine::TurnPhase;
+use crate::error::RummyError;
+use crate::frontend::commands::{self, Command};
+use crate::game::{Hand, PlayAction, PlayerType};
+use crate::i18n::{self, Prompt};
+use crate::net::protocol::{
+    self, ClientMessage, DECLARE_WINDOW, PlayerAction, ServerMessage, TableView,
+};
+use crate::rng::SeedDisclosure;
+use crate::stats::GameSummary;
+use futures_util::{SinkExt, StreamExt};
+use tokio::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Joins the game hosted at `url` as `name` and plays it from this terminal
+/// until someone wins or the server hangs up.
+pub async fn run(url: &str, name: &str) -> Result<(), RummyError> {
+    let (socket, _response) = tokio_tungstenite::connect_async(url).await.map_err(|err| {
+        let error = err.to_string();
+        let url = url.to_string();
+        RummyError::Network(Prompt::CouldNotConnect { url, error }.to_string())
+    })?;
+    let (mut sink, mut source) = socket.split();
+
+    let join = ClientMessage::Join {
+        name: name.to_string(),
+    };
+    sink.send(encode(&join)?)
+        .await
+        .map_err(|err| RummyError::Network(err.to_string()))?;
+
+    let input = Input::default();
+    let mut client = Client::default();
+    client.screen.repaint_on_resize();
+    let _terminal = TerminalGuard::new(&client.screen);
+    restore_on_panic(&client.screen);
+    exit_on_signal(&client.screen, || None);
+    // Counts down the time left to declare, or to take our turn
+    let mut countdown = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            frame = source.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    let message = protocol::decode::<ServerMessage>(text.as_str())?;
+
+                    if let Some(winner) = client.receive(message) {
+                        clear_screen();
+                        println!("{}", Prompt::Victory { player: winner });
+                        if let Some(summary) = &client.summary {
+                            print!("\n{summary}");
+                        }
+                        if let Some(disclosure) = &client.disclosure {
+                            print!("\n{disclosure}");
+                        }
+                        return Ok(());
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(RummyError::Network(Prompt::ConnectionClosed.to_string()));
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(RummyError::Network(err.to_string())),
+            },
+            line = input.next_line() => match line {
+                // PgUp, PgDn and /history only scroll the table
+                Some(line) => match commands::scroll(&line) {
+                    Some(scroll) => client.screen.scroll(scroll),
+                    None if matches!(commands::parse(&line), Some(Ok(Command::Quit))) => {
+                        return Ok(());
+                    }
+                    None => match client
+                        .redeal_input(&line)
+                        .unwrap_or_else(|| parse_input(client.table.as_ref(), &line))
+                    {
+                        Ok(Some(message)) => {
+                            // A new move replaces the complaint about the last one
+                            if matches!(message, ClientMessage::Action { .. }) {
+                                client.message = None;
+                            }
+                            sink.send(encode(&message)?)
+                                .await
+                                .map_err(|err| RummyError::Network(err.to_string()))?;
+                        }
+                        Ok(None) => client.render(),
+                        Err(message) => {
+                            client.message = Some(message);
+                            client.render();
+                        }
+                    },
+                },
+                // Stdin closed, nothing more to say
+                None => return Ok(()),
+            },
+            _ = countdown.tick(), if client.declare_deadline.is_some() || client.turn_deadline.is_some() => {
+                client.render()
+            }
+        }
+    }
+}
+
+fn encode(message: &ClientMessage) -> Result<Message, RummyError> {
+    Ok(Message::Text(protocol::encode(message)?.into()))
+}
+
+/// Turns a line typed at the prompt into a message for the server.
+///
+/// On your turn the line is an action for the current phase, otherwise it's
+/// table talk. A slash command works either way. `Ok(None)` means there's
+/// nothing to send.
+fn parse_input(table: Option<&TableView>, line: &str) -> Result<Option<ClientMessage>, String> {
+    let line = line.trim();
+
+    if let Some(command) = commands::parse(line) {
+        return command_input(table, command.map_err(|err| err.to_string())?);
+    }
+
+    // Declarations are open to everyone the discard completes a meld for,
+    // whoever's turn it is
+    if let Some(table) = table
+        && table
+            .declare
+            .as_ref()
+            .is_some_and(|declare| declare.open_to_you)
+    {
+        let action = match line.to_lowercase().as_str() {
+            answer if i18n::locale().is_yes(answer) => PlayerAction::Declare,
+            "declare" => PlayerAction::Declare,
+            "n" | "no" | "pass" | "" => PlayerAction::Pass,
+            _ => return Err(Prompt::DeclareOrPass.to_string()),
+        };
+        return Ok(Some(ClientMessage::Action { action }));
+    }
+
+    let Some(table) = table.filter(|table| table.acting_player == table.seat) else {
+        let text = normalize_dialogue(line);
+        if text.is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some(ClientMessage::Chat { text }));
+    };
+
+    let action = match table.phase {
+        TurnPhase::Seating | TurnPhase::Quiz => {
+            return Err(Prompt::NotStarted.to_string());
+        }
+        TurnPhase::Choosing => match line.to_lowercase().as_str() {
+            "d" | "draw" => PlayerAction::Draw,
+            "p" | "play" => PlayerAction::Play,
+            "r" | "retrieve" => PlayerAction::Retrieve,
+            _ => return Err(Prompt::DrawPlayOrRetrieve.to_string()),
+        },
+        TurnPhase::Discarding => {
+            let card = answer::<Card>(line, |_| Ok(()))?;
+            PlayerAction::Discard { card }
+        }
+        TurnPhase::LayingOff => {
+            let Cards(cards) = answer(line, |_| Ok(()))?;
+            PlayerAction::LayOff { cards }
+        }
+        TurnPhase::Declaring => {
+            return Err(Prompt::MayBeDeclared.to_string());
+        }
+    };
+
+    Ok(Some(ClientMessage::Action { action }))
+}
+
+/// What a slash command comes to over the network. Only `/say` is sent,
+/// everything else is answered here, in the message line.
+fn command_input(
+    table: Option<&TableView>,
+    command: Command,
+) -> Result<Option<ClientMessage>, String> {
+    let not_started = || Prompt::NotStarted.to_string();
+
+    let answer = match command {
+        Command::Say(text) => {
+            let text = normalize_dialogue(&text);
+            return Ok((!text.is_empty()).then_some(ClientMessage::Chat { text }));
+        }
+        // Scrolled and quit before they get here
+        Command::History | Command::Quit => return Ok(None),
+        Command::Help => Prompt::Commands.to_string(),
+        Command::Hand => commands::hand_line(&table.ok_or_else(not_started)?.hand),
+        Command::Scores => {
+            let scores: Vec<(String, usize)> = table
+                .ok_or_else(not_started)?
+                .players
+                .iter()
+                .map(|seat| (seat.name.clone(), seat.score))
+                .collect();
+            commands::scores_line(&scores)
+        }
+        Command::Hint => {
+            let table = table
+                .filter(|table| table.acting_player == table.seat)
+                .filter(|table| table.phase == TurnPhase::Choosing)
+                .ok_or_else(|| Prompt::HintsAtStartOfTurn.to_string())?;
+            let hand = Hand {
+                cards: table.hand.clone(),
+            };
+            let known = KnownInformation {
+                discards: table.top_discard.into_iter().collect(),
+                ..KnownInformation::default()
+            };
+            let advice = match advise(&hand, &known, PlayerType::Balanced).action {
+                PlayAction::Play => Key::Play,
+                PlayAction::Draw => Key::Draw,
+                PlayAction::Retrieve => Key::Retrieve,
+            };
+            Prompt::Advice(advice).to_string()
+        }
+        Command::Save => Prompt::HostSaves.to_string(),
+    };
+    Err(answer)
+}
+
+/// What the client knows about the table, rebuilt from the server's snapshots.
+#[derive(Default)]
+struct Client {
+    table: Option<TableView>,
+    colors: Vec<ColoredName>,
+    dialogue: Vec<Said>,
+    message: Option<String>,
+    /// Waiting on a yes or no to the redeal.
+    confirming_redeal: bool,
+    /// When the server stops waiting on a declaration from us.
+    declare_deadline: Option<Instant>,
+    /// When the server plays the turn it's waiting on us for, if the table
+    /// plays to a clock.
+    turn_deadline: Option<Instant>,
+    /// The server's seed and deal hashes, once the game's over.
+    disclosure: Option<SeedDisclosure>,
+    /// How the game went, once it's over.
+    summary: Option<GameSummary>,
+    screen: Screen,
+}
+
+impl Client {
+    /// Handles `redeal` and the question that follows it, so a slip of the
+    /// keyboard can't throw a round away. `None` when `line` is neither.
+    fn redeal_input(&mut self, line: &str) -> Option<Result<Option<ClientMessage>, String>> {
+        let line = line.trim().to_lowercase();
+
+        if std::mem::take(&mut self.confirming_redeal) {
+            return Some(match line.as_str() {
+                answer if i18n::locale().is_yes(answer) => Ok(Some(ClientMessage::Redeal)),
+                _ => Err(Prompt::CarryOn.to_string()),
+            });
+        }
+        if line == "redeal" {
+            self.confirming_redeal = true;
+            return Some(Err(Prompt::ConfirmRedeal.to_string()));
+        }
+
+        None
+    }
+
+    /// Applies a message from the server and redraws. Returns the winner once
+    /// the game is over.
+    fn receive(&mut self, message: ServerMessage) -> Option<String> {
+        match message {
+            ServerMessage::Lobby { players, seats } => {
+                self.screen.forget();
+                clear_screen();
+                println!("{}", Prompt::WaitingForPlayers { players, seats });
+                return None;
+            }
+            ServerMessage::State { table } => {
+                if self.colors.len() != table.players.len() {
+                    self.colors = table
+                        .players
+                        .iter()
+                        .enumerate()
+                        .map(|(i, seat)| ColoredName::new(seat.name.clone(), i))
+                        .collect();
+                }
+                let declaring = table
+                    .declare
+                    .as_ref()
+                    .is_some_and(|declare| declare.open_to_you);
+                self.declare_deadline = match self.declare_deadline {
+                    Some(deadline) if declaring => Some(deadline),
+                    _ if declaring => Some(Instant::now() + DECLARE_WINDOW),
+                    _ => None,
+                };
+                self.turn_deadline = table
+                    .turn_seconds_left
+                    .filter(|_| table.acting_player == table.seat)
+                    .map(|secs| Instant::now() + Duration::from_secs(secs));
+                self.table = Some(table);
+            }
+            ServerMessage::Chat { from, text } => self.dialogue.push(Said {
+                speaker: from,
+                text,
+            }),
+            ServerMessage::Error { message } => self.message = Some(message),
+            ServerMessage::GameOver {
+                winner,
+                disclosure,
+                summary,
+            } => {
+                self.disclosure = disclosure;
+                self.summary = summary;
+                self.screen.forget();
+                return Some(winner);
+            }
+        }
+
+        self.render();
+        None
+    }
+
+    fn render(&self) {
+        let Some(table) = &self.table else {
+            return;
+        };
+
+        let prompt =
+            if let (Some(declare), Some(deadline)) = (&table.declare, self.declare_deadline) {
+                let left = deadline.saturating_duration_since(Instant::now()).as_secs();
+                format!(
+                    "{} {} {}",
+                    Prompt::Completes { card: declare.card },
+                    Prompt::DeclareOrPass,
+                    Prompt::SecondsLeft { secs: left }
+                )
+            } else if table.acting_player != table.seat {
+                Prompt::Chat.to_string()
+            } else {
+                let prompt = match table.phase {
+                    TurnPhase::Seating | TurnPhase::Quiz | TurnPhase::Declaring => Prompt::Chat,
+                    TurnPhase::Choosing => Prompt::Choose,
+                    TurnPhase::Discarding => Prompt::Discard,
+                    TurnPhase::LayingOff => Prompt::LayOff,
+                };
+                match self.turn_deadline {
+                    Some(deadline) => {
+                        let left = deadline.saturating_duration_since(Instant::now()).as_secs();
+                        format!("{prompt} {}", Prompt::SecondsLeft { secs: left })
+                    }
+                    None => prompt.to_string(),
+                }
+            };
+
+        let layoff = table.layoff.as_ref().map(|layoff| {
+            (
+                table.players[layoff.meld_owner].name.clone(),
+                layoff.meld.clone(),
+            )
+        });
+        let actions: Vec<String> = table
+            .actions
+            .iter()
+            .rev()
+            .map(|action| action_line(action, &self.colors))
+            .collect();
+        let scores: Vec<(String, usize)> = table
+            .players
+            .iter()
+            .map(|seat| (seat.name.clone(), seat.score))
+            .collect();
+
+        self.screen.draw(TableFrame {
+            book: table.book.clone(),
+            dialogue: self.dialogue.clone(),
+            top_discard: table.top_discard,
+            player_name: table.players[table.seat].name.clone(),
+            hand: table.hand.clone(),
+            layoff,
+            recap: None,
+            prompt: Some(prompt),
+            actions,
+            scores,
+            sheet: score_sheet_panel(&table.score_sheet, &self.colors),
+            chips: Vec::new(),
+            message: self.message.clone(),
+            reveal: table
+                .last_round
+                .as_ref()
+                .map(|summary| layoff_chain(summary, &self.colors))
+                .unwrap_or_default(),
+            odds: Vec::new(),
+            discards: Vec::new(),
+            colors: self.colors.clone(),
+            scroll: 0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::protocol::{DeclareView, SeatView};
+
+    fn table(phase: TurnPhase, acting_player: usize) -> TableView {
+        let seat = |name: &str| SeatView {
+            name: name.to_string(),
+            score: 0,
+            cards_held: 5,
+            is_ai: false,
+        };
+
+        TableView {
+            book: "East of Eden by John Steinbeck".to_string(),
+            seat: 0,
+            hand: vec![],
+            top_discard: None,
+            draw_pile_len: 30,
+            players: vec![seat("Ada"), seat("Lee")],
+            current_player: acting_player,
+            acting_player,
+            phase,
+            layoff: None,
+            declare: None,
+            last_round: None,
+            score_sheet: Default::default(),
+            actions: vec![],
+            turn_seconds_left: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let choosing = table(TurnPhase::Choosing, 0);
+        assert_eq!(
+            parse_input(Some(&choosing), " D "),
+            Ok(Some(ClientMessage::Action {
+                action: PlayerAction::Draw
+            }))
+        );
+        assert!(parse_input(Some(&choosing), "x").is_err());
+
+        let discarding = table(TurnPhase::Discarding, 0);
+        assert_eq!(
+            parse_input(Some(&discarding), "10h"),
+            Ok(Some(ClientMessage::Action {
+                action: PlayerAction::Discard {
+                    card: "10h".parse().unwrap()
+                }
+            }))
+        );
+
+        // Passing on a layoff sends no cards
+        let laying_off = table(TurnPhase::LayingOff, 0);
+        assert_eq!(
+            parse_input(Some(&laying_off), ""),
+            Ok(Some(ClientMessage::Action {
+                action: PlayerAction::LayOff { cards: vec![] }
+            }))
+        );
+
+        // Off turn, whatever is typed is table talk
+        let waiting = table(TurnPhase::Choosing, 1);
+        assert_eq!(
+            parse_input(Some(&waiting), "d"),
+            Ok(Some(ClientMessage::Chat {
+                text: "d".to_string()
+            }))
+        );
+        assert_eq!(parse_input(Some(&waiting), "   "), Ok(None));
+
+        // Even on their turn
+        assert_eq!(
+            parse_input(Some(&choosing), "/say Nice discard"),
+            Ok(Some(ClientMessage::Chat {
+                text: "Nice discard".to_string()
+            }))
+        );
+        assert_eq!(parse_input(Some(&choosing), "/say "), Ok(None));
+
+        // The rest are answered without troubling the server
+        assert_eq!(
+            parse_input(Some(&waiting), "/scores"),
+            Err("Scores: Ada 0, Lee 0".to_string())
+        );
+        assert_eq!(
+            parse_input(Some(&waiting), "/hint"),
+            Err("Hints are for the start of your turn.".to_string())
+        );
+        assert!(
+            parse_input(Some(&choosing), "/hint")
+                .unwrap_err()
+                .starts_with("Hint: ")
+        );
+        assert!(parse_input(None, "/hand").is_err());
+        assert!(parse_input(Some(&choosing), "/dance").is_err());
+
+        // A discard open to your declaration takes a yes or no, even off turn
+        let mut declaring = table(TurnPhase::Declaring, 1);
+        declaring.declare = Some(DeclareView {
+            discarded_by: 1,
+            card: "Ks".parse().unwrap(),
+            open_to_you: true,
+        });
+        assert_eq!(
+            parse_input(Some(&declaring), "y"),
+            Ok(Some(ClientMessage::Action {
+                action: PlayerAction::Declare
+            }))
+        );
+        assert_eq!(
+            parse_input(Some(&declaring), ""),
+            Ok(Some(ClientMessage::Action {
+                action: PlayerAction::Pass
+            }))
+        );
+        assert!(parse_input(Some(&declaring), "d").is_err());
+    }
+
+    #[test]
+    fn test_redeal_asks_first() {
+        let mut client = Client::default();
+        assert_eq!(client.redeal_input("d"), None);
+
+        assert_eq!(
+            client.redeal_input(" Redeal"),
+            Some(Err(Prompt::ConfirmRedeal.to_string()))
+        );
+        assert!(matches!(client.redeal_input("n"), Some(Err(_))));
+        assert_eq!(client.redeal_input("y"), None);
+
+        client.redeal_input("redeal");
+        assert_eq!(
+            client.redeal_input("yes"),
+            Some(Ok(Some(ClientMessage::Redeal)))
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use crate::card::Card;
+use crate::engine::{GameEngine, RoundSummary, TurnPhase};
+use crate::error::RummyError;
+use crate::game::events::{Action, EventSink, GameEvent};
+use crate::game::sheet::ScoreSheet;
+use crate::rng::SeedDisclosure;
+use crate::stats::GameSummary;
+use schemars::{JsonSchema, Schema, schema_for};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+/// How long a discard stays open to declarations before play goes on.
+pub const DECLARE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Bumped whenever a change to the messages, snapshots or events would
+/// misread JSON written by an older version.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// A message, snapshot or event as it's written out: its own fields and the
+/// version of the protocol alongside them.
+///
+/// ```json
+/// {"version": 2, "type": "join", "name": "Ada"}
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Versioned<T> {
+    pub version: u32,
+    #[serde(flatten)]
+    pub body: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(body: T) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            body,
+        }
+    }
+}
+
+/// Just the version, read before the rest so JSON from another version is
+/// turned away for that rather than whatever it doesn't match.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+/// `body` as JSON in this version of the protocol.
+pub fn encode<T: Serialize>(body: &T) -> Result<String, RummyError> {
+    serde_json::to_string(&Versioned::new(body))
+        .map_err(|err| RummyError::Protocol(err.to_string()))
+}
+
+/// A `T` from JSON written in this version of the protocol.
+pub fn decode<T: DeserializeOwned>(json: &str) -> Result<T, RummyError> {
+    let header: Header =
+        serde_json::from_str(json).map_err(|err| RummyError::Protocol(err.to_string()))?;
+    if header.version != PROTOCOL_VERSION {
+        return Err(RummyError::Protocol(format!(
+            "Written in version {} of the protocol, this is version {PROTOCOL_VERSION}",
+            header.version
+        )));
+    }
+
+    serde_json::from_str::<Versioned<T>>(json)
+        .map(|versioned| versioned.body)
+        .map_err(|err| RummyError::Protocol(err.to_string()))
+}
+
+/// The JSON schema of everything the protocol writes, with the names the
+/// `schema` command files them under: what clients send and the server
+/// sends back, a single action and snapshot for front-ends that bring their
+/// own transport, and the events in a replay.
+pub fn schemas() -> [(&'static str, Schema); 5] {
+    [
+        ("client_message", schema_for!(Versioned<ClientMessage>)),
+        ("server_message", schema_for!(Versioned<ServerMessage>)),
+        ("player_action", schema_for!(Versioned<PlayerAction>)),
+        ("table_view", schema_for!(Versioned<TableView>)),
+        ("game_event", schema_for!(Versioned<GameEvent>)),
+    ]
+}
+
+/// Writes every event to `writer` as it happens, a line of JSON each, for
+/// `read_replay` or anything else that speaks the protocol to go over.
+#[derive(Debug)]
+pub struct ReplayWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> ReplayWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write + Send> EventSink for ReplayWriter<W> {
+    fn emit(&mut self, event: &GameEvent) {
+        // A replay that can't be written doesn't hold up the game
+        if let Ok(line) = encode(event) {
+            let _ = writeln!(self.writer, "{line}");
+        }
+    }
+}
+
+/// Every event in a replay, in order.
+pub fn read_replay(reader: impl BufRead) -> Result<Vec<GameEvent>, RummyError> {
+    let mut events = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(|err| RummyError::Io(err.to_string()))?;
+        if !line.trim().is_empty() {
+            events.push(decode(&line)?);
+        }
+    }
+    Ok(events)
+}
+
+/// Messages a client sends to the server, one `Versioned` JSON object per
+/// WebSocket text frame.
+///
+/// ```json
+/// {"version": 2, "type": "join", "name": "Ada"}
+/// {"version": 2, "type": "action", "action": {"kind": "discard", "card": "10h"}}
+/// {"version": 2, "type": "action", "action": {"kind": "declare"}}
+/// {"version": 2, "type": "chat", "text": "Cathy is the real villain here"}
+/// {"version": 2, "type": "redeal"}
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Take a seat at the table. Must be the first message on a connection.
+    Join { name: String },
+    /// Act on your turn.
+    Action { action: PlayerAction },
+    /// Say something to the rest of the table.
+    Chat { text: String },
+    /// Call off the round in play and deal it again. Only the host can.
+    Redeal,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlayerAction {
+    Draw,
+    Retrieve,
+    Play,
+    Discard {
+        card: Card,
+    },
+    /// Lay cards off onto the played hand. No cards passes.
+    LayOff {
+        cards: Vec<Card>,
+    },
+    /// Claim the discard out of turn, while it's open to your declaration.
+    Declare,
+    /// Let the discard go by without declaring.
+    Pass,
+}
+
+impl PlayerAction {
+    /// Takes the action for `seat`, if it's theirs to take.
+    pub fn apply(self, engine: &mut GameEngine, seat: usize) -> Result<(), RummyError> {
+        match self {
+            // Declarations are open to everyone the discard is good for at once
+            PlayerAction::Declare => engine.declare(seat),
+            PlayerAction::Pass => engine.pass_declaration(seat),
+            _ if seat != engine.acting_player_idx() => {
+                Err(RummyError::InvalidMove("It's not your turn".to_string()))
+            }
+            PlayerAction::Draw => engine.draw().map(drop),
+            PlayerAction::Retrieve => engine.retrieve().map(drop),
+            PlayerAction::Discard { card } => engine.discard(card),
+            PlayerAction::Play => engine.play().map(drop),
+            PlayerAction::LayOff { cards } => {
+                // Passing is laying off nothing, anything else has to take the meld
+                if !cards.is_empty() {
+                    engine.check_layoff(&cards)?;
+                }
+                engine.lay_off(&cards).map(drop)
+            }
+        }
+    }
+}
+
+/// Messages the server sends to its clients, `Versioned` like theirs.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Who has joined so far, while the table waits to fill up.
+    Lobby {
+        players: Vec<String>,
+        seats: usize,
+    },
+    /// The table as the receiving player sees it. Sent after every change.
+    State {
+        table: TableView,
+    },
+    Chat {
+        from: String,
+        text: String,
+    },
+    /// The last message couldn't be acted on.
+    Error {
+        message: String,
+    },
+    /// The winner and how the game went, with the seed it was dealt from
+    /// and every deal's hash for anyone who wants to check the server dealt
+    /// fair.
+    GameOver {
+        winner: String,
+        #[serde(default)]
+        disclosure: Option<SeedDisclosure>,
+        #[serde(default)]
+        summary: Option<GameSummary>,
+    },
+}
+
+/// A player at the table, minus anything the viewer shouldn't see.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SeatView {
+    pub name: String,
+    pub score: usize,
+    pub cards_held: usize,
+    pub is_ai: bool,
+}
+
+/// The hand being laid off onto.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct LayoffView {
+    pub played_by: usize,
+    /// Seat whose cards currently make up the meld.
+    pub meld_owner: usize,
+    pub meld: Vec<Card>,
+    pub score_to_beat: u64,
+}
+
+/// A discard open to declarations.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DeclareView {
+    pub discarded_by: usize,
+    pub card: Card,
+    /// Whether the viewer can declare on it and hasn't answered yet.
+    pub open_to_you: bool,
+}
+
+/// One player's view of the table: their own hand and everything public.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TableView {
+    pub book: String,
+    /// Index of the viewer in `players`.
+    pub seat: usize,
+    pub hand: Vec<Card>,
+    pub top_discard: Option<Card>,
+    pub draw_pile_len: usize,
+    pub players: Vec<SeatView>,
+    pub current_player: usize,
+    /// Seat the server is waiting on, which differs from `current_player` during layoffs.
+    pub acting_player: usize,
+    pub phase: TurnPhase,
+    pub layoff: Option<LayoffView>,
+    pub declare: Option<DeclareView>,
+    /// How the last round was won, layoff by layoff.
+    pub last_round: Option<Box<RoundSummary>>,
+    /// Everyone's score round by round.
+    #[serde(default)]
+    pub score_sheet: ScoreSheet,
+    /// Most recent actions, oldest first.
+    pub actions: Vec<Action>,
+    /// Seconds `acting_player` has left before their turn's played for
+    /// them, if the table plays to a clock.
+    pub turn_seconds_left: Option<u64>,
+}
+
+impl TableView {
+    pub fn new(engine: &GameEngine, seat: usize, book: &str, actions: &[Action]) -> Self {
+        let players = engine
+            .players
+            .iter()
+            .map(|player| SeatView {
+                name: player.name.clone(),
+                score: player.score,
+                cards_held: player.hand.cards.len(),
+                is_ai: player.player_type.is_some(),
+            })
+            .collect();
+
+        let layoff = engine.layoff_round().map(|layoff| LayoffView {
+            played_by: layoff.winner_idx,
+            meld_owner: layoff.layoff_winner_idx,
+            meld: layoff.winning_hand.cards.clone(),
+            score_to_beat: layoff.score_to_beat,
+        });
+
+        let declare = engine.declare_window().map(|window| DeclareView {
+            discarded_by: window.discarded_by,
+            card: window.card,
+            open_to_you: window.is_open_to(seat),
+        });
+
+        Self {
+            book: book.to_string(),
+            seat,
+            hand: engine.players[seat].hand.cards.clone(),
+            top_discard: engine.top_discard().copied(),
+            draw_pile_len: engine.deck.draw_pile.len(),
+            players,
+            current_player: engine.current_player_idx,
+            acting_player: engine.acting_player_idx(),
+            phase: engine.phase().clone(),
+            layoff,
+            declare,
+            last_round: engine.last_round().cloned().map(Box::new),
+            score_sheet: engine.score_sheet().clone(),
+            actions: actions.to_vec(),
+            turn_seconds_left: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_replay_is_a_line_an_event() {
+        let mut replay = ReplayWriter::new(Vec::new());
+        replay.emit(&GameEvent::RoundRedealt {
+            player: "Ada".to_string(),
+        });
+        replay.emit(&GameEvent::GameWon {
+            player: "Ada".to_string(),
+            score: 100,
+        });
+        assert_eq!(
+            replay.writer.iter().filter(|&&byte| byte == b'\n').count(),
+            2
+        );
+
+        assert_eq!(read_replay("\n".as_bytes()), Ok(Vec::new()));
+        assert!(matches!(
+            decode::<PlayerAction>("not json"),
+            Err(RummyError::Protocol(_))
+        ));
+    }
+}
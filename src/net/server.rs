@@ -0,0 +1,752 @@
+use crate::dialogue::normalize_dialogue;
+use crate::display::normalize_name;
+use crate::engine::{AiSearch, GameEngine, TurnPhase};
+use crate::error::RummyError;
+use crate::game::events::{ACTION_LOG_LEN, ActionLog, GameEvent};
+use crate::game::rules::{GameRules, Variant};
+use crate::game::{DealRituals, DeckConfig, Hand, Player, PlayerType, TurnOrder};
+use crate::i18n;
+use crate::net::protocol::{
+    self, ClientMessage, DECLARE_WINDOW, PlayerAction, ServerMessage, TableView,
+};
+#[cfg(feature = "scripting")]
+use crate::scripting::HouseRules;
+use crate::stats::{GameRecorder, GameResult, StatsStore};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+
+/// How long the table pauses after each AI move so humans can follow along.
+const AI_PACING_MS: u64 = 1500;
+
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// Address to listen on, e.g. `0.0.0.0:7878`.
+    pub bind: String,
+    /// Book and author under discussion.
+    pub book: String,
+    /// Number of human players to wait for before dealing.
+    pub seats: usize,
+    /// Names of AI players to seat alongside the humans.
+    pub bots: Vec<String>,
+    /// The cards every round is dealt from.
+    pub deck: DeckConfig,
+    /// How the table decides who goes first.
+    pub turn_order: TurnOrder,
+    /// Which game is played.
+    pub variant: Variant,
+    /// Seed for everything left to chance, or `None` for a fresh one.
+    pub seed: Option<u64>,
+    /// Whether anyone can declare out of turn on a discard that completes a
+    /// winning meld for them.
+    pub declarations: bool,
+    /// Whether the deck's cut, or a card burned, before every deal.
+    pub rituals: DealRituals,
+    /// Rules that hold whichever variant is played.
+    pub rules: GameRules,
+    /// How long a human has for each turn before it's played for them,
+    /// `None` for as long as they like.
+    pub turn_timer: Option<Duration>,
+    /// How far a bot's turn searches ahead.
+    pub ai_search: AiSearch,
+    /// House rules to play by.
+    #[cfg(feature = "scripting")]
+    pub house_rules: Option<Arc<HouseRules>>,
+}
+
+enum Inbound {
+    Connected(UnboundedSender<ServerMessage>),
+    Message(ClientMessage),
+    Disconnected,
+}
+
+struct Envelope {
+    conn: usize,
+    inbound: Inbound,
+}
+
+/// Hosts a single game: waits for `config.seats` players to join, then plays
+/// until someone reaches the target score.
+pub async fn run(config: ServerConfig) -> Result<(), RummyError> {
+    let seats = config.seats + config.bots.len();
+    if seats < 2 {
+        return Err(RummyError::Config(
+            "A game needs at least 2 players".to_string(),
+        ));
+    }
+    if config.rules.teams && (seats < 4 || !seats.is_multiple_of(2)) {
+        return Err(RummyError::Config(format!(
+            "Teams need an even number of players, four or more, not {seats}"
+        )));
+    }
+
+    // Catch a deck too small to deal before anyone sits down
+    let deck_size = config.deck.cards()?.len();
+    if deck_size < seats * config.variant.hand_size() + 2 {
+        return Err(RummyError::Config(format!(
+            "A deck of {deck_size} cards is too small for {seats} players"
+        )));
+    }
+
+    let listener = TcpListener::bind(&config.bind)
+        .await
+        .map_err(|err| RummyError::Network(err.to_string()))?;
+
+    let (inbox_tx, inbox_rx) = mpsc::unbounded_channel();
+    tokio::spawn(accept_connections(listener, inbox_tx));
+
+    Table::new(config).run(inbox_rx).await
+}
+
+async fn accept_connections(listener: TcpListener, inbox: UnboundedSender<Envelope>) {
+    let mut next_conn = 0;
+
+    loop {
+        let Ok((stream, _addr)) = listener.accept().await else {
+            continue;
+        };
+
+        tokio::spawn(handle_connection(next_conn, stream, inbox.clone()));
+        next_conn += 1;
+    }
+}
+
+/// Shuttles JSON between one WebSocket and the table until either side hangs up.
+async fn handle_connection(conn: usize, stream: TcpStream, inbox: UnboundedSender<Envelope>) {
+    let Ok(socket) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut sink, mut source) = socket.split();
+
+    let (outbox_tx, mut outbox_rx) = mpsc::unbounded_channel::<ServerMessage>();
+    let replies = outbox_tx.clone();
+    let connected = Envelope {
+        conn,
+        inbound: Inbound::Connected(outbox_tx),
+    };
+    if inbox.send(connected).is_err() {
+        return;
+    }
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = outbox_rx.recv().await {
+            let Ok(json) = protocol::encode(&message) else {
+                continue;
+            };
+
+            if sink.send(Message::Text(json.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(Ok(frame)) = source.next().await {
+        match frame {
+            Message::Text(text) => match protocol::decode::<ClientMessage>(text.as_str()) {
+                Ok(message) => {
+                    let envelope = Envelope {
+                        conn,
+                        inbound: Inbound::Message(message),
+                    };
+                    if inbox.send(envelope).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    let _ = replies.send(ServerMessage::Error {
+                        message: format!("Couldn't read message: {err}"),
+                    });
+                }
+            },
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+
+    let _ = inbox.send(Envelope {
+        conn,
+        inbound: Inbound::Disconnected,
+    });
+    writer.abort();
+}
+
+/// The game itself. Only this task touches the engine, so no locking is needed.
+struct Table {
+    config: ServerConfig,
+    connections: HashMap<usize, UnboundedSender<ServerMessage>>,
+    /// Player name for every connection that has joined.
+    names: HashMap<usize, String>,
+    /// Whoever joined first, who gets to call a redeal.
+    host: Option<String>,
+    engine: Option<GameEngine>,
+    /// Fed by the engine, sent along with the table state.
+    actions: Arc<Mutex<ActionLog>>,
+    /// Sums the game up for the stats file once someone wins.
+    recorder: Arc<Mutex<GameRecorder>>,
+    /// When the discard open to declarations closes, once the table's
+    /// started waiting on them.
+    declare_deadline: Option<Instant>,
+    /// When the human the table's waiting on runs out of time, once it's
+    /// started waiting on them.
+    turn_deadline: Option<Instant>,
+}
+
+impl Table {
+    fn new(config: ServerConfig) -> Self {
+        Self {
+            config,
+            connections: HashMap::new(),
+            names: HashMap::new(),
+            host: None,
+            engine: None,
+            actions: Arc::new(Mutex::new(ActionLog::default())),
+            recorder: Arc::new(Mutex::new(GameRecorder::default())),
+            declare_deadline: None,
+            turn_deadline: None,
+        }
+    }
+
+    async fn run(mut self, mut inbox: UnboundedReceiver<Envelope>) -> Result<(), RummyError> {
+        loop {
+            if self.engine.is_some() {
+                if let Err(err) = self.play_ai_turns().await {
+                    self.report(err);
+                }
+
+                if let Some(engine) = &self.engine
+                    && let Some(winner) = engine.winner()
+                {
+                    let message = ServerMessage::GameOver {
+                        winner: winner.name.clone(),
+                        disclosure: Some(engine.disclosure()),
+                        summary: self.recorder.lock().unwrap().summary(&self.config.book),
+                    };
+                    self.broadcast(message);
+
+                    let result =
+                        self.recorder
+                            .lock()
+                            .unwrap()
+                            .result(&self.config.book)
+                            .map(|result| GameResult {
+                                score_sheet: Some(engine.score_sheet().clone()),
+                                ..result
+                            });
+                    return match result {
+                        Some(result) => StatsStore::default().append(&result),
+                        None => Ok(()),
+                    };
+                }
+            }
+
+            // Nobody gets long to declare, play goes on once time's up
+            let declaring = self
+                .engine
+                .as_ref()
+                .is_some_and(|engine| *engine.phase() == TurnPhase::Declaring);
+            // Nor to take their turn, if the table plays to a clock
+            let timer = self.config.turn_timer.filter(|_| self.engine.is_some());
+            let received = if declaring {
+                let deadline = *self
+                    .declare_deadline
+                    .get_or_insert_with(|| Instant::now() + DECLARE_WINDOW);
+                tokio::time::timeout_at(deadline, inbox.recv()).await
+            } else if let Some(timer) = timer {
+                let deadline = match self.turn_deadline {
+                    Some(deadline) => deadline,
+                    // Everyone's told how long the turn has
+                    None => {
+                        let deadline = Instant::now() + timer;
+                        self.turn_deadline = Some(deadline);
+                        self.broadcast_state();
+                        deadline
+                    }
+                };
+                tokio::time::timeout_at(deadline, inbox.recv()).await
+            } else {
+                Ok(inbox.recv().await)
+            };
+            let Ok(received) = received else {
+                let closed = if declaring {
+                    self.close_declarations()
+                } else {
+                    self.play_out_of_time()
+                };
+                if let Err(err) = closed {
+                    self.report(err);
+                    // A fresh clock, rather than running out again at once
+                    self.declare_deadline = None;
+                    self.turn_deadline = None;
+                }
+                self.broadcast_state();
+                continue;
+            };
+
+            let Some(Envelope { conn, inbound }) = received else {
+                return Ok(());
+            };
+
+            match inbound {
+                Inbound::Connected(outbox) => {
+                    self.connections.insert(conn, outbox);
+                }
+                Inbound::Disconnected => {
+                    self.connections.remove(&conn);
+                    if let Some(name) = self.names.remove(&conn) {
+                        self.log(&name, i18n::Message::LeftTable);
+                        self.broadcast_state();
+                    }
+                }
+                Inbound::Message(ClientMessage::Join { name }) => {
+                    if let Err(err) = self.join(conn, name) {
+                        self.report(err);
+                    }
+                }
+                Inbound::Message(ClientMessage::Chat { text }) => {
+                    if let Some(from) = self.names.get(&conn).cloned() {
+                        let text = normalize_dialogue(&text);
+                        if let Some(engine) = &self.engine {
+                            engine.emit(GameEvent::Dialogue {
+                                player: from.clone(),
+                                text: text.clone(),
+                            });
+                        }
+                        self.broadcast(ServerMessage::Chat { from, text });
+                    } else {
+                        self.send_error(conn, "Join the table before chatting");
+                    }
+                }
+                Inbound::Message(ClientMessage::Redeal) => {
+                    if let Err(err) = self.redeal(conn) {
+                        let message = match err {
+                            RummyError::InvalidMove(message) => message,
+                            err => err.to_string(),
+                        };
+                        self.send_error(conn, &message);
+                    }
+                    self.broadcast_state();
+                }
+                Inbound::Message(ClientMessage::Action { action }) => {
+                    if let Err(err) = self.act(conn, action) {
+                        let message = match err {
+                            RummyError::InvalidMove(message) => message,
+                            err => err.to_string(),
+                        };
+                        self.send_error(conn, &message);
+                    }
+                    self.broadcast_state();
+                }
+            }
+        }
+    }
+
+    fn join(&mut self, conn: usize, name: String) -> Result<(), RummyError> {
+        let name = normalize_name(&name);
+        if name.is_empty() {
+            self.send_error(conn, "Name can't be empty");
+            return Ok(());
+        }
+        if self.names.values().any(|joined| *joined == name) {
+            self.send_error(conn, "Someone at the table already has that name");
+            return Ok(());
+        }
+
+        match &self.engine {
+            // Rejoining a game in progress takes back an empty human seat
+            Some(engine) => {
+                let is_open_seat = engine
+                    .players
+                    .iter()
+                    .any(|p| p.name == name && p.player_type.is_none());
+                if !is_open_seat {
+                    self.send_error(conn, "The game has already started");
+                    return Ok(());
+                }
+
+                self.names.insert(conn, name.clone());
+                self.log(&name, i18n::Message::RejoinedTable);
+                self.broadcast_state();
+            }
+            None => {
+                if self.config.bots.contains(&name) {
+                    self.send_error(conn, "Someone at the table already has that name");
+                    return Ok(());
+                }
+
+                self.names.insert(conn, name);
+                let mut players: Vec<String> = self.names.values().cloned().collect();
+                players.sort();
+                self.broadcast(ServerMessage::Lobby {
+                    players,
+                    seats: self.config.seats,
+                });
+
+                if self.names.len() == self.config.seats {
+                    self.start_game()?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn start_game(&mut self) -> Result<(), RummyError> {
+        // Seated in the order they joined
+        let mut joined: Vec<(&usize, &String)> = self.names.iter().collect();
+        joined.sort();
+        self.host = joined.first().map(|(_, name)| (*name).clone());
+        let humans = joined.into_iter().map(|(_, name)| (name.clone(), None));
+        let bots = self
+            .config
+            .bots
+            .iter()
+            .map(|name| (name.clone(), Some(PlayerType::Balanced)));
+
+        let players: Vec<Player> = humans
+            .chain(bots)
+            .map(|(name, player_type)| Player {
+                name,
+                description: String::new(),
+                player_type,
+                hand: Hand { cards: Vec::new() },
+                actions: VecDeque::new(),
+                dialogue: VecDeque::new(),
+                score: 0,
+            })
+            .collect();
+
+        let mut engine =
+            GameEngine::pre_game(players, self.config.deck.clone(), self.config.variant)?;
+        if let Some(seed) = self.config.seed {
+            engine.set_seed(seed);
+        }
+        engine.allow_declarations(self.config.declarations);
+        engine.set_ai_search(self.config.ai_search);
+        engine.set_deal_rituals(self.config.rituals);
+        engine.set_rules(self.config.rules);
+        #[cfg(feature = "scripting")]
+        if let Some(rules) = &self.config.house_rules {
+            engine.set_house_rules(rules.clone());
+        }
+        engine.subscribe(self.actions.clone());
+        engine.decide_turn_order(self.config.turn_order)?;
+
+        self.recorder = Arc::new(Mutex::new(GameRecorder::new(
+            engine.players.iter().map(|p| p.name.as_str()),
+        )));
+        engine.subscribe(self.recorder.clone());
+        self.engine = Some(engine);
+        self.broadcast_state();
+
+        Ok(())
+    }
+
+    /// Applies a human's action, if it's their turn.
+    fn act(&mut self, conn: usize, action: PlayerAction) -> Result<(), RummyError> {
+        let name = self
+            .names
+            .get(&conn)
+            .cloned()
+            .ok_or_else(|| RummyError::InvalidMove("Join the table first".to_string()))?;
+        let engine = self
+            .engine
+            .as_mut()
+            .ok_or_else(|| RummyError::InvalidMove("The game hasn't started yet".to_string()))?;
+
+        let seat = engine
+            .players
+            .iter()
+            .position(|p| p.name == name)
+            .ok_or_else(|| RummyError::InvalidMove("It's not your turn".to_string()))?;
+
+        let declaring = matches!(action, PlayerAction::Declare | PlayerAction::Pass);
+        // Taking a card is the only move that doesn't end a turn, the next
+        // one gets a clock of its own
+        let ends_turn = !declaring
+            && seat == engine.acting_player_idx()
+            && !matches!(action, PlayerAction::Draw | PlayerAction::Retrieve);
+        let discarding = matches!(action, PlayerAction::Discard { .. });
+
+        // A move that's turned down leaves the clock running
+        action.apply(engine, seat)?;
+        if ends_turn {
+            self.turn_deadline = None;
+        }
+        // Any declarations on it get a window of their own
+        if discarding {
+            self.declare_deadline = None;
+        }
+
+        Ok(())
+    }
+
+    /// Calls off the round and deals it again, if the host asked.
+    fn redeal(&mut self, conn: usize) -> Result<(), RummyError> {
+        let Some(name) = self
+            .names
+            .get(&conn)
+            .filter(|name| self.host.as_ref() == Some(*name))
+            .cloned()
+        else {
+            return Err(RummyError::InvalidMove(
+                "Only the host can call a redeal".to_string(),
+            ));
+        };
+        let engine = self
+            .engine
+            .as_mut()
+            .ok_or_else(|| RummyError::InvalidMove("The game hasn't started yet".to_string()))?;
+
+        engine.redeal(&name)?;
+        self.turn_deadline = None;
+
+        Ok(())
+    }
+
+    /// Plays the turn of whoever the table's waiting on the way the
+    /// analysis recommends, now they've run out of time.
+    fn play_out_of_time(&mut self) -> Result<(), RummyError> {
+        let Some(engine) = self.engine.as_mut() else {
+            return Ok(());
+        };
+        let name = engine.players[engine.acting_player_idx()].name.clone();
+        engine.auto_play()?;
+        self.turn_deadline = None;
+        // Any declarations on the discard played for them get a window of their own
+        self.declare_deadline = None;
+        self.log(&name, i18n::Message::RanOutOfTime);
+
+        Ok(())
+    }
+
+    /// Closes declarations on the discard now time's up on them.
+    fn close_declarations(&mut self) -> Result<(), RummyError> {
+        let Some(engine) = self.engine.as_mut() else {
+            return Ok(());
+        };
+        engine.close_declarations()?;
+        // The next discard gets a window of its own
+        self.declare_deadline = None;
+
+        Ok(())
+    }
+
+    /// Lets AI players act until it's a human's turn or the game is over.
+    async fn play_ai_turns(&mut self) -> Result<(), RummyError> {
+        loop {
+            let Some(engine) = self.engine.as_mut() else {
+                return Ok(());
+            };
+            if engine.winner().is_some() {
+                return Ok(());
+            }
+
+            if *engine.phase() == TurnPhase::Declaring {
+                // AI players answer at once, then it's up to the humans
+                engine.ai_declarations()?;
+                if *engine.phase() == TurnPhase::Declaring {
+                    return Ok(());
+                }
+            } else if *engine.phase() == TurnPhase::LayingOff {
+                match engine.layoff_player_idx() {
+                    None => {
+                        engine.finish_round()?;
+                        self.broadcast_state();
+                        continue;
+                    }
+                    Some(idx) if engine.players[idx].player_type.is_some() => {
+                        engine.ai_lay_off()?;
+                    }
+                    Some(_) => return Ok(()),
+                }
+            } else if engine.current_player().player_type.is_some() {
+                // The decision is worked out off the async workers
+                engine.take_ai_turn_async().await?;
+                self.declare_deadline = None;
+            } else {
+                return Ok(());
+            }
+
+            self.turn_deadline = None;
+            self.broadcast_state();
+            tokio::time::sleep(tokio::time::Duration::from_millis(AI_PACING_MS)).await;
+        }
+    }
+
+    fn log(&mut self, name: &str, message: i18n::Message) {
+        if let Ok(mut actions) = self.actions.lock() {
+            actions.note(name, message);
+        }
+    }
+
+    /// Logs an error the engine hit and tells the table, which plays on:
+    /// one bad move shouldn't end the game for everyone.
+    fn report(&self, err: RummyError) {
+        tracing::error!(error = %err, "The table hit an error");
+        self.broadcast(ServerMessage::Error {
+            message: err.to_string(),
+        });
+    }
+
+    fn send_error(&self, conn: usize, message: &str) {
+        if let Some(outbox) = self.connections.get(&conn) {
+            let _ = outbox.send(ServerMessage::Error {
+                message: message.to_string(),
+            });
+        }
+    }
+
+    fn broadcast(&self, message: ServerMessage) {
+        for (conn, outbox) in &self.connections {
+            if self.names.contains_key(conn) {
+                let _ = outbox.send(message.clone());
+            }
+        }
+    }
+
+    /// Sends every joined player their own view of the table.
+    fn broadcast_state(&self) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let actions = match self.actions.lock() {
+            Ok(actions) => actions.recent(ACTION_LOG_LEN),
+            Err(_) => Vec::new(),
+        };
+
+        for (conn, name) in &self.names {
+            let Some(seat) = engine.players.iter().position(|p| p.name == *name) else {
+                continue;
+            };
+            let Some(outbox) = self.connections.get(conn) else {
+                continue;
+            };
+
+            let mut table = TableView::new(engine, seat, &self.config.book, &actions);
+            table.turn_seconds_left = self
+                .turn_deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()).as_secs());
+            let _ = outbox.send(ServerMessage::State { table });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::Card;
+
+    fn player(name: &str) -> Player {
+        Player {
+            name: name.to_string(),
+            description: String::new(),
+            player_type: None,
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        }
+    }
+
+    /// Two seats on a clock, everything else as it comes.
+    fn config() -> ServerConfig {
+        ServerConfig {
+            bind: "127.0.0.1:0".to_string(),
+            book: "East of Eden by John Steinbeck".to_string(),
+            seats: 2,
+            bots: Vec::new(),
+            deck: DeckConfig::default(),
+            turn_order: TurnOrder::default(),
+            variant: Variant::default(),
+            seed: None,
+            declarations: true,
+            rituals: DealRituals::default(),
+            rules: GameRules::default(),
+            turn_timer: Some(Duration::from_secs(30)),
+            ai_search: AiSearch::Off,
+            #[cfg(feature = "scripting")]
+            house_rules: None,
+        }
+    }
+
+    /// A table on a clock with Ada and Bram seated, Ada to play.
+    fn seated_table() -> Table {
+        let mut table = Table::new(config());
+        table.names.insert(0, "Ada".to_string());
+        table.names.insert(1, "Bram".to_string());
+        let mut engine = GameEngine::new(vec![player("Ada"), player("Bram")]).unwrap();
+        engine.allow_declarations(true);
+        table.engine = Some(engine);
+        table
+    }
+
+    #[test]
+    fn test_a_turned_down_move_keeps_the_clock_running() {
+        let mut table = seated_table();
+        let deadline = Instant::now() + Duration::from_secs(30);
+        table.turn_deadline = Some(deadline);
+
+        table.act(0, PlayerAction::Draw).unwrap();
+        assert_eq!(table.turn_deadline, Some(deadline));
+
+        // Bram's card isn't Ada's to discard
+        let card = table.engine.as_ref().unwrap().players[1].hand.cards[0];
+        assert!(table.act(0, PlayerAction::Discard { card }).is_err());
+        assert_eq!(table.turn_deadline, Some(deadline));
+        assert!(table.act(1, PlayerAction::Discard { card }).is_err());
+        assert_eq!(table.turn_deadline, Some(deadline));
+
+        let card = table.engine.as_ref().unwrap().players[0].hand.cards[0];
+        table.act(0, PlayerAction::Discard { card }).unwrap();
+        assert_eq!(table.turn_deadline, None);
+    }
+
+    #[test]
+    fn test_declarations_get_a_fresh_window_after_time_runs_out() {
+        let hand = |cards: &str| Hand {
+            cards: cards
+                .split(' ')
+                .map(|card| card.parse::<Card>().unwrap())
+                .collect(),
+        };
+        let expired = Instant::now();
+
+        // A discard played for someone out of time opens a window of its own
+        let mut table = seated_table();
+        table.declare_deadline = Some(expired);
+        table.turn_deadline = Some(expired);
+        table.engine.as_mut().unwrap().draw().unwrap();
+        table.play_out_of_time().unwrap();
+        assert_eq!(table.declare_deadline, None);
+        assert_eq!(table.turn_deadline, None);
+
+        // As does the one after declarations close on the last
+        let mut table = seated_table();
+        let engine = table.engine.as_mut().unwrap();
+        engine.players[0].hand = hand("Ks 3d 4c 7h 8s");
+        engine.players[1].hand = hand("2h 2s Kd Kc 9h");
+        engine.draw().unwrap();
+        engine.discard("Ks".parse().unwrap()).unwrap();
+        assert_eq!(*engine.phase(), TurnPhase::Declaring);
+        table.declare_deadline = Some(expired);
+        table.close_declarations().unwrap();
+        assert_eq!(table.declare_deadline, None);
+        assert_eq!(*table.engine.as_ref().unwrap().phase(), TurnPhase::Choosing);
+    }
+
+    #[tokio::test]
+    async fn test_teams_need_partners_for_everyone() {
+        for (seats, bots) in [(2, 0), (2, 1), (3, 2)] {
+            let mut config = config();
+            config.seats = seats;
+            config.bots = (0..bots).map(|bot| format!("Bot {bot}")).collect();
+            config.rules.teams = true;
+            assert!(matches!(run(config).await, Err(RummyError::Config(_))));
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! The book quiz a club can play before the first deal, for a small head
+//! start. The questions come from the LLM, so the quiz is only as good as
+//! the model's reading of the book.
+
+/// How many questions the quiz asks.
+pub const QUIZ_QUESTIONS: usize = 3;
+
+/// Points each right answer is worth at the start of the match.
+pub const QUIZ_POINTS: usize = 2;
+
+const CHOICE_LETTERS: [char; 4] = ['A', 'B', 'C', 'D'];
+
+/// A multiple choice question about the book.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QuizQuestion {
+    pub question: String,
+    pub choices: Vec<String>,
+    /// Index into `choices` of the right one.
+    pub answer: usize,
+}
+
+impl QuizQuestion {
+    /// Whether `reply`, a choice's letter or its text, is the right answer.
+    pub fn is_correct(&self, reply: &str) -> bool {
+        let reply = reply.trim();
+        let picked = match reply.chars().collect::<Vec<_>>().as_slice() {
+            [letter] => choice_index(*letter),
+            _ => self
+                .choices
+                .iter()
+                .position(|choice| choice.eq_ignore_ascii_case(reply)),
+        };
+        picked == Some(self.answer)
+    }
+
+    /// The choices with their letters, e.g. `A) Salinas`.
+    pub fn lettered_choices(&self) -> impl Iterator<Item = String> + '_ {
+        CHOICE_LETTERS
+            .iter()
+            .zip(&self.choices)
+            .map(|(letter, choice)| format!("{letter}) {choice}"))
+    }
+}
+
+/// Starting points for `correct` right answers.
+pub fn quiz_bonus(correct: usize) -> usize {
+    correct * QUIZ_POINTS
+}
+
+/// What to ask the LLM for `questions` questions about `book`, in the shape
+/// `parse_quiz` reads.
+pub fn quiz_prompt(book: &str, questions: usize) -> String {
+    format!(
+        "Write {questions} multiple choice questions about {book} for a book club that has just read it. Ask about the plot and the characters, not trivia about the author. Give every question four choices and write each one exactly like this, with nothing else:\n\nQ: <question>\nA) <choice>\nB) <choice>\nC) <choice>\nD) <choice>\nAnswer: <letter>"
+    )
+}
+
+/// Reads the questions out of the LLM's answer. Questions that don't come
+/// out whole, say without four choices or a right answer, are left out.
+pub fn parse_quiz(text: &str) -> Vec<QuizQuestion> {
+    let mut questions = Vec::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for line in text.lines().map(str::trim) {
+        if let Some(question) = line.strip_prefix("Q:") {
+            current = Some((question.trim().to_string(), Vec::new()));
+        } else if let Some(answer) = line.strip_prefix("Answer:") {
+            let answer = answer.trim().chars().next().and_then(choice_index);
+            if let (Some((question, choices)), Some(answer)) = (current.take(), answer)
+                && choices.len() == CHOICE_LETTERS.len()
+            {
+                questions.push(QuizQuestion {
+                    question,
+                    choices,
+                    answer,
+                });
+            }
+        } else if let Some((_, choices)) = &mut current
+            && let Some(choice) = lettered_choice(line, choices.len())
+        {
+            choices.push(choice.to_string());
+        }
+    }
+
+    questions
+}
+
+fn choice_index(letter: char) -> Option<usize> {
+    CHOICE_LETTERS
+        .iter()
+        .position(|l| l.eq_ignore_ascii_case(&letter))
+}
+
+/// The text of `line` if it's the choice lettered for position `idx`.
+fn lettered_choice(line: &str, idx: usize) -> Option<&str> {
+    let letter = CHOICE_LETTERS.get(idx)?;
+    let rest = line.strip_prefix(*letter)?;
+    let choice = rest
+        .strip_prefix(')')
+        .or_else(|| rest.strip_prefix('.'))?
+        .trim();
+    (!choice.is_empty()).then_some(choice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quiz() {
+        let text = "Here's your quiz!\n\n\
+            Q: Where do the Trasks settle?\n\
+            A) Salinas Valley\nB) Boston\nC) Paris\nD) Connecticut\n\
+            Answer: A\n\n\
+            Q: Who is missing a choice?\n\
+            A) Cal\nB) Aron\nC) Lee\n\
+            Answer: C\n\n\
+            Q: What does timshel mean?\n\
+            A. Thou shalt\nB. Thou mayest\nC. Thou must\nD. Thou wilt\n\
+            Answer: b) Thou mayest";
+
+        let quiz = parse_quiz(text);
+        assert_eq!(quiz.len(), 2);
+        assert_eq!(quiz[0].question, "Where do the Trasks settle?");
+        assert_eq!(quiz[0].answer, 0);
+        assert_eq!(quiz[1].choices[1], "Thou mayest");
+        assert_eq!(quiz[1].answer, 1);
+        assert_eq!(
+            quiz[1].lettered_choices().next().as_deref(),
+            Some("A) Thou shalt")
+        );
+
+        assert!(quiz[1].is_correct(" b "));
+        assert!(quiz[1].is_correct("thou mayest"));
+        assert!(!quiz[1].is_correct("A"));
+        assert!(!quiz[1].is_correct(""));
+        assert_eq!(quiz_bonus(2), 4);
+    }
+}
@@ -0,0 +1,199 @@
+//! Where a game's randomness comes from.
+//!
+//! Every game owns one `RngService`, seeded once. Dealing, the AI's sampling,
+//! table talk and screen effects each draw from their own stream derived from
+//! that seed, so the same seed deals the same game however often the AI
+//! thinks or the confetti falls in between. The deal's stream starts over
+//! at every deal, so a game picked back up from a save deals on just as it
+//! would have.
+//!
+//! Every deal is hashed as it's shuffled, and the seed is disclosed with the
+//! hashes once the game's won, so anyone can play the seed again and check
+//! nothing was dealt that the seed didn't.
+
+use crate::card::Card;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// What a stream of randomness is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    /// Shuffling the deck for every round, and reshuffling the discards.
+    Deal,
+    /// Deciding who goes first.
+    Seating,
+    /// The AI's sampling of cards it might draw.
+    Ai,
+    /// Picking canned lines of table talk.
+    Talk,
+    /// Anything only for show, like confetti or the order of quiz questions.
+    Effects,
+}
+
+impl RngStream {
+    /// Mixed into the game's seed, so every stream gets its own.
+    fn salt(self) -> u64 {
+        match self {
+            RngStream::Deal => 0x6465_616c,
+            RngStream::Seating => 0x7365_6174,
+            RngStream::Ai => 0x6169,
+            RngStream::Talk => 0x7461_6c6b,
+            RngStream::Effects => 0x6566_6673,
+        }
+    }
+}
+
+/// Hands out the RNG streams for one game.
+#[derive(Clone, Debug)]
+pub struct RngService {
+    seed: u64,
+    streams: HashMap<RngStream, StdRng>,
+}
+
+impl Default for RngService {
+    /// Seeded at random.
+    fn default() -> Self {
+        Self::seeded(rand::random())
+    }
+}
+
+impl RngService {
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    /// The seed everything is derived from, to play the same game again.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The RNG for `stream`, started the first time it's asked for.
+    pub fn stream(&mut self, stream: RngStream) -> &mut StdRng {
+        let seed = self.seed;
+        self.streams
+            .entry(stream)
+            .or_insert_with(|| StdRng::seed_from_u64(mix(seed, stream.salt())))
+    }
+
+    /// Starts `stream` over from the seed and `count`, wherever it was up to.
+    pub fn restart(&mut self, stream: RngStream, count: u64) {
+        let seed = mix(mix(self.seed, stream.salt()), count);
+        self.streams.insert(stream, StdRng::seed_from_u64(seed));
+    }
+
+    /// A new RNG seeded from `stream`, for work that leaves this thread or
+    /// outlives the borrow of the service.
+    pub fn fork(&mut self, stream: RngStream) -> StdRng {
+        StdRng::seed_from_u64(self.stream(stream).random())
+    }
+
+    /// The seed `stream` starts from. For work that can't take its turn on
+    /// the stream, like an AI decision worked out ahead of time, which has
+    /// to come out the same whenever it's worked out.
+    pub fn seed_of(&self, stream: RngStream) -> u64 {
+        mix(self.seed, stream.salt())
+    }
+}
+
+/// The seed a game was played from and the hash of every deal, in order,
+/// redeals included.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SeedDisclosure {
+    pub seed: u64,
+    pub deal_hashes: Vec<u64>,
+}
+
+impl fmt::Display for SeedDisclosure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Seed: {}", self.seed)?;
+        for (round, hash) in self.deal_hashes.iter().enumerate() {
+            writeln!(f, "Deal {}: {hash:016x}", round + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// A fingerprint of a shuffled deck, top card last. FNV-1a over the cards'
+/// names, so it doesn't change between builds or platforms.
+pub fn deal_hash(cards: &[Card]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for card in cards {
+        for byte in card.to_string().bytes().chain([b' ']) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// SplitMix64's finalizer over `seed` and `salt`, so nearby seeds still give
+/// unrelated streams.
+fn mix(seed: u64, salt: u64) -> u64 {
+    let mut z = seed ^ salt.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streams_are_independent_and_seeded() {
+        let mut rngs = RngService::seeded(7);
+        let deal: Vec<u64> = (0..4)
+            .map(|_| rngs.stream(RngStream::Deal).random())
+            .collect();
+
+        // Using another stream first doesn't change the deal
+        let mut other = RngService::seeded(7);
+        for _ in 0..10 {
+            let _: u64 = other.stream(RngStream::Effects).random();
+        }
+        let _ = other.fork(RngStream::Ai);
+        let replayed: Vec<u64> = (0..4)
+            .map(|_| other.stream(RngStream::Deal).random())
+            .collect();
+        assert_eq!(deal, replayed);
+
+        let ai = StdRng::seed_from_u64(other.seed_of(RngStream::Ai)).random::<u64>();
+        assert_eq!(
+            ai,
+            RngService::seeded(7).stream(RngStream::Ai).random::<u64>()
+        );
+
+        let talk: u64 = other.stream(RngStream::Talk).random();
+        assert_ne!(talk, deal[0]);
+        let mut reseeded = RngService::seeded(8);
+        assert_ne!(reseeded.stream(RngStream::Deal).random::<u64>(), deal[0]);
+    }
+
+    #[test]
+    fn test_deal_hashes_are_stable() {
+        let cards: Vec<Card> = ["As", "Kh", "2c"]
+            .map(|card| card.parse().unwrap())
+            .to_vec();
+        let mut reversed = cards.clone();
+        reversed.reverse();
+        assert_eq!(deal_hash(&cards), deal_hash(&cards.clone()));
+        assert_ne!(deal_hash(&cards), deal_hash(&reversed));
+        assert_eq!(deal_hash(&[]), 0xcbf2_9ce4_8422_2325);
+
+        let disclosure = SeedDisclosure {
+            seed: 42,
+            deal_hashes: vec![0xab],
+        };
+        assert_eq!(
+            disclosure.to_string(),
+            "Seed: 42\nDeal 1: 00000000000000ab\n"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Saving a game between turns and picking it back up later, conversation
+//! and all.
+//!
+//! The table talk is saved along with the cards, so a restored game's players
+//! remember what they were discussing instead of starting from a blank page.
+
+use crate::dialogue::relationships::Relationships;
+use crate::engine::{EngineSnapshot, GameEngine};
+use crate::error::RummyError;
+use crate::game::events::{Action, EventSink, GameEvent};
+use crate::i18n::Prompt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever a change to the format would misread older saves.
+pub const SAVE_VERSION: u32 = 1;
+
+/// How many lines of table talk a save keeps, plenty for the screen and for
+/// the players to pick up the thread.
+pub const TRANSCRIPT_LEN: usize = 50;
+
+/// Where bookclub_rummy keeps its files: `$XDG_DATA_HOME/bookclub_rummy`,
+/// falling back to `~/.local/share` and then the working directory.
+pub fn data_dir() -> PathBuf {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .unwrap_or_default()
+        .join("bookclub_rummy")
+}
+
+/// Where the local game saves itself.
+pub fn default_save_path() -> PathBuf {
+    data_dir().join("saved_game.json")
+}
+
+/// Where the local game writes out everything said and done at the table
+/// once it's over.
+pub fn default_transcript_path() -> PathBuf {
+    data_dir().join("transcript.txt")
+}
+
+/// One thing said at the table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptLine {
+    pub player: String,
+    pub text: String,
+}
+
+/// The most recent table talk, oldest first, kept from the `Dialogue` events,
+/// and what the players hold against each other.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    lines: VecDeque<TranscriptLine>,
+    #[serde(default)]
+    relationships: Relationships,
+}
+
+impl Transcript {
+    pub fn lines(&self) -> impl Iterator<Item = &TranscriptLine> {
+        self.lines.iter()
+    }
+
+    /// What each player said last, which is what the players are prompted with.
+    pub fn last_lines(&self) -> HashMap<String, String> {
+        self.lines
+            .iter()
+            .map(|line| (line.player.clone(), line.text.clone()))
+            .collect()
+    }
+
+    /// The grudges and alliances the players are prompted with.
+    pub fn relationships(&self) -> &Relationships {
+        &self.relationships
+    }
+}
+
+impl EventSink for Transcript {
+    fn emit(&mut self, event: &GameEvent) {
+        self.relationships.emit(event);
+
+        let GameEvent::Dialogue { player, text } = event else {
+            return;
+        };
+
+        self.lines.push_back(TranscriptLine {
+            player: player.clone(),
+            text: text.clone(),
+        });
+        while self.lines.len() > TRANSCRIPT_LEN {
+            self.lines.pop_front();
+        }
+    }
+}
+
+/// A game saved between turns.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u32,
+    pub book: String,
+    pub engine: EngineSnapshot,
+    pub transcript: Transcript,
+}
+
+impl SavedGame {
+    pub fn new(
+        book: &str,
+        engine: &GameEngine,
+        transcript: &Transcript,
+    ) -> Result<Self, RummyError> {
+        Ok(Self {
+            version: SAVE_VERSION,
+            book: book.to_string(),
+            engine: engine.snapshot()?,
+            transcript: transcript.clone(),
+        })
+    }
+
+    /// Writes the save next to `path` first and moves it into place, so a
+    /// crash mid-write leaves the previous save intact.
+    pub fn save(&self, path: &Path) -> Result<(), RummyError> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| RummyError::Io(err.to_string()))?;
+        }
+
+        let json = serde_json::to_string(self).map_err(|err| RummyError::Io(err.to_string()))?;
+        let partial = path.with_extension("partial");
+        fs::write(&partial, json)
+            .and_then(|()| fs::rename(&partial, path))
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, RummyError> {
+        let json = fs::read_to_string(path)
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+        let saved: Self = serde_json::from_str(&json).map_err(|err| {
+            RummyError::Config(format!("{} isn't a saved game: {err}", path.display()))
+        })?;
+
+        if saved.version != SAVE_VERSION {
+            return Err(RummyError::Config(format!(
+                "{} was saved by a different version of bookclub_rummy",
+                path.display()
+            )));
+        }
+
+        Ok(saved)
+    }
+
+    /// The engine and transcript to carry on with. Lines from anyone no longer
+    /// at the table are dropped rather than put in someone else's mouth, and
+    /// so are grudges against them.
+    pub fn restore(self) -> Result<(String, GameEngine, Transcript), RummyError> {
+        let engine = GameEngine::restore(self.engine)?;
+
+        let mut transcript = self.transcript;
+        transcript
+            .lines
+            .retain(|line| engine.players.iter().any(|p| p.name == line.player));
+        transcript
+            .relationships
+            .retain(|name| engine.players.iter().any(|p| p.name == name));
+
+        Ok((self.book, engine, transcript))
+    }
+}
+
+/// Everything said and done at the table, to read back over: the table talk
+/// and then the action log, each in the order it happened.
+pub fn transcript_text(book: &str, lines: &[TranscriptLine], actions: &[Action]) -> String {
+    let title = Prompt::TranscriptTitle {
+        book: book.to_string(),
+    };
+    let mut text = format!("{title}\n\n{}\n", Prompt::TableTalk);
+    for line in lines {
+        text.push_str(&format!("{}: {}\n", line.player, line.text));
+    }
+    text.push_str(&format!("\n{}\n", Prompt::Actions));
+    for action in actions {
+        text.push_str(&format!("{action}\n"));
+    }
+    text
+}
+
+/// Writes `transcript_text` to `path`, over whatever game was there before.
+pub fn write_transcript(
+    path: &Path,
+    book: &str,
+    lines: &[TranscriptLine],
+    actions: &[Action],
+) -> Result<(), RummyError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|err| RummyError::Io(err.to_string()))?;
+    }
+
+    fs::write(path, transcript_text(book, lines, actions))
+        .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Hand, Player, PlayerType};
+
+    fn player(name: &str, player_type: Option<PlayerType>) -> Player {
+        Player {
+            name: name.to_string(),
+            description: "reads the last page first".to_string(),
+            player_type,
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        }
+    }
+
+    fn said(player: &str, text: &str) -> GameEvent {
+        GameEvent::Dialogue {
+            player: player.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_saved_games_keep_the_conversation() {
+        let mut engine = GameEngine::new(vec![
+            player("Ada", None),
+            player("Lee", Some(PlayerType::Aggressive)),
+        ])
+        .unwrap();
+        engine.players[1].score = 40;
+
+        let mut transcript = Transcript::default();
+        transcript.emit(&said("Lee", "Cal is the real hero."));
+        transcript.emit(&said("Ada", "Aron would disagree."));
+        transcript.emit(&said("Lee", "Aron isn't here to."));
+        transcript.emit(&said("Sam", "Timshel!"));
+        transcript.emit(&GameEvent::CardDiscarded {
+            player: "Ada".to_string(),
+            card: "9c".parse().unwrap(),
+        });
+        transcript.emit(&GameEvent::CardDrawn {
+            player: "Lee".to_string(),
+            retrieved: Some("9c".parse().unwrap()),
+        });
+
+        let saved = SavedGame::new("East of Eden", &engine, &transcript).unwrap();
+        let (book, restored, transcript) = saved.restore().unwrap();
+        assert_eq!(book, "East of Eden");
+        assert_eq!(restored.players[1].score, 40);
+        assert_eq!(restored.players[1].description, "reads the last page first");
+        assert_eq!(restored.players[0].hand, engine.players[0].hand);
+        assert_eq!(restored.top_discard(), engine.top_discard());
+
+        // Sam never sat down, so their line goes
+        assert_eq!(transcript.lines().count(), 3);
+        let last_lines = transcript.last_lines();
+        assert_eq!(last_lines["Lee"], "Aron isn't here to.");
+        assert_eq!(last_lines["Ada"], "Aron would disagree.");
+        assert_eq!(transcript.relationships().warmth("Lee", "Ada"), 1);
+
+        // Mid-turn there's nothing sensible to save
+        engine.draw().unwrap();
+        assert!(SavedGame::new("East of Eden", &engine, &Transcript::default()).is_err());
+    }
+
+    #[test]
+    fn test_the_whole_game_is_written_out() {
+        use crate::game::events::{ACTION_LOG_LEN, ActionLog};
+
+        let mut log = ActionLog::default();
+        for card in ["2c", "3c", "4c", "5c", "6c", "7c", "8c"] {
+            log.emit(&GameEvent::CardDiscarded {
+                player: "Ada".to_string(),
+                card: card.parse().unwrap(),
+            });
+        }
+        assert_eq!(log.recent(ACTION_LOG_LEN).len(), ACTION_LOG_LEN);
+        assert_eq!(log.actions().len(), 7);
+
+        let lines = [TranscriptLine {
+            player: "Lee".to_string(),
+            text: "Cal is the real hero.".to_string(),
+        }];
+        let text = transcript_text("East of Eden", &lines, &log.actions());
+        assert!(text.contains("Lee: Cal is the real hero.\n"));
+        // The first discard is long gone from the screen, but not from the transcript
+        assert!(text.contains("Ada discarded the 2c\n"));
+        assert!(text.ends_with("Ada discarded the 8c\n"));
+    }
+}
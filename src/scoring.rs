@@ -1,13 +1,20 @@
 use crate::{
     Suite,
-    card::{Card, ToU64},
+    card::set::is_run,
+    card::{Card, CardSet, Name},
+    error::RummyError,
+    game::rules::{arrange_gin, deadwood_points},
 };
 use smallvec::SmallVec;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{LazyLock, RwLock};
 
 pub type CardVec = SmallVec<[Card; 6]>;
 
-type MeldScoringClosure = fn(CardVec) -> Result<u64, String>;
+pub type MeldScoringClosure = fn(CardVec) -> Result<u64, RummyError>;
 
 pub const MELD_FUNCTIONS: &[MeldScoringClosure] = &[
     pair_score,
@@ -24,305 +31,728 @@ pub const MELD_FUNCTIONS: &[MeldScoringClosure] = &[
     royal_flush_score,
 ];
 
-/// Calculates score for having a pair in the hand.
-pub fn pair_score(hand: CardVec) -> Result<u64, String> {
-    for i in 0..hand.len() {
-        for j in (i + 1)..hand.len() {
-            if hand[i].rank == hand[j].rank {
-                return Ok(2);
-            }
-        }
+/// Cards in a meld. Bigger hands are scored by their best 5.
+pub const MELD_SIZE: usize = 5;
+
+/// Names of the melds, by the score each is worth. No two melds score the same.
+pub const MELD_NAMES: &[(u64, &str)] = &[
+    (2, "Pair"),
+    (5, "Two Pair"),
+    (10, "Sequence of Three"),
+    (15, "Three of a Kind"),
+    (20, "Straight"),
+    (25, "Flush"),
+    (30, "Sequence of Four"),
+    (35, "Full Set"),
+    (40, "Full House"),
+    (50, "Four of a Kind"),
+    (80, "Straight Flush"),
+    (100, "Royal Flush"),
+];
+
+/// The name of the meld worth `score`, if there is one.
+pub fn meld_name(score: u64) -> Option<&'static str> {
+    MELD_NAMES
+        .iter()
+        .find(|(meld_score, _)| *meld_score == score)
+        .map(|(_, name)| *name)
+}
+
+/// Hands the meld cache holds before it starts over, a few dozen MB of them.
+pub const MELD_CACHE_CAPACITY: usize = 1 << 20;
+
+/// The best score of every hand `best_meld_score` has scored, keyed on
+/// `canonical_key`. Shared by every search and simulation, on every thread.
+static MELD_CACHE: LazyLock<RwLock<HashMap<u64, Option<u64>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Cards a `canonical_key` packs in exactly.
+pub const CANONICAL_CARDS: usize = 10;
+
+/// A key for a hand that's the same whatever order its cards are in: each
+/// card's name and suite packed into 6 bits, sorted. Copies of the same card
+/// from different decks share a code, as they score the same.
+///
+/// Hands of up to `CANONICAL_CARDS` have a key of their own. Bigger ones are
+/// hashed, with the top bit set so they can't be taken for a smaller hand.
+pub fn canonical_key(cards: &[Card]) -> u64 {
+    // Zero is left for no card, so hands of different sizes don't collide
+    let mut codes: SmallVec<[u64; CANONICAL_CARDS]> = cards
+        .iter()
+        .map(|card| card.name as u64 * 4 + card.suite as u64 + 1)
+        .collect();
+    codes.sort_unstable();
+
+    if codes.len() > CANONICAL_CARDS {
+        let mut hasher = DefaultHasher::new();
+        codes.hash(&mut hasher);
+        return hasher.finish() | 1 << 63;
     }
-    Ok(0)
+    codes.iter().fold(0, |key, code| key << 6 | code)
 }
 
-/// Calculates score for having exactly two pairs in the hand.
-pub fn two_pair_score(hand: CardVec) -> Result<u64, String> {
-    let mut map = HashMap::new();
-    for card in hand.iter() {
-        *map.entry(card.rank).or_insert(0) += 1;
+/// The best score any meld gives `cards`, or `None` if none of them could
+/// score the hand. Hands of up to 5 cards are only scored once, after that
+/// the score comes from a cache.
+pub fn best_meld_score(cards: &[Card]) -> Option<u64> {
+    let score = |cards: &[Card]| {
+        let set = CardSet::from(cards);
+        MELD_SET_FUNCTIONS.iter().map(|meld| meld(&set)).max()
+    };
+
+    if cards.len() > MELD_SIZE {
+        return score(cards);
+    }
+    let key = canonical_key(cards);
+    if let Some(best) = MELD_CACHE.read().unwrap().get(&key) {
+        return *best;
     }
 
-    let mut pairs: Vec<usize> = map.into_values().collect::<Vec<usize>>();
-    pairs.retain(|&i| i >= 2);
+    let best = score(cards);
 
-    if pairs.len() == 2 {
-        return Ok(5);
+    let mut cache = MELD_CACHE.write().unwrap();
+    if cache.len() >= MELD_CACHE_CAPACITY {
+        cache.clear();
     }
-    Ok(0)
+    cache.insert(key, best);
+    best
 }
 
-/// Calculates score for having a sequence of three consecutive ranks of the same suite.
-pub fn sequence_of_three_score(hand: CardVec) -> Result<u64, String> {
-    let mut map: HashMap<Suite, Vec<u64>> = HashMap::new();
+/// The best meld in a hand, from `best_meld`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BestMeld {
+    pub score: u64,
+    /// The cards making the meld, the whole hand if it's no bigger than one.
+    pub cards: Vec<Card>,
+}
 
-    for card in hand {
-        let entry = map.entry(card.suite).or_default();
-        entry.push(card.rank.to_u64().unwrap());
+/// The best meld among `cards`, trying every `MELD_SIZE` card combination of
+/// a bigger hand. Of melds scoring the same, the first combination wins.
+pub fn best_meld(cards: &[Card]) -> BestMeld {
+    if cards.len() <= MELD_SIZE {
+        return BestMeld {
+            score: best_meld_score(cards).unwrap_or(0),
+            cards: cards.to_vec(),
+        };
     }
 
-    let mut values = Vec::new();
-
-    for (_suite, vals) in map.iter() {
-        if vals.len() >= 3 {
-            values.append(&mut vals.clone());
+    let mut best: Option<BestMeld> = None;
+    for meld in combinations(cards, MELD_SIZE) {
+        let score = best_meld_score(&meld).unwrap_or(0);
+        if best.as_ref().is_none_or(|best| score > best.score) {
+            best = Some(BestMeld { score, cards: meld });
         }
     }
+    best.expect("a hand bigger than a meld has combinations")
+}
 
-    values.dedup();
-    values.sort();
+/// A meld a hand holds, from `explain`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MeldMatch {
+    pub name: &'static str,
+    pub score: u64,
+    /// The cards making the meld, a group for each part of it: a full house
+    /// is its three of a kind, then its pair.
+    pub parts: Vec<Vec<Card>>,
+}
 
-    for window in values.windows(3) {
-        if window[2] == window[1] + 1 && window[1] == window[0] + 1 {
-            return Ok(10);
-        }
+impl MeldMatch {
+    /// Every card making the meld.
+    pub fn cards(&self) -> impl Iterator<Item = &Card> {
+        self.parts.iter().flatten()
     }
-
-    Ok(0)
 }
 
-/// Calculates score for having three cards of the same rank.
-pub fn three_of_a_kind_score(hand: CardVec) -> Result<u64, String> {
-    let mut map = HashMap::new();
-    for card in hand.iter() {
-        *map.entry(card.rank).or_insert(0) += 1;
+impl fmt::Display for MeldMatch {
+    /// Writes the meld as its parts add up, e.g. `Full House: 2s 2h 2c + 3s 3h = 40`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = self
+            .parts
+            .iter()
+            .map(|part| {
+                let cards: Vec<String> = part.iter().map(Card::to_string).collect();
+                cards.join(" ")
+            })
+            .collect();
+        write!(f, "{}: {} = {}", self.name, parts.join(" + "), self.score)
     }
+}
 
-    let mut pairs: Vec<usize> = map.into_values().collect::<Vec<usize>>();
-    pairs.retain(|&i| i == 3);
+/// Every meld `hand` holds, lowest scoring first, with the cards making each.
+/// The last is the one the hand scores as.
+pub fn explain(hand: &[Card]) -> Vec<MeldMatch> {
+    let set = CardSet::from(hand);
+    MELD_SET_FUNCTIONS
+        .iter()
+        .zip(MELD_NAMES)
+        .filter(|(meld, _)| meld(&set) > 0)
+        .map(|(_, &(score, name))| MeldMatch {
+            name,
+            score,
+            parts: meld_parts(name, hand, &set),
+        })
+        .collect()
+}
 
-    if !pairs.is_empty() {
-        return Ok(15);
+/// The cards making the meld called `name`, which `set` holds.
+fn meld_parts(name: &str, hand: &[Card], set: &CardSet) -> Vec<Vec<Card>> {
+    let held = |n: u8| {
+        Name::ALL
+            .into_iter()
+            .rev()
+            .find(|name| set.count(*name) == n)
+            .map(|name| of_name(hand, name, n as usize))
+    };
+    let pairs = || {
+        Name::ALL
+            .into_iter()
+            .rev()
+            .filter(|name| set.count(*name) >= 2)
+    };
+    match name {
+        "Pair" => pairs().take(1).map(|pair| of_name(hand, pair, 2)).collect(),
+        "Two Pair" => pairs().map(|pair| of_name(hand, pair, 2)).collect(),
+        "Sequence of Three" => vec![suited_run(hand, set, 3, 0)],
+        "Sequence of Four" => vec![suited_run(hand, set, 4, 0)],
+        "Three of a Kind" => held(3).into_iter().collect(),
+        "Four of a Kind" => held(4).into_iter().collect(),
+        "Full Set" => pairs()
+            .map(|pair| (pair, suited_run(hand, set, 3, 1 << pair as u32)))
+            .find(|(_, run)| !run.is_empty())
+            .map(|(pair, run)| vec![of_name(hand, pair, 2), run])
+            .unwrap_or_default(),
+        "Full House" => held(3).into_iter().chain(held(2)).collect(),
+        // Straights and flushes take the whole hand
+        _ => vec![hand.to_vec()],
     }
-    Ok(0)
 }
 
-/// Calculates score for having a straight (5 consecutive ranks).
-pub fn straight_score(hand: CardVec) -> Result<u64, String> {
-    let mut values = hand
-        .iter()
-        .map(|c| c.rank.to_u64().unwrap())
-        .collect::<Vec<u64>>();
+/// The first `n` cards called `name` in `hand`.
+fn of_name(hand: &[Card], name: Name, n: usize) -> Vec<Card> {
+    hand.iter()
+        .filter(|card| card.name == name)
+        .take(n)
+        .copied()
+        .collect()
+}
 
-    values.sort();
+/// The highest `n` names in a row in one suite of `hand`, leaving out the
+/// names in `skip`, or nothing if there aren't any.
+fn suited_run(hand: &[Card], set: &CardSet, n: u32, skip: u16) -> Vec<Card> {
+    let start = Suite::ALL
+        .into_iter()
+        .filter_map(|suite| {
+            let mask = set.suite_mask(suite) & !skip;
+            let starts = (1..n).fold(mask, |run, shift| run & mask >> shift);
+            (starts != 0).then(|| (15 - starts.leading_zeros(), suite))
+        })
+        .max_by_key(|(start, _)| *start);
+    let Some((start, suite)) = start else {
+        return Vec::new();
+    };
+    (start..start + n)
+        .filter_map(|bit| {
+            hand.iter()
+                .find(|card| card.suite == suite && card.name as u32 == bit)
+                .copied()
+        })
+        .collect()
+}
 
-    if values.windows(2).all(|w| w[0] + 1 == w[1]) {
-        return Ok(20);
-    }
-    Ok(0)
+/// The deadwood left in `hand` once it's melded into gin's sets and runs
+/// as well as it can be, as `arrange_gin` melds it: what a gin player
+/// knocks with, and what counts against everyone else.
+pub fn deadwood(hand: &[Card]) -> u64 {
+    let (_melded, deadwood) = arrange_gin(hand);
+    deadwood_points(&deadwood)
 }
 
-/// Calculates score for having a flush (all cards same suit).
-pub fn flush_score(hand: CardVec) -> Result<u64, String> {
-    let mut values = hand.iter().map(|c| c.suite).collect::<Vec<_>>();
-    values.dedup();
+/// Two hands ranked against each other: the better meld first, then for
+/// melds scoring the same the higher ranked meld, as `compare_meld_rank`,
+/// then the higher cards. `Equal` if nothing tells them apart, and then
+/// whichever was there first should hold.
+pub fn compare_hands(a: &[Card], b: &[Card]) -> Ordering {
+    best_meld_score(a)
+        .cmp(&best_meld_score(b))
+        .then_with(|| compare_meld_rank(a, b))
+        .then_with(|| high_cards(a).cmp(&high_cards(b)))
+}
 
-    if values.len() == 1 {
-        return Ok(25);
-    }
-    Ok(0)
+/// Two melds scoring the same, ranked by the highest name in each of their
+/// parts in turn: three kings and a pair of twos beat three queens and a
+/// pair of aces, and a pair of kings beats a pair of twos.
+pub fn compare_meld_rank(a: &[Card], b: &[Card]) -> Ordering {
+    meld_rank(a).cmp(&meld_rank(b))
 }
 
-/// Calculates score for having a sequence of four consecutive ranks o the same suite.
-pub fn sequence_of_four_score(hand: CardVec) -> Result<u64, String> {
-    let mut map: HashMap<Suite, Vec<u64>> = HashMap::new();
+fn meld_rank(cards: &[Card]) -> Vec<usize> {
+    let Some(meld) = explain(cards).pop() else {
+        return Vec::new();
+    };
+    meld.parts
+        .iter()
+        .map(|part| {
+            part.iter()
+                .map(|card| card.name as usize)
+                .max()
+                .unwrap_or(0)
+        })
+        .collect()
+}
 
-    for card in hand {
-        let entry = map.entry(card.suite).or_default();
-        entry.push(card.rank.to_u64().unwrap());
-    }
+/// The names of `cards`, highest first.
+fn high_cards(cards: &[Card]) -> Vec<usize> {
+    let mut names: Vec<usize> = cards.iter().map(|card| card.name as usize).collect();
+    names.sort_unstable_by(|a, b| b.cmp(a));
+    names
+}
 
-    let mut values = Vec::new();
+/// Every way of picking `k` of `items`, keeping their order, from
+/// `combinations`. The combinations come in order too, the first items'
+/// first.
+#[derive(Clone, Debug)]
+pub struct Combinations<'a, T> {
+    items: &'a [T],
+    /// Which items the next combination picks.
+    indices: Vec<usize>,
+    done: bool,
+}
 
-    for (_suite, vals) in map.iter() {
-        if vals.len() >= 3 {
-            values.append(&mut vals.clone());
-        }
+/// Every way of picking `k` of `items`, one at a time.
+pub fn combinations<T: Copy>(items: &[T], k: usize) -> Combinations<'_, T> {
+    Combinations {
+        items,
+        indices: (0..k).collect(),
+        done: k > items.len(),
     }
+}
 
-    values.dedup();
-    values.sort();
-
-    if values.len() >= 4 {
-        let mut sequence_len = 1;
+impl<T: Copy> Iterator for Combinations<'_, T> {
+    type Item = Vec<T>;
 
-        for i in 0..(values.len() - 1) {
-            if values[i] + 1 == values[i + 1] {
-                sequence_len += 1;
-            } else {
-                sequence_len = 1;
-            }
-            if sequence_len == 4 {
-                return Ok(30);
-            }
-            if sequence_len < 2 && i >= 2 {
-                return Ok(0);
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+        let combination = self.indices.iter().map(|&idx| self.items[idx]).collect();
+
+        // Move the last pick that can still move along one, and the picks
+        // after it up behind it
+        let (n, k) = (self.items.len(), self.indices.len());
+        match (0..k).rev().find(|&i| self.indices[i] < n - k + i) {
+            Some(i) => {
+                self.indices[i] += 1;
+                for j in i + 1..k {
+                    self.indices[j] = self.indices[j - 1] + 1;
+                }
             }
+            None => self.done = true,
         }
+
+        Some(combination)
     }
+}
 
-    Ok(0)
+/// Calculates score for having a pair in the hand.
+pub fn pair_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(pair_in(&CardSet::from(&hand)))
 }
 
-/// Calculates score for having a pair plus a sequence of three consecutive ranks.
-pub fn full_set_score(hand: CardVec) -> Result<u64, String> {
-    let mut hand_clone = hand.clone();
-    let mut values = hand
-        .iter()
-        .map(|c| c.rank.to_u64().unwrap())
-        .collect::<Vec<u64>>();
+/// Calculates score for having exactly two pairs in the hand.
+pub fn two_pair_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(two_pair_in(&CardSet::from(&hand)))
+}
 
-    values.sort();
+/// Calculates score for having a sequence of three consecutive ranks of the same suite.
+pub fn sequence_of_three_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(sequence_of_three_in(&CardSet::from(&hand)))
+}
 
-    let mut pairs = values
-        .windows(2)
-        .filter(|vec| vec[0] == vec[1])
-        .collect::<Vec<&[u64]>>();
-    pairs.sort();
+/// Calculates score for having three cards of the same rank.
+pub fn three_of_a_kind_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(three_of_a_kind_in(&CardSet::from(&hand)))
+}
 
-    if !pairs.is_empty() {
-        let high_pair = pairs.last().unwrap();
-        hand_clone.retain(|c| c.rank.to_u64().unwrap() != high_pair[0]);
+/// Calculates score for having a straight (5 consecutive ranks).
+pub fn straight_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(straight_in(&CardSet::from(&hand)))
+}
 
-        let mut values = hand_clone
-            .iter()
-            .map(|c| c.rank.to_u64().unwrap())
-            .collect::<Vec<u64>>();
+/// Calculates score for having a flush (all cards same suit).
+pub fn flush_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(flush_in(&CardSet::from(&hand)))
+}
 
-        values.sort();
+/// Calculates score for having a sequence of four consecutive ranks o the same suite.
+pub fn sequence_of_four_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(sequence_of_four_in(&CardSet::from(&hand)))
+}
 
-        let mut sequence_len = 1;
+/// Calculates score for having a pair plus a sequence of three consecutive ranks of the same suite.
+pub fn full_set_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(full_set_in(&CardSet::from(&hand)))
+}
 
-        for i in 0..(values.len().saturating_sub(1)) {
-            if values[i] + 1 == values[i + 1] {
-                sequence_len += 1;
-            } else {
-                sequence_len = 1;
-            }
-            if sequence_len == 3 {
-                return Ok(35);
-            }
-            if sequence_len < 2 && i >= 1 {
-                return Ok(0);
-            }
-        }
-    }
+/// Calculates score for having a full house (three of a kind + pair).
+pub fn full_house_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(full_house_in(&CardSet::from(&hand)))
+}
 
-    Ok(0)
+/// Calculates score for having four cards of the same rank.
+pub fn four_of_a_kind_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(four_of_a_kind_in(&CardSet::from(&hand)))
 }
 
-/// Calculates score for having a full house (three of a kind + pair).
-pub fn full_house_score(hand: CardVec) -> Result<u64, String> {
-    let mut values = hand
-        .iter()
-        .map(|c| c.rank.to_u64().unwrap())
-        .collect::<Vec<u64>>();
+/// Calculates score for having a straight flush (straight + flush).
+pub fn straight_flush_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(straight_flush_in(&CardSet::from(&hand)))
+}
 
-    values.sort();
+/// Calculates score for having a royal flush (A, K, Q, J, 10 all same suit).
+pub fn royal_flush_score(hand: CardVec) -> Result<u64, RummyError> {
+    Ok(royal_flush_in(&CardSet::from(&hand)))
+}
 
-    let mut count = HashMap::new();
-    for rank in values {
-        *count.entry(rank).or_insert(0) += 1;
-    }
+/// The meld checks on a `CardSet`, in the same order as `MELD_FUNCTIONS`.
+/// Each gives the meld's score if the cards hold it and 0 if they don't.
+pub const MELD_SET_FUNCTIONS: &[fn(&CardSet) -> u64] = &[
+    pair_in,
+    two_pair_in,
+    sequence_of_three_in,
+    three_of_a_kind_in,
+    straight_in,
+    flush_in,
+    sequence_of_four_in,
+    full_set_in,
+    full_house_in,
+    four_of_a_kind_in,
+    straight_flush_in,
+    royal_flush_in,
+];
 
-    let mut frequencies = count.into_values().collect::<Vec<usize>>();
-    frequencies.sort();
+fn score_if(held: bool, score: u64) -> u64 {
+    if held { score } else { 0 }
+}
 
-    if frequencies == vec![2, 3] {
-        return Ok(40);
-    }
+pub fn pair_in(set: &CardSet) -> u64 {
+    score_if(set.highest_held(2).is_some(), 2)
+}
 
-    Ok(0)
+/// Two names held twice or more, so a full house counts.
+pub fn two_pair_in(set: &CardSet) -> u64 {
+    let pairs = Name::ALL
+        .iter()
+        .filter(|name| set.count(**name) >= 2)
+        .count();
+    score_if(pairs == 2, 5)
 }
 
-/// Calculates score for having four cards of the same rank.
-pub fn four_of_a_kind_score(hand: CardVec) -> Result<u64, String> {
-    let mut map = HashMap::new();
-    for card in hand.iter() {
-        *map.entry(card.rank).or_insert(0) += 1;
-    }
+/// The suite with the longest run of names in a row among `cards`, and how
+/// long it is. The first suite in `Suite::ALL` takes a tie, and no cards
+/// are a run of 0.
+pub fn longest_suited_run(cards: &[Card]) -> (Suite, usize) {
+    let set: CardSet = cards.iter().copied().collect();
+    longest_suited_run_in(&set, 0)
+}
 
-    let mut pairs: Vec<usize> = map.into_values().collect::<Vec<usize>>();
-    pairs.retain(|&i| i == 4);
+/// `longest_suited_run` for a set, leaving out the names in `skip`.
+fn longest_suited_run_in(set: &CardSet, skip: u16) -> (Suite, usize) {
+    Suite::ALL
+        .iter()
+        .map(|&suite| (suite, run_length(set.suite_mask(suite) & !skip)))
+        .fold((Suite::ALL[0], 0), |longest, run| {
+            if run.1 > longest.1 { run } else { longest }
+        })
+}
 
-    if !pairs.is_empty() {
-        return Ok(50);
+/// The most names in a row in `mask`.
+fn run_length(mask: u16) -> usize {
+    let mut run = mask;
+    let mut len = 0;
+    while run != 0 {
+        run &= run >> 1;
+        len += 1;
     }
+    len
+}
 
-    Ok(0)
+pub fn sequence_of_three_in(set: &CardSet) -> u64 {
+    score_if(longest_suited_run_in(set, 0).1 >= 3, 10)
 }
 
-/// Calculates score for having a straight flush (straight + flush).
-pub fn straight_flush_score(hand: CardVec) -> Result<u64, String> {
-    if straight_score(hand.clone()).unwrap() > 0 && flush_score(hand).unwrap() > 0 {
-        return Ok(80);
-    }
+/// Exactly three of a name, four of a kind is its own meld.
+pub fn three_of_a_kind_in(set: &CardSet) -> u64 {
+    score_if(set.names_held(3) > 0, 15)
+}
 
-    Ok(0)
+/// Every card a different name and all of them in a row, in any suites.
+pub fn straight_in(set: &CardSet) -> u64 {
+    let names = set.name_mask();
+    score_if(
+        names.count_ones() as usize == set.len() && is_run(names),
+        20,
+    )
 }
 
-/// Calculates score for having a royal flush (A, K, Q, J, 10 all same suit).
-pub fn royal_flush_score(hand: CardVec) -> Result<u64, String> {
-    if straight_score(hand.clone()).unwrap() > 0 && flush_score(hand.clone()).unwrap() > 0 {
-        let rank_accum = hand
-            .iter()
-            .fold(0, |acc, card| acc + card.rank.to_u64().unwrap());
-        if rank_accum == 60 {
-            return Ok(100);
-        }
-    }
+pub fn flush_in(set: &CardSet) -> u64 {
+    let suites = Suite::ALL
+        .iter()
+        .filter(|suite| set.suite_mask(**suite) != 0)
+        .count();
+    score_if(suites == 1, 25)
+}
 
-    Ok(0)
+pub fn sequence_of_four_in(set: &CardSet) -> u64 {
+    score_if(longest_suited_run_in(set, 0).1 >= 4, 30)
+}
+
+/// A pair, and three names in a row in one suite among the rest.
+pub fn full_set_in(set: &CardSet) -> u64 {
+    let full_set = Name::ALL
+        .iter()
+        .filter(|name| set.count(**name) >= 2)
+        .any(|&pair| longest_suited_run_in(set, 1 << pair as u32).1 >= 3);
+    score_if(full_set, 35)
+}
+
+/// Exactly a three of a kind and a pair, nothing else.
+pub fn full_house_in(set: &CardSet) -> u64 {
+    let names = set.name_mask().count_ones();
+    score_if(
+        names == 2 && set.names_held(3) == 1 && set.names_held(2) == 1,
+        40,
+    )
+}
+
+/// Exactly four of a name.
+pub fn four_of_a_kind_in(set: &CardSet) -> u64 {
+    score_if(set.names_held(4) > 0, 50)
+}
+
+pub fn straight_flush_in(set: &CardSet) -> u64 {
+    score_if(straight_in(set) > 0 && flush_in(set) > 0, 80)
+}
+
+/// A straight flush from ten up to ace.
+pub fn royal_flush_in(set: &CardSet) -> u64 {
+    const TEN_TO_ACE: u16 = 0b11111 << 8;
+    score_if(
+        straight_flush_in(set) > 0 && set.name_mask() == TEN_TO_ACE,
+        100,
+    )
 }
 
 /// Tests the `two_pair_score` function for 5_card hands.
 #[cfg(test)]
 mod tests {
     use super::*;
+    use quickcheck::{Arbitrary, Gen, quickcheck};
     use smallvec::smallvec;
 
+    /// Five different cards bunched around a name and a suite, so melds come
+    /// up far more often than in a dealt hand.
+    #[derive(Clone, Debug)]
+    struct FiveCards(CardVec);
+
+    impl Arbitrary for FiveCards {
+        fn arbitrary(g: &mut Gen) -> Self {
+            // Low enough for a straight up from it
+            let base = *g.choose(&Name::ALL[..9]).unwrap() as usize;
+            let suite = *g.choose(&Suite::ALL).unwrap();
+
+            let mut hand = CardVec::new();
+            while hand.len() < MELD_SIZE {
+                let name = Name::ALL[base + *g.choose(&[0, 1, 2, 3, 4]).unwrap()];
+                let suite = if bool::arbitrary(g) {
+                    suite
+                } else {
+                    *g.choose(&Suite::ALL).unwrap()
+                };
+                let card = Card {
+                    name,
+                    rank: name.to_rank().unwrap(),
+                    suite,
+                };
+                if !hand.contains(&card) {
+                    hand.push(card);
+                }
+            }
+
+            FiveCards(hand)
+        }
+    }
+
+    fn holds(meld: MeldScoringClosure, hand: &CardVec) -> bool {
+        meld(hand.clone()).unwrap() > 0
+    }
+
+    /// `n` names in a row in one suite, found the slow way.
+    fn suited_run(hand: &CardVec, n: usize) -> bool {
+        hand.iter().any(|start| {
+            (1..n).all(|step| {
+                hand.iter().any(|card| {
+                    card.suite == start.suite && card.name as usize == start.name as usize + step
+                })
+            })
+        })
+    }
+
+    quickcheck! {
+        // Three of a Kind is exactly three, four are a meld of their own
+        fn four_of_a_kind_holds_three_of_a_name_and_a_pair(hand: FiveCards) -> bool {
+            let FiveCards(hand) = hand;
+            !holds(four_of_a_kind_score, &hand)
+                || (CardSet::from(&hand).highest_held(3).is_some() && holds(pair_score, &hand))
+        }
+
+        fn full_house_holds_three_of_a_kind_and_a_pair(hand: FiveCards) -> bool {
+            let FiveCards(hand) = hand;
+            !holds(full_house_score, &hand)
+                || (holds(three_of_a_kind_score, &hand)
+                    && holds(pair_score, &hand)
+                    && holds(two_pair_score, &hand))
+        }
+
+        fn straight_flush_is_a_straight_and_a_flush(hand: FiveCards) -> bool {
+            let FiveCards(hand) = hand;
+            holds(straight_flush_score, &hand)
+                == (holds(straight_score, &hand) && holds(flush_score, &hand))
+        }
+
+        fn royal_flush_is_a_straight_flush(hand: FiveCards) -> bool {
+            let FiveCards(hand) = hand;
+            !holds(royal_flush_score, &hand) || holds(straight_flush_score, &hand)
+        }
+
+        fn longer_suited_runs_hold_the_shorter(hand: FiveCards) -> bool {
+            let FiveCards(hand) = hand;
+            (!holds(straight_flush_score, &hand) || holds(sequence_of_four_score, &hand))
+                && (!holds(sequence_of_four_score, &hand) || holds(sequence_of_three_score, &hand))
+        }
+
+        fn sequences_are_runs_in_one_suite(hand: FiveCards) -> bool {
+            let FiveCards(hand) = hand;
+            holds(sequence_of_three_score, &hand) == suited_run(&hand, 3)
+                && holds(sequence_of_four_score, &hand) == suited_run(&hand, 4)
+        }
+
+        fn full_set_is_a_pair_and_a_suited_run_of_the_rest(hand: FiveCards) -> bool {
+            let FiveCards(hand) = hand;
+            let full_set = hand.iter().any(|pair| {
+                let paired = hand.iter().filter(|card| card.name == pair.name).count() >= 2;
+                let rest: CardVec = hand.iter().copied().filter(|card| card.name != pair.name).collect();
+                paired && suited_run(&rest, 3)
+            });
+            holds(full_set_score, &hand) == full_set
+        }
+    }
+
+    #[test]
+    fn test_best_meld_score_is_cached_by_hand() {
+        let cards: Vec<Card> = ["9h", "Jh", "10h", "Qh", "Kh"]
+            .iter()
+            .map(|card| card.parse().unwrap())
+            .collect();
+        let mut shuffled = cards.clone();
+        shuffled.reverse();
+
+        assert_eq!(canonical_key(&cards), canonical_key(&shuffled));
+        assert_ne!(canonical_key(&cards), canonical_key(&cards[..4]));
+        let hand = |cards: &[Card]| crate::game::Hand {
+            cards: cards.to_vec(),
+        };
+        assert_ne!(hand(&cards), hand(&shuffled));
+        assert_eq!(
+            hand(&cards).canonical_key(),
+            hand(&shuffled).canonical_key()
+        );
+
+        // Too big to pack, but still the same shuffled
+        let big = [cards.clone(), shuffled.clone(), cards.clone()].concat();
+        let key = canonical_key(&big);
+        assert_eq!(key >> 63, 1);
+        assert_eq!(
+            key,
+            canonical_key(&[shuffled.clone(), cards.clone(), cards.clone()].concat())
+        );
+        assert_ne!(key, canonical_key(&big[1..]));
+
+        assert_eq!(best_meld_score(&cards), Some(80));
+        assert_eq!(best_meld_score(&shuffled), Some(80));
+        assert!(
+            MELD_CACHE
+                .read()
+                .unwrap()
+                .contains_key(&canonical_key(&cards))
+        );
+    }
+
+    #[test]
+    fn test_best_meld_of_any_size_hand() {
+        let cards = |cards: &str| -> Vec<Card> {
+            cards.split(' ').map(|card| card.parse().unwrap()).collect()
+        };
+
+        let picks: Vec<Vec<u8>> = combinations(&[1, 2, 3, 4], 2).collect();
+        assert_eq!(picks, [[1, 2], [1, 3], [1, 4], [2, 3], [2, 4], [3, 4]]);
+        assert_eq!(combinations(&[1, 2, 3, 4, 5, 6, 7], 5).count(), 21);
+        assert_eq!(combinations(&[1, 2], 0).count(), 1);
+        assert_eq!(combinations(&[1, 2], 3).count(), 0);
+
+        // A hand of a meld or smaller is scored whole
+        let pair = best_meld(&cards("9h 9c 2d"));
+        assert_eq!((pair.score, pair.cards.len()), (2, 3));
+        assert_eq!(best_meld(&cards("9h 9c 2d 5s 7c")).score, 2);
+
+        // Bigger hands by their best 5, wherever those are
+        let house = best_meld(&cards("Kh 3d Kd 7c 2h Kc 2s"));
+        assert_eq!(house.score, 40);
+        assert_eq!(house.cards, cards("Kh Kd 2h Kc 2s"));
+        assert_eq!(best_meld(&cards("Ah Kh Qh Jh 10h 9h")).score, 100);
+    }
+
     #[test]
     fn test_two_pair_score() {
         // Test case 1: Two pairs (2s, 2h, 3c, 3d) → Should return 5
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("3c".to_string()).unwrap(),
-            Card::from_string("3d".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "3c".parse::<Card>().unwrap(),
+            "3d".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
         ];
         let score = two_pair_score(hand).unwrap();
         assert_eq!(score, 5);
 
         // Test case 2: No pairs (all unique) → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("4c".to_string()).unwrap(),
-            Card::from_string("5d".to_string()).unwrap(),
-            Card::from_string("6s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "4c".parse::<Card>().unwrap(),
+            "5d".parse::<Card>().unwrap(),
+            "6s".parse::<Card>().unwrap(),
         ];
         let score = two_pair_score(hand).unwrap();
         assert_eq!(score, 0);
 
         // Test case 3: Three of a kind (2s x3) → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("2c".to_string()).unwrap(),
-            Card::from_string("3d".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "2c".parse::<Card>().unwrap(),
+            "3d".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
         ];
         let score = two_pair_score(hand).unwrap();
         assert_eq!(score, 0);
 
         // Test case 4: Full house (three of a kind + pair) → Should return 5
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("2c".to_string()).unwrap(),
-            Card::from_string("3d".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "2c".parse::<Card>().unwrap(),
+            "3d".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
         ];
         let score = two_pair_score(hand).unwrap();
         assert_eq!(score, 5);
@@ -332,33 +762,33 @@ mod tests {
     fn test_three_of_a_kind() {
         // Test case 1: Three of a kind (2s x3) → Should return 5
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("2c".to_string()).unwrap(),
-            Card::from_string("3d".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "2c".parse::<Card>().unwrap(),
+            "3d".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
         ];
         let score = three_of_a_kind_score(hand).unwrap();
         assert_eq!(score, 15);
 
         // Test case 2: Three of a kind with other cards → Should return 5
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("2c".to_string()).unwrap(),
-            Card::from_string("3d".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "2c".parse::<Card>().unwrap(),
+            "3d".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
         ];
         let score = three_of_a_kind_score(hand).unwrap();
         assert_eq!(score, 0);
 
         // Test case 3: No three of a kind → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("4c".to_string()).unwrap(),
-            Card::from_string("5d".to_string()).unwrap(),
-            Card::from_string("6s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "4c".parse::<Card>().unwrap(),
+            "5d".parse::<Card>().unwrap(),
+            "6s".parse::<Card>().unwrap(),
         ];
         let score = three_of_a_kind_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -368,33 +798,33 @@ mod tests {
     fn test_sequence_of_three_score() {
         // Test case 1: Consecutive ranks in the same suit → Should return 10
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("Kh".to_string()).unwrap(),
-            Card::from_string("Ah".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "Kh".parse::<Card>().unwrap(),
+            "Ah".parse::<Card>().unwrap(),
         ];
         let score = sequence_of_three_score(hand).unwrap();
         assert_eq!(score, 10);
 
         // Test case 2: Not the same suite → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("Kh".to_string()).unwrap(),
-            Card::from_string("Kh".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "Kh".parse::<Card>().unwrap(),
+            "Kh".parse::<Card>().unwrap(),
         ];
         let score = sequence_of_three_score(hand).unwrap();
         assert_eq!(score, 0);
 
         // Test case 3: Three cards with two pairs (2s x2, 3h) → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("Kh".to_string()).unwrap(),
-            Card::from_string("Ah".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "Kh".parse::<Card>().unwrap(),
+            "Ah".parse::<Card>().unwrap(),
         ];
         let score = sequence_of_three_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -404,22 +834,22 @@ mod tests {
     fn test_straight_score() {
         // Test case 1: Five consecutive ranks of the same suite (2s, 3s, 4s, 5s, 6s) → Should return 20
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("5s".to_string()).unwrap(),
-            Card::from_string("6s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "5s".parse::<Card>().unwrap(),
+            "6s".parse::<Card>().unwrap(),
         ];
         let score = straight_score(hand).unwrap();
         assert_eq!(score, 20);
 
         // Test case 2: Not consecutive → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("5s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "5s".parse::<Card>().unwrap(),
         ];
         let score = straight_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -429,47 +859,127 @@ mod tests {
     fn test_flush_score() {
         // Test case 1: All cards same suit (2s, 3s, 4s, etc) → Should return 25
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("8s".to_string()).unwrap(),
-            Card::from_string("5s".to_string()).unwrap(),
-            Card::from_string("As".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "8s".parse::<Card>().unwrap(),
+            "5s".parse::<Card>().unwrap(),
+            "As".parse::<Card>().unwrap(),
         ];
         let score = flush_score(hand).unwrap();
         assert_eq!(score, 25);
 
         // Test case 2: Mixed suits → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("7h".to_string()).unwrap(),
-            Card::from_string("8h".to_string()).unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "7h".parse::<Card>().unwrap(),
+            "8h".parse::<Card>().unwrap(),
         ];
         let score = flush_score(hand).unwrap();
         assert_eq!(score, 0);
     }
 
+    #[test]
+    fn test_explain_lists_every_meld_with_its_cards() {
+        let cards = |cards: &str| -> Vec<Card> {
+            cards.split(' ').map(|card| card.parse().unwrap()).collect()
+        };
+
+        let melds = explain(&cards("2s 3s 2h 2c 3h"));
+        let names: Vec<&str> = melds.iter().map(|meld| meld.name).collect();
+        assert_eq!(names, ["Pair", "Two Pair", "Three of a Kind", "Full House"]);
+        assert_eq!(melds[0].parts, [cards("3s 3h")]);
+        assert_eq!(
+            melds.last().unwrap().to_string(),
+            "Full House: 2s 2h 2c + 3s 3h = 40"
+        );
+
+        // Only the cards making the meld, the run in one suite
+        let melds = explain(&cards("8h 5s 6h 5c 7h"));
+        assert_eq!(
+            melds.last().unwrap().to_string(),
+            "Full Set: 5s 5c + 6h 7h 8h = 35"
+        );
+        assert!(explain(&cards("2s 5h 9d Jc Kh")).is_empty());
+    }
+
+    #[test]
+    fn test_compare_hands_breaks_ties_on_rank_then_high_card() {
+        let cards = |cards: &str| -> Vec<Card> {
+            cards.split(' ').map(|card| card.parse().unwrap()).collect()
+        };
+        let compare = |a: &str, b: &str| compare_hands(&cards(a), &cards(b));
+
+        // The better meld, whatever its cards
+        assert_eq!(
+            compare("2s 2h 2c 3s 3h", "Ks Kh 9c 9s Ah"),
+            Ordering::Greater
+        );
+        // Three kings and a pair of twos over three queens and a pair of aces
+        assert_eq!(
+            compare("Ks Kh Kc 2s 2h", "Qs Qh Qc As Ah"),
+            Ordering::Greater
+        );
+        assert_eq!(compare("5s 5h 9c Jd 2h", "5c 5d 9h Qd 2s"), Ordering::Less);
+        // Nothing tells them apart
+        assert_eq!(compare("5s 5h 9c Jd 2h", "5c 5d 9h Jc 2s"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_deadwood_is_what_gin_cant_meld() {
+        let cards = |cards: &str| -> Vec<Card> {
+            cards.split(' ').map(|card| card.parse().unwrap()).collect()
+        };
+
+        assert_eq!(deadwood(&cards("Ah 2h 3h 8c 8d 8s Js Qs Ks 5d")), 5);
+        assert_eq!(deadwood(&cards("Ah 2h 3h 8c 8d 8s Js Qs Ks 10d")), 10);
+        // Nothing melds, so it all counts: 1 + 10 + 10 + 7
+        assert_eq!(deadwood(&cards("As Kd Qc 7h")), 28);
+        assert_eq!(deadwood(&[]), 0);
+    }
+
+    #[test]
+    fn test_longest_suited_run() {
+        let cards = |cards: &str| -> Vec<Card> {
+            cards.split(' ').map(|card| card.parse().unwrap()).collect()
+        };
+        assert_eq!(
+            longest_suited_run(&cards("2s 3h 4s 4h 5h 6h")),
+            (Suite::Hearts, 4)
+        );
+        // Ranks in a row across suites aren't a run
+        assert_eq!(
+            longest_suited_run(&cards("2s 3h 4d 5c 6s")),
+            (Suite::Spades, 1)
+        );
+        assert_eq!(
+            longest_suited_run(&cards("9d 10d Jd")),
+            (Suite::Diamonds, 3)
+        );
+        assert_eq!(longest_suited_run(&[]), (Suite::ALL[0], 0));
+    }
+
     #[test]
     fn test_sequence_of_four_score() {
         // Test case 1: Four consecutive ranks (2s, 3s, 4s, 5s) → Should return 30
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("5s".to_string()).unwrap(),
-            Card::from_string("5h".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "5s".parse::<Card>().unwrap(),
+            "5h".parse::<Card>().unwrap(),
         ];
         let score = sequence_of_four_score(hand).unwrap();
         assert_eq!(score, 30);
 
         // Test case 2: Not the same suite → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("5h".to_string()).unwrap(),
-            Card::from_string("4h".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "5h".parse::<Card>().unwrap(),
+            "4h".parse::<Card>().unwrap(),
         ];
         let score = sequence_of_four_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -479,22 +989,33 @@ mod tests {
     fn test_full_set_score() {
         // Test case 1: Full set (pair + sequence of three) → Should return 35
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("4d".to_string()).unwrap(),
-            Card::from_string("5h".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "3d".parse::<Card>().unwrap(),
+            "4d".parse::<Card>().unwrap(),
+            "5d".parse::<Card>().unwrap(),
         ];
         let score = full_set_score(hand).unwrap();
         assert_eq!(score, 35);
 
+        // Test case 1b: The sequence isn't in one suite → Should return 0
+        let hand: CardVec = smallvec![
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "4d".parse::<Card>().unwrap(),
+            "5h".parse::<Card>().unwrap(),
+        ];
+        let score = full_set_score(hand).unwrap();
+        assert_eq!(score, 0);
+
         // Test case 2: Only pair → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("4h".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "4h".parse::<Card>().unwrap(),
         ];
         let score = full_set_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -504,22 +1025,22 @@ mod tests {
     fn test_full_house_score() {
         // Test case 1: Full house (three of a kind + pair) → Should return 40
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("2c".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "2c".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
         ];
         let score = full_house_score(hand).unwrap();
         assert_eq!(score, 40);
 
         // Test case 2: Only three of a kind → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("2c".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "2c".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
         ];
         let score = full_house_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -529,22 +1050,22 @@ mod tests {
     fn test_four_of_a_kind_score() {
         // Test case 1: Four of a kind (2s x4) → Should return 50
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("2c".to_string()).unwrap(),
-            Card::from_string("2d".to_string()).unwrap(),
-            Card::from_string("3d".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "2c".parse::<Card>().unwrap(),
+            "2d".parse::<Card>().unwrap(),
+            "3d".parse::<Card>().unwrap(),
         ];
         let score = four_of_a_kind_score(hand).unwrap();
         assert_eq!(score, 50);
 
         // Test case 2: Three of a kind → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("2h".to_string()).unwrap(),
-            Card::from_string("4c".to_string()).unwrap(),
-            Card::from_string("4d".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "2h".parse::<Card>().unwrap(),
+            "4c".parse::<Card>().unwrap(),
+            "4d".parse::<Card>().unwrap(),
         ];
         let score = four_of_a_kind_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -554,22 +1075,22 @@ mod tests {
     fn test_straight_flush_score() {
         // Test case 1: Straight flush (2s, 3s, 4s) → Should return 80
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3s".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("5s".to_string()).unwrap(),
-            Card::from_string("6s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3s".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "5s".parse::<Card>().unwrap(),
+            "6s".parse::<Card>().unwrap(),
         ];
         let score = straight_flush_score(hand).unwrap();
         assert_eq!(score, 80);
 
         // Test case 2: Flush but different suites → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("2s".to_string()).unwrap(),
-            Card::from_string("3h".to_string()).unwrap(),
-            Card::from_string("4s".to_string()).unwrap(),
-            Card::from_string("5h".to_string()).unwrap(),
-            Card::from_string("6s".to_string()).unwrap(),
+            "2s".parse::<Card>().unwrap(),
+            "3h".parse::<Card>().unwrap(),
+            "4s".parse::<Card>().unwrap(),
+            "5h".parse::<Card>().unwrap(),
+            "6s".parse::<Card>().unwrap(),
         ];
         let score = straight_flush_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -579,33 +1100,33 @@ mod tests {
     fn test_royal_flush_score() {
         // Test case 1: Royal flush (A, K, Q, J, 10) → Should return 100
         let hand: CardVec = smallvec![
-            Card::from_string("As".to_string()).unwrap(),
-            Card::from_string("Ks".to_string()).unwrap(),
-            Card::from_string("Qs".to_string()).unwrap(),
-            Card::from_string("Js".to_string()).unwrap(),
-            Card::from_string("10s".to_string()).unwrap(),
+            "As".parse::<Card>().unwrap(),
+            "Ks".parse::<Card>().unwrap(),
+            "Qs".parse::<Card>().unwrap(),
+            "Js".parse::<Card>().unwrap(),
+            "10s".parse::<Card>().unwrap(),
         ];
         let score = royal_flush_score(hand).unwrap();
         assert_eq!(score, 100);
 
         // Test case 2: Not the same suite → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("As".to_string()).unwrap(),
-            Card::from_string("Ks".to_string()).unwrap(),
-            Card::from_string("Qh".to_string()).unwrap(),
-            Card::from_string("Js".to_string()).unwrap(),
-            Card::from_string("10s".to_string()).unwrap(),
+            "As".parse::<Card>().unwrap(),
+            "Ks".parse::<Card>().unwrap(),
+            "Qh".parse::<Card>().unwrap(),
+            "Js".parse::<Card>().unwrap(),
+            "10s".parse::<Card>().unwrap(),
         ];
         let score = royal_flush_score(hand).unwrap();
         assert_eq!(score, 0);
 
         // Test case 3: Not the highest ranks → Should return 0
         let hand: CardVec = smallvec![
-            Card::from_string("As".to_string()).unwrap(),
-            Card::from_string("Ks".to_string()).unwrap(),
-            Card::from_string("Qh".to_string()).unwrap(),
-            Card::from_string("Js".to_string()).unwrap(),
-            Card::from_string("10s".to_string()).unwrap(),
+            "As".parse::<Card>().unwrap(),
+            "Ks".parse::<Card>().unwrap(),
+            "Qh".parse::<Card>().unwrap(),
+            "Js".parse::<Card>().unwrap(),
+            "10s".parse::<Card>().unwrap(),
         ];
         let score = royal_flush_score(hand).unwrap();
         assert_eq!(score, 0);
@@ -0,0 +1,246 @@
+//! House rules written as Rhai scripts.
+//!
+//! A script hooks engine events by defining functions named after them,
+//! `on_card_discarded`, `on_hand_played`, `on_knocked`, `on_layoff_made` and
+//! `on_round_won`. Each gets the event as a map (`player`, `book`, `cards` as
+//! strings like `"Qh"`, and `score` where there is one, a knock's deadwood)
+//! and returns what should happen: nothing, one effect or an array of them.
+//!
+//! ```rhai
+//! // +2 points for playing a Queen during a Brontë book
+//! fn on_hand_played(event) {
+//!     if event.book.contains("Brontë") && event.cards.some(|card| name_of(card) == "Q") {
+//!         [award(event.player, 2), say("gets a Brontë bonus!")]
+//!     }
+//! }
+//! ```
+//!
+//! Scripts only see copies of the event and can only hand effects back, so
+//! the worst a script can do is award silly points. They can't load modules
+//! or `eval`, and a script that runs away is stopped after a while.
+
+use crate::card::Card;
+use crate::error::RummyError;
+use crate::game::events::GameEvent;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{AST, Array, Dynamic, Engine, Map, Scope};
+use std::fmt;
+use std::path::Path;
+
+/// How many operations a hook may run before it's stopped.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// What a house rule does to the game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HouseRuleEffect {
+    /// Adds `points` to `player`'s score, or takes them away if negative.
+    Award { player: String, points: i64 },
+    /// Says something about the player whose move triggered the rule. It's
+    /// shown after their name, e.g. `gets a Brontë bonus!`.
+    Say { text: String },
+}
+
+/// A compiled house rules script.
+pub struct HouseRules {
+    engine: Engine,
+    ast: AST,
+    book: String,
+}
+
+impl HouseRules {
+    pub fn load(path: &Path, book: &str) -> Result<Self, RummyError> {
+        let script = std::fs::read_to_string(path)
+            .map_err(|err| RummyError::Io(format!("{}: {err}", path.display())))?;
+        Self::compile(&script, book)
+    }
+
+    /// Compiles `script` for a game about `book`.
+    pub fn compile(script: &str, book: &str) -> Result<Self, RummyError> {
+        let engine = sandboxed_engine();
+        let ast = engine
+            .compile(script)
+            .map_err(|err| RummyError::Config(format!("House rules don't compile: {err}")))?;
+
+        Ok(Self {
+            engine,
+            ast,
+            book: book.trim().to_string(),
+        })
+    }
+
+    /// Runs the script's hook for `event`, if it has one.
+    pub fn on_event(&self, event: &GameEvent) -> Result<Vec<HouseRuleEffect>, RummyError> {
+        let Some((hook, map)) = self.event_map(event) else {
+            return Ok(Vec::new());
+        };
+        if !self.ast.iter_functions().any(|f| f.name == hook) {
+            return Ok(Vec::new());
+        }
+
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, hook, (map,))
+            .map_err(|err| RummyError::Config(format!("{hook} failed: {err}")))?;
+
+        if result.is_unit() {
+            Ok(Vec::new())
+        } else if result.is_array() {
+            result.cast::<Array>().into_iter().map(effect).collect()
+        } else {
+            effect(result).map(|effect| vec![effect])
+        }
+    }
+
+    /// The hook for `event` and what it's told about it. Only moves are hooked.
+    fn event_map(&self, event: &GameEvent) -> Option<(&'static str, Map)> {
+        let (hook, cards, score): (_, &[Card], _) = match event {
+            GameEvent::CardDiscarded { card, .. } => {
+                ("on_card_discarded", std::slice::from_ref(card), None)
+            }
+            GameEvent::HandPlayed { hand, score, .. } => ("on_hand_played", hand, Some(*score)),
+            GameEvent::Knocked { hand, deadwood, .. } => ("on_knocked", hand, Some(*deadwood)),
+            GameEvent::LayoffMade { cards, score, .. } => ("on_layoff_made", cards, *score),
+            GameEvent::RoundWon { hand, score, .. } => ("on_round_won", hand, Some(*score)),
+            _ => return None,
+        };
+
+        let mut map = Map::new();
+        map.insert("player".into(), event.player().to_string().into());
+        map.insert("book".into(), self.book.clone().into());
+        let cards: Array = cards.iter().map(|card| card.to_string().into()).collect();
+        map.insert("cards".into(), cards.into());
+        if let Some(score) = score {
+            map.insert("score".into(), (score as i64).into());
+        }
+
+        Some((hook, map))
+    }
+}
+
+impl fmt::Debug for HouseRules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HouseRules({})", self.book)
+    }
+}
+
+/// An engine that can't reach outside the script, with `award`, `say` and
+/// card helpers registered.
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_string_size(4096)
+        .set_max_array_size(1024)
+        .set_max_map_size(256)
+        .disable_symbol("eval");
+
+    engine.register_fn("award", |player: &str, points: i64| -> Map {
+        let mut map = Map::new();
+        map.insert("award".into(), points.into());
+        map.insert("player".into(), player.to_string().into());
+        map
+    });
+    engine.register_fn("say", |text: &str| -> Map {
+        let mut map = Map::new();
+        map.insert("say".into(), text.to_string().into());
+        map
+    });
+    engine.register_fn("name_of", |card: &str| -> String {
+        card.parse::<Card>()
+            .map(|card| card.name.to_string())
+            .unwrap_or_default()
+    });
+    engine.register_fn("suite_of", |card: &str| -> String {
+        card.parse::<Card>()
+            .map(|card| card.suite.to_string())
+            .unwrap_or_default()
+    });
+
+    engine
+}
+
+/// Reads back an effect made by `award` or `say`.
+fn effect(value: Dynamic) -> Result<HouseRuleEffect, RummyError> {
+    let invalid = || RummyError::Config("House rules can only return award() or say()".to_string());
+    let map = value.try_cast::<Map>().ok_or_else(invalid)?;
+
+    if let Some(points) = map.get("award") {
+        let points = points.as_int().map_err(|_| invalid())?;
+        let player = map
+            .get("player")
+            .and_then(|player| player.clone().into_string().ok())
+            .ok_or_else(invalid)?;
+        return Ok(HouseRuleEffect::Award { player, points });
+    }
+
+    let text = map
+        .get("say")
+        .and_then(|text| text.clone().into_string().ok())
+        .ok_or_else(invalid)?;
+    Ok(HouseRuleEffect::Say { text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BRONTE_BONUS: &str = r#"
+        fn on_hand_played(event) {
+            if event.book.contains("Brontë") && event.cards.some(|card| name_of(card) == "Q") {
+                [award(event.player, 2), say("gets a Brontë bonus!")]
+            }
+        }
+    "#;
+
+    fn hand_played(cards: &[&str]) -> GameEvent {
+        GameEvent::HandPlayed {
+            player: "Ada".to_string(),
+            hand: cards.iter().map(|card| card.parse().unwrap()).collect(),
+            score: 20,
+        }
+    }
+
+    #[test]
+    fn test_house_rules_hook_events() {
+        let rules = HouseRules::compile(BRONTE_BONUS, "Jane Eyre by Charlotte Brontë").unwrap();
+        assert_eq!(
+            rules.on_event(&hand_played(&["Qh", "Kh", "Ah"])).unwrap(),
+            vec![
+                HouseRuleEffect::Award {
+                    player: "Ada".to_string(),
+                    points: 2
+                },
+                HouseRuleEffect::Say {
+                    text: "gets a Brontë bonus!".to_string()
+                },
+            ]
+        );
+        assert!(
+            rules
+                .on_event(&hand_played(&["Jh", "Kh", "Ah"]))
+                .unwrap()
+                .is_empty()
+        );
+
+        // No hook for discards, and other books don't get the bonus
+        let discard = GameEvent::CardDiscarded {
+            player: "Ada".to_string(),
+            card: "Qh".parse().unwrap(),
+        };
+        assert!(rules.on_event(&discard).unwrap().is_empty());
+
+        let rules = HouseRules::compile(BRONTE_BONUS, "East of Eden").unwrap();
+        assert!(
+            rules
+                .on_event(&hand_played(&["Qh", "Kh", "Ah"]))
+                .unwrap()
+                .is_empty()
+        );
+
+        // Runaway scripts are stopped
+        let forever = HouseRules::compile("fn on_card_discarded(event) { loop {} }", "").unwrap();
+        assert!(forever.on_event(&discard).is_err());
+    }
+}
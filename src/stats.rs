@@ -0,0 +1,531 @@
+//! Club statistics: every finished game is appended to a JSON lines file,
+//! and lifetime records are worked out from it.
+
+use crate::display::normalize_name;
+use crate::error::RummyError;
+use crate::game::events::{EventSink, GameEvent};
+use crate::game::sheet::ScoreSheet;
+use crate::rng::SeedDisclosure;
+use crate::save::data_dir;
+use crate::scoring::meld_name;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One finished game, a line in the stats file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameResult {
+    /// Seconds since the Unix epoch.
+    pub finished_at: u64,
+    pub book: String,
+    /// Final scores, in seating order.
+    pub players: Vec<PlayerResult>,
+    pub rounds: usize,
+    pub winner: String,
+    /// The meld that won each round.
+    pub melds: Vec<MeldHit>,
+    /// The seed the game was dealt from, to check its deals against later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disclosure: Option<SeedDisclosure>,
+    /// Everyone's score round by round, from the engine. Events don't say
+    /// where one round ends and the next starts, so the recorder leaves it
+    /// to be filled in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_sheet: Option<ScoreSheet>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerResult {
+    pub name: String,
+    pub score: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MeldHit {
+    pub player: String,
+    pub meld: String,
+    pub score: u64,
+}
+
+/// How many of each player's lines the summary remembers them by.
+pub const SUMMARY_EXCERPTS: usize = 2;
+
+/// How a finished game went, for the screen after it and anyone else who
+/// wants to tell the story.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct GameSummary {
+    pub book: String,
+    pub rounds: usize,
+    pub winner: String,
+    /// In seating order.
+    pub players: Vec<PlayerSummary>,
+    /// The best meld anyone won a round with.
+    pub biggest_hand: Option<MeldHit>,
+    /// What the club made of the book, summed up after the game with
+    /// `--recap`.
+    #[serde(default)]
+    pub discussion: Option<String>,
+}
+
+/// One player's game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PlayerSummary {
+    pub name: String,
+    pub score: usize,
+    /// Every meld they won a round with, in order.
+    pub melds: Vec<MeldHit>,
+    /// Rounds they won by laying off onto someone else's hand.
+    pub layoff_wins: usize,
+    /// The last few things they said at the table.
+    pub excerpts: Vec<String>,
+}
+
+/// Follows a game through its events and sums it up once someone has won.
+#[derive(Clone, Debug, Default)]
+pub struct GameRecorder {
+    rounds: usize,
+    melds: Vec<MeldHit>,
+    scores: Vec<PlayerResult>,
+    winner: Option<String>,
+    disclosure: Option<SeedDisclosure>,
+    /// The round was settled by a knock, so its score is deadwood, not a meld.
+    knocked: bool,
+    /// Who played the hand this round, to tell a layoff's win from theirs.
+    played_by: Option<String>,
+    layoff_wins: HashMap<String, usize>,
+    excerpts: HashMap<String, Vec<String>>,
+}
+
+impl GameRecorder {
+    /// `players` in seating order, so they're listed even if they never score.
+    pub fn new<'a>(players: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            scores: players
+                .into_iter()
+                .map(|name| PlayerResult {
+                    name: name.to_string(),
+                    score: 0,
+                })
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// The finished game, or `None` while nobody has won yet.
+    pub fn result(&self, book: &str) -> Option<GameResult> {
+        let winner = self.winner.clone()?;
+        let finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        Some(GameResult {
+            finished_at,
+            book: book.trim().to_string(),
+            players: self.scores.clone(),
+            rounds: self.rounds,
+            winner,
+            melds: self.melds.clone(),
+            disclosure: self.disclosure.clone(),
+            score_sheet: None,
+        })
+    }
+
+    /// The story of the finished game, or `None` while nobody has won yet.
+    pub fn summary(&self, book: &str) -> Option<GameSummary> {
+        let winner = self.winner.clone()?;
+
+        let players = self
+            .scores
+            .iter()
+            .map(|player| PlayerSummary {
+                name: player.name.clone(),
+                score: player.score,
+                melds: self
+                    .melds
+                    .iter()
+                    .filter(|hit| hit.player == player.name)
+                    .cloned()
+                    .collect(),
+                layoff_wins: self.layoff_wins.get(&player.name).copied().unwrap_or(0),
+                excerpts: self.excerpts.get(&player.name).cloned().unwrap_or_default(),
+            })
+            .collect();
+
+        Some(GameSummary {
+            book: book.trim().to_string(),
+            rounds: self.rounds,
+            winner,
+            players,
+            // The first of equal hands, it set the bar
+            biggest_hand: self.melds.iter().rev().max_by_key(|hit| hit.score).cloned(),
+            discussion: None,
+        })
+    }
+}
+
+impl EventSink for GameRecorder {
+    fn emit(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::RoundWon { player, score, .. } => {
+                self.rounds += 1;
+                if self
+                    .played_by
+                    .take()
+                    .is_some_and(|played_by| played_by != *player)
+                {
+                    *self.layoff_wins.entry(player.clone()).or_default() += 1;
+                }
+
+                match self.scores.iter_mut().find(|p| p.name == *player) {
+                    Some(result) => result.score += *score as usize,
+                    None => self.scores.push(PlayerResult {
+                        name: player.clone(),
+                        score: *score as usize,
+                    }),
+                }
+
+                if std::mem::take(&mut self.knocked) {
+                    return;
+                }
+                if let Some(meld) = meld_name(*score) {
+                    self.melds.push(MeldHit {
+                        player: player.clone(),
+                        meld: meld.to_string(),
+                        score: *score,
+                    });
+                }
+            }
+            GameEvent::HandPlayed { player, .. } => self.played_by = Some(player.clone()),
+            GameEvent::Knocked { .. } => self.knocked = true,
+            GameEvent::RoundDealt { .. } => self.played_by = None,
+            GameEvent::Dialogue { player, text } if !text.trim().is_empty() => {
+                let lines = self.excerpts.entry(player.clone()).or_default();
+                lines.push(text.trim().to_string());
+                if lines.len() > SUMMARY_EXCERPTS {
+                    lines.remove(0);
+                }
+            }
+            GameEvent::ScoreAdjusted { player, points } => {
+                if let Some(result) = self.scores.iter_mut().find(|p| p.name == *player) {
+                    result.score = result.score.saturating_add_signed(*points as isize);
+                }
+            }
+            GameEvent::QuizScored { player, points, .. } => {
+                if let Some(result) = self.scores.iter_mut().find(|p| p.name == *player) {
+                    result.score += points;
+                }
+            }
+            GameEvent::GameWon { player, .. } => self.winner = Some(player.clone()),
+            GameEvent::SeedDisclosed { disclosure, .. } => {
+                self.disclosure = Some(disclosure.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The JSON lines file finished games are appended to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatsStore {
+    path: PathBuf,
+}
+
+impl Default for StatsStore {
+    /// `stats.jsonl` in the data directory.
+    fn default() -> Self {
+        Self::new(data_dir().join("stats.jsonl"))
+    }
+}
+
+impl StatsStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, result: &GameResult) -> Result<(), RummyError> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| RummyError::Io(err.to_string()))?;
+        }
+
+        let line = serde_json::to_string(result).map_err(|err| RummyError::Io(err.to_string()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| RummyError::Io(format!("{}: {err}", self.path.display())))?;
+
+        writeln!(file, "{line}").map_err(|err| RummyError::Io(err.to_string()))
+    }
+
+    /// Every game recorded so far. No file yet means no games, and a line that
+    /// doesn't parse (say, cut short by a crash) is skipped.
+    pub fn load(&self) -> Result<Vec<GameResult>, RummyError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(RummyError::Io(format!("{}: {err}", self.path.display())));
+            }
+        };
+
+        Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+}
+
+/// One player's record across every game in the store.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerStats {
+    pub name: String,
+    pub games: usize,
+    pub wins: usize,
+    /// How many rounds they won with each meld.
+    pub melds: HashMap<String, usize>,
+    meld_points: u64,
+}
+
+impl PlayerStats {
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.games as f64
+        }
+    }
+
+    /// Average score of the melds they won rounds with.
+    pub fn average_meld_score(&self) -> f64 {
+        let melds: usize = self.melds.values().sum();
+        if melds == 0 {
+            0.0
+        } else {
+            self.meld_points as f64 / melds as f64
+        }
+    }
+
+    /// The meld they've won with most often. Ties go to the higher scoring meld.
+    pub fn favorite_meld(&self) -> Option<&str> {
+        self.melds
+            .iter()
+            .max_by_key(|(meld, count)| {
+                let score = crate::scoring::MELD_NAMES
+                    .iter()
+                    .find(|(_, name)| name == meld)
+                    .map(|(score, _)| *score);
+                (**count, score)
+            })
+            .map(|(meld, _)| meld.as_str())
+    }
+}
+
+/// Lifetime records for everyone who has played, best win rate first.
+/// Names go through `options`, so the report can be shared.
+pub fn lifetime_stats(results: &[GameResult], options: &ExportOptions) -> Vec<PlayerStats> {
+    let mut by_name: HashMap<String, PlayerStats> = HashMap::new();
+
+    for result in results {
+        for player in &result.players {
+            let name = options.player_name(&player.name);
+            let stats = by_name.entry(name.clone()).or_insert_with(|| PlayerStats {
+                name,
+                ..PlayerStats::default()
+            });
+
+            stats.games += 1;
+            if player.name == result.winner {
+                stats.wins += 1;
+            }
+        }
+
+        for hit in &result.melds {
+            let name = options.player_name(&hit.player);
+            let stats = by_name.entry(name.clone()).or_insert_with(|| PlayerStats {
+                name,
+                ..PlayerStats::default()
+            });
+
+            *stats.melds.entry(hit.meld.clone()).or_insert(0) += 1;
+            stats.meld_points += hit.score;
+        }
+    }
+
+    let mut stats: Vec<PlayerStats> = by_name.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.win_rate()
+            .total_cmp(&a.win_rate())
+            .then(b.games.cmp(&a.games))
+            .then(a.name.cmp(&b.name))
+    });
+    stats
+}
+
+/// Hides player names behind stable pseudonyms, so stats can be shared
+/// publicly without naming the club's members.
+///
+/// The same name and salt always give the same pseudonym, across runs and
+/// machines, so a member's record still adds up. Anyone holding the salt can
+/// test guesses against the pseudonyms, so keep it out of what you publish.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pseudonymizer {
+    salt: String,
+}
+
+impl Pseudonymizer {
+    pub fn new(salt: &str) -> Result<Self, RummyError> {
+        if salt.trim().is_empty() {
+            return Err(RummyError::Config(
+                "A pseudonym salt can't be empty".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            salt: salt.to_string(),
+        })
+    }
+
+    /// `Reader-` followed by 8 hex digits. Names are normalized first, so
+    /// however a member's name was typed they get the same pseudonym.
+    pub fn pseudonym(&self, name: &str) -> String {
+        let name = normalize_name(name);
+
+        // FNV-1a, spelled out because std's hashers may change between releases
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in self.salt.bytes().chain([0]).chain(name.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        format!("Reader-{:08x}", (hash >> 32) as u32 ^ hash as u32)
+    }
+}
+
+/// Options for stats leaving the club: exports and webhook summaries.
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    /// Replace names with pseudonyms.
+    pub pseudonymizer: Option<Pseudonymizer>,
+}
+
+impl ExportOptions {
+    /// How `name` should appear in the export.
+    pub fn player_name(&self, name: &str) -> String {
+        match &self.pseudonymizer {
+            Some(pseudonymizer) => pseudonymizer.pseudonym(name),
+            None => name.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_won(player: &str, score: u64) -> GameEvent {
+        GameEvent::RoundWon {
+            player: player.to_string(),
+            score,
+            hand: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_recorded_games_add_up() {
+        let mut recorder = GameRecorder::new(["Ada", "Lee"]);
+        recorder.emit(&round_won("Ada", 20));
+        assert_eq!(recorder.result("East of Eden"), None);
+        assert_eq!(recorder.summary("East of Eden"), None);
+
+        recorder.emit(&round_won("Lee", 10));
+        for text in [
+            "I like my chances.",
+            "Never tell me the odds.",
+            "Told you so.",
+        ] {
+            recorder.emit(&GameEvent::Dialogue {
+                player: "Ada".to_string(),
+                text: text.to_string(),
+            });
+        }
+        // Lee played the hand, Ada laid off onto it and took the round
+        recorder.emit(&GameEvent::HandPlayed {
+            player: "Lee".to_string(),
+            hand: Vec::new(),
+            score: 40,
+        });
+        recorder.emit(&round_won("Ada", 100));
+        recorder.emit(&GameEvent::GameWon {
+            player: "Ada".to_string(),
+            score: 120,
+        });
+        let disclosure = SeedDisclosure {
+            seed: 42,
+            deal_hashes: vec![1, 2, 3],
+        };
+        recorder.emit(&GameEvent::SeedDisclosed {
+            player: "Ada".to_string(),
+            disclosure: disclosure.clone(),
+        });
+
+        let result = recorder.result("East of Eden\n").unwrap();
+        assert_eq!(result.book, "East of Eden");
+        assert_eq!(result.disclosure, Some(disclosure));
+
+        let summary = recorder.summary("East of Eden").unwrap();
+        assert_eq!(summary.rounds, 3);
+        assert_eq!(
+            summary.biggest_hand.as_ref().map(|hit| hit.score),
+            Some(100)
+        );
+        let ada = &summary.players[0];
+        assert_eq!((ada.name.as_str(), ada.score), ("Ada", 120));
+        assert_eq!(ada.melds.len(), 2);
+        assert_eq!(ada.layoff_wins, 1);
+        assert_eq!(ada.excerpts, ["Never tell me the odds.", "Told you so."]);
+        assert_eq!(summary.players[1].layoff_wins, 0);
+        assert_eq!(result.rounds, 3);
+        assert_eq!(result.winner, "Ada");
+        assert_eq!(result.players[0].score, 120);
+
+        let mut lost = result.clone();
+        lost.winner = "Lee".to_string();
+
+        let stats = lifetime_stats(&[result, lost], &ExportOptions::default());
+        let ada = stats.iter().find(|s| s.name == "Ada").unwrap();
+        assert_eq!((ada.games, ada.wins), (2, 1));
+        assert_eq!(ada.win_rate(), 0.5);
+        assert_eq!(ada.average_meld_score(), 60.0);
+        assert_eq!(ada.favorite_meld(), Some("Royal Flush"));
+
+        let lee = stats.iter().find(|s| s.name == "Lee").unwrap();
+        assert_eq!(lee.favorite_meld(), Some("Sequence of Three"));
+    }
+
+    #[test]
+    fn test_pseudonyms_are_stable_and_salted() {
+        let club = Pseudonymizer::new("tuesday night").unwrap();
+        let other_club = Pseudonymizer::new("sunday brunch").unwrap();
+
+        let ada = club.pseudonym("Ada");
+        assert!(ada.starts_with("Reader-"));
+        assert_eq!(ada.len(), "Reader-".len() + 8);
+        assert!(!ada.contains("Ada"));
+
+        assert_eq!(ada, club.pseudonym("  Ada "));
+        assert_eq!(club.pseudonym("Renée"), club.pseudonym("Rene\u{301}e"));
+        assert_ne!(ada, club.pseudonym("Lee"));
+        assert_ne!(ada, other_club.pseudonym("Ada"));
+
+        assert!(Pseudonymizer::new(" ").is_err());
+    }
+}
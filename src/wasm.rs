@@ -0,0 +1,258 @@
+//! JavaScript bindings for a browser front-end. `cargo rustc --lib --release
+//! --target wasm32-unknown-unknown --no-default-features --features wasm
+//! --crate-type cdylib` builds the module and `wasm-bindgen` the bindings.
+//!
+//! Everything crosses the boundary as strings: cards written the way they
+//! are everywhere else, like "10h Jc", and anything bigger as JSON. The
+//! table's snapshots and actions are the network protocol's, versioned the
+//! same way.
+
+use crate::analysis::{self, KnownInformation};
+use crate::card::Card;
+use crate::engine::{GameEngine, TurnPhase};
+use crate::error::RummyError;
+use crate::game::events::{ACTION_LOG_LEN, ActionLog};
+use crate::game::rules::Variant;
+use crate::game::{DeckConfig, Hand, PlayAction, Player, PlayerType, TurnOrder};
+use crate::net::protocol::{PlayerAction, TableView, decode, encode};
+use crate::scoring::meld_name;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::prelude::*;
+
+fn parse_cards(cards: &str) -> Result<Vec<Card>, RummyError> {
+    cards.split_whitespace().map(str::parse).collect()
+}
+
+/// A hand the analysis knows how to advise on, all five cards of it.
+fn parse_hand(cards: &str) -> Result<Hand, RummyError> {
+    let cards = parse_cards(cards)?;
+    let hand_size = Variant::Bookclub.hand_size();
+    if cards.len() != hand_size {
+        return Err(RummyError::InvalidMove(format!(
+            "A hand is {hand_size} cards, not {}",
+            cards.len()
+        )));
+    }
+    Ok(Hand { cards })
+}
+
+/// The best meld in `cards`, if there are any.
+fn meld(cards: &str) -> Result<MeldView, RummyError> {
+    let cards = parse_cards(cards)?;
+    if cards.is_empty() {
+        return Err(RummyError::InvalidMove(
+            "There are no cards to meld".to_string(),
+        ));
+    }
+    let meld = crate::scoring::best_meld(&cards);
+    Ok(MeldView {
+        score: meld.score,
+        name: meld_name(meld.score),
+        cards: meld.cards,
+    })
+}
+
+/// A hand's best meld, as `best_meld` writes it.
+#[derive(Clone, Debug, Serialize)]
+pub struct MeldView {
+    pub score: u64,
+    pub name: Option<&'static str>,
+    pub cards: Vec<Card>,
+}
+
+/// What the AI would do, as `advise` writes it.
+#[derive(Clone, Debug, Serialize)]
+pub struct AdviceView {
+    /// "play", "draw" or "retrieve".
+    pub action: &'static str,
+    pub confidence: f64,
+    pub expected_score: f64,
+    pub discard: Option<Card>,
+}
+
+/// The best meld in `cards`, as JSON `{"score", "name", "cards"}`.
+#[wasm_bindgen(js_name = bestMeld)]
+pub fn best_meld(cards: &str) -> Result<String, JsError> {
+    Ok(serde_json::to_string(&meld(cards)?)?)
+}
+
+/// How a `player_type` AI would play the five cards of `hand` with
+/// `discards` on the table, the top one last, as JSON `{"action", "confidence", "expected_score",
+/// "discard"}`.
+#[wasm_bindgen]
+pub fn advise(hand: &str, discards: &str, player_type: &str) -> Result<String, JsError> {
+    let hand = parse_hand(hand)?;
+    let known = KnownInformation {
+        discards: parse_cards(discards)?,
+        ..KnownInformation::default()
+    };
+    let player_type: PlayerType = player_type.parse()?;
+
+    let decision = analysis::advise(&hand, &known, player_type);
+    let action = match decision.action {
+        PlayAction::Play => "play",
+        PlayAction::Draw => "draw",
+        PlayAction::Retrieve => "retrieve",
+    };
+    let view = AdviceView {
+        action,
+        confidence: decision.confidence,
+        expected_score: decision.expected_score,
+        discard: decision.card_to_discard,
+    };
+    Ok(serde_json::to_string(&view)?)
+}
+
+/// A bookclub game in the browser. The front-end acts for its humans with
+/// `act` and calls `step` until it returns `false` to let the AI players
+/// catch up, redrawing from `view` in between.
+#[wasm_bindgen]
+pub struct Table {
+    engine: GameEngine,
+    book: String,
+    actions: Arc<Mutex<ActionLog>>,
+}
+
+#[wasm_bindgen]
+impl Table {
+    /// Seats `humans` then `bots`, comma separated names, the bots playing
+    /// balanced, and deals from `seed`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(book: &str, humans: &str, bots: &str, seed: u64) -> Result<Table, JsError> {
+        let seat = |name: &str, player_type| Player {
+            name: name.trim().to_string(),
+            description: String::new(),
+            player_type,
+            hand: Hand { cards: Vec::new() },
+            actions: VecDeque::new(),
+            dialogue: VecDeque::new(),
+            score: 0,
+        };
+        let players = humans
+            .split(',')
+            .map(|name| seat(name, None))
+            .chain(
+                bots.split(',')
+                    .map(|name| seat(name, Some(PlayerType::Balanced))),
+            )
+            .filter(|player| !player.name.is_empty())
+            .collect();
+
+        let mut engine = GameEngine::pre_game(players, DeckConfig::default(), Variant::Bookclub)?;
+        engine.set_seed(seed);
+        let actions = Arc::new(Mutex::new(ActionLog::default()));
+        engine.subscribe(actions.clone());
+        engine.decide_turn_order(TurnOrder::default())?;
+
+        Ok(Table {
+            engine,
+            book: book.to_string(),
+            actions,
+        })
+    }
+
+    /// Where `name` ended up sitting once the table decided who goes first.
+    pub fn seat(&self, name: &str) -> Option<usize> {
+        self.engine.players.iter().position(|p| p.name == name)
+    }
+
+    /// The table as `seat` sees it, a versioned JSON `TableView`.
+    pub fn view(&self, seat: usize) -> Result<String, JsError> {
+        if seat >= self.engine.players.len() {
+            return Err(JsError::new(&format!("There's no seat {seat}")));
+        }
+        let actions = self.actions.lock().unwrap().recent(ACTION_LOG_LEN);
+        let view = TableView::new(&self.engine, seat, &self.book, &actions);
+        Ok(encode(&view)?)
+    }
+
+    /// Takes `action`, a versioned JSON `PlayerAction` like
+    /// `{"version": 2, "kind": "discard", "card": "10h"}`, for the human at
+    /// `seat`.
+    pub fn act(&mut self, seat: usize, action: &str) -> Result<(), JsError> {
+        let action: PlayerAction = decode(action)?;
+        action.apply(&mut self.engine, seat)?;
+        Ok(())
+    }
+
+    /// Plays the table's next move if it's up to an AI player: their turn,
+    /// their layoff, or their answer to a declaration. A round nobody's
+    /// left to lay off onto is scored. `false` once the table is waiting on
+    /// a human, or the game is over.
+    pub fn step(&mut self) -> Result<bool, JsError> {
+        let engine = &mut self.engine;
+        if engine.winner().is_some() {
+            return Ok(false);
+        }
+
+        match engine.phase() {
+            TurnPhase::Declaring => {
+                engine.ai_declarations()?;
+                let waiting = engine.declare_window().is_some_and(|window| {
+                    window.eligible.iter().any(|&idx| {
+                        window.is_open_to(idx) && engine.players[idx].player_type.is_none()
+                    })
+                });
+                if waiting {
+                    return Ok(false);
+                }
+                if *engine.phase() == TurnPhase::Declaring {
+                    engine.close_declarations()?;
+                }
+            }
+            TurnPhase::LayingOff => match engine.layoff_player_idx() {
+                None => {
+                    engine.finish_round()?;
+                }
+                Some(idx) if engine.players[idx].player_type.is_some() => {
+                    engine.ai_lay_off()?;
+                }
+                Some(_) => return Ok(false),
+            },
+            _ if engine.current_player().player_type.is_some() => {
+                engine.take_ai_turn()?;
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Who won the game, once someone has.
+    pub fn winner(&self) -> Option<String> {
+        self.engine.winner().map(|player| player.name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A `JsError` can only be made in the browser, so only what succeeds is
+    // tried here
+    #[test]
+    fn test_a_table_steps_to_the_human() {
+        assert!(best_meld("Ks Kh Kd 2c 5h").is_ok());
+
+        let mut table = Table::new("Emma", "Ada", "Bram, Cy", 7).ok().unwrap();
+        assert_eq!(table.engine.players.len(), 3);
+        while table.step().ok().unwrap() {}
+        let ada = table.seat("Ada").unwrap();
+        assert_eq!(table.engine.acting_player_idx(), ada);
+        assert_eq!(*table.engine.phase(), TurnPhase::Choosing);
+        assert!(table.view(ada).is_ok());
+        assert_eq!(table.winner(), None);
+    }
+
+    #[test]
+    fn test_only_whole_hands_are_analysed() {
+        assert!(meld(" ").is_err());
+
+        assert!(parse_hand("").is_err());
+        assert!(parse_hand("9s 9h 9d 2c 5h As").is_err());
+        assert!(parse_hand("2s 3s 4s 5s 6s 7s 8s 9s 10s Js").is_err());
+        assert_eq!(parse_hand("9s 9h 9d 2c 5h").unwrap().cards.len(), 5);
+    }
+}